@@ -0,0 +1,54 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Benchmarks of widget lookups and passes on a tree of 5000 widgets.
+//!
+//! Run them with `cargo bench -p masonry`.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use masonry::testing::TestHarness;
+use masonry::widget::{Flex, SizedBox};
+use masonry::WidgetId;
+
+const WIDGETS: usize = 5000;
+
+/// A column of rows of empty boxes, one box per id, 100 boxes per row.
+fn large_tree(ids: &[WidgetId]) -> Flex {
+    let mut root = Flex::column();
+    for row_ids in ids.chunks(100) {
+        let mut row = Flex::row();
+        for id in row_ids {
+            row = row.with_child_id(SizedBox::empty(), *id);
+        }
+        root = root.with_child(row);
+    }
+    root
+}
+
+fn widget_tree(c: &mut Criterion) {
+    let ids: Vec<WidgetId> = (0..WIDGETS).map(|_| WidgetId::next()).collect();
+    let mut harness = TestHarness::create(large_tree(&ids));
+    let last = ids[WIDGETS - 1];
+
+    // The widget index against a search of the whole tree, for the last widget.
+    c.bench_function("get_widget", |b| b.iter(|| harness.get_widget(last).id()));
+    c.bench_function("find_widget_by_id", |b| {
+        b.iter(|| harness.root_widget().find_widget_by_id(last).unwrap().id());
+    });
+
+    let mut frame = 0;
+    c.bench_function("pointer_move_and_targeted_relayout", |b| {
+        b.iter(|| {
+            frame += 1;
+            harness.mouse_move_to(ids[frame * 37 % WIDGETS]);
+            harness.edit_root_widget(|mut root| {
+                root.edit_descendant(ids[WIDGETS / 2], |mut widget| {
+                    widget.ctx.request_layout();
+                });
+            });
+        });
+    });
+}
+
+criterion_group!(benches, widget_tree);
+criterion_main!(benches);
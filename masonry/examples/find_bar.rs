@@ -0,0 +1,140 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! In-app find: the matches of the search are highlighted in a long text.
+//!
+//! The search is case-insensitive. Pressing Enter in the search box moves to the next
+//! match, which is scrolled into view.
+
+// On Windows platform, don't show a console when opening the app.
+#![windows_subsystem = "windows"]
+
+use std::ops::Range;
+
+use masonry::app_driver::{AppDriver, DriverCtx};
+use masonry::widget::{prelude::*, RootWidget};
+use masonry::widget::{Flex, Label, LineBreaking, Portal, Prose, Textbox};
+use masonry::Action;
+use winit::dpi::LogicalSize;
+use winit::window::Window;
+
+const TEXT: &str = "\
+It was the best of times, it was the worst of times, it was the age of wisdom, \
+it was the age of foolishness, it was the epoch of belief, it was the epoch of \
+incredulity, it was the season of Light, it was the season of Darkness, it was the \
+spring of hope, it was the winter of despair, we had everything before us, we had \
+nothing before us, we were all going direct to Heaven, we were all going direct the \
+other way – in short, the period was so far like the present period, that some of \
+its noisiest authorities insisted on its being received, for good or for evil, in \
+the superlative degree of comparison only.";
+
+const STATUS_INDEX: usize = 1;
+const TEXT_INDEX: usize = 2;
+
+struct Driver {
+    matches: Vec<Range<usize>>,
+    active: Option<usize>,
+}
+
+impl Driver {
+    fn update(&mut self, ctx: &mut DriverCtx<'_>) {
+        let mut root = ctx.get_root::<RootWidget<Flex>>();
+        let mut column = root.get_element();
+
+        let status = match self.active {
+            Some(active) => format!("{} of {}", active + 1, self.matches.len()),
+            None => "No matches".to_string(),
+        };
+        column
+            .child_mut(STATUS_INDEX)
+            .unwrap()
+            .downcast::<Label>()
+            .set_text(status);
+
+        let mut child = column.child_mut(TEXT_INDEX).unwrap();
+        let mut portal = child.downcast::<Portal<Prose>>();
+        let active_rect = {
+            let mut prose = portal.child_mut();
+            prose.set_highlights(self.matches.clone(), self.active);
+            self.active.map(|active| prose.match_rects()[active])
+        };
+        if let Some(rect) = active_rect {
+            portal.pan_viewport_to(rect);
+        }
+    }
+}
+
+impl AppDriver for Driver {
+    fn on_action(&mut self, ctx: &mut DriverCtx<'_>, _widget_id: WidgetId, action: Action) {
+        match action {
+            Action::TextChanged(query) => {
+                self.matches = find_all(TEXT, &query);
+                self.active = (!self.matches.is_empty()).then_some(0);
+            }
+            Action::TextEntered(_) => {
+                let count = self.matches.len();
+                self.active = self.active.map(|active| (active + 1) % count);
+            }
+            _ => return,
+        }
+        self.update(ctx);
+    }
+}
+
+/// The byte ranges of the case-insensitive matches of `query` in `text`.
+///
+/// An empty query matches nothing, which clears the highlights.
+fn find_all(text: &str, query: &str) -> Vec<Range<usize>> {
+    if query.is_empty() {
+        return Vec::new();
+    }
+    let same_char = |a: char, b: char| a.to_lowercase().eq(b.to_lowercase());
+    text.char_indices()
+        .filter_map(|(start, _)| {
+            let mut text_chars = text[start..].char_indices();
+            for query_char in query.chars() {
+                let (_, text_char) = text_chars.next()?;
+                if !same_char(text_char, query_char) {
+                    return None;
+                }
+            }
+            let end = text_chars.next().map_or(text.len(), |(i, _)| start + i);
+            Some(start..end)
+        })
+        // Matches don't overlap.
+        .fold(Vec::new(), |mut matches: Vec<Range<usize>>, range| {
+            match matches.last() {
+                Some(last) if last.end > range.start => {}
+                _ => matches.push(range),
+            }
+            matches
+        })
+}
+
+pub fn main() {
+    let window_size = LogicalSize::new(400.0, 300.0);
+    let window_attributes = Window::default_attributes()
+        .with_title("Find")
+        .with_resizable(true)
+        .with_inner_size(window_size);
+
+    let root = Flex::column()
+        .with_child(Textbox::new(""))
+        .with_child(Label::new("No matches"))
+        .with_flex_child(
+            Portal::new(Prose::new(TEXT).with_line_break_mode(LineBreaking::WordWrap))
+                .constrain_horizontal(true),
+            1.0,
+        );
+
+    masonry::event_loop_runner::run(
+        masonry::event_loop_runner::EventLoop::with_user_event(),
+        window_attributes,
+        RootWidget::new(root),
+        Driver {
+            matches: Vec::new(),
+            active: None,
+        },
+    )
+    .unwrap();
+}
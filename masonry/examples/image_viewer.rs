@@ -0,0 +1,159 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! An image viewer which can be zoomed and rotated with two fingers on a touch screen.
+//!
+//! Double-tap to fit the image to the window again. With a mouse, the wheel zooms
+//! around the cursor.
+
+// On Windows platform, don't show a console when opening the app.
+#![windows_subsystem = "windows"]
+
+use accesskit::Role;
+use masonry::app_driver::{AppDriver, DriverCtx};
+use masonry::gesture::{Gesture, GestureRecognizer};
+use masonry::widget::{FillStrat, RootWidget, WidgetRef};
+use masonry::{
+    AccessCtx, AccessEvent, Action, Affine, BoxConstraints, EventCtx, LayoutCtx, LifeCycle,
+    LifeCycleCtx, PaintCtx, PointerEvent, Size, StatusChange, TextEvent, Widget, WidgetId,
+};
+use smallvec::SmallVec;
+use tracing::{trace_span, Span};
+use vello::peniko::{Format, Image as ImageBuf};
+use vello::Scene;
+use winit::dpi::LogicalSize;
+use winit::window::Window;
+
+/// How much a line of mouse wheel scrolling zooms in.
+const WHEEL_ZOOM: f64 = 1.1;
+
+struct Driver;
+
+impl AppDriver for Driver {
+    fn on_action(&mut self, _ctx: &mut DriverCtx<'_>, _widget_id: WidgetId, _action: Action) {}
+}
+
+struct ImageViewer {
+    image: ImageBuf,
+    /// The transform applied on top of fitting the image to the widget, in local
+    /// coordinates.
+    transform: Affine,
+    gestures: GestureRecognizer,
+}
+
+impl ImageViewer {
+    fn new(image: ImageBuf) -> Self {
+        ImageViewer {
+            image,
+            transform: Affine::IDENTITY,
+            gestures: GestureRecognizer::default(),
+        }
+    }
+
+    fn apply_gestures(&mut self, origin: Affine, gestures: Vec<Gesture>) -> bool {
+        let mut changed = false;
+        for gesture in gestures {
+            match gesture {
+                Gesture::TransformUpdate { .. } => {
+                    // Gestures are in window coordinates.
+                    let update = origin.inverse() * gesture.transform().unwrap() * origin;
+                    self.transform = update * self.transform;
+                    changed = true;
+                }
+                Gesture::Tap { count: 2, .. } => {
+                    self.transform = Affine::IDENTITY;
+                    changed = true;
+                }
+                _ => {}
+            }
+        }
+        changed
+    }
+}
+
+impl Widget for ImageViewer {
+    fn on_pointer_event(&mut self, ctx: &mut EventCtx, event: &PointerEvent) {
+        let origin = Affine::translate(ctx.window_origin().to_vec2());
+        let gestures = self.gestures.handle(event);
+        let mut changed = self.apply_gestures(origin, gestures);
+        if let PointerEvent::MouseWheel(delta, _) = event {
+            if delta.y != 0.0 {
+                let zoom = WHEEL_ZOOM.powf(-delta.y.signum());
+                let cursor = event.local_position(ctx).to_vec2();
+                self.transform = Affine::translate(cursor)
+                    * Affine::scale(zoom)
+                    * Affine::translate(-cursor)
+                    * self.transform;
+                changed = true;
+            }
+        }
+        if changed {
+            ctx.request_paint();
+        }
+        if self.gestures.needs_anim_frame() {
+            ctx.request_anim_frame();
+        }
+    }
+
+    fn on_text_event(&mut self, _ctx: &mut EventCtx, _event: &TextEvent) {}
+
+    fn on_access_event(&mut self, _ctx: &mut EventCtx, _event: &AccessEvent) {}
+
+    fn on_status_change(&mut self, _ctx: &mut LifeCycleCtx, _event: &StatusChange) {}
+
+    fn lifecycle(&mut self, _ctx: &mut LifeCycleCtx, _event: &LifeCycle) {}
+
+    fn on_anim_frame(&mut self, ctx: &mut LifeCycleCtx, interval: u64) {
+        let origin = Affine::translate(ctx.window_origin().to_vec2());
+        let gestures = self.gestures.advance(interval);
+        if self.apply_gestures(origin, gestures) {
+            ctx.request_paint();
+        }
+        if self.gestures.needs_anim_frame() {
+            ctx.request_anim_frame();
+        }
+    }
+
+    fn layout(&mut self, _ctx: &mut LayoutCtx, bc: &BoxConstraints) -> Size {
+        bc.max()
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, scene: &mut Scene) {
+        let image_size = Size::new(self.image.width as f64, self.image.height as f64);
+        let fit = FillStrat::Contain.affine_to_fill(ctx.size(), image_size);
+        scene.draw_image(&self.image, self.transform * fit);
+    }
+
+    fn accessibility_role(&self) -> Role {
+        Role::Image
+    }
+
+    fn accessibility(&mut self, _ctx: &mut AccessCtx) {}
+
+    fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
+        SmallVec::new()
+    }
+
+    fn make_trace_span(&self) -> Span {
+        trace_span!("ImageViewer")
+    }
+}
+
+pub fn main() {
+    let image_bytes = include_bytes!("./assets/PicWithAlpha.png");
+    let image_data = image::load_from_memory(image_bytes).unwrap().to_rgba8();
+    let (width, height) = image_data.dimensions();
+    let image = ImageBuf::new(image_data.to_vec().into(), Format::Rgba8, width, height);
+
+    let window_attributes = Window::default_attributes()
+        .with_title("Image viewer")
+        .with_inner_size(LogicalSize::new(650.0, 450.0));
+
+    masonry::event_loop_runner::run(
+        masonry::event_loop_runner::EventLoop::with_user_event(),
+        window_attributes,
+        RootWidget::new(ImageViewer::new(image)),
+        Driver,
+    )
+    .unwrap();
+}
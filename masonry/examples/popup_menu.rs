@@ -0,0 +1,66 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! A menu opened near the edge of a small window.
+//!
+//! On platforms which support popup windows, the menu extends past the window.
+//! Elsewhere, it's shown inside the window.
+
+// On Windows platform, don't show a console when opening the app.
+#![windows_subsystem = "windows"]
+
+use masonry::app_driver::{AppDriver, DriverCtx};
+use masonry::widget::{prelude::*, RootWidget};
+use masonry::widget::{Button, Flex, Label, Popover, PopoverAlign, PopoverSide, SizedBox};
+use masonry::Action;
+use winit::dpi::LogicalSize;
+use winit::window::Window;
+
+const ITEMS: [&str; 6] = ["New", "Open", "Save", "Save as…", "Export", "Quit"];
+
+struct Driver;
+
+impl AppDriver for Driver {
+    fn on_action(&mut self, _ctx: &mut DriverCtx<'_>, widget_id: WidgetId, action: Action) {
+        match action {
+            Action::ButtonPressed => {
+                println!("Pressed menu item {widget_id:?}");
+            }
+            Action::PopoverToggled(open) => {
+                println!("Menu open: {open}");
+            }
+            action => {
+                eprintln!("Unexpected action {action:?}");
+            }
+        }
+    }
+}
+
+pub fn main() {
+    let window_size = LogicalSize::new(200.0, 120.0);
+    let window_attributes = Window::default_attributes()
+        .with_title("Popup menu")
+        .with_resizable(true)
+        .with_inner_size(window_size);
+
+    masonry::event_loop_runner::run(
+        masonry::event_loop_runner::EventLoop::with_user_event(),
+        window_attributes,
+        RootWidget::new(build_root_widget()),
+        Driver,
+    )
+    .unwrap();
+}
+
+fn build_root_widget() -> Flex {
+    let mut items = Flex::column();
+    for item in ITEMS {
+        items = items.with_child(SizedBox::new(Button::new(item)).width(120.));
+    }
+    let menu = Popover::new(Label::new("File ▾"), items)
+        .with_placement(PopoverSide::Bottom, PopoverAlign::Start)
+        .with_popup_surface(true);
+
+    // The menu is at the bottom of the window, so it doesn't fit below its anchor.
+    Flex::column().with_flex_spacer(1.0).with_child(menu)
+}
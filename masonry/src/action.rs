@@ -4,6 +4,9 @@
 use std::any::Any;
 use std::sync::Arc;
 
+use crate::kurbo::{Point, Size};
+use crate::widget::SortDirection;
+
 // TODO - Refactor - See issue #1
 
 // TODO - TextCursor changed, ImeChanged, EnterKey, MouseEnter
@@ -17,6 +20,21 @@ pub enum Action {
     TextChanged(String),
     TextEntered(String),
     CheckboxChecked(bool),
+    SwitchToggled(bool),
+    RangeChanged(f64, f64),
+    ContextMenuItemSelected(usize),
+    ComboBoxCommitted(String),
+    BreadcrumbsEllipsisClicked(Vec<usize>),
+    PopoverToggled(bool),
+    ViewportMoved(Point),
+    TreeNodeToggled(Vec<usize>, bool),
+    TreeSelectionChanged(Vec<Vec<usize>>),
+    VisibilityChanged(bool),
+    RatingChanged(f64),
+    Dragged(Point),
+    BreakpointChanged(Size),
+    Reordered(usize, usize),
+    TableSortRequested(usize, SortDirection),
     // FIXME - This is a huge hack
     Other(Arc<dyn Any>),
 }
@@ -28,6 +46,25 @@ impl PartialEq for Action {
             (Self::TextChanged(l0), Self::TextChanged(r0)) => l0 == r0,
             (Self::TextEntered(l0), Self::TextEntered(r0)) => l0 == r0,
             (Self::CheckboxChecked(l0), Self::CheckboxChecked(r0)) => l0 == r0,
+            (Self::SwitchToggled(l0), Self::SwitchToggled(r0)) => l0 == r0,
+            (Self::RangeChanged(l0, l1), Self::RangeChanged(r0, r1)) => l0 == r0 && l1 == r1,
+            (Self::ContextMenuItemSelected(l0), Self::ContextMenuItemSelected(r0)) => l0 == r0,
+            (Self::ComboBoxCommitted(l0), Self::ComboBoxCommitted(r0)) => l0 == r0,
+            (Self::BreadcrumbsEllipsisClicked(l0), Self::BreadcrumbsEllipsisClicked(r0)) => {
+                l0 == r0
+            }
+            (Self::PopoverToggled(l0), Self::PopoverToggled(r0)) => l0 == r0,
+            (Self::ViewportMoved(l0), Self::ViewportMoved(r0)) => l0 == r0,
+            (Self::TreeNodeToggled(l0, l1), Self::TreeNodeToggled(r0, r1)) => l0 == r0 && l1 == r1,
+            (Self::TreeSelectionChanged(l0), Self::TreeSelectionChanged(r0)) => l0 == r0,
+            (Self::VisibilityChanged(l0), Self::VisibilityChanged(r0)) => l0 == r0,
+            (Self::RatingChanged(l0), Self::RatingChanged(r0)) => l0 == r0,
+            (Self::Dragged(l0), Self::Dragged(r0)) => l0 == r0,
+            (Self::BreakpointChanged(l0), Self::BreakpointChanged(r0)) => l0 == r0,
+            (Self::Reordered(l0, l1), Self::Reordered(r0, r1)) => l0 == r0 && l1 == r1,
+            (Self::TableSortRequested(l0, l1), Self::TableSortRequested(r0, r1)) => {
+                l0 == r0 && l1 == r1
+            }
             #[allow(ambiguous_wide_pointer_comparisons)]
             // FIXME
             (Self::Other(val_l), Self::Other(val_r)) => Arc::ptr_eq(val_l, val_r),
@@ -43,6 +80,47 @@ impl std::fmt::Debug for Action {
             Self::TextChanged(text) => f.debug_tuple("TextChanged").field(text).finish(),
             Self::TextEntered(text) => f.debug_tuple("TextEntered").field(text).finish(),
             Self::CheckboxChecked(b) => f.debug_tuple("CheckboxChecked").field(b).finish(),
+            Self::SwitchToggled(b) => f.debug_tuple("SwitchToggled").field(b).finish(),
+            Self::RangeChanged(low, high) => f
+                .debug_tuple("RangeChanged")
+                .field(low)
+                .field(high)
+                .finish(),
+            Self::ContextMenuItemSelected(index) => f
+                .debug_tuple("ContextMenuItemSelected")
+                .field(index)
+                .finish(),
+            Self::ComboBoxCommitted(value) => {
+                f.debug_tuple("ComboBoxCommitted").field(value).finish()
+            }
+            Self::BreadcrumbsEllipsisClicked(hidden) => f
+                .debug_tuple("BreadcrumbsEllipsisClicked")
+                .field(hidden)
+                .finish(),
+            Self::PopoverToggled(open) => f.debug_tuple("PopoverToggled").field(open).finish(),
+            Self::ViewportMoved(pos) => f.debug_tuple("ViewportMoved").field(pos).finish(),
+            Self::TreeNodeToggled(path, expanded) => f
+                .debug_tuple("TreeNodeToggled")
+                .field(path)
+                .field(expanded)
+                .finish(),
+            Self::TreeSelectionChanged(paths) => {
+                f.debug_tuple("TreeSelectionChanged").field(paths).finish()
+            }
+            Self::VisibilityChanged(visible) => {
+                f.debug_tuple("VisibilityChanged").field(visible).finish()
+            }
+            Self::RatingChanged(value) => f.debug_tuple("RatingChanged").field(value).finish(),
+            Self::Dragged(position) => f.debug_tuple("Dragged").field(position).finish(),
+            Self::BreakpointChanged(size) => {
+                f.debug_tuple("BreakpointChanged").field(size).finish()
+            }
+            Self::Reordered(from, to) => f.debug_tuple("Reordered").field(from).field(to).finish(),
+            Self::TableSortRequested(column, direction) => f
+                .debug_tuple("TableSortRequested")
+                .field(column)
+                .field(direction)
+                .finish(),
             Self::Other(_) => write!(f, "Other(...)"),
         }
     }
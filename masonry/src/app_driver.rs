@@ -13,6 +13,18 @@ pub struct DriverCtx<'a> {
 
 pub trait AppDriver {
     fn on_action(&mut self, ctx: &mut DriverCtx<'_>, widget_id: WidgetId, action: Action);
+
+    /// Called when the event loop gets [`MasonryUserEvent::Wake`], which other threads can
+    /// send through an [`EventLoopProxy`](winit::event_loop::EventLoopProxy).
+    ///
+    /// [`MasonryUserEvent::Wake`]: crate::event_loop_runner::MasonryUserEvent::Wake
+    fn on_wake(&mut self, _ctx: &mut DriverCtx<'_>) {}
+
+    /// Called once the event loop is running and the window is created.
+    ///
+    /// This is where platform integrations which need a running event loop, like a
+    /// system tray icon on macOS, should be set up.
+    fn on_start(&mut self, _ctx: &mut DriverCtx<'_>) {}
 }
 
 impl<'a> DriverCtx<'a> {
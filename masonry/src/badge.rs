@@ -0,0 +1,179 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::HashMap;
+
+use parley::FontContext;
+use vello::kurbo::{Point, Rect, RoundedRect, Size, Vec2};
+use vello::peniko::Color;
+use vello::Scene;
+
+use crate::paint_scene_helpers::{fill_color, UnitPoint};
+use crate::text2::TextLayout;
+use crate::{theme, ArcStr};
+
+/// The diameter of a [`BadgeContent::Dot`].
+const DOT_SIZE: f64 = 8.0;
+/// The padding between the text of a badge and its edges.
+const TEXT_PADDING: Vec2 = Vec2::new(4.0, 1.0);
+/// The cache is cleared when it holds this many layouts, since badges are usually few.
+const MAX_CACHED_LAYOUTS: usize = 64;
+
+/// A small decoration painted above a widget, like a notification dot or an unread count.
+///
+/// A badge is set on any widget with
+/// [`WidgetMut::set_badge`](crate::widget::WidgetMut::set_badge) or
+/// [`WidgetPod::with_badge`](crate::WidgetPod::with_badge). It is centered on its anchor
+/// point of the widget's layout rect, moved by its offset, and doesn't affect layout: it
+/// may overflow the widget, and is only clipped to the window.
+///
+/// Its description, if any, is appended to the accessibility description of the widget.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Badge {
+    content: BadgeContent,
+    color: Color,
+    anchor: UnitPoint,
+    offset: Vec2,
+    description: Option<ArcStr>,
+}
+
+/// What a [`Badge`] shows.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BadgeContent {
+    /// A plain dot, e.g. to show that something changed.
+    Dot,
+    /// A short text, usually a count.
+    Text(ArcStr),
+}
+
+impl Badge {
+    /// A badge showing a dot, in the top-right corner of the widget.
+    pub fn dot() -> Self {
+        Self::new(BadgeContent::Dot)
+    }
+
+    /// A badge showing `text`, in the top-right corner of the widget.
+    pub fn text(text: impl Into<ArcStr>) -> Self {
+        Self::new(BadgeContent::Text(text.into()))
+    }
+
+    fn new(content: BadgeContent) -> Self {
+        Badge {
+            content,
+            color: theme::BADGE_COLOR,
+            anchor: UnitPoint::TOP_RIGHT,
+            offset: Vec2::ZERO,
+            description: None,
+        }
+    }
+
+    /// Builder-style method for setting the background color of the badge.
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Builder-style method for setting the point of the widget the badge is centered on.
+    pub fn with_anchor(mut self, anchor: UnitPoint) -> Self {
+        self.anchor = anchor;
+        self
+    }
+
+    /// Builder-style method for moving the badge away from its anchor.
+    pub fn with_offset(mut self, offset: impl Into<Vec2>) -> Self {
+        self.offset = offset.into();
+        self
+    }
+
+    /// Builder-style method for setting the text read by screen readers, e.g. "3 unread".
+    ///
+    /// By default, the text of the badge is read, and a dot isn't read at all.
+    pub fn with_description(mut self, description: impl Into<ArcStr>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// What the badge shows.
+    pub fn content(&self) -> &BadgeContent {
+        &self.content
+    }
+
+    /// The text appended to the accessibility description of the widget.
+    pub(crate) fn description(&self) -> Option<&str> {
+        match (&self.description, &self.content) {
+            (Some(description), _) => Some(description),
+            (None, BadgeContent::Text(text)) => Some(text),
+            (None, BadgeContent::Dot) => None,
+        }
+    }
+
+    /// The text of the badge, if any.
+    pub(crate) fn text_content(&self) -> Option<&ArcStr> {
+        match &self.content {
+            BadgeContent::Dot => None,
+            BadgeContent::Text(text) => Some(text),
+        }
+    }
+
+    /// The area covered by the badge, in the coordinates of a widget of the given size.
+    ///
+    /// `text_size` is the size of the laid out text, for text badges.
+    pub(crate) fn bounds(&self, widget_size: Size, text_size: Option<Size>) -> Rect {
+        let size = match text_size {
+            None => Size::new(DOT_SIZE, DOT_SIZE),
+            Some(text_size) => {
+                let height = text_size.height + 2.0 * TEXT_PADDING.y;
+                // Short texts get a round badge rather than a narrow one.
+                let width = (text_size.width + 2.0 * TEXT_PADDING.x).max(height);
+                Size::new(width, height)
+            }
+        };
+        let center = self.anchor.resolve(widget_size.to_rect()) + self.offset;
+        Rect::from_center_size(center, size)
+    }
+
+    /// Paint the badge in `bounds`, as returned by [`bounds`](Self::bounds).
+    pub(crate) fn paint(
+        &self,
+        scene: &mut Scene,
+        bounds: Rect,
+        text_layout: Option<&mut TextLayout<ArcStr>>,
+    ) {
+        let radius = bounds.height() / 2.0;
+        fill_color(scene, &RoundedRect::from_rect(bounds, radius), self.color);
+        if let Some(text_layout) = text_layout {
+            let text_size = text_layout.size();
+            let origin = bounds.center() - text_size.to_vec2() / 2.0;
+            text_layout.draw(scene, Point::new(origin.x, origin.y));
+        }
+    }
+}
+
+/// The text layouts of the badges of all widgets, shared since the same few short texts
+/// are usually shown by many badges.
+#[derive(Default)]
+pub(crate) struct BadgeTextCache {
+    layouts: HashMap<ArcStr, TextLayout<ArcStr>>,
+}
+
+impl BadgeTextCache {
+    /// The layout of `text`, built with the caption text size of the theme.
+    pub(crate) fn layout(
+        &mut self,
+        text: &ArcStr,
+        font_ctx: &mut FontContext,
+    ) -> &mut TextLayout<ArcStr> {
+        if self.layouts.len() >= MAX_CACHED_LAYOUTS && !self.layouts.contains_key(text) {
+            self.layouts.clear();
+        }
+        let layout = self.layouts.entry(text.clone()).or_insert_with(|| {
+            let mut layout = TextLayout::new(text.clone(), theme::TEXT_SIZE_CAPTION as f32);
+            layout.set_brush(theme::BADGE_TEXT_COLOR);
+            layout
+        });
+        if layout.needs_rebuild() {
+            layout.rebuild(font_ctx);
+        }
+        layout
+    }
+}
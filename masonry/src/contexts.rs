@@ -9,14 +9,20 @@ use std::time::Duration;
 use accesskit::{NodeBuilder, TreeUpdate};
 use parley::FontContext;
 use tracing::{trace, warn};
+use vello::Scene;
 use winit::dpi::LogicalPosition;
 
 use crate::action::Action;
+use crate::image_cache::{ImageCache, ImageHandle};
 use crate::promise::PromiseToken;
-use crate::render_root::{RenderRootSignal, RenderRootState};
+use crate::render_root::{PopupSurface, RenderRootSignal, RenderRootState};
+use crate::text2::{BrokenLines, TextBrush, TextLayoutMode, BACKGROUND_LINE_BREAKS_THRESHOLD};
 use crate::text_helpers::{ImeChangeSignal, TextFieldRegistration};
 use crate::widget::{CursorChange, WidgetMut, WidgetState};
-use crate::{CursorIcon, Insets, Point, Rect, Size, Widget, WidgetId, WidgetPod};
+use crate::{
+    Color, CursorIcon, Insets, LayoutDirection, Point, Rect, ResolvedTextStyle, Size, Theme,
+    Widget, WidgetId, WidgetPod,
+};
 
 /// A macro for implementing methods on multiple contexts.
 ///
@@ -74,6 +80,7 @@ pub struct LayoutCtx<'a> {
     pub(crate) global_state: &'a mut RenderRootState,
     pub(crate) widget_state: &'a mut WidgetState,
     pub(crate) mouse_pos: Option<Point>,
+    pub(crate) layout_direction: LayoutDirection,
 }
 
 /// A context passed to paint methods of widgets.
@@ -93,6 +100,8 @@ pub struct AccessCtx<'a> {
     pub(crate) current_node: NodeBuilder,
     pub(crate) rebuild_all: bool,
     pub(crate) scale_factor: f64,
+    /// The part of the window in which the children can be seen, in window coordinates.
+    pub(crate) viewport: Rect,
 }
 
 pub struct WorkerCtx<'a> {
@@ -103,6 +112,10 @@ pub struct WorkerCtx<'a> {
 
 pub struct WorkerFn(pub Box<dyn FnOnce(WorkerCtx) + Send + 'static>);
 
+pub(crate) type DeferredMutation = Box<dyn FnOnce(&mut WidgetMut<'_, Box<dyn Widget>>)>;
+
+pub(crate) type DescendantEdit = Box<dyn FnOnce(WidgetMut<'_, dyn Widget>)>;
+
 impl_context_method!(
     WidgetCtx<'_>,
     EventCtx<'_>,
@@ -116,6 +129,28 @@ impl_context_method!(
             self.widget_state.id
         }
 
+        /// The text style the widget inherits, resolved from its ancestors' and its own.
+        ///
+        /// Text widgets should use it for the properties they don't set themselves. See
+        /// [`TextStyle`](crate::TextStyle) for details.
+        pub fn text_style(&self) -> &ResolvedTextStyle {
+            &self.widget_state.resolved_text_style
+        }
+
+        /// The theme of the window, which defines the styles of widget variants.
+        pub fn theme(&self) -> &Theme {
+            &self.global_state.theme
+        }
+
+        /// Whether animations should be reduced.
+        ///
+        /// When this is `true`, animations that move or resize content should jump to
+        /// their end state. Animations that only show that something is in progress,
+        /// like a spinner, can keep going.
+        pub fn reduced_motion(&self) -> bool {
+            self.global_state.reduced_motion
+        }
+
         /// Skip iterating over the given child.
         ///
         /// Normally, container widgets are supposed to iterate over each of their
@@ -163,6 +198,15 @@ impl_context_method!(
             self.window_origin() + widget_point.to_vec2()
         }
 
+        /// Convert a point from the window's coordinate space to the widget's.
+        ///
+        /// This is the inverse of [`to_window`](Self::to_window). The window origin of a
+        /// widget accounts for the offsets of all its ancestors, including the scroll
+        /// offsets of portals.
+        pub fn to_local(&self, window_point: Point) -> Point {
+            window_point - self.window_origin().to_vec2()
+        }
+
         /// The "hot" (aka hover) status of a widget.
         ///
         /// A widget is "hot" when the mouse is hovered over it. Widgets will
@@ -296,6 +340,17 @@ impl_context_method!(EventCtx<'_>, {
 });
 
 impl<'a> WidgetCtx<'a> {
+    /// Set the theme of the window.
+    ///
+    /// Every widget gets [`LifeCycle::ThemeChanged`](crate::LifeCycle::ThemeChanged) once
+    /// the current edit is done. See [`RenderRoot::set_theme`](crate::render_root::RenderRoot::set_theme).
+    pub fn set_theme(&mut self, theme: Theme) {
+        if self.global_state.theme != theme {
+            self.global_state.theme = theme;
+            self.global_state.theme_changed = true;
+        }
+    }
+
     // FIXME - Assert that child's parent is self
     /// Return a [`WidgetMut`] to a child widget.
     pub fn get_mut<'c, Child: Widget>(
@@ -360,6 +415,18 @@ impl_context_method!(WidgetCtx<'_>, EventCtx<'_>, LifeCycleCtx<'_>, {
         self.widget_state.needs_paint = true;
     }
 
+    /// Set whether the scene painted by this widget is kept until it requests a paint.
+    ///
+    /// By default, a widget is painted again each time it is laid out. A widget with a
+    /// cached scene is only painted again when it calls [`request_paint`](Self::request_paint)
+    /// or [`request_layout`](Self::request_layout), or when layout changes its size or paint
+    /// rect. This is meant for static widgets which are expensive to paint, like complex
+    /// vector icons or charts, which must then request a paint whenever their content changes.
+    pub fn set_paint_cached(&mut self, cached: bool) {
+        trace!("set_paint_cached({})", cached);
+        self.widget_state.is_paint_cached = cached;
+    }
+
     /// Request a layout pass.
     ///
     /// A Widget's [`layout`] method is always called when the widget tree
@@ -382,6 +449,11 @@ impl_context_method!(WidgetCtx<'_>, EventCtx<'_>, LifeCycleCtx<'_>, {
     }
 
     /// Request an animation frame.
+    ///
+    /// The widget will get [`LifeCycle::AnimFrame`](crate::LifeCycle::AnimFrame) and
+    /// [`on_anim_frame`](crate::Widget::on_anim_frame) at the beginning of the next frame.
+    /// The request is only for that frame: animations call this again from
+    /// `on_anim_frame` (or while handling `AnimFrame`) until they are done.
     pub fn request_anim_frame(&mut self) {
         trace!("request_anim_frame");
         self.widget_state.request_anim = true;
@@ -397,6 +469,18 @@ impl_context_method!(WidgetCtx<'_>, EventCtx<'_>, LifeCycleCtx<'_>, {
         self.request_layout();
     }
 
+    /// Set the text color inherited by this widget's descendants, or inherit it from the
+    /// parent with `None`.
+    ///
+    /// This only changes the color of the widget's [`TextStyle`](crate::TextStyle), e.g. for
+    /// a button to color its label depending on whether it's hovered.
+    pub fn set_text_color(&mut self, color: Option<Color>) {
+        if self.widget_state.text_style.color != color {
+            self.widget_state.text_style.color = color;
+            self.widget_state.text_style_changed = true;
+        }
+    }
+
     /// Set the disabled state for this widget.
     ///
     /// Setting this to `false` does not mean a widget is not still disabled; for instance it may
@@ -416,10 +500,32 @@ impl_context_method!(WidgetCtx<'_>, EventCtx<'_>, LifeCycleCtx<'_>, {
     ///
     /// **Note:** Stashed widgets are a WIP feature
     pub fn set_stashed(&mut self, child: &mut WidgetPod<impl Widget>, stashed: bool) {
+        if child.state.is_stashed != stashed {
+            child.state.needs_accessibility_update = true;
+            child.state.request_accessibility_update = true;
+        }
         child.state.is_stashed = stashed;
+        // Let the child know, e.g. so that a focus scope can focus its first widget.
+        child.state.update_focus_chain = true;
         self.children_changed();
     }
 
+    /// Focus the first widget of the focus chain of this widget, i.e. the first
+    /// focusable descendant of this widget.
+    ///
+    /// This is meant to place the focus when opening a dialog or a menu, see
+    /// [`FocusScope`](crate::widget::FocusScope). The focus chain of new descendants is
+    /// only built after the current pass, in [`LifeCycle::BuildFocusChain`](crate::LifeCycle::BuildFocusChain),
+    /// which is where they should be focused from.
+    ///
+    /// Does nothing if this widget has no focusable descendants.
+    pub fn focus_first_in_scope(&mut self) {
+        trace!("focus_first_in_scope");
+        if let Some(first) = self.widget_state.focus_chain.first() {
+            self.global_state.next_focused_widget = Some(*first);
+        }
+    }
+
     #[allow(unused)]
     /// Indicate that text input state has changed.
     ///
@@ -448,6 +554,22 @@ impl_context_method!(
                 .push_back(RenderRootSignal::Action(action, self.widget_state.id));
         }
 
+        /// Queue a mutation of the widget tree to run once the current pass is over.
+        ///
+        /// Widgets can't reach outside of their own subtree during a pass: a widget
+        /// can't remove itself or one of its ancestors, and layout-dependent values
+        /// (sizes, positions) aren't up to date until the next layout pass. Mutations
+        /// like "remove the button that was just clicked" should instead be queued here.
+        ///
+        /// The callback receives the root widget, which can be downcast to its concrete type.
+        /// Deferred mutations run in the order they were queued, after the pass that queued
+        /// them and its post-processing. Calling `defer` from a deferred mutation is allowed;
+        /// the new mutation runs after the current one.
+        pub fn defer(&mut self, f: impl FnOnce(&mut WidgetMut<'_, Box<dyn Widget>>) + 'static) {
+            trace!("defer");
+            self.global_state.deferred_mutations.push_back(Box::new(f));
+        }
+
         /// Run the provided function in the background.
         ///
         /// The function takes a [`WorkerCtx`] which it can use to
@@ -482,6 +604,18 @@ impl_context_method!(
         pub fn request_timer(&mut self, _deadline: Duration) -> TimerToken {
             todo!("request_timer");
         }
+
+        /// The cache through which widgets load their images.
+        pub fn image_cache(&self) -> ImageCache {
+            self.global_state.images.clone()
+        }
+
+        /// Lay out the current widget again once the image of `handle` is decoded.
+        ///
+        /// Does nothing if the image isn't loading anymore.
+        pub fn wait_for_image(&mut self, handle: &ImageHandle) {
+            handle.wait(self.widget_state.id);
+        }
     }
 );
 
@@ -491,7 +625,16 @@ pub struct TimerToken;
 impl EventCtx<'_> {
     /// Send a signal to parent widgets to scroll this widget into view.
     pub fn request_pan_to_this(&mut self) {
-        self.request_pan_to_child = Some(self.widget_state.layout_rect());
+        self.request_pan_to(Rect::from_origin_size(
+            Point::ORIGIN,
+            self.widget_state.size,
+        ));
+    }
+
+    /// Send a signal to parent widgets to scroll `rect`, in the coordinates of this
+    /// widget, into view.
+    pub fn request_pan_to(&mut self, rect: Rect) {
+        self.request_pan_to_child = Some(rect);
     }
 
     /// Set the "active" state of the widget.
@@ -515,6 +658,25 @@ impl EventCtx<'_> {
         self.is_handled
     }
 
+    /// Start moving the window with the pointer, as when its title bar is dragged.
+    ///
+    /// This must be called while a mouse button is pressed, usually on a pointer move.
+    /// The platform takes over the pointer until the button is released.
+    pub fn drag_window(&mut self) {
+        trace!("drag_window");
+        self.global_state
+            .signal_queue
+            .push_back(RenderRootSignal::DragWindow);
+    }
+
+    /// Maximize the window, or restore it if it's already maximized.
+    pub fn toggle_window_maximized(&mut self) {
+        trace!("toggle_window_maximized");
+        self.global_state
+            .signal_queue
+            .push_back(RenderRootSignal::ToggleMaximized);
+    }
+
     /// Request keyboard focus.
     ///
     /// Because only one widget can be focused at a time, multiple focus requests
@@ -582,6 +744,7 @@ impl LifeCycleCtx<'_> {
     pub fn register_for_focus(&mut self) {
         trace!("register_for_focus");
         self.widget_state.focus_chain.push(self.widget_id());
+        self.widget_state.focus_chain_tab_indices.push(0);
     }
 
     /// Register this widget as accepting text input.
@@ -599,9 +762,82 @@ impl LifeCycleCtx<'_> {
     pub fn register_as_portal(&mut self) {
         self.widget_state.is_portal = true;
     }
+
+    /// Register this widget as handling [`LifeCycle::ViewportChanged`].
+    ///
+    /// That event is only sent to the widgets which registered, and to their ancestors.
+    /// This should be called on [`LifeCycle::WidgetAdded`].
+    ///
+    /// [`LifeCycle::ViewportChanged`]: crate::LifeCycle::ViewportChanged
+    /// [`LifeCycle::WidgetAdded`]: crate::LifeCycle::WidgetAdded
+    pub fn register_as_viewport_observer(&mut self) {
+        self.widget_state.is_viewport_observer = true;
+    }
 }
 
 impl LayoutCtx<'_> {
+    /// The size of the window's content area.
+    ///
+    /// This is useful for widgets drawing over other widgets, e.g. to keep a popover
+    /// inside the window.
+    pub fn window_size(&self) -> Size {
+        self.global_state.window_size
+    }
+
+    /// The area this widget can show a popup surface in, in window coordinates, or `None`
+    /// if the platform doesn't support popup surfaces.
+    ///
+    /// See [`show_popup`](Self::show_popup).
+    pub fn popup_bounds(&self) -> Option<Rect> {
+        self.global_state.popup_bounds
+    }
+
+    /// Show some content of this widget in a separate borderless window, covering `rect`
+    /// in window coordinates.
+    ///
+    /// This lets menus and tooltips extend past the edges of the window. The rect should
+    /// be inside [`popup_bounds`](Self::popup_bounds), and the content must be given with
+    /// [`PaintCtx::set_popup_scene`] whenever the widget is painted. Pointer events over the
+    /// popup are routed to the widget tree like events over the main window, at their
+    /// position in window coordinates: the widget should stay active while the popup is
+    /// shown, so that it gets them. Call [`hide_popup`](Self::hide_popup) to close it.
+    pub fn show_popup(&mut self, rect: Rect) {
+        let id = self.widget_state.id;
+        let global_state = &mut *self.global_state;
+        let popup = global_state
+            .popups
+            .entry(id)
+            .or_insert_with(|| PopupSurface {
+                rect: Rect::ZERO,
+                scene: Scene::new(),
+            });
+        if popup.rect != rect {
+            popup.rect = rect;
+            global_state
+                .signal_queue
+                .push_back(RenderRootSignal::ShowPopup(id, rect));
+        }
+    }
+
+    /// Close the popup surface of this widget, if it's shown.
+    pub fn hide_popup(&mut self) {
+        let id = self.widget_state.id;
+        if self.global_state.popups.remove(&id).is_some() {
+            self.global_state
+                .signal_queue
+                .push_back(RenderRootSignal::HidePopup(id));
+        }
+    }
+
+    /// The direction in which the widget should lay out its content.
+    ///
+    /// This is the direction of the window, unless the widget or one of its ancestors
+    /// overrides it. Horizontal containers should place their first child on the right
+    /// in [`LayoutDirection::Rtl`].
+    pub fn layout_direction(&self) -> LayoutDirection {
+        self.layout_direction
+    }
+
     /// Set explicit paint [`Insets`] for this widget.
     ///
     /// You are not required to set explicit paint bounds unless you need
@@ -633,6 +869,35 @@ impl LayoutCtx<'_> {
         self.widget_state.baseline_offset = baseline;
     }
 
+    /// Set the area of the text being edited, e.g. the caret or the text being composed.
+    ///
+    /// While this widget is focused, the platform's input method editor is told to show
+    /// its candidate window next to this area, and is disabled if it's `None` or the
+    /// widget is disabled. The area is in the widget's coordinates.
+    pub fn set_ime_area(&mut self, area: Option<Rect>) {
+        trace!("set_ime_area {:?}", area);
+        self.widget_state.ime_area = area;
+    }
+
+    /// Mark child widget as stashed, from the layout pass.
+    ///
+    /// This is for widgets which only show the children that fit in their size,
+    /// e.g. virtualized lists. Unlike with the other contexts, no new layout is
+    /// requested: the child must be laid out (if unstashed) in this pass.
+    ///
+    /// **Note:** Stashed widgets are a WIP feature
+    pub fn set_stashed(&mut self, child: &mut WidgetPod<impl Widget>, stashed: bool) {
+        if child.state.is_stashed == stashed {
+            return;
+        }
+        child.state.needs_accessibility_update = true;
+        child.state.request_accessibility_update = true;
+        child.state.is_stashed = stashed;
+        child.state.update_focus_chain = true;
+        self.widget_state.children_changed = true;
+        self.widget_state.update_focus_chain = true;
+    }
+
     /// Set the position of a child widget, in the paren't coordinate space. This
     /// will also implicitly change "hot" status and affect the parent's display rect.
     ///
@@ -642,6 +907,15 @@ impl LayoutCtx<'_> {
         if origin != child.state.origin {
             child.state.origin = origin;
             child.state.needs_window_origin = true;
+            // The child's state has already been merged into ours, so the flag must be
+            // set here for the window origins to be updated from the root.
+            self.widget_state.needs_window_origin = true;
+            // The bounds of the accessibility node have moved
+            child.state.needs_accessibility_update = true;
+            child.state.request_accessibility_update = true;
+            self.widget_state.request_accessibility_update = true;
+            // The scene of this widget includes the one of the child at its old origin.
+            self.widget_state.needs_paint = true;
         }
         child.state.is_expecting_place_child_call = false;
 
@@ -660,6 +934,42 @@ impl LayoutCtx<'_> {
             self.widget_state.merge_up(&mut child.state);
         }
     }
+
+    /// Whether the lines of a text of `text_len` bytes should be broken in the background
+    /// when its wrap width changes.
+    pub(crate) fn should_break_lines_in_background(&self, text_len: usize) -> bool {
+        self.global_state.line_breaks.mode() != TextLayoutMode::Sync
+            && text_len >= BACKGROUND_LINE_BREAKS_THRESHOLD
+    }
+
+    /// Break the lines of `layout` in the background, replacing the pending job of
+    /// this widget if there is one.
+    ///
+    /// Once done, the widget is laid out again, and can get the result with
+    /// [`take_background_line_breaks`](Self::take_background_line_breaks).
+    pub(crate) fn break_lines_in_background(
+        &mut self,
+        layout: parley::Layout<TextBrush>,
+        max_advance: Option<f32>,
+        alignment: parley::layout::Alignment,
+    ) {
+        let id = self.widget_state.id;
+        self.global_state
+            .line_breaks
+            .submit(id, layout, max_advance, alignment);
+    }
+
+    /// Take the lines broken in the background for this widget, if they're ready.
+    pub(crate) fn take_background_line_breaks(&mut self) -> Option<BrokenLines> {
+        let id = self.widget_state.id;
+        self.global_state.line_breaks.take(id)
+    }
+
+    /// Discard the pending line breaking job of this widget, if any.
+    pub(crate) fn cancel_background_line_breaks(&mut self) {
+        let id = self.widget_state.id;
+        self.global_state.line_breaks.cancel(id);
+    }
 }
 
 impl_context_method!(LayoutCtx<'_>, PaintCtx<'_>, {
@@ -669,6 +979,16 @@ impl_context_method!(LayoutCtx<'_>, PaintCtx<'_>, {
 });
 
 impl PaintCtx<'_> {
+    /// Set the content of the popup surface of this widget, in the coordinates of the popup.
+    ///
+    /// Does nothing if the widget doesn't show a popup surface, see
+    /// [`LayoutCtx::show_popup`].
+    pub fn set_popup_scene(&mut self, scene: Scene) {
+        if let Some(popup) = self.global_state.popups.get_mut(&self.widget_state.id) {
+            popup.scene = scene;
+        }
+    }
+
     /// The depth in the tree of the currently painting widget.
     ///
     /// This may be used in combination with [`paint_with_z_index`](Self::paint_with_z_index) in order
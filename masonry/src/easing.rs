@@ -0,0 +1,64 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Easing curves for animations.
+
+/// How an animation progresses over time.
+///
+/// An easing maps the fraction of the animation's duration which has elapsed, from 0 to 1,
+/// to how far the animated value has gone from its start to its end, also from 0 to 1.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Easing {
+    /// The value changes at a constant speed.
+    Linear,
+    /// The value starts slowly and speeds up (cubic).
+    EaseIn,
+    /// The value starts quickly and slows down (cubic).
+    EaseOut,
+    /// The value starts slowly, speeds up and slows down again (cubic).
+    #[default]
+    EaseInOut,
+}
+
+impl Easing {
+    /// The progress of the animated value after the fraction `t` of the duration.
+    ///
+    /// `t` is clamped between 0 and 1.
+    pub fn apply(self, t: f64) -> f64 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t * t,
+            Easing::EaseOut => 1.0 - (1.0 - t).powi(3),
+            Easing::EaseInOut => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn curves_go_from_start_to_end() {
+        for easing in [
+            Easing::Linear,
+            Easing::EaseIn,
+            Easing::EaseOut,
+            Easing::EaseInOut,
+        ] {
+            assert_eq!(easing.apply(0.0), 0.0);
+            assert_eq!(easing.apply(1.0), 1.0);
+            assert_eq!(easing.apply(2.0), 1.0);
+        }
+        assert_eq!(Easing::EaseInOut.apply(0.5), 0.5);
+        assert_eq!(Easing::EaseOut.apply(0.5), 0.875);
+        assert_eq!(Easing::EaseIn.apply(0.5), 0.125);
+    }
+}
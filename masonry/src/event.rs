@@ -3,9 +3,9 @@
 
 //! Events.
 
-use crate::kurbo::Rect;
+use crate::kurbo::{Point, Rect};
 // TODO - See issue #14
-use crate::WidgetId;
+use crate::{EventCtx, WidgetId};
 
 use std::{collections::HashSet, path::PathBuf};
 
@@ -72,6 +72,9 @@ pub struct PointerState {
     // pub device_id: DeviceId,
     pub physical_position: PhysicalPosition<f64>,
     pub position: LogicalPosition<f64>,
+    /// Identifies the pointer when several of them are down at once, e.g. the fingers
+    /// of a pinch gesture. The mouse is pointer 0, and each touch has its own id.
+    pub pointer_id: u64,
     pub buttons: HashSet<MouseButton>,
     pub mods: Modifiers,
     pub count: u8,
@@ -122,6 +125,9 @@ pub enum LifeCycle {
     /// As a result, you should try to avoid doing anything computationally
     /// intensive in response to an `AnimFrame` event: it might make the app miss
     /// the monitor's refresh, causing lag or jerky animations.
+    ///
+    /// This is only sent to the widgets which [requested](crate::LifeCycleCtx::request_anim_frame)
+    /// a frame and their ancestors. See also [`Widget::on_anim_frame`](crate::Widget::on_anim_frame).
     AnimFrame(u64),
 
     // TODO - Put in StatusChange
@@ -135,6 +141,28 @@ pub enum LifeCycle {
     /// [`set_disabled`]: crate::EventCtx::set_disabled
     DisabledChanged(bool),
 
+    /// Called when the [text style](crate::TextStyle) inherited by the widget changed.
+    ///
+    /// Text widgets should read the new style with
+    /// [`LifeCycleCtx::text_style`](crate::LifeCycleCtx::text_style), like they do on
+    /// [`WidgetAdded`](LifeCycle::WidgetAdded), and request a layout if their text changed. This is only sent to the widgets whose resolved style
+    /// changed, after [`WidgetMut::set_text_style`](crate::widget::WidgetMut::set_text_style)
+    /// was called on them or one of their ancestors.
+    TextStyleChanged,
+
+    /// Called on every widget when the [`Theme`](crate::Theme) of the window changed.
+    ///
+    /// Widgets styled by the theme should read it again with the `theme` context method,
+    /// and request a paint or a layout.
+    ThemeChanged,
+
+    /// Called on every widget when the window switches to or from
+    /// [reduced motion](crate::render_root::RenderRoot::set_reduced_motion).
+    ///
+    /// Animated widgets should stop their animations, or start them again, according to
+    /// the `reduced_motion` context method.
+    ReducedMotionChanged,
+
     /// Called when the widget tree changes and Masonry wants to rebuild the
     /// Focus-chain.
     ///
@@ -150,6 +178,18 @@ pub enum LifeCycle {
     /// [`EventCtx::request_pan_to_this`](crate::EventCtx::request_pan_to_this).
     RequestPanToChild(Rect),
 
+    /// Called after widgets moved, with the part of the window in which the widget can
+    /// be seen, in window coordinates.
+    ///
+    /// This is the whole window, minus what scrolling ancestors such as
+    /// [`Portal`](crate::widget::Portal) clip away. Containers which clip their children
+    /// should pass the intersection of this rect with their own clip to them.
+    ///
+    /// This is only sent to the widgets which called
+    /// [`register_as_viewport_observer`](crate::LifeCycleCtx::register_as_viewport_observer),
+    /// and to their ancestors.
+    ViewportChanged(Rect),
+
     /// Internal Masonry lifecycle event.
     ///
     /// This should always be passed down to descendant [`WidgetPod`]s.
@@ -180,6 +220,21 @@ pub enum InternalLifeCycle {
     /// Used to route the `DisabledChanged` event to the required widgets.
     RouteDisabledChanged,
 
+    /// Used to route the `TextStyleChanged` event to the required widgets.
+    RouteTextStyleChanged,
+
+    /// The lines of the given widget's text were broken in the background, and
+    /// it must be laid out again.
+    RouteLineBreaksReady(WidgetId),
+
+    /// An image the given widget was waiting for was decoded, and the widget must be
+    /// laid out again.
+    RouteImageLoaded(WidgetId),
+
+    /// Used to route [`WidgetMut::edit_descendant`](crate::widget::WidgetMut::edit_descendant)
+    /// to the given widget.
+    RouteWidgetEdit(WidgetId),
+
     /// The parents widget origin in window coordinate space has changed.
     ParentWindowOrigin {
         mouse_pos: Option<LogicalPosition<f64>>,
@@ -227,6 +282,52 @@ impl PointerEvent {
         }
     }
 
+    /// The position of the pointer, in window coordinates.
+    pub fn position(&self) -> Point {
+        let position = self.pointer_state().position;
+        Point::new(position.x, position.y)
+    }
+
+    /// The position of the pointer, in the coordinate space of the widget receiving
+    /// the event through `ctx`.
+    ///
+    /// The origin is the top left corner of the widget, whatever the ancestors
+    /// offsetting it: scrolled portals are accounted for, and so are zoomed
+    /// containers, which map the pointer position before passing events to their
+    /// children.
+    pub fn local_position(&self, ctx: &EventCtx) -> Point {
+        ctx.to_local(self.position())
+    }
+
+    fn pointer_state_mut(&mut self) -> &mut PointerState {
+        match self {
+            PointerEvent::PointerDown(_, state)
+            | PointerEvent::PointerUp(_, state)
+            | PointerEvent::PointerMove(state)
+            | PointerEvent::PointerEnter(state)
+            | PointerEvent::PointerLeave(state)
+            | PointerEvent::MouseWheel(_, state)
+            | PointerEvent::HoverFile(_, state)
+            | PointerEvent::DropFile(_, state)
+            | PointerEvent::HoverFileCancel(state) => state,
+        }
+    }
+
+    /// This event, with its logical position and wheel delta mapped into content
+    /// zoomed by `zoom` around `origin`.
+    ///
+    /// `origin` is in the same coordinates as the event position.
+    pub(crate) fn with_zoom(&self, origin: Point, zoom: f64) -> Self {
+        let mut event = self.clone();
+        if let PointerEvent::MouseWheel(delta, _) = &mut event {
+            *delta = LogicalPosition::new(delta.x / zoom, delta.y / zoom);
+        }
+        let state = event.pointer_state_mut();
+        let position = origin + (Point::new(state.position.x, state.position.y) - origin) / zoom;
+        state.position = LogicalPosition::new(position.x, position.y);
+        event
+    }
+
     pub fn short_name(&self) -> &'static str {
         match self {
             PointerEvent::PointerDown(_, _) => "PointerDown",
@@ -324,6 +425,7 @@ impl PointerState {
         PointerState {
             physical_position: PhysicalPosition::new(0.0, 0.0),
             position: LogicalPosition::new(0.0, 0.0),
+            pointer_id: 0,
             buttons: Default::default(),
             mods: Default::default(),
             count: 0,
@@ -348,8 +450,12 @@ impl LifeCycle {
             LifeCycle::WidgetAdded => true,
             LifeCycle::AnimFrame(_) => true,
             LifeCycle::DisabledChanged(_) => true,
+            LifeCycle::TextStyleChanged => true,
+            LifeCycle::ThemeChanged => true,
+            LifeCycle::ReducedMotionChanged => true,
             LifeCycle::BuildFocusChain => false,
             LifeCycle::RequestPanToChild(_) => false,
+            LifeCycle::ViewportChanged(_) => false,
         }
     }
 
@@ -362,13 +468,21 @@ impl LifeCycle {
                 InternalLifeCycle::RouteWidgetAdded => "RouteWidgetAdded",
                 InternalLifeCycle::RouteFocusChanged { .. } => "RouteFocusChanged",
                 InternalLifeCycle::RouteDisabledChanged => "RouteDisabledChanged",
+                InternalLifeCycle::RouteTextStyleChanged => "RouteTextStyleChanged",
+                InternalLifeCycle::RouteLineBreaksReady(_) => "RouteLineBreaksReady",
+                InternalLifeCycle::RouteImageLoaded(_) => "RouteImageLoaded",
+                InternalLifeCycle::RouteWidgetEdit(_) => "RouteWidgetEdit",
                 InternalLifeCycle::ParentWindowOrigin { .. } => "ParentWindowOrigin",
             },
             LifeCycle::WidgetAdded => "WidgetAdded",
             LifeCycle::AnimFrame(_) => "AnimFrame",
             LifeCycle::DisabledChanged(_) => "DisabledChanged",
+            LifeCycle::TextStyleChanged => "TextStyleChanged",
+            LifeCycle::ThemeChanged => "ThemeChanged",
+            LifeCycle::ReducedMotionChanged => "ReducedMotionChanged",
             LifeCycle::BuildFocusChain => "BuildFocusChain",
             LifeCycle::RequestPanToChild(_) => "RequestPanToChild",
+            LifeCycle::ViewportChanged(_) => "ViewportChanged",
         }
     }
 }
@@ -386,8 +500,12 @@ impl InternalLifeCycle {
         match self {
             InternalLifeCycle::RouteWidgetAdded
             | InternalLifeCycle::RouteFocusChanged { .. }
-            | InternalLifeCycle::RouteDisabledChanged => true,
-            InternalLifeCycle::ParentWindowOrigin { .. } => false,
+            | InternalLifeCycle::RouteDisabledChanged
+            | InternalLifeCycle::RouteTextStyleChanged
+            | InternalLifeCycle::RouteImageLoaded(_)
+            | InternalLifeCycle::RouteWidgetEdit(_) => true,
+            InternalLifeCycle::RouteLineBreaksReady(_)
+            | InternalLifeCycle::ParentWindowOrigin { .. } => false,
         }
     }
 }
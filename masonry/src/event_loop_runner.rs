@@ -1,27 +1,31 @@
 // Copyright 2024 the Xilem Authors
 // SPDX-License-Identifier: Apache-2.0
 
+use std::collections::HashMap;
 use std::num::NonZeroUsize;
 use std::sync::Arc;
 
 use accesskit_winit::Adapter;
 use tracing::subscriber::SetGlobalDefaultError;
-use tracing::{debug, warn};
+use tracing::{debug, info, info_span, warn};
 use vello::kurbo::Affine;
 use vello::util::{RenderContext, RenderSurface};
 use vello::{peniko::Color, AaSupport, RenderParams, Renderer, RendererOptions, Scene};
 use wgpu::PresentMode;
 use winit::application::ApplicationHandler;
-use winit::dpi::LogicalPosition;
+use winit::dpi::{LogicalPosition, LogicalSize, PhysicalPosition};
 use winit::error::EventLoopError;
 use winit::event::WindowEvent as WinitWindowEvent;
 use winit::event_loop::{ActiveEventLoop, EventLoopProxy};
-use winit::window::{Window, WindowAttributes, WindowId};
+use winit::window::{Window, WindowAttributes, WindowId, WindowLevel};
 
 use crate::app_driver::{AppDriver, DriverCtx};
 use crate::event::{PointerState, WindowEvent};
+use crate::frame_cache::{FrameCache, SurfaceBlit};
+use crate::frame_timeline;
+use crate::reduced_motion::platform_reduced_motion;
 use crate::render_root::{self, RenderRoot, WindowSizePolicy};
-use crate::{PointerEvent, TextEvent, Widget};
+use crate::{PointerEvent, Rect, TextEvent, Widget, WidgetId};
 
 pub enum WindowState<'a> {
     Uninitialized(WindowAttributes),
@@ -44,21 +48,56 @@ struct MainState<'a> {
     renderer: Option<Renderer>,
     // TODO: Winit doesn't seem to let us create these proxies from within the loop
     // The reasons for this are unclear
-    proxy: EventLoopProxy<accesskit_winit::Event>,
+    proxy: EventLoopProxy<MasonryUserEvent>,
 
     // Per-Window state
     // In future, this will support multiple windows
     window: WindowState<'a>,
+    /// The windows showing the popup surfaces of widgets, by the widget hosting them.
+    popups: HashMap<WidgetId, PopupWindow<'a>>,
+    /// Cleared when a popup window or its surface couldn't be created, after which popups
+    /// are shown inside the main window.
+    popups_supported: bool,
+    /// The last frame of the main window, of which only the damaged areas are rendered.
+    frame_cache: FrameCache,
+    /// Copies the frame of the main window to its surface, created with the surface.
+    surface_blit: Option<SurfaceBlit>,
+}
+
+/// A borderless window showing the popup surface of a widget.
+///
+/// See [`RenderRootSignal::ShowPopup`](render_root::RenderRootSignal::ShowPopup).
+struct PopupWindow<'a> {
+    window: Arc<Window>,
+    surface: RenderSurface<'a>,
+    /// The area of the popup, in the coordinates of the widget tree.
+    rect: Rect,
+}
+
+/// The custom events of Masonry's event loop.
+///
+/// An [`EventLoopProxy`] created from the event loop can send these from other threads.
+pub enum MasonryUserEvent {
+    /// An event from the platform's accessibility API.
+    AccessKit(accesskit_winit::Event),
+    /// Call [`AppDriver::on_wake`], e.g. because a background thread queued work for the app.
+    Wake,
+}
+
+impl From<accesskit_winit::Event> for MasonryUserEvent {
+    fn from(event: accesskit_winit::Event) -> Self {
+        MasonryUserEvent::AccessKit(event)
+    }
 }
 
 /// The type of the event loop used by Masonry.
 ///
 /// This *will* be changed to allow custom event types, but is implemented this way for expedience
-pub type EventLoop = winit::event_loop::EventLoop<accesskit_winit::Event>;
+pub type EventLoop = winit::event_loop::EventLoop<MasonryUserEvent>;
 /// The type of the event loop builder used by Masonry.
 ///
 /// This *will* be changed to allow custom event types, but is implemented this way for expedience
-pub type EventLoopBuilder = winit::event_loop::EventLoopBuilder<accesskit_winit::Event>;
+pub type EventLoopBuilder = winit::event_loop::EventLoopBuilder<MasonryUserEvent>;
 
 pub fn run(
     // Clearly, this API needs to be refactored, so we don't mind forcing this to be passed in here directly
@@ -92,18 +131,34 @@ pub fn run_with(
         proxy: event_loop.create_proxy(),
 
         window: WindowState::Uninitialized(window),
+        popups: HashMap::new(),
+        popups_supported: true,
+        frame_cache: FrameCache::default(),
+        surface_blit: None,
     };
+    main_state
+        .render_root
+        .set_reduced_motion(platform_reduced_motion());
 
     // If there is no default tracing subscriber, we set our own. If one has
     // already been set, we get an error which we swallow.
     // By now, we're about to take control of the event loop. The user is unlikely
     // to try to set their own subscriber once the event loop has started.
     let _ = try_init_tracing();
+    frame_timeline::start_capture_from_env();
 
-    event_loop.run_app(&mut main_state)
+    let result = event_loop.run_app(&mut main_state);
+
+    if frame_timeline::is_capturing() {
+        match frame_timeline::finish_capture() {
+            Ok(path) => info!("Frame timeline written to {}", path.display()),
+            Err(err) => warn!("Could not write the frame timeline: {err}"),
+        }
+    }
+    result
 }
 
-impl ApplicationHandler<accesskit_winit::Event> for MainState<'_> {
+impl ApplicationHandler<MasonryUserEvent> for MainState<'_> {
     fn resumed(&mut self, event_loop: &ActiveEventLoop) {
         match std::mem::replace(
             &mut self.window,
@@ -118,8 +173,6 @@ impl ApplicationHandler<accesskit_winit::Event> for MainState<'_> {
 
                 let adapter = Adapter::with_event_loop_proxy(&window, self.proxy.clone());
                 window.set_visible(visible);
-                // TODO: Use signals or some other mechanism to do fine grained ime enable
-                window.set_ime_allowed(true);
                 let window = Arc::new(window);
                 let size = window.inner_size();
                 let surface = pollster::block_on(self.render_cx.create_surface(
@@ -137,6 +190,13 @@ impl ApplicationHandler<accesskit_winit::Event> for MainState<'_> {
                 };
                 self.render_root
                     .handle_window_event(WindowEvent::Rescale(scale_factor));
+                self.update_popup_bounds();
+                self.render_root.edit_root_widget(|root| {
+                    let mut driver_ctx = DriverCtx {
+                        main_root_widget: root,
+                    };
+                    self.app_driver.on_start(&mut driver_ctx);
+                });
             }
             WindowState::Suspended {
                 window,
@@ -154,6 +214,11 @@ impl ApplicationHandler<accesskit_winit::Event> for MainState<'_> {
                     window,
                     surface,
                     accesskit_adapter,
+                };
+                // The popup windows were closed when the app was suspended.
+                let popups: Vec<_> = self.render_root.popups().collect();
+                for (id, rect) in popups {
+                    self.show_popup(event_loop, id, rect);
                 }
             }
             _ => {
@@ -173,6 +238,11 @@ impl ApplicationHandler<accesskit_winit::Event> for MainState<'_> {
                 accesskit_adapter,
             } => {
                 drop(surface);
+                // The surface may be created on another device when the app is resumed.
+                self.frame_cache = FrameCache::default();
+                self.surface_blit = None;
+                // The popups are shown again when the app is resumed.
+                self.popups.clear();
                 self.window = WindowState::Suspended {
                     window,
                     accesskit_adapter,
@@ -184,7 +254,23 @@ impl ApplicationHandler<accesskit_winit::Event> for MainState<'_> {
         }
     }
 
-    fn window_event(&mut self, event_loop: &ActiveEventLoop, _: WindowId, event: WinitWindowEvent) {
+    fn window_event(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        window_id: WindowId,
+        event: WinitWindowEvent,
+    ) {
+        let popup_id = self
+            .popups
+            .iter()
+            .find(|(_, popup)| popup.window.id() == window_id)
+            .map(|(id, _)| *id);
+        if let Some(popup_id) = popup_id {
+            self.popup_window_event(popup_id, event);
+            self.handle_signals(event_loop);
+            return;
+        }
+
         let WindowState::Rendering {
             window,
             accesskit_adapter,
@@ -203,10 +289,29 @@ impl ApplicationHandler<accesskit_winit::Event> for MainState<'_> {
             WinitWindowEvent::ScaleFactorChanged { scale_factor, .. } => {
                 self.render_root
                     .handle_window_event(WindowEvent::Rescale(scale_factor));
+                self.update_popup_bounds();
             }
             WinitWindowEvent::RedrawRequested => {
+                let frame_span =
+                    info_span!("frame", access_nodes = tracing::field::Empty).entered();
                 let (scene, tree_update) = self.render_root.redraw();
+                let access_nodes = self.render_root.access_stats().last_update_nodes;
+                frame_span.record("access_nodes", access_nodes);
                 self.render(scene);
+                drop(frame_span);
+                // The popups are painted in the same pass as the main window. Like it,
+                // only the ones overlapping the damaged areas changed.
+                let zoom = self.render_root.zoom();
+                let damage = self.render_root.paint_damage();
+                for popup in self.popups.values() {
+                    let popup_rect = popup.rect.scale_from_origin(zoom);
+                    if damage
+                        .iter()
+                        .any(|damaged| damaged.intersect(popup_rect).area() > 0.0)
+                    {
+                        popup.window.request_redraw();
+                    }
+                }
                 let WindowState::Rendering {
                     accesskit_adapter, ..
                 } = &mut self.window
@@ -220,6 +325,10 @@ impl ApplicationHandler<accesskit_winit::Event> for MainState<'_> {
             WinitWindowEvent::Resized(size) => {
                 self.render_root
                     .handle_window_event(WindowEvent::Resize(size));
+                self.update_popup_bounds();
+            }
+            WinitWindowEvent::Moved(_) => {
+                self.update_popup_bounds();
             }
             WinitWindowEvent::ModifiersChanged(modifiers) => {
                 self.pointer_state.mods = modifiers;
@@ -285,10 +394,15 @@ impl ApplicationHandler<accesskit_winit::Event> for MainState<'_> {
                     ));
             }
             WinitWindowEvent::Touch(winit::event::Touch {
-                location, phase, ..
+                location,
+                phase,
+                id,
+                ..
             }) => {
                 // FIXME: This is naïve and should be refined for actual use.
                 //        It will also interact with gesture discrimination.
+                // Touches are numbered from 1, since the mouse is pointer 0.
+                self.pointer_state.pointer_id = id.saturating_add(1);
                 self.pointer_state.physical_position = location;
                 self.pointer_state.position = location.to_logical(window.scale_factor());
                 match phase {
@@ -323,6 +437,7 @@ impl ApplicationHandler<accesskit_winit::Event> for MainState<'_> {
                             ));
                     }
                 }
+                self.pointer_state.pointer_id = 0;
             }
             _ => (),
         }
@@ -330,7 +445,20 @@ impl ApplicationHandler<accesskit_winit::Event> for MainState<'_> {
         self.handle_signals(event_loop);
     }
 
-    fn user_event(&mut self, event_loop: &ActiveEventLoop, event: accesskit_winit::Event) {
+    fn user_event(&mut self, event_loop: &ActiveEventLoop, event: MasonryUserEvent) {
+        let event = match event {
+            MasonryUserEvent::AccessKit(event) => event,
+            MasonryUserEvent::Wake => {
+                self.render_root.edit_root_widget(|root| {
+                    let mut driver_ctx = DriverCtx {
+                        main_root_widget: root,
+                    };
+                    self.app_driver.on_wake(&mut driver_ctx);
+                });
+                self.handle_signals(event_loop);
+                return;
+            }
+        };
         match event.window_event {
             // Note that this event can be called at any time, even multiple times if
             // the user restarts their screen reader.
@@ -357,60 +485,208 @@ impl MainState<'_> {
             tracing::warn!("Tried to render whilst suspended or before window created");
             return;
         };
-        let scale = window.scale_factor();
-        let size = window.inner_size();
-        let width = size.width;
-        let height = size.height;
+        let scale = window.scale_factor() * self.render_root.zoom();
+        // The damage is already zoomed.
+        let damage: Vec<Rect> = self
+            .render_root
+            .paint_damage()
+            .iter()
+            .map(|rect| rect.scale_from_origin(window.scale_factor()))
+            .collect();
+        render_damage_to_surface(
+            &mut self.render_cx,
+            &mut self.renderer,
+            &mut self.frame_cache,
+            &mut self.surface_blit,
+            window,
+            surface,
+            &scene,
+            scale,
+            &damage,
+        );
+    }
 
-        if surface.config.width != width || surface.config.height != height {
-            self.render_cx.resize_surface(surface, width, height);
+    fn render_popup(&mut self, id: WidgetId) {
+        let Some(popup) = self.popups.get_mut(&id) else {
+            return;
+        };
+        let Some(scene) = self.render_root.popup_scene(id) else {
+            return;
+        };
+        let scale = popup.window.scale_factor() * self.render_root.zoom();
+        render_to_surface(
+            &mut self.render_cx,
+            &mut self.renderer,
+            &popup.window,
+            &mut popup.surface,
+            scene,
+            scale,
+        );
+    }
+
+    /// Handle an event of the window showing the popup surface of `id`.
+    ///
+    /// Pointer events are sent to the render root at their position in the main window.
+    fn popup_window_event(&mut self, id: WidgetId, event: WinitWindowEvent) {
+        let Some(popup) = self.popups.get(&id) else {
+            return;
+        };
+        match event {
+            WinitWindowEvent::RedrawRequested => self.render_popup(id),
+            WinitWindowEvent::CursorMoved { position, .. } => {
+                let zoom = self.render_root.zoom();
+                let scale_factor = popup.window.scale_factor();
+                let origin = popup.rect.origin();
+                let position = position.to_logical::<f64>(scale_factor);
+                self.pointer_state.position = LogicalPosition::new(
+                    position.x + origin.x * zoom,
+                    position.y + origin.y * zoom,
+                );
+                self.pointer_state.physical_position =
+                    self.pointer_state.position.to_physical(scale_factor);
+                self.render_root
+                    .handle_pointer_event(PointerEvent::PointerMove(self.pointer_state.clone()));
+            }
+            WinitWindowEvent::MouseInput { state, button, .. } => {
+                let event = match state {
+                    winit::event::ElementState::Pressed => {
+                        PointerEvent::PointerDown(button, self.pointer_state.clone())
+                    }
+                    winit::event::ElementState::Released => {
+                        PointerEvent::PointerUp(button, self.pointer_state.clone())
+                    }
+                };
+                self.render_root.handle_pointer_event(event);
+            }
+            WinitWindowEvent::MouseWheel { delta, .. } => {
+                let delta = match delta {
+                    winit::event::MouseScrollDelta::LineDelta(x, y) => {
+                        LogicalPosition::new(x as f64, y as f64)
+                    }
+                    winit::event::MouseScrollDelta::PixelDelta(delta) => {
+                        delta.to_logical(popup.window.scale_factor())
+                    }
+                };
+                self.render_root
+                    .handle_pointer_event(PointerEvent::MouseWheel(
+                        delta,
+                        self.pointer_state.clone(),
+                    ));
+            }
+            // Keyboard input goes to the main window, which keeps the focus.
+            _ => (),
         }
+    }
 
-        let transformed_scene = if scale == 1.0 {
-            None
-        } else {
-            let mut new_scene = Scene::new();
-            new_scene.append(&scene, Some(Affine::scale(scale)));
-            Some(new_scene)
+    /// Set the popup bounds of the render root to the monitor the window is on.
+    ///
+    /// Popups aren't supported on platforms which don't let us position windows.
+    fn update_popup_bounds(&mut self) {
+        let WindowState::Rendering { window, .. } = &self.window else {
+            return;
         };
-        let scene_ref = transformed_scene.as_ref().unwrap_or(&scene);
+        let bounds = match (window.inner_position(), window.current_monitor()) {
+            (Ok(window_position), Some(monitor)) => {
+                let scale = window.scale_factor() * self.render_root.zoom();
+                let monitor_position = monitor.position();
+                let monitor_size = monitor.size();
+                let x0 = (monitor_position.x - window_position.x) as f64 / scale;
+                let y0 = (monitor_position.y - window_position.y) as f64 / scale;
+                Some(Rect::new(
+                    x0,
+                    y0,
+                    x0 + monitor_size.width as f64 / scale,
+                    y0 + monitor_size.height as f64 / scale,
+                ))
+            }
+            _ => None,
+        };
+        self.render_root
+            .set_popup_bounds(bounds.filter(|_| self.popups_supported));
+    }
+
+    /// Stop using popup windows, e.g. because the platform can't create them, so that
+    /// widgets show their popups inside the main window instead.
+    fn disable_popups(&mut self) {
+        self.popups_supported = false;
+        self.popups.clear();
+        // The widgets hide their popup surfaces when they're laid out again.
+        self.render_root.set_popup_bounds(None);
+    }
 
-        let Ok(surface_texture) = surface.surface.get_current_texture() else {
-            warn!("failed to acquire next swapchain texture");
+    /// Create or move the window showing the popup surface of `id`.
+    fn show_popup(&mut self, event_loop: &ActiveEventLoop, id: WidgetId, rect: Rect) {
+        if !self.popups_supported {
+            return;
+        }
+        let WindowState::Rendering { window, .. } = &self.window else {
             return;
         };
-        let dev_id = surface.dev_id;
-        let device = &self.render_cx.devices[dev_id].device;
-        let queue = &self.render_cx.devices[dev_id].queue;
-        let renderer_options = RendererOptions {
-            surface_format: Some(surface.format),
-            use_cpu: false,
-            antialiasing_support: AaSupport {
-                area: true,
-                msaa8: false,
-                msaa16: false,
-            },
-            num_init_threads: NonZeroUsize::new(1),
+        let Ok(window_position) = window.inner_position() else {
+            return;
         };
-        let render_params = RenderParams {
-            base_color: Color::BLACK,
-            width,
-            height,
-            antialiasing_method: vello::AaConfig::Area,
+        let scale = window.scale_factor() * self.render_root.zoom();
+        let position = PhysicalPosition::new(
+            window_position.x + (rect.x0 * scale).round() as i32,
+            window_position.y + (rect.y0 * scale).round() as i32,
+        );
+        let zoom = self.render_root.zoom();
+        let size = LogicalSize::new(rect.width() * zoom, rect.height() * zoom);
+
+        if let Some(popup) = self.popups.get_mut(&id) {
+            popup.rect = rect;
+            popup.window.set_outer_position(position);
+            let _ = popup.window.request_inner_size(size);
+            popup.window.request_redraw();
+            return;
+        }
+
+        let attributes = WindowAttributes::default()
+            .with_decorations(false)
+            .with_resizable(false)
+            .with_active(false)
+            .with_window_level(WindowLevel::AlwaysOnTop)
+            .with_position(position)
+            .with_inner_size(size);
+        let popup_window = match event_loop.create_window(attributes) {
+            Ok(popup_window) => Arc::new(popup_window),
+            Err(err) => {
+                warn!("Couldn't create a popup window, showing popups in the window: {err}");
+                self.disable_popups();
+                return;
+            }
         };
-        self.renderer
-            .get_or_insert_with(|| Renderer::new(device, renderer_options).unwrap())
-            .render_to_surface(device, queue, scene_ref, &surface_texture, &render_params)
-            .expect("failed to render to surface");
-        surface_texture.present();
-        device.poll(wgpu::Maintain::Wait);
+        let physical_size = popup_window.inner_size();
+        let surface = match pollster::block_on(self.render_cx.create_surface(
+            popup_window.clone(),
+            physical_size.width,
+            physical_size.height,
+            PresentMode::AutoVsync,
+        )) {
+            Ok(surface) => surface,
+            Err(err) => {
+                warn!("Couldn't render to a popup window, showing popups in the window: {err}");
+                self.disable_popups();
+                return;
+            }
+        };
+        popup_window.request_redraw();
+        self.popups.insert(
+            id,
+            PopupWindow {
+                window: popup_window,
+                surface,
+                rect,
+            },
+        );
     }
 
-    fn handle_signals(&mut self, _event_loop: &ActiveEventLoop) {
+    fn handle_signals(&mut self, event_loop: &ActiveEventLoop) {
         let WindowState::Rendering { window, .. } = &mut self.window else {
             tracing::warn!("Tried to handle a signal whilst suspended or before window created");
             return;
         };
+        let window = window.clone();
         while let Some(signal) = self.render_root.pop_signal() {
             match signal {
                 render_root::RenderRootSignal::Action(action, widget_id) => {
@@ -433,13 +709,16 @@ impl MainState<'_> {
                     // TODO
                 }
                 render_root::RenderRootSignal::ImeStarted => {
-                    // TODO
+                    window.set_ime_allowed(true);
                 }
-                render_root::RenderRootSignal::ImeMoved => {
-                    // TODO
+                render_root::RenderRootSignal::ImeMoved(area) => {
+                    window.set_ime_cursor_area(
+                        LogicalPosition::new(area.x0, area.y0),
+                        LogicalSize::new(area.width(), area.height()),
+                    );
                 }
                 render_root::RenderRootSignal::ImeInvalidated => {
-                    // TODO
+                    window.set_ime_allowed(false);
                 }
                 render_root::RenderRootSignal::RequestRedraw => {
                     window.request_redraw();
@@ -464,11 +743,149 @@ impl MainState<'_> {
                 render_root::RenderRootSignal::SetTitle(title) => {
                     window.set_title(&title);
                 }
+                render_root::RenderRootSignal::DragWindow => {
+                    // Fails if no mouse button is pressed, or on platforms without support.
+                    if let Err(err) = window.drag_window() {
+                        tracing::warn!("Couldn't drag the window: {err}");
+                    }
+                }
+                render_root::RenderRootSignal::ToggleMaximized => {
+                    window.set_maximized(!window.is_maximized());
+                }
+                render_root::RenderRootSignal::ShowPopup(id, rect) => {
+                    self.show_popup(event_loop, id, rect);
+                }
+                render_root::RenderRootSignal::HidePopup(id) => {
+                    self.popups.remove(&id);
+                }
             }
         }
     }
 }
 
+/// Render `scene`, in logical coordinates, to the surface of `window`.
+fn render_to_surface(
+    render_cx: &mut RenderContext,
+    renderer: &mut Option<Renderer>,
+    window: &Window,
+    surface: &mut RenderSurface<'_>,
+    scene: &Scene,
+    scale: f64,
+) {
+    let size = window.inner_size();
+    let width = size.width;
+    let height = size.height;
+
+    if surface.config.width != width || surface.config.height != height {
+        render_cx.resize_surface(surface, width, height);
+    }
+
+    let transformed_scene = if scale == 1.0 {
+        None
+    } else {
+        let mut new_scene = Scene::new();
+        new_scene.append(scene, Some(Affine::scale(scale)));
+        Some(new_scene)
+    };
+    let scene_ref = transformed_scene.as_ref().unwrap_or(scene);
+
+    let Ok(surface_texture) = surface.surface.get_current_texture() else {
+        warn!("failed to acquire next swapchain texture");
+        return;
+    };
+    let dev_id = surface.dev_id;
+    let device = &render_cx.devices[dev_id].device;
+    let queue = &render_cx.devices[dev_id].queue;
+    let _span = info_span!("gpu_submit").entered();
+    renderer
+        .get_or_insert_with(|| Renderer::new(device, renderer_options(surface.format)).unwrap())
+        .render_to_surface(
+            device,
+            queue,
+            scene_ref,
+            &surface_texture,
+            &render_params(width, height),
+        )
+        .expect("failed to render to surface");
+    surface_texture.present();
+    device.poll(wgpu::Maintain::Wait);
+}
+
+/// Render the areas of `scene` which changed since the last frame in `frame_cache`, and
+/// present the whole frame on `surface`.
+///
+/// `damage` is in pixels; see [`RenderRoot::paint_damage`].
+#[allow(clippy::too_many_arguments)]
+fn render_damage_to_surface(
+    render_cx: &mut RenderContext,
+    renderer: &mut Option<Renderer>,
+    frame_cache: &mut FrameCache,
+    surface_blit: &mut Option<SurfaceBlit>,
+    window: &Window,
+    surface: &mut RenderSurface<'_>,
+    scene: &Scene,
+    scale: f64,
+    damage: &[Rect],
+) {
+    let size = window.inner_size();
+    let width = size.width;
+    let height = size.height;
+
+    if surface.config.width != width || surface.config.height != height {
+        render_cx.resize_surface(surface, width, height);
+    }
+
+    let Ok(surface_texture) = surface.surface.get_current_texture() else {
+        warn!("failed to acquire next swapchain texture");
+        return;
+    };
+    let dev_id = surface.dev_id;
+    let device = &render_cx.devices[dev_id].device;
+    let queue = &render_cx.devices[dev_id].queue;
+    let span = info_span!("gpu_submit", rasterized = tracing::field::Empty).entered();
+    let renderer = renderer
+        .get_or_insert_with(|| Renderer::new(device, renderer_options(surface.format)).unwrap());
+    let rasterized = frame_cache.render(
+        &render_cx.devices[dev_id],
+        renderer,
+        scene,
+        Affine::scale(scale),
+        damage,
+        &render_params(width, height),
+    );
+    span.record("rasterized", tracing::field::debug(rasterized));
+    let Some(frame) = frame_cache.texture() else {
+        return;
+    };
+    surface_blit
+        .get_or_insert_with(|| SurfaceBlit::new(device, surface.format))
+        .blit(device, queue, frame, &surface_texture);
+    surface_texture.present();
+    device.poll(wgpu::Maintain::Wait);
+}
+
+fn renderer_options(surface_format: wgpu::TextureFormat) -> RendererOptions {
+    RendererOptions {
+        surface_format: Some(surface_format),
+        use_cpu: false,
+        antialiasing_support: AaSupport {
+            area: true,
+            msaa8: false,
+            msaa16: false,
+        },
+        num_init_threads: NonZeroUsize::new(1),
+    }
+}
+
+fn render_params(width: u32, height: u32) -> RenderParams {
+    RenderParams {
+        base_color: Color::BLACK,
+        width,
+        height,
+        antialiasing_method: vello::AaConfig::Area,
+    }
+}
+
 pub(crate) fn try_init_tracing() -> Result<(), SetGlobalDefaultError> {
     #[cfg(not(target_arch = "wasm32"))]
     {
@@ -504,9 +921,11 @@ pub(crate) fn try_init_tracing() -> Result<(), SetGlobalDefaultError> {
             )))
             .with_target(false);
 
+        // The filter only applies to the logs, so that the frame timeline can record
+        // the spans of the passes regardless of the log level.
         let registry = tracing_subscriber::registry()
-            .with(env_filter)
-            .with(fmt_layer);
+            .with(fmt_layer.with_filter(env_filter))
+            .with(frame_timeline::layer().with_filter(LevelFilter::INFO));
         tracing::dispatcher::set_global_default(registry.into())
     }
 
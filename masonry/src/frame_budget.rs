@@ -0,0 +1,250 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Measuring the layout and paint passes of each frame against a time budget.
+//!
+//! A [`RenderRoot`](crate::render_root::RenderRoot) with a
+//! [frame budget](crate::render_root::RenderRoot::set_frame_budget) times its layout and
+//! paint passes, and the time each widget spends in them, not counting its children.
+//! When a frame goes over the budget, a warning is logged naming the slowest widgets,
+//! and the frame is recorded in the [`FrameBudgetStats`]. Depending on the
+//! [`BudgetPolicy`], the work which doesn't affect what is displayed is then postponed
+//! to the next frame.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use instant::Instant;
+use tracing::warn;
+
+use crate::WidgetId;
+
+/// The number of widgets named by a [`BudgetOverrun`].
+const SLOWEST_WIDGET_COUNT: usize = 3;
+
+/// What the render root does with the frames going over the budget.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum BudgetPolicy {
+    /// Only report them.
+    #[default]
+    Report,
+    /// Also postpone their accessibility update to the next frame.
+    ///
+    /// The layout and paint passes are never postponed, so what is displayed is the same
+    /// as with [`Report`](Self::Report).
+    DeferNonCritical,
+}
+
+/// A frame which went over the budget.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BudgetOverrun {
+    /// The time spent in the layout passes of the frame.
+    pub layout: Duration,
+    /// The time spent in the paint pass of the frame.
+    pub paint: Duration,
+    /// The widgets which spent the most time in these passes, not counting their
+    /// children, by id and type name, the slowest first.
+    pub slowest_widgets: Vec<(WidgetId, &'static str, Duration)>,
+}
+
+/// Counters about the frames measured against the budget.
+///
+/// See [`RenderRoot::frame_budget_stats`](crate::render_root::RenderRoot::frame_budget_stats).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FrameBudgetStats {
+    /// The number of frames measured so far.
+    pub frames: u64,
+    /// The number of frames which went over the budget.
+    pub over_budget_frames: u64,
+    /// The number of accessibility updates postponed to the next frame.
+    pub deferred_access_updates: u64,
+    /// The most recent frame which went over the budget.
+    pub last_overrun: Option<BudgetOverrun>,
+}
+
+/// A clock which only moves when told to, to measure passes deterministically in tests.
+///
+/// See [`RenderRoot::use_virtual_clock`](crate::render_root::RenderRoot::use_virtual_clock).
+#[derive(Clone, Debug, Default)]
+pub struct VirtualClock(Arc<AtomicU64>);
+
+impl VirtualClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Move the clock forward, e.g. from a widget simulating a slow pass.
+    pub fn advance(&self, duration: Duration) {
+        self.0
+            .fetch_add(duration.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    /// The time the clock was moved forward by so far.
+    pub fn elapsed(&self) -> Duration {
+        Duration::from_nanos(self.0.load(Ordering::Relaxed))
+    }
+}
+
+enum Clock {
+    Real(Instant),
+    Virtual(VirtualClock),
+}
+
+impl Clock {
+    fn now(&self) -> Duration {
+        match self {
+            Clock::Real(start) => start.elapsed(),
+            Clock::Virtual(clock) => clock.elapsed(),
+        }
+    }
+}
+
+/// The time measured so far in the current frame.
+pub(crate) struct FrameBudget {
+    budget: Option<Duration>,
+    pub(crate) policy: BudgetPolicy,
+    clock: Clock,
+    layout: Duration,
+    paint: Duration,
+    /// The time spent by each widget in the current frame, not counting its children.
+    widget_times: HashMap<WidgetId, (&'static str, Duration)>,
+    /// The time spent in the children of the widget currently measured.
+    children_time: Duration,
+    pub(crate) stats: FrameBudgetStats,
+}
+
+/// The start of a measure, see [`FrameBudget::start_widget`].
+pub(crate) struct WidgetMeasure {
+    start: Duration,
+    outer_children_time: Duration,
+}
+
+impl FrameBudget {
+    pub(crate) fn new() -> Self {
+        FrameBudget {
+            budget: None,
+            policy: BudgetPolicy::default(),
+            clock: Clock::Real(Instant::now()),
+            layout: Duration::ZERO,
+            paint: Duration::ZERO,
+            widget_times: HashMap::new(),
+            children_time: Duration::ZERO,
+            stats: FrameBudgetStats::default(),
+        }
+    }
+
+    pub(crate) fn budget(&self) -> Option<Duration> {
+        self.budget
+    }
+
+    pub(crate) fn set_budget(&mut self, budget: Option<Duration>) {
+        self.budget = budget;
+        self.reset_frame();
+    }
+
+    pub(crate) fn set_clock(&mut self, clock: VirtualClock) {
+        self.clock = Clock::Virtual(clock);
+        self.reset_frame();
+    }
+
+    /// Start measuring a widget method, if there is a budget.
+    ///
+    /// The methods of its children called in between are measured separately.
+    pub(crate) fn start_widget(&mut self) -> Option<WidgetMeasure> {
+        self.budget?;
+        Some(WidgetMeasure {
+            start: self.clock.now(),
+            outer_children_time: std::mem::take(&mut self.children_time),
+        })
+    }
+
+    pub(crate) fn end_widget(
+        &mut self,
+        measure: Option<WidgetMeasure>,
+        id: WidgetId,
+        type_name: &'static str,
+    ) {
+        let Some(measure) = measure else {
+            return;
+        };
+        let total = self.clock.now().saturating_sub(measure.start);
+        let own_time = total.saturating_sub(self.children_time);
+        self.widget_times
+            .entry(id)
+            .or_insert((type_name, Duration::ZERO))
+            .1 += own_time;
+        self.children_time = measure.outer_children_time + total;
+    }
+
+    /// Start measuring a pass, if there is a budget.
+    pub(crate) fn start_pass(&self) -> Option<Duration> {
+        self.budget.map(|_| self.clock.now())
+    }
+
+    pub(crate) fn end_layout(&mut self, start: Option<Duration>) {
+        if let Some(start) = start {
+            self.layout += self.clock.now().saturating_sub(start);
+            self.children_time = Duration::ZERO;
+        }
+    }
+
+    pub(crate) fn end_paint(&mut self, start: Option<Duration>) {
+        if let Some(start) = start {
+            self.paint += self.clock.now().saturating_sub(start);
+            self.children_time = Duration::ZERO;
+        }
+    }
+
+    /// Compare the passes of the frame to the budget, and start measuring the next one.
+    ///
+    /// Returns whether the frame went over the budget.
+    pub(crate) fn end_frame(&mut self) -> bool {
+        let Some(budget) = self.budget else {
+            return false;
+        };
+        let (layout, paint) = (self.layout, self.paint);
+        let widget_times = std::mem::take(&mut self.widget_times);
+        self.reset_frame();
+
+        self.stats.frames += 1;
+        if layout + paint <= budget {
+            return false;
+        }
+
+        let mut slowest_widgets: Vec<_> = widget_times
+            .into_iter()
+            .map(|(id, (type_name, time))| (id, type_name, time))
+            .collect();
+        slowest_widgets.sort_by(|a, b| b.2.cmp(&a.2).then(a.0.to_raw().cmp(&b.0.to_raw())));
+        slowest_widgets.truncate(SLOWEST_WIDGET_COUNT);
+
+        let slowest: Vec<_> = slowest_widgets
+            .iter()
+            .map(|(id, type_name, time)| format!("'{type_name}' #{} ({time:?})", id.to_raw()))
+            .collect();
+        warn!(
+            ?layout,
+            ?paint,
+            ?budget,
+            slowest = slowest.join(", "),
+            "Frame went over its budget"
+        );
+
+        self.stats.over_budget_frames += 1;
+        self.stats.last_overrun = Some(BudgetOverrun {
+            layout,
+            paint,
+            slowest_widgets,
+        });
+        true
+    }
+
+    fn reset_frame(&mut self) {
+        self.layout = Duration::ZERO;
+        self.paint = Duration::ZERO;
+        self.widget_times.clear();
+        self.children_time = Duration::ZERO;
+    }
+}
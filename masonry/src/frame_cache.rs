@@ -0,0 +1,386 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Rendering only the areas of a window which changed.
+
+use vello::kurbo::{Affine, Rect};
+use vello::util::DeviceHandle;
+use vello::{RenderParams, Renderer, Scene};
+use wgpu::{
+    CommandEncoderDescriptor, Device, Extent3d, ImageCopyTexture, Origin3d, Queue, SurfaceTexture,
+    Texture, TextureAspect, TextureDescriptor, TextureFormat, TextureUsages, TextureView,
+};
+
+/// The last frame rendered in a window, kept so that the next frames only rasterize
+/// their damaged areas.
+///
+/// Vello always renders its whole target texture, and the contents of a swapchain
+/// texture aren't preserved from one frame to the next. Instead, the frame is kept in a
+/// texture of its own: the bounding box of the damaged areas is rendered in a scratch
+/// texture, copied into the frame, and the frame is then copied to the surface with
+/// [`SurfaceBlit`].
+#[derive(Default)]
+pub(crate) struct FrameCache {
+    frame: Option<Frame>,
+}
+
+struct Frame {
+    texture: Texture,
+    view: TextureView,
+    scratch: Texture,
+    scratch_view: TextureView,
+    width: u32,
+    height: u32,
+    transform: Affine,
+}
+
+impl FrameCache {
+    /// Update the frame to show `scene` drawn with `transform`, and return the area which
+    /// was rasterized, in pixels.
+    ///
+    /// `damage` is in pixels. Anything drawn outside of it must be identical to the last
+    /// frame. The whole frame is rendered when there is no last frame, or when the size
+    /// in `params` or `transform` changed.
+    pub(crate) fn render(
+        &mut self,
+        device_handle: &DeviceHandle,
+        renderer: &mut Renderer,
+        scene: &Scene,
+        transform: Affine,
+        damage: &[Rect],
+        params: &RenderParams,
+    ) -> Option<Rect> {
+        let device = &device_handle.device;
+        let queue = &device_handle.queue;
+        let (width, height) = (params.width, params.height);
+        let is_valid = self.frame.as_ref().is_some_and(|frame| {
+            frame.width == width && frame.height == height && frame.transform == transform
+        });
+        if !is_valid {
+            let frame = Frame::new(device, width, height, transform);
+            let mut transformed_scene = Scene::new();
+            transformed_scene.append(scene, Some(transform));
+            renderer
+                .render_to_texture(device, queue, &transformed_scene, &frame.view, params)
+                .expect("failed to render the frame");
+            self.frame = Some(frame);
+            return Some(Rect::new(0., 0., width as f64, height as f64));
+        }
+        let frame = self.frame.as_mut().unwrap();
+
+        let bounds = Rect::new(0., 0., width as f64, height as f64);
+        let damaged = damage
+            .iter()
+            .copied()
+            .reduce(|a, b| a.union(b))?
+            .expand()
+            .intersect(bounds);
+        if damaged.area() == 0.0 {
+            return None;
+        }
+
+        // The damaged box is rendered at the origin of the scratch texture, on whole
+        // pixels, so that its pixels are the same as in a full render.
+        let mut patch = Scene::new();
+        patch.append(
+            scene,
+            Some(Affine::translate((-damaged.x0, -damaged.y0)) * transform),
+        );
+        let patch_params = RenderParams {
+            width: damaged.width() as u32,
+            height: damaged.height() as u32,
+            ..*params
+        };
+        renderer
+            .render_to_texture(device, queue, &patch, &frame.scratch_view, &patch_params)
+            .expect("failed to render the damaged area");
+
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("Copy damaged area"),
+        });
+        encoder.copy_texture_to_texture(
+            frame.scratch.as_image_copy(),
+            ImageCopyTexture {
+                texture: &frame.texture,
+                mip_level: 0,
+                origin: Origin3d {
+                    x: damaged.x0 as u32,
+                    y: damaged.y0 as u32,
+                    z: 0,
+                },
+                aspect: TextureAspect::All,
+            },
+            Extent3d {
+                width: patch_params.width,
+                height: patch_params.height,
+                depth_or_array_layers: 1,
+            },
+        );
+        queue.submit([encoder.finish()]);
+        Some(damaged)
+    }
+
+    /// The texture holding the last frame, if any.
+    pub(crate) fn texture(&self) -> Option<&Texture> {
+        self.frame.as_ref().map(|frame| &frame.texture)
+    }
+}
+
+impl Frame {
+    fn new(device: &Device, width: u32, height: u32, transform: Affine) -> Self {
+        let create_texture = |label, usage| {
+            device.create_texture(&TextureDescriptor {
+                label: Some(label),
+                size: Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: TextureFormat::Rgba8Unorm,
+                usage: TextureUsages::STORAGE_BINDING | usage,
+                view_formats: &[],
+            })
+        };
+        let texture = create_texture(
+            "Frame texture",
+            TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST | TextureUsages::COPY_SRC,
+        );
+        let scratch = create_texture("Damaged area texture", TextureUsages::COPY_SRC);
+        Frame {
+            view: texture.create_view(&wgpu::TextureViewDescriptor::default()),
+            scratch_view: scratch.create_view(&wgpu::TextureViewDescriptor::default()),
+            texture,
+            scratch,
+            width,
+            height,
+            transform,
+        }
+    }
+}
+
+/// Copies the frame of a [`FrameCache`] to a surface.
+///
+/// This is the pipeline Vello uses in `Renderer::render_to_surface`, which it doesn't
+/// expose: surfaces can't be written to by compute shaders, and usually have another
+/// format than the textures Vello renders to.
+pub(crate) struct SurfaceBlit {
+    bind_layout: wgpu::BindGroupLayout,
+    pipeline: wgpu::RenderPipeline,
+}
+
+impl SurfaceBlit {
+    pub(crate) fn new(device: &Device, format: TextureFormat) -> Self {
+        const SHADERS: &str = r#"
+            @vertex
+            fn vs_main(@builtin(vertex_index) ix: u32) -> @builtin(position) vec4<f32> {
+                // Generate a full screen quad in normalized device coordinates
+                var vertex = vec2(-1.0, 1.0);
+                switch ix {
+                    case 1u: {
+                        vertex = vec2(-1.0, -1.0);
+                    }
+                    case 2u, 4u: {
+                        vertex = vec2(1.0, -1.0);
+                    }
+                    case 5u: {
+                        vertex = vec2(1.0, 1.0);
+                    }
+                    default: {}
+                }
+                return vec4(vertex, 0.0, 1.0);
+            }
+
+            @group(0) @binding(0)
+            var frame: texture_2d<f32>;
+
+            @fragment
+            fn fs_main(@builtin(position) pos: vec4<f32>) -> @location(0) vec4<f32> {
+                let rgba_sep = textureLoad(frame, vec2<i32>(pos.xy), 0);
+                return vec4(rgba_sep.rgb * rgba_sep.a, rgba_sep.a);
+            }
+        "#;
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("blit shaders"),
+            source: wgpu::ShaderSource::Wgsl(SHADERS.into()),
+        });
+        let bind_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: None,
+            entries: &[wgpu::BindGroupLayoutEntry {
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                binding: 0,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            }],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: None,
+            bind_group_layouts: &[&bind_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: None,
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: Some(wgpu::Face::Back),
+                polygon_mode: wgpu::PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+        });
+        SurfaceBlit {
+            bind_layout,
+            pipeline,
+        }
+    }
+
+    /// Copy the whole `frame` to `surface`, which must have the same size.
+    pub(crate) fn blit(
+        &self,
+        device: &Device,
+        queue: &Queue,
+        frame: &Texture,
+        surface: &SurfaceTexture,
+    ) {
+        let frame_view = frame.create_view(&wgpu::TextureViewDescriptor::default());
+        let surface_view = surface
+            .texture
+            .create_view(&wgpu::TextureViewDescriptor::default());
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: &self.bind_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&frame_view),
+            }],
+        });
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("Copy frame to surface"),
+        });
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: None,
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &surface_view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::default()),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                occlusion_query_set: None,
+                timestamp_writes: None,
+            });
+            render_pass.set_pipeline(&self.pipeline);
+            render_pass.set_bind_group(0, &bind_group, &[]);
+            render_pass.draw(0..6, 0..1);
+        }
+        queue.submit([encoder.finish()]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroUsize;
+
+    use vello::peniko::{Color, Fill};
+    use vello::util::RenderContext;
+    use vello::{AaConfig, AaSupport, RendererOptions};
+
+    use super::*;
+    use crate::testing::{read_texture, render_tests_skipped};
+
+    /// A scene with two squares of `color`, one on each half of a 100x50 frame.
+    fn squares(color: Color) -> Scene {
+        let mut scene = Scene::new();
+        for rect in [LEFT, RIGHT] {
+            scene.fill(Fill::NonZero, Affine::IDENTITY, color, None, &rect);
+        }
+        scene
+    }
+
+    const LEFT: Rect = Rect::new(10., 10., 40., 40.);
+    const RIGHT: Rect = Rect::new(60., 10., 90., 40.);
+
+    #[test]
+    fn only_damaged_area_is_rasterized() {
+        if render_tests_skipped() {
+            return;
+        }
+        let mut context = RenderContext::new().unwrap();
+        let device_id = pollster::block_on(context.device(None)).unwrap();
+        let device_handle = &context.devices[device_id];
+        let (device, queue) = (&device_handle.device, &device_handle.queue);
+        let options = RendererOptions {
+            surface_format: None,
+            use_cpu: true,
+            num_init_threads: NonZeroUsize::new(1),
+            antialiasing_support: AaSupport::area_only(),
+        };
+        let mut renderer = Renderer::new(device, options).unwrap();
+        let params = RenderParams {
+            base_color: Color::WHITE,
+            width: 100,
+            height: 50,
+            antialiasing_method: AaConfig::Area,
+        };
+        let mut cache = FrameCache::default();
+        let mut render = |scene: &Scene, damage: &[Rect]| {
+            cache.render(
+                device_handle,
+                &mut renderer,
+                scene,
+                Affine::IDENTITY,
+                damage,
+                &params,
+            )
+        };
+
+        let full = Rect::new(0., 0., 100., 50.);
+        assert_eq!(render(&squares(Color::BLUE), &[]), Some(full));
+        // Both squares turn red, but only the left one is damaged.
+        assert_eq!(render(&squares(Color::RED), &[]), None);
+        let damage = LEFT.inflate(0.5, 0.5);
+        assert_eq!(
+            render(&squares(Color::RED), &[damage]),
+            Some(damage.expand())
+        );
+
+        let frame = read_texture(device, queue, cache.texture().unwrap());
+        assert_eq!(frame.get_pixel(25, 25).0, [0xff, 0x00, 0x00, 0xff]);
+        assert_eq!(frame.get_pixel(75, 25).0, [0x00, 0x00, 0xff, 0xff]);
+        assert_eq!(frame.get_pixel(50, 45).0, [0xff, 0xff, 0xff, 0xff]);
+    }
+}
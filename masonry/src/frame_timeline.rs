@@ -0,0 +1,424 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Recording a timeline of the work done in each frame, for performance debugging.
+//!
+//! When a capture is running, the [`FrameTimelineLayer`] records the `INFO` spans
+//! of the passes (e.g. `pointer_event`, `layout`, `paint`), of the app (e.g. Xilem's
+//! `rebuild`) and of the GPU submission. Frames are delimited by the `frame` span
+//! entered by the event loop around each redraw; the work done between two redraws
+//! is part of the next frame. Only the last frames are kept, so captures can be left
+//! running.
+//!
+//! The capture is written as a [Chrome trace event] JSON file, which can be opened in
+//! [Perfetto](https://ui.perfetto.dev) or `chrome://tracing`.
+//!
+//! The layer is part of the `tracing` subscriber installed by Masonry's event loop.
+//! Apps installing their own subscriber can add it with [`layer`]. When no capture
+//! is running, it only checks an atomic flag for each span.
+//!
+//! A capture can be started in code, e.g. from a "Capture 120 frames" button:
+//!
+//! ```no_run
+//! use masonry::frame_timeline;
+//!
+//! frame_timeline::start_capture(120);
+//! // ... later, e.g. from another action handler:
+//! let path = frame_timeline::finish_capture().unwrap();
+//! println!("Timeline written to {}", path.display());
+//! ```
+//!
+//! It can also be started when the app launches, by setting the
+//! `MASONRY_FRAME_TIMELINE` environment variable to the number of frames to keep.
+//! The capture is then written when the event loop exits.
+//!
+//! [Chrome trace event]: https://docs.google.com/document/d/1CvAClvFfyA5R-PhYUmn5OOQtYMH4h6I0nSsKchNAySU
+
+use std::collections::{HashMap, VecDeque};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use instant::Instant;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Level, Subscriber};
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::Layer;
+
+/// The name of the span delimiting frames.
+const FRAME_SPAN: &str = "frame";
+
+/// The environment variable starting a capture when the event loop starts.
+pub const FRAME_TIMELINE_ENV_VAR: &str = "MASONRY_FRAME_TIMELINE";
+
+static CAPTURING: AtomicBool = AtomicBool::new(false);
+static CAPTURE: Mutex<Option<Capture>> = Mutex::new(None);
+
+/// An event of the Chrome trace event format.
+///
+/// Only "complete" events (i.e. with a duration) are written.
+#[derive(Serialize)]
+struct TraceEvent {
+    name: &'static str,
+    cat: &'static str,
+    ph: &'static str,
+    /// The start of the span, in microseconds since the start of the capture.
+    ts: f64,
+    /// The duration of the span, in microseconds.
+    dur: f64,
+    pid: u32,
+    tid: u64,
+    #[serde(skip_serializing_if = "Map::is_empty")]
+    args: Map<String, Value>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TraceFile<'a> {
+    trace_events: Vec<&'a TraceEvent>,
+    display_time_unit: &'static str,
+}
+
+/// A span which has been entered but hasn't exited yet.
+struct OpenSpan {
+    name: &'static str,
+    target: &'static str,
+    start: Option<Instant>,
+    args: Map<String, Value>,
+}
+
+struct Capture {
+    start: Instant,
+    max_frames: usize,
+    frame_count: u64,
+    /// The events of the last frames, oldest first.
+    frames: VecDeque<Vec<TraceEvent>>,
+    /// The events since the end of the last frame.
+    current_frame: Vec<TraceEvent>,
+    open_spans: HashMap<Id, OpenSpan>,
+}
+
+impl Capture {
+    fn micros_since_start(&self, instant: Instant) -> f64 {
+        instant.duration_since(self.start).as_secs_f64() * 1_000_000.
+    }
+
+    fn end_frame(&mut self) {
+        self.frame_count += 1;
+        self.frames
+            .push_back(std::mem::take(&mut self.current_frame));
+        while self.frames.len() > self.max_frames {
+            self.frames.pop_front();
+        }
+    }
+
+    fn write(&self, writer: impl Write) -> serde_json::Result<()> {
+        let mut events: Vec<&TraceEvent> = self.frames.iter().flatten().collect();
+        // Spans are recorded when they exit, so children come before their parents.
+        // Viewers expect events sorted by start time, parents first.
+        events.sort_by(|a, b| a.ts.total_cmp(&b.ts).then(b.dur.total_cmp(&a.dur)));
+        let file = TraceFile {
+            trace_events: events,
+            display_time_unit: "ms",
+        };
+        serde_json::to_writer(writer, &file)
+    }
+}
+
+/// Start recording the spans of the last `frames` frames.
+///
+/// This replaces the capture in progress, if any.
+pub fn start_capture(frames: usize) {
+    let capture = Capture {
+        start: Instant::now(),
+        max_frames: frames.max(1),
+        frame_count: 0,
+        frames: VecDeque::new(),
+        current_frame: Vec::new(),
+        open_spans: HashMap::new(),
+    };
+    *CAPTURE.lock().unwrap() = Some(capture);
+    CAPTURING.store(true, Ordering::Relaxed);
+}
+
+/// Whether a capture is running.
+pub fn is_capturing() -> bool {
+    CAPTURING.load(Ordering::Relaxed)
+}
+
+/// Stop the capture, and write it to a new file in the temporary directory.
+///
+/// Returns the path of the file. If no capture was running, the file has no events.
+pub fn finish_capture() -> io::Result<PathBuf> {
+    let millis = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    let path = std::env::temp_dir().join(format!("masonry-frame-timeline-{millis}.json"));
+    finish_capture_to(path)
+}
+
+/// Stop the capture, and write it to `path`.
+///
+/// Returns the path of the file. If no capture was running, the file has no events.
+pub fn finish_capture_to(path: impl Into<PathBuf>) -> io::Result<PathBuf> {
+    let path = path.into();
+    CAPTURING.store(false, Ordering::Relaxed);
+    let capture = CAPTURE.lock().unwrap().take();
+
+    let mut writer = BufWriter::new(File::create(&path)?);
+    match capture {
+        Some(capture) => capture.write(&mut writer)?,
+        None => serde_json::to_writer(
+            &mut writer,
+            &TraceFile {
+                trace_events: Vec::new(),
+                display_time_unit: "ms",
+            },
+        )?,
+    }
+    writer.flush()?;
+    Ok(path)
+}
+
+/// Start a capture if [`FRAME_TIMELINE_ENV_VAR`] is set to a number of frames.
+pub(crate) fn start_capture_from_env() {
+    let Ok(frames) = std::env::var(FRAME_TIMELINE_ENV_VAR) else {
+        return;
+    };
+    match frames.trim().parse() {
+        Ok(frames) => start_capture(frames),
+        Err(_) => {
+            tracing::warn!("{FRAME_TIMELINE_ENV_VAR} should be a number of frames, got {frames:?}");
+        }
+    }
+}
+
+/// A [`tracing`] layer recording spans while a capture is running.
+///
+/// See the [module docs](self).
+pub struct FrameTimelineLayer {
+    _private: (),
+}
+
+/// The layer recording frame timelines, to add to a custom `tracing` subscriber.
+///
+/// Only `INFO` spans and above are recorded, so it should be added with a filter
+/// letting them through.
+pub fn layer() -> FrameTimelineLayer {
+    FrameTimelineLayer { _private: () }
+}
+
+/// Collects the fields of a span as trace event arguments.
+struct ArgsVisitor<'a>(&'a mut Map<String, Value>);
+
+impl Visit for ArgsVisitor<'_> {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.0.insert(field.name().into(), value.into());
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.insert(field.name().into(), value.into());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.insert(field.name().into(), value.into());
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.insert(field.name().into(), value.into());
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().into(), value.into());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.0
+            .insert(field.name().into(), format!("{value:?}").into());
+    }
+}
+
+thread_local! {
+    static THREAD_ID: u64 = {
+        use std::sync::atomic::AtomicU64;
+        static NEXT_THREAD_ID: AtomicU64 = AtomicU64::new(1);
+        NEXT_THREAD_ID.fetch_add(1, Ordering::Relaxed)
+    };
+}
+
+impl<S: Subscriber> Layer<S> for FrameTimelineLayer {
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, _ctx: Context<'_, S>) {
+        let metadata = attrs.metadata();
+        if !CAPTURING.load(Ordering::Relaxed) || *metadata.level() > Level::INFO {
+            return;
+        }
+        let mut args = Map::new();
+        attrs.record(&mut ArgsVisitor(&mut args));
+        if let Some(capture) = CAPTURE.lock().unwrap().as_mut() {
+            let span = OpenSpan {
+                name: metadata.name(),
+                target: metadata.target(),
+                start: None,
+                args,
+            };
+            capture.open_spans.insert(id.clone(), span);
+        }
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, _ctx: Context<'_, S>) {
+        if !CAPTURING.load(Ordering::Relaxed) {
+            return;
+        }
+        if let Some(capture) = CAPTURE.lock().unwrap().as_mut() {
+            if let Some(span) = capture.open_spans.get_mut(id) {
+                values.record(&mut ArgsVisitor(&mut span.args));
+            }
+        }
+    }
+
+    fn on_enter(&self, id: &Id, _ctx: Context<'_, S>) {
+        if !CAPTURING.load(Ordering::Relaxed) {
+            return;
+        }
+        if let Some(capture) = CAPTURE.lock().unwrap().as_mut() {
+            if let Some(span) = capture.open_spans.get_mut(id) {
+                span.start.get_or_insert_with(Instant::now);
+            }
+        }
+    }
+
+    fn on_exit(&self, id: &Id, _ctx: Context<'_, S>) {
+        if !CAPTURING.load(Ordering::Relaxed) {
+            return;
+        }
+        let end = Instant::now();
+        let mut capture = CAPTURE.lock().unwrap();
+        let Some(capture) = capture.as_mut() else {
+            return;
+        };
+        let Some(span) = capture.open_spans.remove(id) else {
+            return;
+        };
+        let Some(start) = span.start else {
+            return;
+        };
+
+        let is_frame = span.name == FRAME_SPAN;
+        let mut args = span.args;
+        if is_frame {
+            args.insert("index".into(), capture.frame_count.into());
+        }
+        let ts = capture.micros_since_start(start);
+        let event = TraceEvent {
+            name: span.name,
+            cat: span.target,
+            ph: "X",
+            ts,
+            dur: capture.micros_since_start(end) - ts,
+            pid: std::process::id(),
+            tid: THREAD_ID.with(|id| *id),
+            args,
+        };
+        capture.current_frame.push(event);
+        if is_frame {
+            capture.end_frame();
+        }
+    }
+
+    fn on_close(&self, id: Id, _ctx: Context<'_, S>) {
+        // Spans created but never entered.
+        if !CAPTURING.load(Ordering::Relaxed) {
+            return;
+        }
+        if let Some(capture) = CAPTURE.lock().unwrap().as_mut() {
+            capture.open_spans.remove(&id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tracing::info_span;
+    use tracing_subscriber::prelude::*;
+
+    use winit::dpi::PhysicalSize;
+
+    use super::*;
+    use crate::event::WindowEvent;
+    use crate::testing::{widget_ids, TestHarness};
+    use crate::widget::{Button, Flex};
+
+    fn events(path: &std::path::Path) -> Vec<Value> {
+        let file: Value = serde_json::from_reader(File::open(path).unwrap()).unwrap();
+        assert_eq!(file["displayTimeUnit"], "ms");
+        file["traceEvents"].as_array().unwrap().clone()
+    }
+
+    fn span_of(event: &Value) -> (f64, f64) {
+        let ts = event["ts"].as_f64().unwrap();
+        (ts, ts + event["dur"].as_f64().unwrap())
+    }
+
+    #[test]
+    fn export_headless_frames() {
+        let dir = tempfile::tempdir().unwrap();
+        let subscriber = tracing_subscriber::registry().with(layer());
+        tracing::subscriber::with_default(subscriber, || {
+            let [button_id] = widget_ids();
+            let widget = Flex::column().with_child_id(Button::new("Capture"), button_id);
+            let mut harness = TestHarness::create(widget);
+
+            start_capture(3);
+            for _ in 0..5 {
+                let _frame = info_span!("frame").entered();
+                harness.mouse_click_on(button_id);
+                harness.process_window_event(WindowEvent::Resize(PhysicalSize::new(400, 400)));
+            }
+            // Spans after the last frame aren't part of any frame.
+            harness.mouse_move((0., 0.));
+            let path = finish_capture_to(dir.path().join("timeline.json")).unwrap();
+            assert!(!is_capturing());
+
+            let events = events(&path);
+            let frames: Vec<&Value> = events.iter().filter(|e| e["name"] == "frame").collect();
+            // Only the last 3 frames are kept.
+            let indices: Vec<u64> = frames
+                .iter()
+                .map(|frame| frame["args"]["index"].as_u64().unwrap())
+                .collect();
+            assert_eq!(indices, [2, 3, 4]);
+
+            let mut last_ts = f64::NEG_INFINITY;
+            for event in &events {
+                assert_eq!(event["ph"], "X");
+                let (start, end) = span_of(event);
+                assert!(start >= last_ts, "timestamps aren't monotonic");
+                assert!(end >= start);
+                last_ts = start;
+
+                // Every span is in a frame.
+                assert!(frames.iter().any(|frame| {
+                    let (frame_start, frame_end) = span_of(frame);
+                    frame_start <= start && end <= frame_end
+                }));
+            }
+            // Spans nest: they are either disjoint or one contains the other.
+            for a in &events {
+                for b in &events {
+                    let ((a0, a1), (b0, b1)) = (span_of(a), span_of(b));
+                    let disjoint = a1 <= b0 || b1 <= a0;
+                    let nested = (a0 <= b0 && b1 <= a1) || (b0 <= a0 && a1 <= b1);
+                    assert!(disjoint || nested, "{a} and {b} overlap");
+                }
+            }
+            assert!(events.iter().any(|e| e["name"] == "pointer_event"));
+            assert!(events.iter().any(|e| e["name"] == "layout"));
+        });
+    }
+}
@@ -0,0 +1,419 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Recognizing taps, long presses and two-finger transforms in pointer events.
+//!
+//! A [`GestureRecognizer`] is a state machine which depends neither on the widget tree
+//! nor on the clock: a widget feeds it the pointer events it receives and the intervals
+//! of its animation frames, and gets back the [`Gesture`]s they make.
+
+use std::f64::consts::{PI, TAU};
+
+use winit::event::MouseButton;
+
+use crate::kurbo::{Affine, Point, Vec2};
+use crate::PointerEvent;
+
+/// The thresholds telling gestures apart.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GestureSettings {
+    /// How far a pointer can move from where it was pressed, in logical pixels, before
+    /// the press stops counting as a tap or a long press.
+    pub tap_slop: f64,
+    /// How long a pointer must be held down to make a long press, in nanoseconds.
+    pub long_press_duration: u64,
+    /// How long after a tap the next press can start to count in the same multi-tap,
+    /// in nanoseconds.
+    ///
+    /// Taps are only reported once this delay has passed, when it's known whether
+    /// another tap follows.
+    pub multi_tap_delay: u64,
+    /// How far from the previous tap the next press can be to count in the same
+    /// multi-tap, in logical pixels.
+    pub multi_tap_slop: f64,
+}
+
+impl Default for GestureSettings {
+    fn default() -> Self {
+        GestureSettings {
+            tap_slop: 8.0,
+            long_press_duration: 500_000_000,
+            multi_tap_delay: 300_000_000,
+            multi_tap_slop: 24.0,
+        }
+    }
+}
+
+/// A gesture recognized by a [`GestureRecognizer`].
+///
+/// Positions are in the coordinates of the pointer events, i.e. in window coordinates.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Gesture {
+    /// Two pointers moved, e.g. in a pinch.
+    ///
+    /// This is the change since the previous update: the content under the pointers
+    /// follows them if it's scaled by `scale` and rotated by `rotation` radians around
+    /// `center`, then translated by `translation`. See [`Gesture::transform`].
+    TransformUpdate {
+        translation: Vec2,
+        scale: f64,
+        rotation: f64,
+        center: Point,
+    },
+    /// One or more quick presses of a single pointer, at the same place.
+    Tap { count: u32, position: Point },
+    /// A single pointer held down without moving.
+    ///
+    /// Releasing the pointer afterwards doesn't make a tap.
+    LongPress { position: Point },
+}
+
+impl Gesture {
+    /// The transform of a [`TransformUpdate`](Gesture::TransformUpdate), which should be
+    /// applied after the current transform of the content, or `None` for the other
+    /// gestures.
+    pub fn transform(&self) -> Option<Affine> {
+        match *self {
+            Gesture::TransformUpdate {
+                translation,
+                scale,
+                rotation,
+                center,
+            } => Some(
+                Affine::translate(center.to_vec2() + translation)
+                    * Affine::rotate(rotation)
+                    * Affine::scale(scale)
+                    * Affine::translate(-center.to_vec2()),
+            ),
+            _ => None,
+        }
+    }
+}
+
+/// The press of a single pointer, which may still make a tap or a long press.
+struct Press {
+    origin: Point,
+    /// The number of the tap that this press makes, if it's released quickly.
+    count: u32,
+    elapsed: u64,
+}
+
+/// A tap which is only reported if no other tap follows it.
+struct PendingTap {
+    count: u32,
+    position: Point,
+    elapsed: u64,
+}
+
+/// Turns [`PointerEvent`]s into [`Gesture`]s.
+///
+/// The events should be passed to [`handle`](Self::handle) as the widget receives them,
+/// and time is measured with [`advance`](Self::advance): while
+/// [`needs_anim_frame`](Self::needs_anim_frame) is `true`, the widget should request
+/// animation frames and pass their interval to it. Only the left mouse button and touches
+/// are tracked, and pointers are told apart by their
+/// [`pointer_id`](crate::PointerState::pointer_id).
+///
+/// When more than one pointer is down, the first two make [`Gesture::TransformUpdate`]s,
+/// and no taps or long presses are recognized until all the pointers are released.
+pub struct GestureRecognizer {
+    settings: GestureSettings,
+    /// The pointers which are down with their last position, in the order they were
+    /// pressed.
+    pointers: Vec<(u64, Point)>,
+    press: Option<Press>,
+    pending_tap: Option<PendingTap>,
+}
+
+impl GestureRecognizer {
+    pub fn new(settings: GestureSettings) -> Self {
+        GestureRecognizer {
+            settings,
+            pointers: Vec::new(),
+            press: None,
+            pending_tap: None,
+        }
+    }
+
+    pub fn settings(&self) -> &GestureSettings {
+        &self.settings
+    }
+
+    /// Whether the recognizer waits for time to pass, to report a long press or a tap.
+    pub fn needs_anim_frame(&self) -> bool {
+        self.press.is_some() || self.pending_tap.is_some()
+    }
+
+    /// Forget about the pointers which are down and the pending gestures.
+    pub fn reset(&mut self) {
+        self.pointers.clear();
+        self.press = None;
+        self.pending_tap = None;
+    }
+
+    /// Update the recognizer with a pointer event, and return the gestures it completes.
+    pub fn handle(&mut self, event: &PointerEvent) -> Vec<Gesture> {
+        let mut gestures = Vec::new();
+        let id = event.pointer_state().pointer_id;
+        let position = event.position();
+        let index = self.pointers.iter().position(|(pointer, _)| *pointer == id);
+        match (event, index) {
+            (PointerEvent::PointerDown(MouseButton::Left, _), None) => {
+                self.pointers.push((id, position));
+                if self.pointers.len() > 1 {
+                    // A press of several pointers is never a tap.
+                    self.press = None;
+                    return gestures;
+                }
+                let count = match self.pending_tap.take() {
+                    Some(tap)
+                        if (position - tap.position).hypot() <= self.settings.multi_tap_slop =>
+                    {
+                        tap.count + 1
+                    }
+                    Some(tap) => {
+                        gestures.push(Gesture::Tap {
+                            count: tap.count,
+                            position: tap.position,
+                        });
+                        1
+                    }
+                    None => 1,
+                };
+                self.press = Some(Press {
+                    origin: position,
+                    count,
+                    elapsed: 0,
+                });
+            }
+            (PointerEvent::PointerMove(_), Some(index)) => {
+                let previous = std::mem::replace(&mut self.pointers[index].1, position);
+                if let Some(press) = &self.press {
+                    if (position - press.origin).hypot() > self.settings.tap_slop {
+                        self.press = None;
+                    }
+                }
+                if index < 2 && self.pointers.len() >= 2 {
+                    let other = self.pointers[1 - index].1;
+                    gestures.extend(transform_update((previous, other), (position, other)));
+                }
+            }
+            (PointerEvent::PointerUp(MouseButton::Left, _), Some(index)) => {
+                self.pointers.remove(index);
+                if let Some(press) = self.press.take() {
+                    self.pending_tap = Some(PendingTap {
+                        count: press.count,
+                        position: press.origin,
+                        elapsed: 0,
+                    });
+                }
+            }
+            // E.g. a cancelled touch.
+            (PointerEvent::PointerLeave(_), Some(index)) => {
+                self.pointers.remove(index);
+                self.press = None;
+            }
+            _ => {}
+        }
+        gestures
+    }
+
+    /// Let `interval` nanoseconds pass, and return the gestures completed meanwhile.
+    pub fn advance(&mut self, interval: u64) -> Vec<Gesture> {
+        let mut gestures = Vec::new();
+        if let Some(press) = &mut self.press {
+            press.elapsed += interval;
+            if press.elapsed >= self.settings.long_press_duration {
+                gestures.push(Gesture::LongPress {
+                    position: press.origin,
+                });
+                self.press = None;
+            }
+        }
+        if let Some(tap) = &mut self.pending_tap {
+            tap.elapsed += interval;
+            if tap.elapsed >= self.settings.multi_tap_delay {
+                gestures.push(Gesture::Tap {
+                    count: tap.count,
+                    position: tap.position,
+                });
+                self.pending_tap = None;
+            }
+        }
+        gestures
+    }
+}
+
+impl Default for GestureRecognizer {
+    fn default() -> Self {
+        Self::new(GestureSettings::default())
+    }
+}
+
+/// The transform moving the pair of points `from` onto the pair `to`, if the points
+/// of `from` are apart.
+fn transform_update(from: (Point, Point), to: (Point, Point)) -> Option<Gesture> {
+    let from_span = from.1 - from.0;
+    let to_span = to.1 - to.0;
+    if from_span.hypot() < f64::EPSILON || from == to {
+        return None;
+    }
+    let center = from.0.midpoint(from.1);
+    // The shortest rotation, so that it doesn't jump when the angle wraps around.
+    let mut rotation = to_span.atan2() - from_span.atan2();
+    if rotation > PI {
+        rotation -= TAU;
+    } else if rotation <= -PI {
+        rotation += TAU;
+    }
+    Some(Gesture::TransformUpdate {
+        translation: to.0.midpoint(to.1) - center,
+        scale: to_span.hypot() / from_span.hypot(),
+        rotation,
+        center,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::FRAC_PI_4;
+
+    use winit::dpi::LogicalPosition;
+
+    use super::*;
+    use crate::PointerState;
+
+    const MS: u64 = 1_000_000;
+
+    fn state(id: u64, position: Point) -> PointerState {
+        let mut state = PointerState::empty();
+        state.pointer_id = id;
+        state.position = LogicalPosition::new(position.x, position.y);
+        state
+    }
+
+    fn down(id: u64, position: Point) -> PointerEvent {
+        PointerEvent::PointerDown(MouseButton::Left, state(id, position))
+    }
+
+    fn move_to(id: u64, position: Point) -> PointerEvent {
+        PointerEvent::PointerMove(state(id, position))
+    }
+
+    fn up(id: u64, position: Point) -> PointerEvent {
+        PointerEvent::PointerUp(MouseButton::Left, state(id, position))
+    }
+
+    #[test]
+    fn pinch_accumulates_scale_and_rotation() {
+        let mut recognizer = GestureRecognizer::default();
+        let center = Point::new(150.0, 100.0);
+        // The fingers are on either side of the center, and end up twice as far from it
+        // and rotated by 45 degrees.
+        let finger = |side: f64, step: f64| {
+            let radius = 50.0 * (1.0 + step);
+            let angle = FRAC_PI_4 * step;
+            center + side * radius * Vec2::new(angle.cos(), angle.sin())
+        };
+        assert!(recognizer.handle(&down(1, finger(-1.0, 0.0))).is_empty());
+        assert!(recognizer.handle(&down(2, finger(1.0, 0.0))).is_empty());
+
+        let mut scale = 1.0;
+        let mut rotation = 0.0;
+        let mut transform = Affine::IDENTITY;
+        for step in 1..=10 {
+            let step = f64::from(step) / 10.0;
+            let mut gestures = recognizer.handle(&move_to(1, finger(-1.0, step)));
+            gestures.extend(recognizer.handle(&move_to(2, finger(1.0, step))));
+            assert_eq!(gestures.len(), 2);
+            for gesture in gestures {
+                let Gesture::TransformUpdate {
+                    scale: update_scale,
+                    rotation: update_rotation,
+                    ..
+                } = gesture
+                else {
+                    panic!("unexpected gesture {gesture:?}");
+                };
+                scale *= update_scale;
+                rotation += update_rotation;
+                transform = gesture.transform().unwrap() * transform;
+            }
+        }
+
+        assert!((scale - 2.0).abs() < 1e-9, "{scale}");
+        assert!((rotation - FRAC_PI_4).abs() < 1e-9, "{rotation}");
+        // The content under the fingers follows them.
+        for side in [-1.0, 1.0] {
+            let moved = transform * finger(side, 0.0);
+            assert!((moved - finger(side, 1.0)).hypot() < 1e-9, "{moved:?}");
+        }
+    }
+
+    #[test]
+    fn quick_two_finger_touch_is_not_a_tap() {
+        let mut recognizer = GestureRecognizer::default();
+        let first = Point::new(100.0, 100.0);
+        let second = Point::new(140.0, 100.0);
+        let mut gestures = Vec::new();
+        gestures.extend(recognizer.handle(&down(1, first)));
+        gestures.extend(recognizer.handle(&down(2, second)));
+        gestures.extend(recognizer.advance(20 * MS));
+        gestures.extend(recognizer.handle(&up(1, first)));
+        gestures.extend(recognizer.handle(&up(2, second)));
+        gestures.extend(recognizer.advance(1000 * MS));
+        assert!(gestures.is_empty(), "{gestures:?}");
+        assert!(!recognizer.needs_anim_frame());
+    }
+
+    #[test]
+    fn tap_and_long_press_honor_delays() {
+        let settings = GestureSettings {
+            long_press_duration: 400 * MS,
+            multi_tap_delay: 200 * MS,
+            ..Default::default()
+        };
+        let mut recognizer = GestureRecognizer::new(settings);
+        let position = Point::new(10.0, 10.0);
+
+        // Held for less than the long press duration.
+        recognizer.handle(&down(0, position));
+        assert!(recognizer.advance(399 * MS).is_empty());
+        assert!(recognizer.handle(&up(0, position)).is_empty());
+        // The tap is only reported once no other tap can follow.
+        assert!(recognizer.advance(199 * MS).is_empty());
+        assert_eq!(
+            recognizer.advance(MS),
+            [Gesture::Tap { count: 1, position }]
+        );
+
+        recognizer.handle(&down(0, position));
+        assert_eq!(
+            recognizer.advance(400 * MS),
+            [Gesture::LongPress { position }]
+        );
+        assert!(recognizer.handle(&up(0, position)).is_empty());
+        assert!(recognizer.advance(1000 * MS).is_empty());
+
+        // A double tap, nearby.
+        recognizer.handle(&down(0, position));
+        recognizer.handle(&up(0, position));
+        recognizer.advance(100 * MS);
+        let nearby = position + Vec2::new(5.0, 0.0);
+        recognizer.handle(&down(0, nearby));
+        recognizer.handle(&up(0, nearby));
+        assert_eq!(
+            recognizer.advance(200 * MS),
+            [Gesture::Tap {
+                count: 2,
+                position: nearby
+            }]
+        );
+
+        // Moving the pointer makes a drag.
+        recognizer.handle(&down(0, position));
+        recognizer.handle(&move_to(0, position + Vec2::new(20.0, 0.0)));
+        recognizer.handle(&up(0, position));
+        assert!(recognizer.advance(1000 * MS).is_empty());
+    }
+}
@@ -0,0 +1,614 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Decoding images on a worker thread, and sharing them between widgets.
+//!
+//! Each [`RenderRoot`](crate::render_root::RenderRoot) owns an [`ImageCache`]. Widgets
+//! loading the same source share a single decoded copy, which stays in the cache while
+//! any [`ImageHandle`] to it is alive. Images nothing refers to anymore are kept until
+//! the cache exceeds its byte budget, so that a widget which is rebuilt doesn't decode
+//! its image again.
+
+use std::borrow::Cow;
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::JoinHandle;
+
+use tracing::{trace, warn};
+use vello::peniko::{Format, Image as ImageBuf};
+
+use crate::WidgetId;
+
+/// The default byte budget of an [`ImageCache`].
+pub const DEFAULT_BYTE_BUDGET: usize = 64 * 1024 * 1024;
+
+/// Where to load an image from.
+///
+/// Sources are compared by content: two `Bytes` sources with the same bytes are the
+/// same image.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub enum ImageSource {
+    /// The encoded bytes of the image, e.g. the content of a PNG file.
+    Bytes(Arc<[u8]>),
+    /// The path of an image file.
+    Path(PathBuf),
+}
+
+impl std::fmt::Debug for ImageSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ImageSource::Bytes(bytes) => write!(f, "Bytes({} bytes)", bytes.len()),
+            ImageSource::Path(path) => f.debug_tuple("Path").field(path).finish(),
+        }
+    }
+}
+
+impl From<Vec<u8>> for ImageSource {
+    fn from(bytes: Vec<u8>) -> Self {
+        ImageSource::Bytes(bytes.into())
+    }
+}
+
+impl From<&[u8]> for ImageSource {
+    fn from(bytes: &[u8]) -> Self {
+        ImageSource::Bytes(bytes.into())
+    }
+}
+
+impl From<Arc<[u8]>> for ImageSource {
+    fn from(bytes: Arc<[u8]>) -> Self {
+        ImageSource::Bytes(bytes)
+    }
+}
+
+impl From<PathBuf> for ImageSource {
+    fn from(path: PathBuf) -> Self {
+        ImageSource::Path(path)
+    }
+}
+
+impl From<&Path> for ImageSource {
+    fn from(path: &Path) -> Self {
+        ImageSource::Path(path.to_owned())
+    }
+}
+
+/// Why an image couldn't be loaded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImageError {
+    /// The file couldn't be read.
+    Read(String),
+    /// The bytes aren't an image in a supported format.
+    Decode(String),
+}
+
+impl std::fmt::Display for ImageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ImageError::Read(err) => write!(f, "could not read image: {err}"),
+            ImageError::Decode(err) => write!(f, "could not decode image: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for ImageError {}
+
+/// The state of an image loaded by an [`ImageCache`].
+#[derive(Debug, Clone)]
+pub enum ImageState {
+    /// The image is being decoded.
+    Loading,
+    /// The image was decoded.
+    Ready(ImageBuf),
+    /// The image couldn't be loaded.
+    Failed(ImageError),
+}
+
+struct EntryState {
+    state: ImageState,
+    /// The widgets to lay out again once the image is decoded.
+    waiting: Vec<WidgetId>,
+}
+
+struct Entry {
+    source: ImageSource,
+    state: Mutex<EntryState>,
+}
+
+/// A reference to an image loaded by an [`ImageCache`].
+///
+/// Handles to the same source share the decoded image. It stays in the cache at least
+/// as long as a handle to it is alive.
+#[derive(Clone)]
+pub struct ImageHandle {
+    entry: Arc<Entry>,
+}
+
+impl ImageHandle {
+    /// Where the image is loaded from.
+    pub fn source(&self) -> &ImageSource {
+        &self.entry.source
+    }
+
+    /// Whether the image is loading, ready or failed to load.
+    pub fn state(&self) -> ImageState {
+        self.entry.state.lock().unwrap().state.clone()
+    }
+
+    /// The decoded image, if it's ready.
+    pub fn image(&self) -> Option<ImageBuf> {
+        match self.state() {
+            ImageState::Ready(image) => Some(image),
+            ImageState::Loading | ImageState::Failed(_) => None,
+        }
+    }
+
+    /// Whether both handles refer to the same decoded image.
+    pub fn ptr_eq(&self, other: &ImageHandle) -> bool {
+        Arc::ptr_eq(&self.entry, &other.entry)
+    }
+
+    /// Lay out `widget_id` again once the image is decoded.
+    ///
+    /// Returns `false` if the image isn't loading anymore.
+    pub(crate) fn wait(&self, widget_id: WidgetId) -> bool {
+        let mut state = self.entry.state.lock().unwrap();
+        if !matches!(state.state, ImageState::Loading) {
+            return false;
+        }
+        if !state.waiting.contains(&widget_id) {
+            state.waiting.push(widget_id);
+        }
+        true
+    }
+}
+
+impl std::fmt::Debug for ImageHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("ImageHandle")
+            .field(&self.entry.source)
+            .finish()
+    }
+}
+
+struct DecodeJob {
+    entry: Arc<Entry>,
+}
+
+impl DecodeJob {
+    fn run(self, decode_count: &AtomicUsize) -> JobResult {
+        let _span = tracing::trace_span!("decode_image", source = ?self.entry.source).entered();
+        decode_count.fetch_add(1, Ordering::Relaxed);
+        let result = decode(&self.entry.source);
+        (self.entry, result)
+    }
+}
+
+fn decode(source: &ImageSource) -> Result<ImageBuf, ImageError> {
+    let bytes = match source {
+        ImageSource::Bytes(bytes) => Cow::Borrowed(&bytes[..]),
+        ImageSource::Path(path) => {
+            Cow::Owned(std::fs::read(path).map_err(|err| ImageError::Read(err.to_string()))?)
+        }
+    };
+    let image = image::load_from_memory(&bytes)
+        .map_err(|err| ImageError::Decode(err.to_string()))?
+        .into_rgba8();
+    let (width, height) = image.dimensions();
+    Ok(ImageBuf::new(
+        image.into_raw().into(),
+        Format::Rgba8,
+        width,
+        height,
+    ))
+}
+
+#[derive(Default)]
+struct JobQueue {
+    jobs: VecDeque<DecodeJob>,
+    shutdown: bool,
+}
+
+#[derive(Default)]
+struct Shared {
+    queue: Mutex<JobQueue>,
+    job_added: Condvar,
+    decode_count: AtomicUsize,
+}
+
+type JobResult = (Arc<Entry>, Result<ImageBuf, ImageError>);
+
+struct CachedImage {
+    entry: Arc<Entry>,
+    /// The size of the decoded image, or 0 if it isn't decoded.
+    bytes: usize,
+    last_used: u64,
+}
+
+struct CacheInner {
+    background: bool,
+    shared: Arc<Shared>,
+    result_sender: Sender<JobResult>,
+    result_receiver: Receiver<JobResult>,
+    worker: Option<JoinHandle<()>>,
+    images: HashMap<ImageSource, CachedImage>,
+    byte_budget: usize,
+    /// Incremented each time an image is used, to find the least recently used ones.
+    clock: u64,
+    pending: usize,
+}
+
+/// Decodes images on a worker thread, and shares the decoded images between widgets.
+///
+/// The cache is cheap to clone; clones refer to the same cache.
+#[derive(Clone)]
+pub struct ImageCache {
+    inner: Arc<Mutex<CacheInner>>,
+}
+
+impl ImageCache {
+    /// Create an empty cache, with the [default byte budget](DEFAULT_BYTE_BUDGET).
+    pub fn new() -> Self {
+        let (result_sender, result_receiver) = channel();
+        let inner = CacheInner {
+            background: true,
+            shared: Arc::default(),
+            result_sender,
+            result_receiver,
+            worker: None,
+            images: HashMap::new(),
+            byte_budget: DEFAULT_BYTE_BUDGET,
+            clock: 0,
+            pending: 0,
+        };
+        ImageCache {
+            inner: Arc::new(Mutex::new(inner)),
+        }
+    }
+
+    /// Load the image of `source`.
+    ///
+    /// If the cache already has it, the returned handle shares the decoded image.
+    /// Otherwise, the image is decoded in the background, and the widgets waiting for
+    /// it are laid out again once it's ready.
+    pub fn load(&self, source: impl Into<ImageSource>) -> ImageHandle {
+        let source = source.into();
+        let mut inner = self.inner.lock().unwrap();
+        inner.clock += 1;
+        let clock = inner.clock;
+        if let Some(cached) = inner.images.get_mut(&source) {
+            cached.last_used = clock;
+            return ImageHandle {
+                entry: cached.entry.clone(),
+            };
+        }
+
+        trace!("Loading image {source:?}");
+        let entry = Arc::new(Entry {
+            source: source.clone(),
+            state: Mutex::new(EntryState {
+                state: ImageState::Loading,
+                waiting: Vec::new(),
+            }),
+        });
+        inner.images.insert(
+            source,
+            CachedImage {
+                entry: entry.clone(),
+                bytes: 0,
+                last_used: clock,
+            },
+        );
+        inner.pending += 1;
+        inner
+            .shared
+            .queue
+            .lock()
+            .unwrap()
+            .jobs
+            .push_back(DecodeJob {
+                entry: entry.clone(),
+            });
+        inner.shared.job_added.notify_one();
+        ImageHandle { entry }
+    }
+
+    /// Set how many bytes of decoded images the cache keeps.
+    ///
+    /// When it holds more, the least recently used images which no [`ImageHandle`]
+    /// refers to are evicted. Images still in use are never evicted, so the cache
+    /// can exceed its budget.
+    pub fn set_byte_budget(&self, bytes: usize) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.byte_budget = bytes;
+        inner.evict();
+    }
+
+    /// How many bytes of decoded images the cache keeps.
+    pub fn byte_budget(&self) -> usize {
+        self.inner.lock().unwrap().byte_budget
+    }
+
+    /// How many bytes the decoded images in the cache take.
+    pub fn used_bytes(&self) -> usize {
+        self.inner.lock().unwrap().used_bytes()
+    }
+
+    /// Whether the image of `source` is in the cache.
+    pub fn contains(&self, source: &ImageSource) -> bool {
+        self.inner.lock().unwrap().images.contains_key(source)
+    }
+
+    /// How many images were decoded so far, to check that images are shared.
+    pub fn decode_count(&self) -> usize {
+        self.inner
+            .lock()
+            .unwrap()
+            .shared
+            .decode_count
+            .load(Ordering::Relaxed)
+    }
+
+    /// Set whether images are decoded on a worker thread.
+    ///
+    /// When disabled, images are only decoded when [`run_pending`](Self::run_pending)
+    /// is called, so that tests are deterministic.
+    pub fn set_background_decoding(&self, enabled: bool) {
+        self.inner.lock().unwrap().background = enabled;
+    }
+
+    /// Decode the queued images on the current thread.
+    ///
+    /// The results are handed to the widgets on the next redraw.
+    pub fn run_pending(&self) {
+        let inner = self.inner.lock().unwrap();
+        let jobs = std::mem::take(&mut inner.shared.queue.lock().unwrap().jobs);
+        for job in jobs {
+            // The receiver is owned by `inner`, so this can't fail.
+            let _ = inner
+                .result_sender
+                .send(job.run(&inner.shared.decode_count));
+        }
+    }
+
+    /// Whether some images haven't been decoded yet.
+    pub(crate) fn has_pending(&self) -> bool {
+        self.inner.lock().unwrap().pending > 0
+    }
+
+    /// Whether images are decoded on a worker thread.
+    pub(crate) fn is_background(&self) -> bool {
+        self.inner.lock().unwrap().background
+    }
+
+    /// Store the decoded images in their handles, and return the widgets waiting for them.
+    ///
+    /// The worker thread is started by the first call with images to decode, rather than
+    /// when they are loaded, so that background decoding can still be disabled once the
+    /// widgets of a new [`RenderRoot`](crate::render_root::RenderRoot) were added.
+    pub(crate) fn collect_results(&self) -> Vec<WidgetId> {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.background && inner.pending > 0 && inner.worker.is_none() {
+            inner.spawn_worker();
+        }
+        let mut widgets = Vec::new();
+        let mut decoded = false;
+        while let Ok((entry, result)) = inner.result_receiver.try_recv() {
+            inner.pending -= 1;
+            decoded = true;
+            let (state, bytes) = match result {
+                Ok(image) => {
+                    let bytes = image.width as usize * image.height as usize * 4;
+                    (ImageState::Ready(image), bytes)
+                }
+                Err(err) => {
+                    warn!("Could not load image {:?}: {err}", entry.source);
+                    (ImageState::Failed(err), 0)
+                }
+            };
+            let mut entry_state = entry.state.lock().unwrap();
+            entry_state.state = state;
+            widgets.append(&mut entry_state.waiting);
+            if let Some(cached) = inner.images.get_mut(&entry.source) {
+                if Arc::ptr_eq(&cached.entry, &entry) {
+                    cached.bytes = bytes;
+                }
+            }
+        }
+        if decoded {
+            inner.evict();
+        }
+        widgets
+    }
+}
+
+impl Default for ImageCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Debug for ImageCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let inner = self.inner.lock().unwrap();
+        f.debug_struct("ImageCache")
+            .field("images", &inner.images.len())
+            .field("used_bytes", &inner.used_bytes())
+            .field("byte_budget", &inner.byte_budget)
+            .finish()
+    }
+}
+
+impl CacheInner {
+    fn used_bytes(&self) -> usize {
+        self.images.values().map(|cached| cached.bytes).sum()
+    }
+
+    /// Evict the least recently used images which aren't referenced outside the cache,
+    /// until the cache fits in its budget.
+    fn evict(&mut self) {
+        let mut used_bytes = self.used_bytes();
+        while used_bytes > self.byte_budget {
+            let unreferenced = self
+                .images
+                .iter()
+                .filter(|(_, cached)| cached.bytes > 0 && Arc::strong_count(&cached.entry) == 1)
+                .min_by_key(|(_, cached)| cached.last_used)
+                .map(|(source, _)| source.clone());
+            let Some(source) = unreferenced else {
+                break;
+            };
+            trace!("Evicting image {source:?}");
+            if let Some(cached) = self.images.remove(&source) {
+                used_bytes -= cached.bytes;
+            }
+        }
+    }
+
+    fn spawn_worker(&mut self) {
+        let shared = self.shared.clone();
+        let result_sender = self.result_sender.clone();
+        let worker = std::thread::Builder::new()
+            .name("masonry-image-decoder".into())
+            .spawn(move || worker_loop(&shared, &result_sender));
+        match worker {
+            Ok(worker) => self.worker = Some(worker),
+            Err(err) => {
+                warn!("Could not spawn image decoding thread, decoding synchronously: {err}");
+                self.background = false;
+                let jobs = std::mem::take(&mut self.shared.queue.lock().unwrap().jobs);
+                for job in jobs {
+                    let _ = self.result_sender.send(job.run(&self.shared.decode_count));
+                }
+            }
+        }
+    }
+}
+
+fn worker_loop(shared: &Shared, result_sender: &Sender<JobResult>) {
+    loop {
+        let job = {
+            let mut queue = shared.queue.lock().unwrap();
+            loop {
+                if queue.shutdown {
+                    return;
+                }
+                if let Some(job) = queue.jobs.pop_front() {
+                    break job;
+                }
+                queue = shared.job_added.wait(queue).unwrap();
+            }
+        };
+        if result_sender.send(job.run(&shared.decode_count)).is_err() {
+            return;
+        }
+    }
+}
+
+impl Drop for CacheInner {
+    fn drop(&mut self) {
+        self.shared.queue.lock().unwrap().shutdown = true;
+        self.shared.job_added.notify_all();
+        // The worker only finishes its current image, so this doesn't block for long.
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{ImageFormat, Rgba, RgbaImage};
+
+    use super::*;
+
+    /// Encode a `size` by `size` PNG image filled with `color`.
+    fn png_bytes(size: u32, color: [u8; 4]) -> Vec<u8> {
+        let image = RgbaImage::from_pixel(size, size, Rgba(color));
+        let mut bytes = std::io::Cursor::new(Vec::new());
+        image.write_to(&mut bytes, ImageFormat::Png).unwrap();
+        bytes.into_inner()
+    }
+
+    fn manual_cache() -> ImageCache {
+        let cache = ImageCache::new();
+        cache.set_background_decoding(false);
+        cache
+    }
+
+    #[test]
+    fn same_source_is_decoded_once() {
+        let cache = manual_cache();
+        let bytes = png_bytes(4, [255, 0, 0, 255]);
+        let first = cache.load(bytes.clone());
+        let second = cache.load(bytes);
+        assert!(first.ptr_eq(&second));
+
+        cache.run_pending();
+        cache.collect_results();
+        assert_eq!(cache.decode_count(), 1);
+        assert_eq!(first.image().unwrap().width, 4);
+        assert_eq!(cache.used_bytes(), 4 * 4 * 4);
+    }
+
+    #[test]
+    fn eviction_respects_budget() {
+        let cache = manual_cache();
+        // Room for two 4x4 images.
+        cache.set_byte_budget(2 * 4 * 4 * 4);
+        let sources: Vec<ImageSource> = [[255, 0, 0, 255], [0, 255, 0, 255], [0, 0, 255, 255]]
+            .into_iter()
+            .map(|color| png_bytes(4, color).into())
+            .collect();
+
+        let handles: Vec<ImageHandle> = sources.iter().map(|s| cache.load(s.clone())).collect();
+        cache.run_pending();
+        cache.collect_results();
+        // Images in use are kept, even over budget.
+        assert_eq!(cache.used_bytes(), 3 * 4 * 4 * 4);
+
+        // Use the first image again, so that the second one is the least recently used.
+        let _first = cache.load(sources[0].clone());
+        drop(handles);
+        cache.set_byte_budget(2 * 4 * 4 * 4);
+        assert!(cache.used_bytes() <= cache.byte_budget());
+        assert!(cache.contains(&sources[0]));
+        assert!(!cache.contains(&sources[1]));
+        assert!(cache.contains(&sources[2]));
+
+        cache.set_byte_budget(0);
+        assert!(cache.contains(&sources[0]));
+        assert_eq!(cache.used_bytes(), 4 * 4 * 4);
+    }
+
+    #[test]
+    fn corrupt_source_fails() {
+        let cache = manual_cache();
+        let corrupt = cache.load(vec![1, 2, 3]);
+        let missing = cache.load(PathBuf::from("/this/image/does/not/exist.png"));
+        cache.run_pending();
+        cache.collect_results();
+        assert!(matches!(
+            corrupt.state(),
+            ImageState::Failed(ImageError::Decode(_))
+        ));
+        assert!(matches!(
+            missing.state(),
+            ImageState::Failed(ImageError::Read(_))
+        ));
+    }
+
+    #[test]
+    fn background_decoding() {
+        let cache = ImageCache::new();
+        let handle = cache.load(png_bytes(2, [0, 0, 0, 255]));
+        while cache.has_pending() {
+            std::thread::yield_now();
+            cache.collect_results();
+        }
+        assert!(handle.image().is_some());
+    }
+}
@@ -0,0 +1,29 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+/// The direction in which content flows horizontally.
+///
+/// Right-to-left scripts like Arabic and Hebrew are laid out mirrored: rows start on
+/// the right edge, and text is aligned to the right by default. Vertical layout is
+/// the same in both directions.
+///
+/// The direction is set for the whole window with
+/// [`RenderRoot::set_layout_direction`](crate::render_root::RenderRoot::set_layout_direction),
+/// and can be overridden for a subtree with
+/// [`WidgetMut::set_layout_direction`](crate::widget::WidgetMut::set_layout_direction).
+/// Widgets read it during layout with [`LayoutCtx::layout_direction`](crate::LayoutCtx::layout_direction).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum LayoutDirection {
+    /// Left-to-right.
+    #[default]
+    Ltr,
+    /// Right-to-left.
+    Rtl,
+}
+
+impl LayoutDirection {
+    /// Whether this is [`LayoutDirection::Rtl`].
+    pub fn is_rtl(self) -> bool {
+        self == LayoutDirection::Rtl
+    }
+}
@@ -94,18 +94,32 @@ pub use vello;
 mod util;
 
 mod action;
+mod badge;
 mod bloom;
 mod box_constraints;
 mod contexts;
+mod easing;
 mod event;
+pub mod frame_budget;
+mod frame_cache;
+pub mod frame_timeline;
+pub mod gesture;
+pub mod image_cache;
+mod layout_direction;
 pub mod paint_scene_helpers;
 pub mod promise;
+mod reduced_motion;
 pub mod render_root;
 pub mod testing;
 // mod text;
+mod shadow;
+mod style;
 pub mod text_helpers;
+mod text_style;
 pub mod theme;
+mod validation;
 pub mod widget;
+mod widget_index;
 
 // TODO
 pub mod app_driver;
@@ -115,15 +129,26 @@ pub mod event_loop_runner;
 pub mod text2;
 
 pub use action::Action;
+pub use badge::{Badge, BadgeContent};
 pub use box_constraints::BoxConstraints;
 pub use contexts::{AccessCtx, EventCtx, LayoutCtx, LifeCycleCtx, PaintCtx, WidgetCtx};
+pub use easing::Easing;
 pub use event::{
-    AccessEvent, InternalLifeCycle, LifeCycle, PointerEvent, StatusChange, TextEvent, WindowTheme,
+    AccessEvent, InternalLifeCycle, LifeCycle, PointerEvent, PointerState, StatusChange, TextEvent,
+    WindowTheme,
 };
 pub use kurbo::{Affine, Insets, Point, Rect, Size, Vec2};
+pub use layout_direction::LayoutDirection;
 pub use parley::layout::Alignment as TextAlignment;
 pub use util::{AsAny, Handled};
 pub use vello::peniko::{Color, Gradient};
 pub use widget::{BackgroundBrush, Widget, WidgetId, WidgetPod, WidgetState};
 
+pub use shadow::CachedShadow;
+pub use style::{
+    ButtonStyle, ButtonVariant, Elevation, ShadowStyle, StateColors, TextboxStyle, TextboxVariant,
+    Theme,
+};
 pub use text_helpers::ArcStr;
+pub use text_style::{ResolvedTextStyle, TextStyle};
+pub use validation::{ValidationAdornment, ValidationState};
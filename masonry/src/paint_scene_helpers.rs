@@ -9,12 +9,17 @@ use vello::{
     Scene,
 };
 
+use crate::LayoutDirection;
+
 // TODO - Remove this file
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct UnitPoint {
     u: f64,
     v: f64,
+    /// Whether `u` is measured from the leading edge, which is the right one in
+    /// right-to-left layout.
+    relative: bool,
 }
 
 pub fn stroke<'b>(
@@ -52,19 +57,60 @@ impl UnitPoint {
     pub const BOTTOM: UnitPoint = UnitPoint::new(0.5, 1.0);
     /// `(1.0, 1.0)`
     pub const BOTTOM_RIGHT: UnitPoint = UnitPoint::new(1.0, 1.0);
+    /// `(0.0, 0.0)` in left-to-right layout, `(1.0, 0.0)` in right-to-left layout.
+    pub const TOP_START: UnitPoint = UnitPoint::new_relative(0.0, 0.0);
+    /// `(1.0, 0.0)` in left-to-right layout, `(0.0, 0.0)` in right-to-left layout.
+    pub const TOP_END: UnitPoint = UnitPoint::new_relative(1.0, 0.0);
+    /// `(0.0, 0.5)` in left-to-right layout, `(1.0, 0.5)` in right-to-left layout.
+    pub const START: UnitPoint = UnitPoint::new_relative(0.0, 0.5);
+    /// `(1.0, 0.5)` in left-to-right layout, `(0.0, 0.5)` in right-to-left layout.
+    pub const END: UnitPoint = UnitPoint::new_relative(1.0, 0.5);
+    /// `(0.0, 1.0)` in left-to-right layout, `(1.0, 1.0)` in right-to-left layout.
+    pub const BOTTOM_START: UnitPoint = UnitPoint::new_relative(0.0, 1.0);
+    /// `(1.0, 1.0)` in left-to-right layout, `(0.0, 1.0)` in right-to-left layout.
+    pub const BOTTOM_END: UnitPoint = UnitPoint::new_relative(1.0, 1.0);
 
     /// Create a new `UnitPoint`.
     ///
     /// The `u` and `v` coordinates describe the point, with (0.0, 0.0) being
     /// the top-left, and (1.0, 1.0) being the bottom-right.
     pub const fn new(u: f64, v: f64) -> UnitPoint {
-        UnitPoint { u, v }
+        UnitPoint {
+            u,
+            v,
+            relative: false,
+        }
+    }
+
+    /// Create a new `UnitPoint` relative to the layout direction.
+    ///
+    /// This is like [`new`](Self::new), except that `u` is measured from the right
+    /// edge in right-to-left layout.
+    pub const fn new_relative(u: f64, v: f64) -> UnitPoint {
+        UnitPoint {
+            u,
+            v,
+            relative: true,
+        }
     }
 
     /// Given a rectangle, resolve the point within the rectangle.
+    ///
+    /// Points relative to the layout direction are resolved left-to-right.
     pub fn resolve(self, rect: Rect) -> kurbo::Point {
+        self.resolve_in(rect, LayoutDirection::Ltr)
+    }
+
+    /// Given a rectangle, resolve the point within the rectangle in the given
+    /// layout direction.
+    pub fn resolve_in(self, rect: Rect, direction: LayoutDirection) -> kurbo::Point {
+        let u = if self.relative && direction.is_rtl() {
+            1.0 - self.u
+        } else {
+            self.u
+        };
         kurbo::Point::new(
-            rect.x0 + self.u * (rect.x1 - rect.x0),
+            rect.x0 + u * (rect.x1 - rect.x0),
             rect.y0 + self.v * (rect.y1 - rect.y0),
         )
     }
@@ -0,0 +1,115 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Reading the platform's "reduce motion" accessibility setting.
+//!
+//! winit doesn't expose this setting, so we ask the tool each platform provides
+//! to read it. Any failure (missing tool, unexpected output) means animations
+//! aren't reduced.
+
+use std::process::Command;
+
+/// A program to run, its arguments, and the parser of its output.
+type Query = (&'static str, &'static [&'static str], fn(&str) -> bool);
+
+/// Whether the user asked the platform to reduce animations.
+///
+/// This runs a short-lived process, and is meant to be called once at startup.
+pub(crate) fn platform_reduced_motion() -> bool {
+    let Some((program, args, parse)) = query() else {
+        return false;
+    };
+    match Command::new(program).args(args).output() {
+        Ok(output) if output.status.success() => parse(&String::from_utf8_lossy(&output.stdout)),
+        Ok(_) => false,
+        Err(err) => {
+            tracing::debug!("couldn't read the reduced motion setting: {err}");
+            false
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn query() -> Option<Query> {
+    Some((
+        "defaults",
+        &["read", "com.apple.universalaccess", "reduceMotion"],
+        parse_macos,
+    ))
+}
+
+#[cfg(target_os = "windows")]
+fn query() -> Option<Query> {
+    Some((
+        "reg",
+        &[
+            "query",
+            r"HKCU\Control Panel\Desktop\WindowMetrics",
+            "/v",
+            "MinAnimate",
+        ],
+        parse_windows,
+    ))
+}
+
+#[cfg(all(unix, not(any(target_os = "macos", target_os = "android"))))]
+fn query() -> Option<Query> {
+    Some((
+        "gsettings",
+        &["get", "org.gnome.desktop.interface", "enable-animations"],
+        parse_gnome,
+    ))
+}
+
+#[cfg(not(any(unix, target_os = "windows")))]
+fn query() -> Option<Query> {
+    None
+}
+
+#[cfg(target_os = "android")]
+fn query() -> Option<Query> {
+    None
+}
+
+/// Parse `defaults read com.apple.universalaccess reduceMotion`, which prints `1` when enabled.
+#[cfg(any(target_os = "macos", test))]
+fn parse_macos(output: &str) -> bool {
+    output.trim() == "1"
+}
+
+/// Parse `reg query` of the `MinAnimate` value, which is `0` when animations are turned off.
+#[cfg(any(target_os = "windows", test))]
+fn parse_windows(output: &str) -> bool {
+    output
+        .lines()
+        .find(|line| line.trim_start().starts_with("MinAnimate"))
+        .and_then(|line| line.split_whitespace().last())
+        == Some("0")
+}
+
+/// Parse `gsettings get org.gnome.desktop.interface enable-animations`.
+#[cfg(any(all(unix, not(any(target_os = "macos", target_os = "android"))), test))]
+fn parse_gnome(output: &str) -> bool {
+    output.trim() == "false"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_platform_outputs() {
+        assert!(parse_macos("1\n"));
+        assert!(!parse_macos("0\n"));
+
+        let windows = "\r\nHKEY_CURRENT_USER\\Control Panel\\Desktop\\WindowMetrics\r\n    MinAnimate    REG_SZ    0\r\n\r\n";
+        assert!(parse_windows(windows));
+        assert!(!parse_windows(
+            &windows.replace("REG_SZ    0", "REG_SZ    1")
+        ));
+        assert!(!parse_windows(""));
+
+        assert!(parse_gnome("false\n"));
+        assert!(!parse_gnome("true\n"));
+    }
+}
@@ -1,29 +1,49 @@
 // Copyright 2019 the Xilem Authors and the Druid Authors
 // SPDX-License-Identifier: Apache-2.0
 
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
 
 use accesskit::{ActionRequest, NodeBuilder, Tree, TreeUpdate};
 // Automatically defaults to std::time::Instant on non Wasm platforms
 use instant::Instant;
 use kurbo::Affine;
 use parley::FontContext;
+use smallvec::SmallVec;
 use tracing::{debug, info_span, warn};
 use vello::peniko::{Color, Fill};
 use vello::Scene;
 use winit::dpi::{LogicalPosition, LogicalSize, PhysicalSize};
 use winit::keyboard::{KeyCode, PhysicalKey};
 
-use crate::contexts::{EventCtx, LayoutCtx, LifeCycleCtx, PaintCtx, WidgetCtx, WorkerFn};
+use crate::badge::BadgeTextCache;
+use crate::contexts::{
+    DeferredMutation, DescendantEdit, EventCtx, LayoutCtx, LifeCycleCtx, PaintCtx, WidgetCtx,
+    WorkerFn,
+};
 use crate::debug_logger::DebugLogger;
 use crate::event::{PointerEvent, TextEvent, WindowEvent};
-use crate::kurbo::Point;
-use crate::widget::{WidgetMut, WidgetState};
+use crate::frame_budget::{BudgetPolicy, FrameBudget, FrameBudgetStats, VirtualClock};
+use crate::image_cache::ImageCache;
+use crate::kurbo::{Point, Rect, Size};
+use crate::text2::{LineBreakPool, TextLayoutMode};
+use crate::widget::{focus_scopes_around, WidgetMut, WidgetRef, WidgetState};
+use crate::widget_index::WidgetIndex;
 use crate::{
     AccessCtx, AccessEvent, Action, BoxConstraints, CursorIcon, Handled, InternalLifeCycle,
-    LifeCycle, Widget, WidgetId, WidgetPod,
+    LayoutDirection, LifeCycle, Theme, Widget, WidgetId, WidgetPod,
 };
 
+/// The smallest zoom factor accepted by [`RenderRoot::set_zoom`].
+pub const MIN_ZOOM: f64 = 0.25;
+/// The largest zoom factor accepted by [`RenderRoot::set_zoom`].
+pub const MAX_ZOOM: f64 = 5.0;
+
+/// The zoom factors [`RenderRoot::zoom_in`] and [`RenderRoot::zoom_out`] step through.
+const ZOOM_LEVELS: &[f64] = &[
+    0.25, 0.33, 0.5, 0.67, 0.75, 0.8, 0.9, 1.0, 1.1, 1.25, 1.5, 1.75, 2.0, 2.5, 3.0, 4.0, 5.0,
+];
+
 // TODO - Remove pub(crate)
 pub struct RenderRoot {
     pub(crate) root: WidgetPod<Box<dyn Widget>>,
@@ -32,22 +52,117 @@ pub struct RenderRoot {
     // TODO - Currently this is always 1.0
     // kurbo coordinates are assumed to be in logical pixels
     pub(crate) scale_factor: f64,
+    /// The zoom factor of the whole window, applied on top of the scale factor.
+    pub(crate) zoom: f64,
     /// Is `Some` if the most recently displayed frame was an animation frame.
     pub(crate) last_anim: Option<Instant>,
     pub(crate) last_mouse_pos: Option<LogicalPosition<f64>>,
     pub(crate) cursor_icon: CursorIcon,
+    /// The IME area last reported with [`RenderRootSignal::ImeMoved`].
+    pub(crate) ime_area: Option<Rect>,
     pub(crate) state: RenderRootState,
     // TODO - Add "access_tree_active" to detect when you don't need to update the
     // access tree
     pub(crate) rebuild_access_tree: bool,
+    pub(crate) access_stats: AccessStats,
+    /// The focus reported by the most recent accessibility tree update.
+    pub(crate) last_access_focus: Option<WidgetId>,
 }
 
 pub(crate) struct RenderRootState {
     pub(crate) debug_logger: DebugLogger,
     pub(crate) signal_queue: VecDeque<RenderRootSignal>,
+    pub(crate) deferred_mutations: VecDeque<DeferredMutation>,
+    /// The pending [`WidgetMut::edit_descendant`] callback.
+    pub(crate) descendant_edit: Option<DescendantEdit>,
     pub(crate) focused_widget: Option<WidgetId>,
     pub(crate) next_focused_widget: Option<WidgetId>,
+    /// The widget focused before the focus entered each [`FocusScope`] restoring it,
+    /// by scope, while the scope holds the focus.
+    ///
+    /// [`FocusScope`]: crate::widget::FocusScope
+    pub(crate) focus_restores: HashMap<WidgetId, WidgetId>,
     pub(crate) font_context: FontContext,
+    pub(crate) reduced_motion: bool,
+    pub(crate) layout_direction: LayoutDirection,
+    pub(crate) theme: Theme,
+    /// Whether the theme was changed through a [`WidgetCtx`], and
+    /// [`LifeCycle::ThemeChanged`] must be sent.
+    pub(crate) theme_changed: bool,
+    pub(crate) prune_hidden_access_nodes: bool,
+    /// The areas repainted by the current paint pass, in window coordinates.
+    pub(crate) paint_damage: Vec<Rect>,
+    pub(crate) line_breaks: LineBreakPool,
+    pub(crate) images: ImageCache,
+    pub(crate) badge_text: BadgeTextCache,
+    /// The logical size of the window, as of the current layout pass.
+    pub(crate) window_size: Size,
+    /// The area popup surfaces can cover, if the platform supports them.
+    ///
+    /// See [`RenderRoot::set_popup_bounds`].
+    pub(crate) popup_bounds: Option<Rect>,
+    /// The popup surfaces currently shown, by the widget hosting them.
+    pub(crate) popups: HashMap<WidgetId, PopupSurface>,
+    pub(crate) layout_stats: LayoutStats,
+    pub(crate) frame_budget: FrameBudget,
+    pub(crate) widget_index: WidgetIndex,
+}
+
+impl RenderRootState {
+    /// Mark `rect`, in window coordinates, as needing to be redrawn.
+    pub(crate) fn add_paint_damage(&mut self, rect: Rect) {
+        if rect.area() == 0.0 {
+            return;
+        }
+        // Merge overlapping rects, so that the list stays short.
+        let mut rect = rect;
+        while let Some(index) = self
+            .paint_damage
+            .iter()
+            .position(|damaged| damaged.intersect(rect).area() > 0.0)
+        {
+            rect = rect.union(self.paint_damage.swap_remove(index));
+        }
+        self.paint_damage.push(rect);
+    }
+}
+
+/// Content shown in a separate borderless window, e.g. a menu extending past the edges of
+/// the main window.
+///
+/// See [`LayoutCtx::show_popup`].
+pub(crate) struct PopupSurface {
+    /// The area of the popup window, in the coordinates of the main window.
+    pub(crate) rect: Rect,
+    /// The content of the popup window, in its own coordinates.
+    pub(crate) scene: Scene,
+}
+
+/// Counters describing the work done by the accessibility pass.
+///
+/// See [`RenderRoot::access_stats`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct AccessStats {
+    /// The number of tree updates produced so far.
+    pub updates: u64,
+    /// The number of nodes in the most recent tree update.
+    pub last_update_nodes: usize,
+    /// The total number of nodes emitted across all tree updates.
+    pub total_nodes: u64,
+}
+
+/// Counters describing the work done by the layout pass.
+///
+/// See [`RenderRoot::layout_stats`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct LayoutStats {
+    /// The number of layout passes run so far.
+    pub passes: u64,
+    /// The number of widgets laid out in the most recent pass because they, or one of
+    /// their descendants, requested it.
+    ///
+    /// The other widgets were only laid out again because their parent was.
+    pub last_requested_widgets: usize,
 }
 
 /// Defines how a windows size should be determined
@@ -71,8 +186,13 @@ pub enum RenderRootSignal {
     TextFieldAdded,
     TextFieldRemoved,
     TextFieldFocused,
+    /// A widget editing text was focused: the platform's input method editor should be enabled.
     ImeStarted,
-    ImeMoved,
+    /// The area of the text being edited by the focused widget, in logical window coordinates.
+    ///
+    /// The platform's input method editor should show its candidate window next to it.
+    ImeMoved(Rect),
+    /// No widget editing text is focused anymore: the input method editor should be disabled.
     ImeInvalidated,
     RequestRedraw,
     RequestAnimFrame,
@@ -81,6 +201,17 @@ pub enum RenderRootSignal {
     SetCursor(CursorIcon),
     SetSize(PhysicalSize<u32>),
     SetTitle(String),
+    DragWindow,
+    ToggleMaximized,
+    /// Show the popup surface of the given widget, or move it to the given rect.
+    ///
+    /// The rect is in the coordinates of the widget tree, like the
+    /// [popup bounds](RenderRoot::set_popup_bounds), and may extend past the edges of the
+    /// main window. The content of the popup is returned by [`RenderRoot::popup_scene`], and
+    /// the pointer events over it must be sent to the render root in main window coordinates.
+    ShowPopup(WidgetId, Rect),
+    /// Close the popup surface of the given widget.
+    HidePopup(WidgetId),
 }
 
 impl RenderRoot {
@@ -90,17 +221,39 @@ impl RenderRoot {
             size_policy,
             size: PhysicalSize::new(0, 0),
             scale_factor,
+            zoom: 1.0,
             last_anim: None,
             last_mouse_pos: None,
             cursor_icon: CursorIcon::Default,
+            ime_area: None,
             state: RenderRootState {
                 debug_logger: DebugLogger::new(false),
                 signal_queue: VecDeque::new(),
+                deferred_mutations: VecDeque::new(),
+                descendant_edit: None,
                 focused_widget: None,
                 next_focused_widget: None,
+                focus_restores: HashMap::new(),
                 font_context: FontContext::default(),
+                reduced_motion: false,
+                layout_direction: LayoutDirection::Ltr,
+                theme: Theme::default(),
+                theme_changed: false,
+                prune_hidden_access_nodes: false,
+                paint_damage: Vec::new(),
+                line_breaks: LineBreakPool::new(TextLayoutMode::Background),
+                images: ImageCache::new(),
+                badge_text: BadgeTextCache::default(),
+                window_size: Size::ZERO,
+                popup_bounds: None,
+                popups: HashMap::new(),
+                layout_stats: LayoutStats::default(),
+                frame_budget: FrameBudget::new(),
+                widget_index: WidgetIndex::default(),
             },
             rebuild_access_tree: true,
+            access_stats: AccessStats::default(),
+            last_access_focus: None,
         };
 
         // We send WidgetAdded to all widgets right away
@@ -160,6 +313,9 @@ impl RenderRoot {
     }
 
     pub fn handle_pointer_event(&mut self, event: PointerEvent) -> Handled {
+        if self.zoom != 1.0 {
+            return self.root_on_pointer_event(event.with_zoom(Point::ORIGIN, self.zoom));
+        }
         self.root_on_pointer_event(event)
     }
 
@@ -173,6 +329,8 @@ impl RenderRoot {
 
         // TODO - if root widget's request_anim is still set by the
         // time this is called, emit a warning
+        self.poll_line_breaks();
+        self.poll_images();
         if self.root.state().needs_layout {
             self.root_layout();
         }
@@ -184,7 +342,48 @@ impl RenderRoot {
         }
 
         // TODO - Improve caching of scenes.
-        (self.root_paint(), self.root_accessibility())
+        let scene = self.root_paint();
+
+        // We don't get woken up when a worker thread finishes, so we keep polling.
+        if self.state.line_breaks.has_pending()
+            && self.state.line_breaks.mode() != TextLayoutMode::Manual
+        {
+            self.state
+                .signal_queue
+                .push_back(RenderRootSignal::RequestAnimFrame);
+        }
+        if self.state.images.has_pending() && self.state.images.is_background() {
+            self.state
+                .signal_queue
+                .push_back(RenderRootSignal::RequestAnimFrame);
+        }
+
+        let over_budget = self.state.frame_budget.end_frame();
+        let tree_update = if over_budget && self.can_defer_accessibility() {
+            // The pending accessibility updates stay flagged on the widgets, and are
+            // emitted by the next frame.
+            self.state.frame_budget.stats.deferred_access_updates += 1;
+            self.state
+                .signal_queue
+                .push_back(RenderRootSignal::RequestRedraw);
+            TreeUpdate {
+                nodes: vec![],
+                tree: None,
+                focus: self.state.focused_widget.unwrap_or(self.root.id()).into(),
+            }
+        } else {
+            self.root_accessibility()
+        };
+        (scene, tree_update)
+    }
+
+    /// Whether the accessibility update of an over-budget frame can be postponed.
+    ///
+    /// The tree must have been built already, and the focused node must be in it.
+    fn can_defer_accessibility(&self) -> bool {
+        self.state.frame_budget.policy == BudgetPolicy::DeferNonCritical
+            && !self.rebuild_access_tree
+            && self.last_access_focus == Some(self.state.focused_widget.unwrap_or(self.root.id()))
     }
 
     pub fn pop_signal(&mut self) -> Option<RenderRootSignal> {
@@ -203,6 +402,346 @@ impl RenderRoot {
         self.cursor_icon
     }
 
+    /// Whether the user asked for animations to be reduced.
+    ///
+    /// See [`set_reduced_motion`](Self::set_reduced_motion).
+    pub fn reduced_motion(&self) -> bool {
+        self.state.reduced_motion
+    }
+
+    /// Set whether animations should be reduced, e.g. because of the platform's
+    /// "reduce motion" accessibility setting.
+    ///
+    /// The event loop runner initializes this from the platform setting at startup; render
+    /// roots created otherwise start with animations enabled.
+    /// Widgets can read it with the `reduced_motion` context method; animations that
+    /// move or resize content should then jump to their end state. When this changes,
+    /// widgets get [`LifeCycle::ReducedMotionChanged`], and are laid out again, so that
+    /// animations in progress can do so.
+    pub fn set_reduced_motion(&mut self, reduced_motion: bool) {
+        if self.state.reduced_motion == reduced_motion {
+            return;
+        }
+        self.state.reduced_motion = reduced_motion;
+        self.root_lifecycle(LifeCycle::ReducedMotionChanged);
+        self.root.state.needs_layout = true;
+        self.state
+            .signal_queue
+            .push_back(RenderRootSignal::RequestRedraw);
+    }
+
+    /// The layout direction of the window.
+    ///
+    /// See [`set_layout_direction`](Self::set_layout_direction).
+    pub fn layout_direction(&self) -> LayoutDirection {
+        self.state.layout_direction
+    }
+
+    /// Set the layout direction of the window, e.g. to lay out an Arabic or Hebrew
+    /// translation right-to-left.
+    ///
+    /// Subtrees can override it with [`WidgetMut::set_layout_direction`].
+    pub fn set_layout_direction(&mut self, direction: LayoutDirection) {
+        if self.state.layout_direction == direction {
+            return;
+        }
+        self.state.layout_direction = direction;
+        self.root.state.needs_layout = true;
+        self.state
+            .signal_queue
+            .push_back(RenderRootSignal::RequestRedraw);
+    }
+
+    /// The theme of the window.
+    ///
+    /// See [`set_theme`](Self::set_theme).
+    pub fn theme(&self) -> &Theme {
+        &self.state.theme
+    }
+
+    /// Set the theme of the window, e.g. when the user picks a dark or a high-contrast theme.
+    ///
+    /// Every widget gets [`LifeCycle::ThemeChanged`], so that the existing widgets are
+    /// restyled in place.
+    pub fn set_theme(&mut self, theme: Theme) {
+        if self.state.theme == theme {
+            return;
+        }
+        self.state.theme = theme;
+        self.root_lifecycle(LifeCycle::ThemeChanged);
+    }
+
+    /// The area popup surfaces can cover.
+    ///
+    /// See [`set_popup_bounds`](Self::set_popup_bounds).
+    pub fn popup_bounds(&self) -> Option<Rect> {
+        self.state.popup_bounds
+    }
+
+    /// Set the area popup surfaces can cover, e.g. the screen the window is on, or `None`
+    /// if the platform can't show popup windows.
+    ///
+    /// The bounds are in the coordinates of the widget tree, i.e. logical window coordinates
+    /// divided by the [zoom](Self::zoom). Widgets which support popup surfaces, like
+    /// [`Popover`](crate::widget::Popover), show their content inside the window when this
+    /// is `None`, which is the default.
+    pub fn set_popup_bounds(&mut self, bounds: Option<Rect>) {
+        if self.state.popup_bounds == bounds {
+            return;
+        }
+        self.state.popup_bounds = bounds;
+        self.root.state.needs_layout = true;
+        self.state
+            .signal_queue
+            .push_back(RenderRootSignal::RequestRedraw);
+    }
+
+    /// The content of the popup surface of the given widget, as of the last
+    /// [`redraw`](Self::redraw), in the coordinates of the popup.
+    ///
+    /// See [`RenderRootSignal::ShowPopup`].
+    pub fn popup_scene(&self, id: WidgetId) -> Option<&Scene> {
+        self.state.popups.get(&id).map(|popup| &popup.scene)
+    }
+
+    /// The area of the popup surface of the given widget, in the coordinates of the
+    /// widget tree.
+    pub fn popup_rect(&self, id: WidgetId) -> Option<Rect> {
+        self.state.popups.get(&id).map(|popup| popup.rect)
+    }
+
+    /// The popup surfaces currently shown, by the widget hosting them, with their area in
+    /// the coordinates of the widget tree.
+    ///
+    /// The event loop uses this to show them again, e.g. after the app was suspended.
+    pub fn popups(&self) -> impl Iterator<Item = (WidgetId, Rect)> + '_ {
+        self.state
+            .popups
+            .iter()
+            .map(|(id, popup)| (*id, popup.rect))
+    }
+
+    /// Close the popup surfaces of the widgets which were removed or stashed, or all of
+    /// them if popup surfaces aren't supported anymore.
+    ///
+    /// Widgets are expected to hide their popup themselves, this only catches the ones
+    /// which can't, because they aren't laid out anymore.
+    fn prune_popups(&mut self) {
+        let supported = self.state.popup_bounds.is_some();
+        let removed: Vec<_> = self
+            .state
+            .popups
+            .keys()
+            .copied()
+            .filter(|id| !supported || !self.has_unstashed_widget(*id))
+            .collect();
+        for id in removed {
+            self.state.popups.remove(&id);
+            self.state
+                .signal_queue
+                .push_back(RenderRootSignal::HidePopup(id));
+        }
+    }
+
+    /// The zoom factor of the window.
+    ///
+    /// See [`set_zoom`](Self::set_zoom).
+    pub fn zoom(&self) -> f64 {
+        self.zoom
+    }
+
+    /// Set the zoom factor of the whole window, e.g. from the app's "Zoom" menu.
+    ///
+    /// The zoom applies on top of the scale factor of the display: widgets are laid
+    /// out in a window `zoom` times smaller, and the scene returned by
+    /// [`redraw`](Self::redraw) must be scaled by `zoom` (on top of the scale factor)
+    /// when rendering it. Pointer positions are mapped accordingly, and the bounds
+    /// of accessibility nodes are scaled.
+    ///
+    /// The zoom is clamped between [`MIN_ZOOM`] and [`MAX_ZOOM`]. To zoom part of the
+    /// UI only, use the [`Zoom`](crate::widget::Zoom) widget.
+    pub fn set_zoom(&mut self, zoom: f64) {
+        let zoom = zoom.clamp(MIN_ZOOM, MAX_ZOOM);
+        if self.zoom == zoom {
+            return;
+        }
+        self.zoom = zoom;
+        self.root.state.needs_layout = true;
+        self.rebuild_access_tree = true;
+        self.state
+            .signal_queue
+            .push_back(RenderRootSignal::RequestRedraw);
+    }
+
+    /// Set the zoom factor to the next larger usual zoom level (e.g. 110%, 125%, 150%).
+    pub fn zoom_in(&mut self) {
+        let zoom = ZOOM_LEVELS
+            .iter()
+            .copied()
+            .find(|level| *level > self.zoom + 0.001)
+            .unwrap_or(MAX_ZOOM);
+        self.set_zoom(zoom);
+    }
+
+    /// Set the zoom factor to the next smaller usual zoom level (e.g. 90%, 80%, 75%).
+    pub fn zoom_out(&mut self) {
+        let zoom = ZOOM_LEVELS
+            .iter()
+            .rev()
+            .copied()
+            .find(|level| *level < self.zoom - 0.001)
+            .unwrap_or(MIN_ZOOM);
+        self.set_zoom(zoom);
+    }
+
+    /// Set where the lines of large texts are broken when their wrap width changes.
+    ///
+    /// By default, this is [`TextLayoutMode::Background`]: while the window is being
+    /// resized, large texts keep displaying their previous lines until a worker thread
+    /// has broken them at the new width, instead of making each frame wait for it.
+    /// Small texts are always laid out synchronously.
+    pub fn set_text_layout_mode(&mut self, mode: TextLayoutMode) {
+        if self.state.line_breaks.mode() == mode {
+            return;
+        }
+        self.state.line_breaks.set_mode(mode);
+        if mode == TextLayoutMode::Sync {
+            // Don't leave widgets waiting for results which may never come.
+            self.run_pending_line_breaks();
+        }
+    }
+
+    /// Break the lines of all queued texts on the current thread, and lay out
+    /// the widgets they're for.
+    ///
+    /// This is mostly useful with [`TextLayoutMode::Manual`].
+    pub fn run_pending_line_breaks(&mut self) {
+        self.state.line_breaks.run_pending();
+        self.poll_line_breaks();
+    }
+
+    /// The cache through which widgets load their images.
+    ///
+    /// The returned value refers to the same cache, e.g. to change its byte budget,
+    /// or to load images before the widgets showing them are built.
+    pub fn image_cache(&self) -> ImageCache {
+        self.state.images.clone()
+    }
+
+    /// Decode all queued images on the current thread, and lay out the widgets
+    /// waiting for them.
+    ///
+    /// This is mostly useful after disabling
+    /// [background decoding](ImageCache::set_background_decoding).
+    pub fn run_pending_image_decodes(&mut self) {
+        self.state.images.run_pending();
+        self.poll_images();
+    }
+
+    /// Counters about the accessibility tree updates produced so far.
+    pub fn access_stats(&self) -> AccessStats {
+        self.access_stats
+    }
+
+    /// Counters about the layout passes run so far.
+    pub fn layout_stats(&self) -> LayoutStats {
+        self.state.layout_stats
+    }
+
+    /// Set the time the layout and paint passes of a frame should fit in.
+    ///
+    /// Frames going over it are reported, see [`frame_budget_stats`](Self::frame_budget_stats).
+    /// With `None`, the default, passes aren't measured.
+    pub fn set_frame_budget(&mut self, budget: Option<Duration>) {
+        self.state.frame_budget.set_budget(budget);
+    }
+
+    /// The time the layout and paint passes of a frame should fit in, if any.
+    pub fn frame_budget(&self) -> Option<Duration> {
+        self.state.frame_budget.budget()
+    }
+
+    /// Set what is done with the frames going over the
+    /// [frame budget](Self::set_frame_budget).
+    pub fn set_budget_policy(&mut self, policy: BudgetPolicy) {
+        self.state.frame_budget.policy = policy;
+    }
+
+    /// Measure passes against the [frame budget](Self::set_frame_budget) with `clock`
+    /// instead of the system clock.
+    pub fn use_virtual_clock(&mut self, clock: VirtualClock) {
+        self.state.frame_budget.set_clock(clock);
+    }
+
+    /// Counters about the frames measured against the
+    /// [frame budget](Self::set_frame_budget) so far.
+    pub fn frame_budget_stats(&self) -> &FrameBudgetStats {
+        &self.state.frame_budget.stats
+    }
+
+    /// The areas of the window, in logical coordinates, which changed in the last
+    /// [`redraw`](Self::redraw).
+    ///
+    /// This is the union of the paint rects of the widgets which requested a paint (or
+    /// were laid out) since the previous redraw, including the areas they covered before
+    /// moving. Overlapping rects are merged. Anything drawn outside of these rects is
+    /// identical to the previous frame.
+    ///
+    /// The event loop only rasterizes the bounding box of these rects in the main window,
+    /// on top of its last frame, and only redraws the popup surfaces overlapping them.
+    pub fn paint_damage(&self) -> &[Rect] {
+        &self.state.paint_damage
+    }
+
+    /// Set whether the widgets which can't be seen are reduced to a single node in the
+    /// accessibility tree, instead of exposing their whole subtree.
+    ///
+    /// Stashed widgets are reduced to a hidden node, and widgets scrolled far out of
+    /// view, by more than the size of the viewport they're in, to a node which only
+    /// has their bounds, which tells that they're off-screen. Their subtree is emitted
+    /// again once they're unstashed or scrolled back into view.
+    ///
+    /// This makes tree updates smaller when large parts of the UI are stashed or
+    /// scrolled away, but some assistive technologies expect the full tree, so this is
+    /// `false` by default.
+    pub fn set_prune_hidden_access_nodes(&mut self, prune: bool) {
+        if self.state.prune_hidden_access_nodes == prune {
+            return;
+        }
+        self.state.prune_hidden_access_nodes = prune;
+        self.rebuild_access_tree = true;
+        self.state
+            .signal_queue
+            .push_back(RenderRootSignal::RequestRedraw);
+    }
+
+    /// Return the widget with the given id, if it's in the tree.
+    ///
+    /// Widgets added since the last pass aren't found until they receive `WidgetAdded`.
+    pub fn get_widget(&self, id: WidgetId) -> Option<WidgetRef<'_, dyn Widget>> {
+        self.widget_path(id)?.pop()
+    }
+
+    /// The widgets from the root down to the widget with the given id, following the
+    /// widget index instead of searching the tree.
+    fn widget_path(&self, id: WidgetId) -> Option<SmallVec<[WidgetRef<'_, dyn Widget>; 16]>> {
+        let ids = self.state.widget_index.path(id)?;
+        let mut path: SmallVec<[WidgetRef<'_, dyn Widget>; 16]> = SmallVec::new();
+        let mut widget = self.root.as_dyn();
+        if widget.id() != ids[0] {
+            return None;
+        }
+        for id in &ids[1..] {
+            path.push(widget);
+            widget = widget
+                .children()
+                .into_iter()
+                .find(|child| child.id() == *id)?;
+        }
+        path.push(widget);
+        Some(path)
+    }
+
     pub fn edit_root_widget<R>(
         &mut self,
         f: impl FnOnce(WidgetMut<'_, Box<dyn Widget>>) -> R,
@@ -228,6 +767,19 @@ impl RenderRoot {
         res
     }
 
+    /// Move the focus to the next widget of the focus chain, or to the previous one if
+    /// `forward` is `false`, like an unhandled Tab (or Shift+Tab) key press.
+    ///
+    /// While a trapping [`FocusScope`](crate::widget::FocusScope) holds the focus, the
+    /// focus stays in the innermost one. When no widget is focused, the first (or last)
+    /// widget of the window is focused.
+    pub fn move_focus(&mut self, forward: bool) {
+        let mut widget_state =
+            WidgetState::new(self.root.id(), Some(self.get_kurbo_size()), "<root>");
+        self.state.next_focused_widget = self.widget_from_focus_chain(forward);
+        self.post_event_processing(&mut widget_state);
+    }
+
     fn root_on_pointer_event(&mut self, event: PointerEvent) -> Handled {
         let mut widget_state =
             WidgetState::new(self.root.id(), Some(self.get_kurbo_size()), "<root>");
@@ -376,15 +928,31 @@ impl RenderRoot {
         self.post_event_processing(&mut widget_state);
     }
 
+    /// Run an animation frame, as if `elapsed` had passed since the previous one.
+    ///
+    /// Unlike [`WindowEvent::AnimFrame`], this doesn't measure the time between frames,
+    /// so that tests can move animations forward deterministically.
+    pub(crate) fn animate(&mut self, elapsed: Duration) {
+        if self.wants_animation_frame() {
+            self.root_lifecycle(LifeCycle::AnimFrame(elapsed.as_nanos() as u64));
+        }
+    }
+
     pub(crate) fn root_layout(&mut self) {
         let mut widget_state =
             WidgetState::new(self.root.id(), Some(self.get_kurbo_size()), "<root>");
         let size = self.get_kurbo_size();
+        self.state.window_size = size;
+        self.state.layout_stats.passes += 1;
+        self.state.layout_stats.last_requested_widgets = 0;
+        let budget_start = self.state.frame_budget.start_pass();
         let mouse_pos = self.last_mouse_pos.map(|pos| (pos.x, pos.y).into());
+        let layout_direction = self.state.layout_direction;
         let mut layout_ctx = LayoutCtx {
             global_state: &mut self.state,
             widget_state: &mut widget_state,
             mouse_pos,
+            layout_direction,
         };
 
         let bc = match self.size_policy {
@@ -403,7 +971,8 @@ impl RenderRoot {
         layout_ctx.global_state.debug_logger.pop_span();
 
         if let WindowSizePolicy::Content = self.size_policy {
-            let new_size = LogicalSize::new(size.width, size.height).to_physical(self.scale_factor);
+            let new_size = LogicalSize::new(size.width, size.height)
+                .to_physical(self.scale_factor * self.zoom);
             if self.size != new_size {
                 self.size = new_size;
                 layout_ctx
@@ -414,12 +983,19 @@ impl RenderRoot {
         }
 
         layout_ctx.place_child(&mut self.root, Point::ORIGIN);
+        self.state.frame_budget.end_layout(budget_start);
         self.post_event_processing(&mut widget_state);
+        if !self.state.popups.is_empty() {
+            self.prune_popups();
+        }
     }
 
     fn root_paint(&mut self) -> Scene {
         // TODO - Handle Xilem's VIEW_CONTEXT_CHANGED
 
+        self.state.paint_damage.clear();
+        let budget_start = self.state.frame_budget.start_pass();
+
         let widget_state = WidgetState::new(self.root.id(), Some(self.get_kurbo_size()), "<root>");
         let mut ctx = PaintCtx {
             global_state: &mut self.state,
@@ -434,6 +1010,13 @@ impl RenderRoot {
             let _span = info_span!("paint").entered();
             self.root.paint(&mut ctx, &mut scene);
         }
+        self.state.frame_budget.end_paint(budget_start);
+        if self.zoom != 1.0 {
+            // Damage is reported in logical coordinates, like the rendered scene.
+            for rect in &mut self.state.paint_damage {
+                *rect = rect.scale_from_origin(self.zoom);
+            }
+        }
 
         // FIXME - This is a workaround to Vello panicking when given an
         // empty scene
@@ -452,20 +1035,24 @@ impl RenderRoot {
 
     // TODO - Integrate in unit tests?
     fn root_accessibility(&mut self) -> TreeUpdate {
+        let focus = self.state.focused_widget.unwrap_or(self.root.id());
+        self.last_access_focus = Some(focus);
         let mut tree_update = TreeUpdate {
             nodes: vec![],
             tree: None,
-            focus: self.state.focused_widget.unwrap_or(self.root.id()).into(),
+            focus: focus.into(),
         };
         let mut widget_state =
             WidgetState::new(self.root.id(), Some(self.get_kurbo_size()), "<root>");
+        let viewport = self.get_kurbo_size().to_rect();
         let mut ctx = AccessCtx {
             global_state: &mut self.state,
             widget_state: &mut widget_state,
             tree_update: &mut tree_update,
             current_node: NodeBuilder::default(),
             rebuild_all: self.rebuild_access_tree,
-            scale_factor: self.scale_factor,
+            scale_factor: self.scale_factor * self.zoom,
+            viewport,
         };
 
         {
@@ -477,6 +1064,12 @@ impl RenderRoot {
             self.rebuild_access_tree = false;
         }
 
+        let nodes = tree_update.nodes.len();
+        debug!("Accessibility pass emitted {} nodes", nodes);
+        self.access_stats.updates += 1;
+        self.access_stats.last_update_nodes = nodes;
+        self.access_stats.total_nodes += nodes as u64;
+
         if true {
             tree_update.tree = Some(Tree {
                 root: self.root.id().into(),
@@ -489,8 +1082,9 @@ impl RenderRoot {
         tree_update
     }
 
+    /// The size of the window in layout coordinates, i.e. in logical pixels divided by the zoom.
     fn get_kurbo_size(&self) -> kurbo::Size {
-        let size = self.size.to_logical(self.scale_factor);
+        let size = self.size.to_logical(self.scale_factor * self.zoom);
         kurbo::Size::new(size.width, size.height)
     }
 
@@ -513,6 +1107,12 @@ impl RenderRoot {
                 mouse_pos: self.last_mouse_pos,
             });
             self.root_lifecycle(event);
+
+            let root_state = self.root.state();
+            if root_state.is_viewport_observer || root_state.has_viewport_observers {
+                let window_rect = self.get_kurbo_size().to_rect();
+                self.root_lifecycle(LifeCycle::ViewportChanged(window_rect));
+            }
         }
 
         // Update the disabled state if necessary
@@ -522,14 +1122,30 @@ impl RenderRoot {
             self.root_lifecycle(event);
         }
 
+        // Restyle the widgets if the theme was changed through a `WidgetMut`.
+        // Widgets may change their text style, so do this before routing text styles.
+        if std::mem::take(&mut self.state.theme_changed) {
+            self.root_lifecycle(LifeCycle::ThemeChanged);
+        }
+
+        // Update the text styles if necessary, before the next layout
+        if self.root.state().tree_text_style_changed() {
+            let event = LifeCycle::Internal(InternalLifeCycle::RouteTextStyleChanged);
+            self.root_lifecycle(event);
+        }
+
         // Update the focus-chain if necessary
         // Always do this before sending focus change, since this event updates the focus chain.
         if self.root.state().update_focus_chain {
             let event = LifeCycle::BuildFocusChain;
             self.root_lifecycle(event);
+            // The window is the outermost focus scope.
+            self.root.state.sort_focus_chain();
         }
 
+        self.restore_focus_of_closed_scopes();
         self.update_focus();
+        self.update_ime_area();
 
         // If we need a new paint pass, make sure winit knows it.
         if self.wants_animation_frame() {
@@ -538,7 +1154,7 @@ impl RenderRoot {
                 .push_back(RenderRootSignal::RequestAnimFrame);
         }
 
-        if self.root.state().needs_paint {
+        if self.root.state().wants_paint() {
             self.state
                 .signal_queue
                 .push_back(RenderRootSignal::RequestRedraw);
@@ -550,6 +1166,33 @@ impl RenderRoot {
             tracing::debug!("{:?} added", token);
             self.ime_handlers.push((token, ime_field));
         }
+
+        // Mutations queued with `defer` run once the pass that queued them is fully
+        // processed. Each one goes through `edit_root_widget`, and thus gets its own
+        // post-processing (which drains anything it queues in turn).
+        while let Some(mutation) = self.state.deferred_mutations.pop_front() {
+            self.edit_root_widget(|mut root| mutation(&mut root));
+        }
+    }
+
+    /// Hand the finished line breaking jobs to their widgets.
+    fn poll_line_breaks(&mut self) {
+        for widget_id in self.state.line_breaks.collect_results() {
+            let event = LifeCycle::Internal(InternalLifeCycle::RouteLineBreaksReady(widget_id));
+            self.root_lifecycle(event);
+        }
+        let index = &self.state.widget_index;
+        self.state
+            .line_breaks
+            .retain_widgets(|widget_id| index.contains(widget_id));
+    }
+
+    /// Lay out the widgets whose images were decoded.
+    fn poll_images(&mut self) {
+        for widget_id in self.state.images.collect_results() {
+            let event = LifeCycle::Internal(InternalLifeCycle::RouteImageLoaded(widget_id));
+            self.root_lifecycle(event);
+        }
     }
 
     /// `true` iff any child requested an animation frame since the last `AnimFrame` event.
@@ -566,6 +1209,7 @@ impl RenderRoot {
 
         // Only send RouteFocusChanged in case there's actual change
         if old != new {
+            self.update_focus_restores(old, new);
             let event = LifeCycle::Internal(InternalLifeCycle::RouteFocusChanged { old, new });
             self.state.focused_widget = new;
             self.root_lifecycle(event);
@@ -575,34 +1219,164 @@ impl RenderRoot {
         }
     }
 
+    /// Report the IME area of the focused widget, set with
+    /// [`LayoutCtx::set_ime_area`](crate::LayoutCtx::set_ime_area), if it changed.
+    ///
+    /// Disabled widgets don't get an IME.
+    fn update_ime_area(&mut self) {
+        // The area is reported again after the pending layout pass.
+        if self.root.state().needs_layout {
+            return;
+        }
+        let area = self
+            .state
+            .focused_widget
+            .and_then(|id| self.get_widget(id))
+            .and_then(|widget| {
+                let state = widget.state();
+                if state.is_disabled() {
+                    return None;
+                }
+                let area = state.ime_area? + state.window_origin().to_vec2();
+                Some(area.scale_from_origin(self.zoom))
+            });
+        if area == self.ime_area {
+            return;
+        }
+        match (self.ime_area, area) {
+            (None, Some(_)) => self
+                .state
+                .signal_queue
+                .push_back(RenderRootSignal::ImeStarted),
+            (Some(_), None) => self
+                .state
+                .signal_queue
+                .push_back(RenderRootSignal::ImeInvalidated),
+            _ => (),
+        }
+        if let Some(area) = area {
+            self.state
+                .signal_queue
+                .push_back(RenderRootSignal::ImeMoved(area));
+        }
+        self.ime_area = area;
+    }
+
+    /// Remember which widget to focus again when a scope restoring the focus is closed,
+    /// for the scopes the focus enters, and forget it for the scopes the focus leaves.
+    fn update_focus_restores(&mut self, old: Option<WidgetId>, new: Option<WidgetId>) {
+        let root = self.root.as_dyn();
+        let scopes_around = |id: Option<WidgetId>| {
+            id.map(|id| focus_scopes_around(root, id))
+                .unwrap_or_default()
+        };
+        let old_scopes = scopes_around(old);
+        let new_scopes = scopes_around(new);
+        for scope in &old_scopes {
+            if !new_scopes
+                .iter()
+                .any(|new_scope| new_scope.id() == scope.id())
+            {
+                self.state.focus_restores.remove(&scope.id());
+            }
+        }
+        let Some(old) = old else {
+            return;
+        };
+        for scope in &new_scopes {
+            let entered = !old_scopes
+                .iter()
+                .any(|old_scope| old_scope.id() == scope.id());
+            if entered && scope.restores_focus() {
+                self.state.focus_restores.insert(scope.id(), old);
+            }
+        }
+    }
+
+    /// Give the focus back to the widget focused before the focus entered a scope
+    /// restoring it, if the scope was removed or stashed since and the focus was lost.
+    fn restore_focus_of_closed_scopes(&mut self) {
+        if self.state.focus_restores.is_empty() {
+            return;
+        }
+        let closed: Vec<_> = self
+            .state
+            .focus_restores
+            .iter()
+            .filter(|(scope, _)| {
+                self.get_widget(**scope)
+                    .is_none_or(|scope| scope.state().is_stashed)
+            })
+            .map(|(scope, target)| (*scope, *target))
+            .collect();
+        for (scope, target) in closed {
+            self.state.focus_restores.remove(&scope);
+            // The focus is lost if it went away with the scope, or is still in the
+            // stashed scope, but not if a widget requested it in the meantime.
+            let index = &self.state.widget_index;
+            let focus_is_lost = self.state.next_focused_widget.is_none_or(|focus| {
+                if index.contains(scope) {
+                    index.is_in_subtree(focus, scope)
+                } else {
+                    !index.contains(focus)
+                }
+            });
+            if focus_is_lost && index.contains(target) {
+                debug!("Restoring focus to {target:?} after closing focus scope {scope:?}");
+                self.state.next_focused_widget = Some(target);
+            }
+        }
+    }
+
     fn widget_from_focus_chain(&self, forward: bool) -> Option<WidgetId> {
+        if self.state.focused_widget.is_none() {
+            // Without a focused widget, Tab starts from either end of the window.
+            let focus_chain = self.focus_chain();
+            return if forward {
+                focus_chain.first().copied()
+            } else {
+                focus_chain.last().copied()
+            };
+        }
         self.state.focused_widget.and_then(|focus| {
-            self.focus_chain()
+            // The innermost trapping scope holding the focus keeps it.
+            let focus_chain = focus_scopes_around(self.root.as_dyn(), focus)
+                .into_iter()
+                .rev()
+                .find(|scope| scope.traps_focus() && !scope.state().is_stashed)
+                .map_or(self.focus_chain(), |scope| &scope.state().focus_chain);
+            focus_chain
                 .iter()
                 // Find where the focused widget is in the focus chain
                 .position(|id| id == &focus)
                 .map(|idx| {
                     // Return the id that's next to it in the focus chain
-                    let len = self.focus_chain().len();
+                    let len = focus_chain.len();
                     let new_idx = if forward {
                         (idx + 1) % len
                     } else {
                         (idx + len - 1) % len
                     };
-                    self.focus_chain()[new_idx]
+                    focus_chain[new_idx]
                 })
                 .or_else(|| {
                     // If the currently focused widget isn't in the focus chain,
                     // then we'll just return the first/last entry of the chain, if any.
                     if forward {
-                        self.focus_chain().first().copied()
+                        focus_chain.first().copied()
                     } else {
-                        self.focus_chain().last().copied()
+                        focus_chain.last().copied()
                     }
                 })
         })
     }
 
+    /// Whether the widget with the given id is in the tree, outside of stashed subtrees.
+    fn has_unstashed_widget(&self, id: WidgetId) -> bool {
+        self.widget_path(id)
+            .is_some_and(|path| path.iter().all(|widget| !widget.state().is_stashed))
+    }
+
     // TODO - Store in RenderRootState
     pub(crate) fn focus_chain(&self) -> &[WidgetId] {
         &self.root.state().focus_chain
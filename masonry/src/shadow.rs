@@ -0,0 +1,87 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Drop shadows of widgets.
+
+use vello::kurbo::{Affine, RoundedRect, RoundedRectRadii};
+use vello::peniko::{Color, Fill};
+use vello::Scene;
+
+use crate::ShadowStyle;
+
+/// How many layers approximate the blurred edge of a shadow.
+const SHADOW_LAYERS: usize = 8;
+
+/// The drop shadow of a widget, see [`Elevation`](crate::Elevation).
+///
+/// The blur is approximated with a few translucent layers, which are only computed
+/// again when the shape or the style of the shadow changes. Widgets keep one of these,
+/// and paint it with [`paint`](Self::paint) before their background.
+#[derive(Default)]
+pub struct CachedShadow {
+    /// The shape and style the fragment was built for.
+    key: Option<(RoundedRect, ShadowStyle)>,
+    fragment: Scene,
+    /// How many times the fragment was built, for tests.
+    builds: u32,
+}
+
+impl CachedShadow {
+    /// Paint the shadow of `shape` into `scene`, in the coordinates of `shape`.
+    ///
+    /// The shadow follows the rounded corners of `shape`. Widgets should include
+    /// [`ShadowStyle::insets`] in their paint insets, so that it isn't cut off.
+    pub fn paint(&mut self, scene: &mut Scene, shape: RoundedRect, style: &ShadowStyle) {
+        if style.is_none() {
+            return;
+        }
+        if self.key != Some((shape, *style)) {
+            self.fragment = shadow_fragment(shape, style);
+            self.key = Some((shape, *style));
+            self.builds += 1;
+        }
+        scene.append(&self.fragment, None);
+    }
+
+    #[cfg(test)]
+    pub(crate) fn builds(&self) -> u32 {
+        self.builds
+    }
+}
+
+fn shadow_fragment(shape: RoundedRect, style: &ShadowStyle) -> Scene {
+    let mut fragment = Scene::new();
+    let rect = shape.rect() + style.offset;
+    let radii = shape.radii();
+    let blur = style.blur_radius.max(0.0);
+    if blur == 0.0 {
+        let shadow = RoundedRect::from_rect(rect, radii);
+        fragment.fill(Fill::NonZero, Affine::IDENTITY, style.color, None, &shadow);
+        return fragment;
+    }
+    // Layers go from the shape shrunk by half the blur radius to the shape grown by it,
+    // so that their overlap fades out like a gaussian blur of the shape.
+    let layer_alpha = f64::from(style.color.a) / 255.0 / SHADOW_LAYERS as f64;
+    let color = Color {
+        a: (layer_alpha * 255.0).round().max(1.0) as u8,
+        ..style.color
+    };
+    for layer in 0..SHADOW_LAYERS {
+        let t = layer as f64 / (SHADOW_LAYERS - 1) as f64;
+        let spread = blur * (1.5 * t - 0.5);
+        let grow = |radius: f64| (radius + spread).max(0.0);
+        let radii = RoundedRectRadii::new(
+            grow(radii.top_left),
+            grow(radii.top_right),
+            grow(radii.bottom_right),
+            grow(radii.bottom_left),
+        );
+        let layer_rect = rect.inset(spread);
+        if layer_rect.width() <= 0.0 || layer_rect.height() <= 0.0 {
+            continue;
+        }
+        let layer = RoundedRect::from_rect(layer_rect, radii);
+        fragment.fill(Fill::NonZero, Affine::IDENTITY, color, None, &layer);
+    }
+    fragment
+}
@@ -0,0 +1,436 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::HashMap;
+
+use vello::peniko::Color;
+
+use crate::{theme, ArcStr, Insets, Vec2};
+
+/// A color for each interaction state of a widget.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StateColors {
+    pub normal: Color,
+    pub hovered: Color,
+    /// The color while a button is pressed, or while a textbox is focused.
+    pub active: Color,
+    pub disabled: Color,
+}
+
+impl StateColors {
+    /// The same color in every state.
+    pub const fn uniform(color: Color) -> Self {
+        StateColors {
+            normal: color,
+            hovered: color,
+            active: color,
+            disabled: color,
+        }
+    }
+
+    /// The color for the given state.
+    ///
+    /// Being disabled takes precedence over being active, which takes precedence over
+    /// being hovered.
+    pub fn get(&self, hovered: bool, active: bool, disabled: bool) -> Color {
+        if disabled {
+            self.disabled
+        } else if active {
+            self.active
+        } else if hovered {
+            self.hovered
+        } else {
+            self.normal
+        }
+    }
+}
+
+/// The colors of a [`ButtonVariant`].
+///
+/// The text color is inherited by the label of the button, unless the label sets its
+/// own color. Like all text, a disabled button's label uses
+/// [`theme::DISABLED_TEXT_COLOR`], so `text.disabled` is only used for labels which
+/// don't show their disabled state.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ButtonStyle {
+    pub background: StateColors,
+    pub border: StateColors,
+    pub text: StateColors,
+}
+
+/// The colors of a [`TextboxVariant`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextboxStyle {
+    pub background: StateColors,
+    pub border: StateColors,
+}
+
+/// The look of a [`Button`](crate::widget::Button), as defined by the [`Theme`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ButtonVariant {
+    /// A filled button, for the main action of a screen.
+    Primary,
+    /// An outlined button.
+    Secondary,
+    /// A red button, for destructive actions.
+    Danger,
+    /// A button showing only its text, until it's hovered.
+    Quiet,
+    /// A variant defined with [`Theme::define_button_variant`].
+    Named(ArcStr),
+}
+
+impl ButtonVariant {
+    /// The name of the variant in the [`Theme`].
+    pub fn name(&self) -> &str {
+        match self {
+            ButtonVariant::Primary => "primary",
+            ButtonVariant::Secondary => "secondary",
+            ButtonVariant::Danger => "danger",
+            ButtonVariant::Quiet => "quiet",
+            ButtonVariant::Named(name) => name,
+        }
+    }
+}
+
+/// The look of a [`Textbox`](crate::widget::Textbox), as defined by the [`Theme`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum TextboxVariant {
+    /// A textbox with a background and an underline-like border.
+    Filled,
+    /// A textbox with a transparent background and a border.
+    Outline,
+    /// A variant defined with [`Theme::define_textbox_variant`].
+    Named(ArcStr),
+}
+
+impl TextboxVariant {
+    /// The name of the variant in the [`Theme`].
+    pub fn name(&self) -> &str {
+        match self {
+            TextboxVariant::Filled => "filled",
+            TextboxVariant::Outline => "outline",
+            TextboxVariant::Named(name) => name,
+        }
+    }
+}
+
+/// The drop shadow of a widget, see [`Elevation`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShadowStyle {
+    /// How far the shadow is moved from the widget, usually down.
+    pub offset: Vec2,
+    /// How far the edges of the shadow fade out, on either side of the offset shape.
+    pub blur_radius: f64,
+    pub color: Color,
+}
+
+impl ShadowStyle {
+    /// No shadow.
+    pub const NONE: ShadowStyle = ShadowStyle {
+        offset: Vec2::ZERO,
+        blur_radius: 0.0,
+        color: Color::TRANSPARENT,
+    };
+
+    /// Whether painting the shadow would have no effect.
+    pub fn is_none(&self) -> bool {
+        self.color.a == 0 || (self.blur_radius <= 0.0 && self.offset == Vec2::ZERO)
+    }
+
+    /// How far the shadow extends beyond the bounds of the widget, to be included in its
+    /// paint insets.
+    pub fn insets(&self) -> Insets {
+        if self.is_none() {
+            return Insets::ZERO;
+        }
+        let blur = self.blur_radius.max(0.0);
+        Insets::new(
+            blur - self.offset.x,
+            blur - self.offset.y,
+            blur + self.offset.x,
+            blur + self.offset.y,
+        )
+        .nonnegative()
+    }
+}
+
+/// How high a widget floats above its parent, shown with a drop shadow.
+///
+/// Widgets like [`SizedBox`](crate::widget::SizedBox) paint the [`ShadowStyle`] the
+/// [`Theme`] defines for their elevation below their background.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Elevation {
+    /// One of the levels of the theme, from 0 (no shadow) to 5. Higher levels are
+    /// clamped to 5.
+    Level(u8),
+    /// An arbitrary height in logical pixels, for which the shadow is derived from
+    /// [`theme::SHADOW_COLOR`].
+    Dp(f64),
+}
+
+impl Default for Elevation {
+    fn default() -> Self {
+        Elevation::Level(0)
+    }
+}
+
+/// The styles of the widget variants of a window.
+///
+/// The default theme defines the built-in [`ButtonVariant`]s and [`TextboxVariant`]s,
+/// from the constants of the [`theme`] module. Apps can redefine them, or define new
+/// ones, and switch the theme of a window at runtime with
+/// [`RenderRoot::set_theme`](crate::render_root::RenderRoot::set_theme). Widgets read
+/// it with the `theme` context method, and get [`LifeCycle::ThemeChanged`](crate::LifeCycle::ThemeChanged)
+/// when it changes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    button_variants: HashMap<ArcStr, ButtonStyle>,
+    textbox_variants: HashMap<ArcStr, TextboxStyle>,
+    shadow_levels: [ShadowStyle; 6],
+}
+
+impl Theme {
+    /// Define the style of the button variant named `name`, replacing the previous one.
+    ///
+    /// This can redefine the built-in variants, using the names returned by
+    /// [`ButtonVariant::name`].
+    pub fn define_button_variant(&mut self, name: impl Into<ArcStr>, style: ButtonStyle) {
+        self.button_variants.insert(name.into(), style);
+    }
+
+    /// Builder-style variant of [`define_button_variant`](Self::define_button_variant).
+    pub fn with_button_variant(mut self, name: impl Into<ArcStr>, style: ButtonStyle) -> Self {
+        self.define_button_variant(name, style);
+        self
+    }
+
+    /// Define the style of the textbox variant named `name`, replacing the previous one.
+    pub fn define_textbox_variant(&mut self, name: impl Into<ArcStr>, style: TextboxStyle) {
+        self.textbox_variants.insert(name.into(), style);
+    }
+
+    /// Builder-style variant of [`define_textbox_variant`](Self::define_textbox_variant).
+    pub fn with_textbox_variant(mut self, name: impl Into<ArcStr>, style: TextboxStyle) -> Self {
+        self.define_textbox_variant(name, style);
+        self
+    }
+
+    /// Define the shadow of the [`Elevation::Level`] `level`, which must be at most 5.
+    pub fn define_shadow_level(&mut self, level: u8, style: ShadowStyle) {
+        self.shadow_levels[usize::from(level)] = style;
+    }
+
+    /// The style of a button variant.
+    ///
+    /// Variants the theme doesn't define use the style of [`ButtonVariant::Secondary`].
+    pub fn button_style(&self, variant: &ButtonVariant) -> &ButtonStyle {
+        self.button_variants
+            .get(variant.name())
+            .unwrap_or_else(|| &self.button_variants[ButtonVariant::Secondary.name()])
+    }
+
+    /// The style of a textbox variant.
+    ///
+    /// Variants the theme doesn't define use the style of [`TextboxVariant::Outline`].
+    pub fn textbox_style(&self, variant: &TextboxVariant) -> &TextboxStyle {
+        self.textbox_variants
+            .get(variant.name())
+            .unwrap_or_else(|| &self.textbox_variants[TextboxVariant::Outline.name()])
+    }
+
+    /// The drop shadow of widgets at the given elevation.
+    pub fn shadow_style(&self, elevation: Elevation) -> ShadowStyle {
+        match elevation {
+            Elevation::Level(level) => self.shadow_levels[usize::from(level.min(5))],
+            Elevation::Dp(dp) if dp > 0.0 => ShadowStyle {
+                offset: Vec2::new(0.0, dp / 2.0),
+                blur_radius: dp * 1.5,
+                color: theme::SHADOW_COLOR,
+            },
+            Elevation::Dp(_) => ShadowStyle::NONE,
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        let primary = ButtonStyle {
+            background: StateColors {
+                normal: theme::PRIMARY_DARK,
+                hovered: theme::PRIMARY_LIGHT,
+                active: Color::rgb8(0x00, 0x6a, 0xa6),
+                disabled: theme::DISABLED_BUTTON_LIGHT,
+            },
+            border: StateColors {
+                normal: theme::PRIMARY_DARK,
+                hovered: theme::PRIMARY_LIGHT,
+                active: Color::rgb8(0x00, 0x6a, 0xa6),
+                disabled: theme::DISABLED_BUTTON_LIGHT,
+            },
+            text: StateColors {
+                disabled: theme::DISABLED_TEXT_COLOR,
+                ..StateColors::uniform(Color::WHITE)
+            },
+        };
+        let secondary = ButtonStyle {
+            background: StateColors {
+                active: theme::BACKGROUND_DARK,
+                hovered: theme::BACKGROUND_LIGHT,
+                ..StateColors::uniform(Color::TRANSPARENT)
+            },
+            border: StateColors {
+                normal: theme::BORDER_LIGHT,
+                hovered: theme::FOREGROUND_LIGHT,
+                active: theme::FOREGROUND_LIGHT,
+                disabled: theme::DISABLED_FOREGROUND_DARK,
+            },
+            text: StateColors {
+                disabled: theme::DISABLED_TEXT_COLOR,
+                ..StateColors::uniform(theme::TEXT_COLOR)
+            },
+        };
+        let danger = ButtonStyle {
+            background: StateColors {
+                normal: theme::BADGE_COLOR,
+                hovered: Color::rgb8(0xf0, 0x5a, 0x50),
+                active: Color::rgb8(0xb0, 0x2a, 0x22),
+                disabled: theme::DISABLED_BUTTON_LIGHT,
+            },
+            border: StateColors {
+                normal: theme::BADGE_COLOR,
+                hovered: Color::rgb8(0xf0, 0x5a, 0x50),
+                active: Color::rgb8(0xb0, 0x2a, 0x22),
+                disabled: theme::DISABLED_BUTTON_LIGHT,
+            },
+            text: StateColors {
+                disabled: theme::DISABLED_TEXT_COLOR,
+                ..StateColors::uniform(Color::WHITE)
+            },
+        };
+        let quiet = ButtonStyle {
+            background: StateColors {
+                hovered: theme::BACKGROUND_LIGHT,
+                active: theme::BACKGROUND_DARK,
+                ..StateColors::uniform(Color::TRANSPARENT)
+            },
+            border: StateColors::uniform(Color::TRANSPARENT),
+            text: StateColors {
+                disabled: theme::DISABLED_TEXT_COLOR,
+                ..StateColors::uniform(theme::PRIMARY_LIGHT)
+            },
+        };
+
+        let filled = TextboxStyle {
+            background: StateColors {
+                normal: theme::BACKGROUND_LIGHT,
+                hovered: Color::rgb8(0x44, 0x44, 0x44),
+                active: theme::BACKGROUND_LIGHT,
+                disabled: theme::BACKGROUND_DARK,
+            },
+            border: StateColors {
+                normal: theme::BACKGROUND_LIGHT,
+                hovered: Color::rgb8(0x44, 0x44, 0x44),
+                active: theme::PRIMARY_LIGHT,
+                disabled: theme::BACKGROUND_DARK,
+            },
+        };
+        let outline = TextboxStyle {
+            background: StateColors::uniform(Color::TRANSPARENT),
+            border: StateColors {
+                normal: theme::BORDER_LIGHT,
+                hovered: theme::FOREGROUND_LIGHT,
+                active: theme::PRIMARY_LIGHT,
+                disabled: theme::DISABLED_FOREGROUND_DARK,
+            },
+        };
+
+        let shadow = |offset_y, blur_radius, alpha| ShadowStyle {
+            offset: Vec2::new(0.0, offset_y),
+            blur_radius,
+            color: Color::rgba8(0x00, 0x00, 0x00, alpha),
+        };
+
+        Theme {
+            button_variants: HashMap::new(),
+            textbox_variants: HashMap::new(),
+            shadow_levels: [
+                ShadowStyle::NONE,
+                shadow(1.0, 3.0, 0x4d),
+                shadow(2.0, 6.0, 0x55),
+                shadow(4.0, 10.0, 0x5c),
+                shadow(6.0, 14.0, 0x66),
+                shadow(8.0, 20.0, 0x70),
+            ],
+        }
+        .with_button_variant(ButtonVariant::Primary.name(), primary)
+        .with_button_variant(ButtonVariant::Secondary.name(), secondary)
+        .with_button_variant(ButtonVariant::Danger.name(), danger)
+        .with_button_variant(ButtonVariant::Quiet.name(), quiet)
+        .with_textbox_variant(TextboxVariant::Filled.name(), filled)
+        .with_textbox_variant(TextboxVariant::Outline.name(), outline)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn state_precedence() {
+        let colors = StateColors {
+            normal: Color::BLACK,
+            hovered: Color::RED,
+            active: Color::GREEN,
+            disabled: Color::BLUE,
+        };
+        assert_eq!(colors.get(false, false, false), Color::BLACK);
+        assert_eq!(colors.get(true, false, false), Color::RED);
+        assert_eq!(colors.get(true, true, false), Color::GREEN);
+        assert_eq!(colors.get(true, true, true), Color::BLUE);
+    }
+
+    #[test]
+    fn shadow_insets_cover_blur() {
+        let style = ShadowStyle {
+            offset: Vec2::new(0.0, 4.0),
+            blur_radius: 10.0,
+            color: Color::BLACK,
+        };
+        assert_eq!(style.insets(), Insets::new(10.0, 6.0, 10.0, 14.0));
+        // An offset larger than the blur doesn't reach the other side.
+        let style = ShadowStyle {
+            offset: Vec2::new(12.0, 0.0),
+            ..style
+        };
+        assert_eq!(style.insets(), Insets::new(0.0, 10.0, 22.0, 10.0));
+        assert_eq!(ShadowStyle::NONE.insets(), Insets::ZERO);
+
+        let theme = Theme::default();
+        assert!(theme.shadow_style(Elevation::Level(0)).is_none());
+        assert_eq!(
+            theme.shadow_style(Elevation::Level(9)),
+            theme.shadow_style(Elevation::Level(5))
+        );
+    }
+
+    #[test]
+    fn custom_variants() {
+        let ghost = ButtonStyle {
+            background: StateColors::uniform(Color::TRANSPARENT),
+            border: StateColors::uniform(Color::WHITE),
+            text: StateColors::uniform(Color::WHITE),
+        };
+        let mut theme = Theme::default();
+        theme.define_button_variant("ghost", ghost.clone());
+
+        let variant = ButtonVariant::Named("ghost".into());
+        assert_eq!(theme.button_style(&variant), &ghost);
+        // Unknown variants fall back to the secondary style.
+        let unknown = ButtonVariant::Named("missing".into());
+        assert_eq!(
+            theme.button_style(&unknown),
+            theme.button_style(&ButtonVariant::Secondary)
+        );
+    }
+}
@@ -4,26 +4,37 @@
 //! Tools and infrastructure for testing widgets.
 
 use std::num::NonZeroUsize;
+use std::time::Duration;
 
+use accesskit::{ActionRequest, TreeUpdate};
 use image::io::Reader as ImageReader;
 use image::{Rgba, RgbaImage};
-use vello::util::RenderContext;
-use vello::{block_on_wgpu, RendererOptions};
+use vello::util::{DeviceHandle, RenderContext};
+use vello::{block_on_wgpu, RendererOptions, Scene};
 use wgpu::{
     BufferDescriptor, BufferUsages, CommandEncoderDescriptor, Extent3d, ImageCopyBuffer,
     TextureDescriptor, TextureFormat, TextureUsages,
 };
 use winit::dpi::{LogicalPosition, PhysicalPosition, PhysicalSize};
 use winit::event::{Ime, MouseButton};
+use winit::keyboard::ModifiersState;
 
+use super::render_tests_skipped;
 use super::screenshots::get_image_diff;
 use super::snapshot_utils::get_cargo_workspace;
 use crate::action::Action;
 use crate::event::{PointerEvent, PointerState, TextEvent, WindowEvent};
 use crate::event_loop_runner::try_init_tracing;
-use crate::render_root::{RenderRoot, RenderRootSignal, WindowSizePolicy};
+use crate::frame_budget::{BudgetPolicy, FrameBudgetStats, VirtualClock};
+use crate::frame_cache::FrameCache;
+use crate::image_cache::ImageCache;
+use crate::kurbo::Affine;
+use crate::render_root::{
+    AccessStats, LayoutStats, RenderRoot, RenderRootSignal, WindowSizePolicy,
+};
+use crate::text2::TextLayoutMode;
 use crate::widget::{WidgetMut, WidgetRef};
-use crate::{Color, Handled, Point, Size, Vec2, Widget, WidgetId};
+use crate::{Color, Handled, LayoutDirection, Point, Rect, Size, Theme, Vec2, Widget, WidgetId};
 
 // TODO - Get shorter names
 // TODO - Make them associated consts
@@ -122,6 +133,28 @@ pub struct TestHarness {
     mouse_state: PointerState,
     window_size: PhysicalSize<u32>,
     background_color: Color,
+    /// Created by the first render, and shared by all of them.
+    renderer: Option<HarnessRenderer>,
+}
+
+struct HarnessRenderer {
+    renderer: vello::Renderer,
+    /// The last frame of [`render_incremental`](TestHarness::render_incremental).
+    frame_cache: FrameCache,
+}
+
+thread_local! {
+    /// The render context of the harnesses of the thread, and the id of its device.
+    ///
+    /// With some backends, like OpenGL, dropping a render context breaks the other ones
+    /// of the thread, so the harnesses share one which lives as long as the thread.
+    static RENDER_CONTEXT: (RenderContext, usize) = {
+        let mut context =
+            RenderContext::new().expect("Got non-Send/Sync error from creating render context");
+        let device_id =
+            pollster::block_on(context.device(None)).expect("No compatible device found");
+        (context, device_id)
+    };
 }
 
 /// Assert a snapshot of a rendered frame of your app.
@@ -181,11 +214,16 @@ impl TestHarness {
         // harnesses.
         let _ = try_init_tracing();
 
+        let mut render_root = RenderRoot::new(root_widget, WindowSizePolicy::User, 1.0);
+        // Tests shouldn't depend on how fast a worker thread runs.
+        render_root.set_text_layout_mode(TextLayoutMode::Sync);
+        render_root.image_cache().set_background_decoding(false);
         let mut harness = TestHarness {
-            render_root: RenderRoot::new(root_widget, WindowSizePolicy::User, 1.0),
+            render_root,
             mouse_state,
             window_size,
             background_color,
+            renderer: None,
         };
         harness.process_window_event(WindowEvent::Resize(window_size));
 
@@ -238,104 +276,108 @@ impl TestHarness {
     /// Create a bitmap (an array of pixels), paint the window and return the bitmap as an 8-bits-per-channel RGB image.
     pub fn render(&mut self) -> RgbaImage {
         let (scene, _tree_update) = self.render_root.redraw();
-        if std::env::var("SKIP_RENDER_TESTS").is_ok_and(|it| !it.is_empty()) {
+        let zoom = self.render_root.zoom();
+        let scene = if zoom == 1.0 {
+            scene
+        } else {
+            let mut zoomed_scene = Scene::new();
+            zoomed_scene.append(&scene, Some(Affine::scale(zoom)));
+            zoomed_scene
+        };
+        if render_tests_skipped() {
             return RgbaImage::from_pixel(1, 1, Rgba([255, 255, 255, 255]));
         }
-        let mut context =
-            RenderContext::new().expect("Got non-Send/Sync error from creating render context");
-        let device_id =
-            pollster::block_on(context.device(None)).expect("No compatible device found");
-        let device_handle = &mut context.devices[device_id];
-        let device = &device_handle.device;
-        let queue = &device_handle.queue;
-        let mut renderer = vello::Renderer::new(
-            device,
-            RendererOptions {
-                surface_format: None,
-                // TODO - Examine this value
-                use_cpu: true,
-                num_init_threads: NonZeroUsize::new(1),
-                // TODO - Examine this value
-                antialiasing_support: vello::AaSupport::area_only(),
-            },
-        )
-        .expect("Got non-Send/Sync error from creating renderer");
-
         // TODO - fix window_size
         let (width, height) = (self.window_size.width, self.window_size.height);
-        let render_params = vello::RenderParams {
+        let render_params = self.render_params();
+        RENDER_CONTEXT.with(|(context, device_id)| {
+            let device_handle = &context.devices[*device_id];
+            let harness_renderer = self
+                .renderer
+                .get_or_insert_with(|| HarnessRenderer::new(device_handle));
+            let device = &device_handle.device;
+            let queue = &device_handle.queue;
+
+            let size = Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            };
+            let target = device.create_texture(&TextureDescriptor {
+                label: Some("Target texture"),
+                size,
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: TextureFormat::Rgba8Unorm,
+                usage: TextureUsages::STORAGE_BINDING | TextureUsages::COPY_SRC,
+                view_formats: &[],
+            });
+            let view = target.create_view(&wgpu::TextureViewDescriptor::default());
+            harness_renderer
+                .renderer
+                .render_to_texture(device, queue, &scene, &view, &render_params)
+                .expect("Got non-Send/Sync error from rendering");
+            read_texture(device, queue, &target)
+        })
+    }
+
+    /// Render the areas of the window which changed since the last call, on top of the
+    /// frame it returned, and return the new frame.
+    ///
+    /// This is what the event loop does on each frame: only the
+    /// [paint damage](Self::paint_damage) is rasterized again, so comparing the result
+    /// with [`render`](Self::render) checks that the widgets request a paint whenever
+    /// their appearance changes. The first call renders the whole window.
+    ///
+    /// The other methods which paint the window, like `render` and `paint_damage`,
+    /// consume the damage: the areas which changed before them aren't rendered again
+    /// by the next call.
+    pub fn render_incremental(&mut self) -> RgbaImage {
+        let (scene, _tree_update) = self.render_root.redraw();
+        if render_tests_skipped() {
+            return RgbaImage::from_pixel(1, 1, Rgba([255, 255, 255, 255]));
+        }
+        let render_params = self.render_params();
+        let transform = Affine::scale(self.render_root.zoom());
+        let damage = self.render_root.paint_damage();
+        RENDER_CONTEXT.with(|(context, device_id)| {
+            let device_handle = &context.devices[*device_id];
+            let harness_renderer = self
+                .renderer
+                .get_or_insert_with(|| HarnessRenderer::new(device_handle));
+            let frame_cache = &mut harness_renderer.frame_cache;
+            frame_cache.render(
+                device_handle,
+                &mut harness_renderer.renderer,
+                &scene,
+                transform,
+                damage,
+                &render_params,
+            );
+            read_texture(
+                &device_handle.device,
+                &device_handle.queue,
+                frame_cache.texture().unwrap(),
+            )
+        })
+    }
+
+    fn render_params(&self) -> vello::RenderParams {
+        vello::RenderParams {
             // TODO - Parameterize
             base_color: self.background_color,
-            width,
-            height,
+            width: self.window_size.width,
+            height: self.window_size.height,
             antialiasing_method: vello::AaConfig::Area,
-        };
-
-        let size = Extent3d {
-            width,
-            height,
-            depth_or_array_layers: 1,
-        };
-        let target = device.create_texture(&TextureDescriptor {
-            label: Some("Target texture"),
-            size,
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: wgpu::TextureDimension::D2,
-            format: TextureFormat::Rgba8Unorm,
-            usage: TextureUsages::STORAGE_BINDING | TextureUsages::COPY_SRC,
-            view_formats: &[],
-        });
-        let view = target.create_view(&wgpu::TextureViewDescriptor::default());
-        renderer
-            .render_to_texture(device, queue, &scene, &view, &render_params)
-            .expect("Got non-Send/Sync error from rendering");
-        let padded_byte_width = (width * 4).next_multiple_of(256);
-        let buffer_size = padded_byte_width as u64 * height as u64;
-        let buffer = device.create_buffer(&BufferDescriptor {
-            label: Some("val"),
-            size: buffer_size,
-            usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-        });
-        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
-            label: Some("Copy out buffer"),
-        });
-        encoder.copy_texture_to_buffer(
-            target.as_image_copy(),
-            ImageCopyBuffer {
-                buffer: &buffer,
-                layout: wgpu::ImageDataLayout {
-                    offset: 0,
-                    bytes_per_row: Some(padded_byte_width),
-                    rows_per_image: None,
-                },
-            },
-            size,
-        );
-
-        queue.submit([encoder.finish()]);
-        let buf_slice = buffer.slice(..);
-
-        let (sender, receiver) = futures_intrusive::channel::shared::oneshot_channel();
-        buf_slice.map_async(wgpu::MapMode::Read, move |v| sender.send(v).unwrap());
-        let recv_result = block_on_wgpu(device, receiver.receive()).expect("channel was closed");
-        recv_result.expect("failed to map buffer");
-
-        let data = buf_slice.get_mapped_range();
-        let mut result_unpadded =
-            Vec::<u8>::with_capacity((width * height * 4).try_into().unwrap());
-        for row in 0..height {
-            let start = (row * padded_byte_width).try_into().unwrap();
-            result_unpadded.extend(&data[start..start + (width * 4) as usize]);
         }
-
-        RgbaImage::from_vec(width, height, result_unpadded).expect("failed to create image")
     }
 
     // --- Event helpers ---
 
     /// Move an internal mouse state, and send a MouseMove event to the window.
+    ///
+    /// `pos` is in window coordinates, i.e. it isn't divided by the [zoom](Self::set_zoom).
     pub fn mouse_move(&mut self, pos: impl Into<Point>) {
         // FIXME - Account for scaling
         let pos = pos.into();
@@ -374,7 +416,7 @@ impl TestHarness {
     /// Combines [`mouse_move`](Self::mouse_move), [`mouse_button_press`](Self::mouse_button_press), and [`mouse_button_release`](Self::mouse_button_release).
     pub fn mouse_click_on(&mut self, id: WidgetId) {
         let widget_rect = self.get_widget(id).state().window_layout_rect();
-        let widget_center = widget_rect.center();
+        let widget_center = (widget_rect.center().to_vec2() * self.render_root.zoom()).to_point();
 
         self.mouse_move(widget_center);
         self.mouse_button_press(MouseButton::Left);
@@ -386,11 +428,18 @@ impl TestHarness {
         // FIXME - handle case where the widget isn't visible
         // FIXME - assert that the widget correctly receives the event otherwise?
         let widget_rect = self.get_widget(id).state().window_layout_rect();
-        let widget_center = widget_rect.center();
+        let widget_center = (widget_rect.center().to_vec2() * self.render_root.zoom()).to_point();
 
         self.mouse_move(widget_center);
     }
 
+    /// Set the modifier keys held in the following pointer events, and send a
+    /// ModifierChange event to the window.
+    pub fn set_modifiers(&mut self, modifiers: ModifiersState) {
+        self.mouse_state.mods = modifiers.into();
+        self.process_text_event(TextEvent::ModifierChange(modifiers));
+    }
+
     // TODO - Handle complicated IME
     // TODO - Mock Winit keyboard events
     pub fn keyboard_type_chars(&mut self, text: &str) {
@@ -402,6 +451,42 @@ impl TestHarness {
         self.process_state_after_event();
     }
 
+    /// Move the focus like an unhandled Tab key press, or Shift+Tab if `forward` is
+    /// `false`.
+    ///
+    /// See [`RenderRoot::move_focus`] for details.
+    pub fn move_focus(&mut self, forward: bool) {
+        self.render_root.move_focus(forward);
+        self.process_state_after_event();
+    }
+
+    /// Send the accessibility action `action` to the widget `target`, like assistive
+    /// technologies do.
+    pub fn access_action(&mut self, target: WidgetId, action: accesskit::Action) {
+        self.render_root.root_on_access_event(ActionRequest {
+            action,
+            target: target.into(),
+            data: None,
+        });
+        self.process_state_after_event();
+    }
+
+    /// Send the accessibility action `action` to the widget `target`, with its `data`,
+    /// e.g. the value of a [`SetValue`](accesskit::Action::SetValue) action.
+    pub fn access_action_with_data(
+        &mut self,
+        target: WidgetId,
+        action: accesskit::Action,
+        data: accesskit::ActionData,
+    ) {
+        self.render_root.root_on_access_event(ActionRequest {
+            action,
+            target: target.into(),
+            data: Some(data),
+        });
+        self.process_state_after_event();
+    }
+
     #[cfg(FALSE)]
     /// Simulate the passage of time.
     ///
@@ -437,21 +522,21 @@ impl TestHarness {
     ///
     /// Panics if no Widget with this id can be found.
     pub fn get_widget(&self, id: WidgetId) -> WidgetRef<'_, dyn Widget> {
-        self.root_widget()
-            .find_widget_by_id(id)
+        self.render_root
+            .get_widget(id)
             .expect("could not find widget")
     }
 
     /// Try to return the widget with the given id.
     pub fn try_get_widget(&self, id: WidgetId) -> Option<WidgetRef<'_, dyn Widget>> {
-        self.root_widget().find_widget_by_id(id)
+        self.render_root.get_widget(id)
     }
 
     // TODO - link to focus documentation.
     /// Return the widget that receives keyboard events.
     pub fn focused_widget(&self) -> Option<WidgetRef<'_, dyn Widget>> {
-        self.root_widget()
-            .find_widget_by_id(self.render_root.state.focused_widget?)
+        self.render_root
+            .get_widget(self.render_root.state.focused_widget?)
     }
 
     /// Call the provided visitor on every widget in the widget tree.
@@ -469,6 +554,163 @@ impl TestHarness {
         inspect(self.root_widget(), &f);
     }
 
+    /// Set whether animations should be reduced.
+    ///
+    /// See [`RenderRoot::set_reduced_motion`] for details.
+    pub fn set_reduced_motion(&mut self, reduced_motion: bool) {
+        self.render_root.set_reduced_motion(reduced_motion);
+        self.process_state_after_event();
+    }
+
+    /// Set the layout direction of the window.
+    ///
+    /// See [`RenderRoot::set_layout_direction`] for details.
+    pub fn set_layout_direction(&mut self, direction: LayoutDirection) {
+        self.render_root.set_layout_direction(direction);
+        self.process_state_after_event();
+    }
+
+    /// Set the theme of the window.
+    ///
+    /// See [`RenderRoot::set_theme`] for details.
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.render_root.set_theme(theme);
+        self.process_state_after_event();
+    }
+
+    /// Resize the window, as if the user had resized it.
+    pub fn resize_window(&mut self, size: Size) {
+        self.window_size = PhysicalSize::new(size.width as _, size.height as _);
+        self.process_window_event(WindowEvent::Resize(self.window_size));
+    }
+
+    /// Set the area popup surfaces can cover, as if the window was on a screen.
+    ///
+    /// See [`RenderRoot::set_popup_bounds`] for details.
+    pub fn set_popup_bounds(&mut self, bounds: Option<Rect>) {
+        self.render_root.set_popup_bounds(bounds);
+        self.process_state_after_event();
+    }
+
+    /// Run an animation frame, as if `elapsed` had passed since the previous one.
+    ///
+    /// Unlike sending [`WindowEvent::AnimFrame`], which measures the time between frames,
+    /// this moves animations forward deterministically. Does nothing if no widget
+    /// requested an animation frame.
+    pub fn animate(&mut self, elapsed: Duration) {
+        self.render_root.animate(elapsed);
+        self.process_state_after_event();
+    }
+
+    /// Set the zoom factor of the window.
+    ///
+    /// Layout rects stay in layout coordinates, i.e. a widget whose layout rect is
+    /// `rect` covers `rect * zoom` in the rendered image.
+    ///
+    /// See [`RenderRoot::set_zoom`] for details.
+    pub fn set_zoom(&mut self, zoom: f64) {
+        self.render_root.set_zoom(zoom);
+        self.process_state_after_event();
+    }
+
+    /// Set whether the lines of large texts are broken in the background when their
+    /// wrap width changes.
+    ///
+    /// Background jobs are only run when [`run_pending_line_breaks`](Self::run_pending_line_breaks)
+    /// is called. By default, lines are always broken synchronously.
+    ///
+    /// See [`RenderRoot::set_text_layout_mode`] for details.
+    pub fn set_background_line_breaks(&mut self, enabled: bool) {
+        let mode = if enabled {
+            TextLayoutMode::Manual
+        } else {
+            TextLayoutMode::Sync
+        };
+        self.render_root.set_text_layout_mode(mode);
+        self.process_state_after_event();
+    }
+
+    /// Run the pending background line breaking jobs, and lay out the widgets they're for.
+    pub fn run_pending_line_breaks(&mut self) {
+        self.render_root.run_pending_line_breaks();
+        self.process_state_after_event();
+    }
+
+    /// The cache through which widgets load their images.
+    ///
+    /// In tests, images are only decoded when
+    /// [`run_pending_image_decodes`](Self::run_pending_image_decodes) is called.
+    pub fn image_cache(&self) -> ImageCache {
+        self.render_root.image_cache()
+    }
+
+    /// Decode the queued images, and lay out the widgets waiting for them.
+    pub fn run_pending_image_decodes(&mut self) {
+        self.render_root.run_pending_image_decodes();
+        self.process_state_after_event();
+    }
+
+    /// Run the accessibility pass, and return the resulting tree update.
+    ///
+    /// Like [`render`](Self::render), this also runs the paint pass, but doesn't
+    /// rasterize anything.
+    pub fn access_update(&mut self) -> TreeUpdate {
+        let (_scene, tree_update) = self.render_root.redraw();
+        tree_update
+    }
+
+    /// Run the paint pass, and return the areas of the window which changed.
+    ///
+    /// See [`RenderRoot::paint_damage`] for details. Like
+    /// [`access_update`](Self::access_update), this doesn't rasterize anything.
+    pub fn paint_damage(&mut self) -> Vec<Rect> {
+        let (_scene, _tree_update) = self.render_root.redraw();
+        self.render_root.paint_damage().to_vec()
+    }
+
+    /// Counters about the accessibility tree updates produced so far.
+    ///
+    /// See [`RenderRoot::access_stats`] for details.
+    pub fn access_stats(&self) -> AccessStats {
+        self.render_root.access_stats()
+    }
+
+    /// Counters about the layout passes run so far.
+    ///
+    /// See [`RenderRoot::layout_stats`] for details.
+    pub fn layout_stats(&self) -> LayoutStats {
+        self.render_root.layout_stats()
+    }
+
+    /// Measure the frames against `budget`, and handle the frames going over it with `policy`.
+    ///
+    /// See [`RenderRoot::set_frame_budget`] for details.
+    pub fn set_frame_budget(&mut self, budget: Duration, policy: BudgetPolicy) {
+        self.render_root.set_frame_budget(Some(budget));
+        self.render_root.set_budget_policy(policy);
+    }
+
+    /// Measure the frames against the frame budget with `clock`, which widgets can
+    /// advance to simulate slow passes.
+    pub fn use_virtual_clock(&mut self, clock: VirtualClock) {
+        self.render_root.use_virtual_clock(clock);
+    }
+
+    /// Counters about the frames measured against the frame budget so far.
+    ///
+    /// See [`RenderRoot::frame_budget_stats`] for details.
+    pub fn frame_budget_stats(&self) -> FrameBudgetStats {
+        self.render_root.frame_budget_stats().clone()
+    }
+
+    /// Set whether stashed widgets and widgets scrolled far out of view are pruned from
+    /// the accessibility tree.
+    ///
+    /// See [`RenderRoot::set_prune_hidden_access_nodes`] for details.
+    pub fn set_prune_hidden_access_nodes(&mut self, prune: bool) {
+        self.render_root.set_prune_hidden_access_nodes(prune);
+    }
+
     /// Get a [`WidgetMut`] to the root widget.
     ///
     /// Because of how `WidgetMut` works, it can only be passed to a user-provided callback.
@@ -495,6 +737,15 @@ impl TestHarness {
         }
     }
 
+    /// Pop the first signal matching `predicate` from the queue, e.g. a window request like
+    /// [`RenderRootSignal::DragWindow`].
+    pub fn pop_signal_matching(
+        &mut self,
+        predicate: impl Fn(&RenderRootSignal) -> bool,
+    ) -> Option<RenderRootSignal> {
+        self.render_root.pop_signal_matching(predicate)
+    }
+
     // --- Screenshots ---
 
     /// Method used by [`assert_render_snapshot`]. Use the macro instead.
@@ -567,3 +818,74 @@ impl TestHarness {
         self.render_root.state.debug_logger.write_to_file(path);
     }
 }
+
+impl HarnessRenderer {
+    fn new(device_handle: &DeviceHandle) -> Self {
+        let renderer = vello::Renderer::new(
+            &device_handle.device,
+            RendererOptions {
+                surface_format: None,
+                // TODO - Examine this value
+                use_cpu: true,
+                num_init_threads: NonZeroUsize::new(1),
+                // TODO - Examine this value
+                antialiasing_support: vello::AaSupport::area_only(),
+            },
+        )
+        .expect("Got non-Send/Sync error from creating renderer");
+        HarnessRenderer {
+            renderer,
+            frame_cache: FrameCache::default(),
+        }
+    }
+}
+
+/// Copy the pixels of `texture`, whose format must be `Rgba8Unorm`, to an image.
+pub(crate) fn read_texture(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    texture: &wgpu::Texture,
+) -> RgbaImage {
+    let size = texture.size();
+    let (width, height) = (size.width, size.height);
+    let padded_byte_width = (width * 4).next_multiple_of(256);
+    let buffer_size = padded_byte_width as u64 * height as u64;
+    let buffer = device.create_buffer(&BufferDescriptor {
+        label: Some("val"),
+        size: buffer_size,
+        usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+    let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+        label: Some("Copy out buffer"),
+    });
+    encoder.copy_texture_to_buffer(
+        texture.as_image_copy(),
+        ImageCopyBuffer {
+            buffer: &buffer,
+            layout: wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(padded_byte_width),
+                rows_per_image: None,
+            },
+        },
+        size,
+    );
+
+    queue.submit([encoder.finish()]);
+    let buf_slice = buffer.slice(..);
+
+    let (sender, receiver) = futures_intrusive::channel::shared::oneshot_channel();
+    buf_slice.map_async(wgpu::MapMode::Read, move |v| sender.send(v).unwrap());
+    let recv_result = block_on_wgpu(device, receiver.receive()).expect("channel was closed");
+    recv_result.expect("failed to map buffer");
+
+    let data = buf_slice.get_mapped_range();
+    let mut result_unpadded = Vec::<u8>::with_capacity((width * height * 4).try_into().unwrap());
+    for row in 0..height {
+        let start = (row * padded_byte_width).try_into().unwrap();
+        result_unpadded.extend(&data[start..start + (width * 4) as usize]);
+    }
+
+    RgbaImage::from_vec(width, height, result_unpadded).expect("failed to create image")
+}
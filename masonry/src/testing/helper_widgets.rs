@@ -29,6 +29,7 @@ pub type TextEventFn<S> = dyn FnMut(&mut S, &mut EventCtx, &TextEvent);
 pub type AccessEventFn<S> = dyn FnMut(&mut S, &mut EventCtx, &AccessEvent);
 pub type StatusChangeFn<S> = dyn FnMut(&mut S, &mut LifeCycleCtx, &StatusChange);
 pub type LifeCycleFn<S> = dyn FnMut(&mut S, &mut LifeCycleCtx, &LifeCycle);
+pub type AnimFrameFn<S> = dyn FnMut(&mut S, &mut LifeCycleCtx, u64);
 pub type LayoutFn<S> = dyn FnMut(&mut S, &mut LayoutCtx, &BoxConstraints) -> Size;
 pub type PaintFn<S> = dyn FnMut(&mut S, &mut PaintCtx, &mut Scene);
 pub type RoleFn<S> = dyn Fn(&S) -> Role;
@@ -48,6 +49,7 @@ pub struct ModularWidget<S> {
     on_access_event: Option<Box<AccessEventFn<S>>>,
     on_status_change: Option<Box<StatusChangeFn<S>>>,
     lifecycle: Option<Box<LifeCycleFn<S>>>,
+    on_anim_frame: Option<Box<AnimFrameFn<S>>>,
     layout: Option<Box<LayoutFn<S>>>,
     paint: Option<Box<PaintFn<S>>>,
     role: Option<Box<RoleFn<S>>>,
@@ -125,6 +127,7 @@ impl<S> ModularWidget<S> {
             on_access_event: None,
             on_status_change: None,
             lifecycle: None,
+            on_anim_frame: None,
             layout: None,
             paint: None,
             role: None,
@@ -173,6 +176,14 @@ impl<S> ModularWidget<S> {
         self
     }
 
+    pub fn anim_frame_fn(
+        mut self,
+        f: impl FnMut(&mut S, &mut LifeCycleCtx, u64) + 'static,
+    ) -> Self {
+        self.on_anim_frame = Some(Box::new(f));
+        self
+    }
+
     pub fn layout_fn(
         mut self,
         f: impl FnMut(&mut S, &mut LayoutCtx, &BoxConstraints) -> Size + 'static,
@@ -236,6 +247,12 @@ impl<S: 'static> Widget for ModularWidget<S> {
         }
     }
 
+    fn on_anim_frame(&mut self, ctx: &mut LifeCycleCtx, interval: u64) {
+        if let Some(f) = self.on_anim_frame.as_mut() {
+            f(&mut self.state, ctx, interval);
+        }
+    }
+
     fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints) -> Size {
         let ModularWidget {
             ref mut state,
@@ -397,12 +414,32 @@ impl<W: Widget> Widget for Recorder<W> {
         self.child.lifecycle(ctx, event);
     }
 
+    fn on_anim_frame(&mut self, ctx: &mut LifeCycleCtx, interval: u64) {
+        self.child.on_anim_frame(ctx, interval);
+    }
+
     fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints) -> Size {
         let size = self.child.layout(ctx, bc);
         self.recording.push(Record::Layout(size));
         size
     }
 
+    fn min_intrinsic_width(&mut self, ctx: &mut LayoutCtx, height: f64) -> f64 {
+        self.child.min_intrinsic_width(ctx, height)
+    }
+
+    fn max_intrinsic_width(&mut self, ctx: &mut LayoutCtx, height: f64) -> f64 {
+        self.child.max_intrinsic_width(ctx, height)
+    }
+
+    fn min_intrinsic_height(&mut self, ctx: &mut LayoutCtx, width: f64) -> f64 {
+        self.child.min_intrinsic_height(ctx, width)
+    }
+
+    fn max_intrinsic_height(&mut self, ctx: &mut LayoutCtx, width: f64) -> f64 {
+        self.child.max_intrinsic_height(ctx, width)
+    }
+
     fn paint(&mut self, ctx: &mut PaintCtx, scene: &mut Scene) {
         self.recording.push(Record::Paint);
         self.child.paint(ctx, scene);
@@ -14,6 +14,8 @@ mod screenshots;
 #[cfg(not(tarpaulin_include))]
 mod snapshot_utils;
 
+#[cfg(test)]
+pub(crate) use harness::read_texture;
 pub use harness::{TestHarness, HARNESS_DEFAULT_SIZE};
 pub use helper_widgets::{ModularWidget, Record, Recorder, Recording, ReplaceChild, TestWidgetExt};
 
@@ -24,6 +26,12 @@ pub fn widget_ids<const N: usize>() -> [WidgetId; N] {
     std::array::from_fn(|_| WidgetId::next())
 }
 
+/// Whether the `SKIP_RENDER_TESTS` environment variable is set, in which case
+/// [`TestHarness::render`] returns a blank image instead of rendering the window.
+pub(crate) fn render_tests_skipped() -> bool {
+    std::env::var("SKIP_RENDER_TESTS").is_ok_and(|it| !it.is_empty())
+}
+
 /// This function creates a temporary directory and returns a PathBuf to it.
 ///
 /// This directory will be created relative to the executable and will therefor
@@ -3,7 +3,7 @@
 
 use std::ops::{Deref, DerefMut, Range};
 
-use kurbo::Point;
+use kurbo::{Point, Rect};
 use parley::FontContext;
 use vello::Scene;
 use winit::{
@@ -70,9 +70,50 @@ impl<T: EditableText> TextEditor<T> {
         self.preedit_range = None;
     }
 
-    pub fn rebuild(&mut self, fcx: &mut FontContext) {
-        // TODO: Add the pre-edit range as an underlined region in the text attributes
+    /// Call when another widget becomes focused.
+    ///
+    /// This drops the text being composed with an IME, if any, since it wasn't committed.
+    pub fn focus_lost(&mut self) {
+        if let Some(preedit) = self.preedit_range.take() {
+            self.text_mut().edit(preedit, "");
+        }
+        self.inner.focus_lost();
+    }
+
+    /// The area of the text being composed with an IME, or else of the selection, in the
+    /// coordinates of the layout.
+    ///
+    /// The IME shows its candidate window next to this area. This is not meaningful until
+    /// [`Self::rebuild`] has been called.
+    pub fn ime_area(&self) -> Rect {
+        let range = self
+            .preedit_range
+            .clone()
+            .or_else(|| self.selection.map(|selection| selection.range()))
+            .unwrap_or(0..0);
+        let caret_rect = |position| {
+            let line = self.cursor_line_for_text_position(position);
+            Rect::from_points(line.p0, line.p1)
+        };
+        let (start, end) = (caret_rect(range.start), caret_rect(range.end));
+        if start.y0 == end.y0 {
+            start.union(end)
+        } else {
+            // The range spans several lines, so the area covers their whole width.
+            Rect::new(0., start.y0, self.size().width, end.y1)
+        }
+    }
+
+    /// Replace the whole text, placing the caret at its end.
+    pub fn replace_text(&mut self, text: T) {
+        let len = text.len();
+        self.reset_preedit();
+        self.inner.set_text(text);
+        self.inner.selection = Some(Selection::caret(len, Affinity::Upstream));
+    }
 
+    pub fn rebuild(&mut self, fcx: &mut FontContext) {
+        // The text being composed with an IME is underlined.
         self.inner.rebuild_with_attributes(fcx, |mut builder| {
             if let Some(range) = self.preedit_range.as_ref() {
                 builder.push(
@@ -256,13 +297,18 @@ impl<T: EditableText> TextEditor<T> {
             TextEvent::KeyboardKey(_, _) => Handled::No,
             TextEvent::Ime(ime) => match ime {
                 Ime::Commit(text) => {
-                    if let Some(selection_range) = self.selection.map(|x| x.range()) {
-                        self.text_mut().edit(selection_range.clone(), text);
-                        self.selection = Some(Selection::caret(
-                            selection_range.start + text.len(),
-                            Affinity::Upstream,
-                        ));
-                    }
+                    // The committed text replaces the text being composed, if any. Without
+                    // a selection, e.g. when focused with Tab, it's inserted at the start.
+                    let range = self
+                        .preedit_range
+                        .take()
+                        .or_else(|| self.selection.map(|x| x.range()))
+                        .unwrap_or(0..0);
+                    self.text_mut().edit(range.clone(), text);
+                    self.selection = Some(Selection::caret(
+                        range.start + text.len(),
+                        Affinity::Upstream,
+                    ));
                     let contents = self.text().as_str().to_string();
                     ctx.submit_action(Action::TextChanged(contents));
                     Handled::Yes
@@ -0,0 +1,95 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Highlighting ranges of text, e.g. the matches of a search.
+
+use std::ops::Range;
+
+use kurbo::{Point, Rect};
+use vello::Scene;
+
+use super::{TextLayout, TextStorage};
+use crate::paint_scene_helpers::fill_color;
+use crate::theme;
+
+/// Ranges of a text to highlight, one of which may be the active one, e.g. the current
+/// match of an in-app search.
+///
+/// The ranges are byte offsets into the text, so they don't depend on how the lines
+/// are broken: the highlights follow the text when it's wrapped at another width. Text
+/// widgets own one of these, and paint it behind their text.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TextHighlights {
+    ranges: Vec<Range<usize>>,
+    active_index: Option<usize>,
+}
+
+impl TextHighlights {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn ranges(&self) -> &[Range<usize>] {
+        &self.ranges
+    }
+
+    /// The index in [`ranges`](Self::ranges) of the active highlight, if any.
+    pub fn active_index(&self) -> Option<usize> {
+        self.active_index
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// Replace the highlighted ranges, returning whether they changed.
+    ///
+    /// An `active_index` which isn't the index of one of the `ranges` is ignored. If
+    /// this returns `true`, the widget should request a paint; highlights never affect
+    /// its layout.
+    pub fn set(&mut self, ranges: Vec<Range<usize>>, active_index: Option<usize>) -> bool {
+        let active_index = active_index.filter(|index| *index < ranges.len());
+        if self.ranges == ranges && self.active_index == active_index {
+            return false;
+        }
+        self.ranges = ranges;
+        self.active_index = active_index;
+        true
+    }
+
+    /// Paint the highlights of `layout`, laid out at `origin`.
+    ///
+    /// This should be called before drawing the text, so the highlights are behind it.
+    pub fn paint<T: TextStorage>(&self, scene: &mut Scene, layout: &TextLayout<T>, origin: Point) {
+        for (index, range) in self.ranges.iter().enumerate() {
+            let color = if Some(index) == self.active_index {
+                theme::ACTIVE_TEXT_HIGHLIGHT_COLOR
+            } else {
+                theme::TEXT_HIGHLIGHT_COLOR
+            };
+            for rect in layout.rects_for_range(range.clone()) {
+                fill_color(scene, &(rect + origin.to_vec2()), color);
+            }
+        }
+    }
+
+    /// The bounding rectangle of each highlighted range of `layout`, laid out at `origin`,
+    /// in the order of [`ranges`](Self::ranges).
+    ///
+    /// A range which isn't in the text gets an empty rectangle at `origin`.
+    pub fn match_rects<T: TextStorage>(&self, layout: &TextLayout<T>, origin: Point) -> Vec<Rect> {
+        let offset = origin.to_vec2();
+        self.ranges
+            .iter()
+            .map(|range| {
+                layout
+                    .rects_for_range(range.clone())
+                    .into_iter()
+                    .reduce(|bounds, rect| bounds.union(rect))
+                    .map_or(Rect::from_origin_size(origin, (0.0, 0.0)), |bounds| {
+                        bounds + offset
+                    })
+            })
+            .collect()
+    }
+}
@@ -3,6 +3,7 @@
 
 //! A type for laying out, drawing, and interacting with text.
 
+use std::ops::Range;
 use std::rc::Rc;
 
 use kurbo::{Affine, Line, Point, Rect, Size};
@@ -14,7 +15,7 @@ use parley::{FontContext, Layout, LayoutContext};
 use vello::peniko::{self, Color, Gradient};
 use vello::Scene;
 
-use super::{Link, TextStorage};
+use super::{BrokenLines, Link, TextStorage};
 
 /// A component for displaying text on screen.
 ///
@@ -47,6 +48,8 @@ pub struct TextLayout<T> {
     text_size: f32,
     weight: Weight,
     style: Style,
+    line_height: LineHeight,
+    letter_spacing: f32,
 
     alignment: Alignment,
     max_advance: Option<f32>,
@@ -98,6 +101,24 @@ impl Default for TextBrush {
     }
 }
 
+/// The height of each line in a [`TextLayout`].
+///
+/// If this is smaller than the natural height of the font, each line is
+/// clipped to its own height instead of overlapping its neighbours.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LineHeight {
+    /// A multiple of the font size.
+    FontSizeRelative(f32),
+    /// An absolute height, in logical pixels.
+    Absolute(f32),
+}
+
+impl Default for LineHeight {
+    fn default() -> Self {
+        Self::FontSizeRelative(1.0)
+    }
+}
+
 /// Metrics describing the layout text.
 #[derive(Debug, Clone, Copy, Default)]
 pub struct LayoutMetrics {
@@ -122,6 +143,8 @@ impl<T> TextLayout<T> {
             text_size,
             weight: Weight::NORMAL,
             style: Style::Normal,
+            line_height: LineHeight::default(),
+            letter_spacing: 0.0,
 
             max_advance: None,
             alignment: Default::default(),
@@ -197,6 +220,22 @@ impl<T> TextLayout<T> {
         }
     }
 
+    /// Set the height of each line.
+    pub fn set_line_height(&mut self, line_height: LineHeight) {
+        if line_height != self.line_height {
+            self.line_height = line_height;
+            self.invalidate();
+        }
+    }
+
+    /// Set the extra space added between each character, in logical pixels.
+    pub fn set_letter_spacing(&mut self, letter_spacing: f32) {
+        if letter_spacing != self.letter_spacing {
+            self.letter_spacing = letter_spacing;
+            self.invalidate();
+        }
+    }
+
     /// Set the [`Alignment`] for this layout.
     pub fn set_text_alignment(&mut self, alignment: Alignment) {
         if self.alignment != alignment {
@@ -211,17 +250,28 @@ impl<T> TextLayout<T> {
     /// (the default behaviour).
     pub fn set_max_advance(&mut self, max_advance: Option<f32>) {
         let max_advance = max_advance.map(|it| it.max(0.0));
-        if self.max_advance.is_some() != max_advance.is_some()
+        if self.max_advance_differs(max_advance) {
+            self.max_advance = max_advance;
+            self.needs_line_breaks = true;
+        }
+    }
+
+    /// Whether wrapping at `max_advance` would give different lines than the current
+    /// wrap width.
+    pub(crate) fn max_advance_differs(&self, max_advance: Option<f32>) -> bool {
+        let max_advance = max_advance.map(|it| it.max(0.0));
+        self.max_advance.is_some() != max_advance.is_some()
             || self
                 .max_advance
                 .zip(max_advance)
                 // 1e-4 is an arbitrary small-enough value that we don't care to rewrap
                 .map(|(old, new)| (old - new).abs() >= 1e-4)
                 .unwrap_or(false)
-        {
-            self.max_advance = max_advance;
-            self.needs_line_breaks = true;
-        }
+    }
+
+    /// The alignment passed to Parley when breaking lines.
+    pub(crate) fn alignment(&self) -> Alignment {
+        self.alignment
     }
 
     /// Returns `true` if this layout needs to be rebuilt.
@@ -234,6 +284,17 @@ impl<T> TextLayout<T> {
         self.needs_layout || self.needs_line_breaks
     }
 
+    /// The line height as a multiple of the font size, which is what Parley expects.
+    fn line_height_multiplier(&self) -> f32 {
+        match self.line_height {
+            LineHeight::FontSizeRelative(multiplier) => multiplier.max(0.0),
+            LineHeight::Absolute(height) if self.text_size > 0.0 => {
+                height.max(0.0) / self.text_size
+            }
+            LineHeight::Absolute(_) => 1.0,
+        }
+    }
+
     // TODO: What are the valid use cases for this, where we shouldn't use a run-specific check instead?
     // /// Returns `true` if this layout's text appears to be right-to-left.
     // ///
@@ -283,6 +344,28 @@ impl<T: TextStorage> TextLayout<T> {
         &self.layout
     }
 
+    /// The number of lines of the laid-out text.
+    ///
+    /// This is not meaningful until [`Self::rebuild`] has been called.
+    pub fn line_count(&self) -> usize {
+        self.assert_rebuilt("line_count");
+        self.layout.len()
+    }
+
+    /// Replace the inner layout with one whose lines were broken elsewhere, e.g. on
+    /// a worker thread.
+    ///
+    /// This is ignored if the layout must be rebuilt, since `lines` were then computed
+    /// from an outdated layout or with outdated parameters.
+    pub(crate) fn set_broken_lines(&mut self, lines: BrokenLines) {
+        if self.needs_rebuild() {
+            return;
+        }
+        self.layout = lines.layout;
+        self.max_advance = lines.max_advance;
+        self.needs_line_breaks = false;
+    }
+
     /// The size of the laid-out text, excluding any trailing whitespace.
     ///
     /// This is not meaningful until [`Self::rebuild`] has been called.
@@ -365,16 +448,54 @@ impl<T: TextStorage> TextLayout<T> {
         )
     }
 
-    // TODO: needed for text selection
-    // /// Given a utf-8 range in the underlying text, return a `Vec` of `Rect`s
-    // /// representing the nominal bounding boxes of the text in that range.
-    // ///
-    // /// # Panics
-    // ///
-    // /// Panics if the range start or end is not a character boundary.
-    // pub fn rects_for_range(&self, range: Range<usize>) -> Vec<Rect> {
-    //     self.layout.rects_for_range(range)
-    // }
+    /// Given a utf-8 range in the underlying text, return the rectangles (relative to
+    /// this object's origin) covering the text in that range, one per line.
+    ///
+    /// Each rectangle spans the full height of its line, from the start of the first
+    /// cluster in the range to the end of the last one, so the rectangles stay correct
+    /// when the lines are broken again at another width. Clusters which are only
+    /// partially in the range, e.g. ligatures, are covered entirely.
+    ///
+    /// This is not meaningful until [`Self::rebuild`] has been called.
+    pub fn rects_for_range(&self, range: Range<usize>) -> Vec<Rect> {
+        self.assert_rebuilt("rects_for_range");
+        let mut rects = Vec::new();
+        if range.is_empty() {
+            return rects;
+        }
+        for line in self.layout.lines() {
+            if line.text_range().end <= range.start || line.text_range().start >= range.end {
+                continue;
+            }
+            let metrics = line.metrics();
+            let mut x = metrics.offset;
+            let mut covered: Option<(f32, f32)> = None;
+            for run in line.runs() {
+                for cluster in run.visual_clusters() {
+                    let cluster_range = cluster.text_range();
+                    let advance = cluster.advance();
+                    if cluster_range.start < range.end && cluster_range.end > range.start {
+                        covered = Some(match covered {
+                            Some((x0, x1)) => (x0.min(x), x1.max(x + advance)),
+                            None => (x, x + advance),
+                        });
+                    }
+                    x += advance;
+                }
+            }
+            if let Some((x0, x1)) = covered {
+                // Like the highlight of a selection, from the top of the line.
+                let y0 = metrics.baseline - metrics.ascent - metrics.leading;
+                rects.push(Rect::new(
+                    x0 as f64,
+                    y0 as f64,
+                    x1 as f64,
+                    (y0 + metrics.size()) as f64,
+                ));
+            }
+        }
+        rects
+    }
 
     /// Given the utf-8 position of a character boundary in the underlying text,
     /// return a `Line` suitable for drawing a vertical cursor at that boundary.
@@ -439,6 +560,7 @@ impl<T: TextStorage> TextLayout<T> {
         if self.needs_layout {
             self.needs_layout = false;
 
+            let line_height = self.line_height_multiplier();
             let mut builder =
                 self.layout_context
                     .ranged_builder(fcx, self.text.as_str(), self.scale);
@@ -447,6 +569,8 @@ impl<T: TextStorage> TextLayout<T> {
             builder.push_default(&StyleProperty::FontStack(self.font));
             builder.push_default(&StyleProperty::FontWeight(self.weight));
             builder.push_default(&StyleProperty::FontStyle(self.style));
+            builder.push_default(&StyleProperty::LineHeight(line_height));
+            builder.push_default(&StyleProperty::LetterSpacing(self.letter_spacing));
             // For more advanced features (e.g. variable font axes), these can be set in add_attributes
 
             let builder = self.text.add_attributes(builder);
@@ -496,6 +620,8 @@ impl<T: TextStorage> std::fmt::Debug for TextLayout<T> {
             .field("text_size", &self.text_size)
             .field("weight", &self.weight)
             .field("style", &self.style)
+            .field("line_height", &self.line_height)
+            .field("letter_spacing", &self.letter_spacing)
             .field("alignment", &self.alignment)
             .field("wrap_width", &self.max_advance)
             .field("outdated?", &self.needs_rebuild())
@@ -0,0 +1,258 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Breaking the lines of large texts on a worker thread.
+//!
+//! Shaping a text doesn't depend on the wrap width, so when only the width of a large
+//! text changes (e.g. while the window is resized), the text widget can keep displaying
+//! its current lines, and have them broken again at the new width in the background.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::JoinHandle;
+
+use parley::layout::Alignment;
+use parley::Layout;
+use tracing::{trace, warn};
+
+use crate::text2::TextBrush;
+use crate::WidgetId;
+
+/// Texts with at least this many bytes have their lines broken in the background.
+pub(crate) const BACKGROUND_LINE_BREAKS_THRESHOLD: usize = 10_000;
+
+/// Where the lines of large texts are broken when their wrap width changes.
+///
+/// See [`RenderRoot::set_text_layout_mode`](crate::render_root::RenderRoot::set_text_layout_mode).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextLayoutMode {
+    /// Always on the UI thread, during the layout pass.
+    Sync,
+    /// On a worker thread owned by the [`RenderRoot`](crate::render_root::RenderRoot).
+    Background,
+    /// Like `Background`, but the work is only done when
+    /// [`RenderRoot::run_pending_line_breaks`](crate::render_root::RenderRoot::run_pending_line_breaks)
+    /// is called, so that tests are deterministic.
+    Manual,
+}
+
+/// A layout whose lines were broken in the background.
+pub(crate) struct BrokenLines {
+    pub(crate) layout: Layout<TextBrush>,
+    pub(crate) max_advance: Option<f32>,
+}
+
+struct LineBreakJob {
+    widget_id: WidgetId,
+    generation: u64,
+    layout: Layout<TextBrush>,
+    max_advance: Option<f32>,
+    alignment: Alignment,
+}
+
+impl LineBreakJob {
+    fn run(mut self) -> (WidgetId, u64, BrokenLines) {
+        let _span = tracing::trace_span!("break_lines", widget = ?self.widget_id).entered();
+        self.layout
+            .break_all_lines(self.max_advance, self.alignment);
+        let lines = BrokenLines {
+            layout: self.layout,
+            max_advance: self.max_advance,
+        };
+        (self.widget_id, self.generation, lines)
+    }
+}
+
+#[derive(Default)]
+struct JobQueue {
+    jobs: VecDeque<LineBreakJob>,
+    shutdown: bool,
+}
+
+#[derive(Default)]
+struct Shared {
+    queue: Mutex<JobQueue>,
+    job_added: Condvar,
+}
+
+type JobResult = (WidgetId, u64, BrokenLines);
+
+/// Runs line breaking jobs, and collects their results for the widgets.
+pub(crate) struct LineBreakPool {
+    mode: TextLayoutMode,
+    shared: Arc<Shared>,
+    result_sender: Sender<JobResult>,
+    result_receiver: Receiver<JobResult>,
+    worker: Option<JoinHandle<()>>,
+    next_generation: u64,
+    /// The generation of the latest job of each widget which hasn't been taken yet.
+    ///
+    /// Results of older jobs are stale, and are discarded.
+    latest: HashMap<WidgetId, u64>,
+    ready: HashMap<WidgetId, BrokenLines>,
+}
+
+impl LineBreakPool {
+    pub(crate) fn new(mode: TextLayoutMode) -> Self {
+        let (result_sender, result_receiver) = channel();
+        LineBreakPool {
+            mode,
+            shared: Arc::default(),
+            result_sender,
+            result_receiver,
+            worker: None,
+            next_generation: 0,
+            latest: HashMap::new(),
+            ready: HashMap::new(),
+        }
+    }
+
+    pub(crate) fn mode(&self) -> TextLayoutMode {
+        self.mode
+    }
+
+    pub(crate) fn set_mode(&mut self, mode: TextLayoutMode) {
+        self.mode = mode;
+    }
+
+    /// Queue a job breaking the lines of `layout`, replacing the queued job of the same
+    /// widget if there is one, so that only the latest width is computed.
+    pub(crate) fn submit(
+        &mut self,
+        widget_id: WidgetId,
+        layout: Layout<TextBrush>,
+        max_advance: Option<f32>,
+        alignment: Alignment,
+    ) {
+        trace!("Breaking lines of {widget_id:?} at {max_advance:?} in the background");
+        self.next_generation += 1;
+        let generation = self.next_generation;
+        self.latest.insert(widget_id, generation);
+        self.ready.remove(&widget_id);
+
+        let job = LineBreakJob {
+            widget_id,
+            generation,
+            layout,
+            max_advance,
+            alignment,
+        };
+        {
+            let mut queue = self.shared.queue.lock().unwrap();
+            queue.jobs.retain(|job| job.widget_id != widget_id);
+            queue.jobs.push_back(job);
+        }
+        self.shared.job_added.notify_one();
+
+        if self.mode == TextLayoutMode::Background && self.worker.is_none() {
+            self.spawn_worker();
+        }
+    }
+
+    /// Discard the queued job and the result of `widget_id`, if any.
+    pub(crate) fn cancel(&mut self, widget_id: WidgetId) {
+        if self.latest.remove(&widget_id).is_none() {
+            return;
+        }
+        self.ready.remove(&widget_id);
+        let mut queue = self.shared.queue.lock().unwrap();
+        queue.jobs.retain(|job| job.widget_id != widget_id);
+    }
+
+    /// Run the queued jobs on the current thread.
+    pub(crate) fn run_pending(&mut self) {
+        let jobs = std::mem::take(&mut self.shared.queue.lock().unwrap().jobs);
+        for job in jobs {
+            // The receiver is owned by `self`, so this can't fail.
+            let _ = self.result_sender.send(job.run());
+        }
+    }
+
+    /// Collect the results of finished jobs, and return the widgets they're for.
+    ///
+    /// Stale results are discarded.
+    pub(crate) fn collect_results(&mut self) -> Vec<WidgetId> {
+        let mut widgets = Vec::new();
+        while let Ok((widget_id, generation, lines)) = self.result_receiver.try_recv() {
+            if self.latest.get(&widget_id) == Some(&generation) {
+                self.ready.insert(widget_id, lines);
+                widgets.push(widget_id);
+            }
+        }
+        widgets
+    }
+
+    /// Take the result of the latest job of `widget_id`, if it's finished.
+    pub(crate) fn take(&mut self, widget_id: WidgetId) -> Option<BrokenLines> {
+        let lines = self.ready.remove(&widget_id)?;
+        self.latest.remove(&widget_id);
+        Some(lines)
+    }
+
+    /// Whether some jobs haven't finished yet.
+    pub(crate) fn has_pending(&self) -> bool {
+        self.latest.len() > self.ready.len()
+    }
+
+    /// Cancel the jobs of the widgets for which `keep` returns `false`, e.g. because
+    /// they were removed from the tree.
+    pub(crate) fn retain_widgets(&mut self, keep: impl Fn(WidgetId) -> bool) {
+        let removed: Vec<WidgetId> = self
+            .latest
+            .keys()
+            .copied()
+            .filter(|widget_id| !keep(*widget_id))
+            .collect();
+        for widget_id in removed {
+            self.cancel(widget_id);
+        }
+    }
+
+    fn spawn_worker(&mut self) {
+        let shared = self.shared.clone();
+        let result_sender = self.result_sender.clone();
+        let worker = std::thread::Builder::new()
+            .name("masonry-line-breaks".into())
+            .spawn(move || worker_loop(&shared, &result_sender));
+        match worker {
+            Ok(worker) => self.worker = Some(worker),
+            Err(err) => {
+                warn!("Could not spawn line breaking thread, breaking lines synchronously: {err}");
+                self.mode = TextLayoutMode::Sync;
+                self.run_pending();
+            }
+        }
+    }
+}
+
+fn worker_loop(shared: &Shared, result_sender: &Sender<JobResult>) {
+    loop {
+        let job = {
+            let mut queue = shared.queue.lock().unwrap();
+            loop {
+                if queue.shutdown {
+                    return;
+                }
+                if let Some(job) = queue.jobs.pop_front() {
+                    break job;
+                }
+                queue = shared.job_added.wait(queue).unwrap();
+            }
+        };
+        if result_sender.send(job.run()).is_err() {
+            return;
+        }
+    }
+}
+
+impl Drop for LineBreakPool {
+    fn drop(&mut self) {
+        self.shared.queue.lock().unwrap().shutdown = true;
+        self.shared.job_added.notify_all();
+        // The worker only finishes its current job, so this doesn't block for long.
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
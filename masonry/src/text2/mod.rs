@@ -14,7 +14,14 @@ mod store;
 pub use store::{Link, TextStorage};
 
 mod layout;
-pub use layout::{LayoutMetrics, TextBrush, TextLayout};
+pub use layout::{LayoutMetrics, LineHeight, TextBrush, TextLayout};
+
+mod highlights;
+pub use highlights::TextHighlights;
+
+mod line_breaks;
+pub use line_breaks::TextLayoutMode;
+pub(crate) use line_breaks::{BrokenLines, LineBreakPool, BACKGROUND_LINE_BREAKS_THRESHOLD};
 
 mod selection;
 pub use selection::{
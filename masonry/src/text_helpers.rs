@@ -5,7 +5,11 @@
 
 use kurbo::{Line, Rect, Stroke};
 use parley::Layout;
-use vello::{kurbo::Affine, peniko::Fill, Scene};
+use vello::{
+    kurbo::Affine,
+    peniko::{BlendMode, Fill},
+    Scene,
+};
 
 use crate::{text2::TextBrush, WidgetId};
 
@@ -57,6 +61,28 @@ pub fn render_text(
     scratch_scene.reset();
     for line in layout.lines() {
         let metrics = &line.metrics();
+        // A line height smaller than the font's natural height gives a negative leading.
+        // Rather than letting glyphs overlap the neighbouring lines, clip them to their own line.
+        let clip_line = metrics.leading < 0.0 && line.glyph_runs().next().is_some();
+        if clip_line {
+            let (min_x, max_x) =
+                line.glyph_runs()
+                    .fold((f32::MAX, f32::MIN), |(min_x, max_x), glyph_run| {
+                        (
+                            min_x.min(glyph_run.offset()),
+                            max_x.max(glyph_run.offset() + glyph_run.advance()),
+                        )
+                    });
+            // Leave some horizontal room for glyphs which overhang their advance
+            let overhang = metrics.ascent + metrics.descent;
+            let clip_rect = Rect::new(
+                (min_x - overhang) as f64,
+                (metrics.baseline - metrics.ascent - metrics.leading * 0.5) as f64,
+                (max_x + overhang) as f64,
+                (metrics.baseline + metrics.descent + metrics.leading * 0.5) as f64,
+            );
+            scratch_scene.push_layer(BlendMode::default(), 1., transform, &clip_rect);
+        }
         for glyph_run in line.glyph_runs() {
             let mut x = glyph_run.offset();
             let y = glyph_run.baseline();
@@ -182,6 +208,9 @@ pub fn render_text(
                 );
             }
         }
+        if clip_line {
+            scratch_scene.pop_layer();
+        }
     }
     scene.append(scratch_scene, None);
 }
@@ -0,0 +1,106 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+use parley::style::{FontFamily, FontStack, GenericFamily};
+use vello::peniko::Color;
+
+use crate::text2::{TextBrush, TextLayout};
+use crate::theme;
+
+/// Text styling set on a widget, and inherited by all the text of its descendants.
+///
+/// This is set on any widget, e.g. a [`Flex`](crate::widget::Flex) holding a form, with
+/// [`WidgetMut::set_text_style`](crate::widget::WidgetMut::set_text_style) or
+/// [`WidgetPod::with_text_style`](crate::WidgetPod::with_text_style). Each property that
+/// is `None` is inherited from the closest ancestor setting it, and falls back to the theme.
+///
+/// Text widgets such as [`Label`](crate::widget::Label) read the result with
+/// [`LifeCycleCtx::text_style`](crate::LifeCycleCtx::text_style). Their own text color,
+/// size and font, e.g. set with [`Label::with_text_size`](crate::widget::Label::with_text_size),
+/// take precedence over the inherited ones.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TextStyle {
+    pub color: Option<Color>,
+    pub size: Option<f32>,
+    pub font: Option<FontStack<'static>>,
+}
+
+impl TextStyle {
+    /// Builder-style method for setting the text color.
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = Some(color);
+        self
+    }
+
+    /// Builder-style method for setting the font size.
+    pub fn with_size(mut self, size: f32) -> Self {
+        self.size = Some(size);
+        self
+    }
+
+    /// Builder-style method for setting the font.
+    pub fn with_font(mut self, font: FontStack<'static>) -> Self {
+        self.font = Some(font);
+        self
+    }
+
+    /// The style of a widget setting this style, below a parent with the given style.
+    pub(crate) fn resolve(&self, inherited: &ResolvedTextStyle) -> ResolvedTextStyle {
+        ResolvedTextStyle {
+            color: self.color.unwrap_or(inherited.color),
+            size: self.size.unwrap_or(inherited.size),
+            font: self.font.unwrap_or(inherited.font),
+        }
+    }
+}
+
+/// The text style a widget inherits, once every property is resolved.
+///
+/// See [`TextStyle`] for details.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedTextStyle {
+    pub color: Color,
+    pub size: f32,
+    pub font: FontStack<'static>,
+}
+
+impl Default for ResolvedTextStyle {
+    fn default() -> Self {
+        ResolvedTextStyle {
+            color: theme::TEXT_COLOR,
+            size: theme::TEXT_SIZE_NORMAL as f32,
+            font: FontStack::Single(FontFamily::Generic(GenericFamily::SansSerif)),
+        }
+    }
+}
+
+/// The text style a text widget sets itself, overriding what it inherits.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TextStyleOverrides {
+    pub(crate) brush: Option<TextBrush>,
+    pub(crate) size: Option<f32>,
+    pub(crate) font: Option<FontStack<'static>>,
+}
+
+impl TextStyleOverrides {
+    /// Style `layout` with these overrides on top of the inherited `style`.
+    ///
+    /// If `disabled`, the text uses the disabled color of the theme instead. Returns
+    /// whether the layout must be rebuilt.
+    pub(crate) fn apply<T>(
+        &self,
+        layout: &mut TextLayout<T>,
+        style: &ResolvedTextStyle,
+        disabled: bool,
+    ) -> bool {
+        let brush = match &self.brush {
+            _ if disabled => theme::DISABLED_TEXT_COLOR.into(),
+            Some(brush) => brush.clone(),
+            None => style.color.into(),
+        };
+        layout.set_brush(brush);
+        layout.set_text_size(self.size.unwrap_or(style.size));
+        layout.set_font(self.font.unwrap_or(style.font));
+        layout.needs_rebuild()
+    }
+}
@@ -36,9 +36,21 @@ pub const BORDER_LIGHT: Color = Color::rgb8(0xa1, 0xa1, 0xa1);
 pub const SELECTED_TEXT_BACKGROUND_COLOR: Color = Color::rgb8(0x43, 0x70, 0xA8);
 pub const SELECTED_TEXT_INACTIVE_BACKGROUND_COLOR: Color = Color::rgb8(0x74, 0x74, 0x74);
 pub const SELECTION_TEXT_COLOR: Color = Color::rgb8(0x00, 0x00, 0x00);
+pub const TEXT_HIGHLIGHT_COLOR: Color = Color::rgb8(0x6b, 0x5a, 0x12);
+pub const ACTIVE_TEXT_HIGHLIGHT_COLOR: Color = Color::rgb8(0xd0, 0x8a, 0x10);
 pub const CURSOR_COLOR: Color = Color::WHITE;
+pub const BADGE_COLOR: Color = Color::rgb8(0xe0, 0x3c, 0x31);
+pub const BADGE_TEXT_COLOR: Color = Color::WHITE;
+pub const ERROR_COLOR: Color = Color::rgb8(0xe0, 0x3c, 0x31);
+pub const ERROR_COLOR_LIGHT: Color = Color::rgb8(0xf0, 0x6e, 0x64);
+pub const WARNING_COLOR: Color = Color::rgb8(0xf0, 0xa8, 0x1e);
+pub const WARNING_COLOR_LIGHT: Color = Color::rgb8(0xf8, 0xc8, 0x5a);
+pub const VALIDATION_ICON_SIZE: f64 = 12.;
+pub const SCRIM_COLOR: Color = Color::rgba8(0x00, 0x00, 0x00, 0x99);
+pub const SHADOW_COLOR: Color = Color::rgba8(0x00, 0x00, 0x00, 0x5c);
 pub const TEXT_SIZE_NORMAL: f64 = 15.0;
 pub const TEXT_SIZE_LARGE: f64 = 24.0;
+pub const TEXT_SIZE_CAPTION: f64 = 11.0;
 pub const BASIC_WIDGET_HEIGHT: f64 = 18.0;
 pub const WIDE_WIDGET_WIDTH: f64 = 100.;
 pub const BORDERED_WIDGET_HEIGHT: f64 = 24.0;
@@ -0,0 +1,174 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+use accesskit::{Invalid, NodeBuilder, NodeId, Role};
+use vello::kurbo::{Affine, Circle, Point, Rect, Shape, Size, Stroke};
+use vello::peniko::Color;
+use vello::Scene;
+
+use crate::paint_scene_helpers::fill_color;
+use crate::{theme, AccessCtx, ArcStr, PaintCtx, StateColors, WidgetId};
+
+/// The distance between the validation icon and the top-right corner of the widget.
+const ICON_MARGIN: f64 = 4.0;
+
+/// Whether the value of a form widget is acceptable, e.g. as reported by the app after
+/// checking it.
+///
+/// Form widgets show it with a [`ValidationAdornment`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum ValidationState {
+    /// The value is acceptable. Nothing is shown.
+    #[default]
+    Valid,
+    /// The value can't be used, e.g. because a required field is empty.
+    Invalid { message: ArcStr },
+    /// The value can be used, but may be a mistake.
+    Warning { message: ArcStr },
+}
+
+impl ValidationState {
+    /// The message explaining what's wrong with the value, if any.
+    pub fn message(&self) -> Option<&ArcStr> {
+        match self {
+            ValidationState::Valid => None,
+            ValidationState::Invalid { message } | ValidationState::Warning { message } => {
+                Some(message)
+            }
+        }
+    }
+
+    /// The colors of the border of a widget in this state, or `None` if the widget keeps
+    /// its own border.
+    pub fn border_colors(&self) -> Option<StateColors> {
+        let (color, light_color) = match self {
+            ValidationState::Valid => return None,
+            ValidationState::Invalid { .. } => (theme::ERROR_COLOR, theme::ERROR_COLOR_LIGHT),
+            ValidationState::Warning { .. } => (theme::WARNING_COLOR, theme::WARNING_COLOR_LIGHT),
+        };
+        Some(StateColors {
+            normal: color,
+            hovered: light_color,
+            active: light_color,
+            disabled: theme::DISABLED_FOREGROUND_DARK,
+        })
+    }
+}
+
+/// Shows the [`ValidationState`] of a form widget.
+///
+/// Form widgets own one of these, and call [`paint`](Self::paint) at the end of their
+/// own `paint`, and [`accessibility`](Self::accessibility) at the end of their own
+/// `accessibility`. It paints the border of the widget in the color of the state, and an
+/// icon in its top-right corner. Screen readers are told that an invalid widget is
+/// invalid, with its message as the error message. The message of a warning is
+/// appended to the description of the widget instead.
+///
+/// Changing the state only requires a repaint and an accessibility update, since the
+/// adornment doesn't affect layout.
+#[derive(Debug)]
+pub struct ValidationAdornment {
+    state: ValidationState,
+    /// The id of the accessibility node holding the message.
+    message_id: WidgetId,
+}
+
+impl ValidationAdornment {
+    pub fn new() -> Self {
+        ValidationAdornment {
+            state: ValidationState::Valid,
+            message_id: WidgetId::next(),
+        }
+    }
+
+    pub fn state(&self) -> &ValidationState {
+        &self.state
+    }
+
+    /// Set the state, returning whether it changed.
+    ///
+    /// If it did, the widget should request a paint and an accessibility update.
+    pub fn set_state(&mut self, state: ValidationState) -> bool {
+        if self.state == state {
+            return false;
+        }
+        self.state = state;
+        true
+    }
+
+    /// Paint `border` in the color of the state, with a stroke of `border_width`, and
+    /// the icon of the state in the top-right corner of the widget.
+    ///
+    /// The border color depends on whether the widget is hovered, has focus or is
+    /// disabled, like the colors of a [`TextboxStyle`](crate::TextboxStyle).
+    pub fn paint(&self, ctx: &PaintCtx, scene: &mut Scene, border: &impl Shape, border_width: f64) {
+        let Some(colors) = self.state.border_colors() else {
+            return;
+        };
+        let color = colors.get(ctx.is_hot(), ctx.has_focus(), ctx.is_disabled());
+        scene.stroke(
+            &Stroke::new(border_width),
+            Affine::IDENTITY,
+            color,
+            None,
+            border,
+        );
+        paint_icon(scene, ctx.size(), color);
+    }
+
+    /// Expose the state to screen readers, on the node of the widget.
+    pub fn accessibility(&self, ctx: &mut AccessCtx) {
+        match &self.state {
+            ValidationState::Valid => {}
+            ValidationState::Invalid { message } => {
+                let message_id: NodeId = self.message_id.into();
+                let mut message_node = NodeBuilder::new(Role::StaticText);
+                message_node.set_name(message.to_string());
+                ctx.tree_update
+                    .nodes
+                    .push((message_id, message_node.build()));
+
+                let node = ctx.current_node();
+                node.set_invalid(Invalid::True);
+                node.push_child(message_id);
+                node.set_error_message(message_id);
+            }
+            ValidationState::Warning { message } => {
+                // AccessKit has no warning state, so the message is only described.
+                let node = ctx.current_node();
+                let description = match node.description() {
+                    Some(description) => format!("{description}, {message}"),
+                    None => message.to_string(),
+                };
+                node.set_description(description);
+            }
+        }
+    }
+}
+
+impl Default for ValidationAdornment {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Paint an exclamation mark in a circle of `color`, in the top-right corner.
+fn paint_icon(scene: &mut Scene, widget_size: Size, color: Color) {
+    let radius = theme::VALIDATION_ICON_SIZE / 2.0;
+    let center = Point::new(
+        widget_size.width - ICON_MARGIN - radius,
+        ICON_MARGIN + radius,
+    );
+    fill_color(scene, &Circle::new(center, radius), color);
+
+    let mark_color = theme::WINDOW_BACKGROUND_COLOR;
+    let stem = Rect::new(
+        center.x - 1.0,
+        center.y - radius * 0.6,
+        center.x + 1.0,
+        center.y + radius * 0.15,
+    );
+    fill_color(scene, &stem, mark_color);
+    let dot = Circle::new(Point::new(center.x, center.y + radius * 0.45), 1.1);
+    fill_color(scene, &dot, mark_color);
+}
@@ -34,9 +34,8 @@ pub struct Align {
 impl Align {
     /// Create widget with alignment.
     ///
-    /// Note that the `align` parameter is specified as a `UnitPoint` in
-    /// terms of left and right. This is inadequate for bidi-aware layout
-    /// and thus the API will change when Masonry gains bidi capability.
+    /// Use the `START` and `END` points of [`UnitPoint`], rather than `LEFT` and
+    /// `RIGHT`, for alignments which should be mirrored in right-to-left layout.
     pub fn new(align: UnitPoint, child: impl Widget + 'static) -> Align {
         Align {
             align,
@@ -61,6 +60,18 @@ impl Align {
         Align::new(UnitPoint::LEFT, child)
     }
 
+    /// Create widget aligned to the leading edge, i.e. the left edge in left-to-right
+    /// layout and the right edge in right-to-left layout.
+    pub fn start(child: impl Widget + 'static) -> Align {
+        Align::new(UnitPoint::START, child)
+    }
+
+    /// Create widget aligned to the trailing edge, i.e. the right edge in left-to-right
+    /// layout and the left edge in right-to-left layout.
+    pub fn end(child: impl Widget + 'static) -> Align {
+        Align::new(UnitPoint::END, child)
+    }
+
     /// Align only in the horizontal axis, keeping the child's size in the vertical.
     pub fn horizontal(align: UnitPoint, child: impl Widget + 'static) -> Align {
         Align {
@@ -126,7 +137,10 @@ impl Widget for Align {
         let extra_height = (my_size.height - size.height).max(0.);
         let origin = self
             .align
-            .resolve(Rect::new(0., 0., extra_width, extra_height))
+            .resolve_in(
+                Rect::new(0., 0., extra_width, extra_height),
+                ctx.layout_direction(),
+            )
             .expand();
         ctx.place_child(&mut self.child, origin);
 
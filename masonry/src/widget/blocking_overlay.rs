@@ -0,0 +1,274 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! A container which dims its child and blocks input to it, e.g. while loading.
+
+use accesskit::Role;
+use smallvec::{smallvec, SmallVec};
+use tracing::{trace, trace_span, Span};
+use vello::Scene;
+
+use crate::paint_scene_helpers::fill_color;
+use crate::widget::{Spinner, WidgetMut, WidgetPod, WidgetRef};
+use crate::{
+    theme, AccessCtx, AccessEvent, BoxConstraints, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx,
+    PaintCtx, Point, PointerEvent, Size, StatusChange, TextEvent, Widget,
+};
+
+const SPINNER_SIZE: f64 = 40.0;
+
+/// A container which, while active, paints a translucent scrim over its child and
+/// blocks all input to it, e.g. during a blocking operation.
+///
+/// It's usually placed around the content of the window. While active:
+/// - No pointer, keyboard or accessibility event reaches the child. In particular,
+///   unlike a modal, Escape doesn't dismiss the overlay: only the app does, with
+///   [`WidgetMut::set_active`].
+/// - The child is skipped by keyboard focus. A descendant which had focus keeps it,
+///   but gets no keyboard events until the overlay is deactivated.
+/// - A spinner is shown in the middle of the scrim, unless disabled with
+///   [`with_spinner`](Self::with_spinner).
+/// - The overlay is reported as busy to screen readers.
+pub struct BlockingOverlay<W> {
+    child: WidgetPod<W>,
+    spinner: WidgetPod<Spinner>,
+    is_active: bool,
+    show_spinner: bool,
+}
+
+impl<W: Widget> BlockingOverlay<W> {
+    /// Create a new inactive overlay around `child`.
+    pub fn new(child: W) -> Self {
+        Self::new_pod(WidgetPod::new(child))
+    }
+
+    /// Create a new inactive overlay around the child `child`.
+    pub fn new_pod(child: WidgetPod<W>) -> Self {
+        BlockingOverlay {
+            child,
+            spinner: WidgetPod::new(Spinner::new()),
+            is_active: false,
+            show_spinner: true,
+        }
+    }
+
+    /// Builder-style method to start active or inactive.
+    pub fn with_active(mut self, is_active: bool) -> Self {
+        self.is_active = is_active;
+        self
+    }
+
+    /// Builder-style method to show a spinner while active, which is the default.
+    pub fn with_spinner(mut self, show_spinner: bool) -> Self {
+        self.show_spinner = show_spinner;
+        self
+    }
+
+    /// Whether the overlay blocks input to its child.
+    pub fn is_active(&self) -> bool {
+        self.is_active
+    }
+
+    fn spinner_shown(&self) -> bool {
+        self.is_active && self.show_spinner
+    }
+}
+
+impl<W: Widget> WidgetMut<'_, BlockingOverlay<W>> {
+    /// Activate or deactivate the overlay.
+    pub fn set_active(&mut self, is_active: bool) {
+        if self.widget.is_active == is_active {
+            return;
+        }
+        trace!("BlockingOverlay active: {is_active}");
+        self.widget.is_active = is_active;
+        self.update_spinner();
+        // The child joins or leaves the focus chain.
+        self.ctx.children_changed();
+        self.ctx.request_paint();
+        self.ctx.request_accessibility_update();
+    }
+
+    /// Set whether a spinner is shown while the overlay is active.
+    pub fn set_show_spinner(&mut self, show_spinner: bool) {
+        self.widget.show_spinner = show_spinner;
+        self.update_spinner();
+        self.ctx.request_layout();
+    }
+
+    pub fn child_mut(&mut self) -> WidgetMut<'_, W> {
+        self.ctx.get_mut(&mut self.widget.child)
+    }
+
+    fn update_spinner(&mut self) {
+        let shown = self.widget.spinner_shown();
+        self.ctx.set_stashed(&mut self.widget.spinner, !shown);
+        if shown {
+            // The spinner doesn't get animation frames while stashed.
+            let mut spinner = self.ctx.get_mut(&mut self.widget.spinner);
+            spinner.ctx.request_anim_frame();
+        }
+    }
+}
+
+impl<W: Widget> Widget for BlockingOverlay<W> {
+    fn on_pointer_event(&mut self, ctx: &mut EventCtx, event: &PointerEvent) {
+        if self.is_active {
+            // The children are still visited, but return early.
+            ctx.set_handled();
+        }
+        self.child.on_pointer_event(ctx, event);
+        self.spinner.on_pointer_event(ctx, event);
+    }
+
+    fn on_text_event(&mut self, ctx: &mut EventCtx, event: &TextEvent) {
+        if self.is_active {
+            ctx.set_handled();
+        }
+        self.child.on_text_event(ctx, event);
+        self.spinner.on_text_event(ctx, event);
+    }
+
+    fn on_access_event(&mut self, ctx: &mut EventCtx, event: &AccessEvent) {
+        if self.is_active {
+            ctx.set_handled();
+        }
+        self.child.on_access_event(ctx, event);
+        self.spinner.on_access_event(ctx, event);
+    }
+
+    fn on_status_change(&mut self, _ctx: &mut LifeCycleCtx, _event: &StatusChange) {}
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle) {
+        self.child.lifecycle(ctx, event);
+        self.spinner.lifecycle(ctx, event);
+
+        match event {
+            LifeCycle::WidgetAdded => {
+                if !self.spinner_shown() {
+                    ctx.set_stashed(&mut self.spinner, true);
+                }
+            }
+            LifeCycle::BuildFocusChain => {
+                if self.is_active {
+                    ctx.widget_state.clear_focus_chain();
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints) -> Size {
+        let size = self.child.layout(ctx, bc);
+        ctx.place_child(&mut self.child, Point::ORIGIN);
+
+        if !self.spinner.state().is_stashed {
+            let spinner_bc = BoxConstraints::tight(Size::new(SPINNER_SIZE, SPINNER_SIZE));
+            let spinner_size = self.spinner.layout(ctx, &spinner_bc);
+            let origin = (size.to_vec2() - spinner_size.to_vec2()) / 2.0;
+            ctx.place_child(&mut self.spinner, origin.to_point());
+        }
+        ctx.set_paint_insets(self.child.compute_parent_paint_insets(size));
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, scene: &mut Scene) {
+        self.child.paint(ctx, scene);
+        if self.is_active {
+            fill_color(scene, &ctx.size().to_rect(), theme::SCRIM_COLOR);
+        }
+        if self.spinner.state().is_stashed {
+            ctx.skip_child(&mut self.spinner);
+        } else {
+            self.spinner.paint(ctx, scene);
+        }
+    }
+
+    fn accessibility_role(&self) -> Role {
+        Role::GenericContainer
+    }
+
+    fn accessibility(&mut self, ctx: &mut AccessCtx) {
+        if self.is_active {
+            ctx.current_node().set_busy();
+        }
+        self.child.accessibility(ctx);
+        self.spinner.accessibility(ctx);
+    }
+
+    fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
+        smallvec![self.child.as_dyn(), self.spinner.as_dyn()]
+    }
+
+    fn make_trace_span(&self) -> Span {
+        trace_span!("BlockingOverlay")
+    }
+
+    fn get_debug_text(&self) -> Option<String> {
+        self.is_active.then(|| "active".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{widget_ids, TestHarness};
+    use crate::widget::{Button, Textbox};
+    use crate::{Action, WidgetId};
+
+    fn set_active(harness: &mut TestHarness, is_active: bool) {
+        harness.edit_root_widget(|mut root| {
+            let mut overlay = root.downcast::<BlockingOverlay<Button>>();
+            overlay.set_active(is_active);
+        });
+    }
+
+    fn presses(harness: &mut TestHarness, id: WidgetId) -> usize {
+        std::iter::from_fn(|| harness.pop_action())
+            .filter(|(action, widget_id)| {
+                matches!(action, Action::ButtonPressed) && *widget_id == id
+            })
+            .count()
+    }
+
+    #[test]
+    fn active_overlay_blocks_clicks() {
+        let [button_id] = widget_ids();
+        let overlay =
+            BlockingOverlay::new_pod(WidgetPod::new_with_id(Button::new("Save"), button_id));
+        let mut harness = TestHarness::create(overlay);
+        harness.mouse_click_on(button_id);
+        assert_eq!(presses(&mut harness, button_id), 1);
+
+        set_active(&mut harness, true);
+        harness.mouse_click_on(button_id);
+        assert_eq!(presses(&mut harness, button_id), 0);
+
+        // The button can't be reached with the keyboard either.
+        harness.move_focus(true);
+        assert!(harness.focused_widget().is_none());
+
+        set_active(&mut harness, false);
+        harness.mouse_click_on(button_id);
+        assert_eq!(presses(&mut harness, button_id), 1);
+        harness.move_focus(true);
+        assert_eq!(harness.focused_widget().map(|w| w.id()), Some(button_id));
+    }
+
+    #[test]
+    fn active_overlay_blocks_keyboard() {
+        let [textbox_id] = widget_ids();
+        let overlay =
+            BlockingOverlay::new_pod(WidgetPod::new_with_id(Textbox::new(""), textbox_id));
+        let mut harness = TestHarness::create(overlay);
+        harness.mouse_click_on(textbox_id);
+        harness.edit_root_widget(|mut root| {
+            let mut overlay = root.downcast::<BlockingOverlay<Textbox>>();
+            overlay.set_active(true);
+        });
+
+        harness.keyboard_type_chars("abc");
+        let textbox = harness.get_widget(textbox_id);
+        assert_eq!(textbox.downcast::<Textbox>().unwrap().text(), "");
+    }
+}
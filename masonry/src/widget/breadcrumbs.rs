@@ -0,0 +1,481 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! A row of items with separators, collapsing the middle items when they don't fit.
+
+use std::ops::Range;
+
+use accesskit::Role;
+use smallvec::SmallVec;
+use tracing::{trace, trace_span, Span};
+use vello::Scene;
+
+use crate::kurbo::BezPath;
+use crate::paint_scene_helpers::stroke;
+use crate::widget::{WidgetMut, WidgetPod, WidgetRef};
+use crate::{
+    theme, AccessCtx, AccessEvent, Action, BoxConstraints, EventCtx, LayoutCtx, LifeCycle,
+    LifeCycleCtx, PaintCtx, Point, PointerEvent, Size, StatusChange, TextEvent, Widget,
+};
+
+/// The width of the chevron drawn between items, including its padding.
+const SEPARATOR_WIDTH: f64 = 16.0;
+const CHEVRON_HALF_HEIGHT: f64 = 4.0;
+
+/// A row of items, e.g. the segments of a file path, separated by chevrons.
+///
+/// When the items don't fit in the available width, the items in the middle are
+/// replaced by an ellipsis child: items are hidden from the middle outward (alternating
+/// between the item before and the one after the hidden ones, starting before) until
+/// the row fits, or until only the leading and trailing items are left. By default,
+/// the first and the last items always stay visible.
+///
+/// Whether items are hidden only depends on their [max intrinsic width], so a row
+/// whose width is just enough doesn't switch between the two states.
+///
+/// Clicking the ellipsis submits [`Action::BreadcrumbsEllipsisClicked`] with the
+/// indices of the hidden items, e.g. to show them in a
+/// [`ContextMenu`](super::ContextMenu).
+///
+/// [max intrinsic width]: Widget::max_intrinsic_width
+pub struct Breadcrumbs {
+    items: Vec<WidgetPod<Box<dyn Widget>>>,
+    ellipsis: WidgetPod<Box<dyn Widget>>,
+    leading_count: usize,
+    trailing_count: usize,
+    /// The indices of the items replaced by the ellipsis, as of the last layout.
+    hidden: Range<usize>,
+    /// The horizontal positions of the separators, as of the last layout.
+    separators: Vec<f64>,
+    ellipsis_pressed: bool,
+}
+
+impl Breadcrumbs {
+    /// Create an empty row, showing `ellipsis` in place of the hidden items.
+    pub fn new(ellipsis: impl Widget) -> Self {
+        Breadcrumbs {
+            items: Vec::new(),
+            ellipsis: WidgetPod::new(ellipsis).boxed(),
+            leading_count: 1,
+            trailing_count: 1,
+            hidden: 0..0,
+            separators: Vec::new(),
+            ellipsis_pressed: false,
+        }
+    }
+
+    /// Builder-style method to add an item.
+    pub fn with_child(mut self, child: impl Widget) -> Self {
+        self.items.push(WidgetPod::new(child).boxed());
+        self
+    }
+
+    /// Builder-style method to set how many items at the start and at the end of the
+    /// row are never hidden.
+    pub fn with_kept_items(mut self, leading_count: usize, trailing_count: usize) -> Self {
+        self.leading_count = leading_count;
+        self.trailing_count = trailing_count;
+        self
+    }
+
+    /// The number of items.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Whether the row has no items.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// The indices of the items replaced by the ellipsis.
+    ///
+    /// This is empty when all the items fit.
+    pub fn hidden_items(&self) -> Range<usize> {
+        self.hidden.clone()
+    }
+}
+
+/// The range of items to hide so that they fit in `available_width`, removing them
+/// from the middle outward.
+fn collapsed_range(
+    widths: &[f64],
+    ellipsis_width: f64,
+    available_width: f64,
+    leading_count: usize,
+    trailing_count: usize,
+) -> Range<usize> {
+    let row_width = |hidden: &Range<usize>| {
+        let visible_width: f64 =
+            widths[..hidden.start].iter().sum::<f64>() + widths[hidden.end..].iter().sum::<f64>();
+        let mut slots = widths.len() - hidden.len();
+        let mut width = visible_width;
+        if !hidden.is_empty() {
+            slots += 1;
+            width += ellipsis_width;
+        }
+        width + slots.saturating_sub(1) as f64 * SEPARATOR_WIDTH
+    };
+
+    let collapsible = leading_count..widths.len().saturating_sub(trailing_count);
+    if collapsible.is_empty() || row_width(&(0..0)) <= available_width {
+        return 0..0;
+    }
+    let middle = (collapsible.start + collapsible.end - 1) / 2;
+    let mut hidden = middle..middle + 1;
+    while row_width(&hidden) > available_width && hidden != collapsible {
+        let can_extend_start = hidden.start > collapsible.start;
+        let can_extend_end = hidden.end < collapsible.end;
+        // Extend on the side closest to the middle, before it on ties.
+        let prefer_start = middle + 1 - hidden.start <= hidden.end - middle;
+        if can_extend_start && (prefer_start || !can_extend_end) {
+            hidden.start -= 1;
+        } else {
+            hidden.end += 1;
+        }
+    }
+    hidden
+}
+
+impl WidgetMut<'_, Breadcrumbs> {
+    /// Add an item at the end of the row.
+    pub fn add_child(&mut self, child: impl Widget) {
+        self.widget.items.push(WidgetPod::new(child).boxed());
+        self.ctx.children_changed();
+        self.ctx.request_layout();
+    }
+
+    /// Insert an item at `index`.
+    ///
+    /// # Panics
+    ///
+    /// If `index` is greater than the number of items.
+    pub fn insert_child(&mut self, index: usize, child: impl Widget) {
+        self.widget
+            .items
+            .insert(index, WidgetPod::new(child).boxed());
+        self.ctx.children_changed();
+        self.ctx.request_layout();
+    }
+
+    /// Remove the item at `index`.
+    ///
+    /// # Panics
+    ///
+    /// If `index` is out of bounds.
+    pub fn remove_child(&mut self, index: usize) {
+        self.widget.items.remove(index);
+        self.ctx.children_changed();
+        self.ctx.request_layout();
+    }
+
+    /// Get a [`WidgetMut`] to the item at `index`, if any.
+    pub fn child_mut(&mut self, index: usize) -> Option<WidgetMut<'_, Box<dyn Widget>>> {
+        let child = self.widget.items.get_mut(index)?;
+        Some(self.ctx.get_mut(child))
+    }
+
+    /// Get a [`WidgetMut`] to the ellipsis.
+    pub fn ellipsis_mut(&mut self) -> WidgetMut<'_, Box<dyn Widget>> {
+        self.ctx.get_mut(&mut self.widget.ellipsis)
+    }
+
+    /// Set how many items at the start and at the end of the row are never hidden.
+    pub fn set_kept_items(&mut self, leading_count: usize, trailing_count: usize) {
+        self.widget.leading_count = leading_count;
+        self.widget.trailing_count = trailing_count;
+        self.ctx.request_layout();
+    }
+}
+
+impl Widget for Breadcrumbs {
+    fn on_pointer_event(&mut self, ctx: &mut EventCtx, event: &PointerEvent) {
+        for (index, item) in self.items.iter_mut().enumerate() {
+            if self.hidden.contains(&index) {
+                ctx.skip_child(item);
+            } else {
+                item.on_pointer_event(ctx, event);
+            }
+        }
+        if self.hidden.is_empty() {
+            ctx.skip_child(&mut self.ellipsis);
+            return;
+        }
+
+        self.ellipsis.on_pointer_event(ctx, event);
+        match event {
+            PointerEvent::PointerDown(_, _) => {
+                if self.ellipsis.is_hot() && !ctx.is_disabled() {
+                    self.ellipsis_pressed = true;
+                    ctx.set_active(true);
+                }
+            }
+            PointerEvent::PointerUp(_, _) => {
+                if self.ellipsis_pressed && self.ellipsis.is_hot() {
+                    trace!("Breadcrumbs {:?} ellipsis clicked", ctx.widget_id());
+                    ctx.submit_action(Action::BreadcrumbsEllipsisClicked(
+                        self.hidden.clone().collect(),
+                    ));
+                }
+                self.ellipsis_pressed = false;
+                ctx.set_active(false);
+            }
+            _ => (),
+        }
+    }
+
+    fn on_text_event(&mut self, ctx: &mut EventCtx, event: &TextEvent) {
+        for (index, item) in self.items.iter_mut().enumerate() {
+            if self.hidden.contains(&index) {
+                ctx.skip_child(item);
+            } else {
+                item.on_text_event(ctx, event);
+            }
+        }
+        if self.hidden.is_empty() {
+            ctx.skip_child(&mut self.ellipsis);
+        } else {
+            self.ellipsis.on_text_event(ctx, event);
+        }
+    }
+
+    fn on_access_event(&mut self, ctx: &mut EventCtx, event: &AccessEvent) {
+        for (index, item) in self.items.iter_mut().enumerate() {
+            if self.hidden.contains(&index) {
+                ctx.skip_child(item);
+            } else {
+                item.on_access_event(ctx, event);
+            }
+        }
+        if self.hidden.is_empty() {
+            ctx.skip_child(&mut self.ellipsis);
+        } else {
+            self.ellipsis.on_access_event(ctx, event);
+        }
+    }
+
+    fn on_status_change(&mut self, _ctx: &mut LifeCycleCtx, _event: &StatusChange) {}
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle) {
+        for item in &mut self.items {
+            item.lifecycle(ctx, event);
+        }
+        self.ellipsis.lifecycle(ctx, event);
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints) -> Size {
+        let max_height = bc.max().height;
+        let widths: Vec<f64> = self
+            .items
+            .iter_mut()
+            .map(|item| item.max_intrinsic_width(ctx, max_height))
+            .collect();
+        let ellipsis_width = self.ellipsis.max_intrinsic_width(ctx, max_height);
+        self.hidden = collapsed_range(
+            &widths,
+            ellipsis_width,
+            bc.max().width,
+            self.leading_count,
+            self.trailing_count,
+        );
+
+        // Hidden children are laid out with a zero size, so that they can't be hot.
+        let hidden_bc = BoxConstraints::tight(Size::ZERO);
+        let child_bc = |width: f64| BoxConstraints::new(Size::ZERO, Size::new(width, max_height));
+        let mut sizes = Vec::with_capacity(widths.len());
+        for (index, item) in self.items.iter_mut().enumerate() {
+            if self.hidden.contains(&index) {
+                item.layout(ctx, &hidden_bc);
+                ctx.place_child(item, Point::ORIGIN);
+                sizes.push(Size::ZERO);
+            } else {
+                sizes.push(item.layout(ctx, &child_bc(widths[index])));
+            }
+        }
+        let ellipsis_size = if self.hidden.is_empty() {
+            self.ellipsis.layout(ctx, &hidden_bc);
+            ctx.place_child(&mut self.ellipsis, Point::ORIGIN);
+            Size::ZERO
+        } else {
+            self.ellipsis.layout(ctx, &child_bc(ellipsis_width))
+        };
+
+        let height = sizes
+            .iter()
+            .chain([&ellipsis_size])
+            .map(|size| size.height)
+            .fold(0.0, f64::max);
+        let height = bc.constrain(Size::new(0., height)).height;
+
+        self.separators.clear();
+        let mut x = 0.0;
+        for (index, item) in self.items.iter_mut().enumerate() {
+            if self.hidden.contains(&index) {
+                if index == self.hidden.start {
+                    if x > 0.0 {
+                        self.separators.push(x);
+                        x += SEPARATOR_WIDTH;
+                    }
+                    let y = (height - ellipsis_size.height) / 2.;
+                    ctx.place_child(&mut self.ellipsis, Point::new(x, y));
+                    x += ellipsis_size.width;
+                }
+                continue;
+            }
+            if index > 0 {
+                self.separators.push(x);
+                x += SEPARATOR_WIDTH;
+            }
+            let y = (height - sizes[index].height) / 2.;
+            ctx.place_child(item, Point::new(x, y));
+            x += sizes[index].width;
+        }
+
+        let size = bc.constrain(Size::new(x, height));
+        trace!("Computed layout: size={}, hidden={:?}", size, self.hidden);
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, scene: &mut Scene) {
+        let center_y = ctx.size().height / 2.;
+        for &x in &self.separators {
+            let tip_x = x + SEPARATOR_WIDTH / 2. + CHEVRON_HALF_HEIGHT / 2.;
+            let mut chevron = BezPath::new();
+            chevron.move_to((tip_x - CHEVRON_HALF_HEIGHT, center_y - CHEVRON_HALF_HEIGHT));
+            chevron.line_to((tip_x, center_y));
+            chevron.line_to((tip_x - CHEVRON_HALF_HEIGHT, center_y + CHEVRON_HALF_HEIGHT));
+            stroke(scene, &chevron, theme::DISABLED_TEXT_COLOR, 1.5);
+        }
+
+        for (index, item) in self.items.iter_mut().enumerate() {
+            if self.hidden.contains(&index) {
+                ctx.skip_child(item);
+            } else {
+                item.paint(ctx, scene);
+            }
+        }
+        if self.hidden.is_empty() {
+            ctx.skip_child(&mut self.ellipsis);
+        } else {
+            self.ellipsis.paint(ctx, scene);
+        }
+    }
+
+    fn accessibility_role(&self) -> Role {
+        Role::Navigation
+    }
+
+    fn accessibility(&mut self, ctx: &mut AccessCtx) {
+        for item in &mut self.items {
+            item.accessibility(ctx);
+        }
+        self.ellipsis.accessibility(ctx);
+    }
+
+    fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
+        self.items
+            .iter()
+            .chain([&self.ellipsis])
+            .map(|child| child.as_dyn())
+            .collect()
+    }
+
+    fn make_trace_span(&self) -> Span {
+        trace_span!("Breadcrumbs")
+    }
+
+    fn get_debug_text(&self) -> Option<String> {
+        (!self.hidden.is_empty()).then(|| format!("hidden {:?}", self.hidden))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use insta::assert_debug_snapshot;
+
+    use super::*;
+    use crate::testing::{widget_ids, TestHarness, TestWidgetExt as _};
+    use crate::widget::SizedBox;
+    use crate::WidgetId;
+
+    const ITEM_WIDTH: f64 = 50.;
+    const ELLIPSIS_WIDTH: f64 = 20.;
+
+    fn breadcrumbs(ellipsis_id: WidgetId) -> Breadcrumbs {
+        let ellipsis = SizedBox::empty()
+            .width(ELLIPSIS_WIDTH)
+            .height(20.)
+            .with_id(ellipsis_id);
+        (0..5).fold(Breadcrumbs::new(ellipsis), |breadcrumbs, _| {
+            breadcrumbs.with_child(SizedBox::empty().width(ITEM_WIDTH).height(20.))
+        })
+    }
+
+    fn hidden_at_width(width: f64) -> Range<usize> {
+        let [ellipsis_id] = widget_ids();
+        let harness =
+            TestHarness::create_with_size(breadcrumbs(ellipsis_id), Size::new(width, 40.));
+        harness
+            .root_widget()
+            .downcast::<Breadcrumbs>()
+            .unwrap()
+            .hidden_items()
+    }
+
+    #[test]
+    fn hides_middle_items_outward() {
+        // 5 items and 4 separators
+        let full_width = 5. * ITEM_WIDTH + 4. * SEPARATOR_WIDTH;
+        assert_eq!(hidden_at_width(full_width + 10.), 0..0);
+        // Marginal: exactly enough room
+        assert_eq!(hidden_at_width(full_width), 0..0);
+        assert_eq!(hidden_at_width(full_width - 1.), 2..3);
+        // 4 items, the ellipsis and 4 separators
+        let one_hidden_width = 4. * ITEM_WIDTH + ELLIPSIS_WIDTH + 4. * SEPARATOR_WIDTH;
+        assert_eq!(hidden_at_width(one_hidden_width), 2..3);
+        assert_eq!(hidden_at_width(one_hidden_width - 1.), 1..3);
+        assert_eq!(hidden_at_width(200.), 1..4);
+        // The first and last items are kept even if they don't fit.
+        assert_eq!(hidden_at_width(100.), 1..4);
+    }
+
+    #[test]
+    fn kept_items() {
+        let widths = [ITEM_WIDTH; 5];
+        assert_eq!(collapsed_range(&widths, ELLIPSIS_WIDTH, 0., 2, 1), 2..4);
+        assert_eq!(collapsed_range(&widths, ELLIPSIS_WIDTH, 0., 0, 0), 0..5);
+        assert_eq!(collapsed_range(&widths, ELLIPSIS_WIDTH, 0., 3, 2), 0..0);
+        assert_eq!(collapsed_range(&[], ELLIPSIS_WIDTH, 0., 1, 1), 0..0);
+    }
+
+    #[test]
+    fn ellipsis_only_when_collapsed() {
+        let [ellipsis_id] = widget_ids();
+        let harness = TestHarness::create_with_size(breadcrumbs(ellipsis_id), Size::new(400., 40.));
+        let ellipsis_rect = harness.get_widget(ellipsis_id).state().window_layout_rect();
+        assert_eq!(ellipsis_rect.size(), Size::ZERO);
+
+        let harness = TestHarness::create_with_size(breadcrumbs(ellipsis_id), Size::new(200., 40.));
+        let ellipsis_rect = harness.get_widget(ellipsis_id).state().window_layout_rect();
+        assert_eq!(ellipsis_rect.size(), Size::new(ELLIPSIS_WIDTH, 20.));
+        assert_eq!(ellipsis_rect.x0, ITEM_WIDTH + SEPARATOR_WIDTH);
+    }
+
+    #[test]
+    fn click_ellipsis() {
+        let [ellipsis_id] = widget_ids();
+        let mut harness =
+            TestHarness::create_with_size(breadcrumbs(ellipsis_id), Size::new(250., 40.));
+        assert_debug_snapshot!(harness.root_widget());
+
+        harness.mouse_click_on(ellipsis_id);
+        let breadcrumbs_id = harness.root_widget().id();
+        assert_eq!(
+            harness.pop_action(),
+            Some((
+                Action::BreadcrumbsEllipsisClicked(vec![1, 2]),
+                breadcrumbs_id
+            ))
+        );
+    }
+}
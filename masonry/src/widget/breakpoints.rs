@@ -0,0 +1,188 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! A widget that reports when its available width crosses a breakpoint.
+
+use accesskit::Role;
+use smallvec::{smallvec, SmallVec};
+use tracing::{trace, trace_span, Span};
+use vello::Scene;
+
+use crate::widget::{WidgetMut, WidgetPod, WidgetRef};
+use crate::{
+    AccessCtx, AccessEvent, Action, BoxConstraints, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx,
+    PaintCtx, Point, PointerEvent, Size, StatusChange, TextEvent, Widget,
+};
+
+/// A widget that submits [`Action::BreakpointChanged`] when the width available to its
+/// child crosses one of its breakpoints, e.g. to switch to a narrow layout.
+///
+/// The available width is the maximum width of the constraints the widget is laid out
+/// with. The first action is submitted on the first layout. Afterwards, resizing only
+/// submits an action when a breakpoint is crossed, not for every change of size.
+pub struct Breakpoints<W> {
+    child: WidgetPod<W>,
+    breakpoints: Vec<f64>,
+    /// The number of breakpoints the available width reached, as of the last layout.
+    range: Option<usize>,
+}
+
+impl<W: Widget> Breakpoints<W> {
+    /// Create a new widget with `child`, with the given breakpoint widths.
+    pub fn new(child: W, breakpoints: impl Into<Vec<f64>>) -> Self {
+        Self::new_pod(WidgetPod::new(child), breakpoints)
+    }
+
+    /// Create a new widget with the child `child`, with the given breakpoint widths.
+    pub fn new_pod(child: WidgetPod<W>, breakpoints: impl Into<Vec<f64>>) -> Self {
+        Breakpoints {
+            child,
+            breakpoints: sorted(breakpoints.into()),
+            range: None,
+        }
+    }
+
+    /// The number of breakpoints the available width reached, as of the last layout.
+    ///
+    /// Returns `None` before the widget has been laid out.
+    pub fn range(&self) -> Option<usize> {
+        self.range
+    }
+}
+
+impl<W: Widget> WidgetMut<'_, Breakpoints<W>> {
+    /// Set the breakpoint widths.
+    ///
+    /// An action is submitted on the next layout if the available width is now between
+    /// different breakpoints.
+    pub fn set_breakpoints(&mut self, breakpoints: impl Into<Vec<f64>>) {
+        self.widget.breakpoints = sorted(breakpoints.into());
+        self.ctx.request_layout();
+    }
+
+    pub fn child_mut(&mut self) -> WidgetMut<'_, W> {
+        self.ctx.get_mut(&mut self.widget.child)
+    }
+}
+
+fn sorted(mut breakpoints: Vec<f64>) -> Vec<f64> {
+    breakpoints.sort_by(f64::total_cmp);
+    breakpoints
+}
+
+impl<W: Widget> Widget for Breakpoints<W> {
+    fn on_pointer_event(&mut self, ctx: &mut EventCtx, event: &PointerEvent) {
+        self.child.on_pointer_event(ctx, event);
+    }
+
+    fn on_text_event(&mut self, ctx: &mut EventCtx, event: &TextEvent) {
+        self.child.on_text_event(ctx, event);
+    }
+
+    fn on_access_event(&mut self, ctx: &mut EventCtx, event: &AccessEvent) {
+        self.child.on_access_event(ctx, event);
+    }
+
+    fn on_status_change(&mut self, _ctx: &mut LifeCycleCtx, _event: &StatusChange) {}
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle) {
+        self.child.lifecycle(ctx, event);
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints) -> Size {
+        let available = bc.max();
+        let range = self
+            .breakpoints
+            .iter()
+            .filter(|breakpoint| available.width >= **breakpoint)
+            .count();
+        if self.range != Some(range) {
+            trace!("Crossed a breakpoint: width={}", available.width);
+            self.range = Some(range);
+            ctx.submit_action(Action::BreakpointChanged(available));
+        }
+
+        let size = self.child.layout(ctx, bc);
+        ctx.place_child(&mut self.child, Point::ORIGIN);
+        ctx.set_paint_insets(self.child.compute_parent_paint_insets(size));
+        ctx.set_baseline_offset(self.child.baseline_offset());
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, scene: &mut Scene) {
+        self.child.paint(ctx, scene);
+    }
+
+    fn accessibility_role(&self) -> Role {
+        Role::GenericContainer
+    }
+
+    fn accessibility(&mut self, ctx: &mut AccessCtx) {
+        self.child.accessibility(ctx);
+    }
+
+    fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
+        smallvec![self.child.as_dyn()]
+    }
+
+    fn make_trace_span(&self) -> Span {
+        trace_span!("Breakpoints")
+    }
+
+    fn get_debug_text(&self) -> Option<String> {
+        self.range.map(|range| format!("range {range}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{widget_ids, TestHarness};
+    use crate::widget::SizedBox;
+    use crate::WidgetId;
+
+    fn breakpoint_actions(harness: &mut TestHarness, id: WidgetId) -> Vec<Size> {
+        let mut actions = vec![];
+        while let Some((action, widget_id)) = harness.pop_action() {
+            if let (Action::BreakpointChanged(size), true) = (action, widget_id == id) {
+                actions.push(size);
+            }
+        }
+        actions
+    }
+
+    #[test]
+    fn only_crossing_submits_action() {
+        let [breakpoints_id] = widget_ids();
+        let widget = Breakpoints::new(SizedBox::empty(), [300., 600.]);
+        let mut harness = TestHarness::create_with_size(
+            SizedBox::new_with_id(widget, breakpoints_id).expand(),
+            Size::new(400., 200.),
+        );
+        assert_eq!(
+            breakpoint_actions(&mut harness, breakpoints_id),
+            [Size::new(400., 200.)]
+        );
+
+        harness.resize_window(Size::new(500., 200.));
+        assert_eq!(breakpoint_actions(&mut harness, breakpoints_id), []);
+
+        harness.resize_window(Size::new(700., 250.));
+        assert_eq!(
+            breakpoint_actions(&mut harness, breakpoints_id),
+            [Size::new(700., 250.)]
+        );
+
+        harness.resize_window(Size::new(200., 250.));
+        assert_eq!(
+            breakpoint_actions(&mut harness, breakpoints_id),
+            [Size::new(200., 250.)]
+        );
+        let range = harness
+            .get_widget(breakpoints_id)
+            .downcast::<Breakpoints<SizedBox>>()
+            .unwrap()
+            .range();
+        assert_eq!(range, Some(0));
+    }
+}
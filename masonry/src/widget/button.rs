@@ -9,12 +9,14 @@ use tracing::{trace, trace_span, Span};
 use vello::Scene;
 
 use crate::action::Action;
-use crate::paint_scene_helpers::{fill_lin_gradient, stroke, UnitPoint};
+use crate::paint_scene_helpers::{fill_color, fill_lin_gradient, stroke, UnitPoint};
 use crate::text2::TextStorage;
+use crate::widget::keyboard_activation::KeyboardActivation;
 use crate::widget::{Label, WidgetMut, WidgetPod, WidgetRef};
 use crate::{
-    theme, AccessCtx, AccessEvent, ArcStr, BoxConstraints, EventCtx, Insets, LayoutCtx, LifeCycle,
-    LifeCycleCtx, PaintCtx, PointerEvent, Size, StatusChange, TextEvent, Widget,
+    theme, AccessCtx, AccessEvent, ArcStr, BoxConstraints, ButtonVariant, Color, EventCtx, Insets,
+    LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx, PointerEvent, Size, StatusChange, TextEvent,
+    Theme, Widget,
 };
 
 // the minimum padding added to a button.
@@ -25,8 +27,15 @@ const LABEL_INSETS: Insets = Insets::uniform_xy(8., 2.);
 /// A button with a text label.
 ///
 /// Emits [`Action::ButtonPressed`] when pressed.
+///
+/// When focused, the button can be pressed with Enter, or by holding and releasing Space.
+///
+/// A button with a [`ButtonVariant`] is painted with the colors the [`Theme`] defines for
+/// it, and is restyled when the theme changes.
 pub struct Button {
     label: WidgetPod<Label>,
+    keyboard: KeyboardActivation,
+    variant: Option<ButtonVariant>,
 }
 
 impl Button {
@@ -57,6 +66,35 @@ impl Button {
     pub fn from_label(label: Label) -> Button {
         Button {
             label: WidgetPod::new(label),
+            keyboard: KeyboardActivation::default(),
+            variant: None,
+        }
+    }
+
+    /// Builder-style method for setting the [`ButtonVariant`].
+    pub fn with_variant(mut self, variant: ButtonVariant) -> Self {
+        self.variant = Some(variant);
+        self
+    }
+
+    /// The color of the label for the given state, if the button has a variant.
+    fn text_color(
+        &self,
+        theme: &Theme,
+        hovered: bool,
+        pressed: bool,
+        disabled: bool,
+    ) -> Option<Color> {
+        let style = theme.button_style(self.variant.as_ref()?);
+        Some(style.text.get(hovered, pressed, disabled))
+    }
+
+    fn update_text_color(&self, ctx: &mut LifeCycleCtx) {
+        let pressed = ctx.is_active() || self.keyboard.is_pressed();
+        let color = self.text_color(ctx.theme(), ctx.is_hot(), pressed, ctx.is_disabled());
+        // Without a variant, keep the text style set on the button, if any.
+        if color.is_some() {
+            ctx.set_text_color(color);
         }
     }
 }
@@ -70,6 +108,22 @@ impl WidgetMut<'_, Button> {
     pub fn label_mut(&mut self) -> WidgetMut<'_, Label> {
         self.ctx.get_mut(&mut self.widget.label)
     }
+
+    /// Set the [`ButtonVariant`], or go back to the default look with `None`.
+    pub fn set_variant(&mut self, variant: Option<ButtonVariant>) {
+        if self.widget.variant == variant {
+            return;
+        }
+        self.widget.variant = variant;
+        let color = self.widget.text_color(
+            self.ctx.theme(),
+            self.ctx.is_hot(),
+            self.ctx.is_active(),
+            self.ctx.is_disabled(),
+        );
+        self.ctx.set_text_color(color);
+        self.ctx.request_paint();
+    }
 }
 
 impl Widget for Button {
@@ -97,10 +151,24 @@ impl Widget for Button {
             }
             _ => (),
         }
+        let pressed = ctx.is_active() || self.keyboard.is_pressed();
+        let color = self.text_color(ctx.theme(), ctx.is_hot(), pressed, ctx.is_disabled());
+        if color.is_some() {
+            ctx.set_text_color(color);
+        }
         self.label.on_pointer_event(ctx, event);
     }
 
     fn on_text_event(&mut self, ctx: &mut EventCtx, event: &TextEvent) {
+        if self.keyboard.on_text_event(ctx, event) {
+            ctx.submit_action(Action::ButtonPressed);
+            trace!("Button {:?} activated with keyboard", ctx.widget_id());
+        }
+        let pressed = ctx.is_active() || self.keyboard.is_pressed();
+        let color = self.text_color(ctx.theme(), ctx.is_hot(), pressed, ctx.is_disabled());
+        if color.is_some() {
+            ctx.set_text_color(color);
+        }
         self.label.on_text_event(ctx, event);
     }
 
@@ -117,11 +185,24 @@ impl Widget for Button {
         self.label.on_access_event(ctx, event);
     }
 
-    fn on_status_change(&mut self, ctx: &mut LifeCycleCtx, _event: &StatusChange) {
+    fn on_status_change(&mut self, ctx: &mut LifeCycleCtx, event: &StatusChange) {
+        if let StatusChange::FocusChanged(false) = event {
+            self.keyboard.cancel();
+        }
+        self.update_text_color(ctx);
         ctx.request_paint();
     }
 
     fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle) {
+        match event {
+            LifeCycle::BuildFocusChain => ctx.register_for_focus(),
+            LifeCycle::WidgetAdded | LifeCycle::DisabledChanged(_) => self.update_text_color(ctx),
+            LifeCycle::ThemeChanged => {
+                self.update_text_color(ctx);
+                ctx.request_paint();
+            }
+            _ => {}
+        }
         self.label.lifecycle(ctx, event);
     }
 
@@ -151,7 +232,7 @@ impl Widget for Button {
     }
 
     fn paint(&mut self, ctx: &mut PaintCtx, scene: &mut Scene) {
-        let is_active = ctx.is_active() && !ctx.is_disabled();
+        let is_active = (ctx.is_active() || self.keyboard.is_pressed()) && !ctx.is_disabled();
         let is_hot = ctx.is_hot();
         let size = ctx.size();
         let stroke_width = theme::BUTTON_BORDER_WIDTH;
@@ -161,6 +242,17 @@ impl Widget for Button {
             .inset(-stroke_width / 2.0)
             .to_rounded_rect(theme::BUTTON_BORDER_RADIUS);
 
+        if let Some(variant) = &self.variant {
+            let style = ctx.theme().button_style(variant);
+            let disabled = ctx.is_disabled();
+            let background = style.background.get(is_hot, is_active, disabled);
+            let border_color = style.border.get(is_hot, is_active, disabled);
+            fill_color(scene, &rounded_rect, background);
+            stroke(scene, &rounded_rect, border_color, stroke_width);
+            self.label.paint(ctx, scene);
+            return;
+        }
+
         let bg_gradient = if ctx.is_disabled() {
             [theme::DISABLED_BUTTON_LIGHT, theme::DISABLED_BUTTON_DARK]
         } else if is_active {
@@ -216,12 +308,16 @@ impl Widget for Button {
 
 #[cfg(test)]
 mod tests {
+    use image::Rgba;
     use insta::assert_debug_snapshot;
+    use winit::event::ElementState;
+    use winit::keyboard::{Key, NamedKey};
 
     use super::*;
     use crate::assert_render_snapshot;
-    use crate::testing::{widget_ids, TestHarness, TestWidgetExt};
+    use crate::testing::{render_tests_skipped, widget_ids, TestHarness, TestWidgetExt};
     use crate::theme::PRIMARY_LIGHT;
+    use crate::{ButtonStyle, StateColors, WidgetId};
 
     #[test]
     fn simple_button() {
@@ -242,6 +338,47 @@ mod tests {
         );
     }
 
+    #[test]
+    fn space_held_paints_pressed() {
+        if render_tests_skipped() {
+            return;
+        }
+        let mut harness = TestHarness::create(Button::new("Hello"));
+        let released = harness.render();
+
+        // Key events can't be built outside of winit, so Space is pressed on the
+        // activation state directly, like the text event handler does.
+        let set_space = |harness: &mut TestHarness, state| {
+            harness.edit_root_widget(|mut button| {
+                let mut button = button.downcast::<Button>();
+                button
+                    .widget
+                    .keyboard
+                    .on_key(&Key::Named(NamedKey::Space), state, false);
+                button.ctx.request_paint();
+            });
+        };
+        set_space(&mut harness, ElementState::Pressed);
+        assert_render_snapshot!(harness, "space_held");
+        assert!(harness.render() != released);
+
+        set_space(&mut harness, ElementState::Released);
+        assert!(harness.render() == released);
+    }
+
+    #[test]
+    fn access_invoke_presses_button() {
+        let [button_id] = widget_ids();
+        let widget = Button::new("Hello").with_id(button_id);
+
+        let mut harness = TestHarness::create(widget);
+        harness.access_action(button_id, accesskit::Action::Default);
+        assert_eq!(
+            harness.pop_action(),
+            Some((Action::ButtonPressed, button_id))
+        );
+    }
+
     #[test]
     fn edit_button() {
         let image_1 = {
@@ -277,4 +414,125 @@ mod tests {
         // We don't use assert_eq because we don't want rich assert
         assert!(image_1 == image_2);
     }
+
+    /// The color of the button's background, away from its border and its label.
+    fn background(harness: &mut TestHarness) -> Color {
+        let Rgba([r, g, b, a]) = *harness.render().get_pixel(6, 20);
+        Color::rgba8(r, g, b, a)
+    }
+
+    fn label_color(harness: &TestHarness, button_id: WidgetId) -> Color {
+        let button = harness.get_widget(button_id);
+        button.children()[0].state().resolved_text_style.color
+    }
+
+    fn variant_harness(variant: ButtonVariant) -> (TestHarness, WidgetId) {
+        let [button_id] = widget_ids();
+        let button = Button::new("Hello")
+            .with_variant(variant)
+            .with_id(button_id);
+        let harness = TestHarness::create_with_size(button, Size::new(100.0, 40.0));
+        (harness, button_id)
+    }
+
+    fn set_disabled(harness: &mut TestHarness, disabled: bool) {
+        harness.edit_root_widget(|mut button| button.ctx.set_disabled(disabled));
+    }
+
+    #[test]
+    fn builtin_variants() {
+        if render_tests_skipped() {
+            return;
+        }
+        let default_theme = Theme::default();
+        for variant in [
+            ButtonVariant::Primary,
+            ButtonVariant::Secondary,
+            ButtonVariant::Danger,
+            ButtonVariant::Quiet,
+        ] {
+            let style = default_theme.button_style(&variant).clone();
+            let (mut harness, button_id) = variant_harness(variant.clone());
+            let normal = harness.render();
+            assert_eq!(label_color(&harness, button_id), style.text.normal);
+            assert_render_snapshot!(harness, &format!("variant_{}_normal", variant.name()));
+
+            harness.mouse_move_to(button_id);
+            let hovered = harness.render();
+            assert!(hovered != normal, "{variant:?} doesn't change on hover");
+            assert_eq!(label_color(&harness, button_id), style.text.hovered);
+            assert_render_snapshot!(harness, &format!("variant_{}_hovered", variant.name()));
+
+            harness.mouse_move((500.0, 500.0));
+            set_disabled(&mut harness, true);
+            let disabled = harness.render();
+            assert!(
+                disabled != normal,
+                "{variant:?} doesn't change when disabled"
+            );
+            assert_render_snapshot!(harness, &format!("variant_{}_disabled", variant.name()));
+
+            set_disabled(&mut harness, false);
+            assert!(harness.render() == normal);
+        }
+
+        // The filled variants paint their background as is.
+        let (mut harness, button_id) = variant_harness(ButtonVariant::Danger);
+        assert_eq!(background(&mut harness), theme::BADGE_COLOR);
+        harness.mouse_move_to(button_id);
+        let style = Theme::default()
+            .button_style(&ButtonVariant::Danger)
+            .clone();
+        assert_eq!(background(&mut harness), style.background.hovered);
+    }
+
+    #[test]
+    fn custom_variant() {
+        if render_tests_skipped() {
+            return;
+        }
+        let ghost = ButtonStyle {
+            background: StateColors::uniform(Color::rgb8(0x12, 0x34, 0x56)),
+            border: StateColors::uniform(Color::WHITE),
+            text: StateColors::uniform(Color::rgb8(0xff, 0xee, 0x00)),
+        };
+        let mut ghost_theme = Theme::default();
+        ghost_theme.define_button_variant("ghost", ghost);
+
+        let (mut harness, button_id) = variant_harness(ButtonVariant::Named("ghost".into()));
+        harness.set_theme(ghost_theme);
+        assert_eq!(background(&mut harness), Color::rgb8(0x12, 0x34, 0x56));
+        assert_eq!(
+            label_color(&harness, button_id),
+            Color::rgb8(0xff, 0xee, 0x00)
+        );
+    }
+
+    #[test]
+    fn theme_switch_restyles_button() {
+        if render_tests_skipped() {
+            return;
+        }
+        let (mut harness, button_id) = variant_harness(ButtonVariant::Primary);
+        assert_eq!(background(&mut harness), theme::PRIMARY_DARK);
+
+        let green = Color::rgb8(0x20, 0xa0, 0x40);
+        let green_theme = Theme::default().with_button_variant(
+            ButtonVariant::Primary.name(),
+            ButtonStyle {
+                background: StateColors::uniform(green),
+                border: StateColors::uniform(green),
+                text: StateColors::uniform(Color::BLACK),
+            },
+        );
+        harness.set_theme(green_theme);
+
+        // The same widget is restyled.
+        assert!(harness.try_get_widget(button_id).is_some());
+        assert_eq!(background(&mut harness), green);
+        assert_eq!(label_color(&harness, button_id), Color::BLACK);
+
+        harness.set_theme(Theme::default());
+        assert_eq!(background(&mut harness), theme::PRIMARY_DARK);
+    }
 }
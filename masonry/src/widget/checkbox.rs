@@ -13,40 +13,106 @@ use crate::action::Action;
 use crate::kurbo::{BezPath, Cap, Join, Size};
 use crate::paint_scene_helpers::{fill_lin_gradient, stroke, UnitPoint};
 use crate::text2::TextStorage;
+use crate::widget::keyboard_activation::KeyboardActivation;
 use crate::widget::{Label, WidgetMut, WidgetRef};
 use crate::{
     theme, AccessCtx, AccessEvent, ArcStr, BoxConstraints, EventCtx, LayoutCtx, LifeCycle,
     LifeCycleCtx, PaintCtx, PointerEvent, StatusChange, TextEvent, Widget, WidgetPod,
 };
 
+/// The state of a [`Checkbox`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CheckState {
+    #[default]
+    Unchecked,
+    Checked,
+    /// Neither checked nor unchecked, e.g. for a checkbox summarizing a group of
+    /// checkboxes which aren't all in the same state.
+    ///
+    /// Users can't make a checkbox indeterminate: toggling it checks it.
+    Indeterminate,
+}
+
+impl CheckState {
+    /// Whether this is [`CheckState::Checked`].
+    pub fn is_checked(self) -> bool {
+        self == CheckState::Checked
+    }
+
+    /// The state a user toggle leads to.
+    fn toggled(self) -> CheckState {
+        match self {
+            CheckState::Checked => CheckState::Unchecked,
+            CheckState::Unchecked | CheckState::Indeterminate => CheckState::Checked,
+        }
+    }
+}
+
+impl From<bool> for CheckState {
+    fn from(checked: bool) -> Self {
+        if checked {
+            CheckState::Checked
+        } else {
+            CheckState::Unchecked
+        }
+    }
+}
+
 /// A checkbox that can be toggled.
+///
+/// The checkbox can also be [indeterminate](CheckState::Indeterminate). Toggling it then
+/// checks it, and submits [`Action::CheckboxChecked(true)`](Action::CheckboxChecked).
+///
+/// When focused, the checkbox can be toggled with Enter, or by holding and releasing Space.
 pub struct Checkbox {
-    checked: bool,
+    state: CheckState,
     label: WidgetPod<Label>,
+    keyboard: KeyboardActivation,
 }
 
 impl Checkbox {
     /// Create a new `Checkbox` with a text label.
-    pub fn new(checked: bool, text: impl Into<ArcStr>) -> Checkbox {
+    ///
+    /// `state` is either a `bool` or a [`CheckState`].
+    pub fn new(state: impl Into<CheckState>, text: impl Into<ArcStr>) -> Checkbox {
         Checkbox {
-            checked,
+            state: state.into(),
             label: WidgetPod::new(Label::new(text)),
+            keyboard: KeyboardActivation::default(),
         }
     }
 
     /// Create a new `Checkbox` with the given label.
-    pub fn from_label(checked: bool, label: Label) -> Checkbox {
+    pub fn from_label(state: impl Into<CheckState>, label: Label) -> Checkbox {
         Checkbox {
-            checked,
+            state: state.into(),
             label: WidgetPod::new(label),
+            keyboard: KeyboardActivation::default(),
         }
     }
+
+    /// The current state of the checkbox.
+    pub fn check_state(&self) -> CheckState {
+        self.state
+    }
+
+    fn toggle(&mut self, ctx: &mut EventCtx) {
+        self.state = self.state.toggled();
+        ctx.submit_action(Action::CheckboxChecked(self.state.is_checked()));
+        ctx.request_accessibility_update();
+    }
 }
 
 impl WidgetMut<'_, Checkbox> {
     pub fn set_checked(&mut self, checked: bool) {
-        self.widget.checked = checked;
+        self.set_check_state(checked.into());
+    }
+
+    /// Set the state, including [`CheckState::Indeterminate`].
+    pub fn set_check_state(&mut self, state: CheckState) {
+        self.widget.state = state;
         self.ctx.request_paint();
+        self.ctx.request_accessibility_update();
     }
 
     /// Set the text.
@@ -74,8 +140,7 @@ impl Widget for Checkbox {
             PointerEvent::PointerUp(_, _) => {
                 if ctx.is_active() && !ctx.is_disabled() {
                     if ctx.is_hot() {
-                        self.checked = !self.checked;
-                        ctx.submit_action(Action::CheckboxChecked(self.checked));
+                        self.toggle(ctx);
                         trace!("Checkbox {:?} released", ctx.widget_id());
                     }
                     ctx.request_paint();
@@ -87,16 +152,19 @@ impl Widget for Checkbox {
         self.label.on_pointer_event(ctx, event);
     }
 
-    fn on_text_event(&mut self, _ctx: &mut EventCtx, _event: &TextEvent) {
-        self.label.on_text_event(_ctx, _event);
+    fn on_text_event(&mut self, ctx: &mut EventCtx, event: &TextEvent) {
+        if self.keyboard.on_text_event(ctx, event) {
+            self.toggle(ctx);
+            trace!("Checkbox {:?} activated with keyboard", ctx.widget_id());
+        }
+        self.label.on_text_event(ctx, event);
     }
 
     fn on_access_event(&mut self, ctx: &mut EventCtx, event: &AccessEvent) {
         if event.target == ctx.widget_id() {
             match event.action {
                 accesskit::Action::Default => {
-                    self.checked = !self.checked;
-                    ctx.submit_action(Action::CheckboxChecked(self.checked));
+                    self.toggle(ctx);
                     ctx.request_paint();
                 }
                 _ => {}
@@ -105,11 +173,17 @@ impl Widget for Checkbox {
         self.label.on_access_event(ctx, event);
     }
 
-    fn on_status_change(&mut self, ctx: &mut LifeCycleCtx, _event: &StatusChange) {
+    fn on_status_change(&mut self, ctx: &mut LifeCycleCtx, event: &StatusChange) {
+        if let StatusChange::FocusChanged(false) = event {
+            self.keyboard.cancel();
+        }
         ctx.request_paint();
     }
 
     fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle) {
+        if let LifeCycle::BuildFocusChain = event {
+            ctx.register_for_focus();
+        }
         self.label.lifecycle(ctx, event);
     }
 
@@ -140,13 +214,14 @@ impl Widget for Checkbox {
             .inset(-border_width / 2.)
             .to_rounded_rect(2.);
 
-        fill_lin_gradient(
-            scene,
-            &rect,
-            [theme::BACKGROUND_LIGHT, theme::BACKGROUND_DARK],
-            UnitPoint::TOP,
-            UnitPoint::BOTTOM,
-        );
+        let is_pressed = (ctx.is_active() || self.keyboard.is_pressed()) && !ctx.is_disabled();
+        let bg_gradient = if is_pressed {
+            [theme::BACKGROUND_DARK, theme::BACKGROUND_LIGHT]
+        } else {
+            [theme::BACKGROUND_LIGHT, theme::BACKGROUND_DARK]
+        };
+
+        fill_lin_gradient(scene, &rect, bg_gradient, UnitPoint::TOP, UnitPoint::BOTTOM);
 
         let border_color = if ctx.is_hot() && !ctx.is_disabled() {
             theme::BORDER_LIGHT
@@ -156,13 +231,25 @@ impl Widget for Checkbox {
 
         stroke(scene, &rect, border_color, border_width);
 
-        if self.checked {
-            // Paint the checkmark
-            let mut path = BezPath::new();
-            path.move_to((4.0, 9.0));
-            path.line_to((8.0, 13.0));
-            path.line_to((14.0, 5.0));
-
+        let path = match self.state {
+            CheckState::Checked => {
+                // Paint the checkmark
+                let mut path = BezPath::new();
+                path.move_to((4.0, 9.0));
+                path.line_to((8.0, 13.0));
+                path.line_to((14.0, 5.0));
+                Some(path)
+            }
+            CheckState::Indeterminate => {
+                // Paint a dash
+                let mut path = BezPath::new();
+                path.move_to((5.0, 9.0));
+                path.line_to((13.0, 9.0));
+                Some(path)
+            }
+            CheckState::Unchecked => None,
+        };
+        if let Some(path) = path {
             let style = Stroke {
                 width: 2.0,
                 join: Join::Round,
@@ -194,14 +281,22 @@ impl Widget for Checkbox {
         let _name = self.label.widget().text().as_str().to_string();
         // We may want to add a name if it doesn't interfere with the child label
         // ctx.current_node().set_name(name);
-        if self.checked {
-            ctx.current_node().set_toggled(Toggled::True);
-            ctx.current_node()
-                .set_default_action_verb(DefaultActionVerb::Uncheck);
-        } else {
-            ctx.current_node().set_toggled(Toggled::False);
-            ctx.current_node()
-                .set_default_action_verb(DefaultActionVerb::Check);
+        match self.state {
+            CheckState::Checked => {
+                ctx.current_node().set_toggled(Toggled::True);
+                ctx.current_node()
+                    .set_default_action_verb(DefaultActionVerb::Uncheck);
+            }
+            CheckState::Unchecked => {
+                ctx.current_node().set_toggled(Toggled::False);
+                ctx.current_node()
+                    .set_default_action_verb(DefaultActionVerb::Check);
+            }
+            CheckState::Indeterminate => {
+                ctx.current_node().set_toggled(Toggled::Mixed);
+                ctx.current_node()
+                    .set_default_action_verb(DefaultActionVerb::Check);
+            }
         }
 
         self.label.accessibility(ctx);
@@ -218,7 +313,11 @@ impl Widget for Checkbox {
     fn get_debug_text(&self) -> Option<String> {
         Some(format!(
             "[{}] {}",
-            if self.checked { "X" } else { " " },
+            match self.state {
+                CheckState::Checked => "X",
+                CheckState::Unchecked => " ",
+                CheckState::Indeterminate => "-",
+            },
             self.label.as_ref().text().as_str()
         ))
     }
@@ -261,6 +360,23 @@ mod tests {
         );
     }
 
+    #[test]
+    fn indeterminate_checkbox_checks_on_click() {
+        let [checkbox_id] = widget_ids();
+        let widget = Checkbox::new(CheckState::Indeterminate, "Hello").with_id(checkbox_id);
+
+        let mut harness = TestHarness::create(widget);
+
+        harness.mouse_click_on(checkbox_id);
+        assert_eq!(
+            harness.pop_action(),
+            Some((Action::CheckboxChecked(true), checkbox_id))
+        );
+        let checkbox = harness.get_widget(checkbox_id);
+        let checkbox = checkbox.downcast::<Checkbox>().unwrap();
+        assert_eq!(checkbox.check_state(), CheckState::Checked);
+    }
+
     #[test]
     fn edit_checkbox() {
         let image_1 = {
@@ -0,0 +1,345 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! A container which can collapse its child.
+
+use std::time::Duration;
+
+use accesskit::Role;
+use smallvec::{smallvec, SmallVec};
+use tracing::{trace, trace_span, Span};
+use vello::peniko::BlendMode;
+use vello::Scene;
+
+use crate::kurbo::Affine;
+use crate::widget::{WidgetMut, WidgetPod, WidgetRef};
+use crate::{
+    AccessCtx, AccessEvent, BoxConstraints, Easing, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx,
+    PaintCtx, Point, PointerEvent, Size, StatusChange, TextEvent, Widget,
+};
+
+const DEFAULT_DURATION: Duration = Duration::from_millis(200);
+
+/// A container which shows its child, or hides it by animating its height to zero.
+///
+/// The child is laid out with an unbounded height, so its natural height is measured
+/// again on every layout: the container expands back to it even if the content changed
+/// while collapsed. Content overflowing the container while it animates is clipped.
+///
+/// Once fully collapsed, the child is stashed: it isn't laid out, painted, or given
+/// pointer events.
+pub struct Collapsible<W> {
+    child: WidgetPod<W>,
+    collapsed: bool,
+    /// How far the animation is from collapsed (0) to expanded (1), linear in time.
+    progress: f64,
+    duration: Duration,
+    easing: Easing,
+    /// The size of the child at its last layout.
+    child_size: Size,
+}
+
+impl<W: Widget> Collapsible<W> {
+    /// Create a new expanded container around `child`.
+    pub fn new(child: W) -> Self {
+        Self::new_pod(WidgetPod::new(child))
+    }
+
+    /// Create a new expanded container around the child `child`.
+    pub fn new_pod(child: WidgetPod<W>) -> Self {
+        Collapsible {
+            child,
+            collapsed: false,
+            progress: 1.0,
+            duration: DEFAULT_DURATION,
+            easing: Easing::default(),
+            child_size: Size::ZERO,
+        }
+    }
+
+    /// Builder-style method to start collapsed or expanded, without animating.
+    pub fn collapsed(mut self, collapsed: bool) -> Self {
+        self.collapsed = collapsed;
+        self.progress = if collapsed { 0.0 } else { 1.0 };
+        self
+    }
+
+    /// Builder-style method to set the easing curve of the height animation.
+    pub fn with_easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    /// Builder-style method to set how long collapsing or expanding takes.
+    pub fn with_duration(mut self, duration: Duration) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    /// Whether the container is collapsed, or collapsing.
+    pub fn is_collapsed(&self) -> bool {
+        self.collapsed
+    }
+
+    fn target(&self) -> f64 {
+        if self.collapsed {
+            0.0
+        } else {
+            1.0
+        }
+    }
+}
+
+impl<W: Widget> WidgetMut<'_, Collapsible<W>> {
+    /// Collapse or expand the container.
+    ///
+    /// If `animate` is false, or if the user asked for reduced motion, the container
+    /// jumps to its new height. Otherwise, an animation in progress is reversed from
+    /// where it is.
+    pub fn set_collapsed(&mut self, collapsed: bool, animate: bool) {
+        if self.widget.collapsed == collapsed {
+            return;
+        }
+        self.widget.collapsed = collapsed;
+        if !collapsed {
+            self.ctx.set_stashed(&mut self.widget.child, false);
+        }
+        if animate && !self.ctx.reduced_motion() && !self.widget.duration.is_zero() {
+            self.ctx.request_anim_frame();
+        } else {
+            self.widget.progress = self.widget.target();
+            if collapsed {
+                self.ctx.set_stashed(&mut self.widget.child, true);
+            }
+        }
+        self.ctx.request_layout();
+    }
+
+    /// Set the easing curve of the height animation.
+    pub fn set_easing(&mut self, easing: Easing) {
+        self.widget.easing = easing;
+        self.ctx.request_layout();
+    }
+
+    /// Set how long collapsing or expanding takes.
+    pub fn set_duration(&mut self, duration: Duration) {
+        self.widget.duration = duration;
+    }
+
+    pub fn child_mut(&mut self) -> WidgetMut<'_, W> {
+        self.ctx.get_mut(&mut self.widget.child)
+    }
+}
+
+impl<W: Widget> Widget for Collapsible<W> {
+    fn on_pointer_event(&mut self, ctx: &mut EventCtx, event: &PointerEvent) {
+        self.child.on_pointer_event(ctx, event);
+    }
+
+    fn on_text_event(&mut self, ctx: &mut EventCtx, event: &TextEvent) {
+        self.child.on_text_event(ctx, event);
+    }
+
+    fn on_access_event(&mut self, ctx: &mut EventCtx, event: &AccessEvent) {
+        self.child.on_access_event(ctx, event);
+    }
+
+    fn on_status_change(&mut self, _ctx: &mut LifeCycleCtx, _event: &StatusChange) {}
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle) {
+        self.child.lifecycle(ctx, event);
+
+        match event {
+            LifeCycle::WidgetAdded => {
+                if self.progress == 0.0 {
+                    ctx.set_stashed(&mut self.child, true);
+                }
+            }
+            LifeCycle::AnimFrame(interval) => {
+                let target = self.target();
+                if self.progress == target {
+                    return;
+                }
+                if ctx.reduced_motion() || self.duration.is_zero() {
+                    self.progress = target;
+                } else {
+                    let step = (*interval as f64) * 1e-9 / self.duration.as_secs_f64();
+                    self.progress = if target > self.progress {
+                        (self.progress + step).min(target)
+                    } else {
+                        (self.progress - step).max(target)
+                    };
+                }
+                if self.progress != target {
+                    ctx.request_anim_frame();
+                } else if self.collapsed {
+                    ctx.set_stashed(&mut self.child, true);
+                }
+                ctx.request_layout();
+            }
+            _ => (),
+        }
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints) -> Size {
+        if !self.child.state().is_stashed {
+            let child_bc = BoxConstraints::new(
+                Size::new(bc.min().width, 0.0),
+                Size::new(bc.max().width, f64::INFINITY),
+            );
+            self.child_size = self.child.layout(ctx, &child_bc);
+            ctx.place_child(&mut self.child, Point::ORIGIN);
+        }
+
+        let height = self.child_size.height * self.easing.apply(self.progress);
+        let size = bc.constrain(Size::new(self.child_size.width, height));
+        trace!("Computed layout: size={}, progress={}", size, self.progress);
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, scene: &mut Scene) {
+        if self.child.state().is_stashed {
+            ctx.skip_child(&mut self.child);
+            return;
+        }
+        let clip = ctx.size().to_rect();
+        scene.push_layer(BlendMode::default(), 1., Affine::IDENTITY, &clip);
+        self.child.paint(ctx, scene);
+        scene.pop_layer();
+    }
+
+    fn accessibility_role(&self) -> Role {
+        Role::GenericContainer
+    }
+
+    fn accessibility(&mut self, ctx: &mut AccessCtx) {
+        ctx.current_node().set_expanded(!self.collapsed);
+        self.child.accessibility(ctx);
+    }
+
+    fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
+        smallvec![self.child.as_dyn()]
+    }
+
+    fn make_trace_span(&self) -> Span {
+        trace_span!("Collapsible")
+    }
+
+    fn get_debug_text(&self) -> Option<String> {
+        Some(
+            if self.collapsed {
+                "collapsed"
+            } else {
+                "expanded"
+            }
+            .to_string(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use winit::event::MouseButton;
+
+    use super::*;
+    use crate::testing::{widget_ids, TestHarness};
+    use crate::widget::{Button, Flex, SizedBox};
+    use crate::WidgetId;
+
+    const HEIGHT: f64 = 80.0;
+
+    fn harness(id: WidgetId, child: impl Widget) -> TestHarness {
+        let collapsible = Collapsible::new(child)
+            .with_easing(Easing::EaseOut)
+            .with_duration(Duration::from_millis(200));
+        TestHarness::create(Flex::column().with_child_id(collapsible, id))
+    }
+
+    fn set_collapsed<W: Widget>(harness: &mut TestHarness, collapsed: bool, animate: bool) {
+        harness.edit_root_widget(|mut root| {
+            let mut flex = root.downcast::<Flex>();
+            let mut child = flex.child_mut(0).unwrap();
+            child
+                .downcast::<Collapsible<W>>()
+                .set_collapsed(collapsed, animate);
+        });
+    }
+
+    fn height(harness: &TestHarness, id: WidgetId) -> f64 {
+        harness.get_widget(id).state().layout_rect().height()
+    }
+
+    #[test]
+    fn height_follows_easing() {
+        let [collapsible_id] = widget_ids();
+        let mut harness = harness(collapsible_id, SizedBox::empty().height(HEIGHT));
+        assert_eq!(height(&harness, collapsible_id), HEIGHT);
+
+        set_collapsed::<SizedBox>(&mut harness, true, true);
+        assert_eq!(height(&harness, collapsible_id), HEIGHT);
+
+        harness.animate(Duration::from_millis(100));
+        let expected = HEIGHT * Easing::EaseOut.apply(0.5);
+        assert!((height(&harness, collapsible_id) - expected).abs() < 1e-9);
+
+        harness.animate(Duration::from_millis(100));
+        assert_eq!(height(&harness, collapsible_id), 0.0);
+
+        set_collapsed::<SizedBox>(&mut harness, false, true);
+        harness.animate(Duration::from_millis(150));
+        harness.animate(Duration::from_millis(150));
+        assert_eq!(height(&harness, collapsible_id), HEIGHT);
+    }
+
+    #[test]
+    fn collapsed_child_gets_no_pointer_events() {
+        let [collapsible_id, button_id] = widget_ids();
+        let button = WidgetPod::new_with_id(Button::new("Hidden"), button_id);
+        let collapsible = Collapsible::new_pod(button);
+        let mut harness =
+            TestHarness::create(Flex::column().with_child_id(collapsible, collapsible_id));
+        let button_center = harness
+            .get_widget(button_id)
+            .state()
+            .window_layout_rect()
+            .center();
+
+        set_collapsed::<Button>(&mut harness, true, true);
+        harness.animate(Duration::from_secs(1));
+        assert_eq!(height(&harness, collapsible_id), 0.0);
+        assert!(harness.get_widget(button_id).state().is_stashed);
+
+        harness.mouse_move(button_center);
+        harness.mouse_button_press(MouseButton::Left);
+        harness.mouse_button_release(MouseButton::Left);
+        assert_eq!(harness.pop_action(), None);
+    }
+
+    #[test]
+    fn reduced_motion_jumps_to_end() {
+        let [collapsible_id] = widget_ids();
+        let mut harness = harness(collapsible_id, SizedBox::empty().height(HEIGHT));
+        harness.set_reduced_motion(true);
+
+        set_collapsed::<SizedBox>(&mut harness, true, true);
+        assert_eq!(height(&harness, collapsible_id), 0.0);
+
+        set_collapsed::<SizedBox>(&mut harness, false, true);
+        assert_eq!(height(&harness, collapsible_id), HEIGHT);
+    }
+
+    #[test]
+    fn starts_collapsed() {
+        let [collapsible_id, box_id] = widget_ids();
+        let child = WidgetPod::new_with_id(SizedBox::empty().height(HEIGHT), box_id);
+        let collapsible = Collapsible::new_pod(child).collapsed(true);
+        let mut harness =
+            TestHarness::create(Flex::column().with_child_id(collapsible, collapsible_id));
+        assert_eq!(height(&harness, collapsible_id), 0.0);
+        assert!(harness.get_widget(box_id).state().is_stashed);
+
+        set_collapsed::<SizedBox>(&mut harness, false, false);
+        assert_eq!(height(&harness, collapsible_id), HEIGHT);
+        assert!(!harness.get_widget(box_id).state().is_stashed);
+    }
+}
@@ -0,0 +1,737 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! A text box with a dropdown of options, filtered as the user types.
+
+use std::ops::Range;
+
+use accesskit::Role;
+use smallvec::{smallvec, SmallVec};
+use tracing::{trace, trace_span, Span};
+use vello::Scene;
+use winit::keyboard::{Key, NamedKey};
+
+use crate::paint_scene_helpers::{fill_color, stroke};
+use crate::text2::TextLayout;
+use crate::widget::{Textbox, WidgetMut, WidgetRef};
+use crate::{
+    theme, AccessCtx, AccessEvent, Action, ArcStr, BoxConstraints, EventCtx, Insets, LayoutCtx,
+    LifeCycle, LifeCycleCtx, PaintCtx, Point, PointerEvent, Rect, Size, StatusChange, TextEvent,
+    ValidationAdornment, ValidationState, Vec2, Widget, WidgetPod,
+};
+
+const DROPDOWN_PADDING: f64 = 4.0;
+const OPTION_PADDING: f64 = 8.0;
+const OPTION_HEIGHT: f64 = theme::BORDERED_WIDGET_HEIGHT;
+
+struct ComboOption {
+    label: TextLayout<ArcStr>,
+    /// The part of the label matching the text typed by the user, shown in bold.
+    matched: Option<Range<usize>>,
+}
+
+/// A text box with a dropdown of options, which are filtered as the user types.
+///
+/// The dropdown opens when the text changes, or when the down arrow is pressed. It only
+/// shows the options containing the text (ignoring ASCII case), with the matching part
+/// in bold, and highlights the first of them. The arrow keys move the highlight through
+/// the filtered options.
+///
+/// Pressing Enter commits the highlighted option, or the text itself if no option is
+/// highlighted, e.g. because none matches: the user can enter a value which isn't one of
+/// the options. Clicking an option commits it. Either way, [`Action::ComboBoxCommitted`]
+/// is submitted with the value, and the dropdown closes. Escape closes the dropdown
+/// without committing anything.
+///
+/// The dropdown is drawn below the text box, and may extend past the bounds of this
+/// widget: the widget stays active while the dropdown is open, so that it receives all
+/// pointer events.
+///
+/// Its [`ValidationState`] is shown around the text box, with a [`ValidationAdornment`].
+pub struct EditableComboBox {
+    textbox: WidgetPod<Textbox>,
+    options: Vec<ComboOption>,
+    /// The indices of the options matching the text, in order.
+    filtered: Vec<usize>,
+    /// The index in `filtered` of the highlighted option.
+    highlighted: Option<usize>,
+    /// The text the options were last filtered with.
+    filter_text: String,
+    is_open: bool,
+    dropdown_rect: Rect,
+    pressed_row: Option<usize>,
+    no_match_label: TextLayout<ArcStr>,
+    validation: ValidationAdornment,
+}
+
+impl EditableComboBox {
+    /// Create a new combo box with the given text, and no options.
+    pub fn new(initial_text: impl Into<String>) -> Self {
+        let initial_text = initial_text.into();
+        let mut no_match_label =
+            TextLayout::new(ArcStr::from("No matches"), theme::TEXT_SIZE_NORMAL as f32);
+        no_match_label.set_brush(theme::DISABLED_TEXT_COLOR);
+        EditableComboBox {
+            textbox: WidgetPod::new(Textbox::new(initial_text.clone())),
+            options: Vec::new(),
+            filtered: Vec::new(),
+            highlighted: None,
+            filter_text: initial_text,
+            is_open: false,
+            dropdown_rect: Rect::ZERO,
+            pressed_row: None,
+            no_match_label,
+            validation: ValidationAdornment::new(),
+        }
+    }
+
+    /// Builder-style method to add an option.
+    pub fn with_option(mut self, label: impl Into<ArcStr>) -> Self {
+        self.options.push(new_option(label.into()));
+        self
+    }
+
+    /// Builder-style method for setting the [`ValidationState`].
+    pub fn with_validation_state(mut self, state: ValidationState) -> Self {
+        self.validation.set_state(state);
+        self
+    }
+
+    /// The current text.
+    pub fn text(&self) -> &str {
+        self.textbox.widget().text()
+    }
+
+    pub fn validation_state(&self) -> &ValidationState {
+        self.validation.state()
+    }
+
+    /// Whether the dropdown is open.
+    pub fn is_open(&self) -> bool {
+        self.is_open
+    }
+
+    /// The options shown in the dropdown, i.e. the ones matching the text.
+    pub fn filtered_options(&self) -> impl Iterator<Item = &ArcStr> {
+        self.filtered
+            .iter()
+            .map(|&index| self.options[index].label.text())
+    }
+
+    /// The option which is committed if Enter is pressed.
+    pub fn highlighted_option(&self) -> Option<&ArcStr> {
+        let index = self.filtered[self.highlighted?];
+        Some(self.options[index].label.text())
+    }
+
+    /// Filter the options with the current text, and highlight the first match.
+    fn update_filter(&mut self) {
+        self.filter_text = self.text().to_string();
+        self.filtered.clear();
+        for (index, option) in self.options.iter_mut().enumerate() {
+            let matched = find_match(option.label.text(), &self.filter_text);
+            if matched != option.matched {
+                option.matched = matched.clone();
+                option.label.invalidate();
+            }
+            if matched.is_some() {
+                self.filtered.push(index);
+            }
+        }
+        self.highlighted = if self.filtered.is_empty() || self.filter_text.is_empty() {
+            None
+        } else {
+            Some(0)
+        };
+        self.pressed_row = None;
+    }
+
+    /// Move the highlight to the next (or previous) filtered option, wrapping around.
+    fn move_highlight(&mut self, forward: bool) {
+        let len = self.filtered.len();
+        if len == 0 {
+            return;
+        }
+        self.highlighted = Some(match (self.highlighted, forward) {
+            (None, true) => 0,
+            (None, false) => len - 1,
+            (Some(index), true) => (index + 1) % len,
+            (Some(index), false) => (index + len - 1) % len,
+        });
+    }
+
+    /// The value to commit: the highlighted option if there's one, the text otherwise.
+    fn value_to_commit(&self) -> String {
+        match self.highlighted_option() {
+            Some(option) => option.to_string(),
+            None => self.text().to_string(),
+        }
+    }
+
+    fn open(&mut self, ctx: &mut EventCtx) {
+        trace!("EditableComboBox {:?} opened", ctx.widget_id());
+        self.is_open = true;
+        ctx.set_active(true);
+        ctx.request_layout();
+        ctx.request_accessibility_update();
+    }
+
+    fn close(&mut self, ctx: &mut EventCtx) {
+        trace!("EditableComboBox {:?} closed", ctx.widget_id());
+        self.is_open = false;
+        self.pressed_row = None;
+        ctx.set_active(false);
+        ctx.request_layout();
+        ctx.request_accessibility_update();
+    }
+
+    fn commit(&mut self, ctx: &mut EventCtx) {
+        let value = self.value_to_commit();
+        if value != self.text() {
+            ctx.get_mut(&mut self.textbox).replace_text(value.clone());
+        }
+        self.filter_text.clone_from(&value);
+        trace!(
+            "EditableComboBox {:?} committed {:?}",
+            ctx.widget_id(),
+            value
+        );
+        ctx.submit_action(Action::ComboBoxCommitted(value));
+        self.close(ctx);
+    }
+
+    /// Handle a key press, returning whether it was consumed.
+    fn on_key(&mut self, ctx: &mut EventCtx, key: &Key) -> bool {
+        match key {
+            Key::Named(NamedKey::ArrowDown | NamedKey::ArrowUp) => {
+                if self.is_open {
+                    self.move_highlight(key == &Key::Named(NamedKey::ArrowDown));
+                    ctx.request_paint();
+                    ctx.request_accessibility_update();
+                } else {
+                    self.update_filter();
+                    self.open(ctx);
+                }
+                true
+            }
+            Key::Named(NamedKey::Enter) => {
+                self.commit(ctx);
+                true
+            }
+            Key::Named(NamedKey::Escape) if self.is_open => {
+                self.close(ctx);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn row_at(&self, pos: Point) -> Option<usize> {
+        if !self.dropdown_rect.contains(pos) {
+            return None;
+        }
+        let offset = pos.y - self.dropdown_rect.y0 - DROPDOWN_PADDING;
+        if offset < 0.0 {
+            return None;
+        }
+        let row = (offset / OPTION_HEIGHT) as usize;
+        (row < self.filtered.len()).then_some(row)
+    }
+
+    fn row_rect(&self, row: usize) -> Rect {
+        Rect::from_origin_size(
+            self.dropdown_rect.origin()
+                + Vec2::new(
+                    DROPDOWN_PADDING,
+                    DROPDOWN_PADDING + row as f64 * OPTION_HEIGHT,
+                ),
+            Size::new(
+                self.dropdown_rect.width() - 2. * DROPDOWN_PADDING,
+                OPTION_HEIGHT,
+            ),
+        )
+    }
+
+    fn on_open_dropdown_pointer_event(
+        &mut self,
+        ctx: &mut EventCtx,
+        pos: Point,
+        event: &PointerEvent,
+    ) {
+        match event {
+            PointerEvent::PointerDown(_, _) => {
+                self.pressed_row = self.row_at(pos);
+                if self.pressed_row.is_none() {
+                    self.close(ctx);
+                }
+            }
+            PointerEvent::PointerUp(_, _) => {
+                if let Some(row) = self.pressed_row.take() {
+                    if self.row_at(pos) == Some(row) {
+                        self.highlighted = Some(row);
+                        self.commit(ctx);
+                    }
+                }
+            }
+            PointerEvent::PointerMove(_) => {
+                if let Some(row) = self.row_at(pos) {
+                    if self.highlighted != Some(row) {
+                        self.highlighted = Some(row);
+                        ctx.request_paint();
+                    }
+                }
+            }
+            _ => {}
+        }
+        ctx.set_handled();
+    }
+}
+
+fn new_option(label: ArcStr) -> ComboOption {
+    ComboOption {
+        label: TextLayout::new(label, theme::TEXT_SIZE_NORMAL as f32),
+        matched: Some(0..0),
+    }
+}
+
+/// The first range of `option` which is equal to `text`, ignoring ASCII case.
+fn find_match(option: &str, text: &str) -> Option<Range<usize>> {
+    if text.is_empty() {
+        return Some(0..0);
+    }
+    option
+        .char_indices()
+        .map(|(start, _)| start)
+        .find(|&start| {
+            option[start..]
+                .get(..text.len())
+                .is_some_and(|candidate| candidate.eq_ignore_ascii_case(text))
+        })
+        .map(|start| start..start + text.len())
+}
+
+impl WidgetMut<'_, EditableComboBox> {
+    /// Get a [`WidgetMut`] to the text box.
+    pub fn textbox_mut(&mut self) -> WidgetMut<'_, Textbox> {
+        self.ctx.get_mut(&mut self.widget.textbox)
+    }
+
+    /// Replace the options.
+    pub fn set_options(&mut self, labels: impl IntoIterator<Item = impl Into<ArcStr>>) {
+        self.widget.options = labels
+            .into_iter()
+            .map(|label| new_option(label.into()))
+            .collect();
+        self.widget.update_filter();
+        self.ctx.request_layout();
+        self.ctx.request_accessibility_update();
+    }
+
+    /// Highlight the next filtered option, wrapping around.
+    ///
+    /// This is what the down arrow does while the dropdown is open.
+    pub fn highlight_next(&mut self) {
+        self.widget.move_highlight(true);
+        self.ctx.request_paint();
+        self.ctx.request_accessibility_update();
+    }
+
+    /// Highlight the previous filtered option, wrapping around.
+    ///
+    /// This is what the up arrow does while the dropdown is open.
+    pub fn highlight_previous(&mut self) {
+        self.widget.move_highlight(false);
+        self.ctx.request_paint();
+        self.ctx.request_accessibility_update();
+    }
+
+    /// Commit the highlighted option, or the text if no option is highlighted, as if
+    /// Enter was pressed.
+    pub fn commit(&mut self) {
+        let value = self.widget.value_to_commit();
+        if value != self.widget.text() {
+            self.textbox_mut().replace_text(value.clone());
+        }
+        self.widget.filter_text.clone_from(&value);
+        self.widget.is_open = false;
+        self.widget.pressed_row = None;
+        self.ctx.submit_action(Action::ComboBoxCommitted(value));
+        self.ctx.request_layout();
+        self.ctx.request_accessibility_update();
+    }
+
+    /// Set the [`ValidationState`].
+    ///
+    /// This only repaints the combo box, it doesn't change its layout.
+    pub fn set_validation_state(&mut self, state: ValidationState) {
+        if self.widget.validation.set_state(state) {
+            self.ctx.request_paint();
+            self.ctx.request_accessibility_update();
+        }
+    }
+}
+
+impl Widget for EditableComboBox {
+    fn on_pointer_event(&mut self, ctx: &mut EventCtx, event: &PointerEvent) {
+        let pos = event.local_position(ctx);
+
+        if self.is_open && self.dropdown_rect.contains(pos) {
+            ctx.skip_child(&mut self.textbox);
+            self.on_open_dropdown_pointer_event(ctx, pos, event);
+            return;
+        }
+        if self.is_open {
+            if let PointerEvent::PointerDown(_, _) = event {
+                if !self.textbox.layout_rect().contains(pos) {
+                    self.close(ctx);
+                }
+            }
+        } else if ctx.is_active() {
+            // The dropdown was closed through a `WidgetMut`.
+            ctx.set_active(false);
+        }
+        self.textbox.on_pointer_event(ctx, event);
+    }
+
+    fn on_text_event(&mut self, ctx: &mut EventCtx, event: &TextEvent) {
+        if let TextEvent::KeyboardKey(key_event, _) = event {
+            if key_event.state.is_pressed() && self.on_key(ctx, &key_event.logical_key) {
+                ctx.set_handled();
+                return;
+            }
+        }
+        self.textbox.on_text_event(ctx, event);
+
+        if self.text() != self.filter_text {
+            self.update_filter();
+            if self.is_open {
+                ctx.request_layout();
+                ctx.request_accessibility_update();
+            } else {
+                self.open(ctx);
+            }
+        }
+    }
+
+    fn on_access_event(&mut self, ctx: &mut EventCtx, event: &AccessEvent) {
+        self.textbox.on_access_event(ctx, event);
+    }
+
+    fn on_status_change(&mut self, ctx: &mut LifeCycleCtx, _event: &StatusChange) {
+        if *self.validation.state() != ValidationState::Valid {
+            // The color of the validation state depends on the hovered and focused states.
+            ctx.request_paint();
+        }
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle) {
+        self.textbox.lifecycle(ctx, event);
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints) -> Size {
+        let size = self.textbox.layout(ctx, bc);
+        ctx.place_child(&mut self.textbox, Point::ORIGIN);
+
+        if !self.is_open {
+            self.dropdown_rect = Rect::ZERO;
+            ctx.set_paint_insets(Insets::ZERO);
+            return size;
+        }
+
+        let mut label_width: f64 = 0.0;
+        for &index in &self.filtered {
+            let option = &mut self.options[index];
+            let matched = option.matched.clone().filter(|range| !range.is_empty());
+            option
+                .label
+                .rebuild_with_attributes(ctx.font_ctx(), |mut builder| {
+                    if let Some(range) = matched {
+                        builder.push(
+                            &parley::style::StyleProperty::FontWeight(
+                                parley::fontique::Weight::BOLD,
+                            ),
+                            range,
+                        );
+                    }
+                    builder
+                });
+            label_width = label_width.max(option.label.size().width);
+        }
+        let rows = if self.filtered.is_empty() {
+            self.no_match_label.rebuild(ctx.font_ctx());
+            label_width = self.no_match_label.size().width;
+            1
+        } else {
+            self.filtered.len()
+        };
+
+        let dropdown_size = Size::new(
+            (label_width + 2. * (OPTION_PADDING + DROPDOWN_PADDING)).max(size.width),
+            rows as f64 * OPTION_HEIGHT + 2. * DROPDOWN_PADDING,
+        );
+        self.dropdown_rect = Rect::from_origin_size(Point::new(0., size.height), dropdown_size);
+        ctx.set_paint_insets(Insets::new(
+            0.,
+            0.,
+            (dropdown_size.width - size.width).max(0.),
+            dropdown_size.height,
+        ));
+
+        trace!(
+            "Computed layout: size={}, dropdown={}",
+            size,
+            self.dropdown_rect
+        );
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, scene: &mut Scene) {
+        self.textbox.paint(ctx, scene);
+        let textbox_outline = self.textbox.layout_rect().inset(1.0);
+        self.validation.paint(ctx, scene, &textbox_outline, 1.0);
+
+        if !self.is_open {
+            return;
+        }
+        let dropdown = self
+            .dropdown_rect
+            .to_rounded_rect(theme::BUTTON_BORDER_RADIUS);
+        fill_color(scene, &dropdown, theme::BACKGROUND_LIGHT);
+        stroke(scene, &dropdown, theme::BORDER_LIGHT, 1.0);
+
+        if self.filtered.is_empty() {
+            let row = self.row_rect(0);
+            let label_height = self.no_match_label.size().height;
+            let origin =
+                row.origin() + Vec2::new(OPTION_PADDING, (OPTION_HEIGHT - label_height) / 2.);
+            self.no_match_label.draw(scene, origin);
+            return;
+        }
+        if let Some(row) = self.highlighted {
+            fill_color(scene, &self.row_rect(row), theme::PRIMARY_DARK);
+        }
+        for row in 0..self.filtered.len() {
+            let row_origin = self.row_rect(row).origin();
+            let label = &mut self.options[self.filtered[row]].label;
+            let label_height = label.size().height;
+            label.draw(
+                scene,
+                row_origin + Vec2::new(OPTION_PADDING, (OPTION_HEIGHT - label_height) / 2.),
+            );
+        }
+    }
+
+    fn accessibility_role(&self) -> Role {
+        Role::ComboBox
+    }
+
+    fn accessibility(&mut self, ctx: &mut AccessCtx) {
+        self.textbox.accessibility(ctx);
+        ctx.current_node().set_expanded(self.is_open);
+        if let Some(option) = self.highlighted_option() {
+            ctx.current_node().set_value(option.to_string());
+        }
+        self.validation.accessibility(ctx);
+    }
+
+    fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
+        smallvec![self.textbox.as_dyn()]
+    }
+
+    fn make_trace_span(&self) -> Span {
+        trace_span!("EditableComboBox")
+    }
+
+    fn get_debug_text(&self) -> Option<String> {
+        Some(self.text().to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use winit::event::MouseButton;
+
+    use super::*;
+    use crate::assert_render_snapshot;
+    use crate::testing::{widget_ids, TestHarness};
+    use crate::widget::Flex;
+    use crate::WidgetId;
+
+    fn fruits() -> EditableComboBox {
+        EditableComboBox::new("")
+            .with_option("Apple")
+            .with_option("Banana")
+            .with_option("Blueberry")
+            .with_option("Cherry")
+    }
+
+    fn combo_box(harness: &TestHarness, id: WidgetId) -> &EditableComboBox {
+        harness
+            .get_widget(id)
+            .downcast::<EditableComboBox>()
+            .unwrap()
+            .deref()
+    }
+
+    fn filtered_options(harness: &TestHarness, id: WidgetId) -> Vec<String> {
+        combo_box(harness, id)
+            .filtered_options()
+            .map(|option| option.to_string())
+            .collect()
+    }
+
+    fn committed_values(harness: &mut TestHarness) -> Vec<String> {
+        std::iter::from_fn(|| harness.pop_action())
+            .filter_map(|(action, _)| match action {
+                Action::ComboBoxCommitted(value) => Some(value),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn edit_combo_box(harness: &mut TestHarness, f: impl FnOnce(WidgetMut<'_, EditableComboBox>)) {
+        harness.edit_root_widget(|mut root| {
+            let mut flex = root.downcast::<Flex>();
+            let mut child = flex.child_mut(0).unwrap();
+            f(child.downcast::<EditableComboBox>());
+        });
+    }
+
+    /// Create a harness with the combo box, and focus its text box.
+    fn harness_with_focus(combo_id: WidgetId) -> TestHarness {
+        let widget = Flex::column().with_child_id(fruits(), combo_id);
+        let mut harness = TestHarness::create_with_size(widget, Size::new(300., 300.));
+        let textbox_id = harness.get_widget(combo_id).children()[0].id();
+        harness.mouse_click_on(textbox_id);
+        harness
+    }
+
+    #[test]
+    fn type_prefix_and_select() {
+        let [combo_id] = widget_ids();
+        let mut harness = harness_with_focus(combo_id);
+        assert!(!combo_box(&harness, combo_id).is_open());
+
+        harness.keyboard_type_chars("b");
+        assert!(combo_box(&harness, combo_id).is_open());
+        assert_eq!(
+            filtered_options(&harness, combo_id),
+            ["Banana", "Blueberry"]
+        );
+        assert_eq!(
+            combo_box(&harness, combo_id)
+                .highlighted_option()
+                .map(|option| option.to_string())
+                .as_deref(),
+            Some("Banana")
+        );
+
+        harness.keyboard_type_chars("l");
+        assert_eq!(filtered_options(&harness, combo_id), ["Blueberry"]);
+
+        // Click the first row of the dropdown.
+        let combo_rect = harness.get_widget(combo_id).state().window_layout_rect();
+        let row_pos = Point::new(
+            combo_rect.x0 + DROPDOWN_PADDING + OPTION_PADDING,
+            combo_rect.y1 + DROPDOWN_PADDING + OPTION_HEIGHT / 2.,
+        );
+        harness.mouse_move(row_pos);
+        harness.mouse_button_press(MouseButton::Left);
+        harness.mouse_button_release(MouseButton::Left);
+
+        assert_eq!(committed_values(&mut harness), ["Blueberry"]);
+        assert!(!combo_box(&harness, combo_id).is_open());
+        assert_eq!(combo_box(&harness, combo_id).text(), "Blueberry");
+    }
+
+    #[test]
+    fn no_match_commits_free_value() {
+        let [combo_id] = widget_ids();
+        let mut harness = harness_with_focus(combo_id);
+
+        harness.keyboard_type_chars("kiwi");
+        assert!(combo_box(&harness, combo_id).is_open());
+        assert!(filtered_options(&harness, combo_id).is_empty());
+        assert_eq!(combo_box(&harness, combo_id).highlighted_option(), None);
+
+        edit_combo_box(&mut harness, |mut combo_box| combo_box.commit());
+        assert_eq!(committed_values(&mut harness), ["kiwi"]);
+        assert_eq!(combo_box(&harness, combo_id).text(), "kiwi");
+    }
+
+    #[test]
+    fn navigate_filtered_options() {
+        let [combo_id] = widget_ids();
+        let mut harness = harness_with_focus(combo_id);
+
+        harness.keyboard_type_chars("E");
+        assert_eq!(
+            filtered_options(&harness, combo_id),
+            ["Apple", "Blueberry", "Cherry"]
+        );
+
+        let highlighted = |harness: &TestHarness| {
+            combo_box(harness, combo_id)
+                .highlighted_option()
+                .map(|option| option.to_string())
+        };
+        edit_combo_box(&mut harness, |mut combo_box| combo_box.highlight_next());
+        assert_eq!(highlighted(&harness).as_deref(), Some("Blueberry"));
+        edit_combo_box(&mut harness, |mut combo_box| combo_box.highlight_next());
+        edit_combo_box(&mut harness, |mut combo_box| combo_box.highlight_next());
+        // Wrapped around
+        assert_eq!(highlighted(&harness).as_deref(), Some("Apple"));
+        edit_combo_box(&mut harness, |mut combo_box| combo_box.highlight_previous());
+        assert_eq!(highlighted(&harness).as_deref(), Some("Cherry"));
+
+        edit_combo_box(&mut harness, |mut combo_box| combo_box.commit());
+        assert_eq!(committed_values(&mut harness), ["Cherry"]);
+    }
+
+    #[test]
+    fn validation_state_snapshots() {
+        let combo_box = fruits().with_validation_state(ValidationState::Invalid {
+            message: "Unknown fruit".into(),
+        });
+        let mut harness = TestHarness::create_with_size(combo_box, Size::new(200.0, 40.0));
+        assert_render_snapshot!(harness, "invalid");
+
+        harness.edit_root_widget(|mut combo_box| {
+            let mut combo_box = combo_box.downcast::<EditableComboBox>();
+            combo_box.set_validation_state(ValidationState::Warning {
+                message: "Out of season".into(),
+            });
+        });
+        assert_render_snapshot!(harness, "warning");
+    }
+
+    #[test]
+    fn validation_state_is_exposed_on_combo_box() {
+        let [combo_id] = widget_ids();
+        let mut harness = harness_with_focus(combo_id);
+        harness.access_update();
+
+        edit_combo_box(&mut harness, |mut combo_box| {
+            combo_box.set_validation_state(ValidationState::Warning {
+                message: "Not in the list".into(),
+            });
+        });
+        let tree_update = harness.access_update();
+        let (_, node) = tree_update
+            .nodes
+            .iter()
+            .find(|(id, _)| *id == combo_id.into())
+            .expect("combo box node wasn't emitted");
+        assert_eq!(node.role(), Role::ComboBox);
+        assert_eq!(node.invalid(), None);
+        assert_eq!(node.description(), Some("Not in the list"));
+    }
+
+    #[test]
+    fn match_ignores_ascii_case() {
+        assert_eq!(find_match("Blueberry", "BER"), Some(4..7));
+        assert_eq!(find_match("Blueberry", ""), Some(0..0));
+        assert_eq!(find_match("Blueberry", "kiwi"), None);
+        assert_eq!(find_match("Crème brûlée", "brû"), Some(7..11));
+    }
+}
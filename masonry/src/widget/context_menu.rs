@@ -0,0 +1,522 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! A widget showing a menu of actions when its child is right-clicked.
+
+use accesskit::Role;
+use smallvec::SmallVec;
+use tracing::{trace, trace_span, Span};
+use vello::Scene;
+use winit::event::{ElementState, MouseButton};
+use winit::keyboard::{Key, NamedKey};
+
+use crate::paint_scene_helpers::{fill_color, stroke};
+use crate::widget::{Label, WidgetMut, WidgetRef};
+use crate::{
+    theme, AccessCtx, AccessEvent, Action, ArcStr, BoxConstraints, EventCtx, Insets, LayoutCtx,
+    LifeCycle, LifeCycleCtx, PaintCtx, Point, PointerEvent, Rect, Size, StatusChange, TextEvent,
+    Vec2, Widget, WidgetPod,
+};
+
+const MENU_PADDING: f64 = 4.0;
+const ITEM_PADDING: f64 = 8.0;
+const ITEM_HEIGHT: f64 = theme::BORDERED_WIDGET_HEIGHT;
+/// How long the pointer must be held down to open the menu, in nanoseconds.
+const LONG_PRESS_DURATION: u64 = 500_000_000;
+/// How far the pointer can move before a press stops counting as a long press.
+const LONG_PRESS_SLOP: f64 = 8.0;
+
+struct LongPress {
+    origin: Point,
+    elapsed: u64,
+}
+
+/// A widget which opens a menu over its child on right-click or long-press.
+///
+/// Selecting an item submits [`Action::ContextMenuItemSelected`] with the index of the
+/// item. The menu closes when an item is selected, when the pointer is pressed outside
+/// of it, or when Escape is pressed.
+///
+/// The menu is drawn at the pointer position, and may extend past the bounds of this
+/// widget: the widget stays active while the menu is open, so that it receives all pointer
+/// events. If a context menu is nested in another one, only the innermost menu opens.
+pub struct ContextMenu<W: Widget> {
+    child: WidgetPod<W>,
+    items: Vec<WidgetPod<Label>>,
+    /// Where the menu was requested, in local coordinates, if it's open.
+    open_at: Option<Point>,
+    menu_rect: Rect,
+    hovered_item: Option<usize>,
+    pressed_item: Option<usize>,
+    long_press: Option<LongPress>,
+}
+
+impl<W: Widget> ContextMenu<W> {
+    /// Create a context menu around `child`, with no items.
+    pub fn new(child: W) -> Self {
+        Self::new_pod(WidgetPod::new(child))
+    }
+
+    /// Create a context menu around a child that is already in a [`WidgetPod`].
+    pub fn new_pod(child: WidgetPod<W>) -> Self {
+        ContextMenu {
+            child,
+            items: Vec::new(),
+            open_at: None,
+            menu_rect: Rect::ZERO,
+            hovered_item: None,
+            pressed_item: None,
+            long_press: None,
+        }
+    }
+
+    /// Builder-style method to add an item to the menu.
+    pub fn with_item(mut self, label: impl Into<ArcStr>) -> Self {
+        self.items.push(WidgetPod::new(Label::new(label)));
+        self
+    }
+
+    /// Whether the menu is open.
+    pub fn is_open(&self) -> bool {
+        self.open_at.is_some()
+    }
+
+    fn item_at(&self, pos: Point) -> Option<usize> {
+        if !self.menu_rect.contains(pos) {
+            return None;
+        }
+        let offset = pos.y - self.menu_rect.y0 - MENU_PADDING;
+        if offset < 0.0 {
+            return None;
+        }
+        let index = (offset / ITEM_HEIGHT) as usize;
+        (index < self.items.len()).then_some(index)
+    }
+
+    fn open(&mut self, ctx: &mut EventCtx, pos: Point) {
+        trace!("ContextMenu {:?} opened at {}", ctx.widget_id(), pos);
+        self.open_at = Some(pos);
+        self.hovered_item = None;
+        self.pressed_item = None;
+        self.long_press = None;
+        for item in &mut self.items {
+            ctx.set_stashed(item, false);
+        }
+        ctx.set_active(true);
+        ctx.request_layout();
+        ctx.request_accessibility_update();
+    }
+
+    fn close(&mut self, ctx: &mut EventCtx) {
+        trace!("ContextMenu {:?} closed", ctx.widget_id());
+        self.open_at = None;
+        self.hovered_item = None;
+        self.pressed_item = None;
+        for item in &mut self.items {
+            ctx.set_stashed(item, true);
+        }
+        ctx.set_active(false);
+        ctx.request_layout();
+        ctx.request_accessibility_update();
+    }
+
+    fn on_open_menu_pointer_event(&mut self, ctx: &mut EventCtx, pos: Point, event: &PointerEvent) {
+        match event {
+            PointerEvent::PointerDown(_, _) => {
+                self.pressed_item = self.item_at(pos);
+                if self.pressed_item.is_none() {
+                    self.close(ctx);
+                }
+            }
+            PointerEvent::PointerUp(_, _) => {
+                if let Some(index) = self.pressed_item.take() {
+                    if self.item_at(pos) == Some(index) {
+                        ctx.submit_action(Action::ContextMenuItemSelected(index));
+                        self.close(ctx);
+                    }
+                }
+            }
+            PointerEvent::PointerMove(_) => {
+                let hovered_item = self.item_at(pos);
+                if hovered_item != self.hovered_item {
+                    self.hovered_item = hovered_item;
+                    ctx.request_paint();
+                }
+            }
+            _ => {}
+        }
+        ctx.set_handled();
+    }
+}
+
+impl<W: Widget> WidgetMut<'_, ContextMenu<W>> {
+    /// Get a [`WidgetMut`] to the child.
+    pub fn child_mut(&mut self) -> WidgetMut<'_, W> {
+        self.ctx.get_mut(&mut self.widget.child)
+    }
+
+    /// Replace the items of the menu.
+    pub fn set_items(&mut self, labels: impl IntoIterator<Item = impl Into<ArcStr>>) {
+        self.widget.items = labels
+            .into_iter()
+            .map(|label| WidgetPod::new(Label::new(label)))
+            .collect();
+        self.widget.hovered_item = None;
+        self.widget.pressed_item = None;
+        self.ctx.children_changed();
+    }
+
+    /// Set the label of the item at `index`.
+    pub fn set_item_label(&mut self, index: usize, label: impl Into<ArcStr>) {
+        let item = &mut self.widget.items[index];
+        self.ctx.get_mut(item).set_text(label);
+    }
+}
+
+impl<W: Widget> Widget for ContextMenu<W> {
+    fn on_pointer_event(&mut self, ctx: &mut EventCtx, event: &PointerEvent) {
+        let pos = event.local_position(ctx);
+
+        self.child.on_pointer_event(ctx, event);
+        for item in &mut self.items {
+            item.on_pointer_event(ctx, event);
+        }
+
+        if self.is_open() {
+            self.on_open_menu_pointer_event(ctx, pos, event);
+            return;
+        }
+
+        // An inner context menu has already handled the event.
+        let handled = ctx.is_handled();
+
+        match event {
+            PointerEvent::PointerDown(MouseButton::Right, _) if !handled => {
+                self.open(ctx, pos);
+                ctx.set_handled();
+            }
+            PointerEvent::PointerDown(MouseButton::Left, _) if !handled => {
+                self.long_press = Some(LongPress {
+                    origin: pos,
+                    elapsed: 0,
+                });
+                // Stay active until release, so that a menu opened by the long press
+                // receives the following pointer events.
+                ctx.set_active(true);
+                ctx.request_anim_frame();
+            }
+            PointerEvent::PointerMove(_) => {
+                if let Some(long_press) = &self.long_press {
+                    if (pos - long_press.origin).hypot() > LONG_PRESS_SLOP {
+                        self.long_press = None;
+                    }
+                }
+            }
+            PointerEvent::PointerUp(_, _) => {
+                self.long_press = None;
+                ctx.set_active(false);
+            }
+            PointerEvent::PointerLeave(_) => {
+                self.long_press = None;
+            }
+            _ => {}
+        }
+    }
+
+    fn on_text_event(&mut self, ctx: &mut EventCtx, event: &TextEvent) {
+        if self.is_open() {
+            if let TextEvent::KeyboardKey(key_event, _) = event {
+                if key_event.logical_key == Key::Named(NamedKey::Escape)
+                    && key_event.state == ElementState::Pressed
+                {
+                    self.close(ctx);
+                    ctx.set_handled();
+                    return;
+                }
+            }
+        }
+        self.child.on_text_event(ctx, event);
+    }
+
+    fn on_access_event(&mut self, ctx: &mut EventCtx, event: &AccessEvent) {
+        if event.target == ctx.widget_id() && !self.is_open() {
+            if let accesskit::Action::ShowContextMenu = event.action {
+                let center = ctx.size().to_rect().center();
+                self.open(ctx, center);
+                return;
+            }
+        }
+        self.child.on_access_event(ctx, event);
+        for (index, item) in self.items.iter_mut().enumerate() {
+            if event.target == item.id() && event.action == accesskit::Action::Default {
+                ctx.submit_action(Action::ContextMenuItemSelected(index));
+                self.close(ctx);
+                return;
+            }
+        }
+    }
+
+    fn on_status_change(&mut self, _ctx: &mut LifeCycleCtx, _event: &StatusChange) {}
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle) {
+        self.child.lifecycle(ctx, event);
+        for item in &mut self.items {
+            item.lifecycle(ctx, event);
+        }
+
+        // Items are only shown while the menu is open. New items are stashed
+        // once they have been added.
+        if !self.is_open() {
+            for item in &mut self.items {
+                if !item.state().is_stashed {
+                    ctx.set_stashed(item, true);
+                }
+            }
+        }
+
+        if let LifeCycle::AnimFrame(interval) = event {
+            if let Some(long_press) = &mut self.long_press {
+                long_press.elapsed += *interval;
+                if long_press.elapsed >= LONG_PRESS_DURATION {
+                    let origin = long_press.origin;
+                    self.long_press = None;
+                    self.open_at = Some(origin);
+                    for item in &mut self.items {
+                        ctx.set_stashed(item, false);
+                    }
+                    ctx.request_layout();
+                    ctx.request_accessibility_update();
+                } else {
+                    ctx.request_anim_frame();
+                }
+            }
+        }
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints) -> Size {
+        let size = self.child.layout(ctx, bc);
+        ctx.place_child(&mut self.child, Point::ORIGIN);
+
+        let Some(open_at) = self.open_at else {
+            self.menu_rect = Rect::ZERO;
+            ctx.set_paint_insets(Insets::ZERO);
+            return size;
+        };
+
+        let mut label_width: f64 = 0.0;
+        let mut label_heights = Vec::with_capacity(self.items.len());
+        for item in &mut self.items {
+            let label_size = item.layout(ctx, &BoxConstraints::UNBOUNDED);
+            label_width = label_width.max(label_size.width);
+            label_heights.push(label_size.height);
+        }
+        let menu_size = Size::new(
+            label_width + 2. * (ITEM_PADDING + MENU_PADDING),
+            self.items.len() as f64 * ITEM_HEIGHT + 2. * MENU_PADDING,
+        );
+        self.menu_rect = Rect::from_origin_size(open_at, menu_size);
+
+        for (index, (item, label_height)) in self.items.iter_mut().zip(label_heights).enumerate() {
+            let item_origin = open_at
+                + Vec2::new(
+                    MENU_PADDING + ITEM_PADDING,
+                    MENU_PADDING + index as f64 * ITEM_HEIGHT + (ITEM_HEIGHT - label_height) / 2.,
+                );
+            ctx.place_child(item, item_origin);
+        }
+
+        // The menu is painted over whatever is around this widget.
+        let bounds = size.to_rect();
+        ctx.set_paint_insets(self.menu_rect.union(bounds) - bounds);
+
+        trace!("Computed layout: size={}, menu={}", size, self.menu_rect);
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, scene: &mut Scene) {
+        self.child.paint(ctx, scene);
+
+        if !self.is_open() {
+            return;
+        }
+        let menu = self.menu_rect.to_rounded_rect(theme::BUTTON_BORDER_RADIUS);
+        fill_color(scene, &menu, theme::BACKGROUND_LIGHT);
+        stroke(scene, &menu, theme::BORDER_LIGHT, 1.0);
+        if let Some(index) = self.hovered_item {
+            let row = Rect::from_origin_size(
+                self.menu_rect.origin()
+                    + Vec2::new(MENU_PADDING, MENU_PADDING + index as f64 * ITEM_HEIGHT),
+                Size::new(self.menu_rect.width() - 2. * MENU_PADDING, ITEM_HEIGHT),
+            );
+            fill_color(scene, &row, theme::PRIMARY_DARK);
+        }
+        for item in &mut self.items {
+            item.paint(ctx, scene);
+        }
+    }
+
+    fn accessibility_role(&self) -> Role {
+        Role::GenericContainer
+    }
+
+    fn accessibility(&mut self, ctx: &mut AccessCtx) {
+        self.child.accessibility(ctx);
+        for item in &mut self.items {
+            item.accessibility(ctx);
+        }
+        if !self.is_open() {
+            ctx.current_node()
+                .add_action(accesskit::Action::ShowContextMenu);
+        }
+    }
+
+    fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
+        std::iter::once(self.child.as_dyn())
+            .chain(self.items.iter().map(|item| item.as_dyn()))
+            .collect()
+    }
+
+    fn make_trace_span(&self) -> Span {
+        trace_span!("ContextMenu")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::testing::{widget_ids, TestHarness};
+    use crate::widget::{Flex, SizedBox};
+
+    fn target() -> SizedBox {
+        SizedBox::new(Label::new("Right-click me"))
+            .width(200.)
+            .height(200.)
+    }
+
+    fn is_open<W: Widget>(harness: &TestHarness, id: crate::WidgetId) -> bool {
+        harness
+            .get_widget(id)
+            .downcast::<ContextMenu<W>>()
+            .unwrap()
+            .is_open()
+    }
+
+    #[test]
+    fn right_click_and_select() {
+        let [menu_id] = widget_ids();
+        let widget = Flex::column().with_child_id(
+            ContextMenu::new(target())
+                .with_item("Copy")
+                .with_item("Paste"),
+            menu_id,
+        );
+        let mut harness = TestHarness::create_with_size(widget, Size::new(300., 300.));
+
+        let click_pos = harness.get_widget(menu_id).state().window_origin() + Vec2::new(20., 20.);
+        harness.mouse_move(click_pos);
+        harness.mouse_button_press(MouseButton::Right);
+        harness.mouse_button_release(MouseButton::Right);
+        assert!(is_open::<SizedBox>(&harness, menu_id));
+        assert_eq!(harness.pop_action(), None);
+
+        // Select "Paste"
+        let paste_pos = click_pos + Vec2::new(MENU_PADDING + 5., MENU_PADDING + 1.5 * ITEM_HEIGHT);
+        harness.mouse_move(paste_pos);
+        harness.mouse_button_press(MouseButton::Left);
+        harness.mouse_button_release(MouseButton::Left);
+        assert_eq!(
+            harness.pop_action(),
+            Some((Action::ContextMenuItemSelected(1), menu_id))
+        );
+        assert!(!is_open::<SizedBox>(&harness, menu_id));
+    }
+
+    #[test]
+    fn click_outside_closes() {
+        let [menu_id] = widget_ids();
+        let widget =
+            Flex::column().with_child_id(ContextMenu::new(target()).with_item("Copy"), menu_id);
+        let mut harness = TestHarness::create_with_size(widget, Size::new(300., 300.));
+
+        let origin = harness.get_widget(menu_id).state().window_origin();
+        harness.mouse_move(origin + Vec2::new(20., 20.));
+        harness.mouse_button_press(MouseButton::Right);
+        harness.mouse_button_release(MouseButton::Right);
+        assert!(is_open::<SizedBox>(&harness, menu_id));
+
+        harness.mouse_move(origin + Vec2::new(180., 180.));
+        harness.mouse_button_press(MouseButton::Left);
+        harness.mouse_button_release(MouseButton::Left);
+        assert!(!is_open::<SizedBox>(&harness, menu_id));
+        assert_eq!(harness.pop_action(), None);
+    }
+
+    #[test]
+    fn menu_outside_bounds_receives_clicks() {
+        let [menu_id] = widget_ids();
+        let widget = Flex::column().with_child_id(
+            ContextMenu::new(Label::new("Small")).with_item("A rather long item"),
+            menu_id,
+        );
+        let mut harness = TestHarness::create_with_size(widget, Size::new(300., 300.));
+
+        harness.mouse_move_to(menu_id);
+        harness.mouse_button_press(MouseButton::Right);
+        harness.mouse_button_release(MouseButton::Right);
+        assert!(is_open::<Label>(&harness, menu_id));
+
+        // The item is placed past the right edge of the label.
+        let item_id = harness.get_widget(menu_id).children()[1].id();
+        let item_rect = harness.get_widget(item_id).state().window_layout_rect();
+        let menu_rect = harness.get_widget(menu_id).state().window_layout_rect();
+        assert!(item_rect.x1 > menu_rect.x1);
+
+        harness.mouse_move(Point::new(item_rect.x1 - 1., item_rect.center().y));
+        harness.mouse_button_press(MouseButton::Left);
+        harness.mouse_button_release(MouseButton::Left);
+        assert_eq!(
+            harness.pop_action(),
+            Some((Action::ContextMenuItemSelected(0), menu_id))
+        );
+    }
+
+    #[test]
+    fn long_press_animates_until_open() {
+        let [menu_id] = widget_ids();
+        let widget =
+            Flex::column().with_child_id(ContextMenu::new(target()).with_item("Copy"), menu_id);
+        let mut harness = TestHarness::create_with_size(widget, Size::new(300., 300.));
+
+        harness.mouse_move_to(menu_id);
+        harness.mouse_button_press(MouseButton::Left);
+        let frame = Duration::from_millis(100);
+        for _ in 0..4 {
+            harness.animate(frame);
+            assert!(!is_open::<SizedBox>(&harness, menu_id));
+            assert!(harness.get_widget(menu_id).state().request_anim);
+        }
+
+        // The long press is over: the menu opens and stops requesting frames.
+        harness.animate(frame);
+        assert!(is_open::<SizedBox>(&harness, menu_id));
+        assert!(!harness.get_widget(menu_id).state().request_anim);
+    }
+
+    #[test]
+    fn nested_menus_prefer_innermost() {
+        let [outer_id, inner_id] = widget_ids();
+        let inner = WidgetPod::new_with_id(ContextMenu::new(target()).with_item("Inner"), inner_id);
+        let outer = ContextMenu::new_pod(inner).with_item("Outer");
+        let widget = Flex::column().with_child_id(outer, outer_id);
+        let mut harness = TestHarness::create_with_size(widget, Size::new(300., 300.));
+
+        let origin = harness.get_widget(inner_id).state().window_origin();
+        harness.mouse_move(origin + Vec2::new(20., 20.));
+        harness.mouse_button_press(MouseButton::Right);
+        harness.mouse_button_release(MouseButton::Right);
+        assert!(is_open::<SizedBox>(&harness, inner_id));
+        assert!(!is_open::<ContextMenu<SizedBox>>(&harness, outer_id));
+    }
+}
@@ -0,0 +1,333 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! A widget whose child can be dragged around, like the items of a diagram editor.
+
+use accesskit::Role;
+use smallvec::{smallvec, SmallVec};
+use tracing::{trace, trace_span, Span};
+use vello::Scene;
+use winit::event::MouseButton;
+
+use crate::widget::{WidgetMut, WidgetPod, WidgetRef};
+use crate::{
+    AccessCtx, AccessEvent, Action, BoxConstraints, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx,
+    PaintCtx, Point, PointerEvent, Size, StatusChange, TextEvent, Widget,
+};
+
+/// How far the pointer must move while pressed for a press to become a drag.
+const DRAG_THRESHOLD: f64 = 4.0;
+
+/// Whether the pointer moved far enough from where it was pressed to start a drag.
+pub(crate) fn is_drag(press_origin: Point, position: Point) -> bool {
+    (position - press_origin).hypot() > DRAG_THRESHOLD
+}
+
+/// A grid the positions of a dragged widget snap to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SnapGrid {
+    /// The distance between two grid points, on each axis.
+    ///
+    /// Positions don't snap on an axis where this isn't positive.
+    pub size: Size,
+    /// A point of the grid.
+    pub origin: Point,
+}
+
+impl SnapGrid {
+    /// Create a grid with the given cell size, with a point at the origin.
+    pub fn new(size: Size) -> Self {
+        SnapGrid {
+            size,
+            origin: Point::ORIGIN,
+        }
+    }
+
+    /// Create a grid of square cells, with a point at the origin.
+    pub fn square(spacing: f64) -> Self {
+        Self::new(Size::new(spacing, spacing))
+    }
+
+    /// Builder-style method to move the grid so that it has a point at `origin`.
+    pub fn with_origin(mut self, origin: Point) -> Self {
+        self.origin = origin;
+        self
+    }
+
+    /// The grid point nearest to `point`.
+    pub fn snap(&self, point: Point) -> Point {
+        let snap_axis = |value: f64, origin: f64, spacing: f64| {
+            if spacing > 0.0 {
+                origin + ((value - origin) / spacing).round() * spacing
+            } else {
+                value
+            }
+        };
+        Point::new(
+            snap_axis(point.x, self.origin.x, self.size.width),
+            snap_axis(point.y, self.origin.y, self.size.height),
+        )
+    }
+}
+
+/// The drag in progress, from the press of the left button to its release.
+struct Drag {
+    /// Where the pointer was pressed, in window coordinates.
+    press_origin: Point,
+    /// The position of the child when the pointer was pressed.
+    start_position: Point,
+    /// Whether the pointer moved further than the threshold.
+    moved: bool,
+}
+
+/// A widget letting its child be dragged around its area, e.g. an item on a canvas.
+///
+/// The widget takes all the space it's given, and places its child at its position.
+/// Pressing the left mouse button on the child and moving the pointer further than a
+/// small threshold drags it. The widget holds the pointer until the button is released,
+/// and submits [`Action::Dragged`] with the new position whenever the child moves.
+///
+/// With a [`SnapGrid`], the positions snap to the nearest grid point during the drag.
+/// Holding Alt disables snapping until the key is released.
+pub struct Draggable<W> {
+    child: WidgetPod<W>,
+    position: Point,
+    snap: Option<SnapGrid>,
+    drag: Option<Drag>,
+}
+
+impl<W: Widget> Draggable<W> {
+    /// Create a new draggable widget around `child`, at the origin of its area.
+    pub fn new(child: W) -> Self {
+        Self::new_pod(WidgetPod::new(child))
+    }
+
+    /// Create a new draggable widget around the child `child`.
+    pub fn new_pod(child: WidgetPod<W>) -> Self {
+        Draggable {
+            child,
+            position: Point::ORIGIN,
+            snap: None,
+            drag: None,
+        }
+    }
+
+    /// Builder-style method to set the position of the child in the area of the widget.
+    pub fn with_position(mut self, position: Point) -> Self {
+        self.position = position;
+        self
+    }
+
+    /// Builder-style method to snap the positions of the child to `grid` during drags.
+    pub fn with_snap(mut self, grid: SnapGrid) -> Self {
+        self.snap = Some(grid);
+        self
+    }
+
+    /// The position of the child in the area of the widget.
+    pub fn position(&self) -> Point {
+        self.position
+    }
+}
+
+impl<W: Widget> WidgetMut<'_, Draggable<W>> {
+    pub fn child_mut(&mut self) -> WidgetMut<'_, W> {
+        self.ctx.get_mut(&mut self.widget.child)
+    }
+
+    /// Move the child to `position`, without snapping it.
+    pub fn set_position(&mut self, position: Point) {
+        self.widget.position = position;
+        self.ctx.request_layout();
+    }
+
+    /// Set the grid the positions of the child snap to during drags, if any.
+    ///
+    /// This doesn't move the child until it's dragged.
+    pub fn set_snap(&mut self, grid: Option<SnapGrid>) {
+        self.widget.snap = grid;
+    }
+}
+
+impl<W: Widget> Widget for Draggable<W> {
+    fn on_pointer_event(&mut self, ctx: &mut EventCtx, event: &PointerEvent) {
+        self.child.on_pointer_event(ctx, event);
+        if ctx.is_handled() || ctx.is_disabled() {
+            return;
+        }
+
+        let state = event.pointer_state();
+        let position = Point::new(state.position.x, state.position.y);
+        match event {
+            PointerEvent::PointerDown(MouseButton::Left, _) => {
+                if self.child.is_hot() && !self.child.has_active() {
+                    self.drag = Some(Drag {
+                        press_origin: position,
+                        start_position: self.position,
+                        moved: false,
+                    });
+                    ctx.set_active(true);
+                }
+            }
+            PointerEvent::PointerMove(_) => {
+                let Some(drag) = &mut self.drag else {
+                    return;
+                };
+                drag.moved |= is_drag(drag.press_origin, position);
+                if !drag.moved {
+                    return;
+                }
+                let mut new_position = drag.start_position + (position - drag.press_origin);
+                if let Some(grid) = self.snap {
+                    if !state.mods.state().alt_key() {
+                        new_position = grid.snap(new_position);
+                    }
+                }
+                if new_position != self.position {
+                    trace!(
+                        "Draggable {:?} dragged to {}",
+                        ctx.widget_id(),
+                        new_position
+                    );
+                    self.position = new_position;
+                    ctx.submit_action(Action::Dragged(new_position));
+                    ctx.request_layout();
+                }
+            }
+            PointerEvent::PointerUp(MouseButton::Left, _) => {
+                if self.drag.take().is_some() {
+                    ctx.set_active(false);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn on_text_event(&mut self, ctx: &mut EventCtx, event: &TextEvent) {
+        self.child.on_text_event(ctx, event);
+    }
+
+    fn on_access_event(&mut self, ctx: &mut EventCtx, event: &AccessEvent) {
+        self.child.on_access_event(ctx, event);
+    }
+
+    fn on_status_change(&mut self, _ctx: &mut LifeCycleCtx, _event: &StatusChange) {}
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle) {
+        self.child.lifecycle(ctx, event);
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints) -> Size {
+        let child_size = self.child.layout(ctx, &bc.loosen());
+        ctx.place_child(&mut self.child, self.position);
+
+        // In an unbounded area, the widget is just large enough to show its child.
+        let max = bc.max();
+        let size = Size::new(
+            if max.width.is_finite() {
+                max.width
+            } else {
+                self.position.x + child_size.width
+            },
+            if max.height.is_finite() {
+                max.height
+            } else {
+                self.position.y + child_size.height
+            },
+        );
+        let size = bc.constrain(size);
+        trace!("Computed layout: size={}", size);
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, scene: &mut Scene) {
+        self.child.paint(ctx, scene);
+    }
+
+    fn accessibility_role(&self) -> Role {
+        Role::GenericContainer
+    }
+
+    fn accessibility(&mut self, ctx: &mut AccessCtx) {
+        self.child.accessibility(ctx);
+    }
+
+    fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
+        smallvec![self.child.as_dyn()]
+    }
+
+    fn make_trace_span(&self) -> Span {
+        trace_span!("Draggable")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use winit::keyboard::ModifiersState;
+
+    use super::*;
+    use crate::testing::{widget_ids, TestHarness};
+    use crate::widget::SizedBox;
+
+    fn item_harness() -> (TestHarness, crate::WidgetId) {
+        let [item] = widget_ids();
+        let child = SizedBox::empty().width(40.).height(40.);
+        let draggable = Draggable::new_pod(WidgetPod::new_with_id(child, item))
+            .with_position(Point::new(20., 20.))
+            .with_snap(SnapGrid::square(25.).with_origin(Point::new(5., 5.)));
+        let harness = TestHarness::create_with_size(draggable, Size::new(400., 400.));
+        (harness, item)
+    }
+
+    fn item_origin(harness: &TestHarness, item: crate::WidgetId) -> Point {
+        harness.get_widget(item).state().layout_rect().origin()
+    }
+
+    #[test]
+    fn grid_snapping() {
+        let grid = SnapGrid::new(Size::new(10., 0.)).with_origin(Point::new(3., 3.));
+        assert_eq!(grid.snap(Point::new(9., 17.4)), Point::new(13., 17.4));
+        assert_eq!(grid.snap(Point::new(-4., 0.)), Point::new(-7., 0.));
+    }
+
+    #[test]
+    fn drag_snaps_to_grid() {
+        let (mut harness, item) = item_harness();
+        harness.mouse_move((30., 30.));
+        harness.mouse_button_press(MouseButton::Left);
+        harness.mouse_move((32., 31.));
+        assert_eq!(harness.pop_action(), None);
+
+        harness.mouse_move((97., 143.));
+        assert_eq!(item_origin(&harness, item), Point::new(80., 130.));
+        assert!(matches!(
+            harness.pop_action(),
+            Some((Action::Dragged(position), _)) if position == Point::new(80., 130.)
+        ));
+
+        // The pointer is held outside of the child.
+        harness.mouse_move((350., 10.));
+        harness.mouse_button_release(MouseButton::Left);
+        assert_eq!(item_origin(&harness, item), Point::new(330., 5.));
+        let position = item_origin(&harness, item) - Point::new(5., 5.);
+        assert_eq!((position.x % 25., position.y % 25.), (0., 0.));
+
+        // Moves after the release don't drag.
+        harness.mouse_move((100., 100.));
+        assert_eq!(item_origin(&harness, item), Point::new(330., 5.));
+    }
+
+    #[test]
+    fn alt_disables_snapping() {
+        let (mut harness, item) = item_harness();
+        harness.mouse_move((30., 30.));
+        harness.mouse_button_press(MouseButton::Left);
+        harness.set_modifiers(ModifiersState::ALT);
+        harness.mouse_move((97., 143.));
+        assert_eq!(item_origin(&harness, item), Point::new(87., 133.));
+
+        harness.set_modifiers(ModifiersState::empty());
+        harness.mouse_move((98., 143.));
+        assert_eq!(item_origin(&harness, item), Point::new(80., 130.));
+    }
+}
@@ -3,21 +3,30 @@
 
 //! A widget that arranges its children in a one-dimensional array.
 
+use std::ops::Range;
+
 use accesskit::Role;
 use kurbo::{Affine, Stroke};
 use smallvec::SmallVec;
 use tracing::{trace, trace_span, Span};
 use vello::Scene;
+use winit::event::MouseButton;
 
 use crate::kurbo::common::FloatExt;
-use crate::kurbo::Vec2;
+use crate::kurbo::{Line, Vec2};
 use crate::theme::get_debug_color;
+use crate::widget::draggable::is_drag;
 use crate::widget::{WidgetMut, WidgetRef};
 use crate::{
-    AccessCtx, AccessEvent, BoxConstraints, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx,
-    Point, PointerEvent, Rect, Size, StatusChange, TextEvent, Widget, WidgetId, WidgetPod,
+    AccessCtx, AccessEvent, Action, BoxConstraints, CachedShadow, Elevation, EventCtx, LayoutCtx,
+    LifeCycle, LifeCycleCtx, PaintCtx, Point, PointerEvent, Rect, Size, StatusChange, TextEvent,
+    Widget, WidgetId, WidgetPod,
 };
 
+/// How fast the neighbors of a child being reordered move out of its way, in pixels
+/// per second.
+const REORDER_SHIFT_SPEED: f64 = 800.0;
+
 /// A container with either horizontal or vertical layout.
 ///
 /// This widget is the foundation of most layouts, and is highly configurable.
@@ -26,7 +35,18 @@ pub struct Flex {
     cross_alignment: CrossAxisAlignment,
     main_alignment: MainAxisAlignment,
     fill_major_axis: bool,
+    reversed: bool,
+    wrap: FlexWrap,
+    gap: f64,
+    reorderable: bool,
+    reorder_drag: Option<ReorderDrag>,
+    /// How far each child is moved on the main axis from its place, while children are
+    /// being reordered. Empty when no child is moved.
+    reorder_shifts: Vec<f64>,
+    elevation: Elevation,
+    shadow: CachedShadow,
     children: Vec<Child>,
+    placeholder: Option<WidgetPod<Box<dyn Widget>>>,
 }
 
 /// Optional parameters for an item in a [`Flex`] container (row or column).
@@ -40,6 +60,10 @@ pub struct Flex {
 pub struct FlexParams {
     flex: f64,
     alignment: Option<CrossAxisAlignment>,
+    margin_before: Option<f64>,
+    margin_after: Option<f64>,
+    min_major: Option<f64>,
+    max_major: Option<f64>,
 }
 
 /// An axis in visual space.
@@ -93,6 +117,23 @@ pub enum MainAxisAlignment {
     SpaceAround,
 }
 
+/// Whether the children of a [`Flex`] are broken into several lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FlexWrap {
+    /// Children are laid out on a single line, shrinking flex children to fit.
+    #[default]
+    NoWrap,
+    /// Children which don't fit on the current line on the main axis start a new line.
+    ///
+    /// Lines are stacked on the cross axis, and [`MainAxisAlignment`] and
+    /// [`CrossAxisAlignment`] apply within each line. Flex factors are ignored: flex
+    /// children are laid out like other children, and flex spacers are empty.
+    Wrap,
+    /// Like [`Wrap`](FlexWrap::Wrap), but lines are stacked from the end of the cross axis,
+    /// e.g. the first line of a row is at the bottom.
+    WrapReverse,
+}
+
 // --- Flex impl ---
 
 impl Flex {
@@ -104,6 +145,15 @@ impl Flex {
             cross_alignment: CrossAxisAlignment::Center,
             main_alignment: MainAxisAlignment::Start,
             fill_major_axis: false,
+            reversed: false,
+            wrap: FlexWrap::NoWrap,
+            gap: 0.0,
+            reorderable: false,
+            reorder_drag: None,
+            reorder_shifts: Vec::new(),
+            elevation: Elevation::default(),
+            shadow: CachedShadow::default(),
+            placeholder: None,
         }
     }
 
@@ -145,6 +195,44 @@ impl Flex {
         self
     }
 
+    /// Builder-style method for laying out the children from the end of the main axis.
+    ///
+    /// See [`set_reversed`](WidgetMut::set_reversed).
+    pub fn reversed(mut self, reversed: bool) -> Self {
+        self.reversed = reversed;
+        self
+    }
+
+    /// Builder-style method for specifying whether children wrap onto several lines.
+    pub fn wrap(mut self, wrap: FlexWrap) -> Self {
+        self.wrap = wrap;
+        self
+    }
+
+    /// Builder-style method for setting the space between children, and between lines
+    /// when wrapping.
+    ///
+    /// This is in addition to spacers, which are children as far as the gap is concerned.
+    pub fn gap(mut self, gap: f64) -> Self {
+        self.gap = gap.max(0.0);
+        self
+    }
+
+    /// Builder-style method for letting the user reorder the children by dragging them.
+    ///
+    /// See [`set_reorderable`](WidgetMut::set_reorderable).
+    pub fn reorderable(mut self, reorderable: bool) -> Self {
+        self.reorderable = reorderable;
+        self
+    }
+
+    /// Builder-style method for painting a drop shadow below the children, with the
+    /// style the [`Theme`](crate::Theme) defines for `elevation`.
+    pub fn elevation(mut self, elevation: Elevation) -> Self {
+        self.elevation = elevation;
+        self
+    }
+
     /// Builder-style variant of `add_child`.
     ///
     /// Convenient for assembling a group of widgets in a single expression.
@@ -163,6 +251,7 @@ impl Flex {
         let child = Child::Fixed {
             widget,
             alignment: None,
+            margins: (0.0, 0.0),
         };
         self.children.push(child);
         self
@@ -170,22 +259,7 @@ impl Flex {
 
     /// Builder-style method to add a flexible child to the container.
     pub fn with_flex_child(mut self, child: impl Widget, params: impl Into<FlexParams>) -> Self {
-        // TODO - dedup?
-        let params = params.into();
-        let child = if params.flex > 0.0 {
-            Child::Flex {
-                widget: WidgetPod::new(Box::new(child)),
-                alignment: params.alignment,
-                flex: params.flex,
-            }
-        } else {
-            // TODO
-            tracing::warn!("Flex value should be > 0.0. To add a non-flex child use the add_child or with_child methods.\nSee the docs for masonry::widget::Flex for more information");
-            Child::Fixed {
-                widget: WidgetPod::new(Box::new(child)),
-                alignment: None,
-            }
-        };
+        let child = Child::new_flex(WidgetPod::new(Box::new(child)), params.into());
         self.children.push(child);
         self
     }
@@ -232,6 +306,15 @@ impl Flex {
         self
     }
 
+    /// Builder-style method to set the widget shown when the container has no children.
+    ///
+    /// See [`set_placeholder`](WidgetMut::set_placeholder).
+    pub fn with_placeholder(mut self, placeholder: impl Widget) -> Self {
+        self.placeholder = Some(WidgetPod::new(Box::new(placeholder)));
+        self
+    }
+
+    /// The number of children, including spacers but not the placeholder.
     pub fn len(&self) -> usize {
         self.children.len()
     }
@@ -239,6 +322,447 @@ impl Flex {
     pub fn is_empty(&self) -> bool {
         self.len() == 0
     }
+
+    /// What the child at `idx` is, or `None` if there is no child at `idx`.
+    ///
+    /// Indices count spacers, as in [`len`](Self::len) and the methods of [`WidgetMut`].
+    pub fn child_kind(&self, idx: usize) -> Option<ChildKind> {
+        self.children.get(idx).map(Child::kind)
+    }
+
+    /// What each child is, spacers included, in order.
+    pub fn iter_children(&self) -> impl Iterator<Item = ChildKind> + '_ {
+        self.children.iter().map(Child::kind)
+    }
+
+    /// Where the child at `idx` was placed by the last layout pass, in the coordinates
+    /// of the container.
+    ///
+    /// The rect is the child's own area, after the gaps, margins and alignment offsets
+    /// were applied. Returns `None` for spacers, and if there is no child at `idx`.
+    pub fn child_rect(&self, idx: usize) -> Option<Rect> {
+        let widget = self.children.get(idx)?.widget()?;
+        Some(widget.layout_rect())
+    }
+
+    /// Returns `true` if `idx` is a valid index for `method`.
+    ///
+    /// Insertion methods accept `idx == len`. Otherwise, this panics in debug
+    /// builds and logs an error in release builds.
+    fn check_index(&self, method: &str, idx: usize, is_insertion: bool) -> bool {
+        let len = self.children.len();
+        let in_bounds = if is_insertion { idx <= len } else { idx < len };
+        if !in_bounds {
+            debug_panic!(
+                "Flex::{}: index {} is out of bounds for Flex with {} children (including spacers)",
+                method,
+                idx,
+                len,
+            );
+        }
+        in_bounds
+    }
+
+    /// How far the child at `idx` should be moved from its place by the drag in progress.
+    fn reorder_target_shift(&self, idx: usize) -> f64 {
+        let Some(drag) = &self.reorder_drag else {
+            return 0.0;
+        };
+        if idx == drag.index {
+            return drag.offset;
+        }
+        let is_passed = if drag.index < drag.target {
+            drag.index < idx && idx <= drag.target
+        } else {
+            drag.target <= idx && idx < drag.index
+        };
+        match (drag.span(drag.index), drag.span(idx)) {
+            // The children between the dragged one and its target make room for it.
+            (Some(dragged), Some(passed)) if is_passed => {
+                let direction = (span_center(dragged) - span_center(passed)).signum();
+                let (margin_before, margin_after) = self.children[drag.index].margins();
+                direction * (margin_before + dragged.1 - dragged.0 + margin_after + self.gap)
+            }
+            _ => 0.0,
+        }
+    }
+
+    /// Move the children being reordered towards their target by at most `distance`,
+    /// and return whether they all reached it.
+    fn step_reorder_shifts(&mut self, distance: f64) -> bool {
+        self.reorder_shifts.resize(self.children.len(), 0.0);
+        let mut done = true;
+        for idx in 0..self.children.len() {
+            let target = self.reorder_target_shift(idx);
+            let shift = &mut self.reorder_shifts[idx];
+            if (target - *shift).abs() <= distance {
+                *shift = target;
+            } else {
+                *shift += distance.copysign(target - *shift);
+                done = false;
+            }
+        }
+        if done && self.reorder_drag.is_none() {
+            self.reorder_shifts.clear();
+        }
+        done
+    }
+
+    /// Drag children to reorder them, see [`set_reorderable`](WidgetMut::set_reorderable).
+    fn reorder_on_pointer_event(&mut self, ctx: &mut EventCtx, event: &PointerEvent) {
+        if ctx.is_disabled() || self.wrap != FlexWrap::NoWrap {
+            return;
+        }
+        let position = event.local_position(ctx);
+        match event {
+            PointerEvent::PointerDown(MouseButton::Left, _) => {
+                if ctx.is_handled() || self.reorder_drag.is_some() {
+                    return;
+                }
+                let pressed = self.children.iter().position(|child| {
+                    child
+                        .widget()
+                        .is_some_and(|widget| widget.is_hot() && !widget.has_active())
+                });
+                let Some(index) = pressed else {
+                    return;
+                };
+                self.reorder_shifts.resize(self.children.len(), 0.0);
+                let spans = (self.children.iter().enumerate())
+                    .filter_map(|(idx, child)| {
+                        let (start, end) = self.direction.major_span(child.widget()?.layout_rect());
+                        let shift = self.reorder_shifts[idx];
+                        Some((idx, (start - shift, end - shift)))
+                    })
+                    .collect();
+                self.reorder_drag = Some(ReorderDrag {
+                    index,
+                    press_origin: position,
+                    moved: false,
+                    offset: 0.0,
+                    spans,
+                    target: index,
+                });
+                ctx.set_active(true);
+            }
+            PointerEvent::PointerMove(_) => {
+                let Some(drag) = &mut self.reorder_drag else {
+                    return;
+                };
+                drag.moved |= is_drag(drag.press_origin, position);
+                if !drag.moved {
+                    return;
+                }
+                let Some(dragged) = self.children.get(drag.index).and_then(Child::widget) else {
+                    self.reorder_drag = None;
+                    return;
+                };
+                if Rect::from_origin_size(Point::ORIGIN, ctx.size()).contains(position) {
+                    drag.offset = self.direction.major_vec(position - drag.press_origin);
+                    drag.target = drag.drop_target();
+                } else {
+                    // Dropping outside of the container cancels the drag.
+                    drag.offset = 0.0;
+                    drag.target = drag.index;
+                }
+
+                // Keep the dragged child in view, if the container is in a portal.
+                self.reorder_shifts.resize(self.children.len(), 0.0);
+                let moved_by = drag.offset - self.reorder_shifts[drag.index];
+                let dragged_rect =
+                    dragged.layout_rect() + Vec2::from(self.direction.pack(moved_by, 0.0));
+                ctx.request_pan_to(dragged_rect);
+
+                self.reorder_shifts[drag.index] = drag.offset;
+                ctx.request_layout();
+                ctx.request_paint();
+                ctx.request_anim_frame();
+            }
+            PointerEvent::PointerUp(MouseButton::Left, _) => {
+                let Some(drag) = &self.reorder_drag else {
+                    return;
+                };
+                ctx.set_active(false);
+                let (from, to) = (drag.index, drag.target);
+                if from != to {
+                    // The children stay where they are shown, and move to their new place
+                    // from there.
+                    let landing_start = drag.landing_start();
+                    self.reorder_shifts.resize(self.children.len(), 0.0);
+                    let shifts: Vec<f64> = (0..self.children.len())
+                        .map(|idx| self.reorder_shifts[idx] - self.reorder_target_shift(idx))
+                        .collect();
+                    self.reorder_shifts = shifts;
+                    if let (Some(start), Some(landing_start)) =
+                        (drag.span(from).map(|span| span.0), landing_start)
+                    {
+                        self.reorder_shifts[from] = start + drag.offset - landing_start;
+                    }
+                    let child = self.children.remove(from);
+                    self.children.insert(to, child);
+                    let shift = self.reorder_shifts.remove(from);
+                    self.reorder_shifts.insert(to, shift);
+                    // The focus chain follows the order of the children.
+                    ctx.children_changed();
+                    trace!("Flex {:?} reordered {} to {}", ctx.widget_id(), from, to);
+                    ctx.submit_action(Action::Reordered(from, to));
+                    ctx.request_accessibility_update();
+                }
+                self.reorder_drag = None;
+                ctx.request_layout();
+                ctx.request_paint();
+                ctx.request_anim_frame();
+            }
+            _ => (),
+        }
+    }
+
+    /// Whether children are placed from the end of the main axis, because the container
+    /// is [`reversed`](Self::reversed), or because it's a row in right-to-left layout.
+    /// Both together cancel out.
+    fn flips_main_axis(&self, ctx: &LayoutCtx) -> bool {
+        let rtl = self.direction == Axis::Horizontal && ctx.layout_direction().is_rtl();
+        self.reversed != rtl
+    }
+
+    /// Lay out the children in lines, for [`FlexWrap::Wrap`] and [`FlexWrap::WrapReverse`].
+    /// Share `remaining` between the flex children.
+    ///
+    /// Each time a child is clamped to its bounds, the space is shared again between the
+    /// other children.
+    fn distribute_flex(&self, remaining: f64) -> FlexShares {
+        let mut clamped_majors = vec![None; self.children.len()];
+        loop {
+            let mut space_left = remaining;
+            let mut flex_left = 0.0;
+            for (child, clamped_major) in self.children.iter().zip(&clamped_majors) {
+                match (child, clamped_major) {
+                    (_, Some(major)) => space_left -= major,
+                    (Child::Flex { flex, .. } | Child::FlexedSpacer(flex, _), None) => {
+                        flex_left += flex;
+                    }
+                    _ => {}
+                }
+            }
+            if flex_left == 0.0 {
+                return FlexShares {
+                    px_per_flex: 0.0,
+                    clamped_majors,
+                    surplus: space_left.max(0.0),
+                };
+            }
+            let px_per_flex = space_left.max(0.0) / flex_left;
+
+            let mut clamped_any = false;
+            for (child, clamped_major) in self.children.iter().zip(&mut clamped_majors) {
+                let Child::Flex {
+                    flex,
+                    bounds: (min, max),
+                    ..
+                } = child
+                else {
+                    continue;
+                };
+                if clamped_major.is_some() {
+                    continue;
+                }
+                let desired_major = flex * px_per_flex;
+                if let Some(max) = max.filter(|max| desired_major > *max) {
+                    *clamped_major = Some(max);
+                    clamped_any = true;
+                } else if let Some(min) = min.filter(|min| desired_major < *min) {
+                    *clamped_major = Some(min);
+                    clamped_any = true;
+                }
+            }
+            if !clamped_any {
+                return FlexShares {
+                    px_per_flex,
+                    clamped_majors,
+                    surplus: 0.0,
+                };
+            }
+        }
+    }
+
+    fn layout_wrapped(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints) -> Size {
+        let max_major = self.direction.major(bc.max());
+        let child_bc = self.direction.constraints(&bc.loosen(), 0.0, max_major);
+
+        // Measure the children, and break them into lines.
+        let mut sizes = Vec::with_capacity(self.children.len());
+        let mut lines: Vec<FlexLine> = Vec::new();
+        for (idx, child) in self.children.iter_mut().enumerate() {
+            let size = match child {
+                Child::Fixed { widget, .. } | Child::Flex { widget, .. } => {
+                    widget.layout(ctx, &child_bc)
+                }
+                Child::FixedSpacer(len, calculated_size) => {
+                    *calculated_size = len.max(0.0);
+                    self.direction.pack(*calculated_size, 0.0).into()
+                }
+                Child::FlexedSpacer(_, calculated_size) => {
+                    *calculated_size = 0.0;
+                    Size::ZERO
+                }
+            };
+            let (margin_before, margin_after) = child.margins();
+            let child_major = margin_before + self.direction.major(size).expand() + margin_after;
+            match lines.last_mut() {
+                Some(line) if line.major + self.gap + child_major <= max_major => {
+                    line.children.end = idx + 1;
+                    line.major += self.gap + child_major;
+                }
+                _ => lines.push(FlexLine {
+                    children: idx..idx + 1,
+                    major: child_major,
+                    minor: 0.0,
+                    max_above_baseline: 0.0,
+                    max_below_baseline: 0.0,
+                }),
+            }
+            sizes.push(size);
+        }
+
+        // Measure the lines on the minor axis.
+        for line in &mut lines {
+            for idx in line.children.clone() {
+                let (widget, alignment) = match &self.children[idx] {
+                    Child::Fixed {
+                        widget, alignment, ..
+                    }
+                    | Child::Flex {
+                        widget, alignment, ..
+                    } => (widget, alignment),
+                    _ => continue,
+                };
+                let size = sizes[idx];
+                line.minor = line.minor.max(self.direction.minor(size).expand());
+                let alignment = alignment.unwrap_or(self.cross_alignment);
+                if alignment == CrossAxisAlignment::Baseline && self.direction == Axis::Horizontal {
+                    let baseline_offset = widget.baseline_offset();
+                    line.max_above_baseline =
+                        line.max_above_baseline.max(size.height - baseline_offset);
+                    line.max_below_baseline = line.max_below_baseline.max(baseline_offset);
+                }
+            }
+            line.minor = line
+                .minor
+                .max(line.max_above_baseline + line.max_below_baseline);
+        }
+
+        let content_major = lines.iter().map(|line| line.major).fold(0.0, f64::max);
+        let major = if self.fill_major_axis && max_major.is_finite() {
+            max_major
+        } else {
+            content_major
+        };
+        let minor = lines.iter().map(|line| line.minor).sum::<f64>()
+            + self.gap * lines.len().saturating_sub(1) as f64;
+        let my_size = bc.constrain(Size::from(self.direction.pack(major, minor)));
+        let container_major = self.direction.major(my_size);
+        let container_minor = self.direction.minor(my_size);
+        let reverse = self.wrap == FlexWrap::WrapReverse;
+
+        let flip = self.flips_main_axis(ctx);
+        let mut line_start = 0.0;
+        for line in &lines {
+            let n_widgets = (self.children[line.children.clone()].iter())
+                .filter(|child| child.widget().is_some())
+                .count();
+            let extra = (container_major - line.major).max(0.0);
+            let mut spacing = Spacing::new(self.main_alignment, extra, n_widgets);
+            let mut major = spacing.next().unwrap_or(0.);
+            let line_minor_pos = if reverse {
+                container_minor - line_start - line.minor
+            } else {
+                line_start
+            };
+            for idx in line.children.clone() {
+                if idx > line.children.start {
+                    major += self.gap;
+                }
+                let child_size = sizes[idx];
+                let (margin_before, margin_after) = self.children[idx].margins();
+                major += margin_before;
+                match &mut self.children[idx] {
+                    Child::Fixed {
+                        widget, alignment, ..
+                    }
+                    | Child::Flex {
+                        widget, alignment, ..
+                    } => {
+                        let alignment = alignment.unwrap_or(self.cross_alignment);
+                        let child_minor_offset = match alignment {
+                            CrossAxisAlignment::Baseline
+                                if matches!(self.direction, Axis::Horizontal) =>
+                            {
+                                let baseline_height =
+                                    line.max_above_baseline + line.max_below_baseline;
+                                let child_above_baseline =
+                                    child_size.height - widget.baseline_offset();
+                                (line.minor - baseline_height)
+                                    + (line.max_above_baseline - child_above_baseline)
+                            }
+                            CrossAxisAlignment::Fill => {
+                                let fill_size: Size = self
+                                    .direction
+                                    .pack(self.direction.major(child_size), line.minor)
+                                    .into();
+                                widget.layout(ctx, &BoxConstraints::tight(fill_size));
+                                0.0
+                            }
+                            _ => {
+                                let extra_minor = line.minor - self.direction.minor(child_size);
+                                alignment.align(extra_minor)
+                            }
+                        };
+
+                        let mut child_pos: Point = self
+                            .direction
+                            .pack(major, line_minor_pos + child_minor_offset)
+                            .into();
+                        if flip {
+                            child_pos = self.direction.flip_major(
+                                child_pos,
+                                widget.layout_rect().size(),
+                                my_size,
+                            );
+                        }
+                        ctx.place_child(widget, child_pos);
+                        major += self.direction.major(child_size).expand() + margin_after;
+                        major += spacing.next().unwrap_or(0.);
+                    }
+                    Child::FixedSpacer(_, calculated_size)
+                    | Child::FlexedSpacer(_, calculated_size) => {
+                        major += *calculated_size;
+                    }
+                }
+            }
+            line_start += line.minor + self.gap;
+        }
+
+        // The baseline is the one of the last child of the bottom line.
+        let bottom_line = if reverse { lines.first() } else { lines.last() };
+        let baseline_offset = bottom_line
+            .and_then(|line| {
+                (self.children[line.children.clone()].iter().rev()).find_map(|child| child.widget())
+            })
+            .map(|widget| {
+                let extra_bottom_padding = my_size.height - widget.layout_rect().max_y();
+                widget.baseline_offset() + extra_bottom_padding
+            })
+            .unwrap_or(0.0);
+
+        ctx.set_baseline_offset(baseline_offset);
+        trace!(
+            "Computed wrapped layout: size={}, lines={}, baseline_offset={}",
+            my_size,
+            lines.len(),
+            baseline_offset
+        );
+        my_size
+    }
 }
 
 // --- Mutate live Flex - WidgetMut ---
@@ -275,6 +799,57 @@ impl<'a> WidgetMut<'a, Flex> {
         self.ctx.request_layout();
     }
 
+    /// Set whether the children are laid out from the end of the main axis, e.g. from
+    /// the bottom of a column, like a chat log growing upward.
+    ///
+    /// Only the visual order is reversed: the indices of the children, including
+    /// spacers, stay the same. [`MainAxisAlignment::Start`] packs the children at the
+    /// end of the main axis, and [`MainAxisAlignment::End`] at its start. The baseline
+    /// of a column is the one of its first child, which is at the bottom. A reversed row
+    /// in right-to-left layout is laid out from left to right.
+    pub fn set_reversed(&mut self, reversed: bool) {
+        self.widget.reversed = reversed;
+        self.ctx.request_layout();
+    }
+
+    /// Set whether children wrap onto several lines (see [`FlexWrap`]).
+    pub fn set_wrap(&mut self, wrap: FlexWrap) {
+        self.widget.wrap = wrap;
+        self.ctx.request_layout();
+    }
+
+    /// Set the space between children, and between lines when wrapping.
+    pub fn set_gap(&mut self, gap: f64) {
+        self.widget.gap = gap.max(0.0);
+        self.ctx.request_layout();
+    }
+
+    /// Set whether the user can reorder the children by dragging them.
+    ///
+    /// Pressing the left mouse button on a child and moving the pointer along the main
+    /// axis drags the child, while the children it passes move out of its way and a line
+    /// shows where it will be dropped. On release, the child is moved like with
+    /// [`move_child`](Self::move_child), and [`Action::Reordered`] is submitted with the
+    /// old and new indices, which include spacers. Releasing the pointer outside of the
+    /// container cancels the drag. In a [`Portal`](crate::widget::Portal), the dragged
+    /// child is kept in view.
+    ///
+    /// This has no effect on containers which wrap (see [`FlexWrap`]).
+    pub fn set_reorderable(&mut self, reorderable: bool) {
+        self.widget.reorderable = reorderable;
+        if !reorderable && self.widget.reorder_drag.take().is_some() {
+            self.widget.reorder_shifts.clear();
+            self.ctx.request_layout();
+        }
+    }
+
+    /// Set the elevation of the container, see [`Flex::elevation`].
+    pub fn set_elevation(&mut self, elevation: Elevation) {
+        self.widget.elevation = elevation;
+        // The paint insets depend on the shadow.
+        self.ctx.request_layout();
+    }
+
     /// Add a non-flex child widget.
     ///
     /// See also [`with_child`].
@@ -284,41 +859,25 @@ impl<'a> WidgetMut<'a, Flex> {
         let child = Child::Fixed {
             widget: WidgetPod::new(Box::new(child)),
             alignment: None,
+            margins: (0.0, 0.0),
         };
         self.widget.children.push(child);
-        // TODO
-        self.ctx.widget_state.children_changed = true;
-        self.ctx.widget_state.needs_layout = true;
+        self.ctx.children_changed();
     }
 
     pub fn add_child_id(&mut self, child: impl Widget, id: WidgetId) {
         let child = Child::Fixed {
             widget: WidgetPod::new_with_id(Box::new(child), id),
             alignment: None,
+            margins: (0.0, 0.0),
         };
         self.widget.children.push(child);
-        // TODO
-        self.ctx.widget_state.children_changed = true;
-        self.ctx.widget_state.needs_layout = true;
+        self.ctx.children_changed();
     }
 
     /// Add a flexible child widget.
     pub fn add_flex_child(&mut self, child: impl Widget, params: impl Into<FlexParams>) {
-        let params = params.into();
-        let child = if params.flex > 0.0 {
-            Child::Flex {
-                widget: WidgetPod::new(Box::new(child)),
-                alignment: params.alignment,
-                flex: params.flex,
-            }
-        } else {
-            // TODO
-            tracing::warn!("Flex value should be > 0.0. To add a non-flex child use the add_child or with_child methods.\nSee the docs for masonry::widget::Flex for more information");
-            Child::Fixed {
-                widget: WidgetPod::new(Box::new(child)),
-                alignment: None,
-            }
-        };
+        let child = Child::new_flex(WidgetPod::new(Box::new(child)), params.into());
         self.widget.children.push(child);
         self.ctx.children_changed();
     }
@@ -380,14 +939,16 @@ impl<'a> WidgetMut<'a, Flex> {
 
     /// Add a non-flex child widget.
     pub fn insert_child_pod(&mut self, idx: usize, widget: WidgetPod<Box<dyn Widget>>) {
+        if !self.widget.check_index("insert_child_pod", idx, true) {
+            return;
+        }
         let child = Child::Fixed {
             widget,
             alignment: None,
+            margins: (0.0, 0.0),
         };
         self.widget.children.insert(idx, child);
-        // TODO
-        self.ctx.widget_state.children_changed = true;
-        self.ctx.widget_state.needs_layout = true;
+        self.ctx.children_changed();
     }
 
     pub fn insert_flex_child(
@@ -396,25 +957,12 @@ impl<'a> WidgetMut<'a, Flex> {
         child: impl Widget,
         params: impl Into<FlexParams>,
     ) {
-        let params = params.into();
-        let child = if params.flex > 0.0 {
-            Child::Flex {
-                widget: WidgetPod::new(Box::new(child)),
-                alignment: params.alignment,
-                flex: params.flex,
-            }
-        } else {
-            // TODO
-            tracing::warn!("Flex value should be > 0.0. To add a non-flex child use the add_child or with_child methods.\nSee the docs for masonry::widget::Flex for more information");
-            Child::Fixed {
-                widget: WidgetPod::new(Box::new(child)),
-                alignment: None,
-            }
-        };
+        if !self.widget.check_index("insert_flex_child", idx, true) {
+            return;
+        }
+        let child = Child::new_flex(WidgetPod::new(Box::new(child)), params.into());
         self.widget.children.insert(idx, child);
-        // TODO
-        self.ctx.widget_state.children_changed = true;
-        self.ctx.widget_state.needs_layout = true;
+        self.ctx.children_changed();
     }
 
     // TODO - remove
@@ -439,6 +987,9 @@ impl<'a> WidgetMut<'a, Flex> {
     ///
     /// [`add_default_spacer`]: Self::add_default_spacer
     pub fn insert_spacer(&mut self, idx: usize, mut len: f64) {
+        if !self.widget.check_index("insert_spacer", idx, true) {
+            return;
+        }
         if len < 0.0 {
             tracing::warn!("add_spacer called with negative length: {}", len);
         }
@@ -452,6 +1003,9 @@ impl<'a> WidgetMut<'a, Flex> {
 
     /// Add an empty spacer widget with a specific `flex` factor.
     pub fn insert_flex_spacer(&mut self, idx: usize, flex: f64) {
+        if !self.widget.check_index("insert_flex_spacer", idx, true) {
+            return;
+        }
         let flex = if flex >= 0.0 {
             flex
         } else {
@@ -465,12 +1019,88 @@ impl<'a> WidgetMut<'a, Flex> {
     }
 
     pub fn remove_child(&mut self, idx: usize) {
+        if !self.widget.check_index("remove_child", idx, false) {
+            return;
+        }
         self.widget.children.remove(idx);
-        self.ctx.widget_state.needs_layout = true;
+        self.ctx.children_changed();
+    }
+
+    /// Move the child at `from_idx` to `to_idx`, shifting the children in between.
+    ///
+    /// Unlike removing the child and inserting it again, this keeps it in the widget
+    /// tree, along with its state and flex parameters. Spacers can be moved too.
+    pub fn move_child(&mut self, from_idx: usize, to_idx: usize) {
+        if !self.widget.check_index("move_child", from_idx, false)
+            || !self.widget.check_index("move_child", to_idx, false)
+        {
+            return;
+        }
+        let child = self.widget.children.remove(from_idx);
+        self.widget.children.insert(to_idx, child);
+        // The focus chain follows the order of the children.
+        self.ctx.children_changed();
+    }
+
+    /// Swap the children at indices `a` and `b`.
+    ///
+    /// Like [`move_child`](Self::move_child), this keeps both children in the widget tree.
+    pub fn swap_children(&mut self, a: usize, b: usize) {
+        if !self.widget.check_index("swap_children", a, false)
+            || !self.widget.check_index("swap_children", b, false)
+        {
+            return;
+        }
+        self.widget.children.swap(a, b);
+        self.ctx.children_changed();
+    }
+
+    /// Set the flex factor, alignment, margins and size bounds of the child at `idx`.
+    ///
+    /// The child becomes a non-flex child if the flex factor isn't positive, e.g. with
+    /// [`FlexParams::non_flex`], which keeps the alignment and margins of `params`. This
+    /// has no effect on spacers.
+    pub fn update_child_flex_params(&mut self, idx: usize, params: impl Into<FlexParams>) {
+        if !self
+            .widget
+            .check_index("update_child_flex_params", idx, false)
+        {
+            return;
+        }
+        let child = &mut self.widget.children[idx];
+        let placeholder = Child::FixedSpacer(0.0, 0.0);
+        let widget = match std::mem::replace(child, placeholder) {
+            Child::Fixed { widget, .. } | Child::Flex { widget, .. } => widget,
+            spacer => {
+                tracing::warn!("update_child_flex_params called on a spacer");
+                *child = spacer;
+                return;
+            }
+        };
+        *child = Child::with_params(widget, params.into());
+        self.ctx.request_layout();
+    }
+
+    /// What the child at `idx` is, see [`Flex::child_kind`].
+    pub fn child_kind(&self, idx: usize) -> Option<ChildKind> {
+        self.widget.child_kind(idx)
+    }
+
+    /// What each child is, see [`Flex::iter_children`].
+    pub fn iter_children(&self) -> impl Iterator<Item = ChildKind> + '_ {
+        self.widget.iter_children()
+    }
+
+    /// Where the child at `idx` was placed, see [`Flex::child_rect`].
+    pub fn child_rect(&self, idx: usize) -> Option<Rect> {
+        self.widget.child_rect(idx)
     }
 
     // FIXME - Remove Box
     pub fn child_mut(&mut self, idx: usize) -> Option<WidgetMut<'_, Box<dyn Widget>>> {
+        if !self.widget.check_index("child_mut", idx, false) {
+            return None;
+        }
         let child = match &mut self.widget.children[idx] {
             Child::Fixed { widget, .. } | Child::Flex { widget, .. } => widget,
             Child::FixedSpacer(..) => return None,
@@ -482,7 +1112,36 @@ impl<'a> WidgetMut<'a, Flex> {
 
     pub fn clear(&mut self) {
         self.widget.children.clear();
-        self.ctx.widget_state.needs_layout = true;
+        self.ctx.children_changed();
+    }
+
+    /// Set the widget shown, centered, when the container has no children other than spacers.
+    ///
+    /// The placeholder is stashed as soon as a child is added, and keeps its state until
+    /// it is shown again. It isn't counted by [`len`](Flex::len), and child indices
+    /// ignore it.
+    pub fn set_placeholder(&mut self, placeholder: impl Widget) {
+        self.widget.placeholder = Some(WidgetPod::new(Box::new(placeholder)));
+        self.ctx.children_changed();
+        self.ctx.request_layout();
+    }
+
+    /// Remove the placeholder set with [`set_placeholder`](Self::set_placeholder).
+    pub fn remove_placeholder(&mut self) {
+        if self.widget.placeholder.take().is_some() {
+            self.ctx.children_changed();
+            self.ctx.request_layout();
+        }
+    }
+
+    // FIXME - Remove Box
+    /// Return a [`WidgetMut`] to the placeholder, if one is set.
+    ///
+    /// The placeholder can be edited while it's stashed, e.g. to change the text it will
+    /// show once the container is empty again.
+    pub fn placeholder_mut(&mut self) -> Option<WidgetMut<'_, Box<dyn Widget>>> {
+        let placeholder = self.widget.placeholder.as_mut()?;
+        Some(self.ctx.get_mut(placeholder))
     }
 }
 
@@ -491,29 +1150,75 @@ impl Widget for Flex {
         for child in self.children.iter_mut().filter_map(|x| x.widget_mut()) {
             child.on_pointer_event(ctx, event);
         }
+        if let Some(placeholder) = &mut self.placeholder {
+            placeholder.on_pointer_event(ctx, event);
+        }
+        if self.reorderable {
+            self.reorder_on_pointer_event(ctx, event);
+        }
     }
 
     fn on_text_event(&mut self, ctx: &mut EventCtx, event: &TextEvent) {
         for child in self.children.iter_mut().filter_map(|x| x.widget_mut()) {
             child.on_text_event(ctx, event);
         }
+        if let Some(placeholder) = &mut self.placeholder {
+            placeholder.on_text_event(ctx, event);
+        }
     }
 
     fn on_access_event(&mut self, ctx: &mut EventCtx, event: &AccessEvent) {
         for child in self.children.iter_mut().filter_map(|x| x.widget_mut()) {
             child.on_access_event(ctx, event);
         }
+        if let Some(placeholder) = &mut self.placeholder {
+            placeholder.on_access_event(ctx, event);
+        }
     }
 
     fn on_status_change(&mut self, _ctx: &mut LifeCycleCtx, _event: &StatusChange) {}
 
     fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle) {
+        // The shadow of the elevation may have changed, along with the paint insets.
+        if matches!(event, LifeCycle::ThemeChanged) && self.elevation != Elevation::default() {
+            ctx.request_layout();
+        }
         for child in self.children.iter_mut().filter_map(|x| x.widget_mut()) {
             child.lifecycle(ctx, event);
         }
+        if let Some(placeholder) = &mut self.placeholder {
+            placeholder.lifecycle(ctx, event);
+        }
+    }
+
+    fn on_anim_frame(&mut self, ctx: &mut LifeCycleCtx, interval: u64) {
+        if self.reorder_shifts.is_empty() {
+            return;
+        }
+        let distance = if ctx.reduced_motion() {
+            f64::INFINITY
+        } else {
+            REORDER_SHIFT_SPEED * interval as f64 * 1e-9
+        };
+        if !self.step_reorder_shifts(distance) {
+            ctx.request_anim_frame();
+        }
+        ctx.request_layout();
     }
 
     fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints) -> Size {
+        ctx.set_paint_insets(ctx.theme().shadow_style(self.elevation).insets());
+        if let Some(placeholder) = &mut self.placeholder {
+            let is_empty = self.children.iter().all(|child| child.widget().is_none());
+            ctx.set_stashed(placeholder, !is_empty);
+            if is_empty {
+                return layout_placeholder(placeholder, ctx, bc);
+            }
+        }
+        if self.wrap != FlexWrap::NoWrap {
+            return self.layout_wrapped(ctx, bc);
+        }
+
         // we loosen our constraints when passing to children.
         let loosened_bc = bc.loosen();
 
@@ -525,11 +1230,15 @@ impl Widget for Flex {
         let mut any_use_baseline = self.cross_alignment == CrossAxisAlignment::Baseline;
 
         // Measure non-flex children.
-        let mut major_non_flex = 0.0;
+        let mut major_non_flex = self.gap * self.children.len().saturating_sub(1) as f64;
         let mut flex_sum = 0.0;
         for child in &mut self.children {
+            let (margin_before, margin_after) = child.margins();
+            major_non_flex += margin_before + margin_after;
             match child {
-                Child::Fixed { widget, alignment } => {
+                Child::Fixed {
+                    widget, alignment, ..
+                } => {
                     any_use_baseline &= *alignment == Some(CrossAxisAlignment::Baseline);
 
                     let child_bc = self.direction.constraints(&loosened_bc, 0.0, f64::INFINITY);
@@ -567,14 +1276,21 @@ impl Widget for Flex {
         let mut remainder: f64 = 0.0;
 
         let mut major_flex: f64 = 0.0;
-        let px_per_flex = remaining / flex_sum;
+        let shares = self.distribute_flex(remaining);
+        let px_per_flex = shares.px_per_flex;
         // Measure flex children.
-        for child in &mut self.children {
+        for (child, clamped_major) in self.children.iter_mut().zip(shares.clamped_majors) {
             match child {
                 Child::Flex { widget, flex, .. } => {
-                    let desired_major = (*flex) * px_per_flex + remainder;
-                    let actual_major = desired_major.round();
-                    remainder = desired_major - actual_major;
+                    let actual_major = match clamped_major {
+                        Some(major) => major.round(),
+                        None => {
+                            let desired_major = (*flex) * px_per_flex + remainder;
+                            let actual_major = desired_major.round();
+                            remainder = desired_major - actual_major;
+                            actual_major
+                        }
+                    };
 
                     let child_bc = self.direction.constraints(&loosened_bc, 0.0, actual_major);
                     let child_size = widget.layout(ctx, &child_bc);
@@ -601,8 +1317,11 @@ impl Widget for Flex {
             (remaining - major_flex).max(0.0)
         } else {
             // if we are *not* expected to fill our available space this usually
-            // means we don't have any extra, unless dictated by our constraints.
-            (self.direction.major(bc.min()) - (major_non_flex + major_flex)).max(0.0)
+            // means we don't have any extra, unless dictated by our constraints,
+            // or left by flex children which all reached their maximum size.
+            (self.direction.major(bc.min()) - (major_non_flex + major_flex))
+                .max(shares.surplus)
+                .max(0.0)
         };
 
         let mut spacing = Spacing::new(self.main_alignment, extra, self.children.len());
@@ -617,10 +1336,19 @@ impl Widget for Flex {
         let extra_height = minor - minor_dim.min(minor);
 
         let mut major = spacing.next().unwrap_or(0.);
+        // Children are placed once our size is known, so that the main axis can be flipped.
+        let mut child_positions = Vec::with_capacity(self.children.len());
 
-        for child in &mut self.children {
+        for (idx, child) in self.children.iter_mut().enumerate() {
+            if idx > 0 {
+                major += self.gap;
+            }
+            let (margin_before, margin_after) = child.margins();
+            major += margin_before;
             match child {
-                Child::Fixed { widget, alignment }
+                Child::Fixed {
+                    widget, alignment, ..
+                }
                 | Child::Flex {
                     widget, alignment, ..
                 } => {
@@ -652,8 +1380,8 @@ impl Widget for Flex {
                     };
 
                     let child_pos: Point = self.direction.pack(major, child_minor_offset).into();
-                    ctx.place_child(widget, child_pos);
-                    major += self.direction.major(child_size).expand();
+                    child_positions.push(child_pos);
+                    major += self.direction.major(child_size).expand() + margin_after;
                     major += spacing.next().unwrap_or(0.);
                 }
                 Child::FlexedSpacer(_, calculated_size)
@@ -683,10 +1411,31 @@ impl Widget for Flex {
             bc.constrain(my_size)
         };
 
+        let flip = self.flips_main_axis(ctx);
+        let widgets = (self.children.iter_mut().enumerate())
+            .filter_map(|(idx, child)| Some((idx, child.widget_mut()?)));
+        for ((idx, widget), mut child_pos) in widgets.zip(child_positions) {
+            if flip {
+                child_pos =
+                    self.direction
+                        .flip_major(child_pos, widget.layout_rect().size(), my_size);
+            }
+            // Children being reordered are moved on the main axis.
+            if let Some(&shift) = self.reorder_shifts.get(idx) {
+                child_pos += Vec2::from(self.direction.pack(shift, 0.0));
+            }
+            ctx.place_child(widget, child_pos);
+        }
+
+        // The baseline of a column is the one of its bottom child.
+        let bottom_child = if self.reversed {
+            self.children.first()
+        } else {
+            self.children.last()
+        };
         let baseline_offset = match self.direction {
             Axis::Horizontal => max_below_baseline,
-            Axis::Vertical => (self.children)
-                .last()
+            Axis::Vertical => bottom_child
                 .map(|last| {
                     let child = last.widget();
                     if let Some(widget) = child {
@@ -711,9 +1460,36 @@ impl Widget for Flex {
     }
 
     fn paint(&mut self, ctx: &mut PaintCtx, scene: &mut Scene) {
+        let shadow_style = ctx.theme().shadow_style(self.elevation);
+        let shape = ctx.size().to_rounded_rect(0.0);
+        self.shadow.paint(scene, shape, &shadow_style);
+
         for child in self.children.iter_mut().filter_map(|x| x.widget_mut()) {
             child.paint(ctx, scene);
         }
+        if let Some(placeholder) = &mut self.placeholder {
+            if placeholder.state().is_stashed {
+                ctx.skip_child(placeholder);
+            } else {
+                placeholder.paint(ctx, scene);
+            }
+        }
+
+        // Show where the child being reordered will be dropped.
+        if let Some(edge) = self.reorder_drag.as_ref().and_then(ReorderDrag::drop_edge) {
+            let minor = self.direction.minor(ctx.size());
+            let line = Line::new(
+                self.direction.pack(edge, 0.0),
+                self.direction.pack(edge, minor),
+            );
+            scene.stroke(
+                &Stroke::new(2.0),
+                Affine::IDENTITY,
+                crate::theme::PRIMARY_LIGHT,
+                None,
+                &line,
+            );
+        }
 
         // paint the baseline if we're debugging layout
         if ctx.debug_paint && ctx.widget_state.baseline_offset != 0.0 {
@@ -734,12 +1510,16 @@ impl Widget for Flex {
         for child in self.children.iter_mut().filter_map(|x| x.widget_mut()) {
             child.accessibility(ctx);
         }
+        if let Some(placeholder) = &mut self.placeholder {
+            placeholder.accessibility(ctx);
+        }
     }
 
     fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
         self.children
             .iter()
             .filter_map(|child| child.widget())
+            .chain(&self.placeholder)
             .map(|widget_pod| widget_pod.as_dyn())
             .collect()
     }
@@ -751,6 +1531,29 @@ impl Widget for Flex {
 
 // --- Others impls ---
 
+/// Lay out the placeholder of an empty [`Flex`], centered in the available space.
+fn layout_placeholder(
+    placeholder: &mut WidgetPod<Box<dyn Widget>>,
+    ctx: &mut LayoutCtx,
+    bc: &BoxConstraints,
+) -> Size {
+    let child_size = placeholder.layout(ctx, &bc.loosen());
+    // Take all the available space, unless it's unbounded.
+    let fill = |max: f64, child: f64| if max.is_finite() { max } else { child };
+    let max = bc.max();
+    let size = bc.constrain(Size::new(
+        fill(max.width, child_size.width),
+        fill(max.height, child_size.height),
+    ));
+    let origin = ((size - child_size).to_vec2() / 2.0).to_point();
+    ctx.place_child(placeholder, origin);
+
+    let extra_bottom_padding = size.height - placeholder.layout_rect().max_y();
+    ctx.set_baseline_offset(placeholder.baseline_offset() + extra_bottom_padding);
+    trace!("Computed layout with placeholder: size={}", size);
+    size
+}
+
 impl Axis {
     /// Get the axis perpendicular to this one.
     pub fn cross(self) -> Axis {
@@ -787,6 +1590,13 @@ impl Axis {
     }
 
     /// Extract the coordinate locating the argument with respect to this axis.
+    /// Mirror the origin `pos` of a rect of size `child_size` on this axis, inside of a
+    /// container of size `size`.
+    fn flip_major(self, pos: Point, child_size: Size, size: Size) -> Point {
+        let major = self.major(size) - self.major_pos(pos) - self.major(child_size);
+        self.pack(major, self.minor_pos(pos)).into()
+    }
+
     pub fn major_pos(self, pos: Point) -> f64 {
         match self {
             Axis::Horizontal => pos.x,
@@ -861,8 +1671,63 @@ impl FlexParams {
         FlexParams {
             flex,
             alignment: alignment.into(),
+            margin_before: None,
+            margin_after: None,
+            min_major: None,
+            max_major: None,
+        }
+    }
+
+    /// `FlexParams` for a non-flex child, with an optional [`CrossAxisAlignment`].
+    ///
+    /// This is meant for [`update_child_flex_params`](WidgetMut::update_child_flex_params),
+    /// to stop a child from flexing while keeping its alignment and margins.
+    pub fn non_flex(alignment: impl Into<Option<CrossAxisAlignment>>) -> Self {
+        FlexParams {
+            flex: 0.0,
+            ..FlexParams::new(1.0, alignment)
         }
     }
+
+    /// Builder-style method to add space on the main axis before the child, i.e. above
+    /// it in a column, or on its leading side in a row.
+    ///
+    /// Unlike a spacer, this doesn't take up an index among the children of the
+    /// container, and it adds to the [`gap`](Flex::gap) rather than replacing it.
+    pub fn margin_before(mut self, margin: f64) -> Self {
+        self.margin_before = Some(margin.max(0.0));
+        self
+    }
+
+    /// Builder-style method to add space on the main axis after the child.
+    ///
+    /// See [`margin_before`](Self::margin_before).
+    pub fn margin_after(mut self, margin: f64) -> Self {
+        self.margin_after = Some(margin.max(0.0));
+        self
+    }
+
+    /// Builder-style method to set the size the child's share of the space on the main
+    /// axis can't go below.
+    ///
+    /// The space needed to give it that share is taken from the other flex children.
+    /// This has no effect in a [wrapping](Flex::wrap) container.
+    pub fn min_size(mut self, min: f64) -> Self {
+        self.min_major = Some(min.max(0.0));
+        self
+    }
+
+    /// Builder-style method to set the size the child's share of the space on the main
+    /// axis can't go above.
+    ///
+    /// The space it doesn't take is shared by the other flex children. If all flex
+    /// children reach their maximum, what is left is distributed according to the
+    /// [`MainAxisAlignment`] of the container. This has no effect in a
+    /// [wrapping](Flex::wrap) container.
+    pub fn max_size(mut self, max: f64) -> Self {
+        self.max_major = Some(max.max(0.0));
+        self
+    }
 }
 
 impl CrossAxisAlignment {
@@ -981,21 +1846,174 @@ impl From<f64> for FlexParams {
     }
 }
 
+/// What a child of a [`Flex`] is, as reported by [`Flex::child_kind`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ChildKind {
+    /// A widget, flexible or not.
+    Widget { id: WidgetId },
+    /// A spacer of the given length.
+    FixedSpacer(f64),
+    /// A spacer with the given flex factor.
+    FlexSpacer(f64),
+}
+
 enum Child {
     Fixed {
         widget: WidgetPod<Box<dyn Widget>>,
         alignment: Option<CrossAxisAlignment>,
+        /// The space before and after the child on the main axis.
+        margins: (f64, f64),
     },
     Flex {
         widget: WidgetPod<Box<dyn Widget>>,
         alignment: Option<CrossAxisAlignment>,
         flex: f64,
+        margins: (f64, f64),
+        /// The bounds of the child's share of the main axis.
+        bounds: (Option<f64>, Option<f64>),
     },
     FixedSpacer(f64, f64),
     FlexedSpacer(f64, f64),
 }
 
+/// A child being dragged to another index of a reorderable [`Flex`].
+struct ReorderDrag {
+    /// The index of the dragged child.
+    index: usize,
+    /// Where the pointer was pressed, in local coordinates.
+    press_origin: Point,
+    /// Whether the pointer moved further than the drag threshold.
+    moved: bool,
+    /// How far the child was dragged on the main axis.
+    offset: f64,
+    /// The indices of the children which aren't spacers, with their extent on the main
+    /// axis when the drag started.
+    spans: Vec<(usize, (f64, f64))>,
+    /// The index the child is moved to if it's dropped now.
+    target: usize,
+}
+
+impl ReorderDrag {
+    fn span(&self, idx: usize) -> Option<(f64, f64)> {
+        self.spans
+            .iter()
+            .find_map(|&(span_idx, span)| (span_idx == idx).then_some(span))
+    }
+
+    /// The index of the furthest child whose center the center of the dragged child crossed.
+    fn drop_target(&self) -> usize {
+        let Some(dragged) = self.span(self.index) else {
+            return self.index;
+        };
+        let start_center = span_center(dragged);
+        let center = start_center + self.offset;
+        let mut target = self.index;
+        for &(idx, span) in &self.spans {
+            let span_center = span_center(span);
+            let crossed = (center - span_center) * (span_center - start_center).signum() > 0.0;
+            if crossed && idx > self.index {
+                target = target.max(idx);
+            } else if crossed && idx < self.index {
+                target = target.min(idx);
+            }
+        }
+        target
+    }
+
+    /// The edge of the target child on the far side from the dragged child, next to
+    /// which it's dropped.
+    fn drop_edge(&self) -> Option<f64> {
+        if !self.moved || self.target == self.index {
+            return None;
+        }
+        let dragged = self.span(self.index)?;
+        let target = self.span(self.target)?;
+        if span_center(target) > span_center(dragged) {
+            Some(target.1)
+        } else {
+            Some(target.0)
+        }
+    }
+
+    /// Where the dragged child starts on the main axis once it's dropped.
+    fn landing_start(&self) -> Option<f64> {
+        let dragged = self.span(self.index)?;
+        let target = self.span(self.target)?;
+        if span_center(target) > span_center(dragged) {
+            Some(target.1 - (dragged.1 - dragged.0))
+        } else {
+            Some(target.0)
+        }
+    }
+}
+
+fn span_center(span: (f64, f64)) -> f64 {
+    (span.0 + span.1) / 2.0
+}
+
+/// How the space left by the non-flex children is shared by the flex children of a [`Flex`].
+struct FlexShares {
+    /// The space per flex factor of the children within their bounds.
+    px_per_flex: f64,
+    /// The share of each child clamped to its bounds, by index.
+    clamped_majors: Vec<Option<f64>>,
+    /// The space left when all flex children are clamped to their maximum size.
+    surplus: f64,
+}
+
+/// A line of children of a wrapping [`Flex`], see [`FlexWrap::Wrap`].
+struct FlexLine {
+    /// The indices of the children on the line.
+    children: Range<usize>,
+    major: f64,
+    minor: f64,
+    max_above_baseline: f64,
+    max_below_baseline: f64,
+}
+
 impl Child {
+    /// A child added as a flex child, which is a non-flex child if `params.flex` isn't
+    /// positive.
+    fn new_flex(widget: WidgetPod<Box<dyn Widget>>, params: FlexParams) -> Self {
+        if params.flex <= 0.0 {
+            // TODO
+            tracing::warn!("Flex value should be > 0.0. To add a non-flex child use the add_child or with_child methods.\nSee the docs for masonry::widget::Flex for more information");
+        }
+        Child::with_params(widget, params)
+    }
+
+    /// A flex child, or a non-flex child with the alignment and margins of `params` if
+    /// `params.flex` isn't positive.
+    fn with_params(widget: WidgetPod<Box<dyn Widget>>, params: FlexParams) -> Self {
+        let margins = (
+            params.margin_before.unwrap_or(0.0),
+            params.margin_after.unwrap_or(0.0),
+        );
+        if params.flex > 0.0 {
+            Child::Flex {
+                widget,
+                alignment: params.alignment,
+                flex: params.flex,
+                margins,
+                bounds: (params.min_major, params.max_major),
+            }
+        } else {
+            Child::Fixed {
+                widget,
+                alignment: params.alignment,
+                margins,
+            }
+        }
+    }
+
+    /// The space before and after the child on the main axis, which is zero for spacers.
+    fn margins(&self) -> (f64, f64) {
+        match self {
+            Child::Fixed { margins, .. } | Child::Flex { margins, .. } => *margins,
+            _ => (0.0, 0.0),
+        }
+    }
+
     fn widget_mut(&mut self) -> Option<&mut WidgetPod<Box<dyn Widget>>> {
         match self {
             Child::Fixed { widget, .. } | Child::Flex { widget, .. } => Some(widget),
@@ -1008,14 +2026,26 @@ impl Child {
             _ => None,
         }
     }
+
+    fn kind(&self) -> ChildKind {
+        match self {
+            Child::Fixed { widget, .. } | Child::Flex { widget, .. } => {
+                ChildKind::Widget { id: widget.id() }
+            }
+            Child::FixedSpacer(len, _) => ChildKind::FixedSpacer(*len),
+            Child::FlexedSpacer(flex, _) => ChildKind::FlexSpacer(*flex),
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::time::Duration;
+
     use super::*;
     use crate::assert_render_snapshot;
-    use crate::testing::TestHarness;
-    use crate::widget::Label;
+    use crate::testing::{widget_ids, TestHarness};
+    use crate::widget::{Label, SizedBox, Textbox};
 
     #[test]
     #[allow(clippy::cognitive_complexity)]
@@ -1378,6 +2408,118 @@ mod tests {
         assert!(image_1 == image_2);
     }
 
+    #[test]
+    fn margins_replace_spacers() {
+        let image_1 = {
+            let widget = Flex::column()
+                .with_child(Label::new("a"))
+                .with_spacer(5.0)
+                .with_flex_child(Label::new("b"), 1.0)
+                .with_spacer(10.0)
+                .with_child(Label::new("c"));
+
+            let mut harness = TestHarness::create(widget);
+            harness.render()
+        };
+
+        let image_2 = {
+            let widget = Flex::column()
+                .with_child(Label::new("a"))
+                .with_flex_child(
+                    Label::new("b"),
+                    FlexParams::new(1.0, None)
+                        .margin_before(5.0)
+                        .margin_after(10.0),
+                )
+                .with_child(Label::new("c"));
+
+            let mut harness = TestHarness::create(widget);
+            harness.render()
+        };
+
+        let image_3 = {
+            let widget = Flex::column()
+                .with_child(Label::new("a"))
+                .with_flex_child(Label::new("b"), 1.0)
+                .with_child(Label::new("c"));
+
+            let mut harness = TestHarness::create(widget);
+            harness.edit_root_widget(|mut flex| {
+                let mut flex = flex.downcast::<Flex>();
+                flex.update_child_flex_params(
+                    1,
+                    FlexParams::new(1.0, None)
+                        .margin_before(5.0)
+                        .margin_after(10.0),
+                );
+            });
+            harness.render()
+        };
+
+        // We don't use assert_eq because we don't want rich assert
+        assert!(image_1 == image_2);
+        assert!(image_1 == image_3);
+    }
+
+    #[test]
+    fn flex_child_stays_within_max_size() {
+        let [capped_id, other_id] = widget_ids();
+        let widget = Flex::row()
+            .with_flex_child(
+                SizedBox::new_with_id(SizedBox::empty().expand_width(), capped_id),
+                FlexParams::new(10.0, None).max_size(50.0),
+            )
+            .with_flex_child(
+                SizedBox::new_with_id(SizedBox::empty().expand_width(), other_id),
+                1.0,
+            );
+        let mut harness = TestHarness::create_with_size(widget, Size::new(300.0, 100.0));
+
+        // The space the capped child doesn't take goes to the other child.
+        let capped_rect = harness.get_widget(capped_id).state().window_layout_rect();
+        let other_rect = harness.get_widget(other_id).state().window_layout_rect();
+        assert_eq!(capped_rect.width(), 50.0);
+        assert_eq!(other_rect.width(), 250.0);
+
+        // Once all flex children are capped, the space left is extra space.
+        harness.edit_root_widget(|mut flex| {
+            let mut flex = flex.downcast::<Flex>();
+            flex.set_main_axis_alignment(MainAxisAlignment::Center);
+            flex.update_child_flex_params(1, FlexParams::new(1.0, None).max_size(100.0));
+        });
+        let capped_rect = harness.get_widget(capped_id).state().window_layout_rect();
+        let other_rect = harness.get_widget(other_id).state().window_layout_rect();
+        assert_eq!(capped_rect.width(), 50.0);
+        assert_eq!(other_rect.width(), 100.0);
+        assert_eq!(capped_rect.x0, 75.0);
+    }
+
+    #[test]
+    fn non_flex_params_keep_alignment() {
+        let [label_id] = widget_ids();
+        let widget = Flex::column()
+            .cross_axis_alignment(CrossAxisAlignment::Start)
+            .with_flex_child(SizedBox::new_with_id(Label::new("hello"), label_id), 1.0);
+        let mut harness = TestHarness::create_with_size(widget, Size::new(200.0, 100.0));
+        assert_eq!(
+            harness.get_widget(label_id).state().window_layout_rect().x0,
+            0.0
+        );
+
+        harness.edit_root_widget(|mut flex| {
+            let mut flex = flex.downcast::<Flex>();
+            flex.update_child_flex_params(
+                0,
+                FlexParams::non_flex(CrossAxisAlignment::End).margin_before(10.0),
+            );
+            // The child is the box around the label.
+            assert!(matches!(flex.child_kind(0), Some(ChildKind::Widget { .. })));
+        });
+        let rect = harness.get_widget(label_id).state().window_layout_rect();
+        assert_eq!(rect.x1, 200.0);
+        assert_eq!(rect.y0, 10.0);
+    }
+
     #[test]
     fn get_flex_child() {
         let widget = Flex::column()
@@ -1403,7 +2545,434 @@ mod tests {
 
             assert!(flex.child_mut(2).is_none());
         });
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Flex::remove_child: index 3 is out of bounds for Flex with 2 children"
+    )]
+    fn remove_child_out_of_bounds() {
+        let widget = Flex::column()
+            .with_child(Label::new("hello"))
+            .with_child(Label::new("world"));
+
+        let mut harness = TestHarness::create(widget);
+        harness.edit_root_widget(|mut flex| {
+            let mut flex = flex.downcast::<Flex>();
+            flex.remove_child(3);
+        });
+    }
+
+    #[test]
+    fn child_kinds_report_spacers_and_ids() {
+        let [label_id] = widget_ids();
+        let widget = Flex::row()
+            .with_child_id(Label::new("hello"), label_id)
+            .with_spacer(4.0)
+            .with_flex_child(Label::new("world"), 2.0)
+            .with_flex_spacer(1.0);
+        let world_id = widget.children()[1].id();
+
+        assert_eq!(
+            widget.child_kind(0),
+            Some(ChildKind::Widget { id: label_id })
+        );
+        assert_eq!(
+            widget.iter_children().collect::<Vec<_>>(),
+            [
+                ChildKind::Widget { id: label_id },
+                ChildKind::FixedSpacer(4.0),
+                ChildKind::Widget { id: world_id },
+                ChildKind::FlexSpacer(1.0),
+            ]
+        );
+        assert_eq!(widget.child_kind(4), None);
+
+        let mut harness = TestHarness::create(widget);
+        harness.edit_root_widget(|mut flex| {
+            let mut flex = flex.downcast::<Flex>();
+            flex.remove_child(1);
+            assert_eq!(flex.child_kind(1), Some(ChildKind::Widget { id: world_id }));
+            assert_eq!(flex.iter_children().count(), 3);
+        });
+    }
+
+    #[test]
+    fn child_rects_follow_gaps() {
+        let widget = Flex::row()
+            .gap(6.0)
+            .with_child(Label::new("a"))
+            .with_child(Label::new("bb"))
+            .with_child(Label::new("ccc"))
+            .with_spacer(10.0);
+
+        let mut harness = TestHarness::create(widget);
+        harness.edit_root_widget(|mut flex| {
+            let flex = flex.downcast::<Flex>();
+            let rects: Vec<_> = (0..3).map(|idx| flex.child_rect(idx).unwrap()).collect();
+            assert_eq!(rects[0].x0, 0.0);
+            for pair in rects.windows(2) {
+                assert!(pair[0].width() > 0.0);
+                assert_eq!(pair[1].x0, pair[0].x1 + 6.0);
+            }
+            assert_eq!(flex.child_rect(3), None);
+            assert_eq!(flex.child_rect(4), None);
+        });
+    }
+
+    #[test]
+    fn move_child_keeps_state() {
+        let [textbox_id, label_id] = widget_ids();
+        let widget = Flex::column()
+            .with_child_id(Textbox::new(""), textbox_id)
+            .with_child_id(Label::new("hello"), label_id)
+            .with_flex_spacer(1.0);
+
+        let mut harness = TestHarness::create(widget);
+        harness.mouse_click_on(textbox_id);
+        harness.keyboard_type_chars("typed");
+
+        harness.edit_root_widget(|mut flex| {
+            let mut flex = flex.downcast::<Flex>();
+            flex.move_child(0, 2);
+
+            let mut child = flex.child_mut(2).unwrap();
+            assert_eq!(child.ctx.widget_id(), textbox_id);
+            assert_eq!(child.downcast::<Textbox>().text(), "typed");
+            std::mem::drop(child);
+
+            let child = flex.child_mut(0).unwrap();
+            assert_eq!(child.ctx.widget_id(), label_id);
+        });
+
+        let textbox = harness.get_widget(textbox_id);
+        assert_eq!(
+            textbox.downcast::<Textbox>().unwrap().text().to_string(),
+            "typed"
+        );
+        let label_rect = harness.get_widget(label_id).state().layout_rect();
+        let textbox_rect = textbox.state().layout_rect();
+        assert!(label_rect.y1 <= textbox_rect.y0);
+    }
+
+    #[test]
+    fn moved_child_keeps_focus_and_tab_order() {
+        let [first_id, second_id] = widget_ids();
+        let widget = Flex::column()
+            .with_child_id(Textbox::new(""), first_id)
+            .with_child_id(Textbox::new(""), second_id);
+
+        let mut harness = TestHarness::create(widget);
+        harness.mouse_click_on(first_id);
+        harness.edit_root_widget(|mut flex| {
+            let mut flex = flex.downcast::<Flex>();
+            flex.move_child(0, 1);
+        });
+        assert_eq!(harness.focused_widget().map(|w| w.id()), Some(first_id));
+
+        // Tab goes through the children in their new order.
+        harness.move_focus(false);
+        assert_eq!(harness.focused_widget().map(|w| w.id()), Some(second_id));
+    }
+
+    #[test]
+    fn swap_spacer_and_child() {
+        let [label_id] = widget_ids();
+        let widget = Flex::column()
+            .with_spacer(20.0)
+            .with_child_id(Label::new("hello"), label_id);
+
+        let mut harness = TestHarness::create(widget);
+        harness.edit_root_widget(|mut flex| {
+            let mut flex = flex.downcast::<Flex>();
+            flex.swap_children(0, 1);
+
+            assert_eq!(flex.child_mut(0).unwrap().ctx.widget_id(), label_id);
+            assert!(flex.child_mut(1).is_none());
+        });
+
+        let label_rect = harness.get_widget(label_id).state().layout_rect();
+        assert_eq!(label_rect.y0, 0.0);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Flex::move_child: index 2 is out of bounds for Flex with 2 children"
+    )]
+    fn move_child_out_of_bounds() {
+        let widget = Flex::column()
+            .with_child(Label::new("hello"))
+            .with_child(Label::new("world"));
+
+        let mut harness = TestHarness::create(widget);
+        harness.edit_root_widget(|mut flex| {
+            let mut flex = flex.downcast::<Flex>();
+            flex.move_child(0, 2);
+        });
+    }
+
+    #[test]
+    fn placeholder_is_centered_when_only_spacers() {
+        let widget = Flex::column()
+            .with_spacer(20.0)
+            .with_flex_spacer(1.0)
+            .with_placeholder(SizedBox::empty().width(40.0).height(20.0));
+
+        let harness = TestHarness::create_with_size(widget, Size::new(200.0, 100.0));
+        let placeholder = harness.root_widget().children()[0];
+        assert!(!placeholder.state().is_stashed);
+        assert_eq!(
+            placeholder.state().layout_rect(),
+            Rect::new(80.0, 40.0, 120.0, 60.0)
+        );
+    }
+
+    #[test]
+    fn placeholder_snapshot() {
+        let widget = Flex::column()
+            .with_spacer(20.0)
+            .with_placeholder(Label::new("No results"));
+
+        let mut harness = TestHarness::create_with_size(widget, Size::new(200.0, 100.0));
+        assert_render_snapshot!(harness, "placeholder_centered");
+    }
+
+    #[test]
+    fn placeholder_hidden_while_flex_has_children() {
+        let [label_id] = widget_ids();
+        let widget = Flex::column()
+            .with_spacer(10.0)
+            .with_placeholder(Label::new("No results"));
+
+        let mut harness = TestHarness::create(widget);
+        let placeholder_id = harness.root_widget().children()[0].id();
+        harness.edit_root_widget(|mut flex| {
+            let mut flex = flex.downcast::<Flex>();
+            let mut placeholder = flex.placeholder_mut().unwrap();
+            placeholder.downcast::<Label>().set_text("Nothing here");
+        });
+
+        harness.edit_root_widget(|mut flex| {
+            let mut flex = flex.downcast::<Flex>();
+            flex.add_child_id(Label::new("hello"), label_id);
+            // The placeholder doesn't count as a child.
+            assert_eq!(flex.widget.len(), 2);
+        });
+        assert!(harness.get_widget(placeholder_id).state().is_stashed);
+        assert_eq!(harness.get_widget(label_id).state().layout_rect().y0, 10.0);
 
-        // TODO - test out-of-bounds access?
+        harness.edit_root_widget(|mut flex| {
+            let mut flex = flex.downcast::<Flex>();
+            flex.remove_child(1);
+        });
+        let placeholder = harness.get_widget(placeholder_id);
+        assert!(!placeholder.state().is_stashed);
+        let label = placeholder.downcast::<Label>().unwrap();
+        assert_eq!(label.text().as_ref(), "Nothing here");
+    }
+
+    #[test]
+    fn flex_row_wrap_snapshots() {
+        let widget = Flex::row()
+            .wrap(FlexWrap::Wrap)
+            .gap(10.0)
+            .with_child(Label::new("hello world"))
+            .with_child(Label::new("wrapping"))
+            .with_child(Label::new("several"))
+            .with_child(Label::new("wide labels"));
+
+        let mut harness = TestHarness::create_with_size(widget, Size::new(200.0, 100.0));
+
+        harness.edit_root_widget(|mut flex| {
+            let mut flex = flex.downcast::<Flex>();
+            flex.set_main_axis_alignment(MainAxisAlignment::Start);
+        });
+        assert_render_snapshot!(harness, "row_wrap_start");
+
+        harness.edit_root_widget(|mut flex| {
+            let mut flex = flex.downcast::<Flex>();
+            flex.set_main_axis_alignment(MainAxisAlignment::Center);
+        });
+        assert_render_snapshot!(harness, "row_wrap_center");
+
+        harness.edit_root_widget(|mut flex| {
+            let mut flex = flex.downcast::<Flex>();
+            flex.set_main_axis_alignment(MainAxisAlignment::SpaceBetween);
+        });
+        assert_render_snapshot!(harness, "row_wrap_spaceBetween");
+
+        harness.edit_root_widget(|mut flex| {
+            let mut flex = flex.downcast::<Flex>();
+            flex.set_main_axis_alignment(MainAxisAlignment::Start);
+            flex.set_wrap(FlexWrap::WrapReverse);
+        });
+        assert_render_snapshot!(harness, "row_wrap_reverse");
+    }
+
+    #[test]
+    fn wrapped_row_breaks_into_lines() {
+        let [first_id, second_id, third_id] = widget_ids();
+        let widget = Flex::row()
+            .wrap(FlexWrap::Wrap)
+            .gap(10.0)
+            .with_child_id(SizedBox::empty().width(40.0).height(20.0), first_id)
+            .with_child_id(SizedBox::empty().width(40.0).height(30.0), second_id)
+            .with_child_id(SizedBox::empty().width(40.0).height(10.0), third_id);
+
+        let mut harness = TestHarness::create_with_size(widget, Size::new(100.0, 100.0));
+        let origin =
+            |harness: &TestHarness, id| harness.get_widget(id).state().layout_rect().origin();
+
+        // The third child doesn't fit after the gap, and starts a second line below the
+        // first one, which is as tall as its tallest child.
+        assert_eq!(origin(&harness, first_id), Point::new(0.0, 5.0));
+        assert_eq!(origin(&harness, second_id), Point::new(50.0, 0.0));
+        assert_eq!(origin(&harness, third_id), Point::new(0.0, 40.0));
+
+        // The main axis alignment applies to each line.
+        harness.edit_root_widget(|mut flex| {
+            let mut flex = flex.downcast::<Flex>();
+            flex.set_main_axis_alignment(MainAxisAlignment::End);
+        });
+        assert_eq!(origin(&harness, first_id), Point::new(10.0, 5.0));
+        assert_eq!(origin(&harness, second_id), Point::new(60.0, 0.0));
+        assert_eq!(origin(&harness, third_id), Point::new(60.0, 40.0));
+
+        harness.edit_root_widget(|mut flex| {
+            let mut flex = flex.downcast::<Flex>();
+            flex.set_wrap(FlexWrap::NoWrap);
+        });
+        assert_eq!(origin(&harness, third_id).y, 45.0);
+    }
+
+    #[test]
+    fn reversed_row_keeps_indices() {
+        let [first_id, second_id] = widget_ids();
+        let widget = Flex::row()
+            .reversed(true)
+            .must_fill_main_axis(true)
+            .with_child_id(SizedBox::empty().width(10.0).height(10.0), first_id)
+            .with_spacer(5.0)
+            .with_child_id(SizedBox::empty().width(20.0).height(10.0), second_id);
+
+        let mut harness = TestHarness::create_with_size(widget, Size::new(100.0, 100.0));
+        let origin =
+            |harness: &TestHarness, id| harness.get_widget(id).state().layout_rect().origin();
+
+        // The first child is on the right, the spacer stays between the children, and
+        // the start of the main axis is on the right.
+        assert_eq!(origin(&harness, first_id), Point::new(90.0, 45.0));
+        assert_eq!(origin(&harness, second_id), Point::new(65.0, 45.0));
+
+        harness.edit_root_widget(|mut flex| {
+            let mut flex = flex.downcast::<Flex>();
+            flex.set_main_axis_alignment(MainAxisAlignment::End);
+            // Indices are the ones of the children vector.
+            flex.remove_child(1);
+        });
+        assert_eq!(origin(&harness, first_id), Point::new(20.0, 45.0));
+        assert_eq!(origin(&harness, second_id), Point::new(0.0, 45.0));
+    }
+
+    #[test]
+    fn reversed_column_grows_upward() {
+        let [first_id, second_id] = widget_ids();
+        let widget = Flex::column()
+            .reversed(true)
+            .with_child_id(SizedBox::empty().width(10.0).height(10.0), first_id)
+            .with_child_id(SizedBox::empty().width(10.0).height(20.0), second_id);
+
+        let mut harness = TestHarness::create_with_size(widget, Size::new(100.0, 100.0));
+        let origin =
+            |harness: &TestHarness, id| harness.get_widget(id).state().layout_rect().origin();
+
+        assert_eq!(origin(&harness, first_id), Point::new(45.0, 90.0));
+        assert_eq!(origin(&harness, second_id), Point::new(45.0, 70.0));
+        // The baseline is the one of the bottom child, which is the first one.
+        assert_eq!(harness.root_widget().state().baseline_offset, 0.0);
+
+        harness.edit_root_widget(|mut flex| {
+            let mut flex = flex.downcast::<Flex>();
+            flex.set_reversed(false);
+        });
+        assert_eq!(origin(&harness, first_id), Point::new(45.0, 0.0));
+        assert_eq!(origin(&harness, second_id), Point::new(45.0, 10.0));
+        assert_eq!(harness.root_widget().state().baseline_offset, 70.0);
+    }
+
+    #[test]
+    fn wrap_reverse_stacks_lines_from_the_end() {
+        let [first_id, second_id] = widget_ids();
+        let widget = Flex::row()
+            .wrap(FlexWrap::WrapReverse)
+            .cross_axis_alignment(CrossAxisAlignment::Start)
+            .with_child_id(SizedBox::empty().width(60.0).height(20.0), first_id)
+            .with_child_id(SizedBox::empty().width(60.0).height(30.0), second_id);
+
+        let harness = TestHarness::create_with_size(widget, Size::new(100.0, 100.0));
+        let first_rect = harness.get_widget(first_id).state().layout_rect();
+        let second_rect = harness.get_widget(second_id).state().layout_rect();
+
+        // The first line is at the bottom, and the second one above it.
+        assert_eq!(first_rect.origin(), Point::new(0.0, 80.0));
+        assert_eq!(second_rect.origin(), Point::new(0.0, 50.0));
+    }
+
+    fn reorderable_row() -> (TestHarness, [WidgetId; 3]) {
+        let ids = widget_ids();
+        let mut row = Flex::row().reorderable(true);
+        for id in ids {
+            row = row.with_child_id(SizedBox::empty().width(40.0).height(40.0), id);
+        }
+        let harness = TestHarness::create_with_size(row, Size::new(200.0, 100.0));
+        (harness, ids)
+    }
+
+    fn child_x(harness: &TestHarness, id: WidgetId) -> f64 {
+        harness.get_widget(id).state().layout_rect().x0
+    }
+
+    #[test]
+    fn drag_reorders_children() {
+        let (mut harness, [first, second, third]) = reorderable_row();
+        harness.mouse_move((20.0, 50.0));
+        harness.mouse_button_press(MouseButton::Left);
+        harness.mouse_move((70.0, 50.0));
+        harness.animate(Duration::from_secs(1));
+
+        // The first child follows the pointer past the center of the second one, which
+        // makes room for it.
+        assert_eq!(child_x(&harness, first), 50.0);
+        assert_eq!(child_x(&harness, second), 0.0);
+        assert_eq!(child_x(&harness, third), 80.0);
+        assert_eq!(harness.pop_action(), None);
+
+        harness.mouse_button_release(MouseButton::Left);
+        assert!(matches!(
+            harness.pop_action(),
+            Some((Action::Reordered(0, 1), _))
+        ));
+        harness.animate(Duration::from_secs(1));
+        assert_eq!(child_x(&harness, first), 40.0);
+        assert_eq!(child_x(&harness, second), 0.0);
+        harness.edit_root_widget(|mut flex| {
+            let mut flex = flex.downcast::<Flex>();
+            assert_eq!(flex.child_mut(0).unwrap().ctx.widget_id(), second);
+        });
+    }
+
+    #[test]
+    fn dropping_outside_cancels_reorder() {
+        let (mut harness, [first, second, _]) = reorderable_row();
+        harness.mouse_move((20.0, 50.0));
+        harness.mouse_button_press(MouseButton::Left);
+        harness.mouse_move((70.0, 50.0));
+        harness.mouse_move((70.0, 150.0));
+        harness.mouse_button_release(MouseButton::Left);
+        assert_eq!(harness.pop_action(), None);
+
+        harness.animate(Duration::from_secs(1));
+        assert_eq!(child_x(&harness, first), 0.0);
+        assert_eq!(child_x(&harness, second), 40.0);
     }
 }
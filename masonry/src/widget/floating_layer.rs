@@ -0,0 +1,337 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! A widget showing floating children, like action buttons, above its content.
+
+use accesskit::Role;
+use smallvec::SmallVec;
+use tracing::{trace, trace_span, Span};
+use vello::Scene;
+
+use crate::widget::{WidgetMut, WidgetPod, WidgetRef};
+use crate::{
+    AccessCtx, AccessEvent, BoxConstraints, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx,
+    Point, PointerEvent, Size, StatusChange, TextEvent, Vec2, Widget, WidgetId,
+};
+
+/// The corner of a [`FloatingLayer`] a floating child is pinned to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl Corner {
+    fn is_left(self) -> bool {
+        matches!(self, Corner::TopLeft | Corner::BottomLeft)
+    }
+
+    fn is_top(self) -> bool {
+        matches!(self, Corner::TopLeft | Corner::TopRight)
+    }
+}
+
+struct Floating {
+    child: WidgetPod<Box<dyn Widget>>,
+    corner: Corner,
+    offset: Vec2,
+}
+
+/// A widget showing floating children above its content, each pinned to one of its
+/// corners, like the floating action button of Material Design.
+///
+/// When the layer fills the window, e.g. as the root widget around a
+/// [`Portal`](crate::widget::Portal), the floating children stay at the corners of the
+/// window while the content scrolls.
+///
+/// The offset of a floating child is its distance to the edges of the corner. Children
+/// pinned to the same corner are stacked vertically, away from the corner, in the
+/// order they were added, with the vertical offset between them.
+///
+/// Floating children get the pointer events in their bounds, and the content gets the
+/// other ones.
+pub struct FloatingLayer<W> {
+    content: WidgetPod<W>,
+    floating: Vec<Floating>,
+}
+
+impl<W: Widget> FloatingLayer<W> {
+    /// Create a new layer above `content`.
+    pub fn new(content: W) -> Self {
+        Self::new_pod(WidgetPod::new(content))
+    }
+
+    /// Create a new layer above the child `content`.
+    pub fn new_pod(content: WidgetPod<W>) -> Self {
+        FloatingLayer {
+            content,
+            floating: Vec::new(),
+        }
+    }
+
+    /// Builder-style method to add a floating child pinned to `corner`.
+    pub fn with_floating(self, corner: Corner, offset: Vec2, child: impl Widget) -> Self {
+        self.with_floating_pod(corner, offset, WidgetPod::new(Box::new(child)))
+    }
+
+    /// Builder-style method to add a floating child with the given id, pinned to `corner`.
+    pub fn with_floating_id(
+        self,
+        corner: Corner,
+        offset: Vec2,
+        child: impl Widget,
+        id: WidgetId,
+    ) -> Self {
+        self.with_floating_pod(corner, offset, WidgetPod::new_with_id(Box::new(child), id))
+    }
+
+    fn with_floating_pod(
+        mut self,
+        corner: Corner,
+        offset: Vec2,
+        child: WidgetPod<Box<dyn Widget>>,
+    ) -> Self {
+        self.floating.push(Floating {
+            child,
+            corner,
+            offset,
+        });
+        self
+    }
+
+    /// The number of floating children.
+    pub fn floating_count(&self) -> usize {
+        self.floating.len()
+    }
+}
+
+impl<W: Widget> WidgetMut<'_, FloatingLayer<W>> {
+    pub fn content_mut(&mut self) -> WidgetMut<'_, W> {
+        self.ctx.get_mut(&mut self.widget.content)
+    }
+
+    /// Add a floating child pinned to `corner`, above the other ones.
+    pub fn add_floating(&mut self, corner: Corner, offset: Vec2, child: impl Widget) {
+        self.widget.floating.push(Floating {
+            child: WidgetPod::new(Box::new(child)),
+            corner,
+            offset,
+        });
+        self.ctx.children_changed();
+    }
+
+    /// Remove the floating child at `idx`.
+    ///
+    /// # Panics
+    ///
+    /// If `idx` is out of bounds.
+    pub fn remove_floating(&mut self, idx: usize) {
+        self.widget.floating.remove(idx);
+        self.ctx.children_changed();
+    }
+
+    pub fn floating_mut(&mut self, idx: usize) -> Option<WidgetMut<'_, Box<dyn Widget>>> {
+        let floating = self.widget.floating.get_mut(idx)?;
+        Some(self.ctx.get_mut(&mut floating.child))
+    }
+
+    /// Pin the floating child at `idx` to `corner`, with the given offset.
+    ///
+    /// # Panics
+    ///
+    /// If `idx` is out of bounds.
+    pub fn set_floating_position(&mut self, idx: usize, corner: Corner, offset: Vec2) {
+        let floating = &mut self.widget.floating[idx];
+        floating.corner = corner;
+        floating.offset = offset;
+        self.ctx.request_layout();
+    }
+}
+
+impl<W: Widget> Widget for FloatingLayer<W> {
+    fn on_pointer_event(&mut self, ctx: &mut EventCtx, event: &PointerEvent) {
+        // The floating children are above the content, which doesn't get the events they
+        // cover, unless it holds the pointer.
+        let mut covered = false;
+        for floating in self.floating.iter_mut().rev() {
+            floating.child.on_pointer_event(ctx, event);
+            covered |= floating.child.state.is_hot;
+        }
+        if !covered || self.content.state.has_active {
+            self.content.on_pointer_event(ctx, event);
+        } else {
+            ctx.skip_child(&mut self.content);
+        }
+    }
+
+    fn on_text_event(&mut self, ctx: &mut EventCtx, event: &TextEvent) {
+        self.content.on_text_event(ctx, event);
+        for floating in &mut self.floating {
+            floating.child.on_text_event(ctx, event);
+        }
+    }
+
+    fn on_access_event(&mut self, ctx: &mut EventCtx, event: &AccessEvent) {
+        self.content.on_access_event(ctx, event);
+        for floating in &mut self.floating {
+            floating.child.on_access_event(ctx, event);
+        }
+    }
+
+    fn on_status_change(&mut self, _ctx: &mut LifeCycleCtx, _event: &StatusChange) {}
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle) {
+        self.content.lifecycle(ctx, event);
+        for floating in &mut self.floating {
+            floating.child.lifecycle(ctx, event);
+        }
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints) -> Size {
+        let size = self.content.layout(ctx, bc);
+        ctx.place_child(&mut self.content, Point::ORIGIN);
+
+        // How far from its vertical edge the next child pinned to each corner goes.
+        let mut stacks = [0.0; 4];
+        let child_bc = BoxConstraints::new(Size::ZERO, size);
+        for floating in &mut self.floating {
+            let child_size = floating.child.layout(ctx, &child_bc);
+            let corner = floating.corner;
+            let stack = &mut stacks[corner as usize];
+            let x = if corner.is_left() {
+                floating.offset.x
+            } else {
+                size.width - floating.offset.x - child_size.width
+            };
+            let distance = *stack + floating.offset.y;
+            let y = if corner.is_top() {
+                distance
+            } else {
+                size.height - distance - child_size.height
+            };
+            *stack = distance + child_size.height;
+            ctx.place_child(&mut floating.child, Point::new(x, y));
+        }
+
+        trace!("Computed layout: size={}", size);
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, scene: &mut Scene) {
+        self.content.paint(ctx, scene);
+        for floating in &mut self.floating {
+            floating.child.paint(ctx, scene);
+        }
+    }
+
+    fn accessibility_role(&self) -> Role {
+        Role::GenericContainer
+    }
+
+    fn accessibility(&mut self, ctx: &mut AccessCtx) {
+        self.content.accessibility(ctx);
+        for floating in &mut self.floating {
+            floating.child.accessibility(ctx);
+        }
+    }
+
+    fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
+        std::iter::once(self.content.as_dyn())
+            .chain(self.floating.iter().map(|floating| floating.child.as_dyn()))
+            .collect()
+    }
+
+    fn make_trace_span(&self) -> Span {
+        trace_span!("FloatingLayer")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{widget_ids, TestHarness};
+    use crate::widget::{Button, Flex, Portal, SizedBox};
+    use crate::{Action, Rect};
+
+    const MARGIN: Vec2 = Vec2::new(16., 16.);
+
+    fn fab(text: &'static str) -> SizedBox {
+        SizedBox::new(Button::new(text)).width(56.).height(56.)
+    }
+
+    /// A long column of buttons as wide as the window, scrolling under the floating
+    /// children.
+    fn content() -> Portal<Flex> {
+        let column = (0..50).fold(Flex::column(), |column, index| {
+            let row = Button::new(format!("Row {index}"));
+            column.with_child(SizedBox::new(row).width(400.).height(40.))
+        });
+        Portal::new(column)
+    }
+
+    #[test]
+    fn floating_child_stays_at_its_corner() {
+        let [fab_id] = widget_ids();
+        let layer = FloatingLayer::new(content()).with_floating_id(
+            Corner::BottomRight,
+            MARGIN,
+            fab("+"),
+            fab_id,
+        );
+        let mut harness = TestHarness::create_with_size(layer, Size::new(400., 400.));
+        let expected = Rect::new(328., 328., 384., 384.);
+        assert_eq!(harness.get_widget(fab_id).state().layout_rect(), expected);
+
+        harness.mouse_move((100., 100.));
+        harness.mouse_wheel(Vec2::new(0., 300.));
+        let portal = harness.root_widget().children()[0].downcast::<Portal<Flex>>();
+        assert_eq!(portal.unwrap().get_viewport_pos(), Point::new(0., 300.));
+        assert_eq!(harness.get_widget(fab_id).state().layout_rect(), expected);
+    }
+
+    #[test]
+    fn floating_children_stack_without_overlap() {
+        let ids = widget_ids::<3>();
+        let layer = FloatingLayer::new(content())
+            .with_floating_id(Corner::BottomRight, MARGIN, fab("+"), ids[0])
+            .with_floating_id(Corner::BottomRight, MARGIN, fab("-"), ids[1])
+            .with_floating_id(Corner::TopLeft, Vec2::new(8., 4.), fab("?"), ids[2]);
+        let harness = TestHarness::create_with_size(layer, Size::new(400., 400.));
+        let rect = |index: usize| harness.get_widget(ids[index]).state().layout_rect();
+        assert_eq!(rect(0), Rect::new(328., 328., 384., 384.));
+        assert_eq!(rect(1), Rect::new(328., 256., 384., 312.));
+        assert_eq!(rect(2), Rect::new(8., 4., 64., 60.));
+    }
+
+    #[test]
+    fn only_events_in_bounds_are_intercepted() {
+        let [fab_id] = widget_ids();
+        let layer = FloatingLayer::new(content()).with_floating_id(
+            Corner::BottomRight,
+            MARGIN,
+            fab("+"),
+            fab_id,
+        );
+        let mut harness = TestHarness::create_with_size(layer, Size::new(400., 400.));
+
+        // The button of the row below the floating button doesn't get the click.
+        harness.mouse_click_on(fab_id);
+        let fab_button = harness.get_widget(fab_id).children()[0].id();
+        assert_eq!(
+            harness.pop_action(),
+            Some((Action::ButtonPressed, fab_button))
+        );
+        assert_eq!(harness.pop_action(), None);
+
+        // Clicks next to it reach the content.
+        harness.mouse_move((100., 350.));
+        harness.mouse_button_press(winit::event::MouseButton::Left);
+        harness.mouse_button_release(winit::event::MouseButton::Left);
+        assert!(matches!(
+            harness.pop_action(),
+            Some((Action::ButtonPressed, id)) if id != fab_button
+        ));
+    }
+}
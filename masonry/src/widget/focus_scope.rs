@@ -0,0 +1,375 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! A widget grouping the focusable widgets of a dialog, menu or sidebar.
+
+use accesskit::Role;
+use smallvec::{smallvec, SmallVec};
+use tracing::{trace, trace_span, Span};
+use vello::Scene;
+
+use crate::widget::{WidgetMut, WidgetPod, WidgetRef};
+use crate::{
+    AccessCtx, AccessEvent, BoxConstraints, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx,
+    Point, PointerEvent, Size, StatusChange, TextEvent, Widget, WidgetId,
+};
+
+/// A widget grouping the focusable widgets of its child, e.g. of a dialog.
+///
+/// Scopes are handled by the focus pass:
+///
+/// - A trapping scope keeps the focus: while a widget of the scope is focused, Tab and
+///   Shift+Tab cycle through the focusable widgets of the scope, and the widgets outside
+///   of it never get the focus. When scopes are nested, the innermost trapping scope
+///   holding the focus is used.
+/// - When a scope which restores the focus is removed or stashed (e.g. when a popover
+///   closes) while it holds the focus, the widget focused before the focus entered the
+///   scope gets it back, if it still exists.
+/// - An autofocus scope focuses its first focusable widget when it's added or unstashed,
+///   see [`focus_first_in_scope`](crate::EventCtx::focus_first_in_scope).
+pub struct FocusScope {
+    child: WidgetPod<Box<dyn Widget>>,
+    trap: bool,
+    restore_on_remove: bool,
+    autofocus: bool,
+    /// Whether the scope was shown as of the last focus chain update.
+    was_shown: bool,
+}
+
+impl FocusScope {
+    /// Create a new scope around `child`, which neither traps nor restores the focus.
+    pub fn new(child: impl Widget) -> Self {
+        Self::new_pod(WidgetPod::new(Box::new(child)))
+    }
+
+    /// Create a new scope around the child `child`.
+    pub fn new_pod(child: WidgetPod<Box<dyn Widget>>) -> Self {
+        FocusScope {
+            child,
+            trap: false,
+            restore_on_remove: false,
+            autofocus: false,
+            was_shown: false,
+        }
+    }
+
+    /// Builder-style method to set whether Tab and Shift+Tab cycle within the scope
+    /// while it holds the focus.
+    pub fn with_trap(mut self, trap: bool) -> Self {
+        self.trap = trap;
+        self
+    }
+
+    /// Builder-style method to set whether the focus goes back to the widget focused
+    /// before the scope was entered, when the scope is removed or stashed.
+    pub fn with_restore_on_remove(mut self, restore_on_remove: bool) -> Self {
+        self.restore_on_remove = restore_on_remove;
+        self
+    }
+
+    /// Builder-style method to set whether the first focusable widget of the scope is
+    /// focused when the scope is added or unstashed.
+    pub fn with_autofocus(mut self, autofocus: bool) -> Self {
+        self.autofocus = autofocus;
+        self
+    }
+
+    /// Whether Tab and Shift+Tab cycle within the scope while it holds the focus.
+    pub fn traps_focus(&self) -> bool {
+        self.trap
+    }
+
+    /// Whether the focus is restored when the scope is removed or stashed.
+    pub fn restores_focus(&self) -> bool {
+        self.restore_on_remove
+    }
+}
+
+impl WidgetMut<'_, FocusScope> {
+    pub fn child_mut(&mut self) -> WidgetMut<'_, Box<dyn Widget>> {
+        self.ctx.get_mut(&mut self.widget.child)
+    }
+
+    /// Set whether Tab and Shift+Tab cycle within the scope while it holds the focus.
+    pub fn set_trap(&mut self, trap: bool) {
+        self.widget.trap = trap;
+    }
+
+    /// Set whether the focus is restored when the scope is removed or stashed.
+    ///
+    /// This only applies the next time the focus enters the scope.
+    pub fn set_restore_on_remove(&mut self, restore_on_remove: bool) {
+        self.widget.restore_on_remove = restore_on_remove;
+    }
+
+    /// Set whether the first focusable widget of the scope is focused when the scope
+    /// is unstashed.
+    pub fn set_autofocus(&mut self, autofocus: bool) {
+        self.widget.autofocus = autofocus;
+    }
+
+    /// Focus the first focusable widget of the scope.
+    pub fn focus_first(&mut self) {
+        self.ctx.focus_first_in_scope();
+    }
+}
+
+impl Widget for FocusScope {
+    fn on_pointer_event(&mut self, ctx: &mut EventCtx, event: &PointerEvent) {
+        self.child.on_pointer_event(ctx, event);
+    }
+
+    fn on_text_event(&mut self, ctx: &mut EventCtx, event: &TextEvent) {
+        self.child.on_text_event(ctx, event);
+    }
+
+    fn on_access_event(&mut self, ctx: &mut EventCtx, event: &AccessEvent) {
+        self.child.on_access_event(ctx, event);
+    }
+
+    fn on_status_change(&mut self, _ctx: &mut LifeCycleCtx, _event: &StatusChange) {}
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle) {
+        self.child.lifecycle(ctx, event);
+
+        // The focus chain of the scope is complete once the child has built its own.
+        if let LifeCycle::BuildFocusChain = event {
+            ctx.widget_state.sort_focus_chain();
+            let is_shown = !ctx.is_stashed();
+            if self.autofocus && is_shown && !self.was_shown {
+                trace!("FocusScope {:?} focuses its first widget", ctx.widget_id());
+                ctx.focus_first_in_scope();
+            }
+            self.was_shown = is_shown;
+        }
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints) -> Size {
+        let size = self.child.layout(ctx, bc);
+        ctx.place_child(&mut self.child, Point::ORIGIN);
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, scene: &mut Scene) {
+        self.child.paint(ctx, scene);
+    }
+
+    fn accessibility_role(&self) -> Role {
+        Role::GenericContainer
+    }
+
+    fn accessibility(&mut self, ctx: &mut AccessCtx) {
+        self.child.accessibility(ctx);
+    }
+
+    fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
+        smallvec![self.child.as_dyn()]
+    }
+
+    fn make_trace_span(&self) -> Span {
+        trace_span!("FocusScope")
+    }
+}
+
+/// The scopes around the widget `id`, from the outermost to the innermost.
+pub(crate) fn focus_scopes_around<'w>(
+    root: WidgetRef<'w, dyn Widget>,
+    id: WidgetId,
+) -> Vec<WidgetRef<'w, FocusScope>> {
+    fn visit<'w>(
+        widget: WidgetRef<'w, dyn Widget>,
+        id: WidgetId,
+        scopes: &mut Vec<WidgetRef<'w, FocusScope>>,
+    ) -> bool {
+        if widget.id() == id {
+            return true;
+        }
+        if !widget.state().children.may_contain(&id) {
+            return false;
+        }
+        let found = widget
+            .children()
+            .into_iter()
+            .any(|child| visit(child, id, scopes));
+        if found {
+            scopes.extend(widget.downcast::<FocusScope>());
+        }
+        found
+    }
+
+    let mut scopes = Vec::new();
+    visit(root, id, &mut scopes);
+    scopes.reverse();
+    scopes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{widget_ids, TestHarness};
+    use crate::widget::{Button, Flex};
+
+    fn focused(harness: &TestHarness) -> Option<WidgetId> {
+        harness.focused_widget().map(|widget| widget.id())
+    }
+
+    fn buttons(ids: &[WidgetId]) -> Flex {
+        ids.iter().fold(Flex::row(), |row, id| {
+            row.with_child_id(Button::new(format!("{id:?}")), *id)
+        })
+    }
+
+    #[test]
+    fn tab_cycles_within_trapping_scope() {
+        let [before, first, second, after] = widget_ids();
+        let dialog = FocusScope::new(buttons(&[first, second]))
+            .with_trap(true)
+            .with_autofocus(true);
+        let widget = Flex::row()
+            .with_child_id(Button::new("Before"), before)
+            .with_child(dialog)
+            .with_child_id(Button::new("After"), after);
+        let mut harness = TestHarness::create(widget);
+        assert_eq!(focused(&harness), Some(first));
+
+        for expected in [second, first, second] {
+            harness.move_focus(true);
+            assert_eq!(focused(&harness), Some(expected));
+        }
+        for expected in [first, second] {
+            harness.move_focus(false);
+            assert_eq!(focused(&harness), Some(expected));
+        }
+    }
+
+    #[test]
+    fn tab_follows_tab_indices() {
+        let [third, first, second] = widget_ids();
+        let widget = [(third, 3), (first, 1), (second, 2)].into_iter().fold(
+            Flex::row(),
+            |row, (id, tab_index)| {
+                let button: Box<dyn Widget> = Box::new(Button::new(format!("{tab_index}")));
+                row.with_child_pod(
+                    WidgetPod::new_with_id(button, id).with_tab_index(Some(tab_index)),
+                )
+            },
+        );
+        let mut harness = TestHarness::create(widget);
+
+        for expected in [first, second, third, first] {
+            harness.move_focus(true);
+            assert_eq!(focused(&harness), Some(expected));
+        }
+
+        // Widgets with the same index are in tree order.
+        harness.edit_root_widget(|mut root| {
+            let mut row = root.downcast::<Flex>();
+            row.child_mut(2).unwrap().set_tab_index(Some(1));
+        });
+        for expected in [second, third, first] {
+            harness.move_focus(true);
+            assert_eq!(focused(&harness), Some(expected));
+        }
+    }
+
+    #[test]
+    fn negative_tab_index_is_skipped_by_tab() {
+        let [first, skipped, last] = widget_ids();
+        let widget = Flex::row()
+            .with_child_id(Button::new("First"), first)
+            .with_child_pod(
+                WidgetPod::<Box<dyn Widget>>::new_with_id(
+                    Box::new(Button::new("Skipped")),
+                    skipped,
+                )
+                .with_tab_index(Some(-1)),
+            )
+            .with_child_id(Button::new("Last"), last);
+        let mut harness = TestHarness::create(widget);
+
+        for expected in [first, last, first] {
+            harness.move_focus(true);
+            assert_eq!(focused(&harness), Some(expected));
+        }
+
+        harness.access_action(skipped, accesskit::Action::Focus);
+        assert_eq!(focused(&harness), Some(skipped));
+    }
+
+    #[test]
+    fn removing_scope_restores_focus() {
+        let [outside, first, second] = widget_ids();
+        let page = FocusScope::new(Flex::column().with_child_id(Button::new("Open"), outside))
+            .with_autofocus(true);
+        let mut harness = TestHarness::create(page);
+        assert_eq!(focused(&harness), Some(outside));
+
+        harness.edit_root_widget(|mut page| {
+            let mut page = page.downcast::<FocusScope>();
+            let mut child = page.child_mut();
+            let dialog = FocusScope::new(buttons(&[first, second]))
+                .with_trap(true)
+                .with_restore_on_remove(true)
+                .with_autofocus(true);
+            child.downcast::<Flex>().add_child(dialog);
+        });
+        assert_eq!(focused(&harness), Some(first));
+        harness.move_focus(true);
+        assert_eq!(focused(&harness), Some(second));
+
+        harness.edit_root_widget(|mut page| {
+            let mut page = page.downcast::<FocusScope>();
+            let mut child = page.child_mut();
+            child.downcast::<Flex>().remove_child(1);
+        });
+        assert_eq!(focused(&harness), Some(outside));
+    }
+
+    #[test]
+    fn nested_scopes_trap_at_innermost_level() {
+        let ids = widget_ids::<6>();
+        let [outside, outer_first, inner_first, inner_second, outer_last, outside_last] = ids;
+        let inner = FocusScope::new(buttons(&[inner_first, inner_second]))
+            .with_trap(true)
+            .with_autofocus(true);
+        let outer = FocusScope::new(
+            Flex::row()
+                .with_child_id(Button::new("Outer first"), outer_first)
+                .with_child(inner)
+                .with_child_id(Button::new("Outer last"), outer_last),
+        )
+        .with_trap(true);
+        let widget = Flex::row()
+            .with_child_id(Button::new("Outside"), outside)
+            .with_child(outer)
+            .with_child_id(Button::new("Outside last"), outside_last);
+        let mut harness = TestHarness::create(widget);
+        assert_eq!(focused(&harness), Some(inner_first));
+
+        for expected in [inner_second, inner_first, inner_second] {
+            harness.move_focus(true);
+            assert_eq!(focused(&harness), Some(expected));
+        }
+
+        // Once the inner scope stops trapping, the outer one does.
+        harness.edit_root_widget(|mut root| {
+            let mut root = root.downcast::<Flex>();
+            let mut outer = root.child_mut(1).unwrap();
+            let mut outer = outer.downcast::<FocusScope>();
+            let mut outer_child = outer.child_mut();
+            let mut outer_child = outer_child.downcast::<Flex>();
+            let mut inner = outer_child.child_mut(1).unwrap();
+            inner.downcast::<FocusScope>().set_trap(false);
+        });
+        for expected in [
+            outer_last,
+            outer_first,
+            inner_first,
+            inner_second,
+            outer_last,
+        ] {
+            harness.move_focus(true);
+            assert_eq!(focused(&harness), Some(expected));
+        }
+    }
+}
@@ -5,28 +5,45 @@
 //! Please consider using SVG and the SVG widget as it scales much better.
 
 use accesskit::Role;
-use kurbo::Affine;
+use kurbo::{Affine, Line};
 use smallvec::SmallVec;
 use tracing::{trace, trace_span, Span};
 use vello::peniko::{BlendMode, Image as ImageBuf};
 use vello::Scene;
 
+use crate::image_cache::{ImageHandle, ImageSource, ImageState};
+use crate::paint_scene_helpers::{fill_color, stroke};
 use crate::widget::{FillStrat, WidgetMut, WidgetRef};
 use crate::{
-    AccessCtx, AccessEvent, BoxConstraints, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx,
-    PointerEvent, Size, StatusChange, TextEvent, Widget,
+    theme, AccessCtx, AccessEvent, BoxConstraints, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx,
+    PaintCtx, PointerEvent, Size, StatusChange, TextEvent, Widget,
 };
 
 // TODO - Resolve name collision between masonry::Image and peniko::Image
 
+/// The size of the placeholder painted while an image is loading, or if it failed to load.
+const PLACEHOLDER_SIZE: Size = Size::new(48., 48.);
+
 /// A widget that renders a bitmap Image.
 ///
+/// The image is either given directly, or loaded through the
+/// [`ImageCache`](crate::image_cache::ImageCache), in which case a placeholder is
+/// painted until it's decoded, and a crossed-out box if it can't be loaded.
+///
 /// The underlying image uses `Arc` for buffer data, making it cheap to clone.
 pub struct Image {
-    image_data: ImageBuf,
+    content: ImageContent,
     fill: FillStrat,
 }
 
+enum ImageContent {
+    Data(ImageBuf),
+    /// A source to load once the widget is added, since the cache is only reachable
+    /// through a context.
+    Source(ImageSource),
+    Handle(ImageHandle),
+}
+
 impl Image {
     /// Create an image drawing widget from an image buffer.
     ///
@@ -35,7 +52,25 @@ impl Image {
     #[inline]
     pub fn new(image_data: ImageBuf) -> Self {
         Image {
-            image_data,
+            content: ImageContent::Data(image_data),
+            fill: FillStrat::default(),
+        }
+    }
+
+    /// Create an image drawing widget which loads its image from `source` through
+    /// the image cache of the window.
+    pub fn from_source(source: impl Into<ImageSource>) -> Self {
+        Image {
+            content: ImageContent::Source(source.into()),
+            fill: FillStrat::default(),
+        }
+    }
+
+    /// Create an image drawing widget showing an image loaded through the
+    /// [`ImageCache`](crate::image_cache::ImageCache).
+    pub fn from_handle(handle: ImageHandle) -> Self {
+        Image {
+            content: ImageContent::Handle(handle),
             fill: FillStrat::default(),
         }
     }
@@ -46,6 +81,15 @@ impl Image {
         self.fill = mode;
         self
     }
+
+    /// Whether the image is loading, ready or failed to load.
+    pub fn image_state(&self) -> ImageState {
+        match &self.content {
+            ImageContent::Data(image_data) => ImageState::Ready(image_data.clone()),
+            ImageContent::Source(_) => ImageState::Loading,
+            ImageContent::Handle(handle) => handle.state(),
+        }
+    }
 }
 
 impl<'a> WidgetMut<'a, Image> {
@@ -59,7 +103,20 @@ impl<'a> WidgetMut<'a, Image> {
     /// Set new `ImageBuf`.
     #[inline]
     pub fn set_image_data(&mut self, image_data: ImageBuf) {
-        self.widget.image_data = image_data;
+        self.widget.content = ImageContent::Data(image_data);
+        self.ctx.request_layout();
+    }
+
+    /// Load a new image from `source` through the image cache of the window.
+    pub fn set_source(&mut self, source: impl Into<ImageSource>) {
+        let handle = self.ctx.image_cache().load(source);
+        self.set_handle(handle);
+    }
+
+    /// Show an image loaded through the [`ImageCache`](crate::image_cache::ImageCache).
+    pub fn set_handle(&mut self, handle: ImageHandle) {
+        self.ctx.wait_for_image(&handle);
+        self.widget.content = ImageContent::Handle(handle);
         self.ctx.request_layout();
     }
 }
@@ -73,14 +130,29 @@ impl Widget for Image {
 
     fn on_status_change(&mut self, _ctx: &mut LifeCycleCtx, _event: &StatusChange) {}
 
-    fn lifecycle(&mut self, _ctx: &mut LifeCycleCtx, _event: &LifeCycle) {}
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle) {
+        if let LifeCycle::WidgetAdded = event {
+            if let ImageContent::Source(source) = &self.content {
+                let handle = ctx.image_cache().load(source.clone());
+                self.content = ImageContent::Handle(handle);
+            }
+            if let ImageContent::Handle(handle) = &self.content {
+                ctx.wait_for_image(handle);
+            }
+        }
+    }
 
     fn layout(&mut self, _ctx: &mut LayoutCtx, bc: &BoxConstraints) -> Size {
         // If either the width or height is constrained calculate a value so that the image fits
         // in the size exactly. If it is unconstrained by both width and height take the size of
         // the image.
         let max = bc.max();
-        let image_size = Size::new(self.image_data.width as f64, self.image_data.height as f64);
+        let image_size = match self.image_state() {
+            ImageState::Ready(image_data) => {
+                Size::new(image_data.width as f64, image_data.height as f64)
+            }
+            ImageState::Loading | ImageState::Failed(_) => PLACEHOLDER_SIZE,
+        };
         let size = if bc.is_width_bounded() && !bc.is_height_bounded() {
             let ratio = max.width / image_size.width;
             Size::new(max.width, ratio * image_size.height)
@@ -95,13 +167,32 @@ impl Widget for Image {
     }
 
     fn paint(&mut self, ctx: &mut PaintCtx, scene: &mut Scene) {
-        let image_size = Size::new(self.image_data.width as f64, self.image_data.height as f64);
-        let transform = self.fill.affine_to_fill(ctx.size(), image_size);
-
         let clip_rect = ctx.size().to_rect();
-        scene.push_layer(BlendMode::default(), 1., Affine::IDENTITY, &clip_rect);
-        scene.draw_image(&self.image_data, transform);
-        scene.pop_layer();
+        match self.image_state() {
+            ImageState::Ready(image_data) => {
+                let image_size = Size::new(image_data.width as f64, image_data.height as f64);
+                let transform = self.fill.affine_to_fill(ctx.size(), image_size);
+
+                scene.push_layer(BlendMode::default(), 1., Affine::IDENTITY, &clip_rect);
+                scene.draw_image(&image_data, transform);
+                scene.pop_layer();
+            }
+            ImageState::Loading => {
+                fill_color(scene, &clip_rect, theme::BACKGROUND_LIGHT);
+            }
+            ImageState::Failed(_) => {
+                fill_color(scene, &clip_rect, theme::BACKGROUND_LIGHT);
+                let border = clip_rect.inset(-0.5);
+                stroke(scene, &border, theme::DISABLED_FOREGROUND_LIGHT, 1.0);
+                let cross = [
+                    Line::new((border.x0, border.y0), (border.x1, border.y1)),
+                    Line::new((border.x1, border.y0), (border.x0, border.y1)),
+                ];
+                for line in cross {
+                    stroke(scene, &line, theme::DISABLED_FOREGROUND_LIGHT, 1.0);
+                }
+            }
+        }
     }
 
     fn accessibility_role(&self) -> Role {
@@ -125,11 +216,29 @@ impl Widget for Image {
 #[cfg(not(target_arch = "wasm32"))]
 #[cfg(test)]
 mod tests {
+    use image::{ImageFormat, Rgba, RgbaImage};
     use vello::peniko::Format;
 
     use super::*;
     use crate::assert_render_snapshot;
-    use crate::testing::TestHarness;
+    use crate::testing::{widget_ids, Record, Recording, TestHarness, TestWidgetExt};
+    use crate::widget::Flex;
+
+    /// Encode a PNG image filled with `color`.
+    fn png_bytes(width: u32, height: u32, color: [u8; 4]) -> Vec<u8> {
+        let image = RgbaImage::from_pixel(width, height, Rgba(color));
+        let mut bytes = std::io::Cursor::new(Vec::new());
+        image.write_to(&mut bytes, ImageFormat::Png).unwrap();
+        bytes.into_inner()
+    }
+
+    fn paint_count(recording: &Recording) -> usize {
+        recording
+            .drain()
+            .into_iter()
+            .filter(|record| matches!(record, Record::Paint))
+            .count()
+    }
 
     /// Painting an empty image shouldn't crash.
     #[test]
@@ -192,4 +301,66 @@ mod tests {
         // We don't use assert_eq because we don't want rich assert
         assert!(render_1 == render_2);
     }
+
+    #[test]
+    fn shared_source_is_decoded_once() {
+        let bytes = png_bytes(8, 8, [255, 0, 0, 255]);
+        let first = Recording::default();
+        let second = Recording::default();
+        let widget = Flex::row()
+            .with_child(Image::from_source(bytes.clone()).record(&first))
+            .with_child(Image::from_source(bytes).record(&second));
+
+        let mut harness = TestHarness::create(widget);
+        let _ = harness.paint_damage();
+        assert_eq!(harness.image_cache().decode_count(), 0);
+        first.clear();
+        second.clear();
+
+        harness.run_pending_image_decodes();
+        assert_eq!(harness.image_cache().decode_count(), 1);
+        let _ = harness.paint_damage();
+        assert_eq!(paint_count(&first), 1);
+        assert_eq!(paint_count(&second), 1);
+
+        // Nothing else happens once the image is there.
+        let _ = harness.paint_damage();
+        assert_eq!(paint_count(&first), 0);
+        assert_eq!(paint_count(&second), 0);
+    }
+
+    #[test]
+    fn loaded_image_is_laid_out() {
+        let [image_id] = widget_ids();
+        let image = Image::from_source(png_bytes(8, 16, [255, 0, 0, 255]));
+        let widget = Flex::row().with_child_id(image, image_id);
+
+        // The image fills the height of the row, and keeps its aspect ratio.
+        let mut harness = TestHarness::create(widget);
+        let size = harness.get_widget(image_id).state().layout_rect().size();
+        assert_eq!(size.width, size.height);
+
+        harness.run_pending_image_decodes();
+        let image = harness.get_widget(image_id);
+        assert!(matches!(
+            image.downcast::<Image>().unwrap().image_state(),
+            ImageState::Ready(_)
+        ));
+        let size = image.state().layout_rect().size();
+        assert_eq!(size.width * 2., size.height);
+    }
+
+    #[test]
+    fn corrupt_source_paints_error() {
+        let image_widget = Image::from_source(vec![0, 1, 2, 3]);
+        let mut harness = TestHarness::create_with_size(image_widget, Size::new(40., 40.));
+        harness.run_pending_image_decodes();
+
+        let image = harness.root_widget();
+        assert!(matches!(
+            image.downcast::<Image>().unwrap().image_state(),
+            ImageState::Failed(_)
+        ));
+        let _ = harness.render();
+    }
 }
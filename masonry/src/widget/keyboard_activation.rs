@@ -0,0 +1,177 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Keyboard activation shared by button-like widgets.
+
+use winit::event::ElementState;
+use winit::keyboard::{Key, NamedKey};
+
+use crate::{EventCtx, TextEvent};
+
+/// What a key event did to a [`KeyboardActivation`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum KeyOutcome {
+    /// The key isn't one that activates the widget.
+    Ignored,
+    /// The key was consumed, but nothing changed (e.g. a key repeat).
+    Consumed,
+    /// The widget started or stopped looking pressed, without activating.
+    PressChanged,
+    /// The widget should fire its action.
+    Activate,
+}
+
+/// Tracks keyboard presses on a focused button-like widget, mirroring pointer
+/// presses.
+///
+/// - Enter activates the widget immediately on key-down.
+/// - Space makes the widget look pressed while held, and activates it on release.
+/// - Escape while Space is held cancels the press, as does losing focus
+///   (see [`cancel`](Self::cancel)).
+#[derive(Debug, Default)]
+pub(crate) struct KeyboardActivation {
+    space_held: bool,
+}
+
+impl KeyboardActivation {
+    /// Whether Space is held, i.e. the widget should be painted as pressed.
+    pub fn is_pressed(&self) -> bool {
+        self.space_held
+    }
+
+    pub fn on_key(&mut self, key: &Key, state: ElementState, repeat: bool) -> KeyOutcome {
+        match (key, state) {
+            (Key::Named(NamedKey::Space), ElementState::Pressed) => {
+                if self.space_held || repeat {
+                    KeyOutcome::Consumed
+                } else {
+                    self.space_held = true;
+                    KeyOutcome::PressChanged
+                }
+            }
+            (Key::Named(NamedKey::Space), ElementState::Released) => {
+                if self.space_held {
+                    self.space_held = false;
+                    KeyOutcome::Activate
+                } else {
+                    KeyOutcome::Ignored
+                }
+            }
+            (Key::Named(NamedKey::Escape), ElementState::Pressed) => {
+                if self.space_held {
+                    self.space_held = false;
+                    KeyOutcome::PressChanged
+                } else {
+                    KeyOutcome::Ignored
+                }
+            }
+            (Key::Named(NamedKey::Enter), ElementState::Pressed) => {
+                if repeat {
+                    KeyOutcome::Consumed
+                } else {
+                    KeyOutcome::Activate
+                }
+            }
+            _ => KeyOutcome::Ignored,
+        }
+    }
+
+    /// Cancel a press in progress without activating.
+    ///
+    /// Returns whether the widget was pressed.
+    pub fn cancel(&mut self) -> bool {
+        std::mem::take(&mut self.space_held)
+    }
+
+    /// Handle a text event sent to the widget.
+    ///
+    /// Returns `true` if the widget should fire its action.
+    pub fn on_text_event(&mut self, ctx: &mut EventCtx, event: &TextEvent) -> bool {
+        let TextEvent::KeyboardKey(key_event, _) = event else {
+            return false;
+        };
+        if !ctx.is_focused() || ctx.is_disabled() {
+            return false;
+        }
+        let outcome = self.on_key(&key_event.logical_key, key_event.state, key_event.repeat);
+        if outcome != KeyOutcome::Ignored {
+            ctx.set_handled();
+        }
+        if matches!(outcome, KeyOutcome::PressChanged | KeyOutcome::Activate) {
+            ctx.request_paint();
+        }
+        outcome == KeyOutcome::Activate
+    }
+}
+
+// winit key events can't be constructed outside of winit, so the state machine
+// is tested directly.
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SPACE: Key = Key::Named(NamedKey::Space);
+    const ENTER: Key = Key::Named(NamedKey::Enter);
+    const ESCAPE: Key = Key::Named(NamedKey::Escape);
+
+    #[test]
+    fn space_activates_on_release() {
+        let mut activation = KeyboardActivation::default();
+
+        let outcome = activation.on_key(&SPACE, ElementState::Pressed, false);
+        assert_eq!(outcome, KeyOutcome::PressChanged);
+        assert!(activation.is_pressed());
+
+        let outcome = activation.on_key(&SPACE, ElementState::Pressed, true);
+        assert_eq!(outcome, KeyOutcome::Consumed);
+
+        let outcome = activation.on_key(&SPACE, ElementState::Released, false);
+        assert_eq!(outcome, KeyOutcome::Activate);
+        assert!(!activation.is_pressed());
+
+        // A second release doesn't fire again
+        let outcome = activation.on_key(&SPACE, ElementState::Released, false);
+        assert_eq!(outcome, KeyOutcome::Ignored);
+    }
+
+    #[test]
+    fn escape_cancels_space() {
+        let mut activation = KeyboardActivation::default();
+
+        activation.on_key(&SPACE, ElementState::Pressed, false);
+        let outcome = activation.on_key(&ESCAPE, ElementState::Pressed, false);
+        assert_eq!(outcome, KeyOutcome::PressChanged);
+        assert!(!activation.is_pressed());
+
+        let outcome = activation.on_key(&SPACE, ElementState::Released, false);
+        assert_eq!(outcome, KeyOutcome::Ignored);
+
+        // Escape without a press is left to other widgets
+        let outcome = activation.on_key(&ESCAPE, ElementState::Pressed, false);
+        assert_eq!(outcome, KeyOutcome::Ignored);
+    }
+
+    #[test]
+    fn enter_activates_on_press() {
+        let mut activation = KeyboardActivation::default();
+
+        let outcome = activation.on_key(&ENTER, ElementState::Pressed, false);
+        assert_eq!(outcome, KeyOutcome::Activate);
+        let outcome = activation.on_key(&ENTER, ElementState::Pressed, true);
+        assert_eq!(outcome, KeyOutcome::Consumed);
+        let outcome = activation.on_key(&ENTER, ElementState::Released, false);
+        assert_eq!(outcome, KeyOutcome::Ignored);
+    }
+
+    #[test]
+    fn cancel_on_focus_loss() {
+        let mut activation = KeyboardActivation::default();
+
+        activation.on_key(&SPACE, ElementState::Pressed, false);
+        assert!(activation.cancel());
+        assert!(!activation.cancel());
+
+        let outcome = activation.on_key(&SPACE, ElementState::Released, false);
+        assert_eq!(outcome, KeyOutcome::Ignored);
+    }
+}
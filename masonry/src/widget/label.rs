@@ -4,7 +4,9 @@
 //! A label widget.
 
 use accesskit::Role;
-use kurbo::{Affine, Point, Size};
+use std::ops::Range;
+
+use kurbo::{Affine, Point, Rect, Size};
 use parley::layout::Alignment;
 use parley::style::{FontFamily, FontStack};
 use smallvec::SmallVec;
@@ -12,11 +14,12 @@ use tracing::trace;
 use vello::peniko::BlendMode;
 use vello::Scene;
 
-use crate::text2::{TextBrush, TextLayout, TextStorage};
+use crate::text2::{LineHeight, TextBrush, TextHighlights, TextLayout, TextStorage};
+use crate::text_style::TextStyleOverrides;
 use crate::widget::{WidgetMut, WidgetRef};
 use crate::{
-    AccessCtx, AccessEvent, ArcStr, BoxConstraints, Color, EventCtx, LayoutCtx, LifeCycle,
-    LifeCycleCtx, PaintCtx, PointerEvent, StatusChange, TextEvent, Widget,
+    AccessCtx, AccessEvent, ArcStr, BoxConstraints, Color, EventCtx, LayoutCtx, LayoutDirection,
+    LifeCycle, LifeCycleCtx, PaintCtx, PointerEvent, StatusChange, TextEvent, Widget,
 };
 
 // added padding between the edges of the widget and the text.
@@ -40,9 +43,13 @@ pub struct Label {
     // (Rich text is not yet fully integrated, and so the architecture by which a label
     // has rich text properties specified still needs to be designed)
     text_layout: TextLayout<ArcStr>,
+    /// The alignment set by the user, if any; otherwise the text is aligned to the
+    /// leading edge of the layout direction.
+    alignment: Option<Alignment>,
     line_break_mode: LineBreaking,
     show_disabled: bool,
-    brush: TextBrush,
+    style: TextStyleOverrides,
+    highlights: TextHighlights,
 }
 
 impl Label {
@@ -50,9 +57,11 @@ impl Label {
     pub fn new(text: impl Into<ArcStr>) -> Self {
         Self {
             text_layout: TextLayout::new(text.into(), crate::theme::TEXT_SIZE_NORMAL as f32),
+            alignment: None,
             line_break_mode: LineBreaking::Overflow,
             show_disabled: true,
-            brush: crate::theme::TEXT_COLOR.into(),
+            style: TextStyleOverrides::default(),
+            highlights: TextHighlights::new(),
         }
     }
 
@@ -62,21 +71,28 @@ impl Label {
 
     #[doc(alias = "with_text_color")]
     pub fn with_text_brush(mut self, color: Color) -> Self {
+        self.style.brush = Some(color.into());
         self.text_layout.set_brush(color);
         self
     }
 
     pub fn with_text_size(mut self, size: f32) -> Self {
+        self.style.size = Some(size);
         self.text_layout.set_text_size(size);
         self
     }
 
+    /// Set the alignment of the text.
+    ///
+    /// By default, text is aligned to the left, or to the right in right-to-left layout.
     pub fn with_text_alignment(mut self, alignment: Alignment) -> Self {
+        self.alignment = Some(alignment);
         self.text_layout.set_text_alignment(alignment);
         self
     }
 
     pub fn with_font(mut self, font: FontStack<'static>) -> Self {
+        self.style.font = Some(font);
         self.text_layout.set_font(font);
         self
     }
@@ -84,6 +100,16 @@ impl Label {
         self.with_font(FontStack::Single(font))
     }
 
+    pub fn with_line_height(mut self, line_height: LineHeight) -> Self {
+        self.text_layout.set_line_height(line_height);
+        self
+    }
+
+    pub fn with_letter_spacing(mut self, letter_spacing: f32) -> Self {
+        self.text_layout.set_letter_spacing(letter_spacing);
+        self
+    }
+
     pub fn with_line_break_mode(mut self, line_break_mode: LineBreaking) -> Self {
         self.line_break_mode = line_break_mode;
         self
@@ -93,6 +119,42 @@ impl Label {
     pub fn empty() -> Self {
         Self::new("")
     }
+
+    /// The ranges of the text which are highlighted, see [`WidgetMut::set_highlights`].
+    pub fn highlights(&self) -> &TextHighlights {
+        &self.highlights
+    }
+
+    /// The bounding rectangle of each highlighted range, in the label's coordinates.
+    ///
+    /// This is meant to scroll a match into view, e.g. with
+    /// [`Portal::pan_viewport_to`](crate::widget::Portal::pan_viewport_to) after
+    /// translating it to the content of the portal. It's only meaningful after layout.
+    pub fn match_rects(&self) -> Vec<Rect> {
+        self.highlights
+            .match_rects(&self.text_layout, Point::new(LABEL_X_PADDING, 0.0))
+    }
+}
+
+impl Label {
+    /// Lay out the text with the given `max_advance`, and return the size of the label.
+    fn measure(&mut self, ctx: &mut LayoutCtx, max_advance: Option<f32>) -> Size {
+        let alignment = self.alignment.unwrap_or(match ctx.layout_direction() {
+            LayoutDirection::Ltr => Alignment::Start,
+            LayoutDirection::Rtl => Alignment::End,
+        });
+        self.text_layout.set_text_alignment(alignment);
+        self.text_layout.set_max_advance(max_advance);
+        if self.text_layout.needs_rebuild() {
+            self.text_layout.rebuild(ctx.font_ctx());
+        }
+        // We ignore trailing whitespace for a label
+        let text_size = self.text_layout.size();
+        Size {
+            height: text_size.height,
+            width: text_size.width + 2. * LABEL_X_PADDING,
+        }
+    }
 }
 
 impl WidgetMut<'_, Label> {
@@ -113,31 +175,62 @@ impl WidgetMut<'_, Label> {
         self.set_text_properties(|layout| layout.set_text(new_text));
     }
 
+    /// Apply the inherited text style, and the label's own, to the text.
+    fn apply_text_style(&mut self) {
+        let disabled = self.widget.show_disabled && self.ctx.is_disabled();
+        let label = &mut *self.widget;
+        if label
+            .style
+            .apply(&mut label.text_layout, self.ctx.text_style(), disabled)
+        {
+            self.ctx.request_layout();
+        }
+    }
+
     #[doc(alias = "set_text_color")]
     pub fn set_text_brush(&mut self, brush: impl Into<TextBrush>) {
-        let brush = brush.into();
-        self.widget.brush = brush;
-        if !self.ctx.is_disabled() {
-            let brush = self.widget.brush.clone();
-            self.set_text_properties(|layout| layout.set_brush(brush));
-        }
+        self.widget.style.brush = Some(brush.into());
+        self.apply_text_style();
     }
     pub fn set_text_size(&mut self, size: f32) {
-        self.set_text_properties(|layout| layout.set_text_size(size));
+        self.widget.style.size = Some(size);
+        self.apply_text_style();
     }
     pub fn set_alignment(&mut self, alignment: Alignment) {
+        self.widget.alignment = Some(alignment);
         self.set_text_properties(|layout| layout.set_text_alignment(alignment));
     }
     pub fn set_font(&mut self, font_stack: FontStack<'static>) {
-        self.set_text_properties(|layout| layout.set_font(font_stack));
+        self.widget.style.font = Some(font_stack);
+        self.apply_text_style();
     }
     pub fn set_font_family(&mut self, family: FontFamily<'static>) {
         self.set_font(FontStack::Single(family));
     }
+    pub fn set_line_height(&mut self, line_height: LineHeight) {
+        self.set_text_properties(|layout| layout.set_line_height(line_height));
+    }
+    pub fn set_letter_spacing(&mut self, letter_spacing: f32) {
+        self.set_text_properties(|layout| layout.set_letter_spacing(letter_spacing));
+    }
     pub fn set_line_break_mode(&mut self, line_break_mode: LineBreaking) {
         self.widget.line_break_mode = line_break_mode;
         self.ctx.request_paint();
     }
+
+    /// Highlight the byte `ranges` of the text, e.g. the matches of a search, with the
+    /// one at `active_index` in the active highlight color.
+    ///
+    /// The highlights are painted behind the text. An empty `ranges` clears them.
+    pub fn set_highlights(&mut self, ranges: Vec<Range<usize>>, active_index: Option<usize>) {
+        if self.widget.highlights.set(ranges, active_index) {
+            self.ctx.request_paint();
+        }
+    }
+
+    pub fn match_rects(&self) -> Vec<Rect> {
+        self.widget.match_rects()
+    }
 }
 
 impl Widget for Label {
@@ -177,17 +270,17 @@ impl Widget for Label {
 
     fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle) {
         match event {
-            LifeCycle::DisabledChanged(disabled) => {
-                if self.show_disabled {
-                    if *disabled {
-                        self.text_layout
-                            .set_brush(crate::theme::DISABLED_TEXT_COLOR);
-                    } else {
-                        self.text_layout.set_brush(self.brush.clone());
-                    }
-                }
+            LifeCycle::WidgetAdded
+            | LifeCycle::TextStyleChanged
+            | LifeCycle::DisabledChanged(_) => {
+                let disabled = self.show_disabled && ctx.is_disabled();
                 // TODO: Parley seems to require a relayout when colours change
-                ctx.request_layout();
+                if self
+                    .style
+                    .apply(&mut self.text_layout, ctx.text_style(), disabled)
+                {
+                    ctx.request_layout();
+                }
             }
             LifeCycle::BuildFocusChain => {
                 if !self.text_layout.text().links().is_empty() {
@@ -209,16 +302,7 @@ impl Widget for Label {
         } else {
             None
         };
-        self.text_layout.set_max_advance(max_advance);
-        if self.text_layout.needs_rebuild() {
-            self.text_layout.rebuild(ctx.font_ctx());
-        }
-        // We ignore trailing whitespace for a label
-        let text_size = self.text_layout.size();
-        let label_size = Size {
-            height: text_size.height,
-            width: text_size.width + 2. * LABEL_X_PADDING,
-        };
+        let label_size = self.measure(ctx, max_advance);
         let size = bc.constrain(label_size);
         trace!(
             "Computed layout: max={:?}. w={}, h={}",
@@ -229,6 +313,26 @@ impl Widget for Label {
         size
     }
 
+    // The sizes are rounded up like in `layout`, by `BoxConstraints::constrain`.
+    fn min_intrinsic_width(&mut self, ctx: &mut LayoutCtx, height: f64) -> f64 {
+        if self.line_break_mode == LineBreaking::WordWrap {
+            // Breaking at every opportunity leaves the longest word as the widest line
+            self.measure(ctx, Some(0.0)).expand().width
+        } else {
+            self.max_intrinsic_width(ctx, height)
+        }
+    }
+
+    fn max_intrinsic_width(&mut self, ctx: &mut LayoutCtx, _height: f64) -> f64 {
+        self.measure(ctx, None).expand().width
+    }
+
+    fn max_intrinsic_height(&mut self, ctx: &mut LayoutCtx, width: f64) -> f64 {
+        let max_advance = (self.line_break_mode == LineBreaking::WordWrap && width.is_finite())
+            .then_some(width as f32 - 2. * LABEL_X_PADDING as f32);
+        self.measure(ctx, max_advance).expand().height
+    }
+
     fn paint(&mut self, ctx: &mut PaintCtx, scene: &mut Scene) {
         if self.text_layout.needs_rebuild() {
             debug_panic!("Called Label paint before layout");
@@ -237,8 +341,9 @@ impl Widget for Label {
             let clip_rect = ctx.size().to_rect();
             scene.push_layer(BlendMode::default(), 1., Affine::IDENTITY, &clip_rect);
         }
-        self.text_layout
-            .draw(scene, Point::new(LABEL_X_PADDING, 0.0));
+        let origin = Point::new(LABEL_X_PADDING, 0.0);
+        self.highlights.paint(scene, &self.text_layout, origin);
+        self.text_layout.draw(scene, origin);
 
         if self.line_break_mode == LineBreaking::Clip {
             scene.pop_layer();
@@ -265,14 +370,20 @@ impl Widget for Label {
 
 #[cfg(test)]
 mod tests {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    use image::Rgba;
     use insta::assert_debug_snapshot;
     use parley::style::GenericFamily;
+    use smallvec::smallvec;
 
     use super::*;
-    use crate::assert_render_snapshot;
-    use crate::testing::TestHarness;
+    use crate::testing::{render_tests_skipped, widget_ids, ModularWidget, TestHarness};
     use crate::theme::{PRIMARY_DARK, PRIMARY_LIGHT};
     use crate::widget::{Flex, SizedBox};
+    use crate::WidgetPod;
+    use crate::{assert_render_snapshot, theme};
 
     #[test]
     fn simple_label() {
@@ -370,4 +481,134 @@ mod tests {
         // We don't use assert_eq because we don't want rich assert
         assert!(image_1 == image_2);
     }
+
+    #[test]
+    fn line_height_and_letter_spacing() {
+        let [default_id, spaced_id] = widget_ids();
+
+        let widget = Flex::row()
+            .with_child_id(Label::new("Hello\nworld"), default_id)
+            .with_child_id(
+                Label::new("Hello\nworld")
+                    .with_line_height(LineHeight::FontSizeRelative(1.5))
+                    .with_letter_spacing(2.0),
+                spaced_id,
+            );
+
+        let harness = TestHarness::create(widget);
+
+        let default_rect = harness.get_widget(default_id).state().layout_rect();
+        let spaced_rect = harness.get_widget(spaced_id).state().layout_rect();
+        assert!(spaced_rect.height() > default_rect.height());
+        assert!(spaced_rect.width() > default_rect.width());
+    }
+
+    #[test]
+    fn intrinsic_width() {
+        let widths = Rc::new(Cell::new((0.0, 0.0, 0.0)));
+
+        let widths_clone = widths.clone();
+        let widget = ModularWidget::new(WidgetPod::new(
+            Label::new("The quick brown fox").with_line_break_mode(LineBreaking::WordWrap),
+        ))
+        .lifecycle_fn(|child, ctx, event| child.lifecycle(ctx, event))
+        .layout_fn(move |child, ctx, bc| {
+            let min_width = child.min_intrinsic_width(ctx, f64::INFINITY);
+            let max_width = child.max_intrinsic_width(ctx, f64::INFINITY);
+            let size = child.layout(ctx, &BoxConstraints::UNBOUNDED);
+            ctx.place_child(child, Point::ZERO);
+            widths_clone.set((min_width, max_width, size.width));
+            bc.constrain(size)
+        })
+        .paint_fn(|child, ctx, scene| child.paint(ctx, scene))
+        .children_fn(|child| smallvec![child.as_dyn()]);
+
+        let _harness = TestHarness::create(widget);
+
+        let (min_width, max_width, single_line_width) = widths.get();
+        assert_eq!(max_width, single_line_width);
+        assert!(min_width > 0.0);
+        assert!(min_width < max_width);
+    }
+
+    fn pixel_color(harness: &mut TestHarness, point: Point) -> Color {
+        let Rgba([r, g, b, a]) = *harness.render().get_pixel(point.x as u32, point.y as u32);
+        Color::rgba8(r, g, b, a)
+    }
+
+    #[test]
+    fn active_highlight_has_its_own_color() {
+        if render_tests_skipped() {
+            return;
+        }
+        // The spaces are highlighted, so no glyph is painted over the highlights.
+        let mut harness = TestHarness::create(Label::new("a b c"));
+        harness.edit_root_widget(|mut label| {
+            let mut label = label.downcast::<Label>();
+            label.set_highlights(vec![1..2, 3..4], Some(1));
+        });
+        let origin = harness.root_widget().state().window_origin().to_vec2();
+        let rects = harness
+            .root_widget()
+            .downcast::<Label>()
+            .unwrap()
+            .match_rects();
+        let inactive = rects[0].center() + origin;
+        let active = rects[1].center() + origin;
+        assert_eq!(
+            pixel_color(&mut harness, inactive),
+            theme::TEXT_HIGHLIGHT_COLOR
+        );
+        assert_eq!(
+            pixel_color(&mut harness, active),
+            theme::ACTIVE_TEXT_HIGHLIGHT_COLOR
+        );
+
+        // Clearing the highlights.
+        harness.edit_root_widget(|mut label| {
+            let mut label = label.downcast::<Label>();
+            label.set_highlights(vec![], None);
+        });
+        assert_ne!(
+            pixel_color(&mut harness, active),
+            theme::ACTIVE_TEXT_HIGHLIGHT_COLOR
+        );
+    }
+
+    #[test]
+    fn setting_highlights_only_repaints() {
+        let mut harness = TestHarness::create(Label::new("Hello world"));
+        harness.render();
+        let passes = harness.layout_stats().passes;
+
+        let word = 0..5;
+        harness.edit_root_widget(|mut label| {
+            let mut label = label.downcast::<Label>();
+            label.set_highlights(vec![word.clone()], Some(0));
+        });
+        assert!(!harness.paint_damage().is_empty());
+        assert_eq!(harness.layout_stats().passes, passes);
+    }
+
+    #[test]
+    fn rtl_aligns_to_the_end() {
+        let [default_id, centered_id] = widget_ids();
+        let widget = Flex::column()
+            .with_child_id(Label::new("Hello"), default_id)
+            .with_child_id(
+                Label::new("Hello").with_text_alignment(Alignment::Middle),
+                centered_id,
+            );
+        let mut harness = TestHarness::create(widget);
+        let alignment = |harness: &TestHarness, id| {
+            let label = harness.get_widget(id);
+            label.downcast::<Label>().unwrap().text_layout.alignment()
+        };
+        assert_eq!(alignment(&harness, default_id), Alignment::Start);
+
+        harness.set_layout_direction(crate::LayoutDirection::Rtl);
+        assert_eq!(alignment(&harness, default_id), Alignment::End);
+        // Explicit alignments are kept.
+        assert_eq!(alignment(&harness, centered_id), Alignment::Middle);
+    }
 }
@@ -0,0 +1,437 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! A scrollable grid of columns, into which children of different heights are packed.
+
+use accesskit::Role;
+use smallvec::SmallVec;
+use tracing::{trace, trace_span, Span};
+use vello::peniko::BlendMode;
+use vello::Scene;
+
+use crate::kurbo::{Affine, Rect};
+use crate::widget::{WidgetMut, WidgetPod, WidgetRef};
+use crate::{
+    AccessCtx, AccessEvent, BoxConstraints, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx,
+    PointerEvent, Size, StatusChange, TextEvent, Vec2, Widget, WidgetId,
+};
+
+/// The width of the columns when the grid has an unbounded width.
+const DEFAULT_COLUMN_WIDTH: f64 = 200.0;
+
+/// How many columns a [`MasonryGrid`] has.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GridColumns {
+    /// Always this many columns.
+    Fixed(usize),
+    /// As many columns as fit, each at least this wide.
+    MinWidth(f64),
+}
+
+/// A grid which places each of its children into its shortest column, like a
+/// Pinterest board.
+///
+/// Children are laid out with the width of a column, and the height they want. The
+/// number of columns is either fixed, or derived from the width of the grid, in which
+/// case the children are reflowed when it changes.
+///
+/// The grid scrolls with the mouse wheel when its content is taller than it. Only the
+/// children in view are laid out and painted, the others are stashed. Each child is
+/// still laid out once when the width of the columns changes, to know where the
+/// following children go; a stashed child whose height changes only moves the
+/// children after it once it's in view again.
+pub struct MasonryGrid {
+    children: Vec<WidgetPod<Box<dyn Widget>>>,
+    columns: GridColumns,
+    gap: f64,
+    scroll_offset: f64,
+    /// The height of each child at `measured_width`, once it has been laid out.
+    heights: Vec<Option<f64>>,
+    /// The column width the heights were measured with.
+    measured_width: f64,
+}
+
+impl MasonryGrid {
+    /// Create an empty grid with the given columns.
+    pub fn new(columns: GridColumns) -> Self {
+        MasonryGrid {
+            children: Vec::new(),
+            columns,
+            gap: 0.0,
+            scroll_offset: 0.0,
+            heights: Vec::new(),
+            measured_width: 0.0,
+        }
+    }
+
+    /// Builder-style method to add a child.
+    pub fn with_child(mut self, child: impl Widget) -> Self {
+        self.children.push(WidgetPod::new(Box::new(child)));
+        self.heights.push(None);
+        self
+    }
+
+    /// Builder-style method to add a child with the given id.
+    pub fn with_child_id(mut self, child: impl Widget, id: WidgetId) -> Self {
+        self.children
+            .push(WidgetPod::new_with_id(Box::new(child), id));
+        self.heights.push(None);
+        self
+    }
+
+    /// Builder-style method to set the space between columns, and between the
+    /// children of a column.
+    pub fn with_gap(mut self, gap: f64) -> Self {
+        self.gap = gap.max(0.0);
+        self
+    }
+
+    /// The number of children.
+    pub fn len(&self) -> usize {
+        self.children.len()
+    }
+
+    /// Whether the grid has no children.
+    pub fn is_empty(&self) -> bool {
+        self.children.is_empty()
+    }
+
+    /// The number of columns, and their width, for a grid of the given width.
+    fn column_layout(&self, width: f64) -> (usize, f64) {
+        let gap = self.gap;
+        match self.columns {
+            GridColumns::Fixed(count) => {
+                let count = count.max(1);
+                if width.is_finite() {
+                    let gaps = gap * (count - 1) as f64;
+                    (count, ((width - gaps) / count as f64).max(0.0))
+                } else {
+                    (count, DEFAULT_COLUMN_WIDTH)
+                }
+            }
+            GridColumns::MinWidth(min_width) => {
+                if width.is_finite() {
+                    let count = ((width + gap) / (min_width + gap).max(1.0))
+                        .floor()
+                        .max(1.0) as usize;
+                    let gaps = gap * (count - 1) as f64;
+                    let column_width = ((width - gaps) / count as f64).max(0.0);
+                    // Columns past the number of children would stay empty.
+                    (count.min(self.children.len().max(1)), column_width)
+                } else {
+                    (1, min_width)
+                }
+            }
+        }
+    }
+
+    /// Place each child into the shortest column, returning the child rects in content
+    /// coordinates, and the height of the content.
+    fn pack(&self, count: usize, column_width: f64) -> (Vec<Rect>, f64) {
+        let mut bottoms = vec![0.0_f64; count];
+        let mut rects = Vec::with_capacity(self.children.len());
+        for height in &self.heights {
+            let height = height.unwrap_or(0.0);
+            let (column, &top) = bottoms
+                .iter()
+                .enumerate()
+                .min_by(|(_, a), (_, b)| a.total_cmp(b))
+                .unwrap();
+            let x = column as f64 * (column_width + self.gap);
+            rects.push(Rect::new(x, top, x + column_width, top + height));
+            bottoms[column] = top + height + self.gap;
+        }
+        let content_height = bottoms
+            .iter()
+            .map(|&bottom| bottom - self.gap)
+            .fold(0.0, f64::max);
+        (rects, content_height)
+    }
+}
+
+impl WidgetMut<'_, MasonryGrid> {
+    /// Add a child at the end of the grid.
+    pub fn add_child(&mut self, child: impl Widget) {
+        self.insert_child(self.widget.children.len(), child);
+    }
+
+    /// Insert a child at `idx`.
+    ///
+    /// # Panics
+    ///
+    /// If `idx` is greater than the number of children.
+    pub fn insert_child(&mut self, idx: usize, child: impl Widget) {
+        self.widget
+            .children
+            .insert(idx, WidgetPod::new(Box::new(child)));
+        self.widget.heights.insert(idx, None);
+        self.ctx.children_changed();
+    }
+
+    /// Remove the child at `idx`.
+    ///
+    /// # Panics
+    ///
+    /// If `idx` is out of bounds.
+    pub fn remove_child(&mut self, idx: usize) {
+        self.widget.children.remove(idx);
+        self.widget.heights.remove(idx);
+        self.ctx.children_changed();
+    }
+
+    pub fn child_mut(&mut self, idx: usize) -> Option<WidgetMut<'_, Box<dyn Widget>>> {
+        let child = self.widget.children.get_mut(idx)?;
+        // The child may change its height.
+        self.widget.heights[idx] = None;
+        Some(self.ctx.get_mut(child))
+    }
+
+    pub fn set_columns(&mut self, columns: GridColumns) {
+        self.widget.columns = columns;
+        self.ctx.request_layout();
+    }
+
+    pub fn set_gap(&mut self, gap: f64) {
+        self.widget.gap = gap.max(0.0);
+        self.ctx.request_layout();
+    }
+
+    /// Scroll so that the content at `offset` from the top is at the top of the grid.
+    pub fn set_scroll_offset(&mut self, offset: f64) {
+        self.widget.scroll_offset = offset;
+        self.ctx.request_layout();
+    }
+}
+
+impl Widget for MasonryGrid {
+    fn on_pointer_event(&mut self, ctx: &mut EventCtx, event: &PointerEvent) {
+        for child in &mut self.children {
+            child.on_pointer_event(ctx, event);
+        }
+        if ctx.is_handled() {
+            return;
+        }
+        if let PointerEvent::MouseWheel(delta, _) = event {
+            self.scroll_offset += delta.y;
+            ctx.request_layout();
+            ctx.set_handled();
+        }
+    }
+
+    fn on_text_event(&mut self, ctx: &mut EventCtx, event: &TextEvent) {
+        for child in &mut self.children {
+            child.on_text_event(ctx, event);
+        }
+    }
+
+    fn on_access_event(&mut self, ctx: &mut EventCtx, event: &AccessEvent) {
+        for child in &mut self.children {
+            child.on_access_event(ctx, event);
+        }
+    }
+
+    fn on_status_change(&mut self, _ctx: &mut LifeCycleCtx, _event: &StatusChange) {}
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle) {
+        for child in &mut self.children {
+            child.lifecycle(ctx, event);
+        }
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints) -> Size {
+        let (count, column_width) = self.column_layout(bc.max().width);
+        if column_width != self.measured_width {
+            // Reflow: all the heights are measured again.
+            self.measured_width = column_width;
+            self.heights.fill(None);
+        }
+        let child_bc = BoxConstraints::new(
+            Size::new(column_width, 0.0),
+            Size::new(column_width, f64::INFINITY),
+        );
+        let mut laid_out = vec![false; self.children.len()];
+
+        // Measure the children which haven't been laid out at this width.
+        for (index, child) in self.children.iter_mut().enumerate() {
+            if self.heights[index].is_none() {
+                ctx.set_stashed(child, false);
+                self.heights[index] = Some(child.layout(ctx, &child_bc).height);
+                laid_out[index] = true;
+            }
+        }
+
+        let (mut rects, content_height) = self.pack(count, column_width);
+        let width = if bc.is_width_bounded() {
+            bc.max().width
+        } else {
+            count as f64 * (column_width + self.gap) - self.gap
+        };
+        let size = bc.constrain(Size::new(width, content_height));
+        let max_scroll = (content_height - size.height).max(0.0);
+        self.scroll_offset = self.scroll_offset.clamp(0.0, max_scroll);
+        let (top, bottom) = (self.scroll_offset, self.scroll_offset + size.height);
+        let visible: Vec<bool> = rects
+            .iter()
+            .map(|rect| rect.y1 >= top && rect.y0 <= bottom)
+            .collect();
+
+        // Lay out the children in view, whose heights may have changed.
+        let mut moved = false;
+        for (index, child) in self.children.iter_mut().enumerate() {
+            if visible[index] && !laid_out[index] {
+                ctx.set_stashed(child, false);
+                let height = child.layout(ctx, &child_bc).height;
+                moved |= self.heights[index] != Some(height);
+                self.heights[index] = Some(height);
+                laid_out[index] = true;
+            }
+        }
+        if moved {
+            rects = self.pack(count, column_width).0;
+        }
+
+        for (index, child) in self.children.iter_mut().enumerate() {
+            if laid_out[index] {
+                let origin = rects[index].origin() - Vec2::new(0.0, self.scroll_offset);
+                ctx.place_child(child, origin);
+            }
+            ctx.set_stashed(child, !visible[index]);
+        }
+
+        trace!(
+            "Computed layout: size={}, columns={}, scroll_offset={}",
+            size,
+            count,
+            self.scroll_offset
+        );
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, scene: &mut Scene) {
+        let clip_rect = ctx.size().to_rect();
+        scene.push_layer(BlendMode::default(), 1., Affine::IDENTITY, &clip_rect);
+        for child in &mut self.children {
+            if child.state.is_stashed {
+                ctx.skip_child(child);
+            } else {
+                child.paint(ctx, scene);
+            }
+        }
+        scene.pop_layer();
+    }
+
+    fn accessibility_role(&self) -> Role {
+        Role::GenericContainer
+    }
+
+    fn accessibility(&mut self, ctx: &mut AccessCtx) {
+        for child in &mut self.children {
+            child.accessibility(ctx);
+        }
+    }
+
+    fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
+        self.children.iter().map(|child| child.as_dyn()).collect()
+    }
+
+    fn make_trace_span(&self) -> Span {
+        trace_span!("MasonryGrid")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use winit::dpi::PhysicalSize;
+
+    use super::*;
+    use crate::event::WindowEvent;
+    use crate::testing::{widget_ids, TestHarness};
+    use crate::widget::SizedBox;
+
+    fn card(height: f64) -> SizedBox {
+        SizedBox::empty().height(height)
+    }
+
+    #[test]
+    fn cards_go_into_the_shortest_column() {
+        let ids = widget_ids::<5>();
+        let mut grid = MasonryGrid::new(GridColumns::Fixed(2)).with_gap(10.);
+        for (height, id) in [100., 40., 30., 50., 20.].into_iter().zip(ids) {
+            grid = grid.with_child_id(card(height), id);
+        }
+        let harness = TestHarness::create_with_size(grid, Size::new(410., 400.));
+
+        let rects = ids.map(|id| harness.get_widget(id).state().layout_rect());
+        assert_eq!(rects[0], Rect::new(0., 0., 200., 100.));
+        assert_eq!(rects[1], Rect::new(210., 0., 410., 40.));
+        assert_eq!(rects[2], Rect::new(210., 50., 410., 80.));
+        // The second column is still the shortest.
+        assert_eq!(rects[3], Rect::new(210., 90., 410., 140.));
+        // Both columns are balanced.
+        assert_eq!(rects[4], Rect::new(0., 110., 200., 130.));
+    }
+
+    #[test]
+    fn cards_reflow_when_the_column_count_changes() {
+        let ids = widget_ids::<3>();
+        let mut grid = MasonryGrid::new(GridColumns::MinWidth(150.));
+        for (height, id) in [60., 40., 50.].into_iter().zip(ids) {
+            grid = grid.with_child_id(card(height), id);
+        }
+        let mut harness = TestHarness::create_with_size(grid, Size::new(300., 400.));
+        let rect = |harness: &TestHarness, id| harness.get_widget(id).state().layout_rect();
+        assert_eq!(rect(&harness, ids[2]), Rect::new(150., 40., 300., 90.));
+
+        harness.process_window_event(WindowEvent::Resize(PhysicalSize::new(450, 400)));
+        assert_eq!(rect(&harness, ids[1]), Rect::new(150., 0., 300., 40.));
+        assert_eq!(rect(&harness, ids[2]), Rect::new(300., 0., 450., 50.));
+
+        harness.process_window_event(WindowEvent::Resize(PhysicalSize::new(100, 400)));
+        assert_eq!(rect(&harness, ids[2]), Rect::new(0., 100., 100., 150.));
+    }
+
+    #[test]
+    fn zero_min_width_without_gap() {
+        let ids = widget_ids::<2>();
+        let mut grid = MasonryGrid::new(GridColumns::MinWidth(0.));
+        for id in ids {
+            grid = grid.with_child_id(card(50.), id);
+        }
+        let harness = TestHarness::create_with_size(grid, Size::new(400., 400.));
+
+        let rects = ids.map(|id| harness.get_widget(id).state().layout_rect());
+        assert_eq!(rects[0], Rect::new(0., 0., 1., 50.));
+        assert_eq!(rects[1], Rect::new(1., 0., 2., 50.));
+    }
+
+    #[test]
+    fn only_cards_in_view_are_laid_out() {
+        let grid = (0..100).fold(MasonryGrid::new(GridColumns::Fixed(2)), |grid, _| {
+            grid.with_child(card(50.))
+        });
+        let mut harness = TestHarness::create_with_size(grid, Size::new(200., 100.));
+
+        let shown = |harness: &TestHarness| {
+            harness
+                .root_widget()
+                .children()
+                .iter()
+                .enumerate()
+                .filter(|(_, child)| !child.state().is_stashed)
+                .map(|(index, _)| index)
+                .collect::<Vec<_>>()
+        };
+        // Two rows of 50 pixels fit, and the ones touching the bottom edge count as in view.
+        assert_eq!(shown(&harness), [0, 1, 2, 3, 4, 5]);
+
+        harness.mouse_move((50., 50.));
+        harness.mouse_wheel(Vec2::new(0., 500.));
+        assert_eq!(shown(&harness), [18, 19, 20, 21, 22, 23, 24, 25]);
+        let first = harness.root_widget().children()[20].state().layout_rect();
+        assert_eq!(first.y0, 0.);
+
+        // The scroll offset is clamped to the content.
+        harness.mouse_wheel(Vec2::new(0., 1e6));
+        assert_eq!(shown(&harness), [94, 95, 96, 97, 98, 99]);
+    }
+}
@@ -14,40 +14,88 @@ mod widget_state;
 mod tests;
 
 mod align;
+mod blocking_overlay;
+mod breadcrumbs;
+mod breakpoints;
 mod button;
 mod checkbox;
+mod collapsible;
+mod combo_box;
+mod context_menu;
+mod draggable;
 mod flex;
+mod floating_layer;
+mod focus_scope;
 mod image;
+mod keyboard_activation;
 mod label;
+mod masonry_grid;
+mod popover;
 mod portal;
 mod prose;
+mod range_slider;
+mod rating;
 mod root_widget;
 mod scroll_bar;
 mod sized_box;
+mod skeleton;
 mod spinner;
 mod split;
+mod status_bar;
+mod sticky_list;
+mod switch;
+mod table;
 mod textbox;
+mod tree_view;
+mod visibility_observer;
+mod window_drag_region;
+mod zoom;
 
 use crate::CursorIcon;
 
 pub use self::image::Image;
 pub use align::Align;
+pub use blocking_overlay::BlockingOverlay;
+pub use breadcrumbs::Breadcrumbs;
+pub use breakpoints::Breakpoints;
 pub use button::Button;
-pub use checkbox::Checkbox;
-pub use flex::{Axis, CrossAxisAlignment, Flex, FlexParams, MainAxisAlignment};
+pub use checkbox::{CheckState, Checkbox};
+pub use collapsible::Collapsible;
+pub use combo_box::EditableComboBox;
+pub use context_menu::ContextMenu;
+pub use draggable::{Draggable, SnapGrid};
+pub use flex::{
+    Axis, ChildKind, CrossAxisAlignment, Flex, FlexParams, FlexWrap, MainAxisAlignment,
+};
+pub use floating_layer::{Corner, FloatingLayer};
+pub(crate) use focus_scope::focus_scopes_around;
+pub use focus_scope::FocusScope;
 pub use label::{Label, LineBreaking};
+pub use masonry_grid::{GridColumns, MasonryGrid};
+pub use popover::{Popover, PopoverAlign, PopoverSide};
 pub use portal::Portal;
 pub use prose::Prose;
+pub use range_slider::{RangeSlider, ThumbCrossing};
+pub use rating::Rating;
 pub use root_widget::RootWidget;
 pub use scroll_bar::ScrollBar;
 pub use sized_box::SizedBox;
+pub use skeleton::Skeleton;
 pub use spinner::Spinner;
 pub use split::Split;
+pub use status_bar::{StatusBar, StatusBarSection};
+pub use sticky_list::StickyList;
+pub use switch::Switch;
+pub use table::{ColumnSpec, ColumnWidth, SortDirection, Table};
 pub use textbox::Textbox;
+pub use tree_view::{TreeNode, TreeView};
+pub use visibility_observer::VisibilityObserver;
 pub use widget_mut::WidgetMut;
 pub use widget_pod::WidgetPod;
 pub use widget_ref::WidgetRef;
 pub use widget_state::WidgetState;
+pub use window_drag_region::WindowDragRegion;
+pub use zoom::Zoom;
 
 pub use sized_box::BackgroundBrush;
 #[doc(hidden)]
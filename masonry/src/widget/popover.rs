@@ -0,0 +1,813 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! A widget showing some content next to its anchor child.
+
+use accesskit::Role;
+use smallvec::{smallvec, SmallVec};
+use tracing::{trace, trace_span, Span};
+use vello::Scene;
+use winit::event::MouseButton;
+use winit::keyboard::{Key, NamedKey};
+
+use crate::kurbo::{Affine, BezPath};
+use crate::paint_scene_helpers::{fill_color, stroke};
+use crate::widget::{FocusScope, WidgetMut, WidgetPod, WidgetRef};
+use crate::{
+    theme, AccessCtx, AccessEvent, Action, BoxConstraints, EventCtx, InternalLifeCycle, LayoutCtx,
+    LifeCycle, LifeCycleCtx, PaintCtx, Point, PointerEvent, Rect, Size, StatusChange, TextEvent,
+    Vec2, Widget,
+};
+
+/// The padding between the frame of the popover and its content.
+const POPOVER_PADDING: f64 = 8.0;
+/// The distance between the popover and its anchor, which the arrow spans.
+const ARROW_SIZE: f64 = 6.0;
+
+/// The side of the anchor on which a [`Popover`] is shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PopoverSide {
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+
+impl PopoverSide {
+    fn opposite(self) -> Self {
+        match self {
+            PopoverSide::Top => PopoverSide::Bottom,
+            PopoverSide::Bottom => PopoverSide::Top,
+            PopoverSide::Left => PopoverSide::Right,
+            PopoverSide::Right => PopoverSide::Left,
+        }
+    }
+
+    fn is_vertical(self) -> bool {
+        matches!(self, PopoverSide::Top | PopoverSide::Bottom)
+    }
+}
+
+/// How a [`Popover`] is aligned with its anchor, along the side it's shown on.
+///
+/// For the top and bottom sides, `Start` aligns the left edges; for the left and right
+/// sides, it aligns the top edges.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PopoverAlign {
+    Start,
+    Center,
+    End,
+}
+
+/// A widget which shows some content next to its anchor child when the anchor is clicked.
+///
+/// The content is shown on the requested side of the anchor if it fits in the window,
+/// and on the opposite side otherwise. It's also shifted along that side to stay inside
+/// the window. An arrow can point from the popover to the anchor.
+///
+/// Opening or closing the popover submits [`Action::PopoverToggled`]. It closes when
+/// the pointer is pressed outside of it, or when Escape is pressed. Like
+/// [`ContextMenu`](super::ContextMenu), the widget stays active while the popover is
+/// open, so that it receives all pointer events.
+///
+/// The content is in a [`FocusScope`]: when the popover opens, its first focusable
+/// widget is focused, Tab cycles through its focusable widgets while it's open, and
+/// the widget focused before gets the focus back when it closes.
+///
+/// With [`with_popup_surface`](Self::with_popup_surface), the popover is shown in a
+/// separate borderless window where the platform supports it, so that it can extend past
+/// the edges of the main window. It's then kept inside the
+/// [popup bounds](crate::render_root::RenderRoot::set_popup_bounds) instead of the window,
+/// and drawn without an arrow. Focus, actions and dismissal work the same way.
+pub struct Popover<W: Widget> {
+    anchor: WidgetPod<W>,
+    content: WidgetPod<FocusScope>,
+    side: PopoverSide,
+    align: PopoverAlign,
+    show_arrow: bool,
+    popup_surface: bool,
+    is_open: bool,
+    anchor_pressed: bool,
+    /// Whether the open popover is shown in a popup surface, as of the last layout.
+    in_popup: bool,
+    /// The side the popover is shown on, after flipping.
+    placed_side: PopoverSide,
+    /// The frame of the popover in local coordinates, as of the last layout.
+    frame_rect: Rect,
+    /// The window origin the popover was placed for.
+    placed_at: Point,
+}
+
+impl<W: Widget> Popover<W> {
+    /// Create a popover showing `content` below `anchor`, aligned with its left edge.
+    pub fn new(anchor: W, content: impl Widget) -> Self {
+        Self::new_pod(WidgetPod::new(anchor), content)
+    }
+
+    /// Create a popover around an anchor that is already in a [`WidgetPod`].
+    pub fn new_pod(anchor: WidgetPod<W>, content: impl Widget) -> Self {
+        Popover {
+            anchor,
+            content: WidgetPod::new(
+                FocusScope::new(content)
+                    .with_trap(true)
+                    .with_restore_on_remove(true)
+                    .with_autofocus(true),
+            ),
+            side: PopoverSide::Bottom,
+            align: PopoverAlign::Start,
+            show_arrow: false,
+            popup_surface: false,
+            is_open: false,
+            anchor_pressed: false,
+            in_popup: false,
+            placed_side: PopoverSide::Bottom,
+            frame_rect: Rect::ZERO,
+            placed_at: Point::ORIGIN,
+        }
+    }
+
+    /// Builder-style method to set on which side of the anchor the popover is shown
+    /// if it fits, and how it's aligned with the anchor.
+    pub fn with_placement(mut self, side: PopoverSide, align: PopoverAlign) -> Self {
+        self.side = side;
+        self.align = align;
+        self
+    }
+
+    /// Builder-style method to draw an arrow pointing to the anchor.
+    pub fn with_arrow(mut self, show_arrow: bool) -> Self {
+        self.show_arrow = show_arrow;
+        self
+    }
+
+    /// Builder-style method to show the popover in a popup surface, where the platform
+    /// supports it.
+    pub fn with_popup_surface(mut self, popup_surface: bool) -> Self {
+        self.popup_surface = popup_surface;
+        self
+    }
+
+    /// Whether the popover is open.
+    pub fn is_open(&self) -> bool {
+        self.is_open
+    }
+
+    /// The side of the anchor the popover is shown on, after flipping.
+    pub fn placed_side(&self) -> PopoverSide {
+        self.placed_side
+    }
+
+    /// Whether the open popover is shown in a popup surface.
+    pub fn is_in_popup(&self) -> bool {
+        self.in_popup
+    }
+
+    fn open(&mut self, ctx: &mut EventCtx) {
+        trace!("Popover {:?} opened", ctx.widget_id());
+        self.is_open = true;
+        ctx.set_stashed(&mut self.content, false);
+        ctx.set_active(true);
+        ctx.submit_action(Action::PopoverToggled(true));
+        ctx.request_layout();
+        ctx.request_accessibility_update();
+    }
+
+    fn close(&mut self, ctx: &mut EventCtx) {
+        trace!("Popover {:?} closed", ctx.widget_id());
+        self.is_open = false;
+        ctx.set_stashed(&mut self.content, true);
+        ctx.set_active(false);
+        ctx.submit_action(Action::PopoverToggled(false));
+        ctx.request_layout();
+        ctx.request_accessibility_update();
+    }
+
+    /// The triangle pointing from the frame to the anchor, in local coordinates.
+    fn arrow(&self, anchor_size: Size) -> BezPath {
+        let frame = self.frame_rect;
+        let anchor_center = anchor_size.to_rect().center();
+        // Keep the arrow away from the rounded corners.
+        let margin = theme::BUTTON_BORDER_RADIUS + ARROW_SIZE;
+        let (base_center, direction) = match self.placed_side {
+            PopoverSide::Top => (Point::new(anchor_center.x, frame.y1), Vec2::new(0., 1.)),
+            PopoverSide::Bottom => (Point::new(anchor_center.x, frame.y0), Vec2::new(0., -1.)),
+            PopoverSide::Left => (Point::new(frame.x1, anchor_center.y), Vec2::new(1., 0.)),
+            PopoverSide::Right => (Point::new(frame.x0, anchor_center.y), Vec2::new(-1., 0.)),
+        };
+        let base_center = if self.placed_side.is_vertical() {
+            let x = clamp_within(base_center.x, frame.x0 + margin, frame.x1 - margin);
+            Point::new(x, base_center.y)
+        } else {
+            let y = clamp_within(base_center.y, frame.y0 + margin, frame.y1 - margin);
+            Point::new(base_center.x, y)
+        };
+        let half_base = Vec2::new(direction.y, direction.x) * ARROW_SIZE;
+
+        let mut arrow = BezPath::new();
+        arrow.move_to(base_center - half_base);
+        arrow.line_to(base_center + direction * ARROW_SIZE);
+        arrow.line_to(base_center + half_base);
+        arrow.close_path();
+        arrow
+    }
+}
+
+/// Clamp `value` to `min..=max`, or return the middle of the range if it's empty.
+fn clamp_within(value: f64, min: f64, max: f64) -> f64 {
+    if min > max {
+        (min + max) / 2.
+    } else {
+        value.clamp(min, max)
+    }
+}
+
+/// Place a popover of `size` next to `anchor`, both in window coordinates.
+///
+/// The popover is shown on `side` if it fits there, on the opposite side if it fits
+/// there instead, and otherwise on the side with the most room. It's then shifted along
+/// that side to stay inside `bounds`, usually the window, if possible. Returns the side
+/// used and the rect of the popover.
+fn place_popover(
+    anchor: Rect,
+    size: Size,
+    bounds: Rect,
+    side: PopoverSide,
+    align: PopoverAlign,
+) -> (PopoverSide, Rect) {
+    let room = |side: PopoverSide| match side {
+        PopoverSide::Top => anchor.y0 - bounds.y0 - ARROW_SIZE,
+        PopoverSide::Bottom => bounds.y1 - anchor.y1 - ARROW_SIZE,
+        PopoverSide::Left => anchor.x0 - bounds.x0 - ARROW_SIZE,
+        PopoverSide::Right => bounds.x1 - anchor.x1 - ARROW_SIZE,
+    };
+    let needed = if side.is_vertical() {
+        size.height
+    } else {
+        size.width
+    };
+    let opposite = side.opposite();
+    let side = if room(side) >= needed || (room(opposite) < needed && room(side) >= room(opposite))
+    {
+        side
+    } else {
+        opposite
+    };
+
+    let aligned = |start: f64, end: f64, length: f64, bounds_start: f64, bounds_end: f64| {
+        let position = match align {
+            PopoverAlign::Start => start,
+            PopoverAlign::Center => (start + end - length) / 2.,
+            PopoverAlign::End => end - length,
+        };
+        // Shift it inside the bounds, favoring the start if it's too long.
+        position.min(bounds_end - length).max(bounds_start)
+    };
+    let origin = match side {
+        PopoverSide::Top | PopoverSide::Bottom => {
+            let x = aligned(anchor.x0, anchor.x1, size.width, bounds.x0, bounds.x1);
+            let y = if side == PopoverSide::Top {
+                anchor.y0 - ARROW_SIZE - size.height
+            } else {
+                anchor.y1 + ARROW_SIZE
+            };
+            Point::new(x, y)
+        }
+        PopoverSide::Left | PopoverSide::Right => {
+            let y = aligned(anchor.y0, anchor.y1, size.height, bounds.y0, bounds.y1);
+            let x = if side == PopoverSide::Left {
+                anchor.x0 - ARROW_SIZE - size.width
+            } else {
+                anchor.x1 + ARROW_SIZE
+            };
+            Point::new(x, y)
+        }
+    };
+    (side, Rect::from_origin_size(origin, size))
+}
+
+impl<W: Widget> WidgetMut<'_, Popover<W>> {
+    /// Get a [`WidgetMut`] to the anchor.
+    pub fn anchor_mut(&mut self) -> WidgetMut<'_, W> {
+        self.ctx.get_mut(&mut self.widget.anchor)
+    }
+
+    /// Get a [`WidgetMut`] to the focus scope around the content.
+    pub fn content_mut(&mut self) -> WidgetMut<'_, FocusScope> {
+        self.ctx.get_mut(&mut self.widget.content)
+    }
+
+    /// Set on which side of the anchor the popover is shown if it fits, and how it's
+    /// aligned with the anchor.
+    pub fn set_placement(&mut self, side: PopoverSide, align: PopoverAlign) {
+        self.widget.side = side;
+        self.widget.align = align;
+        self.ctx.request_layout();
+    }
+
+    /// Set whether an arrow points to the anchor.
+    pub fn set_arrow(&mut self, show_arrow: bool) {
+        self.widget.show_arrow = show_arrow;
+        self.ctx.request_paint();
+    }
+
+    /// Set whether the popover is shown in a popup surface, where the platform supports it.
+    pub fn set_popup_surface(&mut self, popup_surface: bool) {
+        self.widget.popup_surface = popup_surface;
+        self.ctx.request_layout();
+    }
+}
+
+impl<W: Widget> Widget for Popover<W> {
+    fn on_pointer_event(&mut self, ctx: &mut EventCtx, event: &PointerEvent) {
+        let pos = event.local_position(ctx);
+
+        if self.is_open {
+            ctx.skip_child(&mut self.anchor);
+            self.content.on_pointer_event(ctx, event);
+            if let PointerEvent::PointerDown(_, _) = event {
+                if !self.frame_rect.contains(pos) {
+                    self.close(ctx);
+                    ctx.set_handled();
+                }
+            }
+            return;
+        }
+
+        self.anchor.on_pointer_event(ctx, event);
+        self.content.on_pointer_event(ctx, event);
+        match event {
+            PointerEvent::PointerDown(MouseButton::Left, _) => {
+                if ctx.is_hot() && !ctx.is_disabled() {
+                    self.anchor_pressed = true;
+                    ctx.set_active(true);
+                }
+            }
+            PointerEvent::PointerUp(_, _) => {
+                if self.anchor_pressed && ctx.is_hot() {
+                    self.open(ctx);
+                } else {
+                    ctx.set_active(false);
+                }
+                self.anchor_pressed = false;
+            }
+            _ => {}
+        }
+    }
+
+    fn on_text_event(&mut self, ctx: &mut EventCtx, event: &TextEvent) {
+        if self.is_open {
+            if let TextEvent::KeyboardKey(key_event, _) = event {
+                if key_event.logical_key == Key::Named(NamedKey::Escape)
+                    && key_event.state.is_pressed()
+                {
+                    self.close(ctx);
+                    ctx.set_handled();
+                    return;
+                }
+            }
+        }
+        self.anchor.on_text_event(ctx, event);
+        self.content.on_text_event(ctx, event);
+    }
+
+    fn on_access_event(&mut self, ctx: &mut EventCtx, event: &AccessEvent) {
+        if event.target == ctx.widget_id() {
+            match event.action {
+                accesskit::Action::Expand if !self.is_open => self.open(ctx),
+                accesskit::Action::Collapse if self.is_open => self.close(ctx),
+                _ => {}
+            }
+        }
+        self.anchor.on_access_event(ctx, event);
+        self.content.on_access_event(ctx, event);
+    }
+
+    fn on_status_change(&mut self, _ctx: &mut LifeCycleCtx, _event: &StatusChange) {}
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle) {
+        self.anchor.lifecycle(ctx, event);
+        self.content.lifecycle(ctx, event);
+
+        // The content is only shown while the popover is open.
+        if !self.is_open && !self.content.state().is_stashed {
+            ctx.set_stashed(&mut self.content, true);
+        }
+
+        match event {
+            // The popover is placed using the window origin of the previous layout pass.
+            // If this widget has moved since, place it again now that its origin is known.
+            LifeCycle::Internal(InternalLifeCycle::ParentWindowOrigin { .. }) => {
+                if self.is_open && ctx.window_origin() != self.placed_at {
+                    ctx.request_layout();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints) -> Size {
+        let size = self.anchor.layout(ctx, bc);
+        ctx.place_child(&mut self.anchor, Point::ORIGIN);
+
+        if !self.is_open {
+            self.frame_rect = Rect::ZERO;
+            self.in_popup = false;
+            ctx.hide_popup();
+            ctx.set_paint_insets(0.);
+            return size;
+        }
+
+        let popup_bounds = ctx.popup_bounds().filter(|_| self.popup_surface);
+        let bounds = popup_bounds.unwrap_or(ctx.window_size().to_rect());
+        let padding = Size::new(2. * POPOVER_PADDING, 2. * POPOVER_PADDING);
+        let max_content_size = (bounds.size() - padding).clamp(Size::ZERO, bounds.size());
+        let content_bc = BoxConstraints::new(Size::ZERO, max_content_size);
+        let content_size = self.content.layout(ctx, &content_bc);
+
+        let window_origin = ctx.widget_state.window_origin();
+        let (side, frame_rect) = place_popover(
+            size.to_rect() + window_origin.to_vec2(),
+            content_size + padding,
+            bounds,
+            self.side,
+            self.align,
+        );
+        self.placed_side = side;
+        self.placed_at = window_origin;
+        self.frame_rect = frame_rect - window_origin.to_vec2();
+        ctx.place_child(
+            &mut self.content,
+            self.frame_rect.origin() + Vec2::new(POPOVER_PADDING, POPOVER_PADDING),
+        );
+
+        self.in_popup = popup_bounds.is_some();
+        if self.in_popup {
+            ctx.show_popup(frame_rect);
+            ctx.set_paint_insets(0.);
+        } else {
+            ctx.hide_popup();
+            // The frame and the arrow are painted outside of the anchor.
+            let popover_rect = self.frame_rect.inflate(ARROW_SIZE, ARROW_SIZE);
+            ctx.set_paint_insets(popover_rect.union(size.to_rect()) - size.to_rect());
+        }
+
+        trace!(
+            "Computed layout: size={}, popover={} on {:?}",
+            size,
+            self.frame_rect,
+            side
+        );
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, scene: &mut Scene) {
+        self.anchor.paint(ctx, scene);
+
+        if !self.is_open {
+            ctx.skip_child(&mut self.content);
+            return;
+        }
+        if self.in_popup {
+            // The popup surface only covers the frame, so there is no room for the arrow.
+            let mut frame_scene = Scene::new();
+            let frame = self.frame_rect.to_rounded_rect(theme::BUTTON_BORDER_RADIUS);
+            fill_color(&mut frame_scene, &frame, theme::BACKGROUND_LIGHT);
+            stroke(&mut frame_scene, &frame, theme::BORDER_LIGHT, 1.0);
+            self.content.paint(ctx, &mut frame_scene);
+
+            let mut popup_scene = Scene::new();
+            let transform = Affine::translate(-self.frame_rect.origin().to_vec2());
+            popup_scene.append(&frame_scene, Some(transform));
+            ctx.set_popup_scene(popup_scene);
+            return;
+        }
+
+        let frame = self.frame_rect.to_rounded_rect(theme::BUTTON_BORDER_RADIUS);
+        fill_color(scene, &frame, theme::BACKGROUND_LIGHT);
+        stroke(scene, &frame, theme::BORDER_LIGHT, 1.0);
+        if self.show_arrow {
+            let arrow = self.arrow(ctx.size());
+            fill_color(scene, &arrow, theme::BACKGROUND_LIGHT);
+            stroke(scene, &arrow, theme::BORDER_LIGHT, 1.0);
+        }
+        self.content.paint(ctx, scene);
+    }
+
+    fn accessibility_role(&self) -> Role {
+        Role::GenericContainer
+    }
+
+    fn accessibility(&mut self, ctx: &mut AccessCtx) {
+        self.anchor.accessibility(ctx);
+        self.content.accessibility(ctx);
+        let node = ctx.current_node();
+        node.set_expanded(self.is_open);
+        node.add_action(if self.is_open {
+            accesskit::Action::Collapse
+        } else {
+            accesskit::Action::Expand
+        });
+    }
+
+    fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
+        smallvec![self.anchor.as_dyn(), self.content.as_dyn()]
+    }
+
+    fn make_trace_span(&self) -> Span {
+        trace_span!("Popover")
+    }
+
+    fn get_debug_text(&self) -> Option<String> {
+        self.is_open.then(|| {
+            let surface = if self.in_popup { " in popup" } else { "" };
+            format!("open on {:?}{surface}", self.placed_side)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render_root::RenderRootSignal;
+    use crate::testing::{widget_ids, TestHarness};
+    use crate::widget::{Button, Collapsible, Flex, Label, SizedBox};
+    use crate::WidgetId;
+
+    const WINDOW_SIZE: Size = Size::new(300., 300.);
+
+    fn popover() -> Popover<SizedBox> {
+        Popover::new(
+            SizedBox::new(Label::new("Anchor")).width(60.).height(30.),
+            SizedBox::empty().width(100.).height(80.),
+        )
+    }
+
+    fn get_popover(harness: &TestHarness, id: WidgetId) -> WidgetRef<'_, Popover<SizedBox>> {
+        harness
+            .get_widget(id)
+            .downcast::<Popover<SizedBox>>()
+            .unwrap()
+    }
+
+    /// The frame of the popover, in window coordinates.
+    fn frame_rect(harness: &TestHarness, id: WidgetId) -> Rect {
+        let origin = harness.get_widget(id).state().window_origin();
+        get_popover(harness, id).frame_rect + origin.to_vec2()
+    }
+
+    #[test]
+    fn placement() {
+        let anchor = Rect::new(100., 100., 160., 130.);
+        let size = Size::new(100., 80.);
+        let (side, rect) = place_popover(
+            anchor,
+            size,
+            WINDOW_SIZE.to_rect(),
+            PopoverSide::Bottom,
+            PopoverAlign::Center,
+        );
+        assert_eq!(side, PopoverSide::Bottom);
+        assert_eq!(rect.origin(), Point::new(80., 130. + ARROW_SIZE));
+
+        let (side, rect) = place_popover(
+            anchor,
+            size,
+            WINDOW_SIZE.to_rect(),
+            PopoverSide::Left,
+            PopoverAlign::End,
+        );
+        assert_eq!(side, PopoverSide::Right);
+        assert_eq!(rect.origin(), Point::new(160. + ARROW_SIZE, 50.));
+
+        // Doesn't fit on either side: use the one with the most room.
+        let (side, _) = place_popover(
+            anchor,
+            Size::new(100., 200.),
+            WINDOW_SIZE.to_rect(),
+            PopoverSide::Top,
+            PopoverAlign::Start,
+        );
+        assert_eq!(side, PopoverSide::Bottom);
+    }
+
+    #[test]
+    fn flips_near_window_edge() {
+        let [popover_id] = widget_ids();
+        let widget = Flex::column()
+            .with_flex_spacer(1.0)
+            .with_child_id(popover().with_arrow(true), popover_id);
+        let mut harness = TestHarness::create_with_size(widget, WINDOW_SIZE);
+
+        harness.mouse_click_on(popover_id);
+        assert_eq!(
+            harness.pop_action(),
+            Some((Action::PopoverToggled(true), popover_id))
+        );
+        let popover = get_popover(&harness, popover_id);
+        assert!(popover.is_open());
+        assert_eq!(popover.placed_side(), PopoverSide::Top);
+
+        let anchor_rect = harness.get_widget(popover_id).state().window_layout_rect();
+        let frame = frame_rect(&harness, popover_id);
+        assert_eq!(frame.y1, anchor_rect.y0 - ARROW_SIZE);
+        assert!(frame.y0 >= 0.);
+    }
+
+    #[test]
+    fn shifts_inside_window() {
+        let [popover_id] = widget_ids();
+        let widget = Flex::row()
+            .with_flex_spacer(1.0)
+            .with_child_id(popover(), popover_id);
+        let mut harness = TestHarness::create_with_size(widget, WINDOW_SIZE);
+
+        harness.mouse_click_on(popover_id);
+        assert_eq!(
+            get_popover(&harness, popover_id).placed_side(),
+            PopoverSide::Bottom
+        );
+        // Aligned with the left edge of the anchor, the popover would overflow.
+        let frame = frame_rect(&harness, popover_id);
+        assert_eq!(frame.x1, WINDOW_SIZE.width);
+    }
+
+    #[test]
+    fn outside_click_dismisses() {
+        let [popover_id] = widget_ids();
+        let widget = Flex::column().with_child_id(popover(), popover_id);
+        let mut harness = TestHarness::create_with_size(widget, WINDOW_SIZE);
+
+        harness.mouse_click_on(popover_id);
+        let _ = harness.pop_action();
+
+        // Clicking the content doesn't close the popover.
+        let frame = frame_rect(&harness, popover_id);
+        harness.mouse_move(frame.center());
+        harness.mouse_button_press(MouseButton::Left);
+        harness.mouse_button_release(MouseButton::Left);
+        assert!(get_popover(&harness, popover_id).is_open());
+        assert_eq!(harness.pop_action(), None);
+
+        harness.mouse_move((290., 290.));
+        harness.mouse_button_press(MouseButton::Left);
+        harness.mouse_button_release(MouseButton::Left);
+        assert!(!get_popover(&harness, popover_id).is_open());
+        assert_eq!(
+            harness.pop_action(),
+            Some((Action::PopoverToggled(false), popover_id))
+        );
+    }
+
+    #[test]
+    fn focus_stays_in_open_content() {
+        let [before_id, popover_id, first_id, second_id] = widget_ids();
+        let content = Flex::column()
+            .with_child_id(Button::new("First"), first_id)
+            .with_child_id(Button::new("Second"), second_id);
+        let popover = Popover::new(
+            SizedBox::new(Label::new("Anchor")).width(60.).height(30.),
+            content,
+        );
+        let page = Flex::column()
+            .with_child_id(Button::new("Before"), before_id)
+            .with_child_id(popover, popover_id);
+        let widget = FocusScope::new(page).with_autofocus(true);
+        let mut harness = TestHarness::create_with_size(widget, WINDOW_SIZE);
+        let focused = |harness: &TestHarness| harness.focused_widget().map(|widget| widget.id());
+        assert_eq!(focused(&harness), Some(before_id));
+
+        // The closed content can't be focused.
+        harness.move_focus(true);
+        assert_eq!(focused(&harness), Some(before_id));
+
+        harness.mouse_click_on(popover_id);
+        assert!(get_popover(&harness, popover_id).is_open());
+        assert_eq!(focused(&harness), Some(first_id));
+        for expected in [second_id, first_id] {
+            harness.move_focus(true);
+            assert_eq!(focused(&harness), Some(expected));
+        }
+
+        harness.mouse_move((290., 290.));
+        harness.mouse_button_press(MouseButton::Left);
+        harness.mouse_button_release(MouseButton::Left);
+        assert!(!get_popover(&harness, popover_id).is_open());
+        assert_eq!(focused(&harness), Some(before_id));
+    }
+
+    #[test]
+    fn popup_surface_extends_past_window() {
+        let [popover_id, button_id] = widget_ids();
+        let popover = Popover::new(
+            SizedBox::new(Label::new("Anchor")).width(60.).height(30.),
+            SizedBox::new_with_id(Button::new("Item"), button_id)
+                .width(100.)
+                .height(80.),
+        )
+        .with_popup_surface(true);
+        let widget = Flex::column()
+            .with_flex_spacer(1.0)
+            .with_child_id(popover, popover_id);
+        let mut harness = TestHarness::create_with_size(widget, WINDOW_SIZE);
+        harness.set_popup_bounds(Some(Rect::new(-50., -50., 350., 600.)));
+
+        harness.mouse_click_on(popover_id);
+        assert_eq!(
+            harness.pop_action(),
+            Some((Action::PopoverToggled(true), popover_id))
+        );
+        let popover = get_popover(&harness, popover_id);
+        assert!(popover.is_in_popup());
+        // There is room below the anchor on the screen, so the popover doesn't flip.
+        assert_eq!(popover.placed_side(), PopoverSide::Bottom);
+        let frame = frame_rect(&harness, popover_id);
+        assert!(frame.y1 > WINDOW_SIZE.height);
+        let signal = harness.pop_signal_matching(
+            |signal| matches!(signal, RenderRootSignal::ShowPopup(id, _) if *id == popover_id),
+        );
+        assert!(matches!(signal, Some(RenderRootSignal::ShowPopup(_, rect)) if rect == frame));
+
+        // Pointer events over the popup come in window coordinates.
+        harness.mouse_move(frame.center());
+        harness.mouse_button_press(MouseButton::Left);
+        harness.mouse_button_release(MouseButton::Left);
+        assert_eq!(
+            harness.pop_action(),
+            Some((Action::ButtonPressed, button_id))
+        );
+        assert!(get_popover(&harness, popover_id).is_open());
+
+        harness.mouse_move((10., 10.));
+        harness.mouse_button_press(MouseButton::Left);
+        harness.mouse_button_release(MouseButton::Left);
+        assert!(!get_popover(&harness, popover_id).is_open());
+        let signal = harness.pop_signal_matching(
+            |signal| matches!(signal, RenderRootSignal::HidePopup(id) if *id == popover_id),
+        );
+        assert!(signal.is_some());
+    }
+
+    #[test]
+    fn popup_surface_fallback() {
+        let [popover_id] = widget_ids();
+        let widget = Flex::column()
+            .with_flex_spacer(1.0)
+            .with_child_id(popover().with_popup_surface(true), popover_id);
+        let mut harness = TestHarness::create_with_size(widget, WINDOW_SIZE);
+
+        // Without popup bounds, the popover stays inside the window.
+        harness.mouse_click_on(popover_id);
+        let popover = get_popover(&harness, popover_id);
+        assert!(!popover.is_in_popup());
+        assert_eq!(popover.placed_side(), PopoverSide::Top);
+        let signal =
+            harness.pop_signal_matching(|signal| matches!(signal, RenderRootSignal::ShowPopup(..)));
+        assert!(signal.is_none());
+    }
+
+    #[test]
+    fn popup_surface_of_stashed_popover_is_closed() {
+        let [popover_id] = widget_ids();
+        let widget = Collapsible::new(
+            Flex::column().with_child_id(popover().with_popup_surface(true), popover_id),
+        );
+        let mut harness = TestHarness::create_with_size(widget, WINDOW_SIZE);
+        harness.set_popup_bounds(Some(Rect::new(-50., -50., 350., 600.)));
+        harness.mouse_click_on(popover_id);
+        assert!(get_popover(&harness, popover_id).is_in_popup());
+
+        // The popover isn't laid out once its parent is stashed, so it can't hide its
+        // popup itself.
+        harness.edit_root_widget(|mut root| {
+            let mut collapsible = root.downcast::<Collapsible<Flex>>();
+            collapsible.set_collapsed(true, false);
+        });
+        let signal = harness.pop_signal_matching(
+            |signal| matches!(signal, RenderRootSignal::HidePopup(id) if *id == popover_id),
+        );
+        assert!(signal.is_some());
+    }
+
+    #[test]
+    fn popup_surfaces_are_closed_without_popup_bounds() {
+        let [popover_id] = widget_ids();
+        let widget = Flex::column()
+            .with_flex_spacer(1.0)
+            .with_child_id(popover().with_popup_surface(true), popover_id);
+        let mut harness = TestHarness::create_with_size(widget, WINDOW_SIZE);
+        harness.set_popup_bounds(Some(Rect::new(-50., -50., 350., 600.)));
+        harness.mouse_click_on(popover_id);
+        assert!(get_popover(&harness, popover_id).is_in_popup());
+
+        // E.g. the event loop couldn't create the popup window.
+        harness.set_popup_bounds(None);
+        let popover = get_popover(&harness, popover_id);
+        assert!(popover.is_open());
+        assert!(!popover.is_in_popup());
+        let signal = harness.pop_signal_matching(
+            |signal| matches!(signal, RenderRootSignal::HidePopup(id) if *id == popover_id),
+        );
+        assert!(signal.is_some());
+    }
+}
@@ -15,8 +15,8 @@ use vello::Scene;
 use crate::kurbo::{Point, Rect, Size, Vec2};
 use crate::widget::{Axis, ScrollBar, WidgetMut, WidgetRef};
 use crate::{
-    AccessCtx, AccessEvent, BoxConstraints, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx,
-    PointerEvent, StatusChange, TextEvent, Widget, WidgetPod,
+    AccessCtx, AccessEvent, Action, BoxConstraints, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx,
+    PaintCtx, PointerEvent, StatusChange, TextEvent, Widget, WidgetPod,
 };
 
 // TODO - refactor - see issue #15
@@ -42,8 +42,12 @@ pub struct Portal<W: Widget> {
 
 impl<W: Widget> Portal<W> {
     pub fn new(child: W) -> Self {
+        Self::new_pod(WidgetPod::new(child))
+    }
+
+    pub fn new_pod(child: WidgetPod<W>) -> Self {
         Portal {
-            child: WidgetPod::new(child),
+            child,
             viewport_pos: Point::ORIGIN,
             constrain_horizontal: false,
             constrain_vertical: false,
@@ -92,6 +96,14 @@ impl<W: Widget> Portal<W> {
         self
     }
 
+    /// Builder-style method to set the initial position of the viewport.
+    ///
+    /// The position is clamped to the content bounds on the first layout.
+    pub fn viewport_pos(mut self, position: Point) -> Self {
+        self.viewport_pos = position;
+        self
+    }
+
     /// Builder-style method to set whether the child must fill the view.
     ///
     /// If `false` (the default) there is no minimum constraint on the child's
@@ -239,6 +251,7 @@ impl<W: Widget> Widget for Portal<W> {
     fn on_pointer_event(&mut self, ctx: &mut EventCtx, event: &PointerEvent) {
         let portal_size = ctx.size();
         let content_size = self.child.layout_rect().size();
+        let prev_viewport_pos = self.viewport_pos;
 
         match event {
             PointerEvent::MouseWheel(delta, _) => {
@@ -281,6 +294,12 @@ impl<W: Widget> Widget for Portal<W> {
                 .into();
             ctx.request_layout();
         }
+
+        // Only user-initiated scrolling is reported; programmatic changes
+        // through `WidgetMut` don't emit an action.
+        if self.viewport_pos != prev_viewport_pos {
+            ctx.submit_action(Action::ViewportMoved(self.viewport_pos));
+        }
     }
 
     // TODO - handle Home/End keys, etc
@@ -305,8 +324,44 @@ impl<W: Widget> Widget for Portal<W> {
             LifeCycle::WidgetAdded => {
                 ctx.register_as_portal();
             }
-            //TODO
-            //LifeCycle::RequestPanToChild(target_rect) => {}
+            LifeCycle::ViewportChanged(viewport) => {
+                // The content is clipped to the portal.
+                let portal_rect = Rect::from_origin_size(ctx.window_origin(), ctx.size());
+                let content_viewport = viewport.intersect(portal_rect);
+                self.child
+                    .lifecycle(ctx, &LifeCycle::ViewportChanged(content_viewport));
+                self.scrollbar_horizontal.lifecycle(ctx, event);
+                self.scrollbar_vertical.lifecycle(ctx, event);
+                return;
+            }
+            LifeCycle::RequestPanToChild(target_rect) => {
+                // The rect is in the coordinates of the portal, not of its content.
+                let target = *target_rect + self.viewport_pos.to_vec2();
+                let viewport = Rect::from_origin_size(self.viewport_pos, ctx.size());
+                let new_pos = Point::new(
+                    compute_pan_range(
+                        viewport.min_x()..viewport.max_x(),
+                        target.min_x()..target.max_x(),
+                    )
+                    .start,
+                    compute_pan_range(
+                        viewport.min_y()..viewport.max_y(),
+                        target.min_y()..target.max_y(),
+                    )
+                    .start,
+                );
+                let portal_size = ctx.size();
+                let content_size = self.child.layout_rect().size();
+                if self.set_viewport_pos_raw(portal_size, content_size, new_pos) {
+                    let scroll_range = content_size - portal_size;
+                    ctx.get_mut(&mut self.scrollbar_horizontal)
+                        .set_cursor_progress(self.viewport_pos.x / scroll_range.width);
+                    ctx.get_mut(&mut self.scrollbar_vertical)
+                        .set_cursor_progress(self.viewport_pos.y / scroll_range.height);
+                    ctx.request_layout();
+                }
+                return;
+            }
             _ => {}
         }
 
@@ -335,7 +390,7 @@ impl<W: Widget> Widget for Portal<W> {
         self.set_viewport_pos_raw(portal_size, content_size, self.viewport_pos);
         // TODO - recompute portal progress
 
-        ctx.place_child(&mut self.child, Point::new(0.0, -self.viewport_pos.y));
+        ctx.place_child(&mut self.child, Point::ORIGIN - self.viewport_pos.to_vec2());
 
         self.scrollbar_horizontal_visible =
             !self.constrain_horizontal && portal_size.width < content_size.width;
@@ -408,6 +463,9 @@ impl<W: Widget> Widget for Portal<W> {
 
         ctx.current_node().set_clips_children();
 
+        // The content is clipped to the portal.
+        let portal_rect = ctx.widget_state.window_layout_rect();
+        ctx.viewport = ctx.viewport.intersect(portal_rect);
         self.child.accessibility(ctx);
         self.scrollbar_horizontal.accessibility(ctx);
         self.scrollbar_vertical.accessibility(ctx);
@@ -424,12 +482,16 @@ impl<W: Widget> Widget for Portal<W> {
 
 #[cfg(test)]
 mod tests {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
     use insta::assert_debug_snapshot;
+    use winit::event::MouseButton;
 
     use super::*;
     use crate::assert_render_snapshot;
-    use crate::testing::{widget_ids, TestHarness};
-    use crate::widget::{Button, Flex, SizedBox};
+    use crate::testing::{widget_ids, ModularWidget, TestHarness};
+    use crate::widget::{Button, CrossAxisAlignment, Flex, SizedBox, Zoom};
 
     fn button(text: &'static str) -> impl Widget {
         SizedBox::new(Button::new(text)).width(70.0).height(40.0)
@@ -502,6 +564,101 @@ mod tests {
         assert_render_snapshot!(harness, "button_list_scroll_to_item_13");
     }
 
+    #[test]
+    fn wheel_scroll_submits_viewport_moved() {
+        let mut column = Flex::column();
+        for _ in 0..100 {
+            column = column.with_child(SizedBox::empty().width(70.0).height(40.0));
+        }
+        let widget = Portal::new(column);
+
+        let mut harness = TestHarness::create_with_size(widget, Size::new(400., 400.));
+        let portal_id = harness.root_widget().id();
+
+        harness.mouse_move(Point::new(10.0, 10.0));
+        harness.mouse_wheel(Vec2::new(0.0, 50.0));
+        assert_eq!(
+            harness.pop_action(),
+            Some((Action::ViewportMoved(Point::new(0.0, 50.0)), portal_id))
+        );
+
+        harness.mouse_wheel(Vec2::new(0.0, 50.0));
+        assert_eq!(
+            harness.pop_action(),
+            Some((Action::ViewportMoved(Point::new(0.0, 100.0)), portal_id))
+        );
+
+        // Programmatic changes are not reported back as actions.
+        harness.edit_root_widget(|mut portal| {
+            let mut portal = portal.downcast::<Portal<Flex>>();
+            portal.set_viewport_pos(Point::new(0.0, 300.0));
+        });
+        assert_eq!(harness.pop_action(), None);
+        assert_eq!(
+            harness
+                .get_widget(portal_id)
+                .downcast::<Portal<Flex>>()
+                .unwrap()
+                .get_viewport_pos(),
+            Point::new(0.0, 300.0)
+        );
+    }
+
+    #[test]
+    fn dragged_child_is_kept_in_view() {
+        let mut column = Flex::column().reorderable(true);
+        for _ in 0..10 {
+            column = column.with_child(SizedBox::empty().width(40.0).height(40.0));
+        }
+        let mut harness = TestHarness::create_with_size(Portal::new(column), Size::new(100., 100.));
+
+        harness.mouse_move((20., 20.));
+        harness.mouse_button_press(MouseButton::Left);
+        // The dragged child goes 15px past the bottom of the portal.
+        harness.mouse_move((20., 95.));
+        harness.edit_root_widget(|mut portal| {
+            let portal = portal.downcast::<Portal<Flex>>();
+            assert_eq!(portal.widget.viewport_pos, Point::new(0., 15.));
+        });
+    }
+
+    #[test]
+    fn local_position_under_scroll_and_zoom() {
+        let [probe_id] = widget_ids();
+        let local_position = Rc::new(Cell::new(None));
+
+        let local_position_clone = local_position.clone();
+        let probe = ModularWidget::new(())
+            .pointer_event_fn(move |_, ctx, event| {
+                if let PointerEvent::PointerDown(_, _) = event {
+                    local_position_clone.set(Some(event.local_position(ctx)));
+                }
+            })
+            .layout_fn(|_, _, bc| bc.constrain(Size::new(100.0, 100.0)));
+        let widget = Portal::new(
+            Flex::column()
+                .cross_axis_alignment(CrossAxisAlignment::Start)
+                .with_spacer(300.0)
+                .with_child(Zoom::new_pod(WidgetPod::new_with_id(probe, probe_id), 2.0))
+                .with_spacer(1000.0),
+        );
+
+        let mut harness = TestHarness::create_with_size(widget, Size::new(400., 400.));
+        harness.edit_root_widget(|mut portal| {
+            let mut portal = portal.downcast::<Portal<Flex>>();
+            portal.set_viewport_pos(Point::new(0.0, 250.0));
+        });
+        // The zoomed probe is painted from (0, 50) to (200, 250) in the window.
+        assert_eq!(
+            harness.get_widget(probe_id).state().window_origin(),
+            Point::new(0.0, 50.0)
+        );
+
+        harness.mouse_move(Point::new(40.0, 90.0));
+        harness.mouse_button_press(MouseButton::Left);
+        assert_eq!(local_position.get(), Some(Point::new(20.0, 20.0)));
+    }
+
     // Helper function for panning tests
     fn make_range(repr: &str) -> Range<f64> {
         let repr = &repr[repr.find('_').unwrap()..];
@@ -1,8 +1,10 @@
 // Copyright 2018 the Xilem Authors and the Druid Authors
 // SPDX-License-Identifier: Apache-2.0
 
+use std::ops::Range;
+
 use accesskit::Role;
-use kurbo::{Affine, Point, Size};
+use kurbo::{Affine, Point, Rect, Size};
 use parley::{
     layout::Alignment,
     style::{FontFamily, FontStack},
@@ -12,7 +14,8 @@ use tracing::trace;
 use vello::{peniko::BlendMode, Scene};
 
 use crate::{
-    text2::{TextBrush, TextStorage, TextWithSelection},
+    text2::{LineHeight, TextBrush, TextHighlights, TextStorage, TextWithSelection},
+    text_style::TextStyleOverrides,
     widget::label::LABEL_X_PADDING,
     AccessCtx, AccessEvent, ArcStr, BoxConstraints, CursorIcon, EventCtx, LayoutCtx, LifeCycle,
     LifeCycleCtx, PaintCtx, PointerEvent, StatusChange, TextEvent, Widget,
@@ -31,7 +34,12 @@ pub struct Prose {
     text_layout: TextWithSelection<ArcStr>,
     line_break_mode: LineBreaking,
     show_disabled: bool,
-    brush: TextBrush,
+    style: TextStyleOverrides,
+    /// The wrap width of the lines being broken in the background, if any.
+    ///
+    /// Until they're ready, the previous lines are displayed.
+    pending_max_advance: Option<Option<f32>>,
+    highlights: TextHighlights,
 }
 
 impl Prose {
@@ -40,7 +48,9 @@ impl Prose {
             text_layout: TextWithSelection::new(text.into(), crate::theme::TEXT_SIZE_NORMAL as f32),
             line_break_mode: LineBreaking::WordWrap,
             show_disabled: true,
-            brush: crate::theme::TEXT_COLOR.into(),
+            style: TextStyleOverrides::default(),
+            pending_max_advance: None,
+            highlights: TextHighlights::new(),
         }
     }
 
@@ -51,12 +61,14 @@ impl Prose {
 
     #[doc(alias = "with_text_color")]
     pub fn with_text_brush(mut self, brush: impl Into<TextBrush>) -> Self {
-        self.brush = brush.into();
-        self.text_layout.set_brush(self.brush.clone());
+        let brush = brush.into();
+        self.text_layout.set_brush(brush.clone());
+        self.style.brush = Some(brush);
         self
     }
 
     pub fn with_text_size(mut self, size: f32) -> Self {
+        self.style.size = Some(size);
         self.text_layout.set_text_size(size);
         self
     }
@@ -67,6 +79,7 @@ impl Prose {
     }
 
     pub fn with_font(mut self, font: FontStack<'static>) -> Self {
+        self.style.font = Some(font);
         self.text_layout.set_font(font);
         self
     }
@@ -74,10 +87,33 @@ impl Prose {
         self.with_font(FontStack::Single(font))
     }
 
+    pub fn with_line_height(mut self, line_height: LineHeight) -> Self {
+        self.text_layout.set_line_height(line_height);
+        self
+    }
+
+    pub fn with_letter_spacing(mut self, letter_spacing: f32) -> Self {
+        self.text_layout.set_letter_spacing(letter_spacing);
+        self
+    }
+
     pub fn with_line_break_mode(mut self, line_break_mode: LineBreaking) -> Self {
         self.line_break_mode = line_break_mode;
         self
     }
+
+    /// The ranges of the text which are highlighted, see [`WidgetMut::set_highlights`].
+    pub fn highlights(&self) -> &TextHighlights {
+        &self.highlights
+    }
+
+    /// The bounding rectangle of each highlighted range, in the prose's coordinates.
+    ///
+    /// See [`Label::match_rects`](super::Label::match_rects).
+    pub fn match_rects(&self) -> Vec<Rect> {
+        self.highlights
+            .match_rects(&*self.text_layout, Point::new(LABEL_X_PADDING, 0.0))
+    }
 }
 
 impl WidgetMut<'_, Prose> {
@@ -108,31 +144,62 @@ impl WidgetMut<'_, Prose> {
         self.set_text_properties(|layout| layout.set_text(new_text));
     }
 
+    /// Apply the inherited text style, and the prose's own, to the text.
+    fn apply_text_style(&mut self) {
+        let disabled = self.widget.show_disabled && self.ctx.is_disabled();
+        let prose = &mut *self.widget;
+        if prose
+            .style
+            .apply(&mut *prose.text_layout, self.ctx.text_style(), disabled)
+        {
+            self.ctx.request_layout();
+        }
+    }
+
     #[doc(alias = "set_text_color")]
     pub fn set_text_brush(&mut self, brush: impl Into<TextBrush>) {
-        let brush = brush.into();
-        self.widget.brush = brush;
-        if !self.ctx.is_disabled() {
-            let brush = self.widget.brush.clone();
-            self.set_text_properties(|layout| layout.set_brush(brush));
-        }
+        self.widget.style.brush = Some(brush.into());
+        self.apply_text_style();
     }
     pub fn set_text_size(&mut self, size: f32) {
-        self.set_text_properties(|layout| layout.set_text_size(size));
+        self.widget.style.size = Some(size);
+        self.apply_text_style();
     }
     pub fn set_alignment(&mut self, alignment: Alignment) {
         self.set_text_properties(|layout| layout.set_text_alignment(alignment));
     }
     pub fn set_font(&mut self, font_stack: FontStack<'static>) {
-        self.set_text_properties(|layout| layout.set_font(font_stack));
+        self.widget.style.font = Some(font_stack);
+        self.apply_text_style();
     }
     pub fn set_font_family(&mut self, family: FontFamily<'static>) {
         self.set_font(FontStack::Single(family));
     }
+    pub fn set_line_height(&mut self, line_height: LineHeight) {
+        self.set_text_properties(|layout| layout.set_line_height(line_height));
+    }
+    pub fn set_letter_spacing(&mut self, letter_spacing: f32) {
+        self.set_text_properties(|layout| layout.set_letter_spacing(letter_spacing));
+    }
     pub fn set_line_break_mode(&mut self, line_break_mode: LineBreaking) {
         self.widget.line_break_mode = line_break_mode;
         self.ctx.request_paint();
     }
+
+    /// Highlight the byte `ranges` of the text, e.g. the matches of a search, with the
+    /// one at `active_index` in the active highlight color.
+    ///
+    /// The highlights are painted behind the text and the selection, and follow the text
+    /// when it's wrapped at another width. An empty `ranges` clears them.
+    pub fn set_highlights(&mut self, ranges: Vec<Range<usize>>, active_index: Option<usize>) {
+        if self.widget.highlights.set(ranges, active_index) {
+            self.ctx.request_paint();
+        }
+    }
+
+    pub fn match_rects(&self) -> Vec<Rect> {
+        self.widget.match_rects()
+    }
 }
 
 impl Widget for Prose {
@@ -209,17 +276,17 @@ impl Widget for Prose {
 
     fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle) {
         match event {
-            LifeCycle::DisabledChanged(disabled) => {
-                if self.show_disabled {
-                    if *disabled {
-                        self.text_layout
-                            .set_brush(crate::theme::DISABLED_TEXT_COLOR);
-                    } else {
-                        self.text_layout.set_brush(self.brush.clone());
-                    }
-                }
+            LifeCycle::WidgetAdded
+            | LifeCycle::TextStyleChanged
+            | LifeCycle::DisabledChanged(_) => {
+                let disabled = self.show_disabled && ctx.is_disabled();
                 // TODO: Parley seems to require a relayout when colours change
-                ctx.request_layout();
+                if self
+                    .style
+                    .apply(&mut *self.text_layout, ctx.text_style(), disabled)
+                {
+                    ctx.request_layout();
+                }
             }
             LifeCycle::BuildFocusChain => {
                 // TODO: This is *definitely* empty
@@ -243,7 +310,31 @@ impl Widget for Prose {
         } else {
             None
         };
-        self.text_layout.set_max_advance(max_advance);
+        if let Some(lines) = ctx.take_background_line_breaks() {
+            self.pending_max_advance = None;
+            self.text_layout.set_broken_lines(lines);
+        }
+        if self.text_layout.max_advance_differs(max_advance) {
+            // Shaping doesn't depend on the width, so if the text is already shaped,
+            // only its lines need to be broken again, which can be done in the background.
+            if !self.text_layout.needs_rebuild()
+                && ctx.should_break_lines_in_background(self.text_layout.text().as_str().len())
+            {
+                if self.pending_max_advance != Some(max_advance) {
+                    self.pending_max_advance = Some(max_advance);
+                    let layout = self.text_layout.layout().clone();
+                    let alignment = self.text_layout.alignment();
+                    ctx.break_lines_in_background(layout, max_advance, alignment);
+                }
+            } else {
+                self.text_layout.set_max_advance(max_advance);
+                if self.pending_max_advance.take().is_some() {
+                    ctx.cancel_background_line_breaks();
+                }
+            }
+        } else if self.pending_max_advance.take().is_some() {
+            ctx.cancel_background_line_breaks();
+        }
         if self.text_layout.needs_rebuild() {
             self.text_layout.rebuild(ctx.font_ctx());
         }
@@ -267,14 +358,17 @@ impl Widget for Prose {
         if self.text_layout.needs_rebuild() {
             debug_panic!("Called Label paint before layout");
         }
-        if self.line_break_mode == LineBreaking::Clip {
+        // The previous lines may be wider than the widget until the new ones are ready.
+        let clip = self.line_break_mode == LineBreaking::Clip || self.pending_max_advance.is_some();
+        if clip {
             let clip_rect = ctx.size().to_rect();
             scene.push_layer(BlendMode::default(), 1., Affine::IDENTITY, &clip_rect);
         }
-        self.text_layout
-            .draw(scene, Point::new(LABEL_X_PADDING, 0.0));
+        let origin = Point::new(LABEL_X_PADDING, 0.0);
+        self.highlights.paint(scene, &*self.text_layout, origin);
+        self.text_layout.draw(scene, origin);
 
-        if self.line_break_mode == LineBreaking::Clip {
+        if clip {
             scene.pop_layer();
         }
     }
@@ -296,3 +390,123 @@ impl Widget for Prose {
         Some(self.text_layout.text().as_str().chars().take(100).collect())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use kurbo::Vec2;
+    use winit::dpi::PhysicalSize;
+
+    use super::*;
+    use crate::event::WindowEvent;
+    use crate::testing::{widget_ids, TestHarness, TestWidgetExt};
+    use crate::widget::SizedBox;
+    use crate::WidgetId;
+
+    fn long_text() -> String {
+        "The quick brown fox jumps over the lazy dog. ".repeat(300)
+    }
+
+    fn line_count(harness: &TestHarness, id: WidgetId) -> usize {
+        let prose = harness.get_widget(id);
+        prose.downcast::<Prose>().unwrap().text_layout.line_count()
+    }
+
+    fn sync_line_count(text: String, width: f64) -> usize {
+        let [prose_id] = widget_ids();
+        let widget = Prose::new(text).with_id(prose_id);
+        let harness = TestHarness::create_with_size(widget, Size::new(width, 400.0));
+        line_count(&harness, prose_id)
+    }
+
+    #[test]
+    fn large_text_keeps_lines_until_background_breaks_are_ready() {
+        let [prose_id] = widget_ids();
+        let widget = Prose::new(long_text()).with_id(prose_id);
+
+        let mut harness = TestHarness::create_with_size(widget, Size::new(400.0, 400.0));
+        harness.set_background_line_breaks(true);
+        let initial_lines = line_count(&harness, prose_id);
+        assert_eq!(initial_lines, sync_line_count(long_text(), 400.0));
+
+        // Resizing quickly doesn't display any intermediate layout.
+        harness.process_window_event(WindowEvent::Resize(PhysicalSize::new(300, 400)));
+        harness.process_window_event(WindowEvent::Resize(PhysicalSize::new(200, 400)));
+        assert_eq!(line_count(&harness, prose_id), initial_lines);
+
+        harness.run_pending_line_breaks();
+        assert_eq!(
+            line_count(&harness, prose_id),
+            sync_line_count(long_text(), 200.0)
+        );
+    }
+
+    #[test]
+    fn highlight_spans_line_break() {
+        let [prose_id] = widget_ids();
+        let widget = Prose::new("The quick brown fox jumps over the lazy dog").with_id(prose_id);
+        let mut harness = TestHarness::create_with_size(widget, Size::new(120.0, 400.0));
+        assert!(line_count(&harness, prose_id) > 1);
+
+        let prose = harness.get_widget(prose_id);
+        let prose = prose.downcast::<Prose>().unwrap();
+        let layout = &prose.text_layout;
+        let first_line_end = layout.layout().get(0).unwrap().text_range().end;
+        let range = first_line_end - 3..first_line_end + 3;
+        let rects = layout.rects_for_range(range.clone());
+
+        // One rectangle at the end of the first line, and one at the start of the second.
+        assert_eq!(rects.len(), 2);
+        assert!(rects[0].y1 <= rects[1].y0 + 1e-3);
+        assert!(rects[0].x0 > 0.0);
+        assert!(rects[1].x0.abs() < 1e-3);
+        // Each rectangle covers the part of the range on its line.
+        assert_eq!(
+            layout.rects_for_range(range.start..first_line_end),
+            [rects[0]]
+        );
+        assert_eq!(
+            layout.rects_for_range(first_line_end..range.end),
+            [rects[1]]
+        );
+
+        harness.edit_root_widget(|mut root| {
+            let mut sized_box = root.downcast::<SizedBox>();
+            let mut child = sized_box.child_mut().unwrap();
+            let mut prose = child.downcast::<Prose>();
+            prose.set_highlights(vec![range.clone()], Some(0));
+        });
+        let prose = harness.get_widget(prose_id);
+        let match_rect = prose.downcast::<Prose>().unwrap().match_rects()[0];
+        let bounds = rects[0].union(rects[1]) + Vec2::new(LABEL_X_PADDING, 0.0);
+        assert_eq!(match_rect, bounds);
+
+        // The highlight follows the text when it's wrapped at another width.
+        harness.process_window_event(WindowEvent::Resize(PhysicalSize::new(800, 400)));
+        let prose = harness.get_widget(prose_id);
+        let prose = prose.downcast::<Prose>().unwrap();
+        assert_eq!(prose.text_layout.rects_for_range(range).len(), 1);
+        assert!(prose.match_rects()[0].height() < match_rect.height());
+    }
+
+    #[test]
+    fn text_change_discards_background_breaks() {
+        let [prose_id] = widget_ids();
+        let widget = Prose::new(long_text()).with_id(prose_id);
+
+        let mut harness = TestHarness::create_with_size(widget, Size::new(400.0, 400.0));
+        harness.set_background_line_breaks(true);
+        harness.process_window_event(WindowEvent::Resize(PhysicalSize::new(200, 400)));
+
+        harness.edit_root_widget(|mut root| {
+            let mut sized_box = root.downcast::<SizedBox>();
+            let mut child = sized_box.child_mut().unwrap();
+            let mut prose = child.downcast::<Prose>();
+            prose.set_text("Short text".into());
+        });
+        assert_eq!(line_count(&harness, prose_id), 1);
+
+        // The stale lines of the previous text are never displayed.
+        harness.run_pending_line_breaks();
+        assert_eq!(line_count(&harness, prose_id), 1);
+    }
+}
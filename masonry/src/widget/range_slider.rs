@@ -0,0 +1,583 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! A slider with two thumbs, selecting a range of values.
+
+use accesskit::{ActionData, Role};
+use kurbo::Circle;
+use smallvec::SmallVec;
+use tracing::{trace, trace_span, Span};
+use vello::Scene;
+use winit::keyboard::{Key, NamedKey};
+
+use crate::action::Action;
+use crate::kurbo::Rect;
+use crate::paint_scene_helpers::{fill_color, stroke};
+use crate::widget::{WidgetMut, WidgetRef};
+use crate::{
+    theme, AccessCtx, AccessEvent, BoxConstraints, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx,
+    PaintCtx, Point, PointerEvent, Size, StatusChange, TextEvent, Widget,
+};
+
+const THUMB_RADIUS: f64 = 7.0;
+const TRACK_THICKNESS: f64 = 4.0;
+
+/// What happens when a thumb of a [`RangeSlider`] is dragged past the other one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ThumbCrossing {
+    /// The dragged thumb stops at the other one.
+    #[default]
+    Clamp,
+    /// The thumbs swap roles, so the dragged thumb keeps following the pointer.
+    Swap,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Thumb {
+    Low,
+    High,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Drag {
+    Thumb(Thumb),
+    /// Dragging the selected range, which keeps its width.
+    Range {
+        grab_value: f64,
+        low: f64,
+        high: f64,
+    },
+}
+
+/// A slider with two thumbs, selecting a range between `min` and `max`.
+///
+/// Each thumb can be dragged, and so can the selected range between them. When
+/// focused, Tab moves between the thumbs, and the arrow keys move the current one.
+///
+/// Emits [`Action::RangeChanged`] with the new `(low, high)` values when the user
+/// changes the range.
+pub struct RangeSlider {
+    min: f64,
+    max: f64,
+    low: f64,
+    high: f64,
+    step: f64,
+    crossing: ThumbCrossing,
+    drag: Option<Drag>,
+    focused_thumb: Thumb,
+}
+
+impl RangeSlider {
+    /// Create a slider between `min` and `max`, with the given selected range.
+    ///
+    /// The range is clamped to `min..=max`, and its bounds are swapped if needed.
+    pub fn new(min: f64, max: f64, low: f64, high: f64) -> Self {
+        let mut slider = RangeSlider {
+            min,
+            max,
+            low,
+            high,
+            step: (max - min) / 100.,
+            crossing: ThumbCrossing::default(),
+            drag: None,
+            focused_thumb: Thumb::Low,
+        };
+        slider.set_range(low, high);
+        slider
+    }
+
+    /// Builder-style method to set what happens when a thumb is dragged past the other.
+    pub fn with_crossing(mut self, crossing: ThumbCrossing) -> Self {
+        self.crossing = crossing;
+        self
+    }
+
+    /// Builder-style method to set how much the arrow keys move a thumb.
+    ///
+    /// Defaults to a hundredth of the slider's bounds.
+    pub fn with_step(mut self, step: f64) -> Self {
+        self.step = step;
+        self
+    }
+
+    /// Return the selected `(low, high)` range.
+    pub fn range(&self) -> (f64, f64) {
+        (self.low, self.high)
+    }
+
+    fn set_range(&mut self, low: f64, high: f64) {
+        let (low, high) = if low <= high {
+            (low, high)
+        } else {
+            (high, low)
+        };
+        self.low = low.clamp(self.min, self.max);
+        self.high = high.clamp(self.min, self.max);
+    }
+
+    /// Move a thumb, applying the crossing behavior. Returns the thumb holding the
+    /// new value, which changes when the thumbs swap.
+    fn move_thumb(&mut self, thumb: Thumb, value: f64) -> Thumb {
+        let value = value.clamp(self.min, self.max);
+        match (thumb, self.crossing) {
+            (Thumb::Low, ThumbCrossing::Swap) if value > self.high => {
+                self.low = self.high;
+                self.high = value;
+                Thumb::High
+            }
+            (Thumb::High, ThumbCrossing::Swap) if value < self.low => {
+                self.high = self.low;
+                self.low = value;
+                Thumb::Low
+            }
+            (Thumb::Low, _) => {
+                self.low = value.min(self.high);
+                Thumb::Low
+            }
+            (Thumb::High, _) => {
+                self.high = value.max(self.low);
+                Thumb::High
+            }
+        }
+    }
+
+    fn thumb_value(&self, thumb: Thumb) -> f64 {
+        match thumb {
+            Thumb::Low => self.low,
+            Thumb::High => self.high,
+        }
+    }
+
+    fn track_rect(size: Size) -> Rect {
+        let y = size.height / 2.;
+        Rect::new(
+            THUMB_RADIUS,
+            y - TRACK_THICKNESS / 2.,
+            (size.width - THUMB_RADIUS).max(THUMB_RADIUS),
+            y + TRACK_THICKNESS / 2.,
+        )
+    }
+
+    fn value_to_x(&self, size: Size, value: f64) -> f64 {
+        let track = Self::track_rect(size);
+        let span = self.max - self.min;
+        if span > 0. {
+            track.x0 + (value - self.min) * track.width() / span
+        } else {
+            track.x0
+        }
+    }
+
+    fn x_to_value(&self, size: Size, x: f64) -> f64 {
+        let track = Self::track_rect(size);
+        if track.width() > 0. {
+            let x = x.clamp(track.x0, track.x1);
+            self.min + (x - track.x0) * (self.max - self.min) / track.width()
+        } else {
+            self.min
+        }
+    }
+
+    fn thumb_center(&self, size: Size, thumb: Thumb) -> Point {
+        Point::new(
+            self.value_to_x(size, self.thumb_value(thumb)),
+            size.height / 2.,
+        )
+    }
+
+    /// Decide what a press at `x` drags, moving the nearest thumb there if the press
+    /// is on the track outside of the thumbs and the selected range.
+    fn start_drag(&mut self, size: Size, x: f64) -> Drag {
+        let low_x = self.value_to_x(size, self.low);
+        let high_x = self.value_to_x(size, self.high);
+        let value = self.x_to_value(size, x);
+
+        let (low_distance, high_distance) = ((x - low_x).abs(), (x - high_x).abs());
+        if low_distance <= THUMB_RADIUS || high_distance <= THUMB_RADIUS {
+            // When the thumbs overlap, pick the one on the side of the press.
+            if low_distance < high_distance || (low_distance == high_distance && x < low_x) {
+                return Drag::Thumb(Thumb::Low);
+            }
+            return Drag::Thumb(Thumb::High);
+        }
+        if low_x < x && x < high_x {
+            return Drag::Range {
+                grab_value: value,
+                low: self.low,
+                high: self.high,
+            };
+        }
+        let thumb = if x < low_x { Thumb::Low } else { Thumb::High };
+        Drag::Thumb(self.move_thumb(thumb, value))
+    }
+
+    fn drag_to(&mut self, size: Size, drag: Drag, x: f64) -> Drag {
+        let value = self.x_to_value(size, x);
+        match drag {
+            Drag::Thumb(thumb) => Drag::Thumb(self.move_thumb(thumb, value)),
+            Drag::Range {
+                grab_value,
+                low,
+                high,
+            } => {
+                let delta = (value - grab_value).clamp(self.min - low, self.max - high);
+                self.low = low + delta;
+                self.high = high + delta;
+                drag
+            }
+        }
+    }
+
+    /// Handle a key press on the focused slider. Returns whether the key was used.
+    fn on_key(&mut self, key: &Key, shift: bool) -> bool {
+        let delta = match key {
+            Key::Named(NamedKey::Tab) => {
+                return match (self.focused_thumb, shift) {
+                    (Thumb::Low, false) => {
+                        self.focused_thumb = Thumb::High;
+                        true
+                    }
+                    (Thumb::High, true) => {
+                        self.focused_thumb = Thumb::Low;
+                        true
+                    }
+                    // Let focus move to the next widget
+                    _ => false,
+                };
+            }
+            Key::Named(NamedKey::ArrowLeft | NamedKey::ArrowDown) => -self.step,
+            Key::Named(NamedKey::ArrowRight | NamedKey::ArrowUp) => self.step,
+            _ => return false,
+        };
+        let value = self.thumb_value(self.focused_thumb) + delta;
+        self.focused_thumb = self.move_thumb(self.focused_thumb, value);
+        true
+    }
+}
+
+impl WidgetMut<'_, RangeSlider> {
+    /// Set the selected range.
+    ///
+    /// The range is clamped to the slider's bounds, and its bounds are swapped if needed.
+    pub fn set_range(&mut self, low: f64, high: f64) {
+        self.widget.set_range(low, high);
+        self.ctx.request_paint();
+        self.ctx.request_accessibility_update();
+    }
+
+    /// Set the bounds of the slider, clamping the selected range to them.
+    pub fn set_bounds(&mut self, min: f64, max: f64) {
+        self.widget.min = min;
+        self.widget.max = max;
+        let (low, high) = self.widget.range();
+        self.widget.set_range(low, high);
+        self.ctx.request_paint();
+        self.ctx.request_accessibility_update();
+    }
+
+    /// Set what happens when a thumb is dragged past the other.
+    pub fn set_crossing(&mut self, crossing: ThumbCrossing) {
+        self.widget.crossing = crossing;
+    }
+
+    /// Set how much the arrow keys move a thumb.
+    pub fn set_step(&mut self, step: f64) {
+        self.widget.step = step;
+    }
+}
+
+impl Widget for RangeSlider {
+    fn on_pointer_event(&mut self, ctx: &mut EventCtx, event: &PointerEvent) {
+        if ctx.is_disabled() {
+            return;
+        }
+        let size = ctx.size();
+        let origin_x = ctx.widget_state.window_origin().x;
+        let local_x = |position: f64| position - origin_x;
+        let prev_range = self.range();
+        match event {
+            PointerEvent::PointerDown(_, state) => {
+                let drag = self.start_drag(size, local_x(state.position.x));
+                if let Drag::Thumb(thumb) = drag {
+                    self.focused_thumb = thumb;
+                }
+                self.drag = Some(drag);
+                ctx.set_active(true);
+                ctx.request_focus();
+                ctx.request_paint();
+            }
+            PointerEvent::PointerMove(state) => {
+                if let Some(drag) = self.drag {
+                    let drag = self.drag_to(size, drag, local_x(state.position.x));
+                    if let Drag::Thumb(thumb) = drag {
+                        self.focused_thumb = thumb;
+                    }
+                    self.drag = Some(drag);
+                }
+            }
+            PointerEvent::PointerUp(_, _) => {
+                if self.drag.take().is_some() {
+                    ctx.request_paint();
+                }
+                ctx.set_active(false);
+            }
+            _ => (),
+        }
+        if self.range() != prev_range {
+            let (low, high) = self.range();
+            ctx.submit_action(Action::RangeChanged(low, high));
+            ctx.request_accessibility_update();
+            ctx.request_paint();
+            trace!(
+                "RangeSlider {:?} moved to {}..{}",
+                ctx.widget_id(),
+                low,
+                high
+            );
+        }
+    }
+
+    fn on_text_event(&mut self, ctx: &mut EventCtx, event: &TextEvent) {
+        let TextEvent::KeyboardKey(key_event, mods) = event else {
+            return;
+        };
+        if !ctx.is_focused() || ctx.is_disabled() || !key_event.state.is_pressed() {
+            return;
+        }
+        let prev_range = self.range();
+        if self.on_key(&key_event.logical_key, mods.shift_key()) {
+            ctx.set_handled();
+            ctx.request_paint();
+        }
+        if self.range() != prev_range {
+            let (low, high) = self.range();
+            ctx.submit_action(Action::RangeChanged(low, high));
+            ctx.request_accessibility_update();
+        }
+    }
+
+    fn on_access_event(&mut self, ctx: &mut EventCtx, event: &AccessEvent) {
+        if event.target != ctx.widget_id() {
+            return;
+        }
+        let current = self.thumb_value(self.focused_thumb);
+        let value = match (event.action, &event.data) {
+            (accesskit::Action::Increment, _) => current + self.step,
+            (accesskit::Action::Decrement, _) => current - self.step,
+            (accesskit::Action::SetValue, Some(ActionData::NumericValue(value))) => *value,
+            _ => return,
+        };
+        self.focused_thumb = self.move_thumb(self.focused_thumb, value);
+        let (low, high) = self.range();
+        ctx.submit_action(Action::RangeChanged(low, high));
+        ctx.request_accessibility_update();
+        ctx.request_paint();
+    }
+
+    fn on_status_change(&mut self, ctx: &mut LifeCycleCtx, _event: &StatusChange) {
+        ctx.request_paint();
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle) {
+        if let LifeCycle::BuildFocusChain = event {
+            ctx.register_for_focus();
+        }
+    }
+
+    fn layout(&mut self, _ctx: &mut LayoutCtx, bc: &BoxConstraints) -> Size {
+        let width = if bc.is_width_bounded() {
+            bc.max().width
+        } else {
+            theme::WIDE_WIDGET_WIDTH
+        };
+        let size = bc.constrain(Size::new(width, 2. * THUMB_RADIUS));
+        trace!("Computed layout: size={}", size);
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, scene: &mut Scene) {
+        let size = ctx.size();
+        let track = Self::track_rect(size);
+        let radius = TRACK_THICKNESS / 2.;
+        fill_color(
+            scene,
+            &track.to_rounded_rect(radius),
+            theme::BACKGROUND_LIGHT,
+        );
+
+        let selected = Rect::new(
+            self.value_to_x(size, self.low),
+            track.y0,
+            self.value_to_x(size, self.high),
+            track.y1,
+        );
+        let selected_color = if ctx.is_disabled() {
+            theme::DISABLED_FOREGROUND_DARK
+        } else {
+            theme::PRIMARY_LIGHT
+        };
+        fill_color(scene, &selected.to_rounded_rect(radius), selected_color);
+
+        for thumb in [Thumb::Low, Thumb::High] {
+            let circle = Circle::new(self.thumb_center(size, thumb), THUMB_RADIUS - 1.);
+            let thumb_color = if ctx.is_disabled() {
+                theme::DISABLED_FOREGROUND_LIGHT
+            } else {
+                theme::FOREGROUND_LIGHT
+            };
+            fill_color(scene, &circle, thumb_color);
+
+            let border_color = if ctx.is_focused() && thumb == self.focused_thumb {
+                theme::PRIMARY_DARK
+            } else {
+                theme::BORDER_DARK
+            };
+            stroke(scene, &circle, border_color, 1.);
+        }
+    }
+
+    fn accessibility_role(&self) -> Role {
+        Role::Slider
+    }
+
+    fn accessibility(&mut self, ctx: &mut AccessCtx) {
+        let node = ctx.current_node();
+        node.set_min_numeric_value(self.min);
+        node.set_max_numeric_value(self.max);
+        node.set_numeric_value_step(self.step);
+        node.set_numeric_value(self.thumb_value(self.focused_thumb));
+        node.add_action(accesskit::Action::Increment);
+        node.add_action(accesskit::Action::Decrement);
+        node.add_action(accesskit::Action::SetValue);
+    }
+
+    fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
+        SmallVec::new()
+    }
+
+    fn make_trace_span(&self) -> Span {
+        trace_span!("RangeSlider")
+    }
+
+    fn get_debug_text(&self) -> Option<String> {
+        Some(format!("{}..{}", self.low, self.high))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use winit::event::MouseButton;
+
+    use super::*;
+    use crate::testing::{widget_ids, TestHarness};
+    use crate::widget::Flex;
+
+    /// The window position of `value` on a slider laid out by [`harness_with`].
+    fn x_of(harness: &TestHarness, slider_id: crate::WidgetId, value: f64) -> Point {
+        let rect = harness.get_widget(slider_id).state().window_layout_rect();
+        let track_width = rect.width() - 2. * THUMB_RADIUS;
+        Point::new(
+            rect.x0 + THUMB_RADIUS + value * track_width / 100.,
+            rect.center().y,
+        )
+    }
+
+    fn harness_with(slider: RangeSlider) -> (TestHarness, crate::WidgetId) {
+        let [slider_id] = widget_ids();
+        let widget = Flex::column().with_child_id(slider, slider_id);
+        (
+            // Makes the track 200px wide
+            TestHarness::create_with_size(widget, Size::new(200. + 2. * THUMB_RADIUS, 100.)),
+            slider_id,
+        )
+    }
+
+    fn drag(harness: &mut TestHarness, from: Point, to: Point) {
+        harness.mouse_move(from);
+        harness.mouse_button_press(MouseButton::Left);
+        harness.mouse_move(to);
+        harness.mouse_button_release(MouseButton::Left);
+    }
+
+    fn range(harness: &TestHarness, slider_id: crate::WidgetId) -> (f64, f64) {
+        let slider = harness.get_widget(slider_id);
+        slider.downcast::<RangeSlider>().unwrap().range()
+    }
+
+    #[test]
+    fn drag_each_thumb() {
+        let (mut harness, slider_id) = harness_with(RangeSlider::new(0., 100., 20., 80.));
+
+        let (from, to) = (
+            x_of(&harness, slider_id, 20.),
+            x_of(&harness, slider_id, 40.),
+        );
+        drag(&mut harness, from, to);
+        assert_eq!(range(&harness, slider_id), (40., 80.));
+        assert_eq!(
+            harness.pop_action(),
+            Some((Action::RangeChanged(40., 80.), slider_id))
+        );
+
+        let (from, to) = (
+            x_of(&harness, slider_id, 80.),
+            x_of(&harness, slider_id, 90.),
+        );
+        drag(&mut harness, from, to);
+        assert_eq!(range(&harness, slider_id), (40., 90.));
+    }
+
+    #[test]
+    fn crossing_thumbs() {
+        let (mut harness, slider_id) = harness_with(RangeSlider::new(0., 100., 20., 60.));
+        let (from, to) = (
+            x_of(&harness, slider_id, 20.),
+            x_of(&harness, slider_id, 80.),
+        );
+        drag(&mut harness, from, to);
+        assert_eq!(range(&harness, slider_id), (60., 60.));
+
+        let (mut harness, slider_id) =
+            harness_with(RangeSlider::new(0., 100., 20., 60.).with_crossing(ThumbCrossing::Swap));
+        drag(&mut harness, from, to);
+        assert_eq!(range(&harness, slider_id), (60., 80.));
+    }
+
+    #[test]
+    fn drag_whole_range() {
+        let (mut harness, slider_id) = harness_with(RangeSlider::new(0., 100., 20., 60.));
+
+        let (from, to) = (
+            x_of(&harness, slider_id, 40.),
+            x_of(&harness, slider_id, 50.),
+        );
+        drag(&mut harness, from, to);
+        assert_eq!(range(&harness, slider_id), (30., 70.));
+
+        // The range keeps its width at the end of the track
+        let (from, to) = (
+            x_of(&harness, slider_id, 50.),
+            x_of(&harness, slider_id, 100.),
+        );
+        drag(&mut harness, from, to);
+        assert_eq!(range(&harness, slider_id), (60., 100.));
+    }
+
+    #[test]
+    fn access_set_value_moves_focused_thumb() {
+        let (mut harness, slider_id) = harness_with(RangeSlider::new(0., 100., 20., 60.));
+        harness.access_action_with_data(
+            slider_id,
+            accesskit::Action::SetValue,
+            ActionData::NumericValue(35.),
+        );
+        assert_eq!(range(&harness, slider_id), (35., 60.));
+        assert_eq!(
+            harness.pop_action(),
+            Some((Action::RangeChanged(35., 60.), slider_id))
+        );
+
+        harness.access_action(slider_id, accesskit::Action::Increment);
+        assert_eq!(range(&harness, slider_id), (36., 60.));
+    }
+}
@@ -0,0 +1,483 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! A star rating widget.
+
+use std::f64::consts::PI;
+
+use accesskit::{ActionData, Role};
+use kurbo::{Affine, BezPath, Point, Rect};
+use smallvec::SmallVec;
+use tracing::{trace, trace_span, Span};
+use vello::peniko::BlendMode;
+use vello::Scene;
+use winit::keyboard::{Key, NamedKey};
+
+use crate::action::Action;
+use crate::kurbo::Size;
+use crate::paint_scene_helpers::{fill_color, stroke};
+use crate::widget::{WidgetMut, WidgetRef};
+use crate::{
+    theme, AccessCtx, AccessEvent, BoxConstraints, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx,
+    PaintCtx, PointerEvent, StatusChange, TextEvent, Widget,
+};
+
+const STAR_SIZE: f64 = 24.0;
+const STAR_PADDING: f64 = 2.0;
+
+/// A row of stars to pick a rating from, e.g. for reviews.
+///
+/// Hovering the stars previews the rating under the pointer, and clicking commits it.
+/// Clicking the current rating again clears it back to 0. When focused, the arrow keys
+/// adjust the rating, and Home and End set it to 0 and to the maximum.
+///
+/// Changing the rating submits [`Action::RatingChanged`]. A read-only rating only
+/// displays its value, and doesn't take focus.
+pub struct Rating {
+    max: u32,
+    value: f64,
+    half_stars: bool,
+    read_only: bool,
+    /// The rating previewed under the pointer.
+    hovered: Option<f64>,
+}
+
+impl Rating {
+    /// Create a new rating out of `max` stars.
+    ///
+    /// The value is clamped between 0 and `max`. It isn't rounded, so that e.g. an
+    /// average rating can be displayed with a partially filled star.
+    pub fn new(max: u32, value: f64) -> Rating {
+        Rating {
+            max,
+            value: value.clamp(0.0, max as f64),
+            half_stars: false,
+            read_only: false,
+            hovered: None,
+        }
+    }
+
+    /// Builder-style method to let the user pick ratings ending with half a star.
+    pub fn with_half_stars(mut self, half_stars: bool) -> Self {
+        self.half_stars = half_stars;
+        self
+    }
+
+    /// Builder-style method to only display the rating, without letting the user change it.
+    pub fn with_read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    /// The current rating, between 0 and [`max`](Self::max).
+    pub fn value(&self) -> f64 {
+        self.value
+    }
+
+    /// The number of stars.
+    pub fn max(&self) -> u32 {
+        self.max
+    }
+
+    fn step(&self) -> f64 {
+        if self.half_stars {
+            0.5
+        } else {
+            1.0
+        }
+    }
+
+    /// The next step below or above the current value.
+    fn next_step(&self, up: bool) -> f64 {
+        let step = self.step();
+        let steps = self.value / step;
+        let next = if up {
+            steps.floor() + 1.0
+        } else {
+            steps.ceil() - 1.0
+        };
+        (next * step).clamp(0.0, self.max as f64)
+    }
+
+    /// The rating picked by a pointer at `x`, in local coordinates.
+    ///
+    /// Any point on a star picks it: the left half picks half a star, if allowed.
+    fn value_at(&self, x: f64) -> f64 {
+        let step = self.step();
+        let stars = (x / STAR_SIZE).clamp(0.0, self.max as f64);
+        ((stars / step).ceil() * step)
+            .max(step)
+            .min(self.max as f64)
+    }
+
+    /// Handle a key press on the focused rating. Returns whether the key was used.
+    fn on_key(&mut self, key: &Key) -> bool {
+        let value = match key {
+            Key::Named(NamedKey::ArrowLeft | NamedKey::ArrowDown) => self.next_step(false),
+            Key::Named(NamedKey::ArrowRight | NamedKey::ArrowUp) => self.next_step(true),
+            Key::Named(NamedKey::Home) => 0.0,
+            Key::Named(NamedKey::End) => self.max as f64,
+            _ => return false,
+        };
+        self.value = value;
+        true
+    }
+
+    fn commit(&mut self, ctx: &mut EventCtx, value: f64) {
+        if value == self.value {
+            return;
+        }
+        self.value = value;
+        ctx.submit_action(Action::RatingChanged(value));
+        ctx.request_accessibility_update();
+        ctx.request_paint();
+        trace!("Rating {:?} set to {}", ctx.widget_id(), value);
+    }
+
+    fn is_interactive(&self, ctx: &EventCtx) -> bool {
+        !self.read_only && !ctx.is_disabled()
+    }
+}
+
+impl WidgetMut<'_, Rating> {
+    /// Set the rating, without submitting an action.
+    ///
+    /// The value is clamped between 0 and the number of stars.
+    pub fn set_value(&mut self, value: f64) {
+        let value = value.clamp(0.0, self.widget.max as f64);
+        if self.widget.value == value {
+            return;
+        }
+        self.widget.value = value;
+        self.ctx.request_paint();
+        self.ctx.request_accessibility_update();
+    }
+
+    /// Set the number of stars.
+    ///
+    /// The rating is clamped to the new number.
+    pub fn set_max(&mut self, max: u32) {
+        self.widget.max = max;
+        self.widget.value = self.widget.value.min(max as f64);
+        self.ctx.request_layout();
+        self.ctx.request_accessibility_update();
+    }
+
+    /// Let the user pick ratings ending with half a star.
+    pub fn set_half_stars(&mut self, half_stars: bool) {
+        self.widget.half_stars = half_stars;
+        self.ctx.request_accessibility_update();
+    }
+
+    /// Only display the rating, without letting the user change it.
+    pub fn set_read_only(&mut self, read_only: bool) {
+        if self.widget.read_only == read_only {
+            return;
+        }
+        self.widget.read_only = read_only;
+        self.widget.hovered = None;
+        self.ctx.children_changed();
+        self.ctx.request_paint();
+        self.ctx.request_accessibility_update();
+    }
+}
+
+/// A five-pointed star inscribed in the square of side `size` at `origin`.
+fn star_path(origin: Point, size: f64) -> BezPath {
+    let center = origin + (size / 2.0, size / 2.0);
+    let outer = size / 2.0;
+    let inner = outer * 0.4;
+    let mut path = BezPath::new();
+    for i in 0..10 {
+        let radius = if i % 2 == 0 { outer } else { inner };
+        let angle = -PI / 2.0 + i as f64 * PI / 5.0;
+        let point = center + (radius * angle.cos(), radius * angle.sin());
+        if i == 0 {
+            path.move_to(point);
+        } else {
+            path.line_to(point);
+        }
+    }
+    path.close_path();
+    path
+}
+
+impl Widget for Rating {
+    fn on_pointer_event(&mut self, ctx: &mut EventCtx, event: &PointerEvent) {
+        if !self.is_interactive(ctx) {
+            return;
+        }
+        let origin_x = ctx.widget_state.window_origin().x;
+        match event {
+            PointerEvent::PointerMove(state) => {
+                let hovered = ctx
+                    .is_hot()
+                    .then(|| self.value_at(state.position.x - origin_x));
+                if hovered != self.hovered {
+                    self.hovered = hovered;
+                    ctx.request_paint();
+                }
+            }
+            PointerEvent::PointerLeave(_) => {
+                if self.hovered.take().is_some() {
+                    ctx.request_paint();
+                }
+            }
+            PointerEvent::PointerDown(_, _) => {
+                ctx.set_active(true);
+            }
+            PointerEvent::PointerUp(_, state) => {
+                if ctx.is_active() && ctx.is_hot() {
+                    let value = self.value_at(state.position.x - origin_x);
+                    // Clicking the current rating clears it.
+                    let value = if value == self.value { 0.0 } else { value };
+                    self.commit(ctx, value);
+                }
+                ctx.set_active(false);
+            }
+            _ => (),
+        }
+    }
+
+    fn on_text_event(&mut self, ctx: &mut EventCtx, event: &TextEvent) {
+        let TextEvent::KeyboardKey(key_event, _) = event else {
+            return;
+        };
+        if !ctx.is_focused() || !self.is_interactive(ctx) || !key_event.state.is_pressed() {
+            return;
+        }
+        let prev_value = self.value;
+        if self.on_key(&key_event.logical_key) {
+            ctx.set_handled();
+        }
+        if self.value != prev_value {
+            let value = self.value;
+            self.value = prev_value;
+            self.commit(ctx, value);
+        }
+    }
+
+    fn on_access_event(&mut self, ctx: &mut EventCtx, event: &AccessEvent) {
+        if event.target != ctx.widget_id() || !self.is_interactive(ctx) {
+            return;
+        }
+        let value = match (event.action, &event.data) {
+            (accesskit::Action::Increment, _) => self.next_step(true),
+            (accesskit::Action::Decrement, _) => self.next_step(false),
+            (accesskit::Action::SetValue, Some(ActionData::NumericValue(value))) => {
+                let step = self.step();
+                ((value / step).round() * step).clamp(0.0, self.max as f64)
+            }
+            _ => return,
+        };
+        self.commit(ctx, value);
+    }
+
+    fn on_status_change(&mut self, ctx: &mut LifeCycleCtx, _event: &StatusChange) {
+        ctx.request_paint();
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle) {
+        if let LifeCycle::BuildFocusChain = event {
+            if !self.read_only {
+                ctx.register_for_focus();
+            }
+        }
+    }
+
+    fn layout(&mut self, _ctx: &mut LayoutCtx, bc: &BoxConstraints) -> Size {
+        let size = bc.constrain(Size::new(STAR_SIZE * self.max as f64, STAR_SIZE));
+        trace!("Computed size: {}", size);
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, scene: &mut Scene) {
+        let shown = self.hovered.unwrap_or(self.value);
+        let fill = if ctx.is_disabled() {
+            theme::DISABLED_FOREGROUND_DARK
+        } else if self.hovered.is_some() {
+            theme::PRIMARY_LIGHT
+        } else {
+            theme::PRIMARY_DARK
+        };
+        let border = if ctx.is_focused() {
+            theme::BORDER_LIGHT
+        } else {
+            theme::FOREGROUND_DARK
+        };
+
+        for i in 0..self.max {
+            let x = i as f64 * STAR_SIZE;
+            let star = star_path(
+                Point::new(x + STAR_PADDING, STAR_PADDING),
+                STAR_SIZE - 2.0 * STAR_PADDING,
+            );
+            let filled = (shown - i as f64).clamp(0.0, 1.0);
+            if filled >= 1.0 {
+                fill_color(scene, &star, fill);
+            } else if filled > 0.0 {
+                let clip = Rect::new(x, 0.0, x + STAR_SIZE * filled, STAR_SIZE);
+                scene.push_layer(BlendMode::default(), 1., Affine::IDENTITY, &clip);
+                fill_color(scene, &star, fill);
+                scene.pop_layer();
+            }
+            stroke(scene, &star, border, 1.0);
+        }
+    }
+
+    fn accessibility_role(&self) -> Role {
+        Role::Slider
+    }
+
+    fn accessibility(&mut self, ctx: &mut AccessCtx) {
+        let node = ctx.current_node();
+        node.set_min_numeric_value(0.0);
+        node.set_max_numeric_value(self.max as f64);
+        node.set_numeric_value_step(self.step());
+        node.set_numeric_value(self.value);
+        if self.read_only {
+            node.set_read_only();
+        } else {
+            node.add_action(accesskit::Action::Increment);
+            node.add_action(accesskit::Action::Decrement);
+            node.add_action(accesskit::Action::SetValue);
+        }
+    }
+
+    fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
+        SmallVec::new()
+    }
+
+    fn make_trace_span(&self) -> Span {
+        trace_span!("Rating")
+    }
+
+    fn get_debug_text(&self) -> Option<String> {
+        Some(format!("{}/{}", self.value, self.max))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use insta::assert_debug_snapshot;
+    use winit::event::MouseButton;
+
+    use super::*;
+    use crate::testing::{widget_ids, TestHarness};
+    use crate::widget::Flex;
+    use crate::WidgetId;
+
+    fn click_at(harness: &mut TestHarness, id: WidgetId, stars: f64) {
+        let rect = harness.get_widget(id).state().window_layout_rect();
+        harness.mouse_move(Point::new(rect.x0 + stars * STAR_SIZE, rect.center().y));
+        harness.mouse_button_press(MouseButton::Left);
+        harness.mouse_button_release(MouseButton::Left);
+    }
+
+    fn value(harness: &TestHarness, id: WidgetId) -> f64 {
+        harness.get_widget(id).downcast::<Rating>().unwrap().value()
+    }
+
+    #[test]
+    fn click_third_star() {
+        let [rating_id] = widget_ids();
+        let widget = Flex::column().with_child_id(Rating::new(5, 0.0), rating_id);
+        let mut harness = TestHarness::create(widget);
+
+        click_at(&mut harness, rating_id, 2.5);
+        assert_eq!(
+            harness.pop_action(),
+            Some((Action::RatingChanged(3.0), rating_id))
+        );
+        assert_eq!(value(&harness, rating_id), 3.0);
+        assert_debug_snapshot!(harness.get_widget(rating_id));
+
+        // Clicking the current rating clears it.
+        click_at(&mut harness, rating_id, 2.5);
+        assert_eq!(
+            harness.pop_action(),
+            Some((Action::RatingChanged(0.0), rating_id))
+        );
+    }
+
+    #[test]
+    fn access_set_value_snaps_to_stars() {
+        let [rating_id] = widget_ids();
+        let widget = Flex::column().with_child_id(Rating::new(5, 0.0), rating_id);
+        let mut harness = TestHarness::create(widget);
+
+        harness.access_action_with_data(
+            rating_id,
+            accesskit::Action::SetValue,
+            ActionData::NumericValue(3.4),
+        );
+        assert_eq!(
+            harness.pop_action(),
+            Some((Action::RatingChanged(3.0), rating_id))
+        );
+        assert_eq!(value(&harness, rating_id), 3.0);
+    }
+
+    #[test]
+    fn hover_previews_without_committing() {
+        let [rating_id] = widget_ids();
+        let widget = Flex::column().with_child_id(Rating::new(5, 1.0), rating_id);
+        let mut harness = TestHarness::create(widget);
+
+        let rect = harness.get_widget(rating_id).state().window_layout_rect();
+        harness.mouse_move(Point::new(rect.x0 + 3.5 * STAR_SIZE, rect.center().y));
+        let rating = harness.get_widget(rating_id);
+        assert_eq!(rating.downcast::<Rating>().unwrap().hovered, Some(4.0));
+        assert_eq!(value(&harness, rating_id), 1.0);
+        assert_eq!(harness.pop_action(), None);
+    }
+
+    #[test]
+    fn half_stars() {
+        let [rating_id] = widget_ids();
+        let rating = Rating::new(5, 0.0).with_half_stars(true);
+        let widget = Flex::column().with_child_id(rating, rating_id);
+        let mut harness = TestHarness::create(widget);
+
+        click_at(&mut harness, rating_id, 2.25);
+        assert_eq!(value(&harness, rating_id), 2.5);
+        click_at(&mut harness, rating_id, 2.75);
+        assert_eq!(value(&harness, rating_id), 3.0);
+    }
+
+    #[test]
+    fn read_only_ignores_clicks() {
+        let [rating_id] = widget_ids();
+        let rating = Rating::new(5, 2.0).with_read_only(true);
+        let widget = Flex::column().with_child_id(rating, rating_id);
+        let mut harness = TestHarness::create(widget);
+
+        click_at(&mut harness, rating_id, 4.5);
+        assert_eq!(harness.pop_action(), None);
+        assert_eq!(value(&harness, rating_id), 2.0);
+    }
+
+    #[test]
+    fn keys() {
+        let mut rating = Rating::new(5, 2.0).with_half_stars(true);
+        assert!(rating.on_key(&Key::Named(NamedKey::ArrowRight)));
+        assert_eq!(rating.value(), 2.5);
+        assert!(rating.on_key(&Key::Named(NamedKey::ArrowLeft)));
+        assert!(rating.on_key(&Key::Named(NamedKey::ArrowDown)));
+        assert_eq!(rating.value(), 1.5);
+        assert!(rating.on_key(&Key::Named(NamedKey::End)));
+        assert!(rating.on_key(&Key::Named(NamedKey::ArrowRight)));
+        assert_eq!(rating.value(), 5.0);
+        assert!(rating.on_key(&Key::Named(NamedKey::Home)));
+        assert_eq!(rating.value(), 0.0);
+        assert!(!rating.on_key(&Key::Named(NamedKey::Enter)));
+
+        // An average rating goes to the next step.
+        let mut rating = Rating::new(5, 3.7);
+        assert!(rating.on_key(&Key::Named(NamedKey::ArrowRight)));
+        assert_eq!(rating.value(), 4.0);
+        let mut rating = Rating::new(5, 3.7);
+        assert!(rating.on_key(&Key::Named(NamedKey::ArrowLeft)));
+        assert_eq!(rating.value(), 3.0);
+    }
+}
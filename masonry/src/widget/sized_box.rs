@@ -14,8 +14,8 @@ use crate::kurbo::RoundedRectRadii;
 use crate::paint_scene_helpers::{fill_color, stroke};
 use crate::widget::{WidgetId, WidgetMut, WidgetPod, WidgetRef};
 use crate::{
-    AccessCtx, AccessEvent, BoxConstraints, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx,
-    Point, PointerEvent, Size, StatusChange, TextEvent, Widget,
+    AccessCtx, AccessEvent, BoxConstraints, CachedShadow, Elevation, EventCtx, LayoutCtx,
+    LifeCycle, LifeCycleCtx, PaintCtx, Point, PointerEvent, Size, StatusChange, TextEvent, Widget,
 };
 
 // FIXME - Improve all doc in this module ASAP.
@@ -55,6 +55,8 @@ pub struct SizedBox {
     background: Option<BackgroundBrush>,
     border: Option<BorderStyle>,
     corner_radius: RoundedRectRadii,
+    elevation: Elevation,
+    shadow: CachedShadow,
 }
 
 impl SizedBox {
@@ -67,6 +69,8 @@ impl SizedBox {
             background: None,
             border: None,
             corner_radius: RoundedRectRadii::from_single_radius(0.0),
+            elevation: Elevation::default(),
+            shadow: CachedShadow::default(),
         }
     }
 
@@ -79,6 +83,8 @@ impl SizedBox {
             background: None,
             border: None,
             corner_radius: RoundedRectRadii::from_single_radius(0.0),
+            elevation: Elevation::default(),
+            shadow: CachedShadow::default(),
         }
     }
 
@@ -95,6 +101,8 @@ impl SizedBox {
             background: None,
             border: None,
             corner_radius: RoundedRectRadii::from_single_radius(0.0),
+            elevation: Elevation::default(),
+            shadow: CachedShadow::default(),
         }
     }
 
@@ -164,6 +172,14 @@ impl SizedBox {
         self
     }
 
+    /// Builder-style method for painting a drop shadow below the background, following
+    /// the rounded corners, with the style the [`Theme`](crate::Theme) defines for
+    /// `elevation`.
+    pub fn elevation(mut self, elevation: Elevation) -> Self {
+        self.elevation = elevation;
+        self
+    }
+
     // TODO - child()
 }
 
@@ -175,6 +191,12 @@ impl WidgetMut<'_, SizedBox> {
     }
 
     pub fn remove_child(&mut self) {
+        if self.widget.child.is_none() {
+            debug_panic!(
+                "SizedBox::remove_child: cannot remove child 0 of SizedBox with 0 children"
+            );
+            return;
+        }
         self.widget.child = None;
         self.ctx.children_changed();
         self.ctx.request_layout();
@@ -240,6 +262,13 @@ impl WidgetMut<'_, SizedBox> {
         self.ctx.request_paint();
     }
 
+    /// Set the elevation of this container, see [`SizedBox::elevation`].
+    pub fn set_elevation(&mut self, elevation: Elevation) {
+        self.widget.elevation = elevation;
+        // The paint insets depend on the shadow.
+        self.ctx.request_layout();
+    }
+
     // TODO - Doc
     pub fn child_mut(&mut self) -> Option<WidgetMut<'_, Box<dyn Widget>>> {
         let child = self.widget.child.as_mut()?;
@@ -292,11 +321,19 @@ impl Widget for SizedBox {
         }
     }
 
-    fn on_access_event(&mut self, _ctx: &mut EventCtx, _event: &AccessEvent) {}
+    fn on_access_event(&mut self, ctx: &mut EventCtx, event: &AccessEvent) {
+        if let Some(ref mut child) = self.child {
+            child.on_access_event(ctx, event);
+        }
+    }
 
     fn on_status_change(&mut self, _ctx: &mut LifeCycleCtx, _event: &StatusChange) {}
 
     fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle) {
+        // The shadow of the elevation may have changed, along with the paint insets.
+        if matches!(event, LifeCycle::ThemeChanged) && self.elevation != Elevation::default() {
+            ctx.request_layout();
+        }
         if let Some(ref mut child) = self.child {
             child.lifecycle(ctx, event);
         }
@@ -326,7 +363,8 @@ impl Widget for SizedBox {
             None => size = bc.constrain((self.width.unwrap_or(0.0), self.height.unwrap_or(0.0))),
         };
 
-        // TODO - figure out paint insets
+        ctx.set_paint_insets(ctx.theme().shadow_style(self.elevation).insets());
+
         // TODO - figure out baseline offset
 
         trace!("Computed size: {}", size);
@@ -344,6 +382,10 @@ impl Widget for SizedBox {
     fn paint(&mut self, ctx: &mut PaintCtx, scene: &mut Scene) {
         let corner_radius = self.corner_radius;
 
+        let shadow_style = ctx.theme().shadow_style(self.elevation);
+        let shape = ctx.size().to_rounded_rect(corner_radius);
+        self.shadow.paint(scene, shape, &shadow_style);
+
         if let Some(background) = self.background.as_mut() {
             let panel = ctx.size().to_rounded_rect(corner_radius);
 
@@ -438,8 +480,9 @@ mod tests {
 
     use super::*;
     use crate::assert_render_snapshot;
-    use crate::testing::TestHarness;
-    use crate::widget::Label;
+    use crate::testing::{widget_ids, TestHarness};
+    use crate::widget::{Align, Flex, Label};
+    use crate::Insets;
 
     #[test]
     fn expand() {
@@ -498,5 +541,85 @@ mod tests {
         assert_render_snapshot!(harness, "label_box_no_size");
     }
 
+    #[test]
+    fn shadow_extends_paint_rect() {
+        let [box_id] = widget_ids();
+        let widget = Flex::row().with_child_id(
+            SizedBox::empty()
+                .width(40.0)
+                .height(40.0)
+                .rounded(8.0)
+                .elevation(Elevation::Level(3)),
+            box_id,
+        );
+
+        let harness = TestHarness::create(widget);
+
+        // Level 3 of the default theme is moved down by 4px, and blurred over 10px.
+        let state = harness.get_widget(box_id).state();
+        assert_eq!(
+            state.paint_rect(),
+            state.layout_rect() + Insets::new(10.0, 6.0, 10.0, 14.0)
+        );
+    }
+
+    #[test]
+    fn unchanged_shadow_is_built_once() {
+        let [box_id] = widget_ids();
+        let widget = Flex::row()
+            .with_child(SizedBox::empty().width(10.0).height(10.0))
+            .with_child_id(
+                SizedBox::empty()
+                    .width(40.0)
+                    .height(40.0)
+                    .rounded(8.0)
+                    .elevation(Elevation::Level(2)),
+                box_id,
+            );
+
+        let mut harness = TestHarness::create(widget);
+        harness.paint_damage();
+
+        // Moving the box paints it again, with the same shadow.
+        for width in [20.0, 30.0, 40.0] {
+            harness.edit_root_widget(|mut root| {
+                let mut flex = root.downcast::<Flex>();
+                let mut child = flex.child_mut(0).unwrap();
+                child.downcast::<SizedBox>().set_width(width);
+            });
+            harness.paint_damage();
+        }
+        let sized_box = harness.get_widget(box_id);
+        assert_eq!(sized_box.downcast::<SizedBox>().unwrap().shadow.builds(), 1);
+
+        // A new elevation builds a new shadow.
+        harness.edit_root_widget(|mut root| {
+            let mut flex = root.downcast::<Flex>();
+            let mut child = flex.child_mut(1).unwrap();
+            child
+                .downcast::<SizedBox>()
+                .set_elevation(Elevation::Level(4));
+        });
+        harness.paint_damage();
+        let sized_box = harness.get_widget(box_id);
+        assert_eq!(sized_box.downcast::<SizedBox>().unwrap().shadow.builds(), 2);
+    }
+
+    #[test]
+    fn elevation_levels() {
+        for level in [1, 3, 5] {
+            let widget = Align::centered(
+                SizedBox::empty()
+                    .width(60.0)
+                    .height(60.0)
+                    .background(Color::WHITE)
+                    .rounded(8.0)
+                    .elevation(Elevation::Level(level)),
+            );
+            let mut harness = TestHarness::create_with_size(widget, Size::new(120.0, 120.0));
+            assert_render_snapshot!(harness, &format!("elevation_level_{level}"));
+        }
+    }
+
     // TODO - add screenshot tests for different brush types
 }
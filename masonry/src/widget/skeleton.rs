@@ -0,0 +1,232 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! A placeholder widget for content that is loading.
+
+use accesskit::Role;
+use smallvec::SmallVec;
+use tracing::trace;
+use vello::Scene;
+
+use crate::paint_scene_helpers::{fill_color, fill_lin_gradient, UnitPoint};
+use crate::widget::{WidgetMut, WidgetRef};
+use crate::{
+    theme, AccessCtx, AccessEvent, BoxConstraints, Color, EventCtx, LayoutCtx, LifeCycle,
+    LifeCycleCtx, PaintCtx, PointerEvent, Size, StatusChange, TextEvent, Widget,
+};
+
+/// Duration of a shimmer sweep, in seconds.
+const SHIMMER_PERIOD: f64 = 1.5;
+const SHIMMER_COLOR: Color = Color::rgba8(0xff, 0xff, 0xff, 0x20);
+
+/// A rounded block with a shimmer sweeping across it, shown in place of content
+/// that is still loading.
+///
+/// The block fills the available space, unless given an explicit width or height.
+///
+/// If [reduced motion](crate::render_root::RenderRoot::set_reduced_motion) is requested, the
+/// shimmer stops, and the block is static.
+pub struct Skeleton {
+    t: f64,
+    width: Option<f64>,
+    height: Option<f64>,
+    corner_radius: f64,
+}
+
+impl Skeleton {
+    /// Create a skeleton filling the available space.
+    pub fn new() -> Self {
+        Skeleton {
+            t: 0.0,
+            width: None,
+            height: None,
+            corner_radius: theme::BUTTON_BORDER_RADIUS,
+        }
+    }
+
+    /// Builder-style method to set the width of the block.
+    pub fn with_width(mut self, width: f64) -> Self {
+        self.width = Some(width);
+        self
+    }
+
+    /// Builder-style method to set the height of the block.
+    pub fn with_height(mut self, height: f64) -> Self {
+        self.height = Some(height);
+        self
+    }
+
+    /// Builder-style method to set the corner radius of the block.
+    pub fn with_corner_radius(mut self, radius: f64) -> Self {
+        self.corner_radius = radius;
+        self
+    }
+}
+
+impl Default for Skeleton {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WidgetMut<'_, Skeleton> {
+    /// Set the width of the block, or `None` to fill the available width.
+    pub fn set_width(&mut self, width: impl Into<Option<f64>>) {
+        self.widget.width = width.into();
+        self.ctx.request_layout();
+    }
+
+    /// Set the height of the block, or `None` to fill the available height.
+    pub fn set_height(&mut self, height: impl Into<Option<f64>>) {
+        self.widget.height = height.into();
+        self.ctx.request_layout();
+    }
+
+    /// Set the corner radius of the block.
+    pub fn set_corner_radius(&mut self, radius: f64) {
+        self.widget.corner_radius = radius;
+        self.ctx.request_paint();
+    }
+}
+
+impl Widget for Skeleton {
+    fn on_pointer_event(&mut self, _ctx: &mut EventCtx, _event: &PointerEvent) {}
+
+    fn on_text_event(&mut self, _ctx: &mut EventCtx, _event: &TextEvent) {}
+
+    fn on_access_event(&mut self, _ctx: &mut EventCtx, _event: &AccessEvent) {}
+
+    fn on_status_change(&mut self, _ctx: &mut LifeCycleCtx, _event: &StatusChange) {}
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle) {
+        match event {
+            LifeCycle::WidgetAdded => {
+                if !ctx.reduced_motion() {
+                    ctx.request_anim_frame();
+                }
+                ctx.request_paint();
+            }
+            LifeCycle::ReducedMotionChanged => {
+                // The shimmer stopped on the last frame under reduced motion.
+                if !ctx.reduced_motion() {
+                    ctx.request_anim_frame();
+                }
+                ctx.request_paint();
+            }
+            LifeCycle::AnimFrame(interval) => {
+                if ctx.reduced_motion() {
+                    self.t = 0.0;
+                } else {
+                    self.t += (*interval as f64) * 1e-9 / SHIMMER_PERIOD;
+                    self.t = self.t.fract();
+                    ctx.request_anim_frame();
+                }
+                ctx.request_paint();
+            }
+            _ => (),
+        }
+    }
+
+    fn layout(&mut self, _ctx: &mut LayoutCtx, bc: &BoxConstraints) -> Size {
+        let width = self.width.unwrap_or(if bc.is_width_bounded() {
+            bc.max().width
+        } else {
+            theme::WIDE_WIDGET_WIDTH
+        });
+        let height = self.height.unwrap_or(if bc.is_height_bounded() {
+            bc.max().height
+        } else {
+            theme::BASIC_WIDGET_HEIGHT
+        });
+        let size = bc.constrain(Size::new(width, height));
+
+        trace!("Computed size: {}", size);
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, scene: &mut Scene) {
+        let block = ctx.size().to_rect().to_rounded_rect(self.corner_radius);
+        fill_color(scene, &block, theme::BACKGROUND_LIGHT);
+
+        if !ctx.reduced_motion() {
+            // The band starts fully left of the block, and ends fully right of it.
+            let band_start = self.t * 1.5 - 0.5;
+            fill_lin_gradient(
+                scene,
+                &block,
+                [Color::TRANSPARENT, SHIMMER_COLOR, Color::TRANSPARENT],
+                UnitPoint::new(band_start, 0.5),
+                UnitPoint::new(band_start + 0.5, 0.5),
+            );
+        }
+    }
+
+    fn accessibility_role(&self) -> Role {
+        Role::ProgressIndicator
+    }
+
+    fn accessibility(&mut self, ctx: &mut AccessCtx) {
+        ctx.current_node().set_busy();
+    }
+
+    fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
+        SmallVec::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::assert_render_snapshot;
+    use crate::testing::{widget_ids, TestHarness};
+    use crate::widget::Flex;
+
+    #[test]
+    fn skeleton_requests_frames() {
+        let [skeleton_id] = widget_ids();
+        let widget = Flex::column().with_child_id(
+            Skeleton::new().with_width(100.).with_height(20.),
+            skeleton_id,
+        );
+        let harness = TestHarness::create(widget);
+
+        let skeleton = harness.get_widget(skeleton_id);
+        assert!(skeleton.state().request_anim);
+        assert_eq!(skeleton.state().layout_rect().size(), Size::new(100., 20.));
+    }
+
+    #[test]
+    fn static_under_reduced_motion() {
+        let [skeleton_id] = widget_ids();
+        let mut harness = TestHarness::create(Flex::column());
+        harness.set_reduced_motion(true);
+
+        harness.edit_root_widget(|mut root| {
+            let mut flex = root.downcast::<Flex>();
+            flex.add_child_id(
+                Skeleton::new().with_width(100.).with_height(20.),
+                skeleton_id,
+            );
+        });
+
+        assert!(!harness.get_widget(skeleton_id).state().request_anim);
+        assert_render_snapshot!(harness, "static");
+    }
+
+    #[test]
+    fn shimmer_resumes_after_reduced_motion() {
+        let [skeleton_id] = widget_ids();
+        let widget = Flex::column().with_child_id(Skeleton::new(), skeleton_id);
+        let mut harness = TestHarness::create(widget);
+
+        // The frame after reduced motion is requested stops the shimmer.
+        harness.set_reduced_motion(true);
+        harness.animate(Duration::from_millis(16));
+        assert!(!harness.get_widget(skeleton_id).state().request_anim);
+
+        harness.set_reduced_motion(false);
+        assert!(harness.get_widget(skeleton_id).state().request_anim);
+    }
+}
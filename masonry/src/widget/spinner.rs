@@ -21,6 +21,8 @@ use crate::{
 // TODO - Set color
 /// An animated spinner widget for showing a loading state.
 ///
+/// The spinner doesn't turn under [reduced motion](crate::render_root::RenderRoot::set_reduced_motion).
+///
 /// To customize the spinner's size, you can place it inside a [`SizedBox`]
 /// that has a fixed width and height.
 ///
@@ -80,15 +82,28 @@ impl Widget for Spinner {
     fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle) {
         match event {
             LifeCycle::WidgetAdded => {
-                ctx.request_anim_frame();
+                if !ctx.reduced_motion() {
+                    ctx.request_anim_frame();
+                }
+                ctx.request_paint();
+            }
+            LifeCycle::ReducedMotionChanged => {
+                // The spinner stopped on the last frame under reduced motion.
+                if !ctx.reduced_motion() {
+                    ctx.request_anim_frame();
+                }
                 ctx.request_paint();
             }
             LifeCycle::AnimFrame(interval) => {
-                self.t += (*interval as f64) * 1e-9;
-                if self.t >= 1.0 {
+                if ctx.reduced_motion() {
                     self.t = 0.0;
+                } else {
+                    self.t += (*interval as f64) * 1e-9;
+                    if self.t >= 1.0 {
+                        self.t = 0.0;
+                    }
+                    ctx.request_anim_frame();
                 }
-                ctx.request_anim_frame();
                 ctx.request_paint();
             }
             _ => (),
@@ -154,6 +169,8 @@ impl Widget for Spinner {
 
 #[cfg(test)]
 mod tests {
+    use std::time::Duration;
+
     use super::*;
     use crate::assert_render_snapshot;
     use crate::testing::TestHarness;
@@ -196,4 +213,20 @@ mod tests {
         // We don't use assert_eq because we don't want rich assert
         assert!(image_1 == image_2);
     }
+
+    #[test]
+    fn stops_under_reduced_motion() {
+        let mut harness = TestHarness::create(Spinner::new());
+        assert!(harness.root_widget().state().request_anim);
+
+        // The frame after reduced motion is requested stops the spinner on its first step.
+        harness.set_reduced_motion(true);
+        harness.animate(Duration::from_millis(300));
+        assert!(!harness.root_widget().state().request_anim);
+        let t = harness.root_widget().downcast::<Spinner>().unwrap().t;
+        assert_eq!(t, 0.0);
+
+        harness.set_reduced_motion(false);
+        assert!(harness.root_widget().state().request_anim);
+    }
 }
@@ -0,0 +1,268 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! A bar with left, center and right sections, usually at the bottom of a window.
+
+use accesskit::Role;
+use smallvec::SmallVec;
+use tracing::{trace, trace_span, Span};
+use vello::Scene;
+
+use crate::kurbo::Line;
+use crate::paint_scene_helpers::{fill_color, stroke};
+use crate::widget::{WidgetMut, WidgetPod, WidgetRef};
+use crate::{
+    theme, AccessCtx, AccessEvent, BoxConstraints, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx,
+    PaintCtx, Point, PointerEvent, Size, StatusChange, TextEvent, Widget,
+};
+
+const DEFAULT_HEIGHT: f64 = theme::BORDERED_WIDGET_HEIGHT;
+const HORIZONTAL_PADDING: f64 = 8.0;
+
+/// One of the sections of a [`StatusBar`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatusBarSection {
+    /// Pinned to the start of the bar.
+    Left,
+    /// Centered in the bar.
+    Center,
+    /// Pinned to the end of the bar.
+    Right,
+}
+
+/// A bar with a fixed height, taking all the available width, with up to three
+/// child sections.
+///
+/// Each section is vertically centered. The bar has a top border, to separate it
+/// from the content above.
+pub struct StatusBar {
+    sections: [Option<WidgetPod<Box<dyn Widget>>>; 3],
+    height: f64,
+}
+
+impl StatusBarSection {
+    fn index(self) -> usize {
+        match self {
+            StatusBarSection::Left => 0,
+            StatusBarSection::Center => 1,
+            StatusBarSection::Right => 2,
+        }
+    }
+}
+
+impl StatusBar {
+    /// Create a status bar with empty sections.
+    pub fn new() -> Self {
+        StatusBar {
+            sections: [None, None, None],
+            height: DEFAULT_HEIGHT,
+        }
+    }
+
+    /// Builder-style method to set the widget in a section.
+    pub fn with_section(mut self, section: StatusBarSection, child: impl Widget) -> Self {
+        self.sections[section.index()] = Some(WidgetPod::new(child).boxed());
+        self
+    }
+
+    /// Builder-style method to set the height of the bar.
+    pub fn with_height(mut self, height: f64) -> Self {
+        self.height = height;
+        self
+    }
+
+    fn children_mut(&mut self) -> impl Iterator<Item = &mut WidgetPod<Box<dyn Widget>>> {
+        self.sections.iter_mut().flatten()
+    }
+}
+
+impl Default for StatusBar {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WidgetMut<'_, StatusBar> {
+    /// Set the widget in a section, replacing the previous one if any.
+    pub fn set_section(&mut self, section: StatusBarSection, child: impl Widget) {
+        self.widget.sections[section.index()] = Some(WidgetPod::new(child).boxed());
+        self.ctx.children_changed();
+        self.ctx.request_layout();
+    }
+
+    /// Remove the widget in a section.
+    pub fn remove_section(&mut self, section: StatusBarSection) {
+        if self.widget.sections[section.index()].take().is_none() {
+            debug_panic!(
+                "StatusBar::remove_section: cannot remove empty section {:?}",
+                section
+            );
+            return;
+        }
+        self.ctx.children_changed();
+        self.ctx.request_layout();
+    }
+
+    /// Get a [`WidgetMut`] to the widget in a section, if any.
+    pub fn section_mut(
+        &mut self,
+        section: StatusBarSection,
+    ) -> Option<WidgetMut<'_, Box<dyn Widget>>> {
+        let child = self.widget.sections[section.index()].as_mut()?;
+        Some(self.ctx.get_mut(child))
+    }
+
+    /// Set the height of the bar.
+    pub fn set_height(&mut self, height: f64) {
+        self.widget.height = height;
+        self.ctx.request_layout();
+    }
+}
+
+impl Widget for StatusBar {
+    fn on_pointer_event(&mut self, ctx: &mut EventCtx, event: &PointerEvent) {
+        for child in self.children_mut() {
+            child.on_pointer_event(ctx, event);
+        }
+    }
+
+    fn on_text_event(&mut self, ctx: &mut EventCtx, event: &TextEvent) {
+        for child in self.children_mut() {
+            child.on_text_event(ctx, event);
+        }
+    }
+
+    fn on_access_event(&mut self, ctx: &mut EventCtx, event: &AccessEvent) {
+        for child in self.children_mut() {
+            child.on_access_event(ctx, event);
+        }
+    }
+
+    fn on_status_change(&mut self, _ctx: &mut LifeCycleCtx, _event: &StatusChange) {}
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle) {
+        for child in self.children_mut() {
+            child.lifecycle(ctx, event);
+        }
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints) -> Size {
+        let width = if bc.is_width_bounded() {
+            bc.max().width
+        } else {
+            bc.min().width
+        };
+        let size = bc.constrain(Size::new(width, self.height));
+        let child_bc = BoxConstraints::new(
+            Size::ZERO,
+            Size::new((size.width - 2. * HORIZONTAL_PADDING).max(0.), size.height),
+        );
+
+        for (index, child) in self.sections.iter_mut().enumerate() {
+            let Some(child) = child else {
+                continue;
+            };
+            let child_size = child.layout(ctx, &child_bc);
+            let x = match index {
+                0 => HORIZONTAL_PADDING,
+                1 => (size.width - child_size.width) / 2.,
+                _ => size.width - HORIZONTAL_PADDING - child_size.width,
+            };
+            let y = (size.height - child_size.height) / 2.;
+            ctx.place_child(child, Point::new(x, y));
+        }
+
+        trace!("Computed layout: size={}", size);
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, scene: &mut Scene) {
+        let size = ctx.size();
+        fill_color(scene, &size.to_rect(), theme::BACKGROUND_DARK);
+        let border = Line::new((0., 0.5), (size.width, 0.5));
+        stroke(scene, &border, theme::BORDER_LIGHT, 1.0);
+
+        for child in self.children_mut() {
+            child.paint(ctx, scene);
+        }
+    }
+
+    fn accessibility_role(&self) -> Role {
+        Role::Status
+    }
+
+    fn accessibility(&mut self, ctx: &mut AccessCtx) {
+        for child in self.children_mut() {
+            child.accessibility(ctx);
+        }
+    }
+
+    fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
+        self.sections
+            .iter()
+            .flatten()
+            .map(|child| child.as_dyn())
+            .collect()
+    }
+
+    fn make_trace_span(&self) -> Span {
+        trace_span!("StatusBar")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{widget_ids, TestHarness, TestWidgetExt as _};
+    use crate::widget::{Flex, Label, SizedBox};
+
+    #[test]
+    fn left_message_and_right_indicator() {
+        let [message_id, indicator_id, bar_id] = widget_ids();
+
+        let widget = Flex::column().with_flex_spacer(1.0).with_child_id(
+            StatusBar::new()
+                .with_section(
+                    StatusBarSection::Left,
+                    Label::new("Ready").with_id(message_id),
+                )
+                .with_section(
+                    StatusBarSection::Right,
+                    SizedBox::empty()
+                        .width(10.)
+                        .height(10.)
+                        .with_id(indicator_id),
+                ),
+            bar_id,
+        );
+
+        let mut harness = TestHarness::create_with_size(widget, Size::new(400., 300.));
+
+        let bar_rect = harness.get_widget(bar_id).state().layout_rect();
+        assert_eq!(bar_rect.width(), 400.);
+        assert_eq!(bar_rect.height(), DEFAULT_HEIGHT);
+        assert_eq!(bar_rect.y1, 300.);
+
+        let message_rect = harness.get_widget(message_id).state().window_layout_rect();
+        assert_eq!(message_rect.x0, HORIZONTAL_PADDING);
+
+        let indicator_rect = harness
+            .get_widget(indicator_id)
+            .state()
+            .window_layout_rect();
+        assert_eq!(indicator_rect.x1, 400. - HORIZONTAL_PADDING);
+        assert_eq!(indicator_rect.y0, bar_rect.y0 + (DEFAULT_HEIGHT - 10.) / 2.);
+
+        harness.edit_root_widget(|mut root| {
+            let mut flex = root.downcast::<Flex>();
+            let mut bar = flex.child_mut(1).unwrap();
+            let mut bar = bar.downcast::<StatusBar>();
+            bar.remove_section(StatusBarSection::Right);
+            bar.set_height(40.);
+        });
+
+        assert!(harness.try_get_widget(indicator_id).is_none());
+        let bar_rect = harness.get_widget(bar_id).state().layout_rect();
+        assert_eq!(bar_rect.height(), 40.);
+    }
+}
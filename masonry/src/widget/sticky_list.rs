@@ -0,0 +1,410 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! A scrollable column whose section headers stay pinned to its top.
+
+use accesskit::Role;
+use smallvec::SmallVec;
+use tracing::{trace, trace_span, Span};
+use vello::peniko::BlendMode;
+use vello::Scene;
+
+use crate::kurbo::{Affine, Point};
+use crate::widget::{WidgetMut, WidgetPod, WidgetRef};
+use crate::{
+    AccessCtx, AccessEvent, BoxConstraints, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx,
+    PointerEvent, Size, StatusChange, TextEvent, Widget, WidgetId,
+};
+
+/// A column of children which scrolls with the mouse wheel, and in which some children,
+/// usually section headers, are sticky.
+///
+/// Once the list is scrolled past a sticky child, it stays pinned to the top of the
+/// list, above the other children, until the next sticky child reaches it and pushes
+/// it up. The pinned child is really moved there, so it gets the pointer events at the
+/// place it's painted, and the children it covers don't.
+///
+/// Children are laid out with the width of the list, and the height they want.
+pub struct StickyList {
+    children: Vec<WidgetPod<Box<dyn Widget>>>,
+    sticky: Vec<bool>,
+    scroll_offset: f64,
+    /// The index of the sticky child pinned to the top, if the list is scrolled past one.
+    pinned: Option<usize>,
+}
+
+impl StickyList {
+    /// Create an empty list.
+    pub fn new() -> Self {
+        StickyList {
+            children: Vec::new(),
+            sticky: Vec::new(),
+            scroll_offset: 0.0,
+            pinned: None,
+        }
+    }
+
+    /// Builder-style method to add a child.
+    pub fn with_child(mut self, child: impl Widget) -> Self {
+        self.children.push(WidgetPod::new(Box::new(child)));
+        self.sticky.push(false);
+        self
+    }
+
+    /// Builder-style method to add a child with the given id.
+    pub fn with_child_id(mut self, child: impl Widget, id: WidgetId) -> Self {
+        self.children
+            .push(WidgetPod::new_with_id(Box::new(child), id));
+        self.sticky.push(false);
+        self
+    }
+
+    /// Builder-style method to add a sticky child, e.g. the header of a section.
+    pub fn with_sticky_child(mut self, child: impl Widget) -> Self {
+        self.children.push(WidgetPod::new(Box::new(child)));
+        self.sticky.push(true);
+        self
+    }
+
+    /// Builder-style method to add a sticky child with the given id.
+    pub fn with_sticky_child_id(mut self, child: impl Widget, id: WidgetId) -> Self {
+        self.children
+            .push(WidgetPod::new_with_id(Box::new(child), id));
+        self.sticky.push(true);
+        self
+    }
+
+    /// The number of children.
+    pub fn len(&self) -> usize {
+        self.children.len()
+    }
+
+    /// Whether the list has no children.
+    pub fn is_empty(&self) -> bool {
+        self.children.is_empty()
+    }
+
+    /// How far the list is scrolled from the top.
+    pub fn scroll_offset(&self) -> f64 {
+        self.scroll_offset
+    }
+
+    /// The index of the sticky child pinned to the top of the list.
+    ///
+    /// Returns `None` until the list is scrolled past the first sticky child.
+    pub fn pinned_child(&self) -> Option<usize> {
+        self.pinned
+    }
+}
+
+impl Default for StickyList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WidgetMut<'_, StickyList> {
+    /// Add a child at the end of the list.
+    pub fn add_child(&mut self, child: impl Widget) {
+        self.insert_child(self.widget.children.len(), child, false);
+    }
+
+    /// Add a sticky child at the end of the list.
+    pub fn add_sticky_child(&mut self, child: impl Widget) {
+        self.insert_child(self.widget.children.len(), child, true);
+    }
+
+    /// Insert a child at `idx`, which is sticky if `sticky` is `true`.
+    ///
+    /// # Panics
+    ///
+    /// If `idx` is greater than the number of children.
+    pub fn insert_child(&mut self, idx: usize, child: impl Widget, sticky: bool) {
+        self.widget
+            .children
+            .insert(idx, WidgetPod::new(Box::new(child)));
+        self.widget.sticky.insert(idx, sticky);
+        self.ctx.children_changed();
+    }
+
+    /// Remove the child at `idx`.
+    ///
+    /// # Panics
+    ///
+    /// If `idx` is out of bounds.
+    pub fn remove_child(&mut self, idx: usize) {
+        self.widget.children.remove(idx);
+        self.widget.sticky.remove(idx);
+        self.ctx.children_changed();
+    }
+
+    pub fn child_mut(&mut self, idx: usize) -> Option<WidgetMut<'_, Box<dyn Widget>>> {
+        let child = self.widget.children.get_mut(idx)?;
+        Some(self.ctx.get_mut(child))
+    }
+
+    /// Set whether the child at `idx` is sticky.
+    ///
+    /// # Panics
+    ///
+    /// If `idx` is out of bounds.
+    pub fn set_sticky(&mut self, idx: usize, sticky: bool) {
+        self.widget.sticky[idx] = sticky;
+        self.ctx.request_layout();
+    }
+
+    /// Scroll so that the content at `offset` from the top is at the top of the list.
+    pub fn set_scroll_offset(&mut self, offset: f64) {
+        self.widget.scroll_offset = offset;
+        self.ctx.request_layout();
+    }
+}
+
+impl Widget for StickyList {
+    fn on_pointer_event(&mut self, ctx: &mut EventCtx, event: &PointerEvent) {
+        // The pinned child is above the others, which don't get the events it covers,
+        // unless they hold the pointer.
+        let mut covered = false;
+        if let Some(pinned) = self.pinned {
+            let child = &mut self.children[pinned];
+            child.on_pointer_event(ctx, event);
+            covered = child.state.is_hot;
+        }
+        for (index, child) in self.children.iter_mut().enumerate() {
+            if Some(index) == self.pinned {
+                continue;
+            }
+            if !covered || child.state.has_active {
+                child.on_pointer_event(ctx, event);
+            } else {
+                ctx.skip_child(child);
+            }
+        }
+        if ctx.is_handled() {
+            return;
+        }
+        if let PointerEvent::MouseWheel(delta, _) = event {
+            self.scroll_offset += delta.y;
+            ctx.request_layout();
+            ctx.set_handled();
+        }
+    }
+
+    fn on_text_event(&mut self, ctx: &mut EventCtx, event: &TextEvent) {
+        for child in &mut self.children {
+            child.on_text_event(ctx, event);
+        }
+    }
+
+    fn on_access_event(&mut self, ctx: &mut EventCtx, event: &AccessEvent) {
+        for child in &mut self.children {
+            child.on_access_event(ctx, event);
+        }
+    }
+
+    fn on_status_change(&mut self, _ctx: &mut LifeCycleCtx, _event: &StatusChange) {}
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle) {
+        for child in &mut self.children {
+            child.lifecycle(ctx, event);
+        }
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints) -> Size {
+        let child_bc = if bc.is_width_bounded() {
+            BoxConstraints::new(
+                Size::new(bc.max().width, 0.0),
+                Size::new(bc.max().width, f64::INFINITY),
+            )
+        } else {
+            BoxConstraints::UNBOUNDED
+        };
+
+        // The position of each child in the content, and its height.
+        let mut spans = Vec::with_capacity(self.children.len());
+        let mut width: f64 = 0.0;
+        let mut content_height = 0.0;
+        for child in &mut self.children {
+            let size = child.layout(ctx, &child_bc);
+            spans.push((content_height, size.height));
+            width = width.max(size.width);
+            content_height += size.height;
+        }
+
+        let size = bc.constrain(Size::new(width, content_height));
+        let max_scroll = (content_height - size.height).max(0.0);
+        self.scroll_offset = self.scroll_offset.clamp(0.0, max_scroll);
+        let scroll_offset = self.scroll_offset;
+
+        // The last sticky child scrolled past is pinned, and pushed up by the next one.
+        self.pinned = (0..self.children.len())
+            .filter(|&index| self.sticky[index])
+            .take_while(|&index| spans[index].0 <= scroll_offset)
+            .last();
+        let pinned_y = self.pinned.map(|pinned| {
+            let next_top = (pinned + 1..self.children.len())
+                .find(|&index| self.sticky[index])
+                .map(|index| spans[index].0 - scroll_offset);
+            let height = spans[pinned].1;
+            next_top.map_or(0.0, |top| (top - height).min(0.0))
+        });
+
+        for (index, child) in self.children.iter_mut().enumerate() {
+            let y = match pinned_y {
+                Some(pinned_y) if self.pinned == Some(index) => pinned_y,
+                _ => spans[index].0 - scroll_offset,
+            };
+            ctx.place_child(child, Point::new(0.0, y));
+        }
+
+        trace!(
+            "Computed layout: size={}, scroll_offset={}, pinned={:?}",
+            size,
+            self.scroll_offset,
+            self.pinned
+        );
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, scene: &mut Scene) {
+        let clip_rect = ctx.size().to_rect();
+        scene.push_layer(BlendMode::default(), 1., Affine::IDENTITY, &clip_rect);
+        for (index, child) in self.children.iter_mut().enumerate() {
+            if Some(index) != self.pinned {
+                child.paint(ctx, scene);
+            }
+        }
+        if let Some(pinned) = self.pinned {
+            self.children[pinned].paint(ctx, scene);
+        }
+        scene.pop_layer();
+    }
+
+    fn accessibility_role(&self) -> Role {
+        Role::List
+    }
+
+    fn accessibility(&mut self, ctx: &mut AccessCtx) {
+        ctx.current_node().set_clips_children();
+        for child in &mut self.children {
+            child.accessibility(ctx);
+        }
+    }
+
+    fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
+        self.children.iter().map(|child| child.as_dyn()).collect()
+    }
+
+    fn make_trace_span(&self) -> Span {
+        trace_span!("StickyList")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use winit::event::MouseButton;
+
+    use super::*;
+    use crate::testing::{widget_ids, TestHarness};
+    use crate::widget::{Button, SizedBox};
+    use crate::{Action, Vec2};
+
+    const HEADER_HEIGHT: f64 = 30.;
+    const ROW_HEIGHT: f64 = 20.;
+
+    /// Three sections of ten rows, each 230 pixels tall, in a list 300 pixels tall.
+    fn sections(header_ids: [WidgetId; 3]) -> StickyList {
+        let mut list = StickyList::new();
+        for (section, id) in header_ids.into_iter().enumerate() {
+            let header = Button::new(format!("Section {section}"));
+            list = list.with_sticky_child_id(SizedBox::new(header).height(HEADER_HEIGHT), id);
+            for row in 0..10 {
+                let row = Button::new(format!("Row {section}.{row}"));
+                list = list.with_child(SizedBox::new(row).height(ROW_HEIGHT));
+            }
+        }
+        list
+    }
+
+    fn scroll_to(harness: &mut TestHarness, offset: f64) {
+        harness.edit_root_widget(|mut list| {
+            list.downcast::<StickyList>().set_scroll_offset(offset);
+        });
+    }
+
+    fn pinned_header(harness: &TestHarness) -> Option<usize> {
+        let list = harness.root_widget();
+        let pinned = list.downcast::<StickyList>().unwrap().pinned_child()?;
+        Some(pinned / 11)
+    }
+
+    #[test]
+    fn header_is_pinned_until_pushed_out() {
+        let header_ids = widget_ids();
+        let mut harness =
+            TestHarness::create_with_size(sections(header_ids), Size::new(200., 300.));
+        let header_y = |harness: &TestHarness, section: usize| {
+            harness
+                .get_widget(header_ids[section])
+                .state()
+                .layout_rect()
+                .y0
+        };
+        assert_eq!(pinned_header(&harness), Some(0));
+        assert_eq!(header_y(&harness, 0), 0.);
+
+        // The first header stays at the top while its rows scroll under it.
+        scroll_to(&mut harness, 100.);
+        assert_eq!(pinned_header(&harness), Some(0));
+        assert_eq!(header_y(&harness, 0), 0.);
+        assert_eq!(header_y(&harness, 1), 130.);
+
+        // The second header reaches the first one, and pushes it up.
+        scroll_to(&mut harness, 215.);
+        assert_eq!(pinned_header(&harness), Some(0));
+        assert_eq!(header_y(&harness, 0), -15.);
+        assert_eq!(header_y(&harness, 1), 15.);
+
+        // Then takes its place.
+        scroll_to(&mut harness, 230.);
+        assert_eq!(pinned_header(&harness), Some(1));
+        assert_eq!(header_y(&harness, 1), 0.);
+
+        harness.mouse_move((100., 150.));
+        harness.mouse_wheel(Vec2::new(0., 70.));
+        assert_eq!(pinned_header(&harness), Some(1));
+        assert_eq!(header_y(&harness, 0), -300.);
+        assert_eq!(header_y(&harness, 1), 0.);
+
+        // The list can't scroll past its end, where the last header is in view.
+        harness.mouse_wheel(Vec2::new(0., 1000.));
+        assert_eq!(pinned_header(&harness), Some(1));
+        assert_eq!(header_y(&harness, 1), 0.);
+        assert_eq!(header_y(&harness, 2), 70.);
+    }
+
+    #[test]
+    fn clicks_on_the_pinned_header_reach_it() {
+        let header_ids = widget_ids();
+        let mut harness =
+            TestHarness::create_with_size(sections(header_ids), Size::new(200., 300.));
+        // The second header covers the rows 2 and 3 of its section.
+        scroll_to(&mut harness, 300.);
+        assert_eq!(pinned_header(&harness), Some(1));
+
+        harness.mouse_click_on(header_ids[1]);
+        let header_button = harness.get_widget(header_ids[1]).children()[0].id();
+        assert_eq!(
+            harness.pop_action(),
+            Some((Action::ButtonPressed, header_button))
+        );
+        assert_eq!(harness.pop_action(), None);
+
+        // The rows below the header still get clicks.
+        harness.mouse_move((100., 50.));
+        harness.mouse_button_press(MouseButton::Left);
+        harness.mouse_button_release(MouseButton::Left);
+        let row_4 = harness.root_widget().children()[11 + 1 + 4].children()[0].id();
+        assert_eq!(harness.pop_action(), Some((Action::ButtonPressed, row_4)));
+    }
+}
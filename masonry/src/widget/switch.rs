@@ -0,0 +1,285 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! A switch widget.
+
+use accesskit::{DefaultActionVerb, Role, Toggled};
+use kurbo::{Circle, Point};
+use smallvec::SmallVec;
+use tracing::{trace, trace_span, Span};
+use vello::Scene;
+
+use crate::action::Action;
+use crate::kurbo::Size;
+use crate::paint_scene_helpers::{fill_color, stroke};
+use crate::widget::keyboard_activation::KeyboardActivation;
+use crate::widget::{WidgetMut, WidgetRef};
+use crate::{
+    theme, AccessCtx, AccessEvent, BoxConstraints, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx,
+    PaintCtx, PointerEvent, StatusChange, TextEvent, Widget,
+};
+
+const SWITCH_WIDTH: f64 = 36.0;
+const SWITCH_HEIGHT: f64 = theme::BASIC_WIDGET_HEIGHT;
+const THUMB_PADDING: f64 = 2.0;
+/// How long the thumb takes to slide from one end to the other, in seconds.
+const SLIDE_DURATION: f64 = 0.15;
+
+/// A pill-shaped toggle with a sliding thumb.
+///
+/// Unlike a [`Checkbox`](super::Checkbox), a switch is either on or off, and toggling
+/// it is expected to take effect immediately.
+///
+/// When focused, the switch can be toggled with Enter, or by holding and releasing Space.
+/// Toggling it submits [`Action::SwitchToggled`].
+pub struct Switch {
+    on: bool,
+    /// Where the thumb is drawn, from 0 (off) to 1 (on).
+    thumb_position: f64,
+    keyboard: KeyboardActivation,
+}
+
+impl Switch {
+    /// Create a new `Switch`.
+    pub fn new(on: bool) -> Switch {
+        Switch {
+            on,
+            thumb_position: if on { 1.0 } else { 0.0 },
+            keyboard: KeyboardActivation::default(),
+        }
+    }
+
+    /// Whether the switch is on.
+    pub fn is_on(&self) -> bool {
+        self.on
+    }
+
+    fn toggle(&mut self, ctx: &mut EventCtx) {
+        self.on = !self.on;
+        ctx.submit_action(Action::SwitchToggled(self.on));
+        ctx.request_anim_frame();
+        ctx.request_paint();
+        ctx.request_accessibility_update();
+    }
+
+    fn thumb_target(&self) -> f64 {
+        if self.on {
+            1.0
+        } else {
+            0.0
+        }
+    }
+}
+
+impl WidgetMut<'_, Switch> {
+    /// Turn the switch on or off, sliding the thumb to its new position.
+    pub fn set_on(&mut self, on: bool) {
+        if self.widget.on == on {
+            return;
+        }
+        self.widget.on = on;
+        self.ctx.request_anim_frame();
+        self.ctx.request_paint();
+        self.ctx.request_accessibility_update();
+    }
+}
+
+impl Widget for Switch {
+    fn on_pointer_event(&mut self, ctx: &mut EventCtx, event: &PointerEvent) {
+        match event {
+            PointerEvent::PointerDown(_, _) => {
+                if !ctx.is_disabled() {
+                    ctx.set_active(true);
+                    ctx.request_paint();
+                    trace!("Switch {:?} pressed", ctx.widget_id());
+                }
+            }
+            PointerEvent::PointerUp(_, _) => {
+                if ctx.is_active() && !ctx.is_disabled() {
+                    if ctx.is_hot() {
+                        self.toggle(ctx);
+                        trace!("Switch {:?} released", ctx.widget_id());
+                    }
+                    ctx.request_paint();
+                }
+                ctx.set_active(false);
+            }
+            _ => (),
+        }
+    }
+
+    fn on_text_event(&mut self, ctx: &mut EventCtx, event: &TextEvent) {
+        if self.keyboard.on_text_event(ctx, event) {
+            self.toggle(ctx);
+            trace!("Switch {:?} activated with keyboard", ctx.widget_id());
+        }
+    }
+
+    fn on_access_event(&mut self, ctx: &mut EventCtx, event: &AccessEvent) {
+        if event.target == ctx.widget_id() {
+            if let accesskit::Action::Default = event.action {
+                self.toggle(ctx);
+            }
+        }
+    }
+
+    fn on_status_change(&mut self, ctx: &mut LifeCycleCtx, event: &StatusChange) {
+        if let StatusChange::FocusChanged(false) = event {
+            self.keyboard.cancel();
+        }
+        ctx.request_paint();
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle) {
+        match event {
+            LifeCycle::BuildFocusChain => ctx.register_for_focus(),
+            LifeCycle::AnimFrame(interval) => {
+                let target = self.thumb_target();
+                if ctx.reduced_motion() {
+                    self.thumb_position = target;
+                } else {
+                    let step = (*interval as f64) * 1e-9 / SLIDE_DURATION;
+                    self.thumb_position = if target > self.thumb_position {
+                        (self.thumb_position + step).min(target)
+                    } else {
+                        (self.thumb_position - step).max(target)
+                    };
+                }
+                if self.thumb_position != target {
+                    ctx.request_anim_frame();
+                }
+                ctx.request_paint();
+            }
+            _ => (),
+        }
+    }
+
+    fn layout(&mut self, _ctx: &mut LayoutCtx, bc: &BoxConstraints) -> Size {
+        let size = bc.constrain(Size::new(SWITCH_WIDTH, SWITCH_HEIGHT));
+        trace!("Computed size: {}", size);
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, scene: &mut Scene) {
+        let size = ctx.size();
+        let border_width = 1.;
+        let track = size
+            .to_rect()
+            .inset(-border_width / 2.)
+            .to_rounded_rect(size.height / 2.);
+
+        let track_color = if ctx.is_disabled() {
+            theme::DISABLED_BUTTON_DARK
+        } else if self.thumb_position > 0.5 {
+            theme::PRIMARY_DARK
+        } else {
+            theme::BACKGROUND_DARK
+        };
+        fill_color(scene, &track, track_color);
+
+        let border_color = if ctx.is_hot() && !ctx.is_disabled() {
+            theme::BORDER_LIGHT
+        } else {
+            theme::BORDER_DARK
+        };
+        stroke(scene, &track, border_color, border_width);
+
+        let is_pressed = (ctx.is_active() || self.keyboard.is_pressed()) && !ctx.is_disabled();
+        let radius = size.height / 2. - THUMB_PADDING;
+        let start = size.height / 2.;
+        let end = size.width - size.height / 2.;
+        let center = Point::new(
+            start + (end - start) * self.thumb_position,
+            size.height / 2.,
+        );
+        let thumb_color = if ctx.is_disabled() {
+            theme::DISABLED_FOREGROUND_DARK
+        } else if is_pressed {
+            theme::FOREGROUND_DARK
+        } else {
+            theme::FOREGROUND_LIGHT
+        };
+        fill_color(scene, &Circle::new(center, radius), thumb_color);
+    }
+
+    fn accessibility_role(&self) -> Role {
+        Role::Switch
+    }
+
+    fn accessibility(&mut self, ctx: &mut AccessCtx) {
+        if self.on {
+            ctx.current_node().set_toggled(Toggled::True);
+            ctx.current_node()
+                .set_default_action_verb(DefaultActionVerb::Uncheck);
+        } else {
+            ctx.current_node().set_toggled(Toggled::False);
+            ctx.current_node()
+                .set_default_action_verb(DefaultActionVerb::Check);
+        }
+    }
+
+    fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
+        SmallVec::new()
+    }
+
+    fn make_trace_span(&self) -> Span {
+        trace_span!("Switch")
+    }
+
+    fn get_debug_text(&self) -> Option<String> {
+        Some(if self.on { "on" } else { "off" }.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use insta::assert_debug_snapshot;
+
+    use super::*;
+    use crate::event::WindowEvent;
+    use crate::testing::{widget_ids, TestHarness, TestWidgetExt};
+
+    #[test]
+    fn simple_switch() {
+        let [switch_id] = widget_ids();
+        let widget = Switch::new(false).with_id(switch_id);
+
+        let mut harness = TestHarness::create(widget);
+
+        assert_debug_snapshot!(harness.root_widget());
+        assert_eq!(harness.pop_action(), None);
+
+        harness.mouse_click_on(switch_id);
+        assert_eq!(
+            harness.pop_action(),
+            Some((Action::SwitchToggled(true), switch_id))
+        );
+
+        assert_debug_snapshot!(harness.root_widget());
+
+        harness.mouse_click_on(switch_id);
+        assert_eq!(
+            harness.pop_action(),
+            Some((Action::SwitchToggled(false), switch_id))
+        );
+    }
+
+    #[test]
+    fn reduced_motion_jumps_to_end() {
+        let [switch_id] = widget_ids();
+        let widget = Switch::new(false).with_id(switch_id);
+
+        let mut harness = TestHarness::create(widget);
+        harness.set_reduced_motion(true);
+        harness.mouse_click_on(switch_id);
+
+        let thumb_position = |harness: &TestHarness| {
+            let switch = harness.get_widget(switch_id);
+            switch.downcast::<Switch>().unwrap().thumb_position
+        };
+        assert_eq!(thumb_position(&harness), 0.0);
+
+        harness.process_window_event(WindowEvent::AnimFrame);
+        assert_eq!(thumb_position(&harness), 1.0);
+    }
+}
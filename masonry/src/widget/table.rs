@@ -0,0 +1,1133 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! A grid of cells under a header row, with sortable and resizable columns.
+
+use std::ops::Range;
+
+use accesskit::Role;
+use smallvec::{smallvec, SmallVec};
+use tracing::{trace, trace_span, Span};
+use vello::peniko::BlendMode;
+use vello::Scene;
+use winit::event::MouseButton;
+use winit::keyboard::{Key, NamedKey};
+
+use crate::kurbo::{Affine, BezPath, Line, Rect};
+use crate::paint_scene_helpers::{fill_color, stroke};
+use crate::widget::{Label, WidgetMut, WidgetPod, WidgetRef};
+use crate::{
+    theme, AccessCtx, AccessEvent, Action, ArcStr, BoxConstraints, CursorIcon, EventCtx, LayoutCtx,
+    LifeCycle, LifeCycleCtx, PaintCtx, Point, PointerEvent, Size, StatusChange, TextEvent, Widget,
+};
+
+const ROW_HEIGHT: f64 = theme::BORDERED_WIDGET_HEIGHT;
+const HEADER_HEIGHT: f64 = theme::BORDERED_WIDGET_HEIGHT;
+const CELL_PADDING: f64 = theme::WIDGET_CONTROL_COMPONENT_PADDING;
+const DEFAULT_MIN_WIDTH: f64 = 24.0;
+/// How far from the edge of a column, in the header, the pointer can grab it to resize it.
+const DIVIDER_HIT_WIDTH: f64 = 4.0;
+const ARROW_HALF_SIZE: f64 = 4.0;
+/// The room kept for the sort arrow at the end of the header of sortable columns.
+const ARROW_SPACE: f64 = 2.0 * ARROW_HALF_SIZE + CELL_PADDING;
+
+/// How wide a column of a [`Table`] is.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ColumnWidth {
+    /// A width in logical pixels.
+    Fixed(f64),
+    /// A share of the width left by the fixed columns, in proportion to the factors
+    /// of the other flex columns.
+    Flex(f64),
+}
+
+/// The order a [`Table`] is sorted in, as indicated in the header of the sorted column.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortDirection {
+    Ascending,
+    Descending,
+}
+
+impl SortDirection {
+    /// The other direction.
+    pub fn reversed(self) -> Self {
+        match self {
+            SortDirection::Ascending => SortDirection::Descending,
+            SortDirection::Descending => SortDirection::Ascending,
+        }
+    }
+}
+
+/// The description of a column of a [`Table`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColumnSpec {
+    title: ArcStr,
+    width: ColumnWidth,
+    min_width: f64,
+    sortable: bool,
+}
+
+impl ColumnSpec {
+    /// Create a column with the given title, as wide as other wide widgets.
+    pub fn new(title: impl Into<ArcStr>) -> Self {
+        ColumnSpec {
+            title: title.into(),
+            width: ColumnWidth::Fixed(theme::WIDE_WIDGET_WIDTH),
+            min_width: DEFAULT_MIN_WIDTH,
+            sortable: false,
+        }
+    }
+
+    /// Builder-style method to set the initial width of the column.
+    pub fn width(mut self, width: f64) -> Self {
+        self.width = ColumnWidth::Fixed(width);
+        self
+    }
+
+    /// Builder-style method to make the column share the width left by the fixed columns.
+    pub fn flex(mut self, factor: f64) -> Self {
+        self.width = ColumnWidth::Flex(factor);
+        self
+    }
+
+    /// Builder-style method to set the width the column can't be resized below.
+    pub fn min_width(mut self, min_width: f64) -> Self {
+        self.min_width = min_width;
+        self
+    }
+
+    /// Builder-style method to make the header of the column clickable to sort the table.
+    pub fn sortable(mut self, sortable: bool) -> Self {
+        self.sortable = sortable;
+        self
+    }
+
+    /// The title of the column.
+    pub fn title(&self) -> &ArcStr {
+        &self.title
+    }
+}
+
+struct Column {
+    spec: ColumnSpec,
+    /// The width the column was resized to by the user, if it was.
+    resized: Option<f64>,
+}
+
+impl Column {
+    fn fixed_width(&self) -> Option<f64> {
+        let width = match (self.resized, self.spec.width) {
+            (Some(width), _) | (None, ColumnWidth::Fixed(width)) => width,
+            (None, ColumnWidth::Flex(_)) => return None,
+        };
+        Some(width.max(self.spec.min_width))
+    }
+}
+
+/// A column being resized by dragging the edge of its header.
+struct ColumnResize {
+    column: usize,
+    start_x: f64,
+    start_width: f64,
+}
+
+/// A grid of widgets under a header row with the title of each column.
+///
+/// Clicking the header of a [sortable] column submits [`Action::TableSortRequested`]
+/// with the direction the table should now be sorted in, and shows that direction in
+/// the header. The table doesn't reorder its rows itself: the app is expected to sort
+/// its data and to update the rows.
+///
+/// Columns are resized by dragging the right edge of their header. A resized column
+/// keeps its width when the columns are [set](WidgetMut::set_columns) again, as long
+/// as its description didn't change.
+///
+/// All rows have the same height. Only the rows in view are laid out and painted,
+/// the others are stashed, so tables with many rows are cheap to lay out. The header
+/// stays at the top, and the rows scroll below it with the mouse wheel, and to keep
+/// the current cell in view.
+///
+/// The current cell is moved with a click, the arrow keys, Home, End, Page Up and
+/// Page Down.
+///
+/// [sortable]: ColumnSpec::sortable
+pub struct Table {
+    columns: Vec<Column>,
+    header: WidgetPod<TableRow>,
+    rows: Vec<WidgetPod<TableRow>>,
+    /// The start and the width of each column, from the last layout.
+    spans: Vec<(f64, f64)>,
+    sort: Option<(usize, SortDirection)>,
+    scroll_offset: f64,
+    /// The rows which aren't stashed.
+    shown: Range<usize>,
+    /// The cell moved by the keyboard, which is the last clicked one, as a row and a column.
+    cursor: Option<(usize, usize)>,
+    resize: Option<ColumnResize>,
+    is_divider_hover: bool,
+}
+
+/// A row of a [`Table`], laying out its cells in the columns of the table.
+struct TableRow {
+    cells: Vec<WidgetPod<TableCell>>,
+    /// The start and the width of each column, set by the table before the layout.
+    spans: Vec<(f64, f64)>,
+}
+
+/// A cell of a [`Table`], or the header of one of its columns.
+struct TableCell {
+    content: WidgetPod<Box<dyn Widget>>,
+    is_header: bool,
+    /// Whether room is kept for a sort arrow, painted when `sort` is set.
+    sortable: bool,
+    sort: Option<SortDirection>,
+}
+
+// --- MARK: BUILDERS ---
+impl Table {
+    /// Create a table with the given columns, and no rows.
+    pub fn new(columns: impl IntoIterator<Item = ColumnSpec>) -> Self {
+        let columns: Vec<Column> = columns
+            .into_iter()
+            .map(|spec| Column {
+                spec,
+                resized: None,
+            })
+            .collect();
+        let header = Self::header_row(&columns, None);
+        Table {
+            columns,
+            header,
+            rows: Vec::new(),
+            spans: Vec::new(),
+            sort: None,
+            scroll_offset: 0.0,
+            shown: 0..0,
+            cursor: None,
+            resize: None,
+            is_divider_hover: false,
+        }
+    }
+
+    /// Builder-style method to add a row with one widget per column.
+    pub fn with_row<W: Widget>(self, cells: impl IntoIterator<Item = W>) -> Self {
+        self.with_row_pods(cells.into_iter().map(|cell| WidgetPod::new(cell).boxed()))
+    }
+
+    /// Builder-style method to add a row with one widget per column, already in pods.
+    pub fn with_row_pods(
+        mut self,
+        cells: impl IntoIterator<Item = WidgetPod<Box<dyn Widget>>>,
+    ) -> Self {
+        self.rows.push(TableRow::new_pod(cells));
+        self.shown = 0..self.rows.len();
+        self
+    }
+
+    /// Builder-style method to show the table as sorted by `column`.
+    ///
+    /// This doesn't reorder the rows.
+    pub fn with_sort(mut self, column: usize, direction: SortDirection) -> Self {
+        self.sort = Some((column, direction));
+        self.header = Self::header_row(&self.columns, self.sort);
+        self
+    }
+}
+
+// --- MARK: METHODS ---
+impl Table {
+    /// The column the table is shown as sorted by, and in which direction.
+    pub fn sort(&self) -> Option<(usize, SortDirection)> {
+        self.sort
+    }
+
+    /// The number of rows.
+    pub fn row_count(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// The width of `column` in the last layout.
+    pub fn column_width(&self, column: usize) -> Option<f64> {
+        self.spans.get(column).map(|&(_, width)| width)
+    }
+
+    fn header_row(columns: &[Column], sort: Option<(usize, SortDirection)>) -> WidgetPod<TableRow> {
+        let cells = columns.iter().enumerate().map(|(index, column)| {
+            let title = WidgetPod::new(Label::new(column.spec.title.clone())).boxed();
+            WidgetPod::new(TableCell {
+                content: title,
+                is_header: true,
+                sortable: column.spec.sortable,
+                sort: sort
+                    .filter(|&(sorted, _)| sorted == index)
+                    .map(|(_, direction)| direction),
+            })
+        });
+        WidgetPod::new(TableRow {
+            cells: cells.collect(),
+            spans: Vec::new(),
+        })
+    }
+
+    /// The start and the width of each column, in a table of the given width.
+    fn column_spans(&self, width: f64) -> Vec<(f64, f64)> {
+        let fixed_width: f64 = self.columns.iter().filter_map(Column::fixed_width).sum();
+        let total_flex: f64 = self
+            .columns
+            .iter()
+            .filter(|column| column.fixed_width().is_none())
+            .map(|column| match column.spec.width {
+                ColumnWidth::Flex(factor) => factor,
+                ColumnWidth::Fixed(_) => 0.,
+            })
+            .sum();
+        let remaining = (width - fixed_width).max(0.);
+
+        let mut x = 0.;
+        let mut spans = Vec::with_capacity(self.columns.len());
+        for column in &self.columns {
+            let width = match (column.fixed_width(), column.spec.width) {
+                (Some(width), _) => width,
+                (None, ColumnWidth::Flex(factor)) if total_flex > 0. => {
+                    (remaining * factor / total_flex).max(column.spec.min_width)
+                }
+                (None, _) => column.spec.min_width,
+            };
+            spans.push((x, width));
+            x += width;
+        }
+        spans
+    }
+
+    fn column_at(&self, x: f64) -> Option<usize> {
+        self.spans
+            .iter()
+            .position(|&(start, width)| (start..start + width).contains(&x))
+    }
+
+    /// The column whose right edge is at `x`, within grabbing distance.
+    fn divider_at(&self, x: f64) -> Option<usize> {
+        self.spans
+            .iter()
+            .position(|&(start, width)| (start + width - x).abs() <= DIVIDER_HIT_WIDTH)
+    }
+
+    fn body_height(size: Size) -> f64 {
+        (size.height - HEADER_HEIGHT).max(0.)
+    }
+
+    /// Sort by `column`, in the other direction if it already is the sorted column.
+    fn toggle_sort(&mut self, ctx: &mut EventCtx, column: usize) {
+        let direction = match self.sort {
+            Some((sorted, direction)) if sorted == column => direction.reversed(),
+            _ => SortDirection::Ascending,
+        };
+        self.sort = Some((column, direction));
+        ctx.get_mut(&mut self.header).set_sort(self.sort);
+        trace!(
+            "Table {:?} sorted by column {} {:?}",
+            ctx.widget_id(),
+            column,
+            direction
+        );
+        ctx.submit_action(Action::TableSortRequested(column, direction));
+    }
+
+    /// Handle a key press, returning whether it moved the cursor.
+    fn on_key(&mut self, key: &Key, page_rows: usize) -> bool {
+        let (row_count, column_count) = (self.rows.len(), self.columns.len());
+        if row_count == 0 || column_count == 0 {
+            return false;
+        }
+        let Some((row, column)) = self
+            .cursor
+            .filter(|&(row, column)| row < row_count && column < column_count)
+        else {
+            return match key {
+                Key::Named(
+                    NamedKey::ArrowUp
+                    | NamedKey::ArrowDown
+                    | NamedKey::ArrowLeft
+                    | NamedKey::ArrowRight
+                    | NamedKey::Home
+                    | NamedKey::End
+                    | NamedKey::PageUp
+                    | NamedKey::PageDown,
+                ) => {
+                    self.cursor = Some((0, 0));
+                    true
+                }
+                _ => false,
+            };
+        };
+        let target = match key {
+            Key::Named(NamedKey::ArrowUp) => row.checked_sub(1).map(|row| (row, column)),
+            Key::Named(NamedKey::ArrowDown) => (row + 1 < row_count).then_some((row + 1, column)),
+            Key::Named(NamedKey::ArrowLeft) => column.checked_sub(1).map(|column| (row, column)),
+            Key::Named(NamedKey::ArrowRight) => {
+                (column + 1 < column_count).then_some((row, column + 1))
+            }
+            Key::Named(NamedKey::Home) => Some((row, 0)),
+            Key::Named(NamedKey::End) => Some((row, column_count - 1)),
+            Key::Named(NamedKey::PageUp) => Some((row.saturating_sub(page_rows), column)),
+            Key::Named(NamedKey::PageDown) => Some(((row + page_rows).min(row_count - 1), column)),
+            _ => None,
+        };
+        match target {
+            Some(target) if target != (row, column) => {
+                self.cursor = Some(target);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Scroll so that the row of the cursor is in view.
+    fn scroll_to_cursor(&mut self, body_height: f64) {
+        let Some((row, _)) = self.cursor else {
+            return;
+        };
+        let top = row as f64 * ROW_HEIGHT;
+        if top < self.scroll_offset {
+            self.scroll_offset = top;
+        } else if top + ROW_HEIGHT > self.scroll_offset + body_height {
+            self.scroll_offset = top + ROW_HEIGHT - body_height;
+        }
+    }
+}
+
+impl TableRow {
+    fn new_pod(cells: impl IntoIterator<Item = WidgetPod<Box<dyn Widget>>>) -> WidgetPod<Self> {
+        let cells = cells.into_iter().map(|content| {
+            WidgetPod::new(TableCell {
+                content,
+                is_header: false,
+                sortable: false,
+                sort: None,
+            })
+        });
+        WidgetPod::new(TableRow {
+            cells: cells.collect(),
+            spans: Vec::new(),
+        })
+    }
+}
+
+// --- MARK: WIDGETMUT ---
+impl WidgetMut<'_, Table> {
+    /// Set the columns of the table.
+    ///
+    /// The columns which are described as before keep the width they were resized to.
+    /// The rows aren't changed, and should have one cell per column.
+    pub fn set_columns(&mut self, columns: impl IntoIterator<Item = ColumnSpec>) {
+        let prev_columns = std::mem::take(&mut self.widget.columns);
+        self.widget.columns = columns
+            .into_iter()
+            .enumerate()
+            .map(|(index, spec)| {
+                let resized = prev_columns
+                    .get(index)
+                    .filter(|prev| prev.spec == spec)
+                    .and_then(|prev| prev.resized);
+                Column { spec, resized }
+            })
+            .collect();
+        let column_count = self.widget.columns.len();
+        self.widget.sort = self
+            .widget
+            .sort
+            .filter(|&(column, _)| column < column_count);
+        self.widget.header = Table::header_row(&self.widget.columns, self.widget.sort);
+        self.ctx.children_changed();
+    }
+
+    /// Show the table as sorted by the given column, in the given direction, or as not sorted.
+    ///
+    /// This doesn't reorder the rows, nor submit [`Action::TableSortRequested`].
+    pub fn set_sort(&mut self, sort: Option<(usize, SortDirection)>) {
+        if self.widget.sort == sort {
+            return;
+        }
+        self.widget.sort = sort;
+        self.ctx.get_mut(&mut self.widget.header).set_sort(sort);
+    }
+
+    /// Add a row at the end of the table, with one widget per column.
+    pub fn add_row_pods(&mut self, cells: impl IntoIterator<Item = WidgetPod<Box<dyn Widget>>>) {
+        let index = self.widget.rows.len();
+        self.insert_row_pods(index, cells);
+    }
+
+    /// Insert a row at `index`, with one widget per column.
+    ///
+    /// # Panics
+    ///
+    /// If `index` is greater than the number of rows.
+    pub fn insert_row_pods(
+        &mut self,
+        index: usize,
+        cells: impl IntoIterator<Item = WidgetPod<Box<dyn Widget>>>,
+    ) {
+        self.widget.rows.insert(index, TableRow::new_pod(cells));
+        // Rows are unstashed when added, the next layout stashes the ones out of view.
+        self.widget.shown = 0..self.widget.rows.len();
+        self.ctx.children_changed();
+    }
+
+    /// Remove the row at `index`.
+    ///
+    /// # Panics
+    ///
+    /// If there is no row at `index`.
+    pub fn remove_row(&mut self, index: usize) {
+        self.widget.rows.remove(index);
+        self.widget.shown = 0..self.widget.rows.len();
+        self.ctx.children_changed();
+    }
+
+    /// Edit the widget in the cell at `row` and `column`.
+    ///
+    /// # Panics
+    ///
+    /// If there is no such cell.
+    pub fn edit_cell<R>(
+        &mut self,
+        row: usize,
+        column: usize,
+        f: impl FnOnce(WidgetMut<'_, Box<dyn Widget>>) -> R,
+    ) -> R {
+        let mut row = self.ctx.get_mut(&mut self.widget.rows[row]);
+        let mut cell = row.ctx.get_mut(&mut row.widget.cells[column]);
+        let content = cell.ctx.get_mut(&mut cell.widget.content);
+        f(content)
+    }
+}
+
+impl WidgetMut<'_, TableRow> {
+    fn set_sort(&mut self, sort: Option<(usize, SortDirection)>) {
+        for (index, cell) in self.widget.cells.iter_mut().enumerate() {
+            let direction = sort
+                .filter(|&(sorted, _)| sorted == index)
+                .map(|(_, direction)| direction);
+            self.ctx.get_mut(cell).set_sort(direction);
+        }
+    }
+}
+
+impl WidgetMut<'_, TableCell> {
+    fn set_sort(&mut self, sort: Option<SortDirection>) {
+        if self.widget.sort != sort {
+            self.widget.sort = sort;
+            self.ctx.request_paint();
+            self.ctx.request_accessibility_update();
+        }
+    }
+}
+
+// --- MARK: IMPL WIDGET ---
+impl Widget for Table {
+    fn on_pointer_event(&mut self, ctx: &mut EventCtx, event: &PointerEvent) {
+        self.header.on_pointer_event(ctx, event);
+        for row in &mut self.rows {
+            row.on_pointer_event(ctx, event);
+        }
+        if ctx.is_disabled() {
+            return;
+        }
+
+        match event {
+            PointerEvent::PointerDown(MouseButton::Left, _) => {
+                let pos = event.local_position(ctx);
+                if pos.y < HEADER_HEIGHT {
+                    if let Some(column) = self.divider_at(pos.x) {
+                        ctx.set_handled();
+                        ctx.set_active(true);
+                        self.resize = Some(ColumnResize {
+                            column,
+                            start_x: pos.x,
+                            start_width: self.spans[column].1,
+                        });
+                        if !self.is_divider_hover {
+                            self.is_divider_hover = true;
+                            ctx.set_cursor(&CursorIcon::EwResize);
+                        }
+                    } else if let Some(column) = self.column_at(pos.x) {
+                        if self.columns[column].spec.sortable {
+                            ctx.set_handled();
+                            self.toggle_sort(ctx, column);
+                        }
+                    }
+                    return;
+                }
+                let row = ((pos.y - HEADER_HEIGHT + self.scroll_offset) / ROW_HEIGHT).floor();
+                if row < 0. || row as usize >= self.rows.len() {
+                    return;
+                }
+                if let Some(column) = self.column_at(pos.x) {
+                    self.cursor = Some((row as usize, column));
+                    ctx.request_focus();
+                    ctx.request_paint();
+                    ctx.request_accessibility_update();
+                }
+            }
+            PointerEvent::PointerMove(_) => {
+                let pos = event.local_position(ctx);
+                if let Some(resize) = &self.resize {
+                    let column = &mut self.columns[resize.column];
+                    let width =
+                        (resize.start_width + pos.x - resize.start_x).max(column.spec.min_width);
+                    column.resized = Some(width);
+                    ctx.request_layout();
+                } else {
+                    let hover =
+                        ctx.is_hot() && pos.y < HEADER_HEIGHT && self.divider_at(pos.x).is_some();
+                    if self.is_divider_hover != hover {
+                        self.is_divider_hover = hover;
+                        if hover {
+                            ctx.set_cursor(&CursorIcon::EwResize);
+                        } else {
+                            ctx.clear_cursor();
+                        }
+                    }
+                }
+            }
+            PointerEvent::PointerUp(MouseButton::Left, _) => {
+                if self.resize.take().is_some() {
+                    ctx.set_handled();
+                    ctx.set_active(false);
+                    let pos = event.local_position(ctx);
+                    self.is_divider_hover =
+                        ctx.is_hot() && pos.y < HEADER_HEIGHT && self.divider_at(pos.x).is_some();
+                    if !self.is_divider_hover {
+                        ctx.clear_cursor();
+                    }
+                }
+            }
+            PointerEvent::MouseWheel(delta, _) => {
+                self.scroll_offset += delta.y;
+                ctx.request_layout();
+                ctx.set_handled();
+            }
+            _ => {}
+        }
+    }
+
+    fn on_text_event(&mut self, ctx: &mut EventCtx, event: &TextEvent) {
+        self.header.on_text_event(ctx, event);
+        for row in &mut self.rows {
+            row.on_text_event(ctx, event);
+        }
+        let TextEvent::KeyboardKey(key_event, _) = event else {
+            return;
+        };
+        if !ctx.is_focused() || ctx.is_disabled() || !key_event.state.is_pressed() {
+            return;
+        }
+        let body_height = Self::body_height(ctx.size());
+        let page_rows = ((body_height / ROW_HEIGHT).floor() as usize).max(1);
+        if self.on_key(&key_event.logical_key, page_rows) {
+            ctx.set_handled();
+            self.scroll_to_cursor(body_height);
+            ctx.request_layout();
+            ctx.request_paint();
+            ctx.request_accessibility_update();
+        }
+    }
+
+    fn on_access_event(&mut self, ctx: &mut EventCtx, event: &AccessEvent) {
+        self.header.on_access_event(ctx, event);
+        for row in &mut self.rows {
+            row.on_access_event(ctx, event);
+        }
+        if event.action != accesskit::Action::Default {
+            return;
+        }
+        let column = self
+            .header
+            .widget()
+            .cells
+            .iter()
+            .position(|cell| cell.id() == event.target);
+        if let Some(column) = column.filter(|&column| self.columns[column].spec.sortable) {
+            self.toggle_sort(ctx, column);
+        }
+    }
+
+    fn on_status_change(&mut self, ctx: &mut LifeCycleCtx, event: &StatusChange) {
+        if let StatusChange::FocusChanged(_) = event {
+            ctx.request_paint();
+            ctx.request_accessibility_update();
+        }
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle) {
+        self.header.lifecycle(ctx, event);
+        for row in &mut self.rows {
+            row.lifecycle(ctx, event);
+        }
+        if let LifeCycle::BuildFocusChain = event {
+            ctx.register_for_focus();
+        }
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints) -> Size {
+        let natural_width = self
+            .column_spans(0.)
+            .last()
+            .map_or(0., |&(start, width)| start + width);
+        let width = if bc.is_width_bounded() {
+            bc.max().width
+        } else {
+            natural_width
+        };
+        self.spans = self.column_spans(width);
+        let row_width = self
+            .spans
+            .last()
+            .map_or(0., |&(start, width)| start + width)
+            .max(width);
+        let row_bc = BoxConstraints::tight(Size::new(row_width, ROW_HEIGHT));
+
+        self.header.widget_mut().spans = self.spans.clone();
+        let header_bc = BoxConstraints::tight(Size::new(row_width, HEADER_HEIGHT));
+        self.header.layout(ctx, &header_bc);
+        ctx.place_child(&mut self.header, Point::ORIGIN);
+
+        let content_height = self.rows.len() as f64 * ROW_HEIGHT;
+        let size = bc.constrain(Size::new(width, HEADER_HEIGHT + content_height));
+        let body_height = Self::body_height(size);
+        let max_scroll = (content_height - body_height).max(0.);
+        self.scroll_offset = self.scroll_offset.clamp(0., max_scroll);
+
+        let first_row = ((self.scroll_offset / ROW_HEIGHT).floor() as usize).min(self.rows.len());
+        let end_row = (((self.scroll_offset + body_height) / ROW_HEIGHT).ceil() as usize)
+            .min(self.rows.len());
+        let visible = first_row..end_row;
+
+        for index in self.shown.clone() {
+            if !visible.contains(&index) {
+                ctx.set_stashed(&mut self.rows[index], true);
+            }
+        }
+        for index in visible.clone() {
+            let row = &mut self.rows[index];
+            ctx.set_stashed(row, false);
+            row.widget_mut().spans = self.spans.clone();
+            row.layout(ctx, &row_bc);
+            let y = HEADER_HEIGHT + index as f64 * ROW_HEIGHT - self.scroll_offset;
+            ctx.place_child(row, Point::new(0., y));
+        }
+        self.shown = visible;
+
+        trace!("Computed layout: size={}, rows={:?}", size, self.shown);
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, scene: &mut Scene) {
+        let size = ctx.size();
+        scene.push_layer(BlendMode::default(), 1., Affine::IDENTITY, &size.to_rect());
+
+        let body = Rect::new(
+            0.,
+            HEADER_HEIGHT,
+            size.width,
+            size.height.max(HEADER_HEIGHT),
+        );
+        scene.push_layer(BlendMode::default(), 1., Affine::IDENTITY, &body);
+        for (index, row) in self.rows.iter_mut().enumerate() {
+            if self.shown.contains(&index) {
+                row.paint(ctx, scene);
+            } else {
+                ctx.skip_child(row);
+            }
+        }
+        if let Some((row, column)) = self.cursor.filter(|_| ctx.is_focused()) {
+            if let Some(&(x, width)) = self.spans.get(column) {
+                let y = HEADER_HEIGHT + row as f64 * ROW_HEIGHT - self.scroll_offset;
+                let cell_rect = Rect::new(x, y, x + width, y + ROW_HEIGHT);
+                stroke(scene, &cell_rect.inset(-1.), theme::PRIMARY_LIGHT, 1.);
+            }
+        }
+        scene.pop_layer();
+
+        let header_rect = Rect::new(0., 0., size.width, HEADER_HEIGHT);
+        fill_color(scene, &header_rect, theme::BACKGROUND_LIGHT);
+        self.header.paint(ctx, scene);
+        for &(start, width) in &self.spans {
+            let x = start + width - 0.5;
+            stroke(
+                scene,
+                &Line::new((x, 0.), (x, HEADER_HEIGHT)),
+                theme::BORDER_LIGHT,
+                1.,
+            );
+        }
+        let bottom = Line::new((0., HEADER_HEIGHT - 0.5), (size.width, HEADER_HEIGHT - 0.5));
+        stroke(scene, &bottom, theme::BORDER_LIGHT, 1.);
+
+        scene.pop_layer();
+    }
+
+    fn accessibility_role(&self) -> Role {
+        Role::Table
+    }
+
+    fn accessibility(&mut self, ctx: &mut AccessCtx) {
+        let node = ctx.current_node();
+        node.set_row_count(self.rows.len());
+        node.set_column_count(self.columns.len());
+        let current_cell = self
+            .cursor
+            .and_then(|(row, column)| self.rows.get(row)?.widget().cells.get(column))
+            .map(WidgetPod::id);
+        if let Some(cell) = current_cell {
+            node.set_active_descendant(cell.into());
+        }
+        self.header.accessibility(ctx);
+        for row in &mut self.rows {
+            row.accessibility(ctx);
+        }
+    }
+
+    fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
+        std::iter::once(self.header.as_dyn())
+            .chain(self.rows.iter().map(WidgetPod::as_dyn))
+            .collect()
+    }
+
+    fn make_trace_span(&self) -> Span {
+        trace_span!("Table")
+    }
+}
+
+impl Widget for TableRow {
+    fn on_pointer_event(&mut self, ctx: &mut EventCtx, event: &PointerEvent) {
+        for cell in &mut self.cells {
+            cell.on_pointer_event(ctx, event);
+        }
+    }
+
+    fn on_text_event(&mut self, ctx: &mut EventCtx, event: &TextEvent) {
+        for cell in &mut self.cells {
+            cell.on_text_event(ctx, event);
+        }
+    }
+
+    fn on_access_event(&mut self, ctx: &mut EventCtx, event: &AccessEvent) {
+        for cell in &mut self.cells {
+            cell.on_access_event(ctx, event);
+        }
+    }
+
+    fn on_status_change(&mut self, _ctx: &mut LifeCycleCtx, _event: &StatusChange) {}
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle) {
+        for cell in &mut self.cells {
+            cell.lifecycle(ctx, event);
+        }
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints) -> Size {
+        let size = bc.max();
+        // Cells beyond the columns of the table are laid out with no width, at its end.
+        let end = self
+            .spans
+            .last()
+            .map_or(0., |&(start, width)| start + width);
+        for (index, cell) in self.cells.iter_mut().enumerate() {
+            let (x, width) = self.spans.get(index).copied().unwrap_or((end, 0.));
+            cell.layout(ctx, &BoxConstraints::tight(Size::new(width, size.height)));
+            ctx.place_child(cell, Point::new(x, 0.));
+        }
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, scene: &mut Scene) {
+        for cell in &mut self.cells {
+            cell.paint(ctx, scene);
+        }
+    }
+
+    fn accessibility_role(&self) -> Role {
+        Role::Row
+    }
+
+    fn accessibility(&mut self, ctx: &mut AccessCtx) {
+        for cell in &mut self.cells {
+            cell.accessibility(ctx);
+        }
+    }
+
+    fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
+        self.cells.iter().map(WidgetPod::as_dyn).collect()
+    }
+
+    fn make_trace_span(&self) -> Span {
+        trace_span!("TableRow")
+    }
+}
+
+impl Widget for TableCell {
+    fn on_pointer_event(&mut self, ctx: &mut EventCtx, event: &PointerEvent) {
+        self.content.on_pointer_event(ctx, event);
+    }
+
+    fn on_text_event(&mut self, ctx: &mut EventCtx, event: &TextEvent) {
+        self.content.on_text_event(ctx, event);
+    }
+
+    fn on_access_event(&mut self, ctx: &mut EventCtx, event: &AccessEvent) {
+        self.content.on_access_event(ctx, event);
+    }
+
+    fn on_status_change(&mut self, _ctx: &mut LifeCycleCtx, _event: &StatusChange) {}
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle) {
+        self.content.lifecycle(ctx, event);
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints) -> Size {
+        let size = bc.max();
+        let arrow_space = if self.sortable { ARROW_SPACE } else { 0. };
+        let content_width = (size.width - 2. * CELL_PADDING - arrow_space).max(0.);
+        let content_bc = BoxConstraints::new(Size::ZERO, Size::new(content_width, size.height));
+        let content_size = self.content.layout(ctx, &content_bc);
+        let origin = Point::new(CELL_PADDING, (size.height - content_size.height) / 2.);
+        ctx.place_child(&mut self.content, origin);
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, scene: &mut Scene) {
+        let size = ctx.size();
+        scene.push_layer(BlendMode::default(), 1., Affine::IDENTITY, &size.to_rect());
+        self.content.paint(ctx, scene);
+        scene.pop_layer();
+
+        if let Some(direction) = self.sort {
+            let center = Point::new(
+                size.width - CELL_PADDING - ARROW_HALF_SIZE,
+                size.height / 2.,
+            );
+            let (tip, base) = match direction {
+                SortDirection::Ascending => (-ARROW_HALF_SIZE / 2., ARROW_HALF_SIZE / 2.),
+                SortDirection::Descending => (ARROW_HALF_SIZE / 2., -ARROW_HALF_SIZE / 2.),
+            };
+            let mut arrow = BezPath::new();
+            arrow.move_to((center.x - ARROW_HALF_SIZE, center.y + base));
+            arrow.line_to((center.x, center.y + tip));
+            arrow.line_to((center.x + ARROW_HALF_SIZE, center.y + base));
+            stroke(scene, &arrow, theme::TEXT_COLOR, 1.5);
+        }
+    }
+
+    fn accessibility_role(&self) -> Role {
+        if self.is_header {
+            Role::ColumnHeader
+        } else {
+            Role::Cell
+        }
+    }
+
+    fn accessibility(&mut self, ctx: &mut AccessCtx) {
+        if self.sortable {
+            match self.sort {
+                Some(SortDirection::Ascending) => ctx
+                    .current_node()
+                    .set_sort_direction(accesskit::SortDirection::Ascending),
+                Some(SortDirection::Descending) => ctx
+                    .current_node()
+                    .set_sort_direction(accesskit::SortDirection::Descending),
+                None => {}
+            }
+            ctx.current_node().add_action(accesskit::Action::Default);
+        }
+        self.content.accessibility(ctx);
+    }
+
+    fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
+        smallvec![self.content.as_dyn()]
+    }
+
+    fn make_trace_span(&self) -> Span {
+        trace_span!("TableCell")
+    }
+
+    fn get_debug_text(&self) -> Option<String> {
+        match self.sort? {
+            SortDirection::Ascending => Some("▴".into()),
+            SortDirection::Descending => Some("▾".into()),
+        }
+    }
+}
+
+// --- MARK: TESTS ---
+#[cfg(test)]
+mod tests {
+    use insta::assert_debug_snapshot;
+
+    use super::*;
+    use crate::testing::{widget_ids, TestHarness};
+
+    /// A table of four files, with the label of the first row in the `Size` column
+    /// having the given id.
+    fn files(size_label: crate::WidgetId) -> Table {
+        let mut table = Table::new([
+            ColumnSpec::new("Name").width(120.).sortable(true),
+            ColumnSpec::new("Size")
+                .width(80.)
+                .min_width(40.)
+                .sortable(true),
+            ColumnSpec::new("Kind").flex(1.),
+        ]);
+        for row in 0..4 {
+            let size = Label::new(format!("{row} kB"));
+            let size = if row == 0 {
+                WidgetPod::new_with_id(size, size_label)
+            } else {
+                WidgetPod::new(size)
+            };
+            table = table.with_row_pods([
+                WidgetPod::new(Label::new(format!("file{row}"))).boxed(),
+                size.boxed(),
+                WidgetPod::new(Label::new("text")).boxed(),
+            ]);
+        }
+        table
+    }
+
+    fn click(harness: &mut TestHarness, pos: (f64, f64)) {
+        harness.mouse_move(pos);
+        harness.mouse_button_press(MouseButton::Left);
+        harness.mouse_button_release(MouseButton::Left);
+    }
+
+    fn key(key: NamedKey) -> Key {
+        Key::Named(key)
+    }
+
+    #[test]
+    fn header_clicks_request_sort() {
+        let [size_label] = widget_ids();
+        let mut harness = TestHarness::create_with_size(files(size_label), Size::new(400., 200.));
+        let table_id = harness.root_widget().id();
+        let header_y = HEADER_HEIGHT / 2.;
+
+        // Click the `Size` header twice.
+        click(&mut harness, (160., header_y));
+        assert_eq!(
+            harness.pop_action(),
+            Some((
+                Action::TableSortRequested(1, SortDirection::Ascending),
+                table_id
+            ))
+        );
+        click(&mut harness, (160., header_y));
+        assert_eq!(
+            harness.pop_action(),
+            Some((
+                Action::TableSortRequested(1, SortDirection::Descending),
+                table_id
+            ))
+        );
+
+        // Another column is sorted in ascending order first.
+        click(&mut harness, (60., header_y));
+        assert_eq!(
+            harness.pop_action(),
+            Some((
+                Action::TableSortRequested(0, SortDirection::Ascending),
+                table_id
+            ))
+        );
+
+        // `Kind` isn't sortable.
+        click(&mut harness, (300., header_y));
+        assert_eq!(harness.pop_action(), None);
+
+        let table = harness.root_widget();
+        let table = table.downcast::<Table>().unwrap();
+        assert_eq!(table.sort(), Some((0, SortDirection::Ascending)));
+        assert_debug_snapshot!(harness.root_widget());
+    }
+
+    #[test]
+    fn drag_divider_resizes_column() {
+        let [size_label] = widget_ids();
+        let mut harness = TestHarness::create_with_size(files(size_label), Size::new(400., 200.));
+        let label_x = |harness: &TestHarness| {
+            harness
+                .get_widget(size_label)
+                .state()
+                .window_layout_rect()
+                .x0
+        };
+        assert_eq!(label_x(&harness), 120. + CELL_PADDING);
+
+        // Drag the right edge of `Name` by 50 pixels.
+        harness.mouse_move((121., HEADER_HEIGHT / 2.));
+        harness.mouse_button_press(MouseButton::Left);
+        harness.mouse_move((171., HEADER_HEIGHT / 2.));
+        harness.mouse_button_release(MouseButton::Left);
+        assert_eq!(harness.pop_action(), None);
+
+        assert_eq!(label_x(&harness), 170. + CELL_PADDING);
+        let table = harness.root_widget();
+        let table = table.downcast::<Table>().unwrap();
+        assert_eq!(table.column_width(0), Some(170.));
+        // The flex column takes what is left.
+        assert_eq!(table.column_width(2), Some(400. - 170. - 80.));
+
+        // `Size` can't be narrower than its minimum width.
+        harness.mouse_move((250., HEADER_HEIGHT / 2.));
+        harness.mouse_button_press(MouseButton::Left);
+        harness.mouse_move((0., HEADER_HEIGHT / 2.));
+        harness.mouse_button_release(MouseButton::Left);
+        let table = harness.root_widget();
+        let table = table.downcast::<Table>().unwrap();
+        assert_eq!(table.column_width(1), Some(40.));
+
+        // The resized width is kept when the columns are set again.
+        harness.edit_root_widget(|mut table| {
+            let mut table = table.downcast::<Table>();
+            table.set_columns([
+                ColumnSpec::new("Name").width(120.).sortable(true),
+                ColumnSpec::new("Size")
+                    .width(80.)
+                    .min_width(40.)
+                    .sortable(true),
+                ColumnSpec::new("Type").flex(1.),
+            ]);
+        });
+        let table = harness.root_widget();
+        let table = table.downcast::<Table>().unwrap();
+        assert_eq!(table.column_width(0), Some(170.));
+        assert_eq!(table.column_width(1), Some(40.));
+    }
+
+    #[test]
+    fn keys_move_current_cell() {
+        let [size_label] = widget_ids();
+        let mut table = files(size_label);
+        assert!(table.on_key(&key(NamedKey::ArrowDown), 2));
+        assert_eq!(table.cursor, Some((0, 0)));
+        assert!(table.on_key(&key(NamedKey::End), 2));
+        assert_eq!(table.cursor, Some((0, 2)));
+        assert!(!table.on_key(&key(NamedKey::ArrowRight), 2));
+        assert!(table.on_key(&key(NamedKey::PageDown), 2));
+        assert_eq!(table.cursor, Some((2, 2)));
+        assert!(table.on_key(&key(NamedKey::PageDown), 2));
+        assert_eq!(table.cursor, Some((3, 2)));
+        assert!(table.on_key(&key(NamedKey::ArrowLeft), 2));
+        assert!(table.on_key(&key(NamedKey::ArrowUp), 2));
+        assert_eq!(table.cursor, Some((2, 1)));
+
+        // Clicking a cell makes it the current one.
+        let mut harness = TestHarness::create_with_size(table, Size::new(400., 200.));
+        click(&mut harness, (300., HEADER_HEIGHT + 1.5 * ROW_HEIGHT));
+        let table_id = harness.root_widget().id();
+        assert_eq!(harness.focused_widget().map(|w| w.id()), Some(table_id));
+        let table = harness.root_widget();
+        let table = table.downcast::<Table>().unwrap();
+        assert_eq!(table.cursor, Some((1, 2)));
+    }
+}
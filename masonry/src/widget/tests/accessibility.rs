@@ -0,0 +1,180 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+use accesskit::NodeId;
+
+use crate::testing::{widget_ids, ModularWidget, TestHarness};
+use crate::widget::{CrossAxisAlignment, Flex, Label, Portal, SizedBox};
+use crate::*;
+
+fn emitted_ids(tree_update: &accesskit::TreeUpdate) -> Vec<NodeId> {
+    tree_update.nodes.iter().map(|(id, _)| *id).collect()
+}
+
+/// The children of the node of `id`, if it was emitted.
+fn emitted_children(tree_update: &accesskit::TreeUpdate, id: WidgetId) -> Option<Vec<NodeId>> {
+    tree_update
+        .nodes
+        .iter()
+        .find(|(node_id, _)| *node_id == NodeId::from(id))
+        .map(|(_, node)| node.children().to_vec())
+}
+
+/// A widget which takes focus when clicked.
+fn focus_taker() -> impl Widget {
+    ModularWidget::new(())
+        .pointer_event_fn(|_, ctx, event| {
+            if let PointerEvent::PointerDown(_, _) = event {
+                ctx.request_focus();
+            }
+        })
+        .layout_fn(|_, _, _| Size::new(50., 50.))
+}
+
+#[test]
+fn label_change_emits_few_nodes() {
+    let [label_id] = widget_ids();
+
+    let mut widget = Flex::column().cross_axis_alignment(CrossAxisAlignment::Start);
+    for i in 0..1000 {
+        if i == 500 {
+            widget = widget.with_child_id(Label::new("Label 500"), label_id);
+        } else {
+            widget = widget.with_child(Label::new(format!("Label {i}")));
+        }
+    }
+    let mut harness = TestHarness::create(widget);
+
+    // The first update contains the whole tree.
+    harness.access_update();
+    assert!(harness.access_stats().last_update_nodes > 1000);
+
+    harness.edit_root_widget(|mut root| {
+        let mut flex = root.downcast::<Flex>();
+        let mut child = flex.child_mut(500).unwrap();
+        let mut label = child.downcast::<Label>();
+        label.set_text("Changed");
+    });
+    let tree_update = harness.access_update();
+
+    // Only the label and the flex are emitted again.
+    let stats = harness.access_stats();
+    assert_eq!(stats.updates, 2);
+    assert!(stats.last_update_nodes <= 2, "{stats:?}");
+    let (_, label_node) = tree_update
+        .nodes
+        .iter()
+        .find(|(id, _)| *id == NodeId::from(label_id))
+        .expect("label node wasn't emitted");
+    assert_eq!(label_node.name(), Some("Changed"));
+}
+
+#[test]
+fn focus_change_emits_old_and_new_focus() {
+    let [id_1, id_2] = widget_ids();
+    let widget = Flex::row()
+        .with_child_id(focus_taker(), id_1)
+        .with_child_id(focus_taker(), id_2);
+    let mut harness = TestHarness::create(widget);
+
+    harness.mouse_click_on(id_1);
+    harness.access_update();
+    assert_eq!(harness.focused_widget().map(|w| w.id()), Some(id_1));
+
+    harness.mouse_click_on(id_2);
+    let tree_update = harness.access_update();
+    assert_eq!(harness.focused_widget().map(|w| w.id()), Some(id_2));
+
+    let emitted = emitted_ids(&tree_update);
+    assert_eq!(emitted.len(), 2, "{emitted:?}");
+    assert!(emitted.contains(&NodeId::from(id_1)));
+    assert!(emitted.contains(&NodeId::from(id_2)));
+    assert_eq!(tree_update.focus, NodeId::from(id_2));
+}
+
+#[test]
+fn reading_order_sorts_children() {
+    let [first, second, third] = widget_ids();
+    let label = |id, reading_order| {
+        WidgetPod::<Box<dyn Widget>>::new_with_id(Box::new(Label::new(format!("{id:?}"))), id)
+            .with_reading_order(Some(reading_order))
+    };
+    let widget = Flex::row()
+        .with_child_pod(label(third, 3))
+        .with_child_pod(label(first, 1))
+        .with_child_pod(label(second, 2));
+    let mut harness = TestHarness::create(widget);
+    let flex_id = harness.root_widget().id();
+
+    let children = |tree_update: &accesskit::TreeUpdate| {
+        let (_, flex_node) = tree_update
+            .nodes
+            .iter()
+            .find(|(id, _)| *id == NodeId::from(flex_id))
+            .expect("flex node wasn't emitted");
+        flex_node.children().to_vec()
+    };
+    let tree_update = harness.access_update();
+    assert_eq!(
+        children(&tree_update),
+        [first, second, third].map(NodeId::from)
+    );
+
+    // The parent node is emitted again when the order changes.
+    harness.edit_root_widget(|mut root| {
+        let mut flex = root.downcast::<Flex>();
+        flex.child_mut(0).unwrap().set_reading_order(Some(0));
+    });
+    let tree_update = harness.access_update();
+    assert_eq!(
+        children(&tree_update),
+        [third, first, second].map(NodeId::from)
+    );
+}
+
+#[test]
+fn scrolled_out_widgets_are_pruned() {
+    let [first_id, first_label_id, far_id, far_label_id] = widget_ids();
+    let item = |label_id, text: &str| {
+        SizedBox::new_with_id(Label::new(text), label_id)
+            .width(100.0)
+            .height(100.0)
+    };
+    let mut content = Flex::column().with_child_id(item(first_label_id, "First"), first_id);
+    for i in 1..20 {
+        if i == 10 {
+            content = content.with_child_id(item(far_label_id, "Far"), far_id);
+        } else {
+            content =
+                content.with_child(SizedBox::new(Label::new(format!("Item {i}"))).height(100.0));
+        }
+    }
+    let mut harness = TestHarness::create_with_size(Portal::new(content), Size::new(200., 200.));
+    harness.set_prune_hidden_access_nodes(true);
+
+    // The item a whole viewport below the visible ones is emitted without its label.
+    let tree_update = harness.access_update();
+    assert_eq!(
+        emitted_children(&tree_update, first_id),
+        Some(vec![NodeId::from(first_label_id)])
+    );
+    assert_eq!(emitted_children(&tree_update, far_id), Some(vec![]));
+    assert_eq!(emitted_children(&tree_update, far_label_id), None);
+
+    harness.edit_root_widget(|mut root| {
+        let mut portal = root.downcast::<Portal<Flex>>();
+        portal.set_viewport_pos(Point::new(0.0, 1000.0));
+    });
+    let tree_update = harness.access_update();
+    assert_eq!(
+        emitted_children(&tree_update, far_id),
+        Some(vec![NodeId::from(far_label_id)])
+    );
+    let (_, far_label_node) = tree_update
+        .nodes
+        .iter()
+        .find(|(id, _)| *id == NodeId::from(far_label_id))
+        .expect("label node wasn't emitted");
+    assert_eq!(far_label_node.name(), Some("Far"));
+    assert_eq!(emitted_children(&tree_update, first_id), Some(vec![]));
+}
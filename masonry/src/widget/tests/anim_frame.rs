@@ -0,0 +1,71 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests related to animation frames.
+
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::time::Duration;
+
+use crate::testing::{widget_ids, ModularWidget, TestHarness};
+use crate::widget::Flex;
+use crate::LifeCycle;
+
+#[test]
+fn frames_stop_when_no_longer_requested() {
+    let [animated_id, idle_id] = widget_ids();
+    let elapsed = Rc::new(RefCell::new(vec![]));
+    let idle_frames = Rc::new(Cell::new(0));
+
+    // Animates for three frames, recording the total time it got.
+    let animated = ModularWidget::new((3, 0, elapsed.clone()))
+        .lifecycle_fn(|_, ctx, event| {
+            if let LifeCycle::WidgetAdded = event {
+                ctx.request_anim_frame();
+            }
+        })
+        .anim_frame_fn(|(frames_left, total, elapsed), ctx, interval| {
+            *total += interval;
+            elapsed.borrow_mut().push(*total);
+            *frames_left -= 1;
+            if *frames_left > 0 {
+                ctx.request_anim_frame();
+            }
+        });
+    let idle = ModularWidget::new(idle_frames.clone())
+        .anim_frame_fn(|frames, _, _| frames.set(frames.get() + 1));
+    let widget = Flex::row()
+        .with_child_id(animated, animated_id)
+        .with_child_id(idle, idle_id);
+
+    let mut harness = TestHarness::create(widget);
+    for _ in 0..5 {
+        harness.animate(Duration::from_millis(16));
+    }
+
+    assert_eq!(*elapsed.borrow(), [16_000_000, 32_000_000, 48_000_000]);
+    assert_eq!(idle_frames.get(), 0);
+    assert!(!harness.get_widget(animated_id).state().request_anim);
+    assert!(!harness.get_widget(idle_id).state().request_anim);
+}
+
+#[test]
+fn single_request_gets_single_frame() {
+    let [widget_id] = widget_ids();
+    let frames = Rc::new(Cell::new(0));
+
+    // Requests a frame once, and counts the frames it gets afterwards.
+    let widget =
+        ModularWidget::new(frames.clone()).lifecycle_fn(|frames, ctx, event| match event {
+            LifeCycle::WidgetAdded => ctx.request_anim_frame(),
+            LifeCycle::AnimFrame(_) => frames.set(frames.get() + 1),
+            _ => (),
+        });
+
+    let mut harness = TestHarness::create(Flex::row().with_child_id(widget, widget_id));
+    for _ in 0..3 {
+        harness.animate(Duration::from_millis(16));
+    }
+
+    assert_eq!(frames.get(), 1);
+}
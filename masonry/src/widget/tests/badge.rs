@@ -0,0 +1,139 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+use accesskit::NodeId;
+
+use crate::assert_render_snapshot;
+use crate::testing::{widget_ids, TestHarness};
+use crate::widget::{Button, Flex, SizedBox};
+use crate::*;
+
+fn icon() -> SizedBox {
+    SizedBox::empty().width(24.).height(24.)
+}
+
+/// A widget with some room around it, so that its badge isn't clipped by the window.
+fn padded(widget: impl Widget, badge: Badge) -> impl Widget {
+    let row = Flex::row()
+        .with_spacer(16.)
+        .with_child_pod(WidgetPod::<Box<dyn Widget>>::new(Box::new(widget)).with_badge(badge));
+    Flex::column().with_spacer(16.).with_child(row)
+}
+
+#[test]
+fn badge_overflows_without_changing_layout() {
+    let [button_id, icon_id] = widget_ids();
+    let widget = Flex::row()
+        .with_child_id(Button::new("Inbox"), button_id)
+        .with_child_id(icon(), icon_id);
+    let mut harness = TestHarness::create_with_size(widget, Size::new(400., 400.));
+    harness.paint_damage();
+    let button_rect = harness.get_widget(button_id).state().window_layout_rect();
+    let icon_rect = harness.get_widget(icon_id).state().window_layout_rect();
+
+    harness.edit_root_widget(|mut root| {
+        let mut flex = root.downcast::<Flex>();
+        flex.child_mut(0).unwrap().set_badge(Some(Badge::text("3")));
+        flex.child_mut(1).unwrap().set_badge(Some(Badge::dot()));
+    });
+    let damage = harness.paint_damage();
+
+    assert_eq!(
+        harness.get_widget(button_id).state().window_layout_rect(),
+        button_rect
+    );
+    assert_eq!(
+        harness.get_widget(icon_id).state().window_layout_rect(),
+        icon_rect
+    );
+    // The badges are centered on the top-right corners, so they stick out of the widgets.
+    for rect in [button_rect, icon_rect] {
+        let corner = Rect::from_center_size((rect.x1, rect.y0), Size::new(4., 4.));
+        assert!(
+            damage
+                .iter()
+                .any(|damaged| damaged.union(corner) == *damaged),
+            "{corner:?} isn't covered by {damage:?}"
+        );
+    }
+
+    // Removing a badge repaints the area it covered.
+    harness.edit_root_widget(|mut root| {
+        let mut flex = root.downcast::<Flex>();
+        flex.child_mut(1).unwrap().set_badge(None);
+    });
+    let badge_point = Point::new(icon_rect.x1 + 3., icon_rect.y0 - 3.);
+    let damage = harness.paint_damage();
+    assert!(damage.iter().any(|damaged| damaged.contains(badge_point)));
+}
+
+#[test]
+fn badge_description_is_appended() {
+    let [button_id] = widget_ids();
+    let widget = Flex::row().with_child_pod(
+        WidgetPod::<Box<dyn Widget>>::new_with_id(Box::new(Button::new("Inbox")), button_id)
+            .with_badge(Badge::text("3").with_description("3 unread")),
+    );
+    let mut harness = TestHarness::create(widget);
+
+    let tree_update = harness.access_update();
+    let (_, button_node) = tree_update
+        .nodes
+        .iter()
+        .find(|(id, _)| *id == NodeId::from(button_id))
+        .expect("button node wasn't emitted");
+    assert_eq!(button_node.description(), Some("3 unread"));
+
+    harness.edit_root_widget(|mut root| {
+        let mut flex = root.downcast::<Flex>();
+        flex.child_mut(0).unwrap().set_badge(Some(Badge::text("4")));
+    });
+    let tree_update = harness.access_update();
+    let (_, button_node) = tree_update
+        .nodes
+        .iter()
+        .find(|(id, _)| *id == NodeId::from(button_id))
+        .expect("button node wasn't emitted");
+    assert_eq!(button_node.description(), Some("4"));
+}
+
+#[test]
+fn count_badge_on_button() {
+    let widget = padded(Button::new("Inbox"), Badge::text("3"));
+    let mut harness = TestHarness::create_with_size(widget, Size::new(140., 80.));
+    assert_render_snapshot!(harness, "count_badge_on_button");
+}
+
+#[test]
+fn dot_badge_on_icon() {
+    let widget = padded(icon().background(Color::GRAY), Badge::dot());
+    let mut harness = TestHarness::create_with_size(widget, Size::new(60., 60.));
+    assert_render_snapshot!(harness, "dot_badge_on_icon");
+}
+
+#[test]
+fn badge_bounds_follow_size_changes() {
+    let [icon_id] = widget_ids();
+    let widget = Flex::row().with_child_pod(
+        WidgetPod::<Box<dyn Widget>>::new_with_id(Box::new(icon()), icon_id)
+            .with_badge(Badge::dot()),
+    );
+    let mut harness = TestHarness::create_with_size(widget, Size::new(400., 400.));
+    harness.paint_damage();
+    let badge_bounds = |harness: &TestHarness| {
+        harness
+            .get_widget(icon_id)
+            .state()
+            .badge_bounds
+            .expect("badge bounds weren't computed")
+    };
+    assert_eq!(badge_bounds(&harness).center(), Point::new(24., 0.));
+
+    harness.edit_root_widget(|mut root| {
+        let mut flex = root.downcast::<Flex>();
+        let mut child = flex.child_mut(0).unwrap();
+        child.downcast::<SizedBox>().set_width(48.);
+    });
+    harness.paint_damage();
+    assert_eq!(badge_bounds(&harness).center(), Point::new(48., 0.));
+}
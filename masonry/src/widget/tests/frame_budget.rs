@@ -0,0 +1,96 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+use std::time::Duration;
+
+use accesskit::NodeId;
+
+use crate::frame_budget::{BudgetPolicy, VirtualClock};
+use crate::testing::{widget_ids, ModularWidget, TestHarness};
+use crate::widget::{Flex, Label};
+use crate::*;
+
+const BUDGET: Duration = Duration::from_millis(16);
+const SLOW_LAYOUT: Duration = Duration::from_millis(30);
+
+/// A widget whose layout takes longer than a frame, as measured by `clock`.
+fn slow_widget(clock: VirtualClock) -> impl Widget {
+    ModularWidget::new(clock).layout_fn(|clock, _, _| {
+        clock.advance(SLOW_LAYOUT);
+        Size::new(50., 50.)
+    })
+}
+
+#[test]
+fn slow_layout_is_reported() {
+    let [slow_id] = widget_ids();
+    let clock = VirtualClock::new();
+    let widget = Flex::row()
+        .with_child(Label::new("Fast"))
+        .with_child_id(slow_widget(clock.clone()), slow_id);
+    let mut harness = TestHarness::create(widget);
+    harness.use_virtual_clock(clock);
+    harness.set_frame_budget(BUDGET, BudgetPolicy::Report);
+
+    harness.edit_root_widget(|mut root| root.ctx.request_layout());
+    harness.paint_damage();
+
+    let stats = harness.frame_budget_stats();
+    assert_eq!(stats.frames, 1);
+    assert_eq!(stats.over_budget_frames, 1);
+    assert_eq!(stats.deferred_access_updates, 0);
+    let overrun = stats.last_overrun.unwrap();
+    assert_eq!(overrun.layout, SLOW_LAYOUT);
+    assert_eq!(
+        overrun.slowest_widgets[0],
+        (slow_id, "ModularWidget", SLOW_LAYOUT)
+    );
+
+    // Nothing is slow when the widget isn't laid out again.
+    harness.paint_damage();
+    let stats = harness.frame_budget_stats();
+    assert_eq!(stats.frames, 2);
+    assert_eq!(stats.over_budget_frames, 1);
+}
+
+#[test]
+fn over_budget_frame_defers_access_update() {
+    let [label_id] = widget_ids();
+    let clock = VirtualClock::new();
+    let widget = Flex::row()
+        .with_child_id(Label::new("Label"), label_id)
+        .with_child(slow_widget(clock.clone()));
+    let mut harness = TestHarness::create(widget);
+    harness.access_update();
+    let updates = harness.access_stats().updates;
+
+    harness.use_virtual_clock(clock);
+    harness.set_frame_budget(BUDGET, BudgetPolicy::DeferNonCritical);
+    harness.edit_root_widget(|mut root| {
+        let mut flex = root.downcast::<Flex>();
+        let mut child = flex.child_mut(0).unwrap();
+        let mut label = child.downcast::<Label>();
+        label.set_text("Changed");
+    });
+
+    // The frame laying out the slow widget is still painted, but its accessibility
+    // update is postponed.
+    let damage = harness.paint_damage();
+    let label_rect = harness.get_widget(label_id).state().window_layout_rect();
+    assert!(damage
+        .iter()
+        .any(|damaged| damaged.union(label_rect) == *damaged));
+    assert_eq!(harness.access_stats().updates, updates);
+    assert_eq!(harness.frame_budget_stats().deferred_access_updates, 1);
+
+    // The next frame is within the budget, and emits the pending update.
+    let tree_update = harness.access_update();
+    assert_eq!(harness.access_stats().updates, updates + 1);
+    assert_eq!(harness.frame_budget_stats().deferred_access_updates, 1);
+    let (_, label_node) = tree_update
+        .nodes
+        .iter()
+        .find(|(id, _)| *id == NodeId::from(label_id))
+        .expect("label node wasn't emitted");
+    assert_eq!(label_node.name(), Some("Changed"));
+}
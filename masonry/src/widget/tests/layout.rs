@@ -3,6 +3,9 @@
 
 //! Tests related to layout.
 
+use std::cell::Cell;
+use std::rc::Rc;
+
 use crate::kurbo::{Insets, Size};
 use crate::testing::{widget_ids, ModularWidget, TestHarness, TestWidgetExt};
 use crate::widget::{Flex, SizedBox};
@@ -71,3 +74,20 @@ fn layout_insets() {
 // TODO - insets + flex
 // TODO - viewport
 // TODO - insets + viewport
+
+#[test]
+fn reduced_motion_relayouts() {
+    let reduced_motion = Rc::new(Cell::new(false));
+
+    let reduced_motion_clone = reduced_motion.clone();
+    let widget = ModularWidget::new(()).layout_fn(move |_, ctx, _| {
+        reduced_motion_clone.set(ctx.reduced_motion());
+        Size::new(50., 50.)
+    });
+
+    let mut harness = TestHarness::create(widget);
+    assert!(!reduced_motion.get());
+
+    harness.set_reduced_motion(true);
+    assert!(reduced_motion.get());
+}
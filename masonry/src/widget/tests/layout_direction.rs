@@ -0,0 +1,142 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests related to right-to-left layout.
+
+use crate::kurbo::Rect;
+use crate::paint_scene_helpers::UnitPoint;
+use crate::testing::{widget_ids, TestHarness, TestWidgetExt};
+use crate::widget::{Align, CrossAxisAlignment, Flex, Label, MainAxisAlignment, SizedBox};
+use crate::{LayoutDirection, Widget, WidgetId, WidgetPod};
+
+const WIDTH: f64 = 400.;
+
+/// A row filling the window, so that it's mirrored within the window.
+fn row(ids: [WidgetId; 2]) -> Flex {
+    Flex::row()
+        .must_fill_main_axis(true)
+        .with_child_id(SizedBox::empty().width(50.).height(20.), ids[0])
+        .with_spacer(10.)
+        .with_child_id(SizedBox::empty().width(30.).height(40.), ids[1])
+}
+
+fn rects(harness: &TestHarness, ids: [WidgetId; 2]) -> [Rect; 2] {
+    ids.map(|id| harness.get_widget(id).state().layout_rect())
+}
+
+/// `rect` mirrored within the window.
+fn mirrored(rect: Rect) -> Rect {
+    Rect::new(WIDTH - rect.x1, rect.y0, WIDTH - rect.x0, rect.y1)
+}
+
+#[test]
+fn rtl_row_is_mirrored() {
+    let ids = widget_ids();
+    let mut harness = TestHarness::create(row(ids));
+
+    let [first, second] = rects(&harness, ids);
+    assert_eq!(first.x0, 0.);
+    assert_eq!(second.x0, 60.);
+
+    harness.set_layout_direction(LayoutDirection::Rtl);
+    let [first_rtl, second_rtl] = rects(&harness, ids);
+    // The first child is on the right, and the spacing is kept.
+    assert_eq!(first_rtl, mirrored(first));
+    assert_eq!(second_rtl, mirrored(second));
+    assert_eq!(first_rtl.x0 - second_rtl.x1, 10.);
+
+    harness.set_layout_direction(LayoutDirection::Ltr);
+    assert_eq!(rects(&harness, ids), [first, second]);
+}
+
+#[test]
+fn rtl_row_with_end_alignment() {
+    let ids = widget_ids();
+    let widget = row(ids).main_axis_alignment(MainAxisAlignment::End);
+    let mut harness = TestHarness::create(widget);
+    let [first, _] = rects(&harness, ids);
+    assert_eq!(first.x0, 310.);
+
+    harness.set_layout_direction(LayoutDirection::Rtl);
+    let [first_rtl, second_rtl] = rects(&harness, ids);
+    assert_eq!(second_rtl.x0, 0.);
+    assert_eq!(first_rtl, mirrored(first));
+}
+
+#[test]
+fn rtl_column_is_unchanged() {
+    let ids = widget_ids();
+    let widget = Flex::column()
+        .with_child_id(SizedBox::empty().width(50.).height(20.), ids[0])
+        .with_spacer(10.)
+        .with_child_id(SizedBox::empty().width(30.).height(40.), ids[1]);
+    let mut harness = TestHarness::create(widget);
+    let ltr = rects(&harness, ids);
+
+    harness.set_layout_direction(LayoutDirection::Rtl);
+    assert_eq!(rects(&harness, ids), ltr);
+}
+
+#[test]
+fn start_aligned_child_lands_on_the_right() {
+    let [start_id, left_id] = widget_ids();
+    let widget = Flex::column()
+        .with_child(Align::new(
+            UnitPoint::START,
+            SizedBox::empty().width(50.).height(50.).with_id(start_id),
+        ))
+        .with_child(Align::left(
+            SizedBox::empty().width(50.).height(50.).with_id(left_id),
+        ));
+    let mut harness = TestHarness::create(widget);
+    assert_eq!(harness.get_widget(start_id).state().layout_rect().x0, 0.);
+
+    harness.set_layout_direction(LayoutDirection::Rtl);
+    let window_rect = |id| harness.get_widget(id).state().window_layout_rect();
+    assert_eq!(window_rect(start_id).x1, WIDTH);
+    // Physical alignments aren't mirrored.
+    assert_eq!(window_rect(left_id).x0, 0.);
+}
+
+#[test]
+fn subtree_overrides_window_direction() {
+    let ids = widget_ids();
+    let mut harness = TestHarness::create(row(ids));
+    let ltr = rects(&harness, ids);
+
+    harness.edit_root_widget(|mut root| root.set_layout_direction(Some(LayoutDirection::Rtl)));
+    assert_eq!(rects(&harness, ids), ltr.map(mirrored));
+
+    harness.edit_root_widget(|mut root| root.set_layout_direction(None));
+    assert_eq!(rects(&harness, ids), ltr);
+}
+
+#[test]
+fn rtl_row_of_labels_starts_on_the_right() {
+    let ids = widget_ids();
+    let widget = Flex::row()
+        .with_child_id(Label::new("First"), ids[0])
+        .with_child_id(Label::new("Second"), ids[1]);
+    let mut harness = TestHarness::create(widget);
+    harness.set_layout_direction(LayoutDirection::Rtl);
+
+    let [first, second] = rects(&harness, ids);
+    assert_eq!(first.x1, WIDTH);
+    assert_eq!(second.x1, first.x0);
+}
+
+#[test]
+fn row_pod_overrides_window_direction() {
+    let ids = widget_ids();
+    let rtl_row = WidgetPod::<Box<dyn Widget>>::new(Box::new(row(ids)))
+        .with_layout_direction(Some(LayoutDirection::Rtl));
+    // The row is stretched to the width of the window.
+    let widget = Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Fill)
+        .with_child_pod(rtl_row);
+    let harness = TestHarness::create(widget);
+
+    let [first, second] = rects(&harness, ids);
+    assert_eq!(first.x1, WIDTH);
+    assert_eq!(first.x0 - second.x1, 10.);
+}
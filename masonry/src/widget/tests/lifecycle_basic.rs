@@ -85,6 +85,83 @@ fn child_tracking() {
     assert_eq!(child_state.children.entry_count(), 2);
 }
 
+/// A column of rows of empty boxes, one box per id, 100 boxes per row.
+fn large_tree(ids: &[WidgetId]) -> Flex {
+    let mut root = Flex::column();
+    for row_ids in ids.chunks(100) {
+        let mut row = Flex::row();
+        for id in row_ids {
+            row = row.with_child_id(SizedBox::empty(), *id);
+        }
+        root = root.with_child(row);
+    }
+    root
+}
+
+#[test]
+fn find_widget_in_large_tree() {
+    let ids: Vec<WidgetId> = (0..5000).map(|_| WidgetId::next()).collect();
+    let [new_id] = widget_ids();
+
+    let mut harness = TestHarness::create(large_tree(&ids));
+
+    for id in ids.iter().step_by(499) {
+        assert_eq!(harness.get_widget(*id).id(), *id);
+    }
+    assert!(harness.try_get_widget(new_id).is_none());
+
+    // Widgets added after the harness was created are found too.
+    harness.edit_root_widget(|mut root| {
+        let mut root = root.downcast::<Flex>();
+        let mut row = root.child_mut(49).unwrap();
+        let mut row = row.downcast::<Flex>();
+        row.add_child_id(SizedBox::empty(), new_id);
+    });
+    assert_eq!(harness.get_widget(new_id).id(), new_id);
+}
+
+#[test]
+fn targeted_relayout_in_large_tree() {
+    let ids: Vec<WidgetId> = (0..5000).map(|_| WidgetId::next()).collect();
+    let mut harness = TestHarness::create(large_tree(&ids));
+
+    harness.edit_root_widget(|mut root| {
+        root.edit_descendant(ids[2500], |mut widget| widget.ctx.request_layout());
+    });
+    // Only the box, its row and the root are laid out because of the request.
+    assert_eq!(harness.layout_stats().last_requested_widgets, 3);
+}
+
+#[test]
+fn lookups_follow_flex_mutations() {
+    let ids: Vec<WidgetId> = (0..300).map(|_| WidgetId::next()).collect();
+    let mut harness = TestHarness::create(large_tree(&ids));
+
+    // The boxes of a removed row are gone with it.
+    harness.edit_root_widget(|mut root| {
+        let mut root = root.downcast::<Flex>();
+        root.remove_child(1);
+    });
+    assert!(harness.try_get_widget(ids[100]).is_none());
+    assert!(harness.try_get_widget(ids[199]).is_none());
+
+    // Moved rows keep their boxes.
+    harness.edit_root_widget(|mut root| {
+        let mut root = root.downcast::<Flex>();
+        root.move_child(0, 1);
+    });
+    assert_eq!(harness.get_widget(ids[0]).id(), ids[0]);
+    assert_eq!(harness.get_widget(ids[299]).id(), ids[299]);
+
+    harness.edit_root_widget(|mut root| {
+        let mut root = root.downcast::<Flex>();
+        let mut row = root.child_mut(0).unwrap();
+        row.downcast::<Flex>().clear();
+    });
+    assert!(harness.try_get_widget(ids[200]).is_none());
+    assert_eq!(harness.get_widget(ids[0]).id(), ids[0]);
+}
+
 /// Test that all children are registered correctly after a child is replaced.
 #[test]
 #[cfg(FALSE)]
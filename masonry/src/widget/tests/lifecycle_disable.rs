@@ -9,7 +9,7 @@ use std::rc::Rc;
 use smallvec::smallvec;
 
 use crate::testing::{widget_ids, ModularWidget, TestHarness, TestWidgetExt as _};
-use crate::widget::Flex;
+use crate::widget::{Button, Flex};
 use crate::*;
 
 #[cfg(FALSE)]
@@ -193,3 +193,55 @@ fn disable_tree() {
     );
     assert_eq!(harness.window().focus_chain().len(), 1);
 }
+
+#[test]
+fn disabled_container_blocks_nested_button() {
+    let [button_id] = widget_ids();
+    let container = Flex::column().with_child_id(Button::new("Press me"), button_id);
+    let mut harness = TestHarness::create(Flex::row().with_child(container));
+
+    harness.edit_root_widget(|mut root| {
+        let mut root = root.downcast::<Flex>();
+        let mut container = root.child_mut(0).unwrap();
+        container.ctx.set_disabled(true);
+        // An explicitly enabled widget stays disabled inside a disabled container.
+        let mut container = container.downcast::<Flex>();
+        let mut button = container.child_mut(0).unwrap();
+        button.ctx.set_disabled(false);
+    });
+    assert!(harness.get_widget(button_id).state().is_disabled());
+
+    harness.mouse_click_on(button_id);
+    assert_eq!(harness.pop_action(), None);
+
+    harness.edit_root_widget(|mut root| {
+        let mut root = root.downcast::<Flex>();
+        root.child_mut(0).unwrap().ctx.set_disabled(false);
+    });
+    assert!(!harness.get_widget(button_id).state().is_disabled());
+
+    harness.mouse_click_on(button_id);
+    assert_eq!(
+        harness.pop_action(),
+        Some((Action::ButtonPressed, button_id))
+    );
+}
+
+#[test]
+fn widget_added_to_disabled_container() {
+    let [container_id, button_id] = widget_ids();
+    let container = WidgetPod::new_with_id(Flex::column(), container_id).with_disabled(true);
+    let mut harness = TestHarness::create(Flex::row().with_child_pod(container.boxed()));
+    assert!(harness.get_widget(container_id).state().is_disabled());
+
+    harness.edit_root_widget(|mut root| {
+        let mut root = root.downcast::<Flex>();
+        let mut container = root.child_mut(0).unwrap();
+        let mut container = container.downcast::<Flex>();
+        container.add_child_id(Button::new("Press me"), button_id);
+    });
+    assert!(harness.get_widget(button_id).state().is_disabled());
+
+    harness.mouse_click_on(button_id);
+    assert_eq!(harness.pop_action(), None);
+}
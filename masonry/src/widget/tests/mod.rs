@@ -3,9 +3,18 @@
 
 // TODO - See https://github.com/PoignardAzur/masonry-rs/issues/58
 
+mod accessibility;
+mod anim_frame;
+mod badge;
+mod frame_budget;
 mod layout;
+mod layout_direction;
 mod lifecycle_basic;
 mod lifecycle_disable;
 mod lifecycle_focus;
+mod paint_cache;
+mod paint_damage;
 mod safety_rails;
 mod status_change;
+mod text_style;
+mod zoom;
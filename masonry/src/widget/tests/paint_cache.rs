@@ -0,0 +1,89 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests related to widgets keeping their painted scene.
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+use crate::testing::{widget_ids, ModularWidget, TestHarness};
+use crate::widget::{Flex, SizedBox};
+use crate::*;
+
+/// A widget of the given size counting its paints, with a cached scene if `cached` is set.
+fn counted_paints(paints: Rc<Cell<u32>>, size: Rc<Cell<Size>>, cached: bool) -> impl Widget {
+    ModularWidget::new((paints, size))
+        .lifecycle_fn(move |_, ctx, event| {
+            if let LifeCycle::WidgetAdded = event {
+                ctx.set_paint_cached(cached);
+            }
+        })
+        .layout_fn(|(_, size), _, _| size.get())
+        .paint_fn(|(paints, _), _, _| paints.set(paints.get() + 1))
+}
+
+/// Change the width of the box at index 0 of the root flex, which lays out its siblings.
+fn resize_sibling(harness: &mut TestHarness, width: f64) {
+    harness.edit_root_widget(|mut root| {
+        let mut flex = root.downcast::<Flex>();
+        let mut child = flex.child_mut(0).unwrap();
+        child.downcast::<SizedBox>().set_width(width);
+    });
+}
+
+#[test]
+fn cached_scene_survives_layout() {
+    let cached_paints = Rc::new(Cell::new(0));
+    let uncached_paints = Rc::new(Cell::new(0));
+    let size = Rc::new(Cell::new(Size::new(50., 50.)));
+    let widget = Flex::row()
+        .with_child(SizedBox::empty().width(10.).height(10.))
+        .with_child(counted_paints(cached_paints.clone(), size.clone(), true))
+        .with_child(counted_paints(uncached_paints.clone(), size, false));
+    let mut harness = TestHarness::create_with_size(widget, Size::new(400., 400.));
+    harness.paint_damage();
+    assert_eq!(cached_paints.get(), 1);
+    assert_eq!(uncached_paints.get(), 1);
+
+    // The widgets move and are laid out again over several frames.
+    for width in [20., 30., 40.] {
+        resize_sibling(&mut harness, width);
+        harness.paint_damage();
+    }
+    assert_eq!(cached_paints.get(), 1);
+    assert_eq!(uncached_paints.get(), 4);
+}
+
+#[test]
+fn cached_scene_is_invalidated() {
+    let [cached_id] = widget_ids();
+    let paints = Rc::new(Cell::new(0));
+    let size = Rc::new(Cell::new(Size::new(50., 50.)));
+    let widget = Flex::row()
+        .with_child(SizedBox::empty().width(10.).height(10.))
+        .with_child_id(
+            counted_paints(paints.clone(), size.clone(), true),
+            cached_id,
+        );
+    let mut harness = TestHarness::create_with_size(widget, Size::new(400., 400.));
+    harness.paint_damage();
+    assert_eq!(paints.get(), 1);
+
+    // A paint request, here from a new badge.
+    harness.edit_root_widget(|mut root| {
+        let mut flex = root.downcast::<Flex>();
+        flex.child_mut(1).unwrap().set_badge(Some(Badge::dot()));
+    });
+    harness.paint_damage();
+    assert_eq!(paints.get(), 2);
+
+    // A new size, found when a sibling change lays the widget out again.
+    size.set(Size::new(80., 80.));
+    resize_sibling(&mut harness, 20.);
+    harness.paint_damage();
+    assert_eq!(paints.get(), 3);
+    assert_eq!(
+        harness.get_widget(cached_id).state().layout_rect().size(),
+        Size::new(80., 80.)
+    );
+}
@@ -0,0 +1,95 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::testing::{widget_ids, TestHarness};
+use crate::widget::{Flex, SizedBox};
+use crate::*;
+
+fn square() -> SizedBox {
+    SizedBox::empty().width(50.).height(50.)
+}
+
+#[test]
+fn background_change_only_damages_widget() {
+    let [box_1, box_2] = widget_ids();
+    let widget = Flex::row()
+        .with_child_id(square(), box_1)
+        .with_child_id(square(), box_2);
+    let mut harness = TestHarness::create_with_size(widget, Size::new(400., 400.));
+
+    // The first paint damages every widget.
+    let damage = harness.paint_damage();
+    let box_1_rect = harness.get_widget(box_1).state().window_layout_rect();
+    assert!(damage
+        .iter()
+        .any(|damaged| damaged.union(box_1_rect) == *damaged));
+
+    harness.edit_root_widget(|mut root| {
+        let mut flex = root.downcast::<Flex>();
+        let mut child = flex.child_mut(1).unwrap();
+        let mut sized_box = child.downcast::<SizedBox>();
+        sized_box.set_background(Color::RED);
+    });
+    let box_2_rect = harness.get_widget(box_2).state().window_layout_rect();
+    assert_eq!(harness.paint_damage(), vec![box_2_rect]);
+
+    // Nothing changed since the last paint.
+    assert_eq!(harness.paint_damage(), vec![]);
+}
+
+#[test]
+fn moved_widget_damages_old_and_new_area() {
+    let [box_1, box_2] = widget_ids();
+    let widget = Flex::row()
+        .with_child_id(square(), box_1)
+        .with_child_id(square(), box_2);
+    let mut harness = TestHarness::create_with_size(widget, Size::new(400., 400.));
+    harness.paint_damage();
+
+    let old_rect = harness.get_widget(box_2).state().window_layout_rect();
+    harness.edit_root_widget(|mut root| {
+        let mut flex = root.downcast::<Flex>();
+        let mut child = flex.child_mut(0).unwrap();
+        let mut sized_box = child.downcast::<SizedBox>();
+        sized_box.set_width(100.);
+    });
+    let new_rect = harness.get_widget(box_2).state().window_layout_rect();
+    assert_ne!(old_rect, new_rect);
+
+    let damage = harness.paint_damage();
+    for rect in [old_rect, new_rect] {
+        assert!(
+            damage.iter().any(|damaged| damaged.union(rect) == *damaged),
+            "{rect:?} isn't covered by {damage:?}"
+        );
+    }
+}
+
+#[test]
+fn incremental_render_matches_full_render() {
+    let [box_1, box_2] = widget_ids();
+    let widget = Flex::row()
+        .with_child_id(square().background(Color::BLUE), box_1)
+        .with_child_id(square().background(Color::BLUE), box_2);
+    let mut harness = TestHarness::create_with_size(widget, Size::new(200., 100.));
+    harness.render_incremental();
+
+    harness.edit_root_widget(|mut root| {
+        let mut flex = root.downcast::<Flex>();
+        let mut child = flex.child_mut(1).unwrap();
+        let mut sized_box = child.downcast::<SizedBox>();
+        sized_box.set_background(Color::RED);
+    });
+    let incremental = harness.render_incremental();
+    assert!(incremental == harness.render());
+
+    // The second box moves, leaving its old area to the background.
+    harness.edit_root_widget(|mut root| {
+        let mut flex = root.downcast::<Flex>();
+        let mut child = flex.child_mut(0).unwrap();
+        let mut sized_box = child.downcast::<SizedBox>();
+        sized_box.set_width(120.);
+    });
+    let incremental = harness.render_incremental();
+    assert!(incremental == harness.render());
+}
@@ -1,11 +1,14 @@
 // Copyright 2022 the Xilem Authors
 // SPDX-License-Identifier: Apache-2.0
 
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
 use smallvec::smallvec;
 
-use crate::testing::{ModularWidget, TestHarness};
+use crate::testing::{widget_ids, ModularWidget, TestHarness};
 use crate::widget::Flex;
-use crate::{LifeCycle, Point, Size, Widget, WidgetPod};
+use crate::widget::WidgetMut;
+use crate::{Action, EventCtx, LifeCycle, Point, PointerEvent, Size, Widget, WidgetId, WidgetPod};
 
 fn make_parent_widget<W: Widget>(child: W) -> ModularWidget<WidgetPod<W>> {
     let child = WidgetPod::new(child);
@@ -204,6 +207,60 @@ fn allow_non_recurse_stashed_paint() {
 
 // ---
 
+/// A parent of rows which removes its last row on each pointer event, calling
+/// `ctx.children_changed()` only if `notify` is true.
+fn shrinking_parent(notify: bool) -> ModularWidget<Vec<WidgetPod<Flex>>> {
+    let rows = vec![WidgetPod::new(Flex::row()), WidgetPod::new(Flex::row())];
+    ModularWidget::new(rows)
+        .pointer_event_fn(move |rows, ctx, event| {
+            for row in rows.iter_mut() {
+                row.on_pointer_event(ctx, event);
+            }
+            if rows.pop().is_some() && notify {
+                ctx.children_changed();
+            }
+        })
+        .lifecycle_fn(|rows, ctx, event| {
+            for row in rows.iter_mut() {
+                row.lifecycle(ctx, event);
+            }
+        })
+        .layout_fn(|rows, ctx, bc| {
+            for row in rows.iter_mut() {
+                row.layout(ctx, bc);
+                ctx.place_child(row, Point::ZERO);
+            }
+            bc.max()
+        })
+        .children_fn(|rows| rows.iter().map(|row| row.as_dyn()).collect())
+}
+
+#[should_panic(expected = "children changed in method on_pointer_event")]
+#[test]
+fn check_forget_children_changed_in_pointer_event() {
+    let mut harness = TestHarness::create(shrinking_parent(false));
+    harness.mouse_move(Point::ZERO);
+}
+
+#[test]
+fn children_changed_refreshes_cached_children() {
+    let id = WidgetId::next();
+    let widget = crate::widget::SizedBox::new_with_id(shrinking_parent(true), id);
+    let mut harness = TestHarness::create(widget);
+    let parent = harness.get_widget(id).children()[0];
+    let rows: Vec<_> = parent.children().iter().map(|row| row.id()).collect();
+
+    // The last event doesn't remove anything, so its checks compare the children
+    // with the ones cached after the second row was removed.
+    harness.mouse_move(Point::ZERO);
+    harness.mouse_move(Point::new(1.0, 1.0));
+    harness.mouse_move(Point::new(2.0, 2.0));
+
+    for row in rows {
+        assert!(harness.try_get_widget(row).is_none());
+    }
+}
+
 #[cfg(FALSE)]
 #[should_panic(expected = "children changed")]
 #[test]
@@ -367,3 +424,97 @@ fn check_paint_rect_includes_children() {
     harness.mouse_move(Point::ZERO);
     harness.render();
 }
+
+#[test]
+fn defer_removal_of_action_source() {
+    let [button_id] = widget_ids();
+    let button = ModularWidget::new(()).pointer_event_fn(|_, ctx, event| {
+        if let PointerEvent::PointerDown(..) = event {
+            ctx.submit_action(Action::ButtonPressed);
+            // A widget can't remove itself while handling an event, so we queue the removal.
+            ctx.defer(|root| {
+                root.downcast::<Flex>().remove_child(0);
+            });
+        }
+    });
+    let widget = Flex::column().with_child_id(button, button_id);
+
+    let mut harness = TestHarness::create(widget);
+    harness.mouse_click_on(button_id);
+
+    assert_eq!(
+        harness.pop_action(),
+        Some((Action::ButtonPressed, button_id))
+    );
+    assert!(harness.try_get_widget(button_id).is_none());
+}
+
+/// A button which submits an action when pressed, and runs `on_press` with its context.
+fn pressable(on_press: impl Fn(&mut EventCtx) + 'static) -> ModularWidget<()> {
+    ModularWidget::new(()).pointer_event_fn(move |_, ctx, event| {
+        if let PointerEvent::PointerDown(..) = event {
+            ctx.submit_action(Action::ButtonPressed);
+            on_press(ctx);
+        }
+    })
+}
+
+/// Remove the child of `flex` with the given id, looking up its index in the current tree.
+fn remove_child_with_id(flex: &mut WidgetMut<'_, Flex>, id: WidgetId) {
+    let idx = (0..flex.widget.len())
+        .find(|&idx| {
+            flex.child_mut(idx)
+                .is_some_and(|child| child.ctx.widget_id() == id)
+        })
+        .unwrap();
+    flex.remove_child(idx);
+}
+
+#[test]
+fn direct_removal_during_pass_panics_on_shifted_index() {
+    let [first_id, second_id] = widget_ids();
+    let rows = Flex::column()
+        .with_child_id(pressable(|_| {}), first_id)
+        .with_child_id(pressable(|_| {}), second_id);
+    // Pressing a row removes it and the row below it, by their indices when the press started.
+    let widget = make_parent_widget(rows).pointer_event_fn(|rows, ctx, event| {
+        rows.on_pointer_event(ctx, event);
+        if let PointerEvent::PointerDown(..) = event {
+            let mut rows = ctx.get_mut(rows);
+            rows.remove_child(0);
+            rows.remove_child(1);
+        }
+    });
+
+    let mut harness = TestHarness::create(widget);
+    let result = catch_unwind(AssertUnwindSafe(|| harness.mouse_click_on(first_id)));
+    let message = *result.unwrap_err().downcast::<String>().unwrap();
+    assert_eq!(
+        message,
+        "Flex::remove_child: index 1 is out of bounds for Flex with 1 children (including spacers)"
+    );
+}
+
+#[test]
+fn deferred_removal_after_pass_succeeds() {
+    let [first_id, second_id] = widget_ids();
+    // The same removals as above, queued by the pressed row itself.
+    let first = pressable(move |ctx| {
+        for id in [first_id, second_id] {
+            ctx.defer(move |root| remove_child_with_id(&mut root.downcast(), id));
+        }
+    });
+    let widget = Flex::column()
+        .with_child_id(first, first_id)
+        .with_child_id(pressable(|_| {}), second_id);
+
+    let mut harness = TestHarness::create(widget);
+    harness.mouse_click_on(first_id);
+
+    assert_eq!(
+        harness.pop_action(),
+        Some((Action::ButtonPressed, first_id))
+    );
+    assert!(harness.try_get_widget(first_id).is_none());
+    assert!(harness.try_get_widget(second_id).is_none());
+}
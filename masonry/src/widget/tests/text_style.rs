@@ -0,0 +1,144 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests related to text styles inherited from ancestors.
+
+use image::RgbaImage;
+
+use crate::testing::{widget_ids, TestHarness};
+use crate::widget::{Flex, Label};
+use crate::*;
+
+const WINDOW_SIZE: Size = Size::new(200., 200.);
+
+fn render(widget: impl Widget) -> RgbaImage {
+    TestHarness::create_with_size(widget, WINDOW_SIZE).render()
+}
+
+fn set_text_style(harness: &mut TestHarness, id: WidgetId, text_style: TextStyle) {
+    harness.edit_root_widget(|mut root| {
+        let mut flex = root.downcast::<Flex>();
+        let mut child = flex.child_mut(0).unwrap();
+        assert_eq!(child.ctx.widget_id(), id);
+        child.set_text_style(text_style);
+    });
+}
+
+#[test]
+fn color_is_inherited() {
+    let [form_id] = widget_ids();
+    let form = Flex::column()
+        .with_child(Label::new("Name"))
+        .with_child(Label::new("Address"));
+    let mut harness =
+        TestHarness::create_with_size(Flex::column().with_child_id(form, form_id), WINDOW_SIZE);
+    set_text_style(
+        &mut harness,
+        form_id,
+        TextStyle::default().with_color(Color::RED),
+    );
+
+    let expected = render(
+        Flex::column().with_child(
+            Flex::column()
+                .with_child(Label::new("Name").with_text_brush(Color::RED))
+                .with_child(Label::new("Address").with_text_brush(Color::RED)),
+        ),
+    );
+    assert!(harness.render() == expected);
+}
+
+#[test]
+fn own_style_wins() {
+    let [form_id, section_id, label_id] = widget_ids();
+    let section = Flex::column().with_child_id(Label::new("Street"), label_id);
+    let form = Flex::column()
+        .with_child(Label::new("Name").with_text_brush(Color::BLUE))
+        .with_child_id(section, section_id);
+    let mut harness =
+        TestHarness::create_with_size(Flex::column().with_child_id(form, form_id), WINDOW_SIZE);
+    harness.edit_root_widget(|mut root| {
+        let mut flex = root.downcast::<Flex>();
+        let mut form = flex.child_mut(0).unwrap();
+        let mut form = form.downcast::<Flex>();
+        let mut section = form.child_mut(1).unwrap();
+        section.set_text_style(TextStyle::default().with_color(Color::GREEN));
+    });
+    set_text_style(
+        &mut harness,
+        form_id,
+        TextStyle::default().with_color(Color::RED).with_size(20.),
+    );
+
+    let section_style = &harness.get_widget(section_id).state().resolved_text_style;
+    assert_eq!(section_style.color, Color::GREEN);
+    assert_eq!(section_style.size, 20.);
+    let label_style = &harness.get_widget(label_id).state().resolved_text_style;
+    assert_eq!(label_style.color, Color::GREEN);
+
+    // The label setting its own color keeps it.
+    let expected = render(
+        Flex::column().with_child(
+            Flex::column()
+                .with_child(
+                    Label::new("Name")
+                        .with_text_brush(Color::BLUE)
+                        .with_text_size(20.),
+                )
+                .with_child(
+                    Flex::column().with_child(
+                        Label::new("Street")
+                            .with_text_brush(Color::GREEN)
+                            .with_text_size(20.),
+                    ),
+                ),
+        ),
+    );
+    assert!(harness.render() == expected);
+}
+
+#[test]
+fn size_change_only_lays_out_affected_widgets() {
+    let [form_id] = widget_ids();
+    let form = Flex::column()
+        .with_child(Label::new("Name"))
+        .with_child(Label::new("Title").with_text_size(30.));
+    let sidebar = Flex::column()
+        .with_child(Label::new("Home"))
+        .with_child(Label::new("Settings"));
+    let mut harness = TestHarness::create_with_size(
+        Flex::row().with_child_id(form, form_id).with_child(sidebar),
+        WINDOW_SIZE,
+    );
+    let passes = harness.layout_stats().passes;
+
+    set_text_style(&mut harness, form_id, TextStyle::default().with_size(20.));
+
+    // Only the "Name" label, the form and the root are laid out because of the change:
+    // the title sets its own size, and the sidebar is unaffected.
+    let stats = harness.layout_stats();
+    assert_eq!(stats.passes, passes + 1);
+    assert_eq!(stats.last_requested_widgets, 3);
+}
+
+#[test]
+fn removed_style_falls_back_to_theme() {
+    let [form_id, label_id] = widget_ids();
+    let form = Flex::column().with_child_id(Label::new("Name"), label_id);
+    let mut harness =
+        TestHarness::create_with_size(Flex::column().with_child_id(form, form_id), WINDOW_SIZE);
+    set_text_style(
+        &mut harness,
+        form_id,
+        TextStyle::default().with_color(Color::RED).with_size(20.),
+    );
+    set_text_style(&mut harness, form_id, TextStyle::default());
+
+    let label_style = &harness.get_widget(label_id).state().resolved_text_style;
+    assert_eq!(label_style, &ResolvedTextStyle::default());
+    assert_eq!(label_style.color, theme::TEXT_COLOR);
+    assert_eq!(label_style.size, theme::TEXT_SIZE_NORMAL as f32);
+
+    let expected = render(Flex::column().with_child(Flex::column().with_child(Label::new("Name"))));
+    assert!(harness.render() == expected);
+}
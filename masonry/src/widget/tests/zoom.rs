@@ -0,0 +1,101 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tests related to the zoom factor of the window.
+
+use image::RgbaImage;
+
+use crate::kurbo::{Rect, Size};
+use crate::testing::{widget_ids, TestHarness};
+use crate::widget::{Button, Flex, SizedBox};
+use crate::{Action, Color};
+
+fn boxes(width: f64, height: f64) -> SizedBox {
+    SizedBox::empty()
+        .width(width)
+        .height(height)
+        .background(Color::rgb8(0x20, 0x60, 0xd0))
+        .border(Color::WHITE, width / 10.0)
+        .rounded(width / 5.0)
+}
+
+#[test]
+fn zoom_scales_window_rects() {
+    let [box_id] = widget_ids();
+    let widget = Flex::column().with_child_id(boxes(40.0, 30.0), box_id);
+    let mut harness = TestHarness::create_with_size(widget, Size::new(300.0, 300.0));
+
+    harness.set_zoom(1.5);
+    // The window is 200x200 in layout coordinates, and the box is centered in it.
+    let rect = harness.get_widget(box_id).state().window_layout_rect();
+    assert_eq!(rect, Rect::new(80.0, 0.0, 120.0, 30.0));
+    // Which is rendered at 1.5 times that.
+    assert_eq!(rect.scale_from_origin(1.5).size(), Size::new(60.0, 45.0));
+
+    // The zoom is clamped to 5, so the window is 60x60.
+    harness.set_zoom(100.0);
+    let rect = harness.get_widget(box_id).state().window_layout_rect();
+    assert_eq!(rect, Rect::new(10.0, 0.0, 50.0, 30.0));
+}
+
+#[test]
+fn click_button_at_zoomed_position() {
+    let [button_id] = widget_ids();
+    let widget = Flex::column()
+        .with_flex_spacer(1.0)
+        .with_child_id(Button::new("Zoomed"), button_id);
+    let mut harness = TestHarness::create_with_size(widget, Size::new(300.0, 300.0));
+    harness.set_zoom(1.5);
+
+    let rect = harness.get_widget(button_id).state().window_layout_rect();
+    // The unzoomed position of the button is empty.
+    harness.mouse_move(rect.center());
+    harness.mouse_button_press(winit::event::MouseButton::Left);
+    harness.mouse_button_release(winit::event::MouseButton::Left);
+    assert_eq!(harness.pop_action(), None);
+
+    harness.mouse_click_on(button_id);
+    assert_eq!(
+        harness.pop_action(),
+        Some((Action::ButtonPressed, button_id))
+    );
+}
+
+fn assert_images_close(image: &RgbaImage, reference: &RgbaImage) {
+    assert_eq!(image.dimensions(), reference.dimensions());
+    let different_pixels = image
+        .pixels()
+        .zip(reference.pixels())
+        .filter(|(pixel, ref_pixel)| {
+            pixel
+                .0
+                .iter()
+                .zip(ref_pixel.0)
+                .any(|(channel, ref_channel)| channel.abs_diff(ref_channel) > 8)
+        })
+        .count();
+    // Antialiased edges may differ slightly.
+    let max_different_pixels = (image.width() * image.height()) as usize / 100;
+    assert!(
+        different_pixels <= max_different_pixels,
+        "{different_pixels} pixels differ"
+    );
+}
+
+#[test]
+fn render_zoomed_matches_double_size() {
+    let mut harness = TestHarness::create_with_size(
+        Flex::column().with_child(boxes(40.0, 30.0)),
+        Size::new(200.0, 200.0),
+    );
+    harness.set_zoom(2.0);
+    let zoomed = harness.render();
+
+    let mut harness = TestHarness::create_with_size(
+        Flex::column().with_child(boxes(80.0, 60.0)),
+        Size::new(200.0, 200.0),
+    );
+    let reference = harness.render();
+
+    assert_images_close(&zoomed, &reference);
+}
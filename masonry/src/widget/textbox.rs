@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use accesskit::Role;
-use kurbo::{Affine, Point, Size, Stroke};
+use kurbo::{Affine, Point, Size, Stroke, Vec2};
 use parley::{
     layout::Alignment,
     style::{FontFamily, FontStack},
@@ -15,9 +15,12 @@ use vello::{
 };
 
 use crate::{
-    text2::{TextBrush, TextEditor, TextStorage, TextWithSelection},
+    paint_scene_helpers::fill_color,
+    text2::{LineHeight, TextBrush, TextEditor, TextStorage, TextWithSelection},
+    text_style::TextStyleOverrides,
     AccessCtx, AccessEvent, BoxConstraints, CursorIcon, EventCtx, LayoutCtx, LifeCycle,
-    LifeCycleCtx, PaintCtx, PointerEvent, StatusChange, TextEvent, Widget,
+    LifeCycleCtx, PaintCtx, PointerEvent, StatusChange, TextEvent, TextboxVariant,
+    ValidationAdornment, ValidationState, Widget,
 };
 
 use super::{LineBreaking, WidgetMut, WidgetRef};
@@ -36,6 +39,11 @@ const TEXTBOX_MARGIN: f64 = 8.0;
 /// The textbox widget is a widget which shows text which can be edited by the user
 ///
 /// For immutable text [`Prose`](super::Prose) should be preferred
+///
+/// A textbox with a [`TextboxVariant`] is painted with the colors the
+/// [`Theme`](crate::Theme) defines for it, and is restyled when the theme changes.
+///
+/// Its [`ValidationState`] is shown with a [`ValidationAdornment`].
 // TODO: RichTextBox 👀
 pub struct Textbox {
     // We hardcode the underlying storage type as `String`.
@@ -45,7 +53,9 @@ pub struct Textbox {
     editor: TextEditor<String>,
     line_break_mode: LineBreaking,
     show_disabled: bool,
-    brush: TextBrush,
+    style: TextStyleOverrides,
+    variant: Option<TextboxVariant>,
+    validation: ValidationAdornment,
 }
 
 impl Textbox {
@@ -54,7 +64,9 @@ impl Textbox {
             editor: TextEditor::new(initial_text.into(), crate::theme::TEXT_SIZE_NORMAL as f32),
             line_break_mode: LineBreaking::WordWrap,
             show_disabled: true,
-            brush: crate::theme::TEXT_COLOR.into(),
+            style: TextStyleOverrides::default(),
+            variant: None,
+            validation: ValidationAdornment::new(),
         }
     }
 
@@ -65,12 +77,14 @@ impl Textbox {
 
     #[doc(alias = "with_text_color")]
     pub fn with_text_brush(mut self, brush: impl Into<TextBrush>) -> Self {
-        self.brush = brush.into();
-        self.editor.set_brush(self.brush.clone());
+        let brush = brush.into();
+        self.editor.set_brush(brush.clone());
+        self.style.brush = Some(brush);
         self
     }
 
     pub fn with_text_size(mut self, size: f32) -> Self {
+        self.style.size = Some(size);
         self.editor.set_text_size(size);
         self
     }
@@ -81,6 +95,7 @@ impl Textbox {
     }
 
     pub fn with_font(mut self, font: FontStack<'static>) -> Self {
+        self.style.font = Some(font);
         self.editor.set_font(font);
         self
     }
@@ -88,10 +103,36 @@ impl Textbox {
         self.with_font(FontStack::Single(font))
     }
 
+    pub fn with_line_height(mut self, line_height: LineHeight) -> Self {
+        self.editor.set_line_height(line_height);
+        self
+    }
+
+    pub fn with_letter_spacing(mut self, letter_spacing: f32) -> Self {
+        self.editor.set_letter_spacing(letter_spacing);
+        self
+    }
+
     pub fn with_line_break_mode(mut self, line_break_mode: LineBreaking) -> Self {
         self.line_break_mode = line_break_mode;
         self
     }
+
+    /// Builder-style method for setting the [`TextboxVariant`].
+    pub fn with_variant(mut self, variant: TextboxVariant) -> Self {
+        self.variant = Some(variant);
+        self
+    }
+
+    /// Builder-style method for setting the [`ValidationState`].
+    pub fn with_validation_state(mut self, state: ValidationState) -> Self {
+        self.validation.set_state(state);
+        self
+    }
+
+    pub fn validation_state(&self) -> &ValidationState {
+        self.validation.state()
+    }
 }
 
 impl WidgetMut<'_, Textbox> {
@@ -127,31 +168,74 @@ impl WidgetMut<'_, Textbox> {
         self.set_text_properties(|layout| layout.set_text(new_text));
     }
 
+    /// Replace the contents of the text box, placing the cursor at the end.
+    ///
+    /// Unlike [`reset_text`](Self::reset_text), this is meant to be used while the user
+    /// is editing the text, e.g. to accept a completion.
+    pub fn replace_text(&mut self, new_text: String) {
+        self.widget.editor.replace_text(new_text);
+        self.ctx.request_layout();
+    }
+
+    /// Apply the inherited text style, and the textbox's own, to the text.
+    fn apply_text_style(&mut self) {
+        let disabled = self.widget.show_disabled && self.ctx.is_disabled();
+        let textbox = &mut *self.widget;
+        if textbox
+            .style
+            .apply(&mut **textbox.editor, self.ctx.text_style(), disabled)
+        {
+            self.ctx.request_layout();
+        }
+    }
+
     #[doc(alias = "set_text_color")]
     pub fn set_text_brush(&mut self, brush: impl Into<TextBrush>) {
-        let brush = brush.into();
-        self.widget.brush = brush;
-        if !self.ctx.is_disabled() {
-            let brush = self.widget.brush.clone();
-            self.set_text_properties(|layout| layout.set_brush(brush));
-        }
+        self.widget.style.brush = Some(brush.into());
+        self.apply_text_style();
     }
     pub fn set_text_size(&mut self, size: f32) {
-        self.set_text_properties(|layout| layout.set_text_size(size));
+        self.widget.style.size = Some(size);
+        self.apply_text_style();
     }
     pub fn set_alignment(&mut self, alignment: Alignment) {
         self.set_text_properties(|layout| layout.set_text_alignment(alignment));
     }
     pub fn set_font(&mut self, font_stack: FontStack<'static>) {
-        self.set_text_properties(|layout| layout.set_font(font_stack));
+        self.widget.style.font = Some(font_stack);
+        self.apply_text_style();
     }
     pub fn set_font_family(&mut self, family: FontFamily<'static>) {
         self.set_font(FontStack::Single(family));
     }
+    pub fn set_line_height(&mut self, line_height: LineHeight) {
+        self.set_text_properties(|layout| layout.set_line_height(line_height));
+    }
+    pub fn set_letter_spacing(&mut self, letter_spacing: f32) {
+        self.set_text_properties(|layout| layout.set_letter_spacing(letter_spacing));
+    }
     pub fn set_line_break_mode(&mut self, line_break_mode: LineBreaking) {
         self.widget.line_break_mode = line_break_mode;
         self.ctx.request_paint();
     }
+
+    /// Set the [`TextboxVariant`], or go back to the default look with `None`.
+    pub fn set_variant(&mut self, variant: Option<TextboxVariant>) {
+        if self.widget.variant != variant {
+            self.widget.variant = variant;
+            self.ctx.request_paint();
+        }
+    }
+
+    /// Set the [`ValidationState`].
+    ///
+    /// This only repaints the textbox, it doesn't change its layout.
+    pub fn set_validation_state(&mut self, state: ValidationState) {
+        if self.widget.validation.set_state(state) {
+            self.ctx.request_paint();
+            self.ctx.request_accessibility_update();
+        }
+    }
 }
 
 impl Widget for Textbox {
@@ -229,22 +313,34 @@ impl Widget for Textbox {
             }
             _ => {}
         }
+        if self.variant.is_some() || *self.validation.state() != ValidationState::Valid {
+            // The colors of variants and validation states depend on the hovered and
+            // focused states.
+            ctx.request_paint();
+        }
     }
 
     fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle) {
         match event {
-            LifeCycle::DisabledChanged(disabled) => {
-                if self.show_disabled {
-                    if *disabled {
-                        self.editor.set_brush(crate::theme::DISABLED_TEXT_COLOR);
-                    } else {
-                        self.editor.set_brush(self.brush.clone());
-                    }
-                }
+            LifeCycle::WidgetAdded
+            | LifeCycle::TextStyleChanged
+            | LifeCycle::DisabledChanged(_) => {
+                let disabled = self.show_disabled && ctx.is_disabled();
                 // TODO: Parley seems to require a relayout when colours change
-                ctx.request_layout();
+                if self
+                    .style
+                    .apply(&mut **self.editor, ctx.text_style(), disabled)
+                {
+                    ctx.request_layout();
+                }
+            }
+            LifeCycle::ThemeChanged => {
+                if self.variant.is_some() {
+                    ctx.request_paint();
+                }
             }
             LifeCycle::BuildFocusChain => {
+                ctx.register_for_focus();
                 // TODO: This will always be empty
                 if !self.editor.text().links().is_empty() {
                     tracing::warn!("Links present in text, but not yet integrated");
@@ -269,6 +365,8 @@ impl Widget for Textbox {
         if self.editor.needs_rebuild() {
             self.editor.rebuild(ctx.font_ctx());
         }
+        let ime_area = self.editor.ime_area() + Vec2::new(TEXTBOX_PADDING, TEXTBOX_PADDING);
+        ctx.set_ime_area(Some(ime_area));
         // We ignore trailing whitespace for a label
         let text_size = self.editor.size();
         let label_size = Size {
@@ -295,17 +393,31 @@ impl Widget for Textbox {
             scene.push_layer(BlendMode::default(), 1., Affine::IDENTITY, &clip_rect);
         }
 
+        let outline_rect = ctx.size().to_rect().inset(1.0);
+        let outline_color = if let Some(variant) = &self.variant {
+            let style = ctx.theme().textbox_style(variant);
+            let (hovered, focused, disabled) = (ctx.is_hot(), ctx.is_focused(), ctx.is_disabled());
+            fill_color(
+                scene,
+                &ctx.size().to_rect(),
+                style.background.get(hovered, focused, disabled),
+            );
+            style.border.get(hovered, focused, disabled)
+        } else {
+            Color::WHITE
+        };
+
         self.editor
             .draw(scene, Point::new(TEXTBOX_PADDING, TEXTBOX_PADDING));
 
-        let outline_rect = ctx.size().to_rect().inset(1.0);
         scene.stroke(
             &Stroke::new(1.0),
             Affine::IDENTITY,
-            Color::WHITE,
+            outline_color,
             None,
             &outline_rect,
         );
+        self.validation.paint(ctx, scene, &outline_rect, 1.0);
         if self.line_break_mode == LineBreaking::Clip {
             scene.pop_layer();
         }
@@ -315,8 +427,9 @@ impl Widget for Textbox {
         Role::TextInput
     }
 
-    fn accessibility(&mut self, _ctx: &mut AccessCtx) {
+    fn accessibility(&mut self, ctx: &mut AccessCtx) {
         // TODO
+        self.validation.accessibility(ctx);
     }
 
     fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
@@ -327,3 +440,206 @@ impl Widget for Textbox {
         Some(self.editor.text().as_str().chars().take(100).collect())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use accesskit::{Invalid, NodeId};
+    use image::Rgba;
+    use winit::event::Ime;
+
+    use super::*;
+    use crate::assert_render_snapshot;
+    use crate::render_root::RenderRootSignal;
+    use crate::testing::{render_tests_skipped, widget_ids, TestHarness};
+    use crate::widget::Flex;
+    use crate::{theme, Action, Rect, Size, WidgetId};
+
+    fn text_changes(harness: &mut TestHarness) -> Vec<String> {
+        std::iter::from_fn(|| harness.pop_action())
+            .filter_map(|(action, _)| match action {
+                Action::TextChanged(text) => Some(text),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn ime(harness: &mut TestHarness, event: Ime) {
+        harness.process_text_event(TextEvent::Ime(event));
+    }
+
+    fn ime_area(harness: &mut TestHarness) -> Option<Rect> {
+        let mut area = None;
+        while let Some(signal) =
+            harness.pop_signal_matching(|signal| matches!(signal, RenderRootSignal::ImeMoved(_)))
+        {
+            if let RenderRootSignal::ImeMoved(rect) = signal {
+                area = Some(rect);
+            }
+        }
+        area
+    }
+
+    fn textbox_text(harness: &TestHarness, id: WidgetId) -> String {
+        let textbox = harness.get_widget(id);
+        textbox.downcast::<Textbox>().unwrap().text().to_string()
+    }
+
+    #[test]
+    fn ime_composition_is_committed() {
+        let mut harness = TestHarness::create(Textbox::new(""));
+        let id = harness.root_widget().id();
+        harness.mouse_click_on(id);
+        harness.keyboard_type_chars("a");
+        assert_eq!(text_changes(&mut harness), ["a"]);
+
+        ime(&mut harness, Ime::Enabled);
+        ime(&mut harness, Ime::Preedit("に".into(), Some((3, 3))));
+        ime(&mut harness, Ime::Preedit("にほん".into(), Some((9, 9))));
+        // The text being composed is shown, but isn't reported as a change.
+        assert_eq!(textbox_text(&harness, id), "aにほん");
+        assert!(text_changes(&mut harness).is_empty());
+
+        ime(&mut harness, Ime::Preedit(String::new(), None));
+        ime(&mut harness, Ime::Commit("日本".into()));
+        assert_eq!(textbox_text(&harness, id), "a日本");
+        assert_eq!(text_changes(&mut harness), ["a日本"]);
+
+        // Committing without clearing the preedit first replaces it too.
+        ime(&mut harness, Ime::Preedit("ご".into(), Some((3, 3))));
+        ime(&mut harness, Ime::Commit("語".into()));
+        assert_eq!(textbox_text(&harness, id), "a日本語");
+        assert_eq!(text_changes(&mut harness), ["a日本語"]);
+    }
+
+    #[test]
+    fn unfinished_composition_is_dropped_on_focus_loss() {
+        let [name_id, address_id] = widget_ids();
+        let form = Flex::column()
+            .with_child_id(Textbox::new(""), name_id)
+            .with_child_id(Textbox::new(""), address_id);
+        let mut harness = TestHarness::create(form);
+        harness.mouse_click_on(name_id);
+        ime(&mut harness, Ime::Preedit("に".into(), Some((3, 3))));
+        assert_eq!(textbox_text(&harness, name_id), "に");
+
+        harness.mouse_click_on(address_id);
+        assert_eq!(textbox_text(&harness, name_id), "");
+        assert!(text_changes(&mut harness).is_empty());
+    }
+
+    #[test]
+    fn ime_area_follows_the_caret() {
+        let mut harness = TestHarness::create(Textbox::new(""));
+        assert_eq!(ime_area(&mut harness), None);
+
+        let id = harness.root_widget().id();
+        harness.mouse_click_on(id);
+        assert!(harness
+            .pop_signal_matching(|signal| matches!(signal, RenderRootSignal::ImeStarted))
+            .is_some());
+        let caret_area = ime_area(&mut harness).unwrap();
+        let textbox_rect = harness.root_widget().state().window_layout_rect();
+        assert!(textbox_rect.contains(caret_area.origin()));
+
+        ime(&mut harness, Ime::Preedit("にほん".into(), Some((9, 9))));
+        let preedit_area = ime_area(&mut harness).unwrap();
+        assert_eq!(preedit_area.x0, caret_area.x0);
+        assert!(preedit_area.width() > caret_area.width());
+
+        harness.edit_root_widget(|mut textbox| textbox.ctx.set_disabled(true));
+        assert!(harness
+            .pop_signal_matching(|signal| matches!(signal, RenderRootSignal::ImeInvalidated))
+            .is_some());
+    }
+
+    fn invalid(message: &str) -> ValidationState {
+        ValidationState::Invalid {
+            message: message.into(),
+        }
+    }
+
+    /// The color in the middle of the validation icon, beside its exclamation mark.
+    fn icon_color(harness: &mut TestHarness) -> Color {
+        let rect = harness.root_widget().state().window_layout_rect();
+        let radius = theme::VALIDATION_ICON_SIZE / 2.0;
+        let x = rect.x1 - 4.0 - radius / 2.0;
+        let y = rect.y0 + 4.0 + radius;
+        let Rgba([r, g, b, a]) = *harness.render().get_pixel(x as u32, y as u32);
+        Color::rgba8(r, g, b, a)
+    }
+
+    #[test]
+    fn validation_states_are_painted() {
+        if render_tests_skipped() {
+            return;
+        }
+        let textbox = Textbox::new("Hello").with_validation_state(invalid("Required"));
+        let mut harness = TestHarness::create_with_size(textbox, Size::new(200.0, 40.0));
+        assert_eq!(icon_color(&mut harness), theme::ERROR_COLOR);
+
+        harness.edit_root_widget(|mut textbox| {
+            let mut textbox = textbox.downcast::<Textbox>();
+            textbox.set_validation_state(ValidationState::Warning {
+                message: "Unusual".into(),
+            });
+        });
+        assert_eq!(icon_color(&mut harness), theme::WARNING_COLOR);
+
+        harness.edit_root_widget(|mut textbox| {
+            let mut textbox = textbox.downcast::<Textbox>();
+            textbox.set_validation_state(ValidationState::Valid);
+        });
+        assert_ne!(icon_color(&mut harness), theme::WARNING_COLOR);
+    }
+
+    #[test]
+    fn validation_state_snapshots() {
+        let textbox = Textbox::new("Hello").with_validation_state(invalid("Required"));
+        let mut harness = TestHarness::create_with_size(textbox, Size::new(200.0, 40.0));
+        assert_render_snapshot!(harness, "invalid");
+
+        harness.edit_root_widget(|mut textbox| {
+            let mut textbox = textbox.downcast::<Textbox>();
+            textbox.set_validation_state(ValidationState::Warning {
+                message: "Unusual".into(),
+            });
+        });
+        assert_render_snapshot!(harness, "warning");
+    }
+
+    #[test]
+    fn error_message_is_exposed_to_accessibility() {
+        let textbox = Textbox::new("").with_validation_state(invalid("Required"));
+        let mut harness = TestHarness::create(textbox);
+        let id: NodeId = harness.root_widget().id().into();
+        let tree_update = harness.access_update();
+
+        let node = |id: NodeId| {
+            let (_, node) = tree_update
+                .nodes
+                .iter()
+                .find(|(node_id, _)| *node_id == id)
+                .expect("node wasn't emitted");
+            node
+        };
+        let textbox_node = node(id);
+        assert_eq!(textbox_node.invalid(), Some(Invalid::True));
+        let message_id = textbox_node.error_message().unwrap();
+        assert!(textbox_node.children().contains(&message_id));
+        assert_eq!(node(message_id).name(), Some("Required"));
+    }
+
+    #[test]
+    fn validation_state_change_only_repaints() {
+        let mut harness = TestHarness::create(Textbox::new("Hello"));
+        harness.render();
+        let passes = harness.layout_stats().passes;
+
+        harness.edit_root_widget(|mut textbox| {
+            let mut textbox = textbox.downcast::<Textbox>();
+            textbox.set_validation_state(invalid("Required"));
+        });
+        assert!(!harness.paint_damage().is_empty());
+        assert_eq!(harness.layout_stats().passes, passes);
+    }
+}
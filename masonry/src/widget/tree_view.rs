@@ -0,0 +1,796 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! A list of hierarchical items, whose children can be shown and hidden.
+
+use std::collections::BTreeSet;
+
+use accesskit::Role;
+use smallvec::SmallVec;
+use tracing::{trace, trace_span, Span};
+use vello::peniko::BlendMode;
+use vello::Scene;
+use winit::event::MouseButton;
+use winit::keyboard::{Key, NamedKey};
+
+use crate::kurbo::{Affine, BezPath, Rect};
+use crate::paint_scene_helpers::{fill_color, stroke};
+use crate::widget::{Label, WidgetMut, WidgetPod, WidgetRef};
+use crate::{
+    theme, AccessCtx, AccessEvent, Action, ArcStr, BoxConstraints, EventCtx, LayoutCtx, LifeCycle,
+    LifeCycleCtx, PaintCtx, Point, PointerEvent, Size, StatusChange, TextEvent, Widget,
+};
+
+const ROW_HEIGHT: f64 = theme::BORDERED_WIDGET_HEIGHT;
+/// The indentation of each level of the tree.
+const INDENT: f64 = 16.0;
+/// The width of the area holding the expansion arrow, before each label.
+const TOGGLE_WIDTH: f64 = 16.0;
+const ARROW_HALF_SIZE: f64 = 4.0;
+
+/// An item of a [`TreeView`], and its children.
+pub struct TreeNode {
+    label: ArcStr,
+    children: Vec<TreeNode>,
+    expanded: bool,
+}
+
+impl TreeNode {
+    /// Create a collapsed item without children.
+    pub fn new(label: impl Into<ArcStr>) -> Self {
+        TreeNode {
+            label: label.into(),
+            children: Vec::new(),
+            expanded: false,
+        }
+    }
+
+    /// Builder-style method to add a child.
+    pub fn with_child(mut self, child: TreeNode) -> Self {
+        self.children.push(child);
+        self
+    }
+
+    /// Builder-style method to add several children.
+    pub fn with_children(mut self, children: impl IntoIterator<Item = TreeNode>) -> Self {
+        self.children.extend(children);
+        self
+    }
+
+    /// Builder-style method to set whether the children are initially shown.
+    pub fn with_expanded(mut self, expanded: bool) -> Self {
+        self.expanded = expanded;
+        self
+    }
+}
+
+/// A node of the tree, stored in depth-first order.
+struct Node {
+    label: WidgetPod<Label>,
+    depth: usize,
+    parent: Option<usize>,
+    index_in_parent: usize,
+    /// The index following the last descendant of this node.
+    subtree_end: usize,
+    expanded: bool,
+}
+
+impl Node {
+    fn has_children(&self, index: usize) -> bool {
+        self.subtree_end > index + 1
+    }
+}
+
+/// A list of items which can have children, shown indented below them when the item
+/// is expanded.
+///
+/// Clicking the arrow before an item (or double-clicking the item) expands or collapses
+/// it, and submits [`Action::TreeNodeToggled`]. Clicking an item selects it, and
+/// submits [`Action::TreeSelectionChanged`]. When [multiple selection] is enabled,
+/// Ctrl-clicking (or Cmd-clicking) an item adds it to the selection or removes it, and
+/// Shift-clicking selects the items between the last clicked one and this one.
+///
+/// When focused, the arrow keys move the selection (Shift extends it), and expand or
+/// collapse the current item or move to its parent or first child. Enter toggles the
+/// current item.
+///
+/// Items are identified in actions by their path: the index of each of their
+/// ancestors in their parent, followed by their own.
+///
+/// All rows have the same height. Only the rows in view are laid out and painted,
+/// the others are stashed, so large trees are cheap to lay out. The tree scrolls with
+/// the mouse wheel, and to keep the current item in view.
+///
+/// Deeply nested items are indented by at most half the width of the tree.
+///
+/// [multiple selection]: TreeView::with_multi_select
+pub struct TreeView {
+    nodes: Vec<Node>,
+    /// The nodes whose ancestors are all expanded, in order.
+    rows: Vec<usize>,
+    selection: BTreeSet<usize>,
+    /// The node moved by the keyboard, which is the last clicked one.
+    cursor: Option<usize>,
+    /// The start of the range selected with Shift.
+    anchor: Option<usize>,
+    multi_select: bool,
+    scroll_offset: f64,
+    /// The nodes which aren't stashed, in order.
+    shown: Vec<usize>,
+    /// The nodes toggled by the current event, to report in actions.
+    toggled: Vec<usize>,
+}
+
+impl TreeView {
+    /// Create a tree with the given top-level items.
+    pub fn new(roots: impl IntoIterator<Item = TreeNode>) -> Self {
+        let mut nodes = Vec::new();
+        for (index, root) in roots.into_iter().enumerate() {
+            flatten(&mut nodes, root, 0, None, index);
+        }
+        let shown = (0..nodes.len()).collect();
+        let mut tree = TreeView {
+            nodes,
+            rows: Vec::new(),
+            selection: BTreeSet::new(),
+            cursor: None,
+            anchor: None,
+            multi_select: false,
+            scroll_offset: 0.0,
+            shown,
+            toggled: Vec::new(),
+        };
+        tree.update_rows();
+        tree
+    }
+
+    /// Builder-style method to allow selecting several items.
+    pub fn with_multi_select(mut self, multi_select: bool) -> Self {
+        self.multi_select = multi_select;
+        self
+    }
+
+    /// Whether the item at `path` shows its children.
+    ///
+    /// Returns `None` if there is no item at `path`.
+    pub fn is_expanded(&self, path: &[usize]) -> Option<bool> {
+        let node = self.node_at(path)?;
+        Some(self.nodes[node].expanded)
+    }
+
+    /// The paths of the selected items, in depth-first order.
+    pub fn selected_paths(&self) -> Vec<Vec<usize>> {
+        self.selection.iter().map(|&node| self.path(node)).collect()
+    }
+
+    /// The number of items whose ancestors are all expanded.
+    pub fn row_count(&self) -> usize {
+        self.rows.len()
+    }
+}
+
+fn flatten(
+    nodes: &mut Vec<Node>,
+    tree_node: TreeNode,
+    depth: usize,
+    parent: Option<usize>,
+    index_in_parent: usize,
+) {
+    let index = nodes.len();
+    nodes.push(Node {
+        label: WidgetPod::new(Label::new(tree_node.label)),
+        depth,
+        parent,
+        index_in_parent,
+        subtree_end: index + 1,
+        expanded: tree_node.expanded,
+    });
+    for (child_index, child) in tree_node.children.into_iter().enumerate() {
+        flatten(nodes, child, depth + 1, Some(index), child_index);
+    }
+    nodes[index].subtree_end = nodes.len();
+}
+
+impl TreeView {
+    fn node_at(&self, path: &[usize]) -> Option<usize> {
+        let (&first, rest) = path.split_first()?;
+        // Walk the siblings, skipping their subtrees.
+        let mut node = 0;
+        for _ in 0..first {
+            node = self.nodes.get(node)?.subtree_end;
+        }
+        self.nodes.get(node)?;
+        for &index in rest {
+            let end = self.nodes[node].subtree_end;
+            node += 1;
+            for _ in 0..index {
+                if node >= end {
+                    return None;
+                }
+                node = self.nodes[node].subtree_end;
+            }
+            if node >= end {
+                return None;
+            }
+        }
+        Some(node)
+    }
+
+    fn path(&self, mut node: usize) -> Vec<usize> {
+        let mut path = vec![self.nodes[node].index_in_parent];
+        while let Some(parent) = self.nodes[node].parent {
+            path.push(self.nodes[parent].index_in_parent);
+            node = parent;
+        }
+        path.reverse();
+        path
+    }
+
+    fn update_rows(&mut self) {
+        self.rows.clear();
+        let mut node = 0;
+        while node < self.nodes.len() {
+            self.rows.push(node);
+            node = if self.nodes[node].expanded {
+                node + 1
+            } else {
+                self.nodes[node].subtree_end
+            };
+        }
+    }
+
+    fn row_of(&self, node: usize) -> Option<usize> {
+        self.rows.binary_search(&node).ok()
+    }
+
+    /// The horizontal offset of the arrow of items at `depth`.
+    fn indent(depth: usize, width: f64) -> f64 {
+        (depth as f64 * INDENT).min(width / 2.)
+    }
+
+    fn set_node_expanded(&mut self, node: usize, expanded: bool) -> bool {
+        if self.nodes[node].expanded == expanded || !self.nodes[node].has_children(node) {
+            return false;
+        }
+        self.nodes[node].expanded = expanded;
+        if !expanded {
+            // Don't leave the cursor on a hidden item.
+            let hidden = node + 1..self.nodes[node].subtree_end;
+            if self.cursor.is_some_and(|cursor| hidden.contains(&cursor)) {
+                self.cursor = Some(node);
+            }
+            if self.anchor.is_some_and(|anchor| hidden.contains(&anchor)) {
+                self.anchor = Some(node);
+            }
+        }
+        self.update_rows();
+        self.toggled.push(node);
+        true
+    }
+
+    fn select_only(&mut self, node: usize) {
+        self.selection.clear();
+        self.selection.insert(node);
+        self.cursor = Some(node);
+        self.anchor = Some(node);
+    }
+
+    fn toggle_selected(&mut self, node: usize) {
+        if !self.selection.remove(&node) {
+            self.selection.insert(node);
+        }
+        self.cursor = Some(node);
+        self.anchor = Some(node);
+    }
+
+    /// Select the rows between the anchor and `node`.
+    fn select_range(&mut self, node: usize) {
+        let anchor_row = self.anchor.and_then(|anchor| self.row_of(anchor));
+        let (Some(anchor_row), Some(row)) = (anchor_row, self.row_of(node)) else {
+            self.select_only(node);
+            return;
+        };
+        let rows = anchor_row.min(row)..=anchor_row.max(row);
+        self.selection = self.rows[rows].iter().copied().collect();
+        self.cursor = Some(node);
+    }
+
+    fn move_cursor(&mut self, node: usize, extend: bool) {
+        if extend && self.multi_select {
+            self.select_range(node);
+        } else {
+            self.select_only(node);
+        }
+    }
+
+    /// Handle a key press, returning whether it did anything.
+    fn on_key(&mut self, key: &Key, shift: bool) -> bool {
+        if self.rows.is_empty() {
+            return false;
+        }
+        let Some(cursor) = self.cursor.filter(|&cursor| self.row_of(cursor).is_some()) else {
+            return match key {
+                Key::Named(
+                    NamedKey::ArrowUp | NamedKey::ArrowDown | NamedKey::Home | NamedKey::End,
+                ) => {
+                    self.select_only(self.rows[0]);
+                    true
+                }
+                _ => false,
+            };
+        };
+        let row = self.row_of(cursor).unwrap();
+        let has_children = self.nodes[cursor].has_children(cursor);
+        let target = match key {
+            Key::Named(NamedKey::ArrowUp) => row.checked_sub(1).map(|row| self.rows[row]),
+            Key::Named(NamedKey::ArrowDown) => self.rows.get(row + 1).copied(),
+            Key::Named(NamedKey::Home) => Some(self.rows[0]),
+            Key::Named(NamedKey::End) => self.rows.last().copied(),
+            Key::Named(NamedKey::ArrowRight) if has_children => {
+                if self.nodes[cursor].expanded {
+                    Some(cursor + 1)
+                } else {
+                    return self.set_node_expanded(cursor, true);
+                }
+            }
+            Key::Named(NamedKey::ArrowLeft) => {
+                if has_children && self.nodes[cursor].expanded {
+                    return self.set_node_expanded(cursor, false);
+                }
+                self.nodes[cursor].parent
+            }
+            Key::Named(NamedKey::Enter) if has_children => {
+                return self.set_node_expanded(cursor, !self.nodes[cursor].expanded);
+            }
+            _ => None,
+        };
+        let Some(target) = target else {
+            return false;
+        };
+        self.move_cursor(target, shift);
+        true
+    }
+
+    /// Scroll so that the cursor is in view.
+    fn scroll_to_cursor(&mut self, height: f64) {
+        let Some(row) = self.cursor.and_then(|cursor| self.row_of(cursor)) else {
+            return;
+        };
+        let top = row as f64 * ROW_HEIGHT;
+        if top < self.scroll_offset {
+            self.scroll_offset = top;
+        } else if top + ROW_HEIGHT > self.scroll_offset + height {
+            self.scroll_offset = top + ROW_HEIGHT - height;
+        }
+    }
+
+    /// Submit the actions for the changes made by an event.
+    fn submit_changes(&mut self, ctx: &mut EventCtx, prev_selection: &BTreeSet<usize>) {
+        for node in std::mem::take(&mut self.toggled) {
+            let expanded = self.nodes[node].expanded;
+            trace!("TreeView {:?} toggled {}", ctx.widget_id(), node);
+            ctx.submit_action(Action::TreeNodeToggled(self.path(node), expanded));
+            ctx.request_layout();
+        }
+        if self.selection != *prev_selection {
+            ctx.submit_action(Action::TreeSelectionChanged(self.selected_paths()));
+            ctx.request_accessibility_update();
+        }
+        ctx.request_paint();
+    }
+}
+
+impl WidgetMut<'_, TreeView> {
+    /// Show or hide the children of the item at `path`.
+    ///
+    /// This doesn't submit [`Action::TreeNodeToggled`].
+    ///
+    /// # Panics
+    ///
+    /// If there is no item at `path`.
+    pub fn set_expanded(&mut self, path: &[usize], expanded: bool) {
+        let node = self.widget.node_at(path).expect("no item at path");
+        if self.widget.set_node_expanded(node, expanded) {
+            self.widget.toggled.clear();
+            self.ctx.request_layout();
+        }
+    }
+
+    /// Set whether several items can be selected.
+    ///
+    /// When disabling it, the selection is reduced to the current item.
+    pub fn set_multi_select(&mut self, multi_select: bool) {
+        self.widget.multi_select = multi_select;
+        if !multi_select && self.widget.selection.len() > 1 {
+            match self.widget.cursor {
+                Some(cursor) if self.widget.selection.contains(&cursor) => {
+                    self.widget.select_only(cursor);
+                }
+                _ => self.widget.selection.clear(),
+            }
+            self.ctx.request_paint();
+            self.ctx.request_accessibility_update();
+        }
+    }
+
+    /// Deselect all items.
+    pub fn clear_selection(&mut self) {
+        self.widget.selection.clear();
+        self.ctx.request_paint();
+        self.ctx.request_accessibility_update();
+    }
+}
+
+impl Widget for TreeView {
+    fn on_pointer_event(&mut self, ctx: &mut EventCtx, event: &PointerEvent) {
+        for node in &mut self.nodes {
+            node.label.on_pointer_event(ctx, event);
+        }
+        if ctx.is_disabled() {
+            return;
+        }
+
+        let prev_selection = self.selection.clone();
+        match event {
+            PointerEvent::PointerDown(MouseButton::Left, state) => {
+                let origin = ctx.widget_state.window_origin();
+                let (x, y) = (state.position.x - origin.x, state.position.y - origin.y);
+                let row = ((y + self.scroll_offset) / ROW_HEIGHT).floor();
+                if row < 0. || row as usize >= self.rows.len() {
+                    return;
+                }
+                let node = self.rows[row as usize];
+                ctx.request_focus();
+
+                let indent = Self::indent(self.nodes[node].depth, ctx.size().width);
+                let on_arrow = (indent..indent + TOGGLE_WIDTH).contains(&x);
+                if on_arrow || state.count >= 2 {
+                    self.set_node_expanded(node, !self.nodes[node].expanded);
+                }
+                if !on_arrow {
+                    let mods = state.mods.state();
+                    if self.multi_select && (mods.control_key() || mods.super_key()) {
+                        self.toggle_selected(node);
+                    } else if mods.shift_key() {
+                        self.move_cursor(node, true);
+                    } else {
+                        self.select_only(node);
+                    }
+                }
+            }
+            PointerEvent::MouseWheel(delta, _) => {
+                self.scroll_offset += delta.y;
+                ctx.request_layout();
+                ctx.set_handled();
+                return;
+            }
+            _ => return,
+        }
+        self.submit_changes(ctx, &prev_selection);
+    }
+
+    fn on_text_event(&mut self, ctx: &mut EventCtx, event: &TextEvent) {
+        for node in &mut self.nodes {
+            node.label.on_text_event(ctx, event);
+        }
+        let TextEvent::KeyboardKey(key_event, mods) = event else {
+            return;
+        };
+        if !ctx.is_focused() || ctx.is_disabled() || !key_event.state.is_pressed() {
+            return;
+        }
+        let prev_selection = self.selection.clone();
+        if self.on_key(&key_event.logical_key, mods.shift_key()) {
+            ctx.set_handled();
+            self.scroll_to_cursor(ctx.size().height);
+            ctx.request_layout();
+            self.submit_changes(ctx, &prev_selection);
+        }
+    }
+
+    fn on_access_event(&mut self, ctx: &mut EventCtx, event: &AccessEvent) {
+        for node in &mut self.nodes {
+            node.label.on_access_event(ctx, event);
+        }
+    }
+
+    fn on_status_change(&mut self, ctx: &mut LifeCycleCtx, event: &StatusChange) {
+        if let StatusChange::FocusChanged(_) = event {
+            ctx.request_paint();
+        }
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle) {
+        for node in &mut self.nodes {
+            node.label.lifecycle(ctx, event);
+        }
+        if let LifeCycle::BuildFocusChain = event {
+            ctx.register_for_focus();
+        }
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints) -> Size {
+        let content_height = self.rows.len() as f64 * ROW_HEIGHT;
+        let height = bc.constrain(Size::new(0., content_height)).height;
+        let max_scroll = (content_height - height).max(0.);
+        self.scroll_offset = self.scroll_offset.clamp(0., max_scroll);
+
+        let first_row = ((self.scroll_offset / ROW_HEIGHT).floor() as usize).min(self.rows.len());
+        let end_row =
+            (((self.scroll_offset + height) / ROW_HEIGHT).ceil() as usize).min(self.rows.len());
+        let visible = &self.rows[first_row..end_row];
+
+        for &node in &self.shown {
+            if visible.binary_search(&node).is_err() {
+                ctx.set_stashed(&mut self.nodes[node].label, true);
+            }
+        }
+
+        let max_width = bc.max().width;
+        let mut widest: f64 = 0.;
+        for (row, &node) in (first_row..).zip(visible) {
+            let node = &mut self.nodes[node];
+            ctx.set_stashed(&mut node.label, false);
+            let label_x = Self::indent(node.depth, max_width) + TOGGLE_WIDTH;
+            let label_bc = BoxConstraints::new(
+                Size::ZERO,
+                Size::new((max_width - label_x).max(0.), ROW_HEIGHT),
+            );
+            let label_size = node.label.layout(ctx, &label_bc);
+            let y = row as f64 * ROW_HEIGHT - self.scroll_offset;
+            let label_origin = Point::new(label_x, y + (ROW_HEIGHT - label_size.height) / 2.);
+            ctx.place_child(&mut node.label, label_origin);
+            widest = widest.max(label_x + label_size.width);
+        }
+        self.shown = visible.to_vec();
+
+        let width = if bc.is_width_bounded() {
+            max_width
+        } else {
+            widest
+        };
+        let size = bc.constrain(Size::new(width, height));
+        trace!(
+            "Computed layout: size={}, rows={:?}",
+            size,
+            first_row..end_row
+        );
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, scene: &mut Scene) {
+        let size = ctx.size();
+        scene.push_layer(BlendMode::default(), 1., Affine::IDENTITY, &size.to_rect());
+
+        let is_focused = ctx.is_focused();
+        let mut shown = self.shown.iter().peekable();
+        for (index, node) in self.nodes.iter_mut().enumerate() {
+            if shown.next_if_eq(&&index).is_none() {
+                ctx.skip_child(&mut node.label);
+                continue;
+            }
+            let Ok(row) = self.rows.binary_search(&index) else {
+                ctx.skip_child(&mut node.label);
+                continue;
+            };
+            let y = row as f64 * ROW_HEIGHT - self.scroll_offset;
+            let row_rect = Rect::new(0., y, size.width, y + ROW_HEIGHT);
+            if self.selection.contains(&index) {
+                fill_color(scene, &row_rect, theme::SELECTED_TEXT_BACKGROUND_COLOR);
+            }
+            if is_focused && self.cursor == Some(index) {
+                stroke(scene, &row_rect.inset(-1.), theme::PRIMARY_LIGHT, 1.);
+            }
+
+            if node.has_children(index) {
+                let center = Point::new(
+                    Self::indent(node.depth, size.width) + TOGGLE_WIDTH / 2.,
+                    y + ROW_HEIGHT / 2.,
+                );
+                let (h, v) = if node.expanded {
+                    (ARROW_HALF_SIZE, ARROW_HALF_SIZE / 2.)
+                } else {
+                    (ARROW_HALF_SIZE / 2., ARROW_HALF_SIZE)
+                };
+                let mut arrow = BezPath::new();
+                if node.expanded {
+                    arrow.move_to((center.x - h, center.y - v));
+                    arrow.line_to((center.x, center.y + v));
+                    arrow.line_to((center.x + h, center.y - v));
+                } else {
+                    arrow.move_to((center.x - h, center.y - v));
+                    arrow.line_to((center.x + h, center.y));
+                    arrow.line_to((center.x - h, center.y + v));
+                }
+                stroke(scene, &arrow, theme::DISABLED_TEXT_COLOR, 1.5);
+            }
+            node.label.paint(ctx, scene);
+        }
+
+        scene.pop_layer();
+    }
+
+    fn accessibility_role(&self) -> Role {
+        Role::Tree
+    }
+
+    fn accessibility(&mut self, ctx: &mut AccessCtx) {
+        if self.multi_select {
+            ctx.current_node().set_multiselectable();
+        }
+        for node in &mut self.nodes {
+            node.label.accessibility(ctx);
+        }
+    }
+
+    fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
+        self.nodes.iter().map(|node| node.label.as_dyn()).collect()
+    }
+
+    fn make_trace_span(&self) -> Span {
+        trace_span!("TreeView")
+    }
+
+    fn get_debug_text(&self) -> Option<String> {
+        let rows: Vec<String> = self
+            .rows
+            .iter()
+            .map(|&index| {
+                let node = &self.nodes[index];
+                let marker = match (node.has_children(index), node.expanded) {
+                    (false, _) => "",
+                    (true, false) => "▸ ",
+                    (true, true) => "▾ ",
+                };
+                let selected = if self.selection.contains(&index) {
+                    " *"
+                } else {
+                    ""
+                };
+                let indent = "  ".repeat(node.depth);
+                format!("{indent}{marker}{}{selected}", node.label.widget().text())
+            })
+            .collect();
+        Some(rows.join("; "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use insta::assert_debug_snapshot;
+
+    use super::*;
+    use crate::kurbo::Vec2;
+    use crate::testing::TestHarness;
+
+    fn files() -> Vec<TreeNode> {
+        vec![
+            TreeNode::new("src").with_children([
+                TreeNode::new("widget")
+                    .with_children([TreeNode::new("label.rs"), TreeNode::new("tree_view.rs")]),
+                TreeNode::new("lib.rs"),
+            ]),
+            TreeNode::new("Cargo.toml"),
+        ]
+    }
+
+    /// Click at `x` in the row `row` of a tree at the origin of the window.
+    fn click_row(harness: &mut TestHarness, row: usize, x: f64) {
+        harness.mouse_move((x, (row as f64 + 0.5) * ROW_HEIGHT));
+        harness.mouse_button_press(MouseButton::Left);
+        harness.mouse_button_release(MouseButton::Left);
+    }
+
+    #[test]
+    fn expand_and_select() {
+        let mut harness =
+            TestHarness::create_with_size(TreeView::new(files()), Size::new(200., 200.));
+        let tree_id = harness.root_widget().id();
+
+        // Click the arrow of `src`.
+        click_row(&mut harness, 0, TOGGLE_WIDTH / 2.);
+        assert_eq!(
+            harness.pop_action(),
+            Some((Action::TreeNodeToggled(vec![0], true), tree_id))
+        );
+        assert_eq!(harness.pop_action(), None);
+
+        // Click `lib.rs`, below the collapsed `widget`.
+        click_row(&mut harness, 2, 100.);
+        assert_eq!(
+            harness.pop_action(),
+            Some((Action::TreeSelectionChanged(vec![vec![0, 1]]), tree_id))
+        );
+        assert_debug_snapshot!(harness.root_widget());
+
+        // Clicking the selected item again changes nothing.
+        click_row(&mut harness, 2, 100.);
+        assert_eq!(harness.pop_action(), None);
+    }
+
+    #[test]
+    fn multi_select_with_modifiers() {
+        let mut tree = TreeView::new(files()).with_multi_select(true);
+        tree.select_only(0);
+        tree.toggle_selected(5);
+        assert_eq!(tree.selected_paths(), [vec![0], vec![1]]);
+        tree.toggle_selected(0);
+        assert_eq!(tree.selected_paths(), [vec![1]]);
+
+        tree.set_node_expanded(0, true);
+        tree.select_only(1);
+        tree.move_cursor(5, true);
+        assert_eq!(tree.selected_paths(), [vec![0, 0], vec![0, 1], vec![1]]);
+    }
+
+    #[test]
+    fn paths() {
+        let tree = TreeView::new(files());
+        assert_eq!(tree.node_at(&[0, 0, 1]), Some(3));
+        assert_eq!(tree.node_at(&[0, 1]), Some(4));
+        assert_eq!(tree.node_at(&[1]), Some(5));
+        assert_eq!(tree.node_at(&[0, 2]), None);
+        assert_eq!(tree.node_at(&[2]), None);
+        assert_eq!(tree.path(4), [0, 1]);
+    }
+
+    #[test]
+    fn keyboard_navigation() {
+        let mut tree = TreeView::new(files()).with_multi_select(true);
+        let key = |key| Key::Named(key);
+
+        assert!(tree.on_key(&key(NamedKey::ArrowDown), false));
+        assert_eq!(tree.selected_paths(), [vec![0]]);
+        assert!(tree.on_key(&key(NamedKey::ArrowRight), false));
+        assert_eq!(tree.is_expanded(&[0]), Some(true));
+        assert!(tree.on_key(&key(NamedKey::ArrowRight), false));
+        assert_eq!(tree.selected_paths(), [vec![0, 0]]);
+        // Extend the selection down to `lib.rs`, skipping the collapsed children of `widget`.
+        assert!(tree.on_key(&key(NamedKey::ArrowDown), true));
+        assert_eq!(tree.selected_paths(), [vec![0, 0], vec![0, 1]]);
+
+        // Collapsing the parent of the cursor moves it to the parent.
+        assert!(tree.on_key(&key(NamedKey::ArrowLeft), false));
+        assert_eq!(tree.selected_paths(), [vec![0]]);
+        assert!(tree.on_key(&key(NamedKey::ArrowLeft), false));
+        assert_eq!(tree.is_expanded(&[0]), Some(false));
+        assert_eq!(tree.row_count(), 2);
+        assert!(tree.on_key(&key(NamedKey::End), false));
+        assert_eq!(tree.selected_paths(), [vec![1]]);
+        assert!(!tree.on_key(&key(NamedKey::ArrowDown), false));
+    }
+
+    #[test]
+    fn deep_nesting_indent_is_capped() {
+        assert_eq!(TreeView::indent(2, 200.), 2. * INDENT);
+        assert_eq!(TreeView::indent(50, 200.), 100.);
+    }
+
+    #[test]
+    fn only_visible_rows_are_laid_out() {
+        let roots = (0..100).map(|index| TreeNode::new(format!("Item {index}")));
+        let mut harness =
+            TestHarness::create_with_size(TreeView::new(roots), Size::new(200., 100.));
+
+        let stashed_count = |harness: &TestHarness| {
+            harness
+                .root_widget()
+                .children()
+                .iter()
+                .filter(|child| child.state().is_stashed)
+                .count()
+        };
+        // 100 pixels fit 4.2 rows.
+        assert_eq!(stashed_count(&harness), 95);
+
+        harness.mouse_move((50., 50.));
+        harness.mouse_wheel(Vec2::new(0., 10. * ROW_HEIGHT));
+        assert_eq!(stashed_count(&harness), 95);
+        let first_shown = harness
+            .root_widget()
+            .children()
+            .into_iter()
+            .position(|child| !child.state().is_stashed);
+        assert_eq!(first_shown, Some(10));
+    }
+}
@@ -0,0 +1,296 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! A widget that reports when its child scrolls in or out of view.
+
+use accesskit::Role;
+use smallvec::{smallvec, SmallVec};
+use tracing::{trace, trace_span, Span};
+use vello::Scene;
+
+use crate::kurbo::Rect;
+use crate::widget::{WidgetMut, WidgetPod, WidgetRef};
+use crate::{
+    AccessCtx, AccessEvent, Action, BoxConstraints, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx,
+    PaintCtx, Point, PointerEvent, Size, StatusChange, TextEvent, Widget,
+};
+
+/// A widget that submits [`Action::VisibilityChanged`] when its child enters or
+/// leaves the viewport, e.g. when it is scrolled out of a [`Portal`](crate::widget::Portal).
+///
+/// The first action is submitted once the child has been laid out, whether it is
+/// visible or not. Afterwards, actions are only submitted when the visibility changes.
+///
+/// Only what ancestors clip is taken into account: a child hidden behind another
+/// widget still counts as visible.
+pub struct VisibilityObserver<W> {
+    child: WidgetPod<W>,
+    threshold: f64,
+    viewport: Option<Rect>,
+    visible: Option<bool>,
+}
+
+impl<W: Widget> VisibilityObserver<W> {
+    /// Create a new widget observing `child`.
+    pub fn new(child: W) -> Self {
+        Self::new_pod(WidgetPod::new(child))
+    }
+
+    /// Create a new widget observing the child `child`.
+    pub fn new_pod(child: WidgetPod<W>) -> Self {
+        VisibilityObserver {
+            child,
+            threshold: 0.0,
+            viewport: None,
+            visible: None,
+        }
+    }
+
+    /// Builder-style method to set which fraction of the child's area must be in the
+    /// viewport for it to count as visible.
+    ///
+    /// With the default of 0, a partially visible child is visible. With 1, the whole
+    /// child must be in view. The threshold is clamped between 0 and 1.
+    pub fn with_threshold(mut self, threshold: f64) -> Self {
+        self.threshold = threshold.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Whether the child was visible the last time the viewport changed.
+    ///
+    /// Returns `None` before the child has been laid out.
+    pub fn is_visible(&self) -> Option<bool> {
+        self.visible
+    }
+}
+
+impl<W: Widget> WidgetMut<'_, VisibilityObserver<W>> {
+    /// Set which fraction of the child's area must be in the viewport for it to count
+    /// as visible.
+    ///
+    /// The threshold is clamped between 0 and 1.
+    pub fn set_threshold(&mut self, threshold: f64) {
+        let threshold = threshold.clamp(0.0, 1.0);
+        if self.widget.threshold == threshold {
+            return;
+        }
+        self.widget.threshold = threshold;
+        if let Some(viewport) = self.widget.viewport {
+            let rect = Rect::from_origin_size(self.ctx.window_origin(), self.ctx.size());
+            let visible = is_visible(rect, viewport, threshold);
+            if self.widget.visible != Some(visible) {
+                self.widget.visible = Some(visible);
+                self.ctx.submit_action(Action::VisibilityChanged(visible));
+            }
+        }
+    }
+
+    pub fn child_mut(&mut self) -> WidgetMut<'_, W> {
+        self.ctx.get_mut(&mut self.widget.child)
+    }
+}
+
+/// Whether at least `threshold` of the area of `rect` is in `viewport`.
+fn is_visible(rect: Rect, viewport: Rect, threshold: f64) -> bool {
+    if rect.area() == 0.0 {
+        // An empty widget is visible if it's in the viewport, edges included.
+        return viewport.x0 <= rect.x0
+            && rect.x1 <= viewport.x1
+            && viewport.y0 <= rect.y0
+            && rect.y1 <= viewport.y1;
+    }
+    let visible_rect = rect.intersect(viewport);
+    let fraction = visible_rect.area() / rect.area();
+    if threshold == 0.0 {
+        fraction > 0.0
+    } else {
+        fraction >= threshold
+    }
+}
+
+impl<W: Widget> Widget for VisibilityObserver<W> {
+    fn on_pointer_event(&mut self, ctx: &mut EventCtx, event: &PointerEvent) {
+        self.child.on_pointer_event(ctx, event);
+    }
+
+    fn on_text_event(&mut self, ctx: &mut EventCtx, event: &TextEvent) {
+        self.child.on_text_event(ctx, event);
+    }
+
+    fn on_access_event(&mut self, ctx: &mut EventCtx, event: &AccessEvent) {
+        self.child.on_access_event(ctx, event);
+    }
+
+    fn on_status_change(&mut self, _ctx: &mut LifeCycleCtx, _event: &StatusChange) {}
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle) {
+        self.child.lifecycle(ctx, event);
+
+        if let LifeCycle::WidgetAdded = event {
+            ctx.register_as_viewport_observer();
+        }
+        if let LifeCycle::ViewportChanged(viewport) = event {
+            self.viewport = Some(*viewport);
+            let rect = Rect::from_origin_size(ctx.window_origin(), ctx.size());
+            let visible = is_visible(rect, *viewport, self.threshold);
+            if self.visible != Some(visible) {
+                trace!("Visibility changed: {}", visible);
+                self.visible = Some(visible);
+                ctx.submit_action(Action::VisibilityChanged(visible));
+            }
+        }
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints) -> Size {
+        let size = self.child.layout(ctx, bc);
+        ctx.place_child(&mut self.child, Point::ORIGIN);
+        ctx.set_paint_insets(self.child.compute_parent_paint_insets(size));
+        ctx.set_baseline_offset(self.child.baseline_offset());
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, scene: &mut Scene) {
+        self.child.paint(ctx, scene);
+    }
+
+    fn accessibility_role(&self) -> Role {
+        Role::GenericContainer
+    }
+
+    fn accessibility(&mut self, ctx: &mut AccessCtx) {
+        self.child.accessibility(ctx);
+    }
+
+    fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
+        smallvec![self.child.as_dyn()]
+    }
+
+    fn make_trace_span(&self) -> Span {
+        trace_span!("VisibilityObserver")
+    }
+
+    fn get_debug_text(&self) -> Option<String> {
+        self.visible
+            .map(|visible| if visible { "visible" } else { "hidden" }.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{widget_ids, Record, Recording, TestHarness, TestWidgetExt as _};
+    use crate::widget::{Flex, Portal, SizedBox};
+    use crate::{LifeCycle, WidgetId};
+
+    fn visibility_actions(harness: &mut TestHarness, id: WidgetId) -> Vec<bool> {
+        let mut actions = vec![];
+        while let Some((action, widget_id)) = harness.pop_action() {
+            if let (Action::VisibilityChanged(visible), true) = (action, widget_id == id) {
+                actions.push(visible);
+            }
+        }
+        actions
+    }
+
+    fn scroll_to(harness: &mut TestHarness, y: f64) {
+        harness.edit_root_widget(|mut portal| {
+            let mut portal = portal.downcast::<Portal<Flex>>();
+            portal.set_viewport_pos(Point::new(0.0, y));
+        });
+    }
+
+    #[test]
+    fn scrolled_out_of_view() {
+        let [observer_id] = widget_ids();
+        let content = Flex::column()
+            .with_child_id(
+                VisibilityObserver::new(SizedBox::empty().width(50.0).height(100.0)),
+                observer_id,
+            )
+            .with_child(SizedBox::empty().width(50.0).height(1000.0));
+        let mut harness =
+            TestHarness::create_with_size(Portal::new(content), Size::new(200.0, 200.0));
+        assert_eq!(visibility_actions(&mut harness, observer_id), [true]);
+
+        // Partially visible.
+        scroll_to(&mut harness, 60.0);
+        assert_eq!(
+            visibility_actions(&mut harness, observer_id),
+            Vec::<bool>::new()
+        );
+
+        scroll_to(&mut harness, 150.0);
+        assert_eq!(visibility_actions(&mut harness, observer_id), [false]);
+
+        scroll_to(&mut harness, 0.0);
+        assert_eq!(visibility_actions(&mut harness, observer_id), [true]);
+    }
+
+    #[test]
+    fn threshold() {
+        let [observer_id] = widget_ids();
+        let content = Flex::column()
+            .with_child_id(
+                VisibilityObserver::new(SizedBox::empty().width(50.0).height(100.0))
+                    .with_threshold(0.5),
+                observer_id,
+            )
+            .with_child(SizedBox::empty().width(50.0).height(1000.0));
+        let mut harness =
+            TestHarness::create_with_size(Portal::new(content), Size::new(200.0, 200.0));
+        assert_eq!(visibility_actions(&mut harness, observer_id), [true]);
+
+        scroll_to(&mut harness, 40.0);
+        assert_eq!(
+            visibility_actions(&mut harness, observer_id),
+            Vec::<bool>::new()
+        );
+
+        scroll_to(&mut harness, 60.0);
+        assert_eq!(visibility_actions(&mut harness, observer_id), [false]);
+    }
+
+    #[test]
+    fn viewport_is_only_sent_to_observers() {
+        let [observer_id] = widget_ids();
+        let record = Recording::default();
+        let content = Flex::column()
+            .with_child_id(
+                VisibilityObserver::new(SizedBox::empty().width(50.0).height(100.0)),
+                observer_id,
+            )
+            .with_child(SizedBox::empty().width(50.0).height(1000.0).record(&record));
+        let mut harness =
+            TestHarness::create_with_size(Portal::new(content), Size::new(200.0, 200.0));
+        assert_eq!(visibility_actions(&mut harness, observer_id), [true]);
+
+        scroll_to(&mut harness, 150.0);
+        assert_eq!(visibility_actions(&mut harness, observer_id), [false]);
+
+        // The sibling of the observer, which didn't register, never got the viewport.
+        assert!(!record
+            .drain()
+            .iter()
+            .any(|record| matches!(record, Record::L(LifeCycle::ViewportChanged(_)))));
+    }
+
+    #[test]
+    fn visible_fraction() {
+        let viewport = Rect::new(0.0, 0.0, 100.0, 100.0);
+        let half_out = Rect::new(0.0, 50.0, 100.0, 150.0);
+        assert!(is_visible(half_out, viewport, 0.0));
+        assert!(is_visible(half_out, viewport, 0.5));
+        assert!(!is_visible(half_out, viewport, 0.6));
+        assert!(!is_visible(
+            Rect::new(0.0, 100.0, 100.0, 200.0),
+            viewport,
+            0.0
+        ));
+        assert!(is_visible(Rect::new(10.0, 10.0, 10.0, 10.0), viewport, 1.0));
+        assert!(!is_visible(
+            Rect::new(10.0, 110.0, 10.0, 110.0),
+            viewport,
+            0.0
+        ));
+    }
+}
@@ -87,6 +87,19 @@ pub trait Widget: AsAny {
     /// changes in the widget graph or in the state of your specific widget.
     fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle);
 
+    /// Move an animation forward.
+    ///
+    /// This is called at the beginning of each animation frame, after
+    /// [`lifecycle`](Self::lifecycle) gets [`LifeCycle::AnimFrame`], but only if this widget
+    /// or one of its descendants called [`request_anim_frame`](LifeCycleCtx::request_anim_frame)
+    /// since the previous frame. `interval` is the time since that frame, in nanoseconds, and is 0 on the first
+    /// frame after the app was idle.
+    ///
+    /// Each request is only for the next frame: to keep animating, call `request_anim_frame`
+    /// again from this method. Frames stop once no widget requests them.
+    #[allow(unused_variables)]
+    fn on_anim_frame(&mut self, ctx: &mut LifeCycleCtx, interval: u64) {}
+
     /// Compute layout.
     ///
     /// A leaf widget should determine its size (subject to the provided
@@ -106,6 +119,49 @@ pub trait Widget: AsAny {
     /// The layout strategy is strongly inspired by Flutter.
     fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints) -> Size;
 
+    /// Return the smallest width this widget can be given without its content
+    /// overflowing, if its height is at most `height`.
+    ///
+    /// For text, this is usually the width of the longest word.
+    ///
+    /// The default implementation returns [`max_intrinsic_width`](Self::max_intrinsic_width).
+    fn min_intrinsic_width(&mut self, ctx: &mut LayoutCtx, height: f64) -> f64 {
+        self.max_intrinsic_width(ctx, height)
+    }
+
+    /// Return the width beyond which giving this widget more room doesn't reduce its
+    /// height, if its height is at most `height`.
+    ///
+    /// For text, this is usually the width of the text laid out on a single line.
+    ///
+    /// The default implementation probes the widget by calling [`layout`](Self::layout)
+    /// with an unbounded width. Since that may change the layout of the widget and of its
+    /// children, these methods should only be called during a layout pass, before the
+    /// widget they're called on is laid out.
+    fn max_intrinsic_width(&mut self, ctx: &mut LayoutCtx, height: f64) -> f64 {
+        let bc = BoxConstraints::new(Size::ZERO, Size::new(f64::INFINITY, height));
+        self.layout(ctx, &bc).width
+    }
+
+    /// Return the smallest height this widget can be given without its content
+    /// overflowing, if it's given a width of `width`.
+    ///
+    /// The default implementation returns [`max_intrinsic_height`](Self::max_intrinsic_height).
+    fn min_intrinsic_height(&mut self, ctx: &mut LayoutCtx, width: f64) -> f64 {
+        self.max_intrinsic_height(ctx, width)
+    }
+
+    /// Return the height beyond which giving this widget more room doesn't change its
+    /// layout, if it's given a width of `width`.
+    ///
+    /// The default implementation probes the widget by calling [`layout`](Self::layout)
+    /// with a width of at most `width` and an unbounded height. See
+    /// [`max_intrinsic_width`](Self::max_intrinsic_width) for caveats.
+    fn max_intrinsic_height(&mut self, ctx: &mut LayoutCtx, width: f64) -> f64 {
+        let bc = BoxConstraints::new(Size::ZERO, Size::new(width, f64::INFINITY));
+        self.layout(ctx, &bc).height
+    }
+
     /// Paint the widget appearance.
     ///
     /// Container widgets can paint a background before recursing to their
@@ -269,10 +325,30 @@ impl Widget for Box<dyn Widget> {
         self.deref_mut().lifecycle(ctx, event);
     }
 
+    fn on_anim_frame(&mut self, ctx: &mut LifeCycleCtx, interval: u64) {
+        self.deref_mut().on_anim_frame(ctx, interval);
+    }
+
     fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints) -> Size {
         self.deref_mut().layout(ctx, bc)
     }
 
+    fn min_intrinsic_width(&mut self, ctx: &mut LayoutCtx, height: f64) -> f64 {
+        self.deref_mut().min_intrinsic_width(ctx, height)
+    }
+
+    fn max_intrinsic_width(&mut self, ctx: &mut LayoutCtx, height: f64) -> f64 {
+        self.deref_mut().max_intrinsic_width(ctx, height)
+    }
+
+    fn min_intrinsic_height(&mut self, ctx: &mut LayoutCtx, width: f64) -> f64 {
+        self.deref_mut().min_intrinsic_height(ctx, width)
+    }
+
+    fn max_intrinsic_height(&mut self, ctx: &mut LayoutCtx, width: f64) -> f64 {
+        self.deref_mut().max_intrinsic_height(ctx, width)
+    }
+
     fn paint(&mut self, ctx: &mut PaintCtx, scene: &mut Scene) {
         self.deref_mut().paint(ctx, scene);
     }
@@ -1,8 +1,13 @@
 // Copyright 2018 the Xilem Authors and the Druid Authors
 // SPDX-License-Identifier: Apache-2.0
 
+use std::cell::Cell;
+use std::rc::Rc;
+
 use crate::contexts::WidgetCtx;
-use crate::Widget;
+use crate::{
+    Badge, InternalLifeCycle, LayoutDirection, LifeCycle, LifeCycleCtx, TextStyle, Widget, WidgetId,
+};
 
 // TODO - Document extension trait workaround.
 // See https://xi.zulipchat.com/#narrow/stream/317477-masonry/topic/Thoughts.20on.20simplifying.20WidgetMut/near/436478885
@@ -19,21 +24,146 @@ use crate::Widget;
 ///
 /// `WidgetMut` implements [`Deref`](std::ops::Deref) with `W::Mut` as target.
 ///
+/// ## Re-entrancy
+///
+/// A `WidgetMut` only gives access to its widget's subtree, and changes made through it
+/// (adding or removing children, resizing) only take effect in the next layout pass.
+/// Layout-dependent values read in the same edit may be stale, and indices of a container's
+/// children shift as soon as a child is removed.
+///
+/// Changes that don't fit those rules, like removing the widget that is currently handling
+/// an event, should be queued with [`EventCtx::defer`](crate::EventCtx::defer).
+///
 /// ## `WidgetMut` as a Receiver
 ///
 /// Once the Receiver trait is stabilized, `WidgetMut` will implement it so that custom
 /// widgets in downstream crates can use `WidgetMut` as the receiver for inherent methods.
-pub struct WidgetMut<'a, W: Widget> {
+pub struct WidgetMut<'a, W: Widget + ?Sized> {
     pub ctx: WidgetCtx<'a>,
     pub widget: &'a mut W,
 }
 
-impl<W: Widget> Drop for WidgetMut<'_, W> {
+impl<W: Widget + ?Sized> Drop for WidgetMut<'_, W> {
     fn drop(&mut self) {
         self.ctx.parent_widget_state.merge_up(self.ctx.widget_state);
     }
 }
 
+impl<'a, W: Widget + ?Sized> WidgetMut<'a, W> {
+    /// Get a `WidgetMut` to the same widget, which borrows this one.
+    ///
+    /// This is useful to pass the widget to a function which takes a `WidgetMut` by value,
+    /// and keep using it afterwards.
+    pub fn reborrow_mut(&mut self) -> WidgetMut<'_, W> {
+        let ctx = WidgetCtx {
+            global_state: self.ctx.global_state,
+            parent_widget_state: self.ctx.parent_widget_state,
+            widget_state: self.ctx.widget_state,
+        };
+        WidgetMut {
+            ctx,
+            widget: self.widget,
+        }
+    }
+
+    /// Set the layout direction of this widget and its descendants, or inherit the
+    /// direction of its parent with `None`.
+    ///
+    /// See [`LayoutDirection`] for details.
+    pub fn set_layout_direction(&mut self, direction: Option<LayoutDirection>) {
+        if self.ctx.widget_state.layout_direction != direction {
+            self.ctx.widget_state.layout_direction = direction;
+            self.ctx.request_layout();
+        }
+    }
+
+    /// Set the text style inherited by this widget's descendants.
+    ///
+    /// Only the text widgets whose style changes are laid out again. See [`TextStyle`]
+    /// for details.
+    pub fn set_text_style(&mut self, text_style: TextStyle) {
+        if self.ctx.widget_state.text_style != text_style {
+            self.ctx.widget_state.text_style = text_style;
+            self.ctx.widget_state.text_style_changed = true;
+        }
+    }
+
+    /// The text style set on this widget with [`set_text_style`](Self::set_text_style).
+    pub fn text_style(&self) -> &TextStyle {
+        &self.ctx.widget_state.text_style
+    }
+
+    /// Set the badge painted above this widget, or remove it with `None`.
+    ///
+    /// The badge doesn't affect layout, so this only requests a paint and an accessibility
+    /// update. See [`Badge`] for details.
+    pub fn set_badge(&mut self, badge: Option<Badge>) {
+        if self.ctx.widget_state.badge != badge {
+            self.ctx.widget_state.badge = badge;
+            self.ctx.request_paint();
+            self.ctx.request_accessibility_update();
+        }
+    }
+
+    /// Set the tab index of this widget, or remove it with `None`.
+    ///
+    /// See [`WidgetPod::with_tab_index`](crate::WidgetPod::with_tab_index) for details.
+    pub fn set_tab_index(&mut self, tab_index: Option<i32>) {
+        if self.ctx.widget_state.tab_index != tab_index {
+            self.ctx.widget_state.tab_index = tab_index;
+            self.ctx.widget_state.update_focus_chain = true;
+        }
+    }
+
+    /// Set the position of this widget in the reading order of its siblings, or remove
+    /// it with `None`.
+    ///
+    /// See [`WidgetPod::with_reading_order`](crate::WidgetPod::with_reading_order) for
+    /// details.
+    pub fn set_reading_order(&mut self, reading_order: Option<u32>) {
+        if self.ctx.widget_state.reading_order != reading_order {
+            self.ctx.widget_state.reading_order = reading_order;
+            // The order is part of the node of the parent.
+            let parent_state = &mut *self.ctx.parent_widget_state;
+            parent_state.needs_accessibility_update = true;
+            parent_state.request_accessibility_update = true;
+        }
+    }
+
+    /// Run `f` on the descendant of this widget with the given id, at any depth.
+    ///
+    /// This is useful when widgets are tracked by id, e.g. with
+    /// [`widget_ids`](crate::testing::widget_ids), rather than by their position in
+    /// each container. The descendant is reached by routing an event down the tree,
+    /// which is why it's passed to a callback instead of being returned.
+    ///
+    /// Returns `None` if no descendant has this id.
+    pub fn edit_descendant<R: 'static>(
+        &mut self,
+        id: WidgetId,
+        f: impl FnOnce(WidgetMut<'_, dyn Widget>) -> R + 'static,
+    ) -> Option<R> {
+        if !self.ctx.widget_state.children.may_contain(&id) {
+            return None;
+        }
+        let result = Rc::new(Cell::new(None));
+        let edit_result = result.clone();
+        self.ctx.global_state.descendant_edit = Some(Box::new(move |widget| {
+            edit_result.set(Some(f(widget)));
+        }));
+
+        let mut ctx = LifeCycleCtx {
+            global_state: self.ctx.global_state,
+            widget_state: self.ctx.widget_state,
+        };
+        let event = LifeCycle::Internal(InternalLifeCycle::RouteWidgetEdit(id));
+        self.widget.lifecycle(&mut ctx, &event);
+        // The bloom filter can return false positives, in which case the edit didn't run.
+        self.ctx.global_state.descendant_edit = None;
+        result.take()
+    }
+}
+
 impl<'a> WidgetMut<'a, Box<dyn Widget>> {
     /// Attempt to downcast to `WidgetMut` of concrete Widget type.
     pub fn try_downcast<W2: Widget>(&mut self) -> Option<WidgetMut<'_, W2>> {
@@ -74,4 +204,85 @@ impl<'a> WidgetMut<'a, Box<dyn Widget>> {
     }
 }
 
-// TODO - unit tests
+impl<'a> WidgetMut<'a, dyn Widget> {
+    /// Attempt to downcast to `WidgetMut` of concrete Widget type.
+    pub fn try_downcast<W2: Widget>(&mut self) -> Option<WidgetMut<'_, W2>> {
+        let ctx = WidgetCtx {
+            global_state: self.ctx.global_state,
+            parent_widget_state: self.ctx.parent_widget_state,
+            widget_state: self.ctx.widget_state,
+        };
+        Some(WidgetMut {
+            ctx,
+            widget: self.widget.as_mut_any().downcast_mut()?,
+        })
+    }
+
+    /// Downcasts to `WidgetMut` of concrete Widget type.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if the downcast fails, with an error message that shows the
+    /// discrepancy between the expected and actual types.
+    pub fn downcast<W2: Widget>(&mut self) -> WidgetMut<'_, W2> {
+        let ctx = WidgetCtx {
+            global_state: self.ctx.global_state,
+            parent_widget_state: self.ctx.parent_widget_state,
+            widget_state: self.ctx.widget_state,
+        };
+        let w1_name = self.widget.type_name();
+        match self.widget.as_mut_any().downcast_mut() {
+            Some(widget) => WidgetMut { ctx, widget },
+            None => {
+                panic!(
+                    "failed to downcast widget: expected widget of type `{}`, found `{}`",
+                    std::any::type_name::<W2>(),
+                    w1_name,
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{widget_ids, TestHarness};
+    use crate::widget::{Button, Flex, Label, SizedBox};
+
+    fn button_text(harness: &TestHarness, button_id: WidgetId) -> String {
+        let button = harness.get_widget(button_id);
+        let label = button.children()[0].downcast::<Label>().unwrap();
+        label.text().to_string()
+    }
+
+    #[test]
+    fn edit_nested_button_by_id() {
+        let [button_id, other_id] = widget_ids();
+        let row = Flex::row()
+            .with_child(Label::new("Label"))
+            .with_child_id(Button::new("Before"), button_id);
+        let widget = Flex::column()
+            .with_child_id(Button::new("Other"), other_id)
+            .with_child(SizedBox::new(row));
+
+        let mut harness = TestHarness::create(widget);
+        let edited = harness.edit_root_widget(|mut root| {
+            root.edit_descendant(button_id, |mut button| {
+                button.downcast::<Button>().set_text("After");
+                button.ctx.widget_id()
+            })
+        });
+        assert_eq!(edited, Some(button_id));
+        assert_eq!(button_text(&harness, button_id), "After");
+        assert_eq!(button_text(&harness, other_id), "Other");
+    }
+
+    #[test]
+    fn edit_missing_descendant() {
+        let [button_id] = widget_ids();
+        let mut harness = TestHarness::create(Flex::column().with_child(Button::new("Button")));
+        let edited = harness.edit_root_widget(|mut root| root.edit_descendant(button_id, |_| ()));
+        assert_eq!(edited, None);
+    }
+}
@@ -3,6 +3,7 @@
 
 use accesskit::{NodeBuilder, NodeId};
 use tracing::{info_span, trace, warn};
+use vello::peniko::BlendMode;
 use vello::Scene;
 use winit::dpi::LogicalPosition;
 
@@ -11,10 +12,10 @@ use crate::kurbo::{Affine, Insets, Point, Rect, Shape, Size};
 use crate::paint_scene_helpers::stroke;
 use crate::render_root::RenderRootState;
 use crate::theme::get_debug_color;
-use crate::widget::{WidgetRef, WidgetState};
+use crate::widget::{WidgetMut, WidgetRef, WidgetState};
 use crate::{
-    AccessCtx, BoxConstraints, EventCtx, InternalLifeCycle, LayoutCtx, LifeCycle, LifeCycleCtx,
-    PaintCtx, StatusChange, Widget, WidgetId,
+    AccessCtx, Badge, BoxConstraints, EventCtx, InternalLifeCycle, LayoutCtx, LifeCycle,
+    LifeCycleCtx, PaintCtx, StatusChange, TextStyle, Widget, WidgetCtx, WidgetId,
 };
 
 // TODO - rewrite links in doc
@@ -57,6 +58,72 @@ impl<W: Widget> WidgetPod<W> {
         }
     }
 
+    /// Builder-style method for setting the badge painted above the widget.
+    ///
+    /// See [`Badge`] for details.
+    pub fn with_badge(mut self, badge: Badge) -> Self {
+        self.state.badge = Some(badge);
+        self
+    }
+
+    /// Builder-style method for setting the text style inherited by the widget's
+    /// descendants.
+    ///
+    /// See [`TextStyle`] for details.
+    pub fn with_text_style(mut self, text_style: TextStyle) -> Self {
+        self.state.text_style = text_style;
+        self
+    }
+
+    /// Builder-style method for setting the layout direction of the widget and its
+    /// descendants, or inheriting the direction of its parent with `None`.
+    ///
+    /// See [`LayoutDirection`](crate::LayoutDirection) for details.
+    pub fn with_layout_direction(mut self, direction: Option<crate::LayoutDirection>) -> Self {
+        self.state.layout_direction = direction;
+        self
+    }
+
+    /// Builder-style method for disabling the widget and its descendants.
+    ///
+    /// Disabled widgets can't be focused, and interactive widgets like buttons ignore
+    /// input while disabled. Descendants of a disabled widget are disabled too, even if
+    /// they are explicitly enabled. See [`EventCtx::is_disabled`](crate::EventCtx::is_disabled)
+    /// for details.
+    pub fn with_disabled(mut self, disabled: bool) -> Self {
+        self.state.is_explicitly_disabled_new = disabled;
+        self
+    }
+
+    /// Builder-style method for setting the tab index of the widget.
+    ///
+    /// Tab and Shift+Tab go through the focusable widgets of a [`FocusScope`] (or of the
+    /// window) by increasing tab index, and in tree order for widgets with the same
+    /// index. Widgets without a tab index have an index of 0. The tab index of a widget
+    /// applies to all of its focusable descendants, and a nested scope is ordered as a
+    /// whole by its own tab index.
+    ///
+    /// Widgets with a negative tab index are skipped by Tab, but they can still be
+    /// focused programmatically, or by assistive technologies.
+    ///
+    /// [`FocusScope`]: crate::widget::FocusScope
+    pub fn with_tab_index(mut self, tab_index: Option<i32>) -> Self {
+        self.state.tab_index = tab_index;
+        self
+    }
+
+    /// Builder-style method for setting the position of the widget in the reading order
+    /// of its siblings.
+    ///
+    /// The accessibility node of the parent lists its children by increasing reading
+    /// order, which is what screen readers follow for linear navigation, and in tree
+    /// order for widgets with the same order. Widgets without a reading order have an
+    /// order of 0.
+    pub fn with_reading_order(mut self, reading_order: Option<u32>) -> Self {
+        self.state.reading_order = reading_order;
+        self
+    }
+
     /// Read-only access to state. We don't mark the field as `pub` because
     /// we want to control mutation.
     pub(crate) fn state(&self) -> &WidgetState {
@@ -245,6 +312,10 @@ impl<W: Widget> WidgetPod<W> {
                 inner_state.is_hot
             );
 
+            // The hovered state is part of the accessibility node
+            inner_state.needs_accessibility_update = true;
+            inner_state.request_accessibility_update = true;
+
             let hot_changed_event = StatusChange::HotChanged(inner_state.is_hot);
             let mut inner_ctx = LifeCycleCtx {
                 global_state,
@@ -266,31 +337,42 @@ impl<W: Widget> WidgetPod<W> {
     fn call_widget_method_with_checks<Ret>(
         &mut self,
         method_name: &str,
-        visit: impl FnOnce(&mut Self) -> Ret,
+        global_state: &mut RenderRootState,
+        visit: impl FnOnce(&mut Self, &mut RenderRootState) -> Ret,
     ) -> Ret {
         if cfg!(not(debug_assertions)) {
-            return visit(self);
+            return visit(self, global_state);
         }
 
         for child in self.inner.children() {
             child.state().mark_as_visited(false);
         }
-        let children_ids: Vec<_> = self.inner.children().iter().map(|w| w.id()).collect();
 
-        let return_value = visit(self);
-
-        let new_children_ids: Vec<_> = self.inner.children().iter().map(|w| w.id()).collect();
-        if children_ids != new_children_ids && !self.state.children_changed {
-            debug_panic!(
-                "Error in '{}' #{}: children changed in method {} but ctx.children_changed() wasn't called",
-                self.inner.short_type_name(),
-                self.state().id.to_raw(),
-                method_name,
-            );
+        let return_value = visit(self, global_state);
+
+        // The children cached by the widget index are only refreshed by RouteWidgetAdded,
+        // which widgets request with children_changed.
+        let children = self.inner.children();
+        if !self.state.children_changed {
+            if let Some(cached_ids) = global_state.widget_index.children(self.id()) {
+                let children_unchanged = children.len() == cached_ids.len()
+                    && children
+                        .iter()
+                        .zip(cached_ids)
+                        .all(|(child, id)| child.id() == *id);
+                if !children_unchanged {
+                    debug_panic!(
+                        "Error in '{}' #{}: children changed in method {} but ctx.children_changed() wasn't called",
+                        self.inner.short_type_name(),
+                        self.state().id.to_raw(),
+                        method_name,
+                    );
+                }
+            }
         }
 
         #[cfg(debug_assertions)]
-        for child in self.inner.children() {
+        for child in children {
             // FIXME - use can_skip callback instead
             if child.state().needs_visit() && !child.state().is_stashed {
                 debug_panic!(
@@ -324,8 +406,14 @@ impl<W: Widget + 'static> WidgetPod<W> {
     ///
     /// Convert a `WidgetPod` containing a widget of a specific concrete type
     /// into a dynamically boxed widget.
+    ///
+    /// The state set with the builder-style methods, like the tab index, is kept.
     pub fn boxed(self) -> WidgetPod<Box<dyn Widget>> {
-        WidgetPod::new_with_id(Box::new(self.inner), self.state.id)
+        WidgetPod {
+            state: self.state,
+            inner: Box::new(self.inner),
+            fragment: self.fragment,
+        }
     }
 }
 
@@ -388,29 +476,33 @@ impl<W: Widget> WidgetPod<W> {
         //let call_inner = true;
 
         if call_inner {
-            self.call_widget_method_with_checks("on_pointer_event", |widget_pod| {
-                // widget_pod is a reborrow of `self`
-                let mut inner_ctx = EventCtx {
-                    global_state: parent_ctx.global_state,
-                    widget_state: &mut widget_pod.state,
-                    is_handled: false,
-                    request_pan_to_child: None,
-                };
-                inner_ctx.widget_state.has_active = false;
+            self.call_widget_method_with_checks(
+                "on_pointer_event",
+                parent_ctx.global_state,
+                |widget_pod, global_state| {
+                    // widget_pod is a reborrow of `self`
+                    let mut inner_ctx = EventCtx {
+                        global_state,
+                        widget_state: &mut widget_pod.state,
+                        is_handled: false,
+                        request_pan_to_child: None,
+                    };
+                    inner_ctx.widget_state.has_active = false;
 
-                widget_pod.inner.on_pointer_event(&mut inner_ctx, event);
+                    widget_pod.inner.on_pointer_event(&mut inner_ctx, event);
 
-                inner_ctx.widget_state.has_active |= inner_ctx.widget_state.is_active;
-                parent_ctx.is_handled |= inner_ctx.is_handled;
+                    inner_ctx.widget_state.has_active |= inner_ctx.widget_state.is_active;
+                    parent_ctx.is_handled |= inner_ctx.is_handled;
 
-                // TODO - there's some dubious logic here
-                if let Some(target_rect) = inner_ctx.request_pan_to_child {
-                    widget_pod.pan_to_child(parent_ctx, target_rect);
-                    let new_rect = target_rect
-                        .with_origin(target_rect.origin() + widget_pod.state.origin.to_vec2());
-                    parent_ctx.request_pan_to_child = Some(new_rect);
-                }
-            });
+                    // TODO - there's some dubious logic here
+                    if let Some(target_rect) = inner_ctx.request_pan_to_child {
+                        widget_pod.pan_to_child(global_state, target_rect);
+                        let new_rect = target_rect
+                            .with_origin(target_rect.origin() + widget_pod.state.origin.to_vec2());
+                        parent_ctx.request_pan_to_child = Some(new_rect);
+                    }
+                },
+            );
         }
 
         // Always merge even if not needed, because merging is idempotent and gives us simpler code.
@@ -448,28 +540,32 @@ impl<W: Widget> WidgetPod<W> {
         }
 
         if self.state.has_focus {
-            self.call_widget_method_with_checks("on_text_event", |widget_pod| {
-                // widget_pod is a reborrow of `self`
-                let mut inner_ctx = EventCtx {
-                    global_state: parent_ctx.global_state,
-                    widget_state: &mut widget_pod.state,
-                    is_handled: false,
-                    request_pan_to_child: None,
-                };
+            self.call_widget_method_with_checks(
+                "on_text_event",
+                parent_ctx.global_state,
+                |widget_pod, global_state| {
+                    // widget_pod is a reborrow of `self`
+                    let mut inner_ctx = EventCtx {
+                        global_state,
+                        widget_state: &mut widget_pod.state,
+                        is_handled: false,
+                        request_pan_to_child: None,
+                    };
 
-                widget_pod.inner.on_text_event(&mut inner_ctx, event);
+                    widget_pod.inner.on_text_event(&mut inner_ctx, event);
 
-                inner_ctx.widget_state.has_active |= inner_ctx.widget_state.is_active;
-                parent_ctx.is_handled |= inner_ctx.is_handled;
+                    inner_ctx.widget_state.has_active |= inner_ctx.widget_state.is_active;
+                    parent_ctx.is_handled |= inner_ctx.is_handled;
 
-                // TODO - there's some dubious logic here
-                if let Some(target_rect) = inner_ctx.request_pan_to_child {
-                    widget_pod.pan_to_child(parent_ctx, target_rect);
-                    let new_rect = target_rect
-                        .with_origin(target_rect.origin() + widget_pod.state.origin.to_vec2());
-                    parent_ctx.request_pan_to_child = Some(new_rect);
-                }
-            });
+                    // TODO - there's some dubious logic here
+                    if let Some(target_rect) = inner_ctx.request_pan_to_child {
+                        widget_pod.pan_to_child(global_state, target_rect);
+                        let new_rect = target_rect
+                            .with_origin(target_rect.origin() + widget_pod.state.origin.to_vec2());
+                        parent_ctx.request_pan_to_child = Some(new_rect);
+                    }
+                },
+            );
         }
 
         // Always merge even if not needed, because merging is idempotent and gives us simpler code.
@@ -488,9 +584,9 @@ impl<W: Widget> WidgetPod<W> {
         parent_ctx.global_state.debug_logger.pop_span();
     }
 
-    fn pan_to_child(&mut self, parent_ctx: &mut EventCtx, rect: Rect) {
+    fn pan_to_child(&mut self, global_state: &mut RenderRootState, rect: Rect) {
         let mut inner_ctx = LifeCycleCtx {
-            global_state: parent_ctx.global_state,
+            global_state,
             widget_state: &mut self.state,
         };
         let event = LifeCycle::RequestPanToChild(rect);
@@ -516,23 +612,44 @@ impl<W: Widget> WidgetPod<W> {
             return;
         }
 
-        if self.id() == event.target || self.state.children.may_contain(&event.target) {
-            self.call_widget_method_with_checks("on_access_event", |widget_pod| {
-                // widget_pod is a reborrow of `self`
-                let mut inner_ctx = EventCtx {
-                    global_state: parent_ctx.global_state,
-                    widget_state: &mut widget_pod.state,
-                    is_handled: false,
-                    request_pan_to_child: None,
-                };
+        if parent_ctx
+            .global_state
+            .widget_index
+            .is_in_subtree(event.target, self.id())
+        {
+            self.call_widget_method_with_checks(
+                "on_access_event",
+                parent_ctx.global_state,
+                |widget_pod, global_state| {
+                    // widget_pod is a reborrow of `self`
+                    let mut inner_ctx = EventCtx {
+                        global_state,
+                        widget_state: &mut widget_pod.state,
+                        is_handled: false,
+                        request_pan_to_child: None,
+                    };
 
-                widget_pod.inner.on_access_event(&mut inner_ctx, event);
+                    widget_pod.inner.on_access_event(&mut inner_ctx, event);
 
-                inner_ctx.widget_state.has_active |= inner_ctx.widget_state.is_active;
-                parent_ctx.is_handled |= inner_ctx.is_handled;
+                    inner_ctx.widget_state.has_active |= inner_ctx.widget_state.is_active;
+                    parent_ctx.is_handled |= inner_ctx.is_handled;
+
+                    // TODO - request_pan_to_child
+                },
+            );
+        }
 
-                // TODO - request_pan_to_child
-            });
+        // Focusable widgets can be focused by assistive technologies, even if they're out
+        // of the Tab order.
+        if self.id() == event.target
+            && event.action == accesskit::Action::Focus
+            && !parent_ctx.is_handled
+            && self.state.focus_chain.contains(&self.id())
+            && !self.state.is_disabled()
+            && !self.state.is_stashed
+        {
+            parent_ctx.global_state.next_focused_widget = Some(self.id());
+            parent_ctx.is_handled = true;
         }
 
         // Always merge even if not needed, because merging is idempotent and gives us simpler code.
@@ -612,24 +729,53 @@ impl<W: Widget> WidgetPod<W> {
                     self.state.is_explicitly_disabled = self.state.is_explicitly_disabled_new;
 
                     if was_disabled != self.state.is_disabled() {
+                        self.state.needs_accessibility_update = true;
+                        self.state.request_accessibility_update = true;
                         // TODO
                         let disabled = self.state.is_disabled();
-                        self.call_widget_method_with_checks("lifecycle", |widget_pod| {
-                            let mut inner_ctx = LifeCycleCtx {
-                                global_state: parent_ctx.global_state,
-                                widget_state: &mut widget_pod.state,
-                            };
-
-                            widget_pod
-                                .inner
-                                .lifecycle(&mut inner_ctx, &LifeCycle::DisabledChanged(disabled));
-                        });
+                        self.call_widget_method_with_checks(
+                            "lifecycle",
+                            parent_ctx.global_state,
+                            |widget_pod, global_state| {
+                                let mut inner_ctx = LifeCycleCtx {
+                                    global_state,
+                                    widget_state: &mut widget_pod.state,
+                                };
+
+                                widget_pod.inner.lifecycle(
+                                    &mut inner_ctx,
+                                    &LifeCycle::DisabledChanged(disabled),
+                                );
+                            },
+                        );
                         //Each widget needs only one of DisabledChanged and RouteDisabledChanged
                         false
                     } else {
                         self.state.children_disabled_changed
                     }
                 }
+                InternalLifeCycle::RouteTextStyleChanged => {
+                    if self.update_resolved_text_style(parent_ctx) {
+                        self.call_widget_method_with_checks(
+                            "lifecycle",
+                            parent_ctx.global_state,
+                            |widget_pod, global_state| {
+                                let mut inner_ctx = LifeCycleCtx {
+                                    global_state,
+                                    widget_state: &mut widget_pod.state,
+                                };
+
+                                widget_pod
+                                    .inner
+                                    .lifecycle(&mut inner_ctx, &LifeCycle::TextStyleChanged);
+                            },
+                        );
+                        // Each widget needs only one of TextStyleChanged and RouteTextStyleChanged
+                        false
+                    } else {
+                        self.state.children_text_style_changed
+                    }
+                }
                 InternalLifeCycle::RouteFocusChanged { old, new } => {
                     let this_changed = if *old == Some(self.state.id) {
                         Some(false)
@@ -641,21 +787,61 @@ impl<W: Widget> WidgetPod<W> {
 
                     if let Some(change) = this_changed {
                         self.state.has_focus = change;
+                        self.state.needs_accessibility_update = true;
+                        self.state.request_accessibility_update = true;
                         extra_event = Some(StatusChange::FocusChanged(change));
                     } else {
                         self.state.has_focus = false;
                     }
 
-                    // Recurse when the target widgets could be our descendants.
-                    // The bloom filter we're checking can return false positives.
-                    match (old, new) {
-                        (Some(old), _) if self.state.children.may_contain(old) => true,
-                        (_, Some(new)) if self.state.children.may_contain(new) => true,
-                        _ => false,
+                    // Recurse when the target widgets are our descendants.
+                    let index = &parent_ctx.global_state.widget_index;
+                    [old, new]
+                        .into_iter()
+                        .flatten()
+                        .any(|id| *id != self.state.id && index.is_in_subtree(*id, self.state.id))
+                }
+                InternalLifeCycle::RouteLineBreaksReady(id)
+                | InternalLifeCycle::RouteImageLoaded(id) => {
+                    if *id == self.state.id {
+                        self.state.needs_layout = true;
+                        false
+                    } else {
+                        parent_ctx
+                            .global_state
+                            .widget_index
+                            .is_in_subtree(*id, self.state.id)
+                    }
+                }
+                InternalLifeCycle::RouteWidgetEdit(id) => {
+                    if *id == self.state.id {
+                        if let Some(edit) = parent_ctx.global_state.descendant_edit.take() {
+                            let ctx = WidgetCtx {
+                                global_state: parent_ctx.global_state,
+                                parent_widget_state: parent_ctx.widget_state,
+                                widget_state: &mut self.state,
+                            };
+                            edit(WidgetMut {
+                                ctx,
+                                widget: &mut self.inner,
+                            });
+                        }
+                        false
+                    } else {
+                        parent_ctx
+                            .global_state
+                            .widget_index
+                            .is_in_subtree(*id, self.state.id)
                     }
                 }
                 InternalLifeCycle::ParentWindowOrigin { mouse_pos } => {
-                    self.state.parent_window_origin = parent_ctx.widget_state.window_origin();
+                    let parent_window_origin = parent_ctx.widget_state.window_origin();
+                    if parent_window_origin != self.state.parent_window_origin {
+                        // The bounds of the accessibility node have moved
+                        self.state.needs_accessibility_update = true;
+                        self.state.request_accessibility_update = true;
+                    }
+                    self.state.parent_window_origin = parent_window_origin;
                     self.state.needs_window_origin = false;
                     let mouse_pos = mouse_pos.map(|pos| LogicalPosition::new(pos.x, pos.y));
                     WidgetPod::update_hot_state(
@@ -679,6 +865,11 @@ impl<W: Widget> WidgetPod<W> {
                 );
 
                 self.state.is_new = false;
+                // Like the text style, the disabled state is inherited as soon as the widget
+                // is added, so that widgets added to a disabled container start disabled.
+                self.state.is_explicitly_disabled = self.state.is_explicitly_disabled_new;
+                self.state.ancestor_disabled = parent_ctx.widget_state.is_disabled();
+                self.update_resolved_text_style(parent_ctx);
                 self.state.update_focus_chain = true;
                 self.state.needs_layout = true;
                 self.state.needs_paint = true;
@@ -697,7 +888,11 @@ impl<W: Widget> WidgetPod<W> {
                 );
                 return;
             }
-            LifeCycle::AnimFrame(_) => true,
+            LifeCycle::AnimFrame(_) => {
+                // Each request is for a single frame. Widgets that keep animating
+                // request the next one while handling this one.
+                std::mem::take(&mut self.state.request_anim)
+            }
             LifeCycle::DisabledChanged(ancestors_disabled) => {
                 self.state.update_focus_chain = true;
 
@@ -711,13 +906,21 @@ impl<W: Widget> WidgetPod<W> {
                 // we or our parent are disabled.
                 was_disabled != self.state.is_disabled()
             }
+            LifeCycle::TextStyleChanged => {
+                // Descendants with a pending change of their own still need to see it,
+                // even if the style of this widget didn't change.
+                self.update_resolved_text_style(parent_ctx)
+                    || self.state.children_text_style_changed
+            }
+            LifeCycle::ThemeChanged => true,
+            LifeCycle::ReducedMotionChanged => true,
             LifeCycle::BuildFocusChain => {
                 if self.state.update_focus_chain {
                     // Replace has_focus to check if the value changed in the meantime
                     let is_focused = parent_ctx.global_state.focused_widget == Some(self.state.id);
                     self.state.has_focus = is_focused;
 
-                    self.state.focus_chain.clear();
+                    self.state.clear_focus_chain();
                     true
                 } else {
                     false
@@ -725,18 +928,28 @@ impl<W: Widget> WidgetPod<W> {
             }
             // This is called by children when going up the widget tree.
             LifeCycle::RequestPanToChild(_) => false,
+            LifeCycle::ViewportChanged(_) => {
+                self.state.is_viewport_observer || self.state.has_viewport_observers
+            }
         };
 
         // widget_pod is a reborrow of `self`
         if call_inner {
-            self.call_widget_method_with_checks("lifecycle", |widget_pod| {
-                let mut inner_ctx = LifeCycleCtx {
-                    global_state: parent_ctx.global_state,
-                    widget_state: &mut widget_pod.state,
-                };
+            self.call_widget_method_with_checks(
+                "lifecycle",
+                parent_ctx.global_state,
+                |widget_pod, global_state| {
+                    let mut inner_ctx = LifeCycleCtx {
+                        global_state,
+                        widget_state: &mut widget_pod.state,
+                    };
 
-                widget_pod.inner.lifecycle(&mut inner_ctx, event);
-            });
+                    widget_pod.inner.lifecycle(&mut inner_ctx, event);
+                    if let LifeCycle::AnimFrame(interval) = event {
+                        widget_pod.inner.on_anim_frame(&mut inner_ctx, *interval);
+                    }
+                },
+            );
         }
 
         if let Some(event) = extra_event.as_ref() {
@@ -755,6 +968,15 @@ impl<W: Widget> WidgetPod<W> {
         match event {
             // we need to (re)register children in case of one of the following events
             LifeCycle::WidgetAdded | LifeCycle::Internal(InternalLifeCycle::RouteWidgetAdded) => {
+                let index = &mut parent_ctx.global_state.widget_index;
+                if matches!(event, LifeCycle::WidgetAdded)
+                    || self.state.children_changed
+                    || !index.contains(self.id())
+                {
+                    // The root widget is its own parent.
+                    let parent = Some(parent_ctx.widget_state.id).filter(|id| *id != self.id());
+                    index.update(self.as_dyn(), parent);
+                }
                 self.state.children_changed = false;
                 parent_ctx.widget_state.children =
                     parent_ctx.widget_state.children.union(self.state.children);
@@ -774,6 +996,10 @@ impl<W: Widget> WidgetPod<W> {
                 // recursions.
                 self.state.is_explicitly_disabled_new = self.state.is_explicitly_disabled;
             }
+            LifeCycle::TextStyleChanged
+            | LifeCycle::Internal(InternalLifeCycle::RouteTextStyleChanged) => {
+                self.state.children_text_style_changed = false;
+            }
             // Update focus-chain of our parent
             LifeCycle::BuildFocusChain => {
                 self.state.update_focus_chain = false;
@@ -789,11 +1015,24 @@ impl<W: Widget> WidgetPod<W> {
                 }
                 self.state.has_focus = had_focus;
 
-                if !self.state.is_disabled() {
-                    parent_ctx
-                        .widget_state
-                        .focus_chain
-                        .extend(&self.state.focus_chain);
+                // Stashed widgets, e.g. the content of a closed popover, can't be
+                // focused with Tab, and neither can widgets with a negative tab index.
+                let tab_index = self.state.tab_index;
+                if !self.state.is_disabled()
+                    && !self.state.is_stashed
+                    && !matches!(tab_index, Some(tab_index) if tab_index < 0)
+                {
+                    let parent_state = &mut *parent_ctx.widget_state;
+                    parent_state.focus_chain.extend(&self.state.focus_chain);
+                    match tab_index {
+                        // The tab index of a widget applies to all of its descendants.
+                        Some(tab_index) => parent_state
+                            .focus_chain_tab_indices
+                            .extend(self.state.focus_chain.iter().map(|_| tab_index)),
+                        None => parent_state
+                            .focus_chain_tab_indices
+                            .extend(&self.state.focus_chain_tab_indices),
+                    }
                 }
             }
             _ => (),
@@ -813,6 +1052,22 @@ impl<W: Widget> WidgetPod<W> {
         parent_ctx.global_state.debug_logger.pop_span();
     }
 
+    /// Resolve the text style of the widget from its own and its parent's.
+    ///
+    /// Returns whether it changed.
+    fn update_resolved_text_style(&mut self, parent_ctx: &LifeCycleCtx) -> bool {
+        self.state.text_style_changed = false;
+        let resolved = self
+            .state
+            .text_style
+            .resolve(&parent_ctx.widget_state.resolved_text_style);
+        if resolved == self.state.resolved_text_style {
+            return false;
+        }
+        self.state.resolved_text_style = resolved;
+        true
+    }
+
     // --- LAYOUT ---
 
     /// Compute layout of a widget.
@@ -843,28 +1098,54 @@ impl<W: Widget> WidgetPod<W> {
         self.mark_as_visited();
         self.check_initialized("layout");
 
+        // The accessibility node only needs to be rebuilt if the widget (or one of
+        // its descendants) requested this layout, or if its size changes below.
+        // Otherwise, re-laying out every child of a large container would
+        // rebuild every node of the container.
+        let requested_layout = self.state.needs_layout;
+        if requested_layout {
+            parent_ctx.global_state.layout_stats.last_requested_widgets += 1;
+        }
+
         self.state.needs_layout = false;
         self.state.is_expecting_place_child_call = true;
-        // TODO - Not everything that has been re-laid out needs to be repainted.
-        self.state.needs_paint = true;
-        self.state.request_accessibility_update = true;
-        self.state.needs_accessibility_update = true;
+        // A cached scene is kept unless the widget asked for this layout, or its paint
+        // rect changes below.
+        if !self.state.is_paint_cached || requested_layout {
+            self.state.needs_paint = true;
+        }
+        let previous_paint_rect = self.state.local_paint_rect;
 
         bc.debug_check(self.inner.short_type_name());
 
         self.state.local_paint_rect = Rect::ZERO;
 
-        let new_size = self.call_widget_method_with_checks("layout", |widget_pod| {
-            // widget_pod is a reborrow of `self`
+        let measure = parent_ctx.global_state.frame_budget.start_widget();
+        let new_size = self.call_widget_method_with_checks(
+            "layout",
+            parent_ctx.global_state,
+            |widget_pod, global_state| {
+                // widget_pod is a reborrow of `self`
 
-            let mut inner_ctx = LayoutCtx {
-                widget_state: &mut widget_pod.state,
-                global_state: parent_ctx.global_state,
-                mouse_pos: parent_ctx.mouse_pos,
-            };
+                let layout_direction = widget_pod
+                    .state
+                    .layout_direction
+                    .unwrap_or(parent_ctx.layout_direction);
+                let mut inner_ctx = LayoutCtx {
+                    widget_state: &mut widget_pod.state,
+                    global_state,
+                    mouse_pos: parent_ctx.mouse_pos,
+                    layout_direction,
+                };
 
-            widget_pod.inner.layout(&mut inner_ctx, bc)
-        });
+                widget_pod.inner.layout(&mut inner_ctx, bc)
+            },
+        );
+        parent_ctx.global_state.frame_budget.end_widget(
+            measure,
+            self.state.id,
+            self.inner.short_type_name(),
+        );
 
         self.state.local_paint_rect = self
             .state
@@ -884,9 +1165,11 @@ impl<W: Widget> WidgetPod<W> {
                 }
 
                 // TODO - This check might be redundant with the code updating local_paint_rect
+                // Stashed children aren't painted, and keep the rect of their last layout.
                 let child_rect = child.state().paint_rect();
                 if !rect_contains(&self.state.local_paint_rect, &child_rect)
                     && !self.state.is_portal
+                    && !child.state().is_stashed
                 {
                     debug_panic!(
                         "Error in '{}' #{}: paint_rect {:?} doesn't contain paint_rect {:?} of child widget '{}' #{}",
@@ -911,6 +1194,14 @@ impl<W: Widget> WidgetPod<W> {
         // size is (0,0)
         // See issue #4
 
+        if requested_layout || new_size != self.state.size {
+            self.state.request_accessibility_update = true;
+            self.state.needs_accessibility_update = true;
+        }
+        if new_size != self.state.size || self.state.local_paint_rect != previous_paint_rect {
+            self.state.needs_paint = true;
+        }
+
         parent_ctx.widget_state.merge_up(&mut self.state);
         self.state.size = new_size;
         self.log_layout_issues(new_size);
@@ -929,6 +1220,78 @@ impl<W: Widget> WidgetPod<W> {
         new_size
     }
 
+    /// Query the [`Widget::min_intrinsic_width`] of the inner widget.
+    ///
+    /// This must be called during the parent's layout, before [`layout`](Self::layout)
+    /// is called on this pod.
+    pub fn min_intrinsic_width(&mut self, parent_ctx: &mut LayoutCtx, height: f64) -> f64 {
+        self.intrinsic_size("min_intrinsic_width", parent_ctx, |widget, ctx| {
+            widget.min_intrinsic_width(ctx, height)
+        })
+    }
+
+    /// Query the [`Widget::max_intrinsic_width`] of the inner widget.
+    ///
+    /// This must be called during the parent's layout, before [`layout`](Self::layout)
+    /// is called on this pod.
+    pub fn max_intrinsic_width(&mut self, parent_ctx: &mut LayoutCtx, height: f64) -> f64 {
+        self.intrinsic_size("max_intrinsic_width", parent_ctx, |widget, ctx| {
+            widget.max_intrinsic_width(ctx, height)
+        })
+    }
+
+    /// Query the [`Widget::min_intrinsic_height`] of the inner widget.
+    ///
+    /// This must be called during the parent's layout, before [`layout`](Self::layout)
+    /// is called on this pod.
+    pub fn min_intrinsic_height(&mut self, parent_ctx: &mut LayoutCtx, width: f64) -> f64 {
+        self.intrinsic_size("min_intrinsic_height", parent_ctx, |widget, ctx| {
+            widget.min_intrinsic_height(ctx, width)
+        })
+    }
+
+    /// Query the [`Widget::max_intrinsic_height`] of the inner widget.
+    ///
+    /// This must be called during the parent's layout, before [`layout`](Self::layout)
+    /// is called on this pod.
+    pub fn max_intrinsic_height(&mut self, parent_ctx: &mut LayoutCtx, width: f64) -> f64 {
+        self.intrinsic_size("max_intrinsic_height", parent_ctx, |widget, ctx| {
+            widget.max_intrinsic_height(ctx, width)
+        })
+    }
+
+    fn intrinsic_size(
+        &mut self,
+        method_name: &str,
+        parent_ctx: &mut LayoutCtx,
+        query: impl FnOnce(&mut W, &mut LayoutCtx) -> f64,
+    ) -> f64 {
+        let _span = self.inner.make_trace_span().entered();
+
+        if self.state.is_stashed {
+            debug_panic!(
+                "Error in '{}' #{}: trying to compute {} of stashed widget.",
+                self.inner.short_type_name(),
+                self.state().id.to_raw(),
+                method_name,
+            );
+            return 0.0;
+        }
+        self.check_initialized(method_name);
+
+        let layout_direction = self
+            .state
+            .layout_direction
+            .unwrap_or(parent_ctx.layout_direction);
+        let mut inner_ctx = LayoutCtx {
+            widget_state: &mut self.state,
+            global_state: parent_ctx.global_state,
+            mouse_pos: parent_ctx.mouse_pos,
+            layout_direction,
+        };
+        query(&mut self.inner, &mut inner_ctx)
+    }
+
     fn log_layout_issues(&self, size: Size) {
         if size.width.is_infinite() {
             let name = self.inner.type_name();
@@ -968,33 +1331,98 @@ impl<W: Widget> WidgetPod<W> {
         self.mark_as_visited();
         self.check_initialized("paint");
 
+        // The badge may overflow the widget, but not the window.
+        let window_rect = Rect::from_origin_size(
+            Point::ORIGIN - self.state.window_origin().to_vec2(),
+            parent_ctx.global_state.window_size,
+        );
         if self.state.needs_paint {
+            // The widget or its badge changed, so the badge may have moved.
+            self.state.badge_bounds = self.badge_bounds(parent_ctx.global_state);
+
+            // Both the area the widget covered when it was last painted and the area it
+            // now covers must be redrawn.
+            let local_paint_rect = match self.state.badge_bounds {
+                Some(bounds) => self
+                    .state
+                    .local_paint_rect
+                    .union(bounds.intersect(window_rect)),
+                None => self.state.local_paint_rect,
+            };
+            let window_paint_rect = local_paint_rect + self.state.window_origin().to_vec2();
+            if let Some(old_rect) = self.state.window_paint_rect.replace(window_paint_rect) {
+                if old_rect != window_paint_rect {
+                    parent_ctx.global_state.add_paint_damage(old_rect);
+                }
+            }
+            parent_ctx.global_state.add_paint_damage(window_paint_rect);
+        }
+
+        let badge_bounds = self.state.badge_bounds;
+
+        // A widget whose descendant requested a paint is painted again to pick up the
+        // new scene of that descendant, but its own area isn't marked as damaged.
+        if self.state.wants_paint() {
             self.state.needs_paint = false;
-            self.call_widget_method_with_checks("paint", |widget_pod| {
-                // TODO - Handle invalidation regions
-                let mut inner_ctx = PaintCtx {
-                    global_state: parent_ctx.global_state,
-                    widget_state: &widget_pod.state,
-                    depth: parent_ctx.depth + 1,
-                    debug_paint: parent_ctx.debug_paint,
-                    debug_widget: parent_ctx.debug_widget,
-                };
+            self.state.request_paint = false;
+            let measure = parent_ctx.global_state.frame_budget.start_widget();
+            self.call_widget_method_with_checks(
+                "paint",
+                parent_ctx.global_state,
+                |widget_pod, global_state| {
+                    let mut inner_ctx = PaintCtx {
+                        global_state,
+                        widget_state: &widget_pod.state,
+                        depth: parent_ctx.depth + 1,
+                        debug_paint: parent_ctx.debug_paint,
+                        debug_widget: parent_ctx.debug_widget,
+                    };
 
-                widget_pod.fragment.reset();
-                widget_pod
-                    .inner
-                    .paint(&mut inner_ctx, &mut widget_pod.fragment);
+                    widget_pod.fragment.reset();
+                    widget_pod
+                        .inner
+                        .paint(&mut inner_ctx, &mut widget_pod.fragment);
 
-                if parent_ctx.debug_paint {
-                    widget_pod.debug_paint_layout_bounds(widget_pod.state.size);
-                }
-            });
+                    if let (Some(badge), Some(bounds)) = (&widget_pod.state.badge, badge_bounds) {
+                        let text_layout = badge.text_content().map(|text| {
+                            global_state
+                                .badge_text
+                                .layout(text, &mut global_state.font_context)
+                        });
+                        let scene = &mut widget_pod.fragment;
+                        scene.push_layer(BlendMode::default(), 1., Affine::IDENTITY, &window_rect);
+                        badge.paint(scene, bounds, text_layout);
+                        scene.pop_layer();
+                    }
+
+                    if parent_ctx.debug_paint {
+                        widget_pod.debug_paint_layout_bounds(widget_pod.state.size);
+                    }
+                },
+            );
+            parent_ctx.global_state.frame_budget.end_widget(
+                measure,
+                self.state.id,
+                self.inner.short_type_name(),
+            );
         }
 
         let transform = Affine::translate(self.state.origin.to_vec2());
         scene.append(&self.fragment, Some(transform));
     }
 
+    /// The area covered by the badge of this widget, if any, in local coordinates.
+    fn badge_bounds(&self, global_state: &mut RenderRootState) -> Option<Rect> {
+        let badge = self.state.badge.as_ref()?;
+        let text_size = badge.text_content().map(|text| {
+            global_state
+                .badge_text
+                .layout(text, &mut global_state.font_context)
+                .size()
+        });
+        Some(badge.bounds(self.state.size, text_size))
+    }
+
     fn debug_paint_layout_bounds(&mut self, size: Size) {
         const BORDER_WIDTH: f64 = 1.0;
         let rect = size.to_rect().inset(BORDER_WIDTH / -2.0);
@@ -1007,47 +1435,91 @@ impl<W: Widget> WidgetPod<W> {
     pub fn accessibility(&mut self, parent_ctx: &mut AccessCtx) {
         let _span = self.inner.make_trace_span().entered();
 
-        // TODO
-        // if self.state.is_stashed {}
-
         // TODO - explain this
         self.mark_as_visited();
         self.check_initialized("accessibility");
 
+        let is_hidden = self.state.is_stashed
+            || is_far_outside(self.state.window_layout_rect(), parent_ctx.viewport);
+        if is_hidden && parent_ctx.global_state.prune_hidden_access_nodes {
+            // Hidden subtrees are reduced to a single node with no children, which is
+            // marked as hidden if stashed, and otherwise only has its off-screen bounds.
+            if parent_ctx.rebuild_all || self.state.needs_accessibility_update {
+                let mut node = self.build_access_node(parent_ctx.scale_factor);
+                node.set_children(Vec::<NodeId>::new());
+                parent_ctx
+                    .tree_update
+                    .nodes
+                    .push((self.state.id.into(), node.build()));
+            }
+            self.state.is_access_pruned = true;
+            self.state.request_accessibility_update = false;
+            self.state.needs_accessibility_update = false;
+            return;
+        }
+
+        // A subtree that was pruned has to be emitted in full again.
+        let rebuild_all = parent_ctx.rebuild_all || self.state.is_access_pruned;
+        self.state.is_access_pruned = false;
+
         // If this widget or a child has requested an accessibility update,
         // or if AccessKit has requested a full rebuild,
         // we call the accessibility method on this widget.
-        if parent_ctx.rebuild_all || self.state.request_accessibility_update {
+        // Its node is only emitted if the widget itself changed: otherwise the
+        // method is only called to reach the children, and AccessKit keeps the
+        // node it already has.
+        if rebuild_all || self.state.request_accessibility_update {
+            let emit_node = rebuild_all || self.state.needs_accessibility_update;
             trace!(
                 "Building accessibility node for widget '{}' #{}",
                 self.inner.short_type_name(),
                 self.state.id.to_raw()
             );
 
-            self.call_widget_method_with_checks("accessibility", |widget_pod| {
-                let current_node = widget_pod.build_access_node(parent_ctx.scale_factor);
-                let mut inner_ctx = AccessCtx {
-                    global_state: parent_ctx.global_state,
-                    widget_state: &mut widget_pod.state,
-                    tree_update: parent_ctx.tree_update,
-                    current_node,
-                    rebuild_all: parent_ctx.rebuild_all,
-                    scale_factor: parent_ctx.scale_factor,
-                };
-                widget_pod.inner.accessibility(&mut inner_ctx);
+            self.call_widget_method_with_checks(
+                "accessibility",
+                parent_ctx.global_state,
+                |widget_pod, global_state| {
+                    let current_node = widget_pod.build_access_node(parent_ctx.scale_factor);
+                    let mut inner_ctx = AccessCtx {
+                        global_state,
+                        widget_state: &mut widget_pod.state,
+                        tree_update: parent_ctx.tree_update,
+                        current_node,
+                        rebuild_all,
+                        scale_factor: parent_ctx.scale_factor,
+                        viewport: parent_ctx.viewport,
+                    };
+                    widget_pod.inner.accessibility(&mut inner_ctx);
 
-                let id: NodeId = inner_ctx.widget_state.id.into();
-                trace!(
-                    "Built node #{} with role={:?}, default_action={:?}",
-                    id.0,
-                    inner_ctx.current_node.role(),
-                    inner_ctx.current_node.default_action_verb(),
-                );
-                inner_ctx
-                    .tree_update
-                    .nodes
-                    .push((id, inner_ctx.current_node.build()));
-            });
+                    if !emit_node {
+                        return;
+                    }
+                    let badge_description = inner_ctx
+                        .widget_state
+                        .badge
+                        .as_ref()
+                        .and_then(Badge::description);
+                    if let Some(suffix) = badge_description {
+                        let description = match inner_ctx.current_node.description() {
+                            Some(description) => format!("{description}, {suffix}"),
+                            None => suffix.to_string(),
+                        };
+                        inner_ctx.current_node.set_description(description);
+                    }
+                    let id: NodeId = inner_ctx.widget_state.id.into();
+                    trace!(
+                        "Built node #{} with role={:?}, default_action={:?}",
+                        id.0,
+                        inner_ctx.current_node.role(),
+                        inner_ctx.current_node.default_action_verb(),
+                    );
+                    inner_ctx
+                        .tree_update
+                        .nodes
+                        .push((id, inner_ctx.current_node.build()));
+                },
+            );
         }
 
         self.state.request_accessibility_update = false;
@@ -1061,9 +1533,11 @@ impl<W: Widget> WidgetPod<W> {
             scale_factor,
         ));
 
+        // Screen readers follow the order of the children for linear navigation.
+        let mut children = self.inner.children();
+        children.sort_by_key(|child| child.state().reading_order.unwrap_or(0));
         node.set_children(
-            self.inner
-                .children()
+            children
                 .iter()
                 .map(|pod| pod.id().into())
                 .collect::<Vec<NodeId>>(),
@@ -1088,6 +1562,12 @@ fn to_accesskit_rect(r: Rect, scale_factor: f64) -> accesskit::Rect {
     accesskit::Rect::new(s * r.x0, s * r.y0, s * r.x1, s * r.y1)
 }
 
+/// Return `true` if `rect` is further away from `viewport` than the size of `viewport`.
+fn is_far_outside(rect: Rect, viewport: Rect) -> bool {
+    let margin = viewport.inflate(viewport.width(), viewport.height());
+    rect.x1 < margin.x0 || rect.x0 > margin.x1 || rect.y1 < margin.y0 || rect.y0 > margin.y1
+}
+
 // TODO - negative rects?
 /// Return `true` if all of `smaller` is within `larger`.
 fn rect_contains(larger: &Rect, smaller: &Rect) -> bool {
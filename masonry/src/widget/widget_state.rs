@@ -9,7 +9,7 @@ use crate::bloom::Bloom;
 use crate::kurbo::{Insets, Point, Rect, Size};
 use crate::text_helpers::TextFieldRegistration;
 use crate::widget::CursorChange;
-use crate::{CursorIcon, WidgetId};
+use crate::{Badge, CursorIcon, LayoutDirection, ResolvedTextStyle, TextStyle, WidgetId};
 
 // FIXME #5 - Make a note documenting this: the only way to get a &mut WidgetState should be in a pass.
 // A pass should reborrow the parent widget state (to avoid crossing wires) and call merge_up at
@@ -59,8 +59,35 @@ pub struct WidgetState {
     /// the baseline. Widgets that contain text or controls that expect to be
     /// laid out alongside text can set this as appropriate.
     pub(crate) baseline_offset: f64,
+    /// The area of the text being edited, in local coordinates, which the platform's
+    /// input method editor is placed next to while this widget is focused.
+    pub(crate) ime_area: Option<Rect>,
     // TODO - Document
     pub(crate) is_portal: bool,
+    /// The widget handles [`LifeCycle::ViewportChanged`](crate::LifeCycle::ViewportChanged).
+    pub(crate) is_viewport_observer: bool,
+    /// A descendant handles [`LifeCycle::ViewportChanged`](crate::LifeCycle::ViewportChanged).
+    ///
+    /// This is never reset, so it may stay set after these descendants are removed.
+    pub(crate) has_viewport_observers: bool,
+    /// The scene of this widget is only painted again on request or when its size changes.
+    pub(crate) is_paint_cached: bool,
+    /// The paint rect in window coordinates when the widget was last painted, so that
+    /// the area it leaves can be repainted if it moves or shrinks.
+    pub(crate) window_paint_rect: Option<Rect>,
+    /// The layout direction of this widget and its descendants, if it overrides the
+    /// one of its parent.
+    pub(crate) layout_direction: Option<LayoutDirection>,
+    /// The badge painted above this widget, if any.
+    pub(crate) badge: Option<Badge>,
+    /// The area covered by the badge, in local coordinates.
+    ///
+    /// This is updated when the widget is painted after a change of its size or badge.
+    pub(crate) badge_bounds: Option<Rect>,
+    /// The text style set on this widget, inherited by its descendants.
+    pub(crate) text_style: TextStyle,
+    /// The text style of this widget, resolved from its own and its ancestors'.
+    pub(crate) resolved_text_style: ResolvedTextStyle,
 
     // --- PASSES ---
 
@@ -79,6 +106,13 @@ pub struct WidgetState {
     // LifeCycle::DisabledChanged or InternalLifeCycle::RouteDisabledChanged
     pub(crate) is_explicitly_disabled_new: bool,
 
+    // `true` if this widget's text style changed, but `resolved_text_style` wasn't updated
+    // by LifeCycle::TextStyleChanged or InternalLifeCycle::RouteTextStyleChanged yet.
+    pub(crate) text_style_changed: bool,
+
+    // `true` if a descendant of this widget changed its text style.
+    pub(crate) children_text_style_changed: bool,
+
     pub(crate) needs_layout: bool,
     pub(crate) needs_paint: bool,
     pub(crate) needs_accessibility_update: bool,
@@ -86,15 +120,34 @@ pub struct WidgetState {
     /// Because of some scrolling or something, `parent_window_origin` needs to be updated.
     pub(crate) needs_window_origin: bool,
 
+    /// Any descendant has requested a paint pass.
+    ///
+    /// Unlike `needs_paint`, this doesn't mark the widget's own area as damaged, but its
+    /// scene still has to be rebuilt to include the new scenes of its children.
+    pub(crate) request_paint: bool,
+
     /// Any descendant has requested an animation frame.
     pub(crate) request_anim: bool,
 
     /// Any descendant has requested an accessibility update.
     pub(crate) request_accessibility_update: bool,
 
+    /// This widget was stashed or scrolled far out of view, and its accessibility
+    /// subtree was reduced to a single node.
+    pub(crate) is_access_pruned: bool,
+
     pub(crate) update_focus_chain: bool,
 
     pub(crate) focus_chain: Vec<WidgetId>,
+    /// The tab index of each widget of `focus_chain`, which the chain is sorted by in
+    /// the nearest focus scope.
+    pub(crate) focus_chain_tab_indices: Vec<i32>,
+    /// The tab index set on this widget, see
+    /// [`WidgetPod::with_tab_index`](crate::WidgetPod::with_tab_index).
+    pub(crate) tab_index: Option<i32>,
+    /// The reading order set on this widget, see
+    /// [`WidgetPod::with_reading_order`](crate::WidgetPod::with_reading_order).
+    pub(crate) reading_order: Option<u32>,
 
     pub(crate) children: Bloom<WidgetId>,
     pub(crate) children_changed: bool,
@@ -155,11 +208,21 @@ impl WidgetState {
             paint_insets: Insets::ZERO,
             local_paint_rect: Rect::ZERO,
             is_portal: false,
+            is_viewport_observer: false,
+            has_viewport_observers: false,
+            is_paint_cached: false,
+            window_paint_rect: None,
+            layout_direction: None,
+            badge: None,
+            badge_bounds: None,
+            text_style: TextStyle::default(),
+            resolved_text_style: ResolvedTextStyle::default(),
             is_new: true,
             children_disabled_changed: false,
             ancestor_disabled: false,
             is_explicitly_disabled: false,
             baseline_offset: 0.0,
+            ime_area: None,
             is_hot: false,
             needs_layout: false,
             needs_paint: false,
@@ -168,14 +231,21 @@ impl WidgetState {
             is_active: false,
             has_active: false,
             has_focus: false,
+            request_paint: false,
             request_anim: false,
             request_accessibility_update: false,
+            is_access_pruned: false,
             focus_chain: Vec::new(),
+            focus_chain_tab_indices: Vec::new(),
+            tab_index: None,
+            reading_order: None,
             children: Bloom::new(),
             children_changed: false,
             cursor_change: CursorChange::Default,
             cursor: None,
             is_explicitly_disabled_new: false,
+            text_style_changed: false,
+            children_text_style_changed: false,
             text_registrations: Vec::new(),
             update_focus_chain: false,
             is_stashed: false,
@@ -208,22 +278,51 @@ impl WidgetState {
             || self.is_explicitly_disabled != self.is_explicitly_disabled_new
     }
 
+    pub(crate) fn tree_text_style_changed(&self) -> bool {
+        self.children_text_style_changed || self.text_style_changed
+    }
+
     /// Update to incorporate state changes from a child.
     ///
     /// This will also clear some requests in the child state.
     ///
     /// This method is idempotent and can be called multiple times.
+    pub(crate) fn clear_focus_chain(&mut self) {
+        self.focus_chain.clear();
+        self.focus_chain_tab_indices.clear();
+    }
+
+    /// Sort the focus chain by tab index, keeping the tree order of the widgets with the
+    /// same index.
+    ///
+    /// This is done by focus scopes: the chain of a scope is then ordered as a whole in
+    /// the chain of the enclosing scope, by the tab index of the scope itself.
+    pub(crate) fn sort_focus_chain(&mut self) {
+        let mut entries: Vec<_> = self
+            .focus_chain
+            .iter()
+            .copied()
+            .zip(self.focus_chain_tab_indices.iter().copied())
+            .collect();
+        entries.sort_by_key(|(_, tab_index)| *tab_index);
+        self.focus_chain = entries.into_iter().map(|(id, _)| id).collect();
+        self.focus_chain_tab_indices = vec![0; self.focus_chain.len()];
+    }
+
     pub(crate) fn merge_up(&mut self, child_state: &mut WidgetState) {
         self.needs_layout |= child_state.needs_layout;
-        self.needs_paint |= child_state.needs_paint;
+        self.request_paint |= child_state.needs_paint || child_state.request_paint;
         self.needs_window_origin |= child_state.needs_window_origin;
         self.request_anim |= child_state.request_anim;
         self.request_accessibility_update |= child_state.request_accessibility_update;
         self.children_disabled_changed |= child_state.children_disabled_changed;
         self.children_disabled_changed |=
             child_state.is_explicitly_disabled_new != child_state.is_explicitly_disabled;
+        self.children_text_style_changed |= child_state.tree_text_style_changed();
         self.has_active |= child_state.has_active;
         self.has_focus |= child_state.has_focus;
+        self.has_viewport_observers |=
+            child_state.is_viewport_observer || child_state.has_viewport_observers;
         self.children_changed |= child_state.children_changed;
         self.text_registrations
             .append(&mut child_state.text_registrations);
@@ -282,6 +381,11 @@ impl WidgetState {
     pub(crate) fn window_origin(&self) -> Point {
         self.parent_window_origin + self.origin.to_vec2()
     }
+
+    /// Whether the widget or one of its descendants needs to be painted.
+    pub(crate) fn wants_paint(&self) -> bool {
+        self.needs_paint || self.request_paint
+    }
 }
 
 impl Clone for VisitBool {
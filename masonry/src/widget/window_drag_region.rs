@@ -0,0 +1,227 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! A widget that moves the window when it's dragged, like a title bar.
+
+use std::time::Duration;
+
+use accesskit::Role;
+use instant::Instant;
+use smallvec::{smallvec, SmallVec};
+use tracing::{trace, trace_span, Span};
+use vello::Scene;
+use winit::event::MouseButton;
+
+use crate::widget::draggable::is_drag;
+use crate::widget::{WidgetMut, WidgetPod, WidgetRef};
+use crate::{
+    AccessCtx, AccessEvent, BoxConstraints, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx,
+    Point, PointerEvent, Size, StatusChange, TextEvent, Widget,
+};
+
+/// The longest delay between two clicks making a double click.
+const DOUBLE_CLICK_DELAY: Duration = Duration::from_millis(500);
+
+/// A widget that moves the window when its child is dragged, to build custom title bars.
+///
+/// Pressing the left mouse button and moving the pointer further than a small threshold
+/// starts a native window move. Double clicking maximizes the window, or restores it.
+/// Presses handled by a descendant, e.g. a button in the title bar, are left alone.
+pub struct WindowDragRegion<W> {
+    child: WidgetPod<W>,
+    /// Where the left button was pressed, while the press could still be a click.
+    press_origin: Option<Point>,
+    /// When the last click was released, to detect double clicks.
+    last_click: Option<Instant>,
+}
+
+impl<W: Widget> WindowDragRegion<W> {
+    /// Create a new drag region around `child`.
+    pub fn new(child: W) -> Self {
+        Self::new_pod(WidgetPod::new(child))
+    }
+
+    /// Create a new drag region around the child `child`.
+    pub fn new_pod(child: WidgetPod<W>) -> Self {
+        WindowDragRegion {
+            child,
+            press_origin: None,
+            last_click: None,
+        }
+    }
+}
+
+impl<W: Widget> WidgetMut<'_, WindowDragRegion<W>> {
+    pub fn child_mut(&mut self) -> WidgetMut<'_, W> {
+        self.ctx.get_mut(&mut self.widget.child)
+    }
+}
+
+impl<W: Widget> Widget for WindowDragRegion<W> {
+    fn on_pointer_event(&mut self, ctx: &mut EventCtx, event: &PointerEvent) {
+        self.child.on_pointer_event(ctx, event);
+        if ctx.is_handled() {
+            return;
+        }
+
+        let state = event.pointer_state();
+        let position = Point::new(state.position.x, state.position.y);
+        match event {
+            PointerEvent::PointerDown(MouseButton::Left, _) => {
+                if self.child.has_active() {
+                    return;
+                }
+                let is_double_click = self
+                    .last_click
+                    .take()
+                    .is_some_and(|last_click| last_click.elapsed() <= DOUBLE_CLICK_DELAY);
+                if is_double_click {
+                    trace!("WindowDragRegion double clicked");
+                    ctx.toggle_window_maximized();
+                } else {
+                    self.press_origin = Some(position);
+                    ctx.set_active(true);
+                }
+            }
+            PointerEvent::PointerMove(_) => {
+                if let Some(press_origin) = self.press_origin {
+                    if is_drag(press_origin, position) {
+                        trace!("WindowDragRegion dragged");
+                        self.press_origin = None;
+                        ctx.set_active(false);
+                        ctx.drag_window();
+                    }
+                }
+            }
+            PointerEvent::PointerUp(MouseButton::Left, _) => {
+                if self.press_origin.take().is_some() {
+                    self.last_click = Some(Instant::now());
+                    ctx.set_active(false);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn on_text_event(&mut self, ctx: &mut EventCtx, event: &TextEvent) {
+        self.child.on_text_event(ctx, event);
+    }
+
+    fn on_access_event(&mut self, ctx: &mut EventCtx, event: &AccessEvent) {
+        self.child.on_access_event(ctx, event);
+    }
+
+    fn on_status_change(&mut self, _ctx: &mut LifeCycleCtx, _event: &StatusChange) {}
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle) {
+        self.child.lifecycle(ctx, event);
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints) -> Size {
+        let size = self.child.layout(ctx, bc);
+        ctx.place_child(&mut self.child, Point::ORIGIN);
+        ctx.set_paint_insets(self.child.compute_parent_paint_insets(size));
+        ctx.set_baseline_offset(self.child.baseline_offset());
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, scene: &mut Scene) {
+        self.child.paint(ctx, scene);
+    }
+
+    fn accessibility_role(&self) -> Role {
+        Role::TitleBar
+    }
+
+    fn accessibility(&mut self, ctx: &mut AccessCtx) {
+        self.child.accessibility(ctx);
+    }
+
+    fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
+        smallvec![self.child.as_dyn()]
+    }
+
+    fn make_trace_span(&self) -> Span {
+        trace_span!("WindowDragRegion")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render_root::RenderRootSignal;
+    use crate::testing::{widget_ids, TestHarness};
+    use crate::widget::{Button, Flex, SizedBox};
+
+    fn title_bar() -> impl Widget {
+        WindowDragRegion::new(
+            SizedBox::new(Flex::row().with_child(Button::new("Close")))
+                .width(300.)
+                .height(30.),
+        )
+    }
+
+    fn drag_requested(harness: &mut TestHarness) -> bool {
+        harness
+            .pop_signal_matching(|signal| matches!(signal, RenderRootSignal::DragWindow))
+            .is_some()
+    }
+
+    fn maximize_requested(harness: &mut TestHarness) -> bool {
+        harness
+            .pop_signal_matching(|signal| matches!(signal, RenderRootSignal::ToggleMaximized))
+            .is_some()
+    }
+
+    #[test]
+    fn drag_threshold() {
+        assert!(!is_drag(Point::new(10., 10.), Point::new(13., 10.)));
+        assert!(!is_drag(Point::new(10., 10.), Point::new(12., 12.)));
+        assert!(is_drag(Point::new(10., 10.), Point::new(10., 15.)));
+    }
+
+    #[test]
+    fn small_moves_are_clicks() {
+        let mut harness = TestHarness::create_with_size(title_bar(), Size::new(400., 400.));
+
+        harness.mouse_move((200., 15.));
+        harness.mouse_button_press(MouseButton::Left);
+        harness.mouse_move((202., 16.));
+        assert!(!drag_requested(&mut harness));
+
+        harness.mouse_move((220., 16.));
+        assert!(drag_requested(&mut harness));
+        // The drag is only requested once per press.
+        harness.mouse_move((240., 16.));
+        harness.mouse_button_release(MouseButton::Left);
+        assert!(!drag_requested(&mut harness));
+    }
+
+    #[test]
+    fn double_click_toggles_maximized() {
+        let mut harness = TestHarness::create_with_size(title_bar(), Size::new(400., 400.));
+
+        harness.mouse_move((200., 15.));
+        harness.mouse_button_press(MouseButton::Left);
+        harness.mouse_button_release(MouseButton::Left);
+        assert!(!maximize_requested(&mut harness));
+        harness.mouse_button_press(MouseButton::Left);
+        harness.mouse_button_release(MouseButton::Left);
+        assert!(maximize_requested(&mut harness));
+        assert!(!drag_requested(&mut harness));
+    }
+
+    #[test]
+    fn presses_on_buttons_are_ignored() {
+        let [button_id] = widget_ids();
+        let title_bar =
+            WindowDragRegion::new(Flex::row().with_child_id(Button::new("Close"), button_id));
+        let mut harness = TestHarness::create_with_size(title_bar, Size::new(400., 400.));
+
+        harness.mouse_move_to(button_id);
+        harness.mouse_button_press(MouseButton::Left);
+        harness.mouse_move((300., 300.));
+        harness.mouse_button_release(MouseButton::Left);
+        assert!(!drag_requested(&mut harness));
+    }
+}
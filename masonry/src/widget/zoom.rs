@@ -0,0 +1,203 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! A widget that scales its child.
+
+use accesskit::Role;
+use smallvec::{smallvec, SmallVec};
+use tracing::{trace, trace_span, Span};
+use vello::Scene;
+
+use crate::kurbo::{Affine, Insets};
+use crate::render_root::{MAX_ZOOM, MIN_ZOOM};
+use crate::widget::{WidgetMut, WidgetPod, WidgetRef};
+use crate::{
+    AccessCtx, AccessEvent, BoxConstraints, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx,
+    Point, PointerEvent, Size, StatusChange, TextEvent, Widget,
+};
+
+/// A widget that lays out its child in a smaller (or larger) space, and scales it
+/// to fill its own size.
+///
+/// With a zoom of 2, a child which is 100 pixels wide is painted 200 pixels wide.
+/// Pointer events are mapped into the coordinates of the child, so hit-testing
+/// follows the painted content.
+///
+/// To zoom a whole window, use [`RenderRoot::set_zoom`](crate::render_root::RenderRoot::set_zoom)
+/// instead.
+///
+/// Descendants are unaware of the zoom: their window layout rects, the paint damage
+/// they cause and the bounds of their accessibility nodes are those of the unscaled
+/// content.
+pub struct Zoom<W> {
+    child: WidgetPod<W>,
+    zoom: f64,
+}
+
+impl<W: Widget> Zoom<W> {
+    /// Create a new widget scaling `child` by `zoom`.
+    ///
+    /// The zoom is clamped between [`MIN_ZOOM`] and [`MAX_ZOOM`].
+    pub fn new(child: W, zoom: f64) -> Self {
+        Self::new_pod(WidgetPod::new(child), zoom)
+    }
+
+    /// Create a new widget scaling the child `child` by `zoom`.
+    pub fn new_pod(child: WidgetPod<W>, zoom: f64) -> Self {
+        Zoom {
+            child,
+            zoom: zoom.clamp(MIN_ZOOM, MAX_ZOOM),
+        }
+    }
+
+    /// The zoom factor applied to the child.
+    pub fn zoom(&self) -> f64 {
+        self.zoom
+    }
+}
+
+impl<W: Widget> WidgetMut<'_, Zoom<W>> {
+    /// Set the zoom factor applied to the child.
+    ///
+    /// The zoom is clamped between [`MIN_ZOOM`] and [`MAX_ZOOM`].
+    pub fn set_zoom(&mut self, zoom: f64) {
+        let zoom = zoom.clamp(MIN_ZOOM, MAX_ZOOM);
+        if self.widget.zoom == zoom {
+            return;
+        }
+        self.widget.zoom = zoom;
+        self.ctx.request_layout();
+    }
+
+    pub fn child_mut(&mut self) -> WidgetMut<'_, W> {
+        self.ctx.get_mut(&mut self.widget.child)
+    }
+}
+
+impl<W: Widget> Widget for Zoom<W> {
+    fn on_pointer_event(&mut self, ctx: &mut EventCtx, event: &PointerEvent) {
+        let event = event.with_zoom(ctx.window_origin(), self.zoom);
+        self.child.on_pointer_event(ctx, &event);
+    }
+
+    fn on_text_event(&mut self, ctx: &mut EventCtx, event: &TextEvent) {
+        self.child.on_text_event(ctx, event);
+    }
+
+    fn on_access_event(&mut self, ctx: &mut EventCtx, event: &AccessEvent) {
+        self.child.on_access_event(ctx, event);
+    }
+
+    fn on_status_change(&mut self, _ctx: &mut LifeCycleCtx, _event: &StatusChange) {}
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle) {
+        self.child.lifecycle(ctx, event);
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints) -> Size {
+        let zoom = self.zoom;
+        let child_bc = BoxConstraints::new(bc.min() / zoom, bc.max() / zoom);
+        let child_size = self.child.layout(ctx, &child_bc);
+        ctx.place_child(&mut self.child, Point::ORIGIN);
+
+        let size = bc.constrain(child_size * zoom);
+        let child_insets = self.child.compute_parent_paint_insets(child_size);
+        let insets = Insets::new(
+            child_insets.x0 * zoom,
+            child_insets.y0 * zoom,
+            child_insets.x1 * zoom,
+            child_insets.y1 * zoom,
+        );
+        ctx.set_paint_insets(insets);
+        let baseline_offset = self.child.baseline_offset();
+        if baseline_offset > 0.0 {
+            ctx.set_baseline_offset(baseline_offset * zoom);
+        }
+
+        trace!("Computed layout: size={}, zoom={}", size, zoom);
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, scene: &mut Scene) {
+        let mut child_scene = Scene::new();
+        self.child.paint(ctx, &mut child_scene);
+        scene.append(&child_scene, Some(Affine::scale(self.zoom)));
+    }
+
+    fn accessibility_role(&self) -> Role {
+        Role::GenericContainer
+    }
+
+    fn accessibility(&mut self, ctx: &mut AccessCtx) {
+        self.child.accessibility(ctx);
+    }
+
+    fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
+        smallvec![self.child.as_dyn()]
+    }
+
+    fn make_trace_span(&self) -> Span {
+        trace_span!("Zoom")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use winit::event::MouseButton;
+
+    use super::*;
+    use crate::testing::{widget_ids, TestHarness};
+    use crate::widget::{Button, Flex, SizedBox};
+    use crate::Action;
+
+    #[test]
+    fn child_is_laid_out_unscaled() {
+        let [zoom_id, box_id] = widget_ids();
+        let widget = Flex::column().with_child_id(
+            Zoom::new_pod(
+                WidgetPod::new_with_id(SizedBox::empty().width(50.0).height(20.0), box_id),
+                2.0,
+            ),
+            zoom_id,
+        );
+        let harness = TestHarness::create(widget);
+
+        assert_eq!(
+            harness.get_widget(zoom_id).state().layout_rect().size(),
+            Size::new(100.0, 40.0)
+        );
+        assert_eq!(
+            harness.get_widget(box_id).state().layout_rect().size(),
+            Size::new(50.0, 20.0)
+        );
+    }
+
+    #[test]
+    fn click_zoomed_button() {
+        let [zoom_id, button_id] = widget_ids();
+        let widget = Flex::column().with_flex_spacer(1.0).with_child_id(
+            Zoom::new_pod(
+                WidgetPod::new_with_id(Button::new("Zoomed"), button_id),
+                1.5,
+            ),
+            zoom_id,
+        );
+        let mut harness = TestHarness::create(widget);
+
+        let zoom_origin = harness.get_widget(zoom_id).state().window_origin();
+        let button_rect = harness.get_widget(button_id).state().window_layout_rect();
+        // Near the bottom-right corner of the painted button, which is outside of
+        // its unscaled layout rect.
+        let corner = Point::new(button_rect.x1 - 2.0, button_rect.y1 - 2.0);
+        let painted_corner = zoom_origin + (corner - zoom_origin) * 1.5;
+        assert!(!button_rect.contains(painted_corner));
+
+        harness.mouse_move(painted_corner);
+        harness.mouse_button_press(MouseButton::Left);
+        harness.mouse_button_release(MouseButton::Left);
+        assert_eq!(
+            harness.pop_action(),
+            Some((Action::ButtonPressed, button_id))
+        );
+    }
+}
@@ -0,0 +1,226 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! An index of the widget tree, by widget id.
+
+use std::collections::HashMap;
+
+use smallvec::SmallVec;
+
+use crate::widget::WidgetRef;
+use crate::{Widget, WidgetId};
+
+/// The parent and children of every widget in the tree, by id.
+///
+/// Widgets own their children, so the index can't point at the widgets themselves.
+/// Instead, it knows the path from the root to every widget, which lets lookups walk
+/// straight down to a widget instead of searching the whole tree.
+///
+/// The entry of a widget is refreshed when it receives `WidgetAdded`, and when
+/// `RouteWidgetAdded` reaches it after it called [`children_changed`]. In debug builds,
+/// every pass checks that the cached children of the widgets it visits are still
+/// up to date.
+///
+/// [`children_changed`]: crate::EventCtx::children_changed
+#[derive(Debug, Default)]
+pub(crate) struct WidgetIndex {
+    nodes: HashMap<WidgetId, IndexNode>,
+}
+
+#[derive(Debug)]
+struct IndexNode {
+    /// `None` for the root widget.
+    parent: Option<WidgetId>,
+    children: SmallVec<[WidgetId; 16]>,
+}
+
+impl WidgetIndex {
+    /// The cached ids of the children of the widget, if it's in the index.
+    pub(crate) fn children(&self, id: WidgetId) -> Option<&[WidgetId]> {
+        self.nodes.get(&id).map(|node| &node.children[..])
+    }
+
+    /// Whether the widget is in the tree.
+    pub(crate) fn contains(&self, id: WidgetId) -> bool {
+        self.nodes.contains_key(&id)
+    }
+
+    /// Whether `id` is `ancestor` or one of its descendants.
+    pub(crate) fn is_in_subtree(&self, id: WidgetId, ancestor: WidgetId) -> bool {
+        let mut current = Some(id);
+        while let Some(id) = current {
+            if id == ancestor {
+                return true;
+            }
+            current = self.nodes.get(&id).and_then(|node| node.parent);
+        }
+        false
+    }
+
+    /// The ids of the widgets from the root down to the widget, which is the last one.
+    pub(crate) fn path(&self, id: WidgetId) -> Option<SmallVec<[WidgetId; 16]>> {
+        let mut path = SmallVec::new();
+        let mut current = Some(id);
+        while let Some(id) = current {
+            path.push(id);
+            current = self.nodes.get(&id)?.parent;
+        }
+        path.reverse();
+        Some(path)
+    }
+
+    /// Record the parent and the current children of `widget`.
+    ///
+    /// The children which aren't part of the widget anymore are removed from the index,
+    /// with their descendants, unless they were moved to another widget in the meantime.
+    /// Children missing from the index, because they were moved from a widget which was
+    /// updated first, are indexed again with their descendants.
+    pub(crate) fn update(&mut self, widget: WidgetRef<'_, dyn Widget>, parent: Option<WidgetId>) {
+        let id = widget.id();
+        let children = widget.children();
+        let children_ids: SmallVec<[WidgetId; 16]> =
+            children.iter().map(|child| child.id()).collect();
+
+        let old = self.nodes.insert(
+            id,
+            IndexNode {
+                parent,
+                children: children_ids.clone(),
+            },
+        );
+        if let Some(old) = old {
+            for child in old.children {
+                if !children_ids.contains(&child) {
+                    self.remove_subtree(child, id);
+                }
+            }
+        }
+
+        for child in children {
+            if let Some(node) = self.nodes.get_mut(&child.id()) {
+                node.parent = Some(id);
+            } else {
+                self.update_subtree(child, id);
+            }
+        }
+    }
+
+    /// Record `widget` and all its descendants.
+    fn update_subtree(&mut self, widget: WidgetRef<'_, dyn Widget>, parent: WidgetId) {
+        let children = widget.children();
+        self.nodes.insert(
+            widget.id(),
+            IndexNode {
+                parent: Some(parent),
+                children: children.iter().map(|child| child.id()).collect(),
+            },
+        );
+        for child in children {
+            self.update_subtree(child, widget.id());
+        }
+    }
+
+    /// Remove `id` and its descendants, if it's still a child of `parent`.
+    fn remove_subtree(&mut self, id: WidgetId, parent: WidgetId) {
+        if self.nodes.get(&id).map(|node| node.parent) != Some(Some(parent)) {
+            return;
+        }
+        if let Some(node) = self.nodes.remove(&id) {
+            for child in node.children {
+                self.remove_subtree(child, id);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::widget_ids;
+    use crate::widget::{Flex, SizedBox};
+    use crate::WidgetPod;
+
+    /// A row of empty boxes with the given ids.
+    fn row(id: WidgetId, boxes: &[WidgetId]) -> WidgetPod<Flex> {
+        let mut row = Flex::row();
+        for box_id in boxes {
+            row = row.with_child_id(SizedBox::empty(), *box_id);
+        }
+        WidgetPod::new_with_id(row, id)
+    }
+
+    /// A column of rows, with the given ids and boxes.
+    fn column(id: WidgetId, rows: &[(WidgetId, &[WidgetId])]) -> WidgetPod<Flex> {
+        let mut column = Flex::column();
+        for (row_id, boxes) in rows {
+            column = column.with_child_pod(row(*row_id, boxes).boxed());
+        }
+        WidgetPod::new_with_id(column, id)
+    }
+
+    #[test]
+    fn index_whole_tree() {
+        let [root, row_1, row_2, box_1, box_2] = widget_ids();
+        let mut index = WidgetIndex::default();
+        index.update(
+            column(root, &[(row_1, &[box_1]), (row_2, &[box_2])]).as_dyn(),
+            None,
+        );
+
+        assert_eq!(&index.path(box_2).unwrap()[..], [root, row_2, box_2]);
+        assert_eq!(index.children(root), Some(&[row_1, row_2][..]));
+        assert!(index.is_in_subtree(box_1, row_1));
+        assert!(index.is_in_subtree(row_1, row_1));
+        assert!(!index.is_in_subtree(box_1, row_2));
+    }
+
+    #[test]
+    fn remove_children_with_their_descendants() {
+        let [root, row_1, row_2, box_1, box_2] = widget_ids();
+        let mut index = WidgetIndex::default();
+        index.update(
+            column(root, &[(row_1, &[box_1]), (row_2, &[box_2])]).as_dyn(),
+            None,
+        );
+
+        index.update(column(root, &[(row_2, &[box_2])]).as_dyn(), None);
+
+        assert!(!index.contains(row_1));
+        assert!(!index.contains(box_1));
+        assert!(index.path(box_1).is_none());
+        assert_eq!(&index.path(box_2).unwrap()[..], [root, row_2, box_2]);
+    }
+
+    #[test]
+    fn move_child_to_widget_updated_first() {
+        let [root, row_1, row_2, moved] = widget_ids();
+        let mut index = WidgetIndex::default();
+        index.update(
+            column(root, &[(row_1, &[moved]), (row_2, &[])]).as_dyn(),
+            None,
+        );
+
+        index.update(row(row_2, &[moved]).as_dyn(), Some(root));
+        index.update(row(row_1, &[]).as_dyn(), Some(root));
+
+        assert_eq!(&index.path(moved).unwrap()[..], [root, row_2, moved]);
+        assert_eq!(index.children(row_1), Some(&[][..]));
+    }
+
+    #[test]
+    fn move_child_to_widget_updated_last() {
+        let [root, row_1, row_2, moved] = widget_ids();
+        let mut index = WidgetIndex::default();
+        index.update(
+            column(root, &[(row_1, &[moved]), (row_2, &[])]).as_dyn(),
+            None,
+        );
+
+        // The moved box is removed with the first row, and indexed again with the second.
+        index.update(row(row_1, &[]).as_dyn(), Some(root));
+        assert!(!index.contains(moved));
+        index.update(row(row_2, &[moved]).as_dyn(), Some(root));
+
+        assert_eq!(&index.path(moved).unwrap()[..], [root, row_2, moved]);
+    }
+}
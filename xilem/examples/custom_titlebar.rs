@@ -0,0 +1,39 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! A window without decorations, moved by dragging its custom title bar.
+//!
+//! Double click the title bar to maximize the window, or to restore it.
+
+use masonry::widget::{CrossAxisAlignment, MainAxisAlignment};
+use winit::{dpi::LogicalSize, error::EventLoopError, window::Window};
+use xilem::{
+    view::{button, flex, label, window_drag_region},
+    EventLoop, MasonryView, Xilem,
+};
+
+fn app_logic(clicks: &mut u32) -> impl MasonryView<u32> {
+    let title_bar = window_drag_region(
+        flex((
+            label("Custom title bar"),
+            button("Click me", |clicks: &mut u32| *clicks += 1),
+        ))
+        .direction(xilem::Axis::Horizontal)
+        .main_axis_alignment(MainAxisAlignment::SpaceBetween),
+    );
+    flex((
+        title_bar,
+        label(format!("The button was clicked {clicks} times")),
+    ))
+    .cross_axis_alignment(CrossAxisAlignment::Fill)
+}
+
+fn main() -> Result<(), EventLoopError> {
+    let window_attributes = Window::default_attributes()
+        .with_title("Custom title bar")
+        .with_decorations(false)
+        .with_min_inner_size(LogicalSize::new(400., 300.));
+    let app = Xilem::new(0, app_logic);
+    app.run_windowed_in(EventLoop::with_user_event(), window_attributes)?;
+    Ok(())
+}
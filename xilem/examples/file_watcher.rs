@@ -0,0 +1,65 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Shows the last file changed in the current directory, using a [`subscription`] to a
+//! polling file watcher running on its own thread.
+
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+
+use winit::error::EventLoopError;
+use xilem::{
+    view::{flex, label, subscription},
+    EventLoop, MasonryView, MessageProxy, Xilem,
+};
+
+/// The modification times of the files in the current directory.
+fn scan() -> HashMap<PathBuf, SystemTime> {
+    let Ok(entries) = std::fs::read_dir(".") else {
+        return HashMap::new();
+    };
+    entries
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let modified = entry.metadata().ok()?.modified().ok()?;
+            Some((entry.path(), modified))
+        })
+        .collect()
+}
+
+/// Send the path of every file changed since the last scan, until the view is removed.
+fn watch(proxy: MessageProxy<PathBuf>) {
+    let mut known = scan();
+    loop {
+        std::thread::sleep(Duration::from_millis(500));
+        let current = scan();
+        for (path, modified) in &current {
+            if known.get(path) != Some(modified) && proxy.send(path.clone()).is_err() {
+                return;
+            }
+        }
+        known = current;
+    }
+}
+
+fn app_logic(last_changed: &mut Option<PathBuf>) -> impl MasonryView<Option<PathBuf>> {
+    let text = match last_changed {
+        Some(path) => format!("Last changed: {}", path.display()),
+        None => "No changes yet".to_string(),
+    };
+    flex((
+        label(text),
+        subscription(watch, |last_changed: &mut Option<PathBuf>, path| {
+            *last_changed = Some(path);
+        }),
+    ))
+}
+
+fn main() -> Result<(), EventLoopError> {
+    let app = Xilem::new(None, app_logic);
+    app.run_windowed(EventLoop::with_user_event(), "File watcher".into())?;
+    Ok(())
+}
@@ -4,7 +4,7 @@
 // On Windows platform, don't show a console when opening the app.
 #![windows_subsystem = "windows"]
 
-use xilem::view::{button, checkbox, flex, label, prose, textbox};
+use xilem::view::{button, checkbox, disabled, flex, label, prose, textbox};
 use xilem::{
     Axis, BoxedMasonryView, Color, EventLoop, EventLoopBuilder, MasonryView, TextAlignment, Xilem,
 };
@@ -38,7 +38,7 @@ fn app_logic(data: &mut AppData) -> impl MasonryView<AppData> {
             label("Label")
                 .color(Color::REBECCA_PURPLE)
                 .alignment(TextAlignment::Start),
-            label("Disabled label").disabled(),
+            disabled(label("Disabled label"), true),
         ))
         .direction(Axis::Horizontal),
         textbox(
@@ -50,7 +50,7 @@ fn app_logic(data: &mut AppData) -> impl MasonryView<AppData> {
         prose(LOREM).alignment(TextAlignment::Middle),
         button(button_label, |data: &mut AppData| data.count += 1),
         checkbox("Check me", data.active, |data: &mut AppData, checked| {
-            data.active = checked;
+            data.active = checked.is_checked();
         }),
         toggleable(data),
         button("Decrement", |data: &mut AppData| data.count -= 1),
@@ -56,7 +56,7 @@ fn app_logic(task_list: &mut TaskList) -> impl MasonryView<TaskList> {
                 task.description.clone(),
                 task.done,
                 move |data: &mut TaskList, checked| {
-                    data.tasks[i].done = checked;
+                    data.tasks[i].done = checked.is_checked();
                 },
             );
             let delete_button = button("Delete", move |data: &mut TaskList| {
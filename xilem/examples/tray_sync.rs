@@ -0,0 +1,111 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! A pretend sync client, which can be paused and resumed from its tray icon.
+//!
+//! The tray icon is green while syncing, and grey while paused. Closing the window
+//! removes the tray icon.
+
+use std::{sync::Arc, time::Duration};
+
+use winit::error::EventLoopError;
+use xilem::{
+    tray::{self, TrayIcon, TrayItem, TraySpec},
+    view::{button, flex, label, subscription},
+    EventLoop, MasonryView, MessageProxy, Xilem,
+};
+
+const ICON_SIZE: u32 = 32;
+
+struct SyncClient {
+    paused: bool,
+    synced_files: u32,
+    syncing_icon: TrayIcon,
+    paused_icon: TrayIcon,
+}
+
+/// A filled circle of the given color, on a transparent background.
+fn circle_icon(color: [u8; 3]) -> TrayIcon {
+    let radius = ICON_SIZE as f32 / 2.;
+    let rgba: Arc<[u8]> = (0..ICON_SIZE * ICON_SIZE)
+        .flat_map(|index| {
+            let x = (index % ICON_SIZE) as f32 + 0.5 - radius;
+            let y = (index / ICON_SIZE) as f32 + 0.5 - radius;
+            let alpha = if x * x + y * y <= radius * radius {
+                255
+            } else {
+                0
+            };
+            [color[0], color[1], color[2], alpha]
+        })
+        .collect();
+    TrayIcon::from_rgba(rgba, ICON_SIZE, ICON_SIZE)
+}
+
+/// Pretend to sync a file every second.
+fn sync_files(proxy: MessageProxy<()>) {
+    while proxy.send(()).is_ok() {
+        std::thread::sleep(Duration::from_secs(1));
+    }
+}
+
+fn toggle_item(client: &SyncClient) -> TrayItem<SyncClient> {
+    if client.paused {
+        TrayItem::new("Resume", |client: &mut SyncClient| client.paused = false)
+    } else {
+        TrayItem::new("Pause", |client: &mut SyncClient| client.paused = true)
+    }
+}
+
+fn tray_logic(client: &mut SyncClient) -> TraySpec<SyncClient> {
+    let (icon, status) = if client.paused {
+        (client.paused_icon.clone(), "Paused")
+    } else {
+        (client.syncing_icon.clone(), "Syncing")
+    };
+    // Sharing the icons between rebuilds makes comparing them cheap.
+    TraySpec::new(icon)
+        .tooltip(format!("{status}, {} files synced", client.synced_files))
+        .item(toggle_item(client))
+        .item(TrayItem::separator())
+        .item(
+            TrayItem::new("Reset counter", |client: &mut SyncClient| {
+                client.synced_files = 0;
+            })
+            .enabled(client.synced_files > 0),
+        )
+}
+
+fn app_logic(client: &mut SyncClient) -> impl MasonryView<SyncClient> {
+    let (status, toggle) = if client.paused {
+        ("Paused", "Resume")
+    } else {
+        ("Syncing", "Pause")
+    };
+    flex((
+        label(format!("{status}, {} files synced", client.synced_files)),
+        button(toggle, |client: &mut SyncClient| {
+            client.paused = !client.paused;
+        }),
+        subscription(sync_files, |client: &mut SyncClient, ()| {
+            if !client.paused {
+                client.synced_files += 1;
+            }
+        }),
+    ))
+}
+
+fn main() -> Result<(), EventLoopError> {
+    if !tray::is_supported() {
+        eprintln!("This desktop has no system tray, the app only shows its window.");
+    }
+    let client = SyncClient {
+        paused: false,
+        synced_files: 0,
+        syncing_icon: circle_icon([40, 180, 80]),
+        paused_icon: circle_icon([140, 140, 140]),
+    };
+    let app = Xilem::new(client, app_logic).with_tray(tray_logic);
+    app.run_windowed(EventLoop::with_user_event(), "Tray sync".into())?;
+    Ok(())
+}
@@ -4,7 +4,7 @@
 use std::{any::Any, ops::Deref, sync::Arc};
 
 use accesskit::Role;
-use masonry::widget::{WidgetMut, WidgetRef};
+use masonry::widget::{SizedBox, WidgetMut, WidgetRef};
 use masonry::{
     AccessCtx, AccessEvent, BoxConstraints, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx,
     Point, PointerEvent, Size, StatusChange, TextEvent, Widget, WidgetPod,
@@ -169,16 +169,28 @@ where
         } else {
             // Otherwise, replace the element.
 
+            // Tear the previous view down before building the new one, so that the views
+            // with a stable key in it can be taken over by those of the new view, see
+            // `with_id_stable_across_hot_reload`.
+            DynWidget::replace_inner(&mut element, WidgetPod::new(SizedBox::empty()).boxed());
+            dyn_state.inner_state = Box::new(());
+
             // Increase the generation, because the underlying widget has been swapped out.
             // Overflow condition: Impossible to overflow, as u64 only ever incremented by 1
             // and starting at 0.
             dyn_state.generation = dyn_state.generation.wrapping_add(1);
-            let (new_element, view_state) = cx
-                .with_id(ViewId::for_type::<V>(dyn_state.generation), |cx| {
-                    self.build(cx)
+            let id = ViewId::for_type::<V>(dyn_state.generation);
+            let (new_element, mut view_state) = cx.with_id(id, |cx| self.build(cx));
+            DynWidget::replace_inner(&mut element, new_element.boxed());
+            if cx.stable_stash().take_reused() {
+                // Apply the new views to the elements which were taken over.
+                DynWidget::downcast(&mut element, |element| {
+                    if let Some(element) = element {
+                        cx.with_id(id, |cx| self.rebuild(&mut view_state, cx, self, element));
+                    }
                 });
+            }
             dyn_state.inner_state = Box::new(view_state);
-            DynWidget::replace_inner(&mut element, new_element.boxed());
             cx.mark_changed();
         }
     }
@@ -0,0 +1,73 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+use std::sync::{Arc, Weak};
+
+use masonry::{widget::WidgetMut, Widget, WidgetId};
+
+use crate::proxy::MessageQueue;
+
+/// A handle to a widget built by an [`on_build`](crate::view::on_build) view, to mutate it
+/// imperatively when the declarative views can't express an operation.
+///
+/// Edits are queued with the app's other pending work, and run as
+/// [deferred mutations](masonry::EventCtx::defer) of the widget tree, once the pass in
+/// progress is over. Queuing an edit wakes the app up, so it also runs when it's queued
+/// outside of a view callback, e.g. from another thread. Edits don't rebuild the view
+/// tree.
+///
+/// The handle can be cloned and stored in the app state. Once the view is removed from
+/// the view tree, edits are discarded.
+#[derive(Clone)]
+pub struct WidgetHandle {
+    id: WidgetId,
+    queue: Arc<MessageQueue>,
+    /// Dropped with the state of the view, to discard edits once it's removed.
+    alive: Weak<()>,
+}
+
+impl WidgetHandle {
+    pub(crate) fn new(id: WidgetId, queue: Arc<MessageQueue>, alive: &Arc<()>) -> Self {
+        WidgetHandle {
+            id,
+            queue,
+            alive: Arc::downgrade(alive),
+        }
+    }
+
+    /// The id of the widget.
+    pub fn id(&self) -> WidgetId {
+        self.id
+    }
+
+    /// Queue `f` to run against the widget.
+    ///
+    /// `W` must be the widget type of the view passed to [`on_build`](crate::view::on_build).
+    /// If it isn't, or if the view was removed, the edit is discarded with a warning.
+    pub fn edit<W: Widget>(&self, f: impl FnOnce(&mut WidgetMut<'_, W>) + Send + 'static) {
+        let id = self.id;
+        if self.alive.strong_count() == 0 {
+            tracing::warn!("Discarding edit of widget {id:?}, whose view was removed");
+            return;
+        }
+        self.queue.push_edit(Box::new(move |root| {
+            let found =
+                root.edit_descendant(id, move |mut widget| match widget.try_downcast::<W>() {
+                    Some(mut widget) => f(&mut widget),
+                    None => tracing::warn!(
+                        "Discarding edit of widget {id:?}, which isn't a {}",
+                        std::any::type_name::<W>()
+                    ),
+                });
+            if found.is_none() {
+                tracing::warn!("Discarding edit of widget {id:?}, which was removed");
+            }
+        }));
+    }
+}
+
+impl std::fmt::Debug for WidgetHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("WidgetHandle").field(&self.id).finish()
+    }
+}
@@ -0,0 +1,77 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{any::Any, cell::RefCell, collections::HashMap, rc::Rc};
+
+/// The parts of the views with a stable key which were removed from the tree, waiting to
+/// be taken over by a new view with the same key.
+///
+/// See [`with_id_stable_across_hot_reload`](crate::view::with_id_stable_across_hot_reload).
+/// The widget and the view state of a view are stashed separately when they are dropped,
+/// and are only taken over together. What wasn't taken over by the end of a rebuild is
+/// dropped with [`clear`](Self::clear).
+#[derive(Clone, Default)]
+pub(crate) struct StableStash(Rc<RefCell<StashInner>>);
+
+#[derive(Default)]
+struct StashInner {
+    entries: HashMap<String, Stashed>,
+    /// Whether a view took over stashed parts since the last call to `take_reused`.
+    reused: bool,
+}
+
+#[derive(Default)]
+struct Stashed {
+    element: Option<Box<dyn Any>>,
+    view: Option<Box<dyn Any>>,
+}
+
+impl StableStash {
+    pub(crate) fn stash_element(&self, key: &str, element: Box<dyn Any>) {
+        let mut inner = self.0.borrow_mut();
+        inner.entries.entry(key.into()).or_default().element = Some(element);
+    }
+
+    pub(crate) fn stash_view(&self, key: &str, view: Box<dyn Any>) {
+        let mut inner = self.0.borrow_mut();
+        inner.entries.entry(key.into()).or_default().view = Some(view);
+    }
+
+    /// Take the element and the view stashed for `key`, if both are there with the
+    /// expected types.
+    pub(crate) fn take<E: 'static, V: 'static>(&self, key: &str) -> Option<(E, V)> {
+        let stashed = {
+            let mut inner = self.0.borrow_mut();
+            let stashed = inner.entries.get(key)?;
+            let complete = stashed.element.as_ref().is_some_and(|e| e.is::<E>())
+                && stashed.view.as_ref().is_some_and(|v| v.is::<V>());
+            if !complete {
+                return None;
+            }
+            inner.reused = true;
+            inner.entries.remove(key)?
+        };
+        let element = stashed.element?.downcast().ok()?;
+        let view = stashed.view?.downcast().ok()?;
+        Some((*element, *view))
+    }
+
+    /// Return whether stashed parts were taken over since the last call.
+    pub(crate) fn take_reused(&self) -> bool {
+        std::mem::take(&mut self.0.borrow_mut().reused)
+    }
+
+    /// Drop everything which wasn't taken over.
+    pub(crate) fn clear(&self) {
+        self.0.borrow_mut().reused = false;
+        // Dropping a stashed widget stashes the widgets with a stable key nested in it,
+        // so this is done outside of the borrow, until nothing is left.
+        loop {
+            let entries = std::mem::take(&mut self.0.borrow_mut().entries);
+            if entries.is_empty() {
+                break;
+            }
+            drop(entries);
+        }
+    }
+}
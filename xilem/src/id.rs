@@ -21,6 +21,13 @@ impl ViewId {
     pub fn routing_id(self) -> u64 {
         self.routing_id
     }
+
+    /// The name of the type of the view with this id, without its path and generic
+    /// parameters, e.g. `Button`.
+    pub fn short_type_name(self) -> &'static str {
+        let name = self.debug.split('<').next().unwrap_or(self.debug);
+        name.rsplit("::").next().unwrap_or(name)
+    }
 }
 
 impl Debug for ViewId {
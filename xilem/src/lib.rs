@@ -2,15 +2,19 @@
 // SPDX-License-Identifier: Apache-2.0
 
 #![allow(clippy::comparison_chain)]
-use std::{any::Any, collections::HashMap};
+use std::{any::Any, collections::HashMap, sync::Arc};
 
 use masonry::{
-    app_driver::AppDriver,
+    app_driver::{AppDriver, DriverCtx},
     event_loop_runner,
     widget::{RootWidget, WidgetMut},
     Widget, WidgetId, WidgetPod,
 };
-pub use masonry::{widget::Axis, Color, TextAlignment};
+pub use masonry::{
+    widget::{Axis, CheckState, SortDirection},
+    Badge, ButtonStyle, ButtonVariant, Color, StateColors, TextAlignment, TextboxStyle,
+    TextboxVariant, Theme, ValidationState,
+};
 use winit::{
     dpi::LogicalSize,
     error::EventLoopError,
@@ -18,13 +22,23 @@ use winit::{
 };
 
 mod any_view;
+mod handle;
+mod hot_reload;
 mod id;
+mod proxy;
 mod sequence;
+pub mod testing;
+pub mod tray;
 mod vec_splice;
 pub use any_view::{AnyMasonryView, BoxedMasonryView};
+pub use handle::WidgetHandle;
+use hot_reload::StableStash;
 pub mod view;
 pub use id::ViewId;
+pub use proxy::MessageProxy;
+use proxy::MessageQueue;
 pub use sequence::{ElementSplice, ViewSequence};
+use tray::{Tray, TrayBackend, TraySpec};
 pub use vec_splice::VecSplice;
 
 pub use masonry::event_loop_runner::{EventLoop, EventLoopBuilder};
@@ -37,12 +51,16 @@ where
     driver: MasonryDriver<State, Logic, View, View::ViewState>,
 }
 
+type ThemeLogic<State> = Box<dyn FnMut(&mut State) -> Theme>;
+
 pub struct MasonryDriver<State, Logic, View, ViewState> {
     state: State,
     logic: Logic,
     current_view: View,
     view_cx: ViewCx,
     view_state: ViewState,
+    tray: Option<Tray<State>>,
+    theme_logic: Option<ThemeLogic<State>>,
 }
 
 impl<State, Logic, View> AppDriver for MasonryDriver<State, Logic, View, View::ViewState>
@@ -56,43 +74,128 @@ where
         widget_id: masonry::WidgetId,
         action: masonry::Action,
     ) {
-        if let Some(id_path) = self.view_cx.widget_map.get(&widget_id) {
-            let message_result = self.current_view.message(
-                &mut self.view_state,
-                id_path.as_slice(),
-                Box::new(action),
-                &mut self.state,
-            );
-            let rebuild = match message_result {
-                MessageResult::Action(()) => {
-                    // It's not entirely clear what to do here
-                    true
-                }
-                MessageResult::RequestRebuild => true,
-                MessageResult::Nop => false,
-                MessageResult::Stale(_) => {
-                    tracing::info!("Discarding message");
-                    false
-                }
-            };
-            if rebuild {
-                let next_view = (self.logic)(&mut self.state);
-                let mut root = ctx.get_root::<RootWidget<View::Element>>();
-
-                self.view_cx.view_tree_changed = false;
-                next_view.rebuild(
-                    &mut self.view_state,
-                    &mut self.view_cx,
-                    &self.current_view,
-                    root.get_element(),
-                );
-                if cfg!(debug_assertions) && !self.view_cx.view_tree_changed {
-                    tracing::debug!("Nothing changed as result of action");
-                }
-                self.current_view = next_view;
-            }
-        } else {
+        if self.handle_action(widget_id, action) {
+            self.rebuild(ctx.get_root::<RootWidget<View::Element>>());
+        }
+        self.defer_queued_edits(ctx.get_root::<RootWidget<View::Element>>());
+    }
+
+    fn on_wake(&mut self, ctx: &mut DriverCtx<'_>) {
+        let tray_changed = match &mut self.tray {
+            Some(tray) => tray.handle_events(&mut self.state),
+            None => false,
+        };
+        if self.deliver_queued_messages() || tray_changed {
+            self.rebuild(ctx.get_root::<RootWidget<View::Element>>());
+        }
+        self.defer_queued_edits(ctx.get_root::<RootWidget<View::Element>>());
+    }
+
+    fn on_start(&mut self, ctx: &mut DriverCtx<'_>) {
+        if let Some(tray) = &mut self.tray {
+            tray.create(&mut self.state);
+        }
+        self.update_theme(ctx.get_root::<RootWidget<View::Element>>());
+    }
+}
+
+impl<State, Logic, View> MasonryDriver<State, Logic, View, View::ViewState>
+where
+    Logic: FnMut(&mut State) -> View,
+    View: MasonryView<State>,
+{
+    /// Deliver `action` to the view which built the widget, and return whether the
+    /// widget tree must be rebuilt.
+    fn handle_action(&mut self, widget_id: masonry::WidgetId, action: masonry::Action) -> bool {
+        let Some(id_path) = self.view_cx.widget_map.get(&widget_id) else {
             eprintln!("Got action {action:?} for unknown widget. Did you forget to use `with_action_widget`?");
+            return false;
+        };
+        self.deliver_message(id_path.clone(), Box::new(action))
+    }
+
+    /// Deliver the messages sent through [`MessageProxy`]s, and return whether the widget
+    /// tree must be rebuilt.
+    ///
+    /// All the messages queued since the last call are delivered as a single batch, so
+    /// that the widget tree is only rebuilt once for all of them.
+    fn deliver_queued_messages(&mut self) -> bool {
+        let batch = self.view_cx.message_queue.take();
+        batch.deliver(|(id_path, message)| self.deliver_message(id_path, message))
+    }
+
+    /// Deliver `message` to the view at `id_path`, and return whether the widget tree
+    /// must be rebuilt.
+    fn deliver_message(&mut self, id_path: Vec<ViewId>, message: Box<dyn Any>) -> bool {
+        let message_result = self.current_view.message(
+            &mut self.view_state,
+            id_path.as_slice(),
+            message,
+            &mut self.state,
+        );
+        match message_result {
+            MessageResult::Action(()) => {
+                // It's not entirely clear what to do here
+                true
+            }
+            MessageResult::RequestRebuild => true,
+            MessageResult::Nop => false,
+            MessageResult::Stale(_) => {
+                tracing::info!("Discarding message");
+                false
+            }
+        }
+    }
+
+    /// Run the app logic and rebuild the widget tree from the new view.
+    fn rebuild(&mut self, mut root: WidgetMut<'_, RootWidget<View::Element>>) {
+        let _span = tracing::info_span!("rebuild").entered();
+        let next_view = (self.logic)(&mut self.state);
+
+        self.view_cx.view_tree_changed = false;
+        next_view.rebuild(
+            &mut self.view_state,
+            &mut self.view_cx,
+            &self.current_view,
+            root.get_element(),
+        );
+        if cfg!(debug_assertions) && !self.view_cx.view_tree_changed {
+            tracing::debug!("Nothing changed as result of action");
+        }
+        self.current_view = next_view;
+        // The views with a stable key which were removed weren't taken over by new ones.
+        self.view_cx.stable_stash.clear();
+        self.update_theme(root);
+        self.deliver_handles();
+        if let Some(tray) = &mut self.tray {
+            tray.rebuild(&mut self.state);
+        }
+    }
+
+    /// Set the theme of the window to the one returned by the theme logic, if any.
+    ///
+    /// Masonry only restyles the widgets if the theme changed.
+    fn update_theme(&mut self, mut root: WidgetMut<'_, RootWidget<View::Element>>) {
+        if let Some(theme_logic) = &mut self.theme_logic {
+            root.ctx.set_theme(theme_logic(&mut self.state));
+        }
+    }
+
+    /// Queue the edits made through [`WidgetHandle`]s as deferred mutations of the widget
+    /// tree, so that they run once the current pass is over.
+    fn defer_queued_edits(&mut self, mut root: WidgetMut<'_, RootWidget<View::Element>>) {
+        for edit in self.view_cx.message_queue.take_edits() {
+            root.ctx.defer(edit);
+        }
+    }
+
+    /// Give the handles of the widgets built since the last call to their views.
+    ///
+    /// The callbacks of the views aren't expected to change the widget tree, so this
+    /// doesn't rebuild it.
+    fn deliver_handles(&mut self) {
+        for (id_path, handle) in std::mem::take(&mut self.view_cx.handles) {
+            self.deliver_message(id_path, Box::new(handle));
         }
     }
 }
@@ -108,21 +211,68 @@ where
             id_path: vec![],
             widget_map: HashMap::new(),
             view_tree_changed: false,
+            handles: vec![],
+            message_queue: Arc::default(),
+            stable_stash: StableStash::default(),
         };
         let (pod, view_state) = first_view.build(&mut view_cx);
         let root_widget = RootWidget::from_pod(pod);
+        let mut driver = MasonryDriver {
+            current_view: first_view,
+            logic,
+            state,
+            view_cx,
+            view_state,
+            tray: None,
+            theme_logic: None,
+        };
+        driver.deliver_handles();
         Xilem {
-            driver: MasonryDriver {
-                current_view: first_view,
-                logic,
-                state,
-                view_cx,
-                view_state,
-            },
+            driver,
             root_widget,
         }
     }
 
+    /// Show an icon in the system tray, with a menu, as described by `tray_logic`.
+    ///
+    /// Like the view tree, the tray is built again after each change of the app state.
+    /// Choosing a menu item, or clicking the icon, calls its callback with the app state.
+    /// If the platform has no system tray, the app runs without it, see
+    /// [`tray::is_supported`].
+    #[cfg(all(
+        feature = "tray",
+        any(target_os = "windows", target_os = "macos", target_os = "linux")
+    ))]
+    pub fn with_tray(
+        self,
+        tray_logic: impl FnMut(&mut State) -> TraySpec<State> + 'static,
+    ) -> Self {
+        self.with_tray_backend(tray::NativeTray::new(), tray_logic)
+    }
+
+    /// Show the tray described by `tray_logic` with a custom [`TrayBackend`].
+    ///
+    /// See [`with_tray`](Self::with_tray) for details.
+    pub fn with_tray_backend(
+        mut self,
+        backend: impl TrayBackend + 'static,
+        tray_logic: impl FnMut(&mut State) -> TraySpec<State> + 'static,
+    ) -> Self {
+        let queue = self.driver.view_cx.message_queue.clone();
+        self.driver.tray = Some(Tray::new(Box::new(backend), Box::new(tray_logic), queue));
+        self
+    }
+
+    /// Style the widget variants with the [`Theme`] returned by `theme_logic`.
+    ///
+    /// The theme is computed again after each change of the app state, so that the app
+    /// can switch it at runtime, e.g. from a "Dark mode" checkbox. The existing widgets
+    /// are restyled in place.
+    pub fn with_theme(mut self, theme_logic: impl FnMut(&mut State) -> Theme + 'static) -> Self {
+        self.driver.theme_logic = Some(Box::new(theme_logic));
+        self
+    }
+
     // TODO: Make windows a specific view
     pub fn run_windowed(
         self,
@@ -147,7 +297,7 @@ where
     // TODO: Make windows into a custom view
     pub fn run_windowed_in(
         self,
-        event_loop: EventLoopBuilder,
+        mut event_loop: EventLoopBuilder,
         window_attributes: WindowAttributes,
     ) -> Result<(), EventLoopError>
     where
@@ -155,7 +305,12 @@ where
         Logic: 'static,
         View: 'static,
     {
-        event_loop_runner::run(event_loop, window_attributes, self.root_widget, self.driver)
+        let event_loop = event_loop.build()?;
+        self.driver
+            .view_cx
+            .message_queue
+            .set_waker(event_loop.create_proxy());
+        event_loop_runner::run_with(window_attributes, event_loop, self.root_widget, self.driver)
     }
 }
 pub trait MasonryView<State, Action = ()>: Send + Sync + 'static {
@@ -179,6 +334,41 @@ pub trait MasonryView<State, Action = ()>: Send + Sync + 'static {
         message: Box<dyn Any>,
         app_state: &mut State,
     ) -> MessageResult<Action>;
+
+    /// Paint a badge, like an unread count, above the widget of this view.
+    ///
+    /// The badge doesn't affect layout. With `None`, e.g. when nothing is unread, no badge
+    /// is shown. See [`Badge`] for details.
+    fn badge(self, badge: impl Into<Option<Badge>>) -> view::Badged<Self>
+    where
+        Self: Sized,
+    {
+        view::Badged::new(self, badge.into())
+    }
+
+    /// Set the position of the widget of this view in the Tab order of its focus scope.
+    ///
+    /// Tab goes through the widgets by increasing index, and in tree order for the same
+    /// index; views without a tab index have an index of 0. With a negative index, the
+    /// widget is skipped by Tab, but it can still be focused programmatically. See
+    /// [`WidgetPod::with_tab_index`] for details.
+    fn tab_index(self, tab_index: i32) -> view::TabIndex<Self>
+    where
+        Self: Sized,
+    {
+        view::TabIndex::new(self, tab_index)
+    }
+
+    /// Set the position of the widget of this view among its siblings, in the order
+    /// screen readers read them in.
+    ///
+    /// See [`WidgetPod::with_reading_order`] for details.
+    fn reading_order(self, reading_order: u32) -> view::ReadingOrder<Self>
+    where
+        Self: Sized,
+    {
+        view::ReadingOrder::new(self, reading_order)
+    }
 }
 
 pub struct ViewCx {
@@ -189,6 +379,15 @@ pub struct ViewCx {
     widget_map: HashMap<WidgetId, Vec<ViewId>>,
     id_path: Vec<ViewId>,
     view_tree_changed: bool,
+    /// The handles of the widgets built by [`on_build`](view::on_build) views, to give
+    /// to those views once the build is over.
+    handles: Vec<(Vec<ViewId>, WidgetHandle)>,
+    /// The messages sent through [`MessageProxy`]s, and the edits queued through
+    /// [`WidgetHandle`]s.
+    message_queue: Arc<MessageQueue>,
+    /// The widgets and states of the removed views with a stable key, see
+    /// [`with_id_stable_across_hot_reload`](view::with_id_stable_across_hot_reload).
+    stable_stash: StableStash,
 }
 
 impl ViewCx {
@@ -216,6 +415,42 @@ impl ViewCx {
         value
     }
 
+    /// Hand a [`WidgetHandle`] to the widget `id` to the view being built, once the build
+    /// is over. Its edits are discarded once `alive` is dropped.
+    pub(crate) fn register_handle(&mut self, id: WidgetId, alive: &Arc<()>) {
+        let handle = WidgetHandle::new(id, self.message_queue.clone(), alive);
+        self.handles.push((self.id_path.clone(), handle));
+    }
+
+    /// A proxy sending messages to the view being built, until `alive` is dropped.
+    pub(crate) fn message_proxy<M: Send + 'static>(&self, alive: &Arc<()>) -> MessageProxy<M> {
+        MessageProxy::new(self.message_queue.clone(), self.id_path.clone(), alive)
+    }
+
+    pub(crate) fn stable_stash(&self) -> StableStash {
+        self.stable_stash.clone()
+    }
+
+    pub(crate) fn id_path(&self) -> &[ViewId] {
+        &self.id_path
+    }
+
+    /// Route the actions of the widgets built at `old_path` to the current id path, for a
+    /// view which took over the widgets of a view elsewhere in the tree.
+    pub(crate) fn rebase_widget_paths(&mut self, old_path: &[ViewId]) {
+        let starts_with_old = |path: &[ViewId]| {
+            path.len() >= old_path.len()
+                && path.iter().zip(old_path).all(|(a, b)| {
+                    a.routing_id() == b.routing_id() && a.short_type_name() == b.short_type_name()
+                })
+        };
+        for path in self.widget_map.values_mut() {
+            if starts_with_old(path) {
+                path.splice(..old_path.len(), self.id_path.iter().copied());
+            }
+        }
+    }
+
     pub fn with_id<R>(&mut self, id: ViewId, f: impl FnOnce(&mut Self) -> R) -> R {
         self.id_path.push(id);
         let res = f(self);
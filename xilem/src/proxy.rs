@@ -0,0 +1,220 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+    any::Any,
+    marker::PhantomData,
+    sync::{mpsc::SendError, Arc, Mutex, OnceLock, Weak},
+};
+
+use masonry::{event_loop_runner::MasonryUserEvent, widget::WidgetMut, Widget};
+use winit::event_loop::EventLoopProxy;
+use xilem_core::{BatchQueue, MessageBatch};
+
+use crate::ViewId;
+
+/// A message sent to the view at the id path.
+pub(crate) type QueuedMessage = (Vec<ViewId>, Box<dyn Any + Send>);
+
+/// An edit queued through a [`WidgetHandle`](crate::WidgetHandle), run against the root
+/// widget as a deferred mutation.
+pub(crate) type QueuedEdit = Box<dyn FnOnce(&mut WidgetMut<'_, Box<dyn Widget>>) + Send>;
+
+/// The messages sent to views from other threads, waiting to be delivered on the UI thread,
+/// and the edits queued through widget handles.
+///
+/// See [`BatchQueue`] for the order in which messages are delivered.
+#[derive(Default)]
+pub(crate) struct MessageQueue {
+    messages: BatchQueue<QueuedMessage>,
+    edits: Mutex<Vec<QueuedEdit>>,
+    /// Wakes the event loop up when a message is queued. Unset until the app runs.
+    waker: OnceLock<EventLoopProxy<MasonryUserEvent>>,
+}
+
+impl MessageQueue {
+    pub(crate) fn set_waker(&self, waker: EventLoopProxy<MasonryUserEvent>) {
+        if self.waker.set(waker).is_err() {
+            tracing::warn!("The message queue of the app already has an event loop");
+        }
+        // Messages and edits may have been queued before the event loop existed.
+        if !self.messages.is_empty() || !self.edits.lock().unwrap().is_empty() {
+            self.wake();
+        }
+    }
+
+    /// Take the queued messages, in the order they were sent.
+    pub(crate) fn take(&self) -> MessageBatch<QueuedMessage> {
+        self.messages.take()
+    }
+
+    /// Take the queued edits, in the order they were queued.
+    pub(crate) fn take_edits(&self) -> Vec<QueuedEdit> {
+        std::mem::take(&mut *self.edits.lock().unwrap())
+    }
+
+    pub(crate) fn push_edit(&self, edit: QueuedEdit) {
+        let mut edits = self.edits.lock().unwrap();
+        edits.push(edit);
+        // The event loop is already woken up for the edits queued before.
+        if edits.len() == 1 {
+            drop(edits);
+            self.wake();
+        }
+    }
+
+    fn push(&self, id_path: Vec<ViewId>, message: Box<dyn Any + Send>) {
+        // The event loop is already woken up for the messages queued before.
+        if self.messages.push((id_path, message)) {
+            self.wake();
+        }
+    }
+
+    fn push_batch(&self, messages: impl IntoIterator<Item = QueuedMessage>) {
+        if self.messages.push_batch(messages) {
+            self.wake();
+        }
+    }
+
+    /// Wake the event loop up, so that the app driver handles what was queued.
+    pub(crate) fn wake(&self) {
+        if let Some(waker) = self.waker.get() {
+            // Fails if the event loop has exited, in which case nobody wants the message.
+            let _ = waker.send_event(MasonryUserEvent::Wake);
+        }
+    }
+}
+
+/// Sends messages of type `M` to a view from any thread.
+///
+/// The messages are delivered to the view on the UI thread, in the order they were sent,
+/// as if the view's widget had submitted them as actions. The messages sent by all
+/// proxies between two wake-ups of the app are delivered together, in the order they
+/// were sent, and the view tree is rebuilt once after all of them.
+pub struct MessageProxy<M> {
+    queue: Arc<MessageQueue>,
+    id_path: Vec<ViewId>,
+    /// Dropped with the state of the view, to stop delivering messages once it's removed.
+    alive: Weak<()>,
+    phantom: PhantomData<fn(M)>,
+}
+
+impl<M: Send + 'static> MessageProxy<M> {
+    pub(crate) fn new(queue: Arc<MessageQueue>, id_path: Vec<ViewId>, alive: &Arc<()>) -> Self {
+        MessageProxy {
+            queue,
+            id_path,
+            alive: Arc::downgrade(alive),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Send `message` to the view.
+    ///
+    /// Returns the message in an error if the view was removed from the view tree.
+    pub fn send(&self, message: M) -> Result<(), SendError<M>> {
+        if self.alive.strong_count() == 0 {
+            return Err(SendError(message));
+        }
+        self.queue.push(self.id_path.clone(), Box::new(message));
+        Ok(())
+    }
+
+    /// Send all of `messages` to the view, in order.
+    ///
+    /// They are delivered contiguously: no message sent by another proxy in the meantime
+    /// is delivered between them. Returns the messages in an error if the view was
+    /// removed from the view tree.
+    pub fn send_batch(&self, messages: Vec<M>) -> Result<(), SendError<Vec<M>>> {
+        if self.alive.strong_count() == 0 {
+            return Err(SendError(messages));
+        }
+        let id_path = &self.id_path;
+        self.queue.push_batch(messages.into_iter().map(|message| {
+            let message: Box<dyn Any + Send> = Box::new(message);
+            (id_path.clone(), message)
+        }));
+        Ok(())
+    }
+}
+
+impl<M> Clone for MessageProxy<M> {
+    fn clone(&self) -> Self {
+        MessageProxy {
+            queue: self.queue.clone(),
+            id_path: self.id_path.clone(),
+            alive: self.alive.clone(),
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<M> std::fmt::Debug for MessageProxy<M> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MessageProxy")
+            .field("id_path", &self.id_path)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use super::*;
+    use crate::testing::{count_rebuilds, ViewHarness};
+    use crate::view::{flex, subscription};
+
+    #[test]
+    fn proxy_messages_are_batched_in_arrival_order() {
+        let (proxy_sender, proxy_receiver) = std::sync::mpsc::channel();
+        let proxy_sender = Mutex::new(proxy_sender);
+        let rebuilds = Arc::new(AtomicUsize::new(0));
+        let rebuilds_clone = rebuilds.clone();
+        let mut harness = ViewHarness::new(vec![], move |_: &mut Vec<u32>| {
+            let subscriber = |index: u32| {
+                let proxy_sender = proxy_sender.lock().unwrap().clone();
+                subscription(
+                    move |proxy: MessageProxy<u32>| proxy_sender.send((index, proxy)).unwrap(),
+                    |received: &mut Vec<u32>, item| received.push(item),
+                )
+            };
+            count_rebuilds(rebuilds_clone.clone(), flex((subscriber(0), subscriber(1))))
+        });
+        let mut proxies = [None, None];
+        for _ in 0..2 {
+            let (index, proxy) = proxy_receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+            proxies[index as usize] = Some(proxy);
+        }
+        let [Some(first), Some(second)] = proxies else {
+            unreachable!()
+        };
+        let initial_rebuilds = rebuilds.load(Ordering::Relaxed);
+
+        first.send(1).unwrap();
+        second.send(2).unwrap();
+        first.send(3).unwrap();
+        second.send_batch(vec![4, 5, 6]).unwrap();
+        first.send(7).unwrap();
+        harness.process_messages();
+        assert_eq!(*harness.state(), [1, 2, 3, 4, 5, 6, 7]);
+        assert_eq!(rebuilds.load(Ordering::Relaxed), initial_rebuilds + 1);
+
+        // Batches sent concurrently with other messages stay contiguous.
+        let sender = std::thread::spawn(move || {
+            for i in 0..100 {
+                first.send(1000 + i).unwrap();
+            }
+        });
+        second.send_batch((0..100).collect()).unwrap();
+        sender.join().unwrap();
+        harness.state().clear();
+        harness.process_messages();
+        let received = harness.state();
+        assert_eq!(received.len(), 200);
+        let start = received.iter().position(|item| *item == 0).unwrap();
+        assert!(received[start..start + 100].iter().copied().eq(0..100));
+        assert_eq!(rebuilds.load(Ordering::Relaxed), initial_rebuilds + 2);
+    }
+}
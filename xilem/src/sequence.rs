@@ -208,7 +208,6 @@ pub struct VecViewState<InnerState> {
     global_generation: u32,
 }
 
-// TODO: We use raw indexing for this value. What would make it invalid?
 impl<T, A, Marker, VT: ViewSequence<T, A, Marker>> ViewSequence<T, A, (WasASequence, Marker)>
     for Vec<VT>
 {
@@ -302,11 +301,14 @@ impl<T, A, Marker, VT: ViewSequence<T, A, Marker>> ViewSequence<T, A, (WasASeque
             .split_first()
             .expect("Id path has elements for vector");
         let (index, generation) = view_id_to_index_generation(start.routing_id());
-        let (seq_state, stored_generation) = &mut seq_state.inner_with_generations[index];
-        if *stored_generation != generation {
-            return MessageResult::Stale(message);
+        // The child may have been removed since the message was sent, or replaced by a
+        // new child at the same index, which has a newer generation.
+        match seq_state.inner_with_generations.get_mut(index) {
+            Some((seq_state, stored_generation)) if *stored_generation == generation => {
+                self[index].message(seq_state, rest, message, app_state)
+            }
+            _ => MessageResult::Stale(message),
         }
-        self[index].message(seq_state, rest, message, app_state)
     }
 
     fn count(&self) -> usize {
@@ -476,3 +478,126 @@ impl_view_tuple!(M0, Seq0, 0; M1, Seq1, 1; M2, Seq2, 2; M3, Seq3, 3; M4, Seq4, 4
 impl_view_tuple!(M0, Seq0, 0; M1, Seq1, 1; M2, Seq2, 2; M3, Seq3, 3; M4, Seq4, 4; M5, Seq5, 5; M6, Seq6, 6; M7, Seq7, 7; M8, Seq8, 8; M9, Seq9, 9; M10, Seq10, 10; M11, Seq11, 11; M12, Seq12, 12; M13, Seq13, 13);
 impl_view_tuple!(M0, Seq0, 0; M1, Seq1, 1; M2, Seq2, 2; M3, Seq3, 3; M4, Seq4, 4; M5, Seq5, 5; M6, Seq6, 6; M7, Seq7, 7; M8, Seq8, 8; M9, Seq9, 9; M10, Seq10, 10; M11, Seq11, 11; M12, Seq12, 12; M13, Seq13, 13; M14, Seq14, 14);
 impl_view_tuple!(M0, Seq0, 0; M1, Seq1, 1; M2, Seq2, 2; M3, Seq3, 3; M4, Seq4, 4; M5, Seq5, 5; M6, Seq6, 6; M7, Seq7, 7; M8, Seq8, 8; M9, Seq9, 9; M10, Seq10, 10; M11, Seq11, 11; M12, Seq12, 12; M13, Seq13, 13; M14, Seq14, 14; M15, Seq15, 15);
+
+#[cfg(test)]
+mod tests {
+    use std::any::Any;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use masonry::WidgetId;
+
+    use super::*;
+    use crate::testing::{assert_element_stable, count_rebuilds, ViewHarness};
+    use crate::view::{button, flex, label};
+
+    #[derive(Default)]
+    struct AppState {
+        visible: bool,
+        text: String,
+        items: Vec<u32>,
+        pressed: Vec<u32>,
+    }
+
+    /// The id path of the view which built the `index`-th child of `parent`.
+    fn path_to_child<Logic, View>(
+        harness: &mut ViewHarness<AppState, Logic, View>,
+        parent: WidgetId,
+        index: usize,
+    ) -> Vec<ViewId>
+    where
+        Logic: FnMut(&mut AppState) -> View,
+        View: MasonryView<AppState>,
+    {
+        let id = harness.masonry_harness().get_widget(parent).children()[index].id();
+        harness.driver.view_cx.widget_map[&id].clone()
+    }
+
+    /// Send a button press to the view at `id_path`, as if it had been queued earlier.
+    fn press_button_at<Logic, View>(
+        harness: &mut ViewHarness<AppState, Logic, View>,
+        id_path: &[ViewId],
+    ) -> MessageResult<()>
+    where
+        Logic: FnMut(&mut AppState) -> View,
+        View: MasonryView<AppState>,
+    {
+        let driver = &mut harness.driver;
+        let message: Box<dyn Any> = Box::new(masonry::Action::ButtonPressed);
+        driver
+            .current_view
+            .message(&mut driver.view_state, id_path, message, &mut driver.state)
+    }
+
+    #[test]
+    fn option_some_to_some_keeps_element() {
+        let rebuilds = Arc::new(AtomicUsize::new(0));
+        let rebuilds_clone = rebuilds.clone();
+        let state = AppState {
+            visible: true,
+            ..Default::default()
+        };
+        let mut harness = ViewHarness::new(state, move |state: &mut AppState| {
+            let rebuilds = rebuilds_clone.clone();
+            let text = state.text.clone();
+            flex(
+                state
+                    .visible
+                    .then(|| count_rebuilds(rebuilds, assert_element_stable(label(text)))),
+            )
+        });
+
+        harness.state().text = "changed".into();
+        harness.rebuild();
+        assert_eq!(rebuilds.load(Ordering::Relaxed), 1);
+
+        // Going through `None` builds a new view rather than rebuilding the old one
+        harness.state().visible = false;
+        harness.rebuild();
+        harness.state().visible = true;
+        harness.rebuild();
+        assert_eq!(rebuilds.load(Ordering::Relaxed), 1);
+        harness.rebuild();
+        assert_eq!(rebuilds.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn stale_message_is_not_delivered_to_replacement_child() {
+        let state = AppState {
+            items: vec![0, 1],
+            ..Default::default()
+        };
+        let mut harness = ViewHarness::new(state, |state: &mut AppState| {
+            flex(
+                state
+                    .items
+                    .iter()
+                    .map(|&item| {
+                        button(item.to_string(), move |state: &mut AppState| {
+                            state.pressed.push(item);
+                        })
+                    })
+                    .collect::<Vec<_>>(),
+            )
+        });
+        let flex_id = harness.masonry_harness().root_widget().children()[0].id();
+        let old_path = path_to_child(&mut harness, flex_id, 1);
+
+        harness.state().items.pop();
+        harness.rebuild();
+        let result = press_button_at(&mut harness, &old_path);
+        assert!(matches!(result, MessageResult::Stale(_)));
+
+        harness.state().items.push(2);
+        harness.rebuild();
+        let result = press_button_at(&mut harness, &old_path);
+        assert!(matches!(result, MessageResult::Stale(_)));
+        assert!(harness.state().pressed.is_empty());
+
+        // The new child gets the messages sent to it.
+        let new_path = path_to_child(&mut harness, flex_id, 1);
+        let result = press_button_at(&mut harness, &new_path);
+        assert!(matches!(result, MessageResult::Action(())));
+        assert_eq!(harness.state().pressed, [2]);
+    }
+}
@@ -0,0 +1,429 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tools for testing when views are rebuilt.
+//!
+//! The views in this module are thin pass-through wrappers: they use the element of their
+//! child, and only record what happens to it. Together with [`ViewHarness`], they can be
+//! used to check which parts of a view tree are rebuilt when the app state changes.
+
+use std::{
+    any::Any,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use masonry::{
+    testing::TestHarness,
+    widget::{RootWidget, WidgetMut},
+    WidgetId, WidgetPod,
+};
+
+use crate::{MasonryDriver, MasonryView, MessageResult, ViewCx, ViewId, Xilem};
+
+/// Runs the logic of an app in a [`TestHarness`], without opening a window.
+pub struct ViewHarness<State, Logic, View>
+where
+    View: MasonryView<State>,
+{
+    harness: TestHarness,
+    /// Used by the tests of the views to send messages to arbitrary id paths.
+    pub(crate) driver: MasonryDriver<State, Logic, View, View::ViewState>,
+}
+
+impl<State, Logic, View> ViewHarness<State, Logic, View>
+where
+    Logic: FnMut(&mut State) -> View,
+    View: MasonryView<State>,
+{
+    /// Build the view returned by `logic` and its widget tree.
+    pub fn new(state: State, logic: Logic) -> Self {
+        let Xilem {
+            root_widget,
+            driver,
+        } = Xilem::new(state, logic);
+        ViewHarness {
+            harness: TestHarness::create(root_widget),
+            driver,
+        }
+    }
+
+    /// Return the app state.
+    pub fn state(&mut self) -> &mut State {
+        &mut self.driver.state
+    }
+
+    /// Run the app logic again, and rebuild the widget tree from the new view.
+    pub fn rebuild(&mut self) {
+        let driver = &mut self.driver;
+        self.harness.edit_root_widget(|mut root| {
+            driver.rebuild(root.downcast::<RootWidget<View::Element>>());
+        });
+    }
+
+    /// Deliver the actions submitted by widgets to the views, as the app would, and
+    /// rebuild the widget tree where needed.
+    ///
+    /// The app handles the actions of each event before the next one. The actions
+    /// submitted since the last call may come from several events, e.g. the characters
+    /// typed with [`keyboard_type_chars`](TestHarness::keyboard_type_chars), so the tree
+    /// is rebuilt once all of them were delivered: rebuilding with the state of an older
+    /// action would undo the newer edits of the widgets.
+    pub fn process_actions(&mut self) {
+        let mut needs_rebuild = false;
+        while let Some((action, widget_id)) = self.harness.pop_action() {
+            needs_rebuild |= self.driver.handle_action(widget_id, action);
+        }
+        if needs_rebuild {
+            self.rebuild();
+        }
+        self.defer_queued_edits();
+    }
+
+    /// Deliver the messages sent through [`MessageProxy`](crate::MessageProxy)s, and run
+    /// the edits queued through [`WidgetHandle`](crate::WidgetHandle)s, as the app would
+    /// when woken up, and rebuild the widget tree where needed.
+    pub fn process_messages(&mut self) {
+        if self.driver.deliver_queued_messages() {
+            self.rebuild();
+        }
+        self.defer_queued_edits();
+    }
+
+    /// Run the edits queued through widget handles as deferred mutations.
+    fn defer_queued_edits(&mut self) {
+        let driver = &mut self.driver;
+        self.harness.edit_root_widget(|mut root| {
+            driver.defer_queued_edits(root.downcast::<RootWidget<View::Element>>());
+        });
+    }
+
+    /// Deliver `message` to the view at `id_path`, e.g. as returned by
+    /// [`resolve_path`](Self::resolve_path), and rebuild the widget tree if needed.
+    pub fn deliver_message(&mut self, id_path: &[ViewId], message: impl Any) {
+        if self
+            .driver
+            .deliver_message(id_path.to_vec(), Box::new(message))
+        {
+            self.rebuild();
+        }
+    }
+
+    /// Return the id path of the view described by `path`, so that tests don't
+    /// depend on the exact ids of the views.
+    ///
+    /// `path` has one segment per id, separated by dots, e.g. `"flex.button@1"`. A
+    /// segment matches an id by the type name of the view, ignoring case and the
+    /// generic parameters (e.g. `button` for [`Button`](crate::view::Button)), by its
+    /// routing id (e.g. `1`, the index of a view in a tuple), or by both (e.g.
+    /// `button@1`). The path may lead to any view which is, or contains, a view whose
+    /// widget sends actions.
+    ///
+    /// # Panics
+    ///
+    /// If no view, or more than one view, matches `path`.
+    pub fn resolve_path(&self, path: &str) -> Vec<ViewId> {
+        let segments: Vec<&str> = path.split('.').collect();
+        let mut matches: Vec<&[ViewId]> = vec![];
+        for (widget_id, id_path) in &self.driver.view_cx.widget_map {
+            // The map keeps the paths of removed widgets.
+            if self.harness.try_get_widget(*widget_id).is_none() {
+                continue;
+            }
+            let Some(prefix) = id_path.get(..segments.len()) else {
+                continue;
+            };
+            let is_match = prefix
+                .iter()
+                .zip(&segments)
+                .all(|(id, segment)| segment_matches(*id, segment));
+            let is_new = !matches.iter().any(|path| same_path(path, prefix));
+            if is_match && is_new {
+                matches.push(prefix);
+            }
+        }
+        match matches.as_slice() {
+            [id_path] => id_path.to_vec(),
+            [] => panic!("no view matches the path {path:?}"),
+            _ => panic!("several views match the path {path:?}: {matches:?}"),
+        }
+    }
+
+    /// Return the underlying Masonry [`TestHarness`].
+    pub fn masonry_harness(&mut self) -> &mut TestHarness {
+        &mut self.harness
+    }
+}
+
+/// Whether `id` matches a segment of a path given to [`ViewHarness::resolve_path`].
+fn segment_matches(id: ViewId, segment: &str) -> bool {
+    let name_matches = |name: &str| id.short_type_name().eq_ignore_ascii_case(name);
+    let routing_id_matches = |routing_id: &str| routing_id.parse::<u64>() == Ok(id.routing_id());
+    match segment.split_once('@') {
+        Some((name, routing_id)) => name_matches(name) && routing_id_matches(routing_id),
+        None => name_matches(segment) || routing_id_matches(segment),
+    }
+}
+
+fn same_path(a: &[ViewId], b: &[ViewId]) -> bool {
+    a.len() == b.len()
+        && a.iter().zip(b).all(|(a, b)| {
+            a.routing_id() == b.routing_id() && a.short_type_name() == b.short_type_name()
+        })
+}
+
+/// Increment `counter` every time `child` is rebuilt.
+pub fn count_rebuilds<V>(counter: Arc<AtomicUsize>, child: V) -> CountRebuilds<V> {
+    CountRebuilds { counter, child }
+}
+
+pub struct CountRebuilds<V> {
+    counter: Arc<AtomicUsize>,
+    child: V,
+}
+
+impl<State, Action, V> MasonryView<State, Action> for CountRebuilds<V>
+where
+    V: MasonryView<State, Action>,
+{
+    type Element = V::Element;
+    type ViewState = V::ViewState;
+
+    fn build(&self, cx: &mut ViewCx) -> (WidgetPod<Self::Element>, Self::ViewState) {
+        self.child.build(cx)
+    }
+
+    fn rebuild(
+        &self,
+        view_state: &mut Self::ViewState,
+        cx: &mut ViewCx,
+        prev: &Self,
+        element: WidgetMut<Self::Element>,
+    ) {
+        self.counter.fetch_add(1, Ordering::Relaxed);
+        self.child.rebuild(view_state, cx, &prev.child, element);
+    }
+
+    fn message(
+        &self,
+        view_state: &mut Self::ViewState,
+        id_path: &[ViewId],
+        message: Box<dyn Any>,
+        app_state: &mut State,
+    ) -> MessageResult<Action> {
+        self.child.message(view_state, id_path, message, app_state)
+    }
+}
+
+/// Panic if `child` is rebuilt with a different element than the one it built.
+pub fn assert_element_stable<V>(child: V) -> AssertElementStable<V> {
+    AssertElementStable { child }
+}
+
+pub struct AssertElementStable<V> {
+    child: V,
+}
+
+pub struct AssertElementStableState<S> {
+    element_id: WidgetId,
+    child_state: S,
+}
+
+impl<State, Action, V> MasonryView<State, Action> for AssertElementStable<V>
+where
+    V: MasonryView<State, Action>,
+{
+    type Element = V::Element;
+    type ViewState = AssertElementStableState<V::ViewState>;
+
+    fn build(&self, cx: &mut ViewCx) -> (WidgetPod<Self::Element>, Self::ViewState) {
+        let (element, child_state) = self.child.build(cx);
+        let view_state = AssertElementStableState {
+            element_id: element.id(),
+            child_state,
+        };
+        (element, view_state)
+    }
+
+    fn rebuild(
+        &self,
+        view_state: &mut Self::ViewState,
+        cx: &mut ViewCx,
+        prev: &Self,
+        element: WidgetMut<Self::Element>,
+    ) {
+        let element_id = element.ctx.widget_id();
+        assert_eq!(
+            element_id, view_state.element_id,
+            "element of the view was replaced"
+        );
+        self.child
+            .rebuild(&mut view_state.child_state, cx, &prev.child, element);
+    }
+
+    fn message(
+        &self,
+        view_state: &mut Self::ViewState,
+        id_path: &[ViewId],
+        message: Box<dyn Any>,
+        app_state: &mut State,
+    ) -> MessageResult<Action> {
+        self.child
+            .message(&mut view_state.child_state, id_path, message, app_state)
+    }
+}
+
+/// A handle to the last view built or rebuilt by a [`probe`].
+pub struct ProbeSlot<V>(Arc<Mutex<Option<V>>>);
+
+impl<V: Clone> ProbeSlot<V> {
+    pub fn new() -> Self {
+        ProbeSlot(Arc::new(Mutex::new(None)))
+    }
+
+    /// Return the last view built or rebuilt by the probe, if any.
+    pub fn last_built(&self) -> Option<V> {
+        self.0.lock().unwrap().clone()
+    }
+}
+
+impl<V: Clone> Default for ProbeSlot<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V> Clone for ProbeSlot<V> {
+    fn clone(&self) -> Self {
+        ProbeSlot(self.0.clone())
+    }
+}
+
+/// Store a copy of `child` in `slot` every time it's built or rebuilt.
+pub fn probe<V: Clone>(slot: ProbeSlot<V>, child: V) -> Probe<V> {
+    Probe { slot, child }
+}
+
+pub struct Probe<V> {
+    slot: ProbeSlot<V>,
+    child: V,
+}
+
+impl<State, Action, V> MasonryView<State, Action> for Probe<V>
+where
+    V: MasonryView<State, Action> + Clone,
+{
+    type Element = V::Element;
+    type ViewState = V::ViewState;
+
+    fn build(&self, cx: &mut ViewCx) -> (WidgetPod<Self::Element>, Self::ViewState) {
+        *self.slot.0.lock().unwrap() = Some(self.child.clone());
+        self.child.build(cx)
+    }
+
+    fn rebuild(
+        &self,
+        view_state: &mut Self::ViewState,
+        cx: &mut ViewCx,
+        prev: &Self,
+        element: WidgetMut<Self::Element>,
+    ) {
+        *self.slot.0.lock().unwrap() = Some(self.child.clone());
+        self.child.rebuild(view_state, cx, &prev.child, element);
+    }
+
+    fn message(
+        &self,
+        view_state: &mut Self::ViewState,
+        id_path: &[ViewId],
+        message: Box<dyn Any>,
+        app_state: &mut State,
+    ) -> MessageResult<Action> {
+        self.child.message(view_state, id_path, message, app_state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::panic::AssertUnwindSafe;
+
+    use super::*;
+    use crate::view::{button, flex};
+
+    #[derive(Clone)]
+    struct Count(u32);
+
+    impl<State, Action> MasonryView<State, Action> for Count {
+        type Element = masonry::widget::Label;
+        type ViewState = ();
+
+        fn build(&self, _cx: &mut ViewCx) -> (WidgetPod<Self::Element>, Self::ViewState) {
+            let label = masonry::widget::Label::new(self.0.to_string());
+            (WidgetPod::new(label), ())
+        }
+
+        fn rebuild(
+            &self,
+            _view_state: &mut Self::ViewState,
+            _cx: &mut ViewCx,
+            prev: &Self,
+            mut element: WidgetMut<Self::Element>,
+        ) {
+            if prev.0 != self.0 {
+                element.set_text(self.0.to_string());
+            }
+        }
+
+        fn message(
+            &self,
+            _view_state: &mut Self::ViewState,
+            _id_path: &[ViewId],
+            message: Box<dyn Any>,
+            _app_state: &mut State,
+        ) -> MessageResult<Action> {
+            MessageResult::Stale(message)
+        }
+    }
+
+    #[test]
+    fn probe_records_last_view() {
+        let slot = ProbeSlot::new();
+        let slot_clone = slot.clone();
+        let mut harness = ViewHarness::new(0, move |count: &mut u32| {
+            probe(slot_clone.clone(), Count(*count))
+        });
+        assert_eq!(slot.last_built().map(|Count(count)| count), Some(0));
+
+        *harness.state() = 3;
+        harness.rebuild();
+        assert_eq!(slot.last_built().map(|Count(count)| count), Some(3));
+    }
+
+    #[test]
+    fn resolved_path_delivers_to_the_right_view() {
+        let mut harness = ViewHarness::new(0, |_: &mut u32| {
+            flex((
+                button("Reset", |count: &mut u32| *count = 0),
+                flex((
+                    button("Add one", |count: &mut u32| *count += 1),
+                    button("Add ten", |count: &mut u32| *count += 10),
+                )),
+            ))
+        });
+        let add_ten = harness.resolve_path("flex.button@1");
+        assert_eq!(add_ten.len(), 2);
+        harness.deliver_message(&add_ten, masonry::Action::ButtonPressed);
+        assert_eq!(*harness.state(), 10);
+
+        // Only one button is a direct child of the outer flex.
+        let reset = harness.resolve_path("button");
+        harness.deliver_message(&reset, masonry::Action::ButtonPressed);
+        assert_eq!(*harness.state(), 0);
+
+        let ambiguous =
+            std::panic::catch_unwind(AssertUnwindSafe(|| harness.resolve_path("flex.button")));
+        assert!(ambiguous.is_err());
+    }
+}
@@ -0,0 +1,662 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! A system tray icon with a small menu, for apps which keep running in the background.
+//!
+//! The tray is described by a [`TraySpec`], returned by the tray logic of the app given to
+//! [`Xilem::with_tray`](crate::Xilem::with_tray). Like the view tree, it's built again after
+//! each change of the app state, and only the differences with the previous one are applied
+//! to the platform's tray.
+
+use std::{
+    collections::VecDeque,
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+use crate::proxy::MessageQueue;
+
+#[cfg(all(
+    feature = "tray",
+    any(target_os = "windows", target_os = "macos", target_os = "linux")
+))]
+mod native;
+#[cfg(all(
+    feature = "tray",
+    any(target_os = "windows", target_os = "macos", target_os = "linux")
+))]
+pub use native::{is_supported, NativeTray};
+
+/// An image shown as the tray icon.
+#[derive(Clone)]
+pub struct TrayIcon {
+    rgba: Arc<[u8]>,
+    width: u32,
+    height: u32,
+}
+
+impl TrayIcon {
+    /// Create an icon from pixels in the RGBA8 format, row by row.
+    ///
+    /// # Panics
+    ///
+    /// If `rgba` isn't `width * height * 4` bytes long.
+    pub fn from_rgba(rgba: impl Into<Arc<[u8]>>, width: u32, height: u32) -> Self {
+        let rgba = rgba.into();
+        assert_eq!(
+            rgba.len(),
+            width as usize * height as usize * 4,
+            "the icon data doesn't match its size"
+        );
+        TrayIcon {
+            rgba,
+            width,
+            height,
+        }
+    }
+
+    pub fn rgba(&self) -> &[u8] {
+        &self.rgba
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+}
+
+impl PartialEq for TrayIcon {
+    fn eq(&self, other: &Self) -> bool {
+        // Icons are usually shared between rebuilds, which makes comparing them cheap.
+        (Arc::ptr_eq(&self.rgba, &other.rgba) || self.rgba == other.rgba)
+            && self.width == other.width
+            && self.height == other.height
+    }
+}
+
+impl fmt::Debug for TrayIcon {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TrayIcon")
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .finish_non_exhaustive()
+    }
+}
+
+type TrayCallback<State> = Box<dyn Fn(&mut State)>;
+
+/// The tray icon of the app, and its menu.
+pub struct TraySpec<State> {
+    pub icon: TrayIcon,
+    /// The text shown when hovering the icon, e.g. the status of the app.
+    pub tooltip: Option<String>,
+    pub menu: Vec<TrayItem<State>>,
+    on_click: Option<TrayCallback<State>>,
+    on_double_click: Option<TrayCallback<State>>,
+}
+
+impl<State> TraySpec<State> {
+    pub fn new(icon: TrayIcon) -> Self {
+        TraySpec {
+            icon,
+            tooltip: None,
+            menu: Vec::new(),
+            on_click: None,
+            on_double_click: None,
+        }
+    }
+
+    /// Builder-style method for setting the tooltip.
+    pub fn tooltip(mut self, tooltip: impl Into<String>) -> Self {
+        self.tooltip = Some(tooltip.into());
+        self
+    }
+
+    /// Add an item at the end of the menu.
+    pub fn item(mut self, item: TrayItem<State>) -> Self {
+        self.menu.push(item);
+        self
+    }
+
+    /// Set the items of the menu.
+    pub fn menu(mut self, menu: impl IntoIterator<Item = TrayItem<State>>) -> Self {
+        self.menu = menu.into_iter().collect();
+        self
+    }
+
+    /// Call `on_click` when the icon is clicked.
+    ///
+    /// On some platforms, e.g. most Linux desktops, clicking the icon opens the menu
+    /// instead.
+    pub fn on_click(mut self, on_click: impl Fn(&mut State) + 'static) -> Self {
+        self.on_click = Some(Box::new(on_click));
+        self
+    }
+
+    /// Call `on_double_click` when the icon is double clicked, usually to open the app.
+    pub fn on_double_click(mut self, on_double_click: impl Fn(&mut State) + 'static) -> Self {
+        self.on_double_click = Some(Box::new(on_double_click));
+        self
+    }
+}
+
+/// An item of the menu of the tray icon.
+pub struct TrayItem<State> {
+    entry: TrayMenuEntry,
+    on_activate: Option<TrayCallback<State>>,
+}
+
+impl<State> TrayItem<State> {
+    /// An item labeled `label`, calling `on_activate` when it's chosen.
+    pub fn new(label: impl Into<String>, on_activate: impl Fn(&mut State) + 'static) -> Self {
+        TrayItem {
+            entry: TrayMenuEntry::Item {
+                label: label.into(),
+                enabled: true,
+                checked: None,
+            },
+            on_activate: Some(Box::new(on_activate)),
+        }
+    }
+
+    /// A line between two groups of items.
+    pub fn separator() -> Self {
+        TrayItem {
+            entry: TrayMenuEntry::Separator,
+            on_activate: None,
+        }
+    }
+
+    /// Set whether the item can be chosen. Items are enabled by default.
+    pub fn enabled(mut self, enabled: bool) -> Self {
+        if let TrayMenuEntry::Item { enabled: e, .. } = &mut self.entry {
+            *e = enabled;
+        }
+        self
+    }
+
+    /// Show a check mark next to the item if `checked` is `true`.
+    pub fn checked(mut self, checked: bool) -> Self {
+        if let TrayMenuEntry::Item { checked: c, .. } = &mut self.entry {
+            *c = Some(checked);
+        }
+        self
+    }
+}
+
+/// An item of the tray menu, as shown by a [`TrayBackend`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TrayMenuEntry {
+    Item {
+        label: String,
+        enabled: bool,
+        /// Whether the item is checked, or `None` if it's not a check item.
+        checked: Option<bool>,
+    },
+    Separator,
+}
+
+/// Identifies an item of the tray menu in the [`TrayEvent`]s of a [`TrayBackend`].
+///
+/// An item keeps its id while it's rebuilt in place.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TrayItemId(u64);
+
+impl TrayItemId {
+    pub fn from_raw(raw: u64) -> Self {
+        TrayItemId(raw)
+    }
+
+    pub fn to_raw(self) -> u64 {
+        self.0
+    }
+}
+
+/// Something the user did with the tray icon.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TrayEvent {
+    /// An item of the menu was chosen.
+    MenuItem(TrayItemId),
+    Click,
+    DoubleClick,
+}
+
+/// Sends the [`TrayEvent`]s of a [`TrayBackend`] to the app, from any thread.
+#[derive(Clone)]
+pub struct TrayEventSink {
+    events: Arc<Mutex<VecDeque<TrayEvent>>>,
+    queue: Arc<MessageQueue>,
+}
+
+impl TrayEventSink {
+    pub fn send(&self, event: TrayEvent) {
+        self.events.lock().unwrap().push_back(event);
+        self.queue.wake();
+    }
+}
+
+/// Why the tray icon couldn't be shown.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TrayError {
+    /// The platform, or the desktop environment, has no system tray.
+    Unsupported,
+    /// The platform failed to create the tray icon.
+    Platform(String),
+}
+
+impl fmt::Display for TrayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrayError::Unsupported => f.write_str("no system tray is available"),
+            TrayError::Platform(message) => write!(f, "couldn't create the tray icon: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for TrayError {}
+
+/// The platform's system tray, as used by [`Xilem::with_tray_backend`](crate::Xilem::with_tray_backend).
+///
+/// The menu starts empty, and is then edited item by item. The indices of the edits are
+/// those of the menu after the previous edits.
+pub trait TrayBackend {
+    /// Show the tray icon, and send what the user does with it to `events`.
+    ///
+    /// If this fails, the app keeps running without a tray, and no other method is called.
+    fn create(
+        &mut self,
+        icon: &TrayIcon,
+        tooltip: Option<&str>,
+        events: TrayEventSink,
+    ) -> Result<(), TrayError>;
+
+    fn set_icon(&mut self, icon: &TrayIcon);
+
+    fn set_tooltip(&mut self, tooltip: Option<&str>);
+
+    fn insert_item(&mut self, index: usize, id: TrayItemId, entry: &TrayMenuEntry);
+
+    /// Replace the item at `index`, which may become another kind of item.
+    fn update_item(&mut self, index: usize, id: TrayItemId, entry: &TrayMenuEntry);
+
+    fn remove_item(&mut self, index: usize);
+
+    /// Remove the tray icon, when the app exits.
+    fn destroy(&mut self);
+}
+
+type TrayLogic<State> = Box<dyn FnMut(&mut State) -> TraySpec<State>>;
+
+/// The tray of a running app, kept in sync with the app state.
+pub(crate) struct Tray<State> {
+    logic: TrayLogic<State>,
+    backend: Box<dyn TrayBackend>,
+    events: TrayEventSink,
+    /// The spec shown by the backend, or `None` if the tray wasn't created (yet).
+    current: Option<TraySpec<State>>,
+    /// The ids of the items of `current`.
+    item_ids: Vec<TrayItemId>,
+    next_item_id: u64,
+}
+
+impl<State> Tray<State> {
+    pub(crate) fn new(
+        backend: Box<dyn TrayBackend>,
+        logic: TrayLogic<State>,
+        queue: Arc<MessageQueue>,
+    ) -> Self {
+        Tray {
+            logic,
+            backend,
+            events: TrayEventSink {
+                events: Arc::default(),
+                queue,
+            },
+            current: None,
+            item_ids: Vec::new(),
+            next_item_id: 0,
+        }
+    }
+
+    /// Show the tray icon, once the event loop is running.
+    pub(crate) fn create(&mut self, state: &mut State) {
+        let spec = (self.logic)(state);
+        let created = self
+            .backend
+            .create(&spec.icon, spec.tooltip.as_deref(), self.events.clone());
+        if let Err(err) = created {
+            tracing::warn!("The app runs without a tray icon: {err}");
+            return;
+        }
+        for (index, item) in spec.menu.iter().enumerate() {
+            let id = self.next_id();
+            self.backend.insert_item(index, id, &item.entry);
+            self.item_ids.push(id);
+        }
+        self.current = Some(spec);
+    }
+
+    /// Build the tray spec again, and apply what changed to the backend.
+    pub(crate) fn rebuild(&mut self, state: &mut State) {
+        if self.current.is_none() {
+            return;
+        }
+        let spec = (self.logic)(state);
+        let prev = self.current.take().unwrap();
+        if spec.icon != prev.icon {
+            self.backend.set_icon(&spec.icon);
+        }
+        if spec.tooltip != prev.tooltip {
+            self.backend.set_tooltip(spec.tooltip.as_deref());
+        }
+        let common = prev.menu.len().min(spec.menu.len());
+        for (index, (prev_item, item)) in prev.menu.iter().zip(&spec.menu).enumerate() {
+            if prev_item.entry != item.entry {
+                self.backend
+                    .update_item(index, self.item_ids[index], &item.entry);
+            }
+        }
+        for index in (common..prev.menu.len()).rev() {
+            self.backend.remove_item(index);
+        }
+        self.item_ids.truncate(common);
+        for (index, item) in spec.menu.iter().enumerate().skip(common) {
+            let id = self.next_id();
+            self.backend.insert_item(index, id, &item.entry);
+            self.item_ids.push(id);
+        }
+        self.current = Some(spec);
+    }
+
+    /// Handle the events sent by the backend, and return whether the app state must be
+    /// shown again.
+    pub(crate) fn handle_events(&mut self, state: &mut State) -> bool {
+        let events = std::mem::take(&mut *self.events.events.lock().unwrap());
+        let Some(spec) = &self.current else {
+            return false;
+        };
+        let mut changed = false;
+        for event in events {
+            let callback = match event {
+                TrayEvent::MenuItem(id) => self
+                    .item_ids
+                    .iter()
+                    .position(|item_id| *item_id == id)
+                    .and_then(|index| spec.menu[index].on_activate.as_ref()),
+                TrayEvent::Click => spec.on_click.as_ref(),
+                TrayEvent::DoubleClick => spec.on_double_click.as_ref(),
+            };
+            if let Some(callback) = callback {
+                callback(state);
+                changed = true;
+            } else if let TrayEvent::MenuItem(id) = event {
+                tracing::debug!("Discarding the event of the removed tray item {id:?}");
+            }
+        }
+        changed
+    }
+
+    fn next_id(&mut self) -> TrayItemId {
+        self.next_item_id += 1;
+        TrayItemId(self.next_item_id)
+    }
+}
+
+impl<State> Drop for Tray<State> {
+    fn drop(&mut self) {
+        if self.current.is_some() {
+            self.backend.destroy();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    enum Op {
+        Create(TrayIcon, Option<String>),
+        SetIcon(TrayIcon),
+        SetTooltip(Option<String>),
+        Insert(usize, TrayMenuEntry),
+        Update(usize, TrayMenuEntry),
+        Remove(usize),
+        Destroy,
+    }
+
+    /// A backend recording what's done to it, and keeping the sink to send events with.
+    #[derive(Clone, Default)]
+    struct MockBackend {
+        ops: Rc<RefCell<Vec<Op>>>,
+        sink: Rc<RefCell<Option<TrayEventSink>>>,
+        ids: Rc<RefCell<Vec<TrayItemId>>>,
+        unsupported: bool,
+    }
+
+    impl MockBackend {
+        fn take_ops(&self) -> Vec<Op> {
+            std::mem::take(&mut self.ops.borrow_mut())
+        }
+
+        fn choose(&self, index: usize) {
+            let id = self.ids.borrow()[index];
+            self.send(TrayEvent::MenuItem(id));
+        }
+
+        fn send(&self, event: TrayEvent) {
+            self.sink.borrow().as_ref().unwrap().send(event);
+        }
+    }
+
+    impl TrayBackend for MockBackend {
+        fn create(
+            &mut self,
+            icon: &TrayIcon,
+            tooltip: Option<&str>,
+            events: TrayEventSink,
+        ) -> Result<(), TrayError> {
+            if self.unsupported {
+                return Err(TrayError::Unsupported);
+            }
+            let op = Op::Create(icon.clone(), tooltip.map(Into::into));
+            self.ops.borrow_mut().push(op);
+            *self.sink.borrow_mut() = Some(events);
+            Ok(())
+        }
+
+        fn set_icon(&mut self, icon: &TrayIcon) {
+            self.ops.borrow_mut().push(Op::SetIcon(icon.clone()));
+        }
+
+        fn set_tooltip(&mut self, tooltip: Option<&str>) {
+            let op = Op::SetTooltip(tooltip.map(Into::into));
+            self.ops.borrow_mut().push(op);
+        }
+
+        fn insert_item(&mut self, index: usize, id: TrayItemId, entry: &TrayMenuEntry) {
+            self.ids.borrow_mut().insert(index, id);
+            self.ops.borrow_mut().push(Op::Insert(index, entry.clone()));
+        }
+
+        fn update_item(&mut self, index: usize, id: TrayItemId, entry: &TrayMenuEntry) {
+            assert_eq!(self.ids.borrow()[index], id);
+            self.ops.borrow_mut().push(Op::Update(index, entry.clone()));
+        }
+
+        fn remove_item(&mut self, index: usize) {
+            self.ids.borrow_mut().remove(index);
+            self.ops.borrow_mut().push(Op::Remove(index));
+        }
+
+        fn destroy(&mut self) {
+            self.ops.borrow_mut().push(Op::Destroy);
+        }
+    }
+
+    #[derive(Default)]
+    struct SyncClient {
+        paused: bool,
+        pending: u32,
+        opened: u32,
+        quitting: bool,
+    }
+
+    fn icon(value: u8) -> TrayIcon {
+        TrayIcon::from_rgba(vec![value; 4], 1, 1)
+    }
+
+    fn item(label: &str) -> TrayMenuEntry {
+        TrayMenuEntry::Item {
+            label: label.into(),
+            enabled: true,
+            checked: None,
+        }
+    }
+
+    fn sync_tray(sync: &mut SyncClient) -> TraySpec<SyncClient> {
+        let (icon, toggle) = if sync.paused {
+            (
+                icon(0),
+                TrayItem::new("Resume", |sync: &mut SyncClient| sync.paused = false),
+            )
+        } else {
+            (
+                icon(255),
+                TrayItem::new("Pause", |sync: &mut SyncClient| sync.paused = true),
+            )
+        };
+        let mut spec = TraySpec::new(icon)
+            .tooltip(format!("{} files to sync", sync.pending))
+            .item(toggle)
+            .item(TrayItem::new("Open", |sync: &mut SyncClient| {
+                sync.opened += 1;
+            }));
+        if !sync.quitting {
+            spec = spec.item(TrayItem::separator()).item(TrayItem::new(
+                "Quit",
+                |sync: &mut SyncClient| {
+                    sync.quitting = true;
+                },
+            ));
+        }
+        spec.on_double_click(|sync: &mut SyncClient| sync.opened += 1)
+    }
+
+    fn tray(backend: &MockBackend) -> Tray<SyncClient> {
+        Tray::new(
+            Box::new(backend.clone()),
+            Box::new(sync_tray),
+            Arc::default(),
+        )
+    }
+
+    #[test]
+    fn rebuilds_apply_differences() {
+        let backend = MockBackend::default();
+        let mut tray = tray(&backend);
+        let mut sync = SyncClient {
+            pending: 3,
+            ..Default::default()
+        };
+        tray.create(&mut sync);
+        assert_eq!(
+            backend.take_ops(),
+            [
+                Op::Create(icon(255), Some("3 files to sync".into())),
+                Op::Insert(0, item("Pause")),
+                Op::Insert(1, item("Open")),
+                Op::Insert(2, TrayMenuEntry::Separator),
+                Op::Insert(3, item("Quit")),
+            ]
+        );
+
+        // Nothing changed.
+        tray.rebuild(&mut sync);
+        assert_eq!(backend.take_ops(), []);
+
+        sync.paused = true;
+        tray.rebuild(&mut sync);
+        assert_eq!(
+            backend.take_ops(),
+            [Op::SetIcon(icon(0)), Op::Update(0, item("Resume"))]
+        );
+
+        sync.pending = 0;
+        sync.quitting = true;
+        tray.rebuild(&mut sync);
+        assert_eq!(
+            backend.take_ops(),
+            [
+                Op::SetTooltip(Some("0 files to sync".into())),
+                Op::Remove(3),
+                Op::Remove(2),
+            ]
+        );
+
+        sync.quitting = false;
+        tray.rebuild(&mut sync);
+        assert_eq!(
+            backend.take_ops(),
+            [
+                Op::Insert(2, TrayMenuEntry::Separator),
+                Op::Insert(3, item("Quit")),
+            ]
+        );
+
+        drop(tray);
+        assert_eq!(backend.take_ops(), [Op::Destroy]);
+    }
+
+    #[test]
+    fn events_reach_the_state() {
+        let backend = MockBackend::default();
+        let mut tray = tray(&backend);
+        let mut sync = SyncClient::default();
+        tray.create(&mut sync);
+        assert!(!tray.handle_events(&mut sync));
+
+        backend.choose(0);
+        assert!(tray.handle_events(&mut sync));
+        assert!(sync.paused);
+        tray.rebuild(&mut sync);
+
+        // The item keeps its id when it's rebuilt.
+        backend.choose(0);
+        backend.send(TrayEvent::DoubleClick);
+        assert!(tray.handle_events(&mut sync));
+        assert!(!sync.paused);
+        assert_eq!(sync.opened, 1);
+
+        // Clicks aren't handled, and the events of removed items are discarded.
+        let quit = backend.ids.borrow()[3];
+        backend.choose(3);
+        tray.handle_events(&mut sync);
+        tray.rebuild(&mut sync);
+        backend.send(TrayEvent::MenuItem(quit));
+        backend.send(TrayEvent::Click);
+        assert!(!tray.handle_events(&mut sync));
+        assert!(sync.quitting);
+    }
+
+    #[test]
+    fn missing_tray_is_not_fatal() {
+        let backend = MockBackend {
+            unsupported: true,
+            ..Default::default()
+        };
+        let mut tray = tray(&backend);
+        let mut sync = SyncClient::default();
+        tray.create(&mut sync);
+        sync.paused = true;
+        tray.rebuild(&mut sync);
+        assert!(!tray.handle_events(&mut sync));
+        drop(tray);
+        assert_eq!(backend.take_ops(), []);
+    }
+}
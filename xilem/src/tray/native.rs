@@ -0,0 +1,284 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! The tray of the platform, using the `tray-icon` crate.
+
+use tray_icon::{
+    menu::{CheckMenuItem, Menu, MenuEvent, MenuId, MenuItem, PredefinedMenuItem},
+    ClickType, TrayIconBuilder, TrayIconEvent,
+};
+
+use super::{
+    TrayBackend, TrayError, TrayEvent, TrayEventSink, TrayIcon, TrayItemId, TrayMenuEntry,
+};
+
+/// The prefix of the ids of our menu items, to tell them apart from those of other menus.
+const MENU_ID_PREFIX: &str = "xilem-tray-";
+
+/// Whether the platform is expected to have a system tray.
+///
+/// On Linux, this requires a desktop session. Even then, some desktop environments, like
+/// GNOME without extensions, don't show tray icons, which can't be detected.
+pub fn is_supported() -> bool {
+    if cfg!(target_os = "linux") {
+        let var_is_set = |name| std::env::var_os(name).is_some_and(|value| !value.is_empty());
+        var_is_set("DBUS_SESSION_BUS_ADDRESS")
+            && (var_is_set("DISPLAY") || var_is_set("WAYLAND_DISPLAY"))
+    } else {
+        true
+    }
+}
+
+/// The system tray of Windows, macOS and Linux desktops.
+///
+/// On Linux, the tray runs on a GTK thread of its own, and is unsupported if GTK or
+/// `libappindicator` isn't available.
+#[derive(Default)]
+pub struct NativeTray {
+    #[cfg(target_os = "linux")]
+    commands: Option<std::sync::mpsc::Sender<Command>>,
+    #[cfg(not(target_os = "linux"))]
+    platform: Option<Platform>,
+}
+
+impl NativeTray {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn send(&mut self, command: Command) {
+        #[cfg(target_os = "linux")]
+        if let Some(commands) = &self.commands {
+            // Fails if the GTK thread exited, in which case there's no tray to edit anymore.
+            let _ = commands.send(command);
+        }
+        #[cfg(not(target_os = "linux"))]
+        if let Some(platform) = &mut self.platform {
+            platform.apply(command);
+        }
+    }
+}
+
+impl TrayBackend for NativeTray {
+    fn create(
+        &mut self,
+        icon: &TrayIcon,
+        tooltip: Option<&str>,
+        events: TrayEventSink,
+    ) -> Result<(), TrayError> {
+        #[cfg(target_os = "linux")]
+        {
+            self.commands = Some(linux::spawn(icon.clone(), tooltip.map(Into::into), events)?);
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            self.platform = Some(Platform::new(icon, tooltip, events)?);
+        }
+        Ok(())
+    }
+
+    fn set_icon(&mut self, icon: &TrayIcon) {
+        self.send(Command::SetIcon(icon.clone()));
+    }
+
+    fn set_tooltip(&mut self, tooltip: Option<&str>) {
+        self.send(Command::SetTooltip(tooltip.map(Into::into)));
+    }
+
+    fn insert_item(&mut self, index: usize, id: TrayItemId, entry: &TrayMenuEntry) {
+        self.send(Command::Insert(index, id, entry.clone()));
+    }
+
+    fn update_item(&mut self, index: usize, id: TrayItemId, entry: &TrayMenuEntry) {
+        self.send(Command::Remove(index));
+        self.send(Command::Insert(index, id, entry.clone()));
+    }
+
+    fn remove_item(&mut self, index: usize) {
+        self.send(Command::Remove(index));
+    }
+
+    fn destroy(&mut self) {
+        self.send(Command::Destroy);
+    }
+}
+
+/// An edit of the tray, sent to the thread owning it.
+enum Command {
+    SetIcon(TrayIcon),
+    SetTooltip(Option<String>),
+    Insert(usize, TrayItemId, TrayMenuEntry),
+    Remove(usize),
+    Destroy,
+}
+
+/// The tray icon and its menu, which must stay on the thread which created them.
+struct Platform {
+    tray: Option<tray_icon::TrayIcon>,
+    menu: Menu,
+}
+
+impl Platform {
+    fn new(
+        icon: &TrayIcon,
+        tooltip: Option<&str>,
+        events: TrayEventSink,
+    ) -> Result<Self, TrayError> {
+        let menu = Menu::new();
+        let mut builder = TrayIconBuilder::new()
+            .with_icon(platform_icon(icon)?)
+            .with_menu(Box::new(menu.clone()));
+        if let Some(tooltip) = tooltip {
+            builder = builder.with_tooltip(tooltip);
+        }
+        let tray = builder
+            .build()
+            .map_err(|err| TrayError::Platform(err.to_string()))?;
+
+        let menu_events = events.clone();
+        MenuEvent::set_event_handler(Some(move |event: MenuEvent| {
+            let id = event.id.0.strip_prefix(MENU_ID_PREFIX);
+            if let Some(id) = id.and_then(|id| id.parse().ok()) {
+                menu_events.send(TrayEvent::MenuItem(TrayItemId::from_raw(id)));
+            }
+        }));
+        TrayIconEvent::set_event_handler(Some(move |event: TrayIconEvent| {
+            match event.click_type {
+                ClickType::Left => events.send(TrayEvent::Click),
+                ClickType::Double => events.send(TrayEvent::DoubleClick),
+                ClickType::Right => {}
+            }
+        }));
+
+        Ok(Platform {
+            tray: Some(tray),
+            menu,
+        })
+    }
+
+    fn apply(&mut self, command: Command) {
+        let result = match command {
+            Command::SetIcon(icon) => match (&self.tray, platform_icon(&icon)) {
+                (Some(tray), Ok(icon)) => tray.set_icon(Some(icon)).map_err(|err| err.to_string()),
+                (_, Err(err)) => Err(err.to_string()),
+                (None, _) => Ok(()),
+            },
+            Command::SetTooltip(tooltip) => match &self.tray {
+                Some(tray) => tray.set_tooltip(tooltip).map_err(|err| err.to_string()),
+                None => Ok(()),
+            },
+            Command::Insert(index, id, entry) => self.insert(index, id, &entry),
+            Command::Remove(index) => {
+                self.menu.remove_at(index);
+                Ok(())
+            }
+            Command::Destroy => {
+                MenuEvent::set_event_handler(None::<fn(MenuEvent)>);
+                TrayIconEvent::set_event_handler(None::<fn(TrayIconEvent)>);
+                // Dropping the tray icon removes it.
+                self.tray = None;
+                Ok(())
+            }
+        };
+        if let Err(err) = result {
+            tracing::warn!("Failed to update the tray icon: {err}");
+        }
+    }
+
+    fn insert(&self, index: usize, id: TrayItemId, entry: &TrayMenuEntry) -> Result<(), String> {
+        let menu_id = MenuId::new(format!("{MENU_ID_PREFIX}{}", id.to_raw()));
+        let result = match entry {
+            TrayMenuEntry::Item {
+                label,
+                enabled,
+                checked: None,
+            } => self
+                .menu
+                .insert(&MenuItem::with_id(menu_id, label, *enabled, None), index),
+            TrayMenuEntry::Item {
+                label,
+                enabled,
+                checked: Some(checked),
+            } => self.menu.insert(
+                &CheckMenuItem::with_id(menu_id, label, *enabled, *checked, None),
+                index,
+            ),
+            TrayMenuEntry::Separator => self.menu.insert(&PredefinedMenuItem::separator(), index),
+        };
+        result.map_err(|err| err.to_string())
+    }
+}
+
+fn platform_icon(icon: &TrayIcon) -> Result<tray_icon::Icon, TrayError> {
+    tray_icon::Icon::from_rgba(icon.rgba().to_vec(), icon.width(), icon.height())
+        .map_err(|err| TrayError::Platform(err.to_string()))
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::{
+        panic::{self, AssertUnwindSafe},
+        sync::mpsc::{self, Sender, TryRecvError},
+        time::Duration,
+    };
+
+    use gtk::glib::{self, ControlFlow};
+
+    use super::{Command, Platform};
+    use crate::tray::{TrayError, TrayEventSink, TrayIcon};
+
+    /// How often the GTK thread looks for edits of the tray.
+    const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+    /// Create the tray on a GTK thread, and return the channel to send it edits.
+    ///
+    /// The tray-icon crate needs GTK on Linux, which winit doesn't run.
+    pub(super) fn spawn(
+        icon: TrayIcon,
+        tooltip: Option<String>,
+        events: TrayEventSink,
+    ) -> Result<Sender<Command>, TrayError> {
+        let (created_tx, created_rx) = mpsc::channel();
+        let (commands, command_rx) = mpsc::channel();
+        std::thread::Builder::new()
+            .name("xilem-tray".into())
+            .spawn(move || {
+                if gtk::init().is_err() {
+                    let _ = created_tx.send(Err(TrayError::Unsupported));
+                    return;
+                }
+                // tray-icon panics if libappindicator can't be loaded.
+                let platform = panic::catch_unwind(AssertUnwindSafe(|| {
+                    Platform::new(&icon, tooltip.as_deref(), events)
+                }));
+                let mut platform = match platform {
+                    Ok(Ok(platform)) => platform,
+                    Ok(Err(err)) => {
+                        let _ = created_tx.send(Err(err));
+                        return;
+                    }
+                    Err(_) => {
+                        let _ = created_tx.send(Err(TrayError::Unsupported));
+                        return;
+                    }
+                };
+                let _ = created_tx.send(Ok(()));
+
+                glib::timeout_add_local(POLL_INTERVAL, move || loop {
+                    match command_rx.try_recv() {
+                        Ok(Command::Destroy) | Err(TryRecvError::Disconnected) => {
+                            platform.apply(Command::Destroy);
+                            gtk::main_quit();
+                            return ControlFlow::Break;
+                        }
+                        Ok(command) => platform.apply(command),
+                        Err(TryRecvError::Empty) => return ControlFlow::Continue,
+                    }
+                });
+                gtk::main();
+            })
+            .map_err(|err| TrayError::Platform(err.to_string()))?;
+        created_rx.recv().unwrap_or(Err(TrayError::Unsupported))?;
+        Ok(commands)
+    }
+}
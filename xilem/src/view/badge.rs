@@ -0,0 +1,63 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+use std::any::Any;
+
+use masonry::{widget::WidgetMut, Badge, WidgetPod};
+
+use crate::{MasonryView, MessageResult, ViewCx, ViewId};
+
+/// A view painting a [`Badge`] above the widget of its child.
+///
+/// This is created with [`MasonryView::badge`].
+pub struct Badged<V> {
+    child: V,
+    badge: Option<Badge>,
+}
+
+impl<V> Badged<V> {
+    pub(crate) fn new(child: V, badge: Option<Badge>) -> Self {
+        Badged { child, badge }
+    }
+}
+
+impl<State, Action, V> MasonryView<State, Action> for Badged<V>
+where
+    V: MasonryView<State, Action>,
+{
+    type Element = V::Element;
+    type ViewState = V::ViewState;
+
+    fn build(&self, cx: &mut ViewCx) -> (WidgetPod<Self::Element>, Self::ViewState) {
+        let (element, view_state) = self.child.build(cx);
+        let element = match &self.badge {
+            Some(badge) => element.with_badge(badge.clone()),
+            None => element,
+        };
+        (element, view_state)
+    }
+
+    fn rebuild(
+        &self,
+        view_state: &mut Self::ViewState,
+        cx: &mut ViewCx,
+        prev: &Self,
+        mut element: WidgetMut<Self::Element>,
+    ) {
+        self.child
+            .rebuild(view_state, cx, &prev.child, element.reborrow_mut());
+        if prev.badge != self.badge {
+            element.set_badge(self.badge.clone());
+        }
+    }
+
+    fn message(
+        &self,
+        view_state: &mut Self::ViewState,
+        id_path: &[ViewId],
+        message: Box<dyn Any>,
+        app_state: &mut State,
+    ) -> MessageResult<Action> {
+        self.child.message(view_state, id_path, message, app_state)
+    }
+}
@@ -0,0 +1,80 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+use std::any::Any;
+
+use masonry::{widget::WidgetMut, WidgetPod};
+
+use crate::{MasonryView, MessageResult, ViewCx, ViewId};
+
+/// Dim `child` and block all input to it while `is_active` is true, e.g. during a
+/// blocking operation, showing a spinner above it.
+///
+/// Backed by [`masonry::widget::BlockingOverlay`]. Unlike a modal, the overlay can't be
+/// dismissed by the user, e.g. with Escape: it stays until `is_active` is false.
+pub fn blocking_overlay<V>(is_active: bool, child: V) -> BlockingOverlay<V> {
+    BlockingOverlay {
+        child,
+        is_active,
+        show_spinner: true,
+    }
+}
+
+pub struct BlockingOverlay<V> {
+    child: V,
+    is_active: bool,
+    show_spinner: bool,
+}
+
+impl<V> BlockingOverlay<V> {
+    /// Set whether a spinner is shown above the child while the overlay is active.
+    pub fn spinner(mut self, show_spinner: bool) -> Self {
+        self.show_spinner = show_spinner;
+        self
+    }
+}
+
+impl<State, Action, V> MasonryView<State, Action> for BlockingOverlay<V>
+where
+    V: MasonryView<State, Action>,
+{
+    type Element = masonry::widget::BlockingOverlay<V::Element>;
+    type ViewState = V::ViewState;
+
+    fn build(&self, cx: &mut ViewCx) -> (WidgetPod<Self::Element>, Self::ViewState) {
+        let (child, view_state) = self.child.build(cx);
+        let overlay = masonry::widget::BlockingOverlay::new_pod(child)
+            .with_active(self.is_active)
+            .with_spinner(self.show_spinner);
+        (WidgetPod::new(overlay), view_state)
+    }
+
+    fn rebuild(
+        &self,
+        view_state: &mut Self::ViewState,
+        cx: &mut ViewCx,
+        prev: &Self,
+        mut element: WidgetMut<Self::Element>,
+    ) {
+        self.child
+            .rebuild(view_state, cx, &prev.child, element.child_mut());
+        if prev.show_spinner != self.show_spinner {
+            element.set_show_spinner(self.show_spinner);
+            cx.mark_changed();
+        }
+        if prev.is_active != self.is_active {
+            element.set_active(self.is_active);
+            cx.mark_changed();
+        }
+    }
+
+    fn message(
+        &self,
+        view_state: &mut Self::ViewState,
+        id_path: &[ViewId],
+        message: Box<dyn Any>,
+        app_state: &mut State,
+    ) -> MessageResult<Action> {
+        self.child.message(view_state, id_path, message, app_state)
+    }
+}
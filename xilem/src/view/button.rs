@@ -3,7 +3,7 @@
 
 use masonry::{widget::WidgetMut, ArcStr, WidgetPod};
 
-use crate::{MasonryView, MessageResult, ViewCx, ViewId};
+use crate::{ButtonVariant, MasonryView, MessageResult, ViewCx, ViewId};
 
 pub fn button<F, State, Action>(label: impl Into<ArcStr>, callback: F) -> Button<F>
 where
@@ -12,12 +12,22 @@ where
     Button {
         label: label.into(),
         callback,
+        variant: None,
     }
 }
 
 pub struct Button<F> {
     label: ArcStr,
     callback: F,
+    variant: Option<ButtonVariant>,
+}
+
+impl<F> Button<F> {
+    /// Style the button with the colors the [`Theme`](crate::Theme) defines for `variant`.
+    pub fn variant(mut self, variant: ButtonVariant) -> Self {
+        self.variant = Some(variant);
+        self
+    }
 }
 
 impl<F, State, Action> MasonryView<State, Action> for Button<F>
@@ -29,7 +39,11 @@ where
 
     fn build(&self, cx: &mut ViewCx) -> (WidgetPod<Self::Element>, Self::ViewState) {
         cx.with_leaf_action_widget(|_| {
-            WidgetPod::new(masonry::widget::Button::new(self.label.clone()))
+            let mut button = masonry::widget::Button::new(self.label.clone());
+            if let Some(variant) = &self.variant {
+                button = button.with_variant(variant.clone());
+            }
+            WidgetPod::new(button)
         })
     }
 
@@ -44,6 +58,10 @@ where
             element.set_text(self.label.clone());
             cx.mark_changed();
         }
+        if prev.variant != self.variant {
+            element.set_variant(self.variant.clone());
+            cx.mark_changed();
+        }
     }
 
     fn message(
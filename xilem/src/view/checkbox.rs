@@ -1,34 +1,42 @@
 // Copyright 2024 the Xilem Authors
 // SPDX-License-Identifier: Apache-2.0
 
-use masonry::{widget::WidgetMut, ArcStr, WidgetPod};
+use masonry::{
+    widget::{CheckState, WidgetMut},
+    ArcStr, WidgetPod,
+};
 
 use crate::{MasonryView, MessageResult, ViewCx, ViewId};
 
+/// A checkbox, which is either checked, unchecked or indeterminate.
+///
+/// `state` is either a `bool` or a [`CheckState`]. `callback` is called with the state
+/// chosen by the user, which is never [`CheckState::Indeterminate`]: toggling an
+/// indeterminate checkbox checks it.
 pub fn checkbox<F, State, Action>(
     label: impl Into<ArcStr>,
-    checked: bool,
+    state: impl Into<CheckState>,
     callback: F,
 ) -> Checkbox<F>
 where
-    F: Fn(&mut State, bool) -> Action + Send + 'static,
+    F: Fn(&mut State, CheckState) -> Action + Send + 'static,
 {
     Checkbox {
         label: label.into(),
         callback,
-        checked,
+        state: state.into(),
     }
 }
 
 pub struct Checkbox<F> {
     label: ArcStr,
-    checked: bool,
+    state: CheckState,
     callback: F,
 }
 
 impl<F, State, Action> MasonryView<State, Action> for Checkbox<F>
 where
-    F: Fn(&mut State, bool) -> Action + Send + Sync + 'static,
+    F: Fn(&mut State, CheckState) -> Action + Send + Sync + 'static,
 {
     type Element = masonry::widget::Checkbox;
     type ViewState = ();
@@ -36,7 +44,7 @@ where
     fn build(&self, cx: &mut ViewCx) -> (WidgetPod<Self::Element>, Self::ViewState) {
         cx.with_leaf_action_widget(|_| {
             WidgetPod::new(masonry::widget::Checkbox::new(
-                self.checked,
+                self.state,
                 self.label.clone(),
             ))
         })
@@ -53,8 +61,8 @@ where
             element.set_text(self.label.clone());
             cx.mark_changed();
         }
-        if prev.checked != self.checked {
-            element.set_checked(self.checked);
+        if prev.state != self.state {
+            element.set_check_state(self.state);
             cx.mark_changed();
         }
     }
@@ -73,7 +81,7 @@ where
         match message.downcast::<masonry::Action>() {
             Ok(action) => {
                 if let masonry::Action::CheckboxChecked(checked) = *action {
-                    MessageResult::Action((self.callback)(app_state, checked))
+                    MessageResult::Action((self.callback)(app_state, checked.into()))
                 } else {
                     tracing::error!("Wrong action type in Checkbox::message: {action:?}");
                     MessageResult::Stale(action)
@@ -86,3 +94,72 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{assert_element_stable, ViewHarness};
+
+    #[test]
+    fn checkbox_maps_check_states() {
+        let mut harness = ViewHarness::new(CheckState::Unchecked, |state: &mut CheckState| {
+            checkbox("Check me", *state, |state: &mut CheckState, new_state| {
+                *state = new_state;
+            })
+        });
+        let checkbox_id = harness.masonry_harness().root_widget().children()[0].id();
+        let widget_state = |harness: &mut ViewHarness<_, _, _>| {
+            let checkbox = harness.masonry_harness().get_widget(checkbox_id);
+            checkbox
+                .downcast::<masonry::widget::Checkbox>()
+                .unwrap()
+                .check_state()
+        };
+
+        for check_state in [
+            CheckState::Checked,
+            CheckState::Indeterminate,
+            CheckState::Unchecked,
+        ] {
+            *harness.state() = check_state;
+            harness.rebuild();
+            assert_eq!(widget_state(&mut harness), check_state);
+        }
+
+        // Clicking an indeterminate checkbox checks it.
+        *harness.state() = CheckState::Indeterminate;
+        harness.rebuild();
+        harness.masonry_harness().mouse_click_on(checkbox_id);
+        harness.process_actions();
+        assert_eq!(*harness.state(), CheckState::Checked);
+        assert_eq!(widget_state(&mut harness), CheckState::Checked);
+
+        harness.masonry_harness().mouse_click_on(checkbox_id);
+        harness.process_actions();
+        assert_eq!(*harness.state(), CheckState::Unchecked);
+    }
+
+    #[test]
+    fn value_only_rebuild_keeps_element() {
+        let mut harness = ViewHarness::new(
+            (CheckState::Unchecked, 0),
+            |(state, _): &mut (CheckState, u32)| {
+                assert_element_stable(checkbox(
+                    "Check me",
+                    *state,
+                    |(state, _): &mut (CheckState, u32), new_state| *state = new_state,
+                ))
+            },
+        );
+        harness.masonry_harness().paint_damage();
+
+        // An unchanged value doesn't touch the widget.
+        harness.state().1 += 1;
+        harness.rebuild();
+        assert_eq!(harness.masonry_harness().paint_damage(), vec![]);
+
+        harness.state().0 = CheckState::Indeterminate;
+        harness.rebuild();
+        assert!(!harness.masonry_harness().paint_damage().is_empty());
+    }
+}
@@ -0,0 +1,165 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{any::Any, marker::PhantomData};
+
+use masonry::{widget::WidgetMut, ArcStr, WidgetPod};
+
+use crate::{MasonryView, MessageResult, ViewCx, ViewId};
+
+type MenuCallback<State, Action> = Box<dyn Fn(&mut State) -> Action + Send + Sync>;
+
+/// An item of a [`context_menu`], calling `callback` when selected.
+pub fn menu_item<State, Action>(
+    label: impl Into<ArcStr>,
+    callback: impl Fn(&mut State) -> Action + Send + Sync + 'static,
+) -> MenuItem<State, Action> {
+    MenuItem {
+        label: label.into(),
+        callback: Box::new(callback),
+    }
+}
+
+pub struct MenuItem<State, Action> {
+    label: ArcStr,
+    callback: MenuCallback<State, Action>,
+}
+
+/// A menu of `items` opening over `child` on right-click or long-press, backed by
+/// [`masonry::widget::ContextMenu`].
+///
+/// The items can change from one rebuild to the next. If context menus are nested,
+/// only the innermost one opens.
+pub fn context_menu<State, Action, V>(
+    child: V,
+    items: impl IntoIterator<Item = MenuItem<State, Action>>,
+) -> ContextMenu<State, Action, V>
+where
+    V: MasonryView<State, Action>,
+{
+    ContextMenu {
+        child,
+        items: items.into_iter().collect(),
+        phantom: PhantomData,
+    }
+}
+
+pub struct ContextMenu<State, Action, V> {
+    child: V,
+    items: Vec<MenuItem<State, Action>>,
+    phantom: PhantomData<fn() -> (State, Action)>,
+}
+
+impl<State, Action, V> MasonryView<State, Action> for ContextMenu<State, Action, V>
+where
+    State: 'static,
+    Action: 'static,
+    V: MasonryView<State, Action>,
+{
+    type Element = masonry::widget::ContextMenu<V::Element>;
+    type ViewState = V::ViewState;
+
+    fn build(&self, cx: &mut ViewCx) -> (WidgetPod<Self::Element>, Self::ViewState) {
+        let (child, child_state) = cx.with_id(ViewId::for_type::<V>(0), |cx| self.child.build(cx));
+        let menu = self.items.iter().fold(
+            masonry::widget::ContextMenu::new_pod(child),
+            |menu, item| menu.with_item(item.label.clone()),
+        );
+        let element = cx.with_action_widget(|_| WidgetPod::new(menu));
+        (element, child_state)
+    }
+
+    fn rebuild(
+        &self,
+        view_state: &mut Self::ViewState,
+        cx: &mut ViewCx,
+        prev: &Self,
+        mut element: WidgetMut<Self::Element>,
+    ) {
+        cx.with_id(ViewId::for_type::<V>(0), |cx| {
+            self.child
+                .rebuild(view_state, cx, &prev.child, element.child_mut());
+        });
+        if prev.items.len() != self.items.len() {
+            element.set_items(self.items.iter().map(|item| item.label.clone()));
+            cx.mark_changed();
+        } else {
+            for (index, (prev_item, item)) in prev.items.iter().zip(&self.items).enumerate() {
+                if prev_item.label != item.label {
+                    element.set_item_label(index, item.label.clone());
+                    cx.mark_changed();
+                }
+            }
+        }
+    }
+
+    fn message(
+        &self,
+        view_state: &mut Self::ViewState,
+        id_path: &[ViewId],
+        message: Box<dyn Any>,
+        app_state: &mut State,
+    ) -> MessageResult<Action> {
+        if let Some((_, rest)) = id_path.split_first() {
+            return self.child.message(view_state, rest, message, app_state);
+        }
+        match message.downcast::<masonry::Action>() {
+            Ok(action) => {
+                if let masonry::Action::ContextMenuItemSelected(index) = *action {
+                    match self.items.get(index) {
+                        Some(item) => MessageResult::Action((item.callback)(app_state)),
+                        None => {
+                            tracing::error!("Selected context menu item {index} doesn't exist");
+                            MessageResult::Nop
+                        }
+                    }
+                } else {
+                    tracing::error!("Wrong action type in ContextMenu::message: {action:?}");
+                    MessageResult::Stale(action)
+                }
+            }
+            Err(message) => {
+                tracing::error!("Wrong message type in ContextMenu::message");
+                MessageResult::Stale(message)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use winit::event::MouseButton;
+
+    use super::*;
+    use crate::testing::ViewHarness;
+    use crate::view::label;
+
+    #[test]
+    fn context_menu_item_updates_state() {
+        let mut harness = ViewHarness::new(0, |count: &mut u32| {
+            context_menu(
+                label("Right-click me"),
+                [menu_item(
+                    format!("Increment {count}"),
+                    |count: &mut u32| {
+                        *count += 1;
+                    },
+                )],
+            )
+        });
+        let masonry = harness.masonry_harness();
+        let menu_id = masonry.root_widget().children()[0].id();
+        masonry.mouse_move_to(menu_id);
+        masonry.mouse_button_press(MouseButton::Right);
+        masonry.mouse_button_release(MouseButton::Right);
+        let item_id = masonry.get_widget(menu_id).children()[1].id();
+        masonry.mouse_click_on(item_id);
+
+        harness.process_actions();
+        assert_eq!(*harness.state(), 1);
+
+        let item = harness.masonry_harness().get_widget(item_id);
+        let item = item.downcast::<masonry::widget::Label>().unwrap();
+        assert_eq!(item.text().as_ref(), "Increment 1");
+    }
+}
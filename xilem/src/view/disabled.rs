@@ -0,0 +1,97 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+use std::any::Any;
+
+use masonry::{widget::WidgetMut, WidgetPod};
+
+use crate::{MasonryView, MessageResult, ViewCx, ViewId};
+
+/// Disable the widget of `child` if `disabled` is true, and all of its descendants.
+///
+/// Disabled widgets ignore input and are skipped by keyboard focus. A widget inside a
+/// disabled view stays disabled, even if it's itself in a view which isn't.
+pub fn disabled<V>(child: V, disabled: bool) -> Disabled<V> {
+    Disabled { child, disabled }
+}
+
+pub struct Disabled<V> {
+    child: V,
+    disabled: bool,
+}
+
+impl<State, Action, V> MasonryView<State, Action> for Disabled<V>
+where
+    V: MasonryView<State, Action>,
+{
+    type Element = V::Element;
+    type ViewState = V::ViewState;
+
+    fn build(&self, cx: &mut ViewCx) -> (WidgetPod<Self::Element>, Self::ViewState) {
+        let (element, view_state) = self.child.build(cx);
+        (element.with_disabled(self.disabled), view_state)
+    }
+
+    fn rebuild(
+        &self,
+        view_state: &mut Self::ViewState,
+        cx: &mut ViewCx,
+        prev: &Self,
+        mut element: WidgetMut<Self::Element>,
+    ) {
+        self.child
+            .rebuild(view_state, cx, &prev.child, element.reborrow_mut());
+        if prev.disabled != self.disabled {
+            element.ctx.set_disabled(self.disabled);
+            cx.mark_changed();
+        }
+    }
+
+    fn message(
+        &self,
+        view_state: &mut Self::ViewState,
+        id_path: &[ViewId],
+        message: Box<dyn Any>,
+        app_state: &mut State,
+    ) -> MessageResult<Action> {
+        self.child.message(view_state, id_path, message, app_state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::ViewHarness;
+    use crate::view::{button, flex};
+
+    #[derive(Default)]
+    struct AppState {
+        enabled: bool,
+        count: u32,
+    }
+
+    #[test]
+    fn disabled_view_disables_nested_button() {
+        let mut harness = ViewHarness::new(AppState::default(), |state: &mut AppState| {
+            // Enabling the button isn't enough inside a disabled flex.
+            let increment = disabled(
+                button("Increment", |state: &mut AppState| state.count += 1),
+                false,
+            );
+            disabled(flex(increment), !state.enabled)
+        });
+        let masonry = harness.masonry_harness();
+        let flex_id = masonry.root_widget().children()[0].id();
+        let button_id = masonry.get_widget(flex_id).children()[0].id();
+
+        harness.masonry_harness().mouse_click_on(button_id);
+        harness.process_actions();
+        assert_eq!(harness.state().count, 0);
+
+        harness.state().enabled = true;
+        harness.rebuild();
+        harness.masonry_harness().mouse_click_on(button_id);
+        harness.process_actions();
+        assert_eq!(harness.state().count, 1);
+    }
+}
@@ -176,3 +176,56 @@ impl ElementSplice for FlexSplice<'_> {
         self.ix / 2
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{assert_element_stable, ViewHarness};
+    use crate::view::label;
+
+    #[derive(Default)]
+    struct AppState {
+        horizontal: bool,
+        count: u32,
+    }
+
+    #[test]
+    fn flex_params_change_keeps_element() {
+        let mut harness = ViewHarness::new(AppState::default(), |state: &mut AppState| {
+            let axis = if state.horizontal {
+                Axis::Horizontal
+            } else {
+                Axis::Vertical
+            };
+            assert_element_stable(flex((label("a"), label("b"))).direction(axis))
+        });
+        let flex_id = harness.masonry_harness().root_widget().children()[0].id();
+        let origins = |harness: &mut ViewHarness<_, _, _>| {
+            let flex = harness.masonry_harness().get_widget(flex_id);
+            flex.children()
+                .iter()
+                .map(|child| child.state().window_layout_rect().origin())
+                .collect::<Vec<_>>()
+        };
+        let vertical = origins(&mut harness);
+        assert!(vertical[1].y > vertical[0].y);
+        harness.masonry_harness().paint_damage();
+
+        // Rebuilding with the same params keeps them, and doesn't touch the widget.
+        harness.state().count += 1;
+        harness.rebuild();
+        assert_eq!(harness.masonry_harness().paint_damage(), vec![]);
+        assert_eq!(origins(&mut harness), vertical);
+
+        // Changing them updates the widget in place.
+        harness.state().horizontal = true;
+        harness.rebuild();
+        let horizontal = origins(&mut harness);
+        assert!(horizontal[1].x > horizontal[0].x);
+        assert_eq!(horizontal[1].y, horizontal[0].y);
+
+        harness.state().count += 1;
+        harness.rebuild();
+        assert_eq!(origins(&mut harness), horizontal);
+    }
+}
@@ -0,0 +1,157 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+use std::any::Any;
+
+use masonry::{widget::WidgetMut, WidgetPod};
+
+use crate::{MasonryView, MessageResult, ViewCx, ViewId};
+
+/// A view setting the tab index of the widget of its child.
+///
+/// This is created with [`MasonryView::tab_index`].
+pub struct TabIndex<V> {
+    child: V,
+    tab_index: i32,
+}
+
+impl<V> TabIndex<V> {
+    pub(crate) fn new(child: V, tab_index: i32) -> Self {
+        TabIndex { child, tab_index }
+    }
+}
+
+impl<State, Action, V> MasonryView<State, Action> for TabIndex<V>
+where
+    V: MasonryView<State, Action>,
+{
+    type Element = V::Element;
+    type ViewState = V::ViewState;
+
+    fn build(&self, cx: &mut ViewCx) -> (WidgetPod<Self::Element>, Self::ViewState) {
+        let (element, view_state) = self.child.build(cx);
+        (element.with_tab_index(Some(self.tab_index)), view_state)
+    }
+
+    fn rebuild(
+        &self,
+        view_state: &mut Self::ViewState,
+        cx: &mut ViewCx,
+        prev: &Self,
+        mut element: WidgetMut<Self::Element>,
+    ) {
+        self.child
+            .rebuild(view_state, cx, &prev.child, element.reborrow_mut());
+        if prev.tab_index != self.tab_index {
+            element.set_tab_index(Some(self.tab_index));
+        }
+    }
+
+    fn message(
+        &self,
+        view_state: &mut Self::ViewState,
+        id_path: &[ViewId],
+        message: Box<dyn Any>,
+        app_state: &mut State,
+    ) -> MessageResult<Action> {
+        self.child.message(view_state, id_path, message, app_state)
+    }
+}
+
+/// A view setting the position of the widget of its child in the reading order of its
+/// siblings.
+///
+/// This is created with [`MasonryView::reading_order`].
+pub struct ReadingOrder<V> {
+    child: V,
+    reading_order: u32,
+}
+
+impl<V> ReadingOrder<V> {
+    pub(crate) fn new(child: V, reading_order: u32) -> Self {
+        ReadingOrder {
+            child,
+            reading_order,
+        }
+    }
+}
+
+impl<State, Action, V> MasonryView<State, Action> for ReadingOrder<V>
+where
+    V: MasonryView<State, Action>,
+{
+    type Element = V::Element;
+    type ViewState = V::ViewState;
+
+    fn build(&self, cx: &mut ViewCx) -> (WidgetPod<Self::Element>, Self::ViewState) {
+        let (element, view_state) = self.child.build(cx);
+        (
+            element.with_reading_order(Some(self.reading_order)),
+            view_state,
+        )
+    }
+
+    fn rebuild(
+        &self,
+        view_state: &mut Self::ViewState,
+        cx: &mut ViewCx,
+        prev: &Self,
+        mut element: WidgetMut<Self::Element>,
+    ) {
+        self.child
+            .rebuild(view_state, cx, &prev.child, element.reborrow_mut());
+        if prev.reading_order != self.reading_order {
+            element.set_reading_order(Some(self.reading_order));
+        }
+    }
+
+    fn message(
+        &self,
+        view_state: &mut Self::ViewState,
+        id_path: &[ViewId],
+        message: Box<dyn Any>,
+        app_state: &mut State,
+    ) -> MessageResult<Action> {
+        self.child.message(view_state, id_path, message, app_state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::ViewHarness;
+    use crate::view::{button, flex};
+
+    #[test]
+    fn tab_index_overrides_focus_order() {
+        let mut harness = ViewHarness::new(2, |second_index: &mut i32| {
+            flex((
+                button("Third", |_: &mut i32| {}).tab_index(3),
+                button("First", |_: &mut i32| {}).tab_index(1),
+                button("Second", |_: &mut i32| {}).tab_index(*second_index),
+            ))
+        });
+        let masonry = harness.masonry_harness();
+        let flex_id = masonry.root_widget().children()[0].id();
+        let [third, first, second] =
+            [0, 1, 2].map(|i| masonry.get_widget(flex_id).children()[i].id());
+        let focused = |harness: &mut ViewHarness<_, _, _>| {
+            harness
+                .masonry_harness()
+                .focused_widget()
+                .map(|widget| widget.id())
+        };
+
+        for expected in [first, second, third] {
+            harness.masonry_harness().move_focus(true);
+            assert_eq!(focused(&mut harness), Some(expected));
+        }
+
+        *harness.state() = 4;
+        harness.rebuild();
+        for expected in [second, first, third] {
+            harness.masonry_harness().move_focus(true);
+            assert_eq!(focused(&mut harness), Some(expected));
+        }
+    }
+}
@@ -0,0 +1,277 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{any::Any, marker::PhantomData, sync::Arc};
+
+use accesskit::Role;
+use masonry::widget::{WidgetMut, WidgetRef};
+use masonry::{
+    AccessCtx, AccessEvent, BoxConstraints, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx,
+    Point, PointerEvent, Size, StatusChange, TextEvent, Widget, WidgetPod,
+};
+use smallvec::SmallVec;
+use vello::Scene;
+
+use crate::hot_reload::StableStash;
+use crate::{MasonryView, MessageResult, ViewCx, ViewId};
+
+/// Keep the widgets and the state of `child` when the view tree is built again from
+/// scratch, e.g. after a hot reload, as long as the new tree has a view with the same `key`.
+///
+/// Views are otherwise matched by their position in the tree: when the type of a
+/// [`BoxedMasonryView`](crate::BoxedMasonryView) changes, as it does when the code
+/// building it was reloaded, everything in it is built again, and the text typed in a
+/// textbox or the offset of a scroll area is lost. When such a tree is torn down, the
+/// widget and the view state of this view are kept aside instead, and the view of the
+/// new tree with the same `key` takes them over, if its child has the same type. The new
+/// child view is then applied to them as a rebuild.
+///
+/// `key` should be unique within the app.
+pub fn with_id_stable_across_hot_reload<State, Action, V>(
+    key: impl Into<String>,
+    child: V,
+) -> StableAcrossHotReload<State, Action, V>
+where
+    V: MasonryView<State, Action>,
+{
+    StableAcrossHotReload {
+        key: key.into(),
+        child: Arc::new(child),
+        phantom: PhantomData,
+    }
+}
+
+pub struct StableAcrossHotReload<State, Action, V> {
+    key: String,
+    child: Arc<V>,
+    phantom: PhantomData<fn() -> (State, Action)>,
+}
+
+pub struct StableAcrossHotReloadState<V: 'static, S: 'static> {
+    key: String,
+    stash: StableStash,
+    /// The child and its state, until they are stashed when this is dropped.
+    child: Option<StableChild<V, S>>,
+}
+
+/// The parts of a [`StableAcrossHotReload`] view which are stashed along its widget.
+struct StableChild<V, S> {
+    /// The view the element was last built or rebuilt with.
+    view: Arc<V>,
+    state: S,
+    /// The id path of the child, to route the actions of its widgets again when it is
+    /// taken over by a view elsewhere in the tree.
+    id_path: Vec<ViewId>,
+}
+
+impl<V: 'static, S: 'static> Drop for StableAcrossHotReloadState<V, S> {
+    fn drop(&mut self) {
+        if let Some(child) = self.child.take() {
+            self.stash.stash_view(&self.key, Box::new(child));
+        }
+    }
+}
+
+impl<State, Action, V> MasonryView<State, Action> for StableAcrossHotReload<State, Action, V>
+where
+    State: 'static,
+    Action: 'static,
+    V: MasonryView<State, Action>,
+    V::ViewState: 'static,
+{
+    type Element = StableWidget<V::Element>;
+    type ViewState = StableAcrossHotReloadState<V, V::ViewState>;
+
+    fn build(&self, cx: &mut ViewCx) -> (WidgetPod<Self::Element>, Self::ViewState) {
+        let stash = cx.stable_stash();
+        let stashed = stash.take::<WidgetPod<V::Element>, StableChild<V, V::ViewState>>(&self.key);
+        let (inner, child) = match stashed {
+            Some((inner, mut child)) => {
+                // The new child view is applied once the element is in the tree, see
+                // `AnyMasonryView::dyn_rebuild`.
+                cx.rebase_widget_paths(&child.id_path);
+                child.id_path = cx.id_path().to_vec();
+                (inner, child)
+            }
+            None => {
+                let (inner, state) = self.child.build(cx);
+                let child = StableChild {
+                    view: self.child.clone(),
+                    state,
+                    id_path: cx.id_path().to_vec(),
+                };
+                (inner, child)
+            }
+        };
+        let widget = StableWidget {
+            inner: Some(inner),
+            key: self.key.clone(),
+            stash: stash.clone(),
+        };
+        let state = StableAcrossHotReloadState {
+            key: self.key.clone(),
+            stash,
+            child: Some(child),
+        };
+        (WidgetPod::new(widget), state)
+    }
+
+    fn rebuild(
+        &self,
+        view_state: &mut Self::ViewState,
+        cx: &mut ViewCx,
+        _prev: &Self,
+        mut element: WidgetMut<Self::Element>,
+    ) {
+        let Some(child) = &mut view_state.child else {
+            return;
+        };
+        // The element may have been built by the view of another tree, which isn't `prev`.
+        if !Arc::ptr_eq(&self.child, &child.view) {
+            let inner = StableWidget::inner_mut(&mut element);
+            self.child.rebuild(&mut child.state, cx, &child.view, inner);
+            child.view = self.child.clone();
+        }
+    }
+
+    fn message(
+        &self,
+        view_state: &mut Self::ViewState,
+        id_path: &[ViewId],
+        message: Box<dyn Any>,
+        app_state: &mut State,
+    ) -> MessageResult<Action> {
+        match &mut view_state.child {
+            Some(child) => child
+                .view
+                .message(&mut child.state, id_path, message, app_state),
+            None => MessageResult::Stale(message),
+        }
+    }
+}
+
+/// The widget of a [`StableAcrossHotReload`] view, which stashes its child when dropped.
+pub struct StableWidget<W: Widget> {
+    /// Always set, until this is dropped.
+    inner: Option<WidgetPod<W>>,
+    key: String,
+    stash: StableStash,
+}
+
+impl<W: Widget> StableWidget<W> {
+    fn inner_mut<'a>(this: &'a mut WidgetMut<'_, Self>) -> WidgetMut<'a, W> {
+        let inner = this
+            .widget
+            .inner
+            .as_mut()
+            .expect("StableWidget has a child");
+        this.ctx.get_mut(inner)
+    }
+
+    fn inner(&mut self) -> &mut WidgetPod<W> {
+        self.inner.as_mut().expect("StableWidget has a child")
+    }
+}
+
+impl<W: Widget> Drop for StableWidget<W> {
+    fn drop(&mut self) {
+        if let Some(inner) = self.inner.take() {
+            self.stash.stash_element(&self.key, Box::new(inner));
+        }
+    }
+}
+
+/// Forward all events to the child widget.
+impl<W: Widget> Widget for StableWidget<W> {
+    fn on_pointer_event(&mut self, ctx: &mut EventCtx, event: &PointerEvent) {
+        self.inner().on_pointer_event(ctx, event);
+    }
+    fn on_text_event(&mut self, ctx: &mut EventCtx, event: &TextEvent) {
+        self.inner().on_text_event(ctx, event);
+    }
+    fn on_access_event(&mut self, ctx: &mut EventCtx, event: &AccessEvent) {
+        self.inner().on_access_event(ctx, event);
+    }
+
+    fn on_status_change(&mut self, _: &mut LifeCycleCtx, _: &StatusChange) {
+        // Intentionally do nothing
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle) {
+        self.inner().lifecycle(ctx, event);
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints) -> Size {
+        let inner = self.inner();
+        let size = inner.layout(ctx, bc);
+        ctx.place_child(inner, Point::ORIGIN);
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, scene: &mut Scene) {
+        self.inner().paint(ctx, scene);
+    }
+
+    fn accessibility_role(&self) -> Role {
+        Role::GenericContainer
+    }
+
+    fn accessibility(&mut self, ctx: &mut AccessCtx) {
+        self.inner().accessibility(ctx);
+    }
+
+    fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
+        self.inner.iter().map(WidgetPod::as_dyn).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::ViewHarness;
+    use crate::view::{flex, label, textbox};
+    use crate::BoxedMasonryView;
+
+    #[derive(Default)]
+    struct AppState {
+        reloaded: bool,
+        text: String,
+    }
+
+    #[test]
+    fn stable_key_keeps_widget_across_reload() {
+        // Setting `reloaded` stands for a hot reload: the view returned by the logic has
+        // another type, so the tree is built again.
+        let mut harness = ViewHarness::new(
+            AppState::default(),
+            |state: &mut AppState| -> BoxedMasonryView<AppState> {
+                let search = with_id_stable_across_hot_reload(
+                    "search",
+                    textbox(state.text.clone(), |state: &mut AppState, text| {
+                        state.text = text;
+                    }),
+                );
+                if state.reloaded {
+                    Box::new(flex((label("Search"), search)))
+                } else {
+                    Box::new(flex((search,)))
+                }
+            },
+        );
+        harness.masonry_harness().move_focus(true);
+        harness.masonry_harness().keyboard_type_chars("xil");
+        harness.process_actions();
+        assert_eq!(harness.state().text, "xil");
+        let textbox_id = harness.masonry_harness().focused_widget().unwrap().id();
+
+        harness.state().reloaded = true;
+        harness.rebuild();
+        let focused = harness.masonry_harness().focused_widget().map(|w| w.id());
+        assert_eq!(focused, Some(textbox_id));
+
+        // The actions of the textbox are routed to the view which took it over.
+        harness.masonry_harness().keyboard_type_chars("em");
+        harness.process_actions();
+        assert_eq!(harness.state().text, "xilem");
+    }
+}
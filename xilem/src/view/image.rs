@@ -0,0 +1,75 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+use masonry::{
+    image_cache::ImageSource,
+    widget::{FillStrat, WidgetMut},
+    WidgetPod,
+};
+
+use crate::{MasonryView, MessageResult, ViewCx, ViewId};
+
+/// An image loaded from `source`, e.g. the bytes of a PNG file or a file path.
+///
+/// Images are decoded in the background by the image cache of the window, and views
+/// showing the same source share one decoded copy. A placeholder is shown until the
+/// image is decoded.
+pub fn image(source: impl Into<ImageSource>) -> Image {
+    Image {
+        source: source.into(),
+        fill: FillStrat::default(),
+    }
+}
+
+pub struct Image {
+    source: ImageSource,
+    fill: FillStrat,
+}
+
+impl Image {
+    /// Set how the image is scaled to fit its box.
+    pub fn fill_mode(mut self, fill: FillStrat) -> Self {
+        self.fill = fill;
+        self
+    }
+}
+
+impl<State, Action> MasonryView<State, Action> for Image {
+    type Element = masonry::widget::Image;
+    type ViewState = ();
+
+    fn build(&self, _cx: &mut ViewCx) -> (WidgetPod<Self::Element>, Self::ViewState) {
+        let widget_pod = WidgetPod::new(
+            masonry::widget::Image::from_source(self.source.clone()).fill_mode(self.fill),
+        );
+        (widget_pod, ())
+    }
+
+    fn rebuild(
+        &self,
+        _view_state: &mut Self::ViewState,
+        cx: &mut ViewCx,
+        prev: &Self,
+        mut element: WidgetMut<Self::Element>,
+    ) {
+        if prev.source != self.source {
+            element.set_source(self.source.clone());
+            cx.mark_changed();
+        }
+        if prev.fill != self.fill {
+            element.set_fill_mode(self.fill);
+            cx.mark_changed();
+        }
+    }
+
+    fn message(
+        &self,
+        _view_state: &mut Self::ViewState,
+        _id_path: &[ViewId],
+        message: Box<dyn std::any::Any>,
+        _app_state: &mut State,
+    ) -> crate::MessageResult<Action> {
+        tracing::error!("Message arrived in Image::message, but Image doesn't consume any messages, this is a bug");
+        MessageResult::Stale(message)
+    }
+}
@@ -3,6 +3,7 @@
 
 use masonry::{widget::WidgetMut, ArcStr, WidgetPod};
 
+use crate::view::{disabled, Disabled};
 use crate::{Color, MasonryView, MessageResult, TextAlignment, ViewCx, ViewId};
 
 pub fn label(label: impl Into<ArcStr>) -> Label {
@@ -10,7 +11,6 @@ pub fn label(label: impl Into<ArcStr>) -> Label {
         label: label.into(),
         text_color: Color::WHITE,
         alignment: TextAlignment::default(),
-        disabled: false,
     }
 }
 
@@ -18,7 +18,6 @@ pub struct Label {
     label: ArcStr,
     text_color: Color,
     alignment: TextAlignment,
-    disabled: bool,
     // TODO: add more attributes of `masonry::widget::Label`
 }
 
@@ -33,9 +32,9 @@ impl Label {
         self
     }
 
-    pub fn disabled(mut self) -> Self {
-        self.disabled = true;
-        self
+    /// Disable the label, the same as wrapping it in [`disabled(view, true)`](disabled()).
+    pub fn disabled(self) -> Disabled<Self> {
+        disabled(self, true)
     }
 }
 
@@ -63,10 +62,6 @@ impl<State, Action> MasonryView<State, Action> for Label {
             element.set_text(self.label.clone());
             cx.mark_changed();
         }
-        // if prev.disabled != self.disabled {
-        //     element.set_disabled(self.disabled);
-        //     cx.mark_changed();
-        // }
         if prev.text_color != self.text_color {
             element.set_text_brush(self.text_color);
             cx.mark_changed();
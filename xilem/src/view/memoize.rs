@@ -109,3 +109,42 @@ where
 {
     Memoize::new(data, view)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, OnceLock};
+
+    use super::*;
+    use crate::testing::{count_rebuilds, ViewHarness};
+    use crate::view::label;
+
+    #[derive(Default)]
+    struct AppState {
+        count: u32,
+        text: String,
+    }
+
+    #[test]
+    fn memoize_skips_rebuild_on_equal_data() {
+        // `memoize` doesn't allow its callback to capture anything
+        fn rebuilds() -> Arc<AtomicUsize> {
+            static REBUILDS: OnceLock<Arc<AtomicUsize>> = OnceLock::new();
+            REBUILDS.get_or_init(Default::default).clone()
+        }
+
+        let mut harness = ViewHarness::new(AppState::default(), |state: &mut AppState| {
+            memoize(state.count, |count: &u32| {
+                count_rebuilds(rebuilds(), label(count.to_string()))
+            })
+        });
+
+        harness.state().text = "not part of the memoized data".into();
+        harness.rebuild();
+        assert_eq!(rebuilds().load(Ordering::Relaxed), 0);
+
+        harness.state().count += 1;
+        harness.rebuild();
+        assert_eq!(rebuilds().load(Ordering::Relaxed), 1);
+    }
+}
@@ -3,23 +3,68 @@
 
 mod arc;
 
+mod badge;
+pub use badge::*;
+
+mod blocking_overlay;
+pub use blocking_overlay::*;
+
 mod button;
 pub use button::*;
 
 mod checkbox;
 pub use checkbox::*;
 
+mod context_menu;
+pub use context_menu::*;
+
+mod disabled;
+pub use disabled::*;
+
 mod flex;
 pub use flex::*;
 
+mod focus_order;
+pub use focus_order::*;
+
+mod hot_reload;
+pub use hot_reload::*;
+
+mod image;
+pub use image::*;
+
 mod label;
 pub use label::*;
 
 mod memoize;
 pub use memoize::*;
 
+mod on_build;
+pub use on_build::*;
+
 mod prose;
 pub use prose::*;
 
+mod responsive;
+pub use responsive::*;
+
+mod scroll;
+pub use scroll::*;
+
+mod subscription;
+pub use subscription::*;
+
+mod switch;
+pub use switch::*;
+
+mod table;
+pub use table::*;
+
 mod textbox;
 pub use textbox::*;
+
+mod visibility;
+pub use visibility::*;
+
+mod window_drag_region;
+pub use window_drag_region::*;
@@ -0,0 +1,233 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{any::Any, marker::PhantomData, sync::Arc};
+
+use masonry::{widget::WidgetMut, WidgetPod};
+
+use crate::{MasonryView, MessageResult, ViewCx, ViewId, WidgetHandle};
+
+type BuildCallback<State, Action> = Box<dyn Fn(&mut State, WidgetHandle) -> Action + Send + Sync>;
+
+/// Call `callback` with a [`WidgetHandle`] to the widget of `child` once it's built,
+/// to mutate it imperatively later on.
+///
+/// The callback runs after the build or rebuild which built the widget, and again if
+/// `child` builds a new widget.
+pub fn on_build<State, Action, V, F>(child: V, callback: F) -> OnBuild<State, Action, V>
+where
+    V: MasonryView<State, Action>,
+    F: Fn(&mut State, WidgetHandle) -> Action + Send + Sync + 'static,
+{
+    OnBuild {
+        child,
+        callback: Box::new(callback),
+        phantom: PhantomData,
+    }
+}
+
+pub struct OnBuild<State, Action, V> {
+    child: V,
+    callback: BuildCallback<State, Action>,
+    phantom: PhantomData<fn() -> (State, Action)>,
+}
+
+pub struct OnBuildState<S> {
+    child_state: S,
+    /// Dropped with the view, to discard the edits of its handles.
+    alive: Arc<()>,
+}
+
+impl<State, Action, V> MasonryView<State, Action> for OnBuild<State, Action, V>
+where
+    State: 'static,
+    Action: 'static,
+    V: MasonryView<State, Action>,
+{
+    type Element = V::Element;
+    type ViewState = OnBuildState<V::ViewState>;
+
+    fn build(&self, cx: &mut ViewCx) -> (WidgetPod<Self::Element>, Self::ViewState) {
+        let (element, child_state) =
+            cx.with_id(ViewId::for_type::<V>(0), |cx| self.child.build(cx));
+        let alive = Arc::new(());
+        cx.register_handle(element.id(), &alive);
+        let view_state = OnBuildState { child_state, alive };
+        (element, view_state)
+    }
+
+    fn rebuild(
+        &self,
+        view_state: &mut Self::ViewState,
+        cx: &mut ViewCx,
+        prev: &Self,
+        mut element: WidgetMut<Self::Element>,
+    ) {
+        let prev_id = element.ctx.widget_id();
+        cx.with_id(ViewId::for_type::<V>(0), |cx| {
+            self.child.rebuild(
+                &mut view_state.child_state,
+                cx,
+                &prev.child,
+                element.reborrow_mut(),
+            );
+        });
+        let id = element.ctx.widget_id();
+        if id != prev_id {
+            // Edits queued through the handles of the old widget don't apply to the new one.
+            view_state.alive = Arc::new(());
+            cx.register_handle(id, &view_state.alive);
+        }
+    }
+
+    fn message(
+        &self,
+        view_state: &mut Self::ViewState,
+        id_path: &[ViewId],
+        message: Box<dyn Any>,
+        app_state: &mut State,
+    ) -> MessageResult<Action> {
+        if let Some((_, rest)) = id_path.split_first() {
+            return self
+                .child
+                .message(&mut view_state.child_state, rest, message, app_state);
+        }
+        match message.downcast::<WidgetHandle>() {
+            Ok(handle) => MessageResult::Action((self.callback)(app_state, *handle)),
+            Err(message) => {
+                tracing::error!("Wrong message type in OnBuild::message");
+                MessageResult::Stale(message)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use masonry::widget::SizedBox;
+
+    use super::*;
+    use crate::testing::ViewHarness;
+    use crate::view::{button, flex};
+    use crate::Color;
+
+    #[derive(Default)]
+    struct AppState {
+        visible: bool,
+        handle: Option<WidgetHandle>,
+    }
+
+    /// A 40x40 black box.
+    struct Swatch;
+
+    impl<State, Action> MasonryView<State, Action> for Swatch {
+        type Element = SizedBox;
+        type ViewState = ();
+
+        fn build(&self, _cx: &mut ViewCx) -> (WidgetPod<Self::Element>, Self::ViewState) {
+            let swatch = SizedBox::empty()
+                .width(40.0)
+                .height(40.0)
+                .background(Color::BLACK);
+            (WidgetPod::new(swatch), ())
+        }
+
+        fn rebuild(
+            &self,
+            _view_state: &mut Self::ViewState,
+            _cx: &mut ViewCx,
+            _prev: &Self,
+            _element: WidgetMut<Self::Element>,
+        ) {
+        }
+
+        fn message(
+            &self,
+            _view_state: &mut Self::ViewState,
+            _id_path: &[ViewId],
+            message: Box<dyn Any>,
+            _app_state: &mut State,
+        ) -> MessageResult<Action> {
+            MessageResult::Stale(message)
+        }
+    }
+
+    fn store_handle(state: &mut AppState, handle: WidgetHandle) {
+        state.handle = Some(handle);
+    }
+
+    #[test]
+    fn widget_handle_edits_widget() {
+        let mut harness = ViewHarness::new(AppState::default(), |_: &mut AppState| {
+            flex((
+                on_build(Swatch, store_handle),
+                button("Widen it", |state: &mut AppState| {
+                    let handle = state.handle.as_ref().unwrap();
+                    handle.edit(|swatch: &mut WidgetMut<'_, SizedBox>| {
+                        swatch.set_width(80.0);
+                    });
+                }),
+            ))
+        });
+        let swatch_id = harness.state().handle.as_ref().unwrap().id();
+        let masonry = harness.masonry_harness();
+        let button_id = masonry.root_widget().children()[0].children()[1].id();
+
+        masonry.mouse_click_on(button_id);
+        harness.process_actions();
+
+        let masonry = harness.masonry_harness();
+        let rect = masonry.get_widget(swatch_id).state().window_layout_rect();
+        assert_eq!(rect.width(), 80.0);
+    }
+
+    #[test]
+    fn widget_handle_edit_outside_callback_runs_on_wake() {
+        let mut harness = ViewHarness::new(AppState::default(), |_: &mut AppState| {
+            flex(on_build(Swatch, store_handle))
+        });
+        let handle = harness.state().handle.clone().unwrap();
+        std::thread::spawn(move || {
+            handle.edit(|swatch: &mut WidgetMut<'_, SizedBox>| {
+                swatch.set_width(60.0);
+            });
+        })
+        .join()
+        .unwrap();
+        harness.process_messages();
+
+        let swatch_id = harness.state().handle.as_ref().unwrap().id();
+        let masonry = harness.masonry_harness();
+        let rect = masonry.get_widget(swatch_id).state().window_layout_rect();
+        assert_eq!(rect.width(), 60.0);
+    }
+
+    #[test]
+    fn widget_handle_of_removed_view_does_nothing() {
+        let state = AppState {
+            visible: true,
+            ..Default::default()
+        };
+        let mut harness = ViewHarness::new(state, |state: &mut AppState| {
+            flex(state.visible.then(|| on_build(Swatch, store_handle)))
+        });
+        let handle = harness.state().handle.take().unwrap();
+        harness.state().visible = false;
+        harness.rebuild();
+
+        let edited = Arc::new(AtomicBool::new(false));
+        let edited_clone = edited.clone();
+        handle.edit(move |_: &mut WidgetMut<'_, SizedBox>| {
+            edited_clone.store(true, Ordering::Relaxed);
+        });
+        harness.state().visible = true;
+        harness.rebuild();
+        assert!(!edited.load(Ordering::Relaxed));
+
+        // The new view hands out a handle to its new widget.
+        let new_handle = harness.state().handle.as_ref().unwrap();
+        assert_ne!(new_handle.id(), handle.id());
+    }
+}
@@ -3,6 +3,7 @@
 
 use masonry::{text2::TextBrush, widget::WidgetMut, ArcStr, WidgetPod};
 
+use crate::view::{disabled, Disabled};
 use crate::{Color, MasonryView, MessageResult, TextAlignment, ViewCx, ViewId};
 
 pub fn prose(label: impl Into<ArcStr>) -> Prose {
@@ -10,7 +11,6 @@ pub fn prose(label: impl Into<ArcStr>) -> Prose {
         label: label.into(),
         text_brush: Color::WHITE.into(),
         alignment: TextAlignment::default(),
-        disabled: false,
     }
 }
 
@@ -18,7 +18,6 @@ pub struct Prose {
     label: ArcStr,
     text_brush: TextBrush,
     alignment: TextAlignment,
-    disabled: bool,
     // TODO: add more attributes of `masonry::widget::Label`
 }
 
@@ -34,9 +33,9 @@ impl Prose {
         self
     }
 
-    pub fn disabled(mut self) -> Self {
-        self.disabled = true;
-        self
+    /// Disable the text, the same as wrapping it in [`disabled(view, true)`](disabled()).
+    pub fn disabled(self) -> Disabled<Self> {
+        disabled(self, true)
     }
 }
 
@@ -64,10 +63,6 @@ impl<State, Action> MasonryView<State, Action> for Prose {
             element.set_text(self.label.clone());
             cx.mark_changed();
         }
-        // if prev.disabled != self.disabled {
-        //     element.set_disabled(self.disabled);
-        //     cx.mark_changed();
-        // }
         if prev.text_brush != self.text_brush {
             element.set_text_brush(self.text_brush.clone());
             cx.mark_changed();
@@ -0,0 +1,180 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{any::Any, marker::PhantomData};
+
+use masonry::{widget::WidgetMut, Size, WidgetPod};
+
+use crate::{MasonryView, MessageResult, ViewCx, ViewId};
+
+/// A view which depends on the size available to it, e.g. to collapse a sidebar in
+/// narrow windows. Backed by [`masonry::widget::Breakpoints`].
+///
+/// `view` is called with the available size as of the last time its width crossed one
+/// of the `breakpoints`, and with [`Size::ZERO`] before the first layout. Resizing
+/// within the same breakpoints doesn't rebuild the view, so the size should only be
+/// used to choose between layouts.
+///
+/// If `view` returns views of different types depending on the size, they need to be
+/// boxed, e.g. as [`BoxedMasonryView`](crate::BoxedMasonryView).
+pub fn responsive<State, Action, V, F>(
+    breakpoints: impl Into<Vec<f64>>,
+    view: F,
+) -> Responsive<State, Action, F>
+where
+    V: MasonryView<State, Action>,
+    F: Fn(Size) -> V + Send + Sync + 'static,
+{
+    Responsive {
+        breakpoints: breakpoints.into(),
+        view_fn: view,
+        phantom: PhantomData,
+    }
+}
+
+pub struct Responsive<State, Action, F> {
+    breakpoints: Vec<f64>,
+    view_fn: F,
+    phantom: PhantomData<fn() -> (State, Action)>,
+}
+
+pub struct ResponsiveState<State, Action, V: MasonryView<State, Action>> {
+    /// The available size, as of the last time a breakpoint was crossed.
+    size: Size,
+    view: V,
+    view_state: V::ViewState,
+}
+
+impl<State, Action, V, F> MasonryView<State, Action> for Responsive<State, Action, F>
+where
+    State: 'static,
+    Action: 'static,
+    V: MasonryView<State, Action>,
+    F: Fn(Size) -> V + Send + Sync + 'static,
+{
+    type Element = masonry::widget::Breakpoints<V::Element>;
+    type ViewState = ResponsiveState<State, Action, V>;
+
+    fn build(&self, cx: &mut ViewCx) -> (WidgetPod<Self::Element>, Self::ViewState) {
+        let size = Size::ZERO;
+        let view = (self.view_fn)(size);
+        let (child, view_state) = cx.with_id(ViewId::for_type::<V>(0), |cx| view.build(cx));
+        let breakpoints = masonry::widget::Breakpoints::new_pod(child, self.breakpoints.clone());
+        let element = cx.with_action_widget(|_| WidgetPod::new(breakpoints));
+        let state = ResponsiveState {
+            size,
+            view,
+            view_state,
+        };
+        (element, state)
+    }
+
+    fn rebuild(
+        &self,
+        view_state: &mut Self::ViewState,
+        cx: &mut ViewCx,
+        prev: &Self,
+        mut element: WidgetMut<Self::Element>,
+    ) {
+        let view = (self.view_fn)(view_state.size);
+        cx.with_id(ViewId::for_type::<V>(0), |cx| {
+            view.rebuild(
+                &mut view_state.view_state,
+                cx,
+                &view_state.view,
+                element.child_mut(),
+            );
+        });
+        view_state.view = view;
+        if prev.breakpoints != self.breakpoints {
+            element.set_breakpoints(self.breakpoints.clone());
+        }
+    }
+
+    fn message(
+        &self,
+        view_state: &mut Self::ViewState,
+        id_path: &[ViewId],
+        message: Box<dyn Any>,
+        app_state: &mut State,
+    ) -> MessageResult<Action> {
+        if let Some((_, rest)) = id_path.split_first() {
+            return view_state
+                .view
+                .message(&mut view_state.view_state, rest, message, app_state);
+        }
+        match message.downcast::<masonry::Action>() {
+            Ok(action) => {
+                if let masonry::Action::BreakpointChanged(size) = *action {
+                    view_state.size = size;
+                    MessageResult::RequestRebuild
+                } else {
+                    tracing::error!("Wrong action type in Responsive::message: {action:?}");
+                    MessageResult::Stale(action)
+                }
+            }
+            Err(message) => {
+                tracing::error!("Wrong message type in Responsive::message");
+                MessageResult::Stale(message)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::testing::{count_rebuilds, ViewHarness};
+    use crate::view::label;
+
+    #[test]
+    fn responsive_switches_layout_at_breakpoint() {
+        let rebuilds = Arc::new(AtomicUsize::new(0));
+        let rebuilds_clone = rebuilds.clone();
+        let mut harness = ViewHarness::new((), move |_: &mut ()| {
+            let rebuilds = rebuilds_clone.clone();
+            responsive([500.0], move |size: Size| {
+                let text = if size.width >= 500.0 {
+                    "Sidebar and content"
+                } else {
+                    "Content"
+                };
+                count_rebuilds(rebuilds.clone(), label(text))
+            })
+        });
+        let text = |harness: &mut ViewHarness<_, _, _>| {
+            let masonry = harness.masonry_harness();
+            let breakpoints_id = masonry.root_widget().children()[0].id();
+            let label_id = masonry.get_widget(breakpoints_id).children()[0].id();
+            let label = masonry.get_widget(label_id);
+            let label = label.downcast::<masonry::widget::Label>().unwrap();
+            label.text().to_string()
+        };
+        harness.process_actions();
+        assert_eq!(text(&mut harness), "Content");
+        let initial_rebuilds = rebuilds.load(Ordering::Relaxed);
+
+        harness
+            .masonry_harness()
+            .resize_window(Size::new(600.0, 400.0));
+        harness.process_actions();
+        assert_eq!(text(&mut harness), "Sidebar and content");
+        assert_eq!(rebuilds.load(Ordering::Relaxed), initial_rebuilds + 1);
+
+        // Resizing without crossing a breakpoint doesn't rebuild the view.
+        harness
+            .masonry_harness()
+            .resize_window(Size::new(700.0, 400.0));
+        harness.process_actions();
+        assert_eq!(rebuilds.load(Ordering::Relaxed), initial_rebuilds + 1);
+
+        harness
+            .masonry_harness()
+            .resize_window(Size::new(300.0, 400.0));
+        harness.process_actions();
+        assert_eq!(text(&mut harness), "Content");
+    }
+}
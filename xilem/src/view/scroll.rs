@@ -0,0 +1,230 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{any::Any, marker::PhantomData};
+
+use masonry::{widget::WidgetMut, Point, Rect, WidgetPod};
+
+use crate::{MasonryView, MessageResult, ViewCx, ViewId};
+
+type ScrollCallback<State, Action> = Box<dyn Fn(&mut State, Point) -> Action + Send + Sync>;
+
+/// A scrollable area around `child`, backed by [`masonry::widget::Portal`].
+///
+/// The content scrolls vertically by default.
+pub fn scroll<State, Action, V>(child: V) -> Scroll<State, Action, V>
+where
+    V: MasonryView<State, Action>,
+{
+    Scroll {
+        child,
+        constrain_horizontal: true,
+        constrain_vertical: false,
+        on_scroll: None,
+        scroll_offset: None,
+        scroll_to: None,
+        phantom: PhantomData,
+    }
+}
+
+/// A view scrolling its child in a [`Portal`](masonry::widget::Portal).
+///
+/// This is created with [`scroll`].
+pub struct Scroll<State, Action, V> {
+    child: V,
+    constrain_horizontal: bool,
+    constrain_vertical: bool,
+    on_scroll: Option<ScrollCallback<State, Action>>,
+    scroll_offset: Option<Point>,
+    scroll_to: Option<(u64, Rect)>,
+    phantom: PhantomData<fn() -> (State, Action)>,
+}
+
+impl<State, Action, V> Scroll<State, Action, V> {
+    /// Only scroll vertically; the child is constrained to the width of the viewport.
+    pub fn vertical(mut self) -> Self {
+        self.constrain_horizontal = true;
+        self.constrain_vertical = false;
+        self
+    }
+
+    /// Only scroll horizontally; the child is constrained to the height of the viewport.
+    pub fn horizontal(mut self) -> Self {
+        self.constrain_horizontal = false;
+        self.constrain_vertical = true;
+        self
+    }
+
+    /// Scroll in both directions.
+    pub fn both(mut self) -> Self {
+        self.constrain_horizontal = false;
+        self.constrain_vertical = false;
+        self
+    }
+
+    /// Called with the new offset whenever the user scrolls the content.
+    ///
+    /// Offsets set through [`scroll_offset`](Self::scroll_offset) or
+    /// [`scroll_to`](Self::scroll_to) don't trigger this callback.
+    pub fn on_scroll<F>(mut self, on_scroll: F) -> Self
+    where
+        F: Fn(&mut State, Point) -> Action + Send + Sync + 'static,
+    {
+        self.on_scroll = Some(Box::new(on_scroll));
+        self
+    }
+
+    /// Control the scroll offset from the app state.
+    ///
+    /// The offset is only pushed to the widget when it differs from the one
+    /// given in the previous view, so that it doesn't override scrolling done
+    /// by the user in the meantime.
+    pub fn scroll_offset(mut self, offset: Point) -> Self {
+        self.scroll_offset = Some(offset);
+        self
+    }
+
+    /// Scroll the minimum amount required to make `target` visible.
+    ///
+    /// `target` is in the child's coordinate space. This is a command: it is
+    /// run once on the rebuild where `key` differs from the previous view's key,
+    /// so bump `key` to issue the scroll again.
+    pub fn scroll_to(mut self, key: u64, target: Rect) -> Self {
+        self.scroll_to = Some((key, target));
+        self
+    }
+}
+
+impl<State, Action, V> MasonryView<State, Action> for Scroll<State, Action, V>
+where
+    State: 'static,
+    Action: 'static,
+    V: MasonryView<State, Action>,
+{
+    type Element = masonry::widget::Portal<V::Element>;
+    type ViewState = V::ViewState;
+
+    fn build(&self, cx: &mut ViewCx) -> (WidgetPod<Self::Element>, Self::ViewState) {
+        let (child, child_state) = cx.with_id(ViewId::for_type::<V>(0), |cx| self.child.build(cx));
+        let mut portal = masonry::widget::Portal::new_pod(child)
+            .constrain_horizontal(self.constrain_horizontal)
+            .constrain_vertical(self.constrain_vertical);
+        if let Some(offset) = self.scroll_offset {
+            portal = portal.viewport_pos(offset);
+        }
+        let element = cx.with_action_widget(|_| WidgetPod::new(portal));
+        (element, child_state)
+    }
+
+    fn rebuild(
+        &self,
+        view_state: &mut Self::ViewState,
+        cx: &mut ViewCx,
+        prev: &Self,
+        mut element: WidgetMut<Self::Element>,
+    ) {
+        if prev.constrain_horizontal != self.constrain_horizontal {
+            element.set_constrain_horizontal(self.constrain_horizontal);
+            cx.mark_changed();
+        }
+        if prev.constrain_vertical != self.constrain_vertical {
+            element.set_constrain_vertical(self.constrain_vertical);
+            cx.mark_changed();
+        }
+        cx.with_id(ViewId::for_type::<V>(0), |cx| {
+            self.child
+                .rebuild(view_state, cx, &prev.child, element.child_mut());
+        });
+        if prev.scroll_offset != self.scroll_offset {
+            if let Some(offset) = self.scroll_offset {
+                element.set_viewport_pos(offset);
+                cx.mark_changed();
+            }
+        }
+        if prev.scroll_to.map(|(key, _)| key) != self.scroll_to.map(|(key, _)| key) {
+            if let Some((_, target)) = self.scroll_to {
+                element.pan_viewport_to(target);
+                cx.mark_changed();
+            }
+        }
+    }
+
+    fn message(
+        &self,
+        view_state: &mut Self::ViewState,
+        id_path: &[ViewId],
+        message: Box<dyn Any>,
+        app_state: &mut State,
+    ) -> MessageResult<Action> {
+        if let Some((_, rest)) = id_path.split_first() {
+            return self.child.message(view_state, rest, message, app_state);
+        }
+        match message.downcast::<masonry::Action>() {
+            Ok(action) => {
+                if let masonry::Action::ViewportMoved(offset) = *action {
+                    match &self.on_scroll {
+                        Some(on_scroll) => MessageResult::Action(on_scroll(app_state, offset)),
+                        None => MessageResult::Nop,
+                    }
+                } else {
+                    tracing::error!("Wrong action type in Scroll::message: {action:?}");
+                    MessageResult::Stale(action)
+                }
+            }
+            Err(message) => {
+                tracing::error!("Wrong message type in Scroll::message");
+                MessageResult::Stale(message)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use masonry::widget::Portal;
+    use masonry::Vec2;
+
+    use super::*;
+    use crate::testing::ViewHarness;
+    use crate::view::{flex, label};
+
+    #[derive(Default)]
+    struct AppState {
+        scroll_y: f64,
+        count: u32,
+    }
+
+    #[test]
+    fn scroll_offset_is_only_applied_when_changed() {
+        let state = AppState {
+            scroll_y: 100.0,
+            ..Default::default()
+        };
+        let mut harness = ViewHarness::new(state, |state: &mut AppState| {
+            let lines: Vec<_> = (0..100).map(|i| label(format!("Line {i}"))).collect();
+            scroll(flex(lines)).scroll_offset(Point::new(0.0, state.scroll_y))
+        });
+        let portal_id = harness.masonry_harness().root_widget().children()[0].id();
+        let viewport_y = |harness: &mut ViewHarness<_, _, _>| {
+            let portal = harness.masonry_harness().get_widget(portal_id);
+            let portal = portal.downcast::<Portal<masonry::widget::Flex>>().unwrap();
+            portal.get_viewport_pos().y
+        };
+        assert_eq!(viewport_y(&mut harness), 100.0);
+
+        let masonry = harness.masonry_harness();
+        masonry.mouse_move_to(portal_id);
+        masonry.mouse_wheel(Vec2::new(0.0, 50.0));
+        harness.process_actions();
+        assert_eq!(viewport_y(&mut harness), 150.0);
+
+        // Rebuilding with the same offset doesn't undo the scrolling done by the user.
+        harness.state().count += 1;
+        harness.rebuild();
+        assert_eq!(viewport_y(&mut harness), 150.0);
+
+        harness.state().scroll_y = 20.0;
+        harness.rebuild();
+        assert_eq!(viewport_y(&mut harness), 20.0);
+    }
+}
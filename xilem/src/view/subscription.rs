@@ -0,0 +1,311 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{
+    any::Any,
+    marker::PhantomData,
+    panic::{catch_unwind, AssertUnwindSafe},
+    sync::{Arc, Mutex},
+};
+
+use masonry::{
+    widget::{SizedBox, WidgetMut},
+    WidgetPod,
+};
+
+use crate::{MasonryView, MessageProxy, MessageResult, ViewCx, ViewId};
+
+type PanicCallback<State, Action> = Box<dyn Fn(&mut State, String) -> Action + Send + Sync>;
+
+/// Subscribe to a callback-based source of events, like a file watcher or a global hotkey
+/// library, while this view is in the view tree.
+///
+/// When the view is built, `setup` runs on a new thread, so it may block. It registers
+/// callbacks which send messages through the [`MessageProxy`], and returns a guard which
+/// keeps the subscription alive. Each message is passed to `on_message` with the app state,
+/// on the UI thread and in the order they were sent; what it returns is the action of this
+/// view.
+///
+/// The guard is dropped when the view is removed, which is how the subscription gets
+/// cancelled. Rebuilding the view doesn't subscribe again. If `setup` panics, the panic is
+/// logged and passed to the [`on_panic`](Subscription::on_panic) callback.
+///
+/// This view has no visual representation.
+pub fn subscription<State, Action, M, S, F, H>(
+    setup: F,
+    on_message: H,
+) -> Subscription<State, Action, M, F, H>
+where
+    M: Send + 'static,
+    S: Send + 'static,
+    F: Fn(MessageProxy<M>) -> S + Send + Sync + 'static,
+    H: Fn(&mut State, M) -> Action + Send + Sync + 'static,
+{
+    Subscription {
+        setup: Arc::new(setup),
+        on_message,
+        on_panic: None,
+        phantom: PhantomData,
+    }
+}
+
+pub struct Subscription<State, Action, M, F, H> {
+    setup: Arc<F>,
+    on_message: H,
+    on_panic: Option<PanicCallback<State, Action>>,
+    phantom: PhantomData<fn(M)>,
+}
+
+/// The message sent to a [`Subscription`] view when its `setup` panicked.
+struct SetupPanicked(String);
+
+impl<State, Action, M, F, H> Subscription<State, Action, M, F, H> {
+    /// Set the callback getting the panic message if `setup` panics.
+    pub fn on_panic(
+        mut self,
+        on_panic: impl Fn(&mut State, String) -> Action + Send + Sync + 'static,
+    ) -> Self {
+        self.on_panic = Some(Box::new(on_panic));
+        self
+    }
+}
+
+/// The guard returned by `setup`, once it has returned.
+enum Guard {
+    Pending,
+    // Only kept to be dropped with the state.
+    #[allow(dead_code)]
+    Running(Box<dyn Any + Send>),
+    Dropped,
+}
+
+pub struct SubscriptionState {
+    guard: Arc<Mutex<Guard>>,
+    // Only kept to be dropped with the state, which disconnects the proxies.
+    #[allow(dead_code)]
+    alive: Arc<()>,
+}
+
+impl Drop for SubscriptionState {
+    fn drop(&mut self) {
+        // If `setup` is still running, its thread drops the guard once it returns.
+        let guard = std::mem::replace(&mut *self.guard.lock().unwrap(), Guard::Dropped);
+        // Dropped outside of the lock, since dropping the guard may block.
+        drop(guard);
+    }
+}
+
+impl<State, Action, M, S, F, H> MasonryView<State, Action> for Subscription<State, Action, M, F, H>
+where
+    State: 'static,
+    Action: 'static,
+    M: Send + 'static,
+    S: Send + 'static,
+    F: Fn(MessageProxy<M>) -> S + Send + Sync + 'static,
+    H: Fn(&mut State, M) -> Action + Send + Sync + 'static,
+{
+    type Element = SizedBox;
+    type ViewState = SubscriptionState;
+
+    fn build(&self, cx: &mut ViewCx) -> (WidgetPod<Self::Element>, Self::ViewState) {
+        let alive = Arc::new(());
+        let proxy = cx.message_proxy::<M>(&alive);
+        let panic_proxy = cx.message_proxy::<SetupPanicked>(&alive);
+        let guard = Arc::new(Mutex::new(Guard::Pending));
+        let thread_guard = guard.clone();
+        let setup = self.setup.clone();
+        let spawned = std::thread::Builder::new()
+            .name("xilem-subscription".into())
+            .spawn(
+                move || match catch_unwind(AssertUnwindSafe(|| setup(proxy))) {
+                    Ok(new_guard) => {
+                        let mut guard = thread_guard.lock().unwrap();
+                        if let Guard::Pending = *guard {
+                            *guard = Guard::Running(Box::new(new_guard));
+                        }
+                    }
+                    Err(payload) => {
+                        let message = panic_message(&*payload);
+                        tracing::error!("Subscription setup panicked: {message}");
+                        let _ = panic_proxy.send(SetupPanicked(message));
+                    }
+                },
+            );
+        if let Err(err) = spawned {
+            tracing::error!("Couldn't spawn the thread of a subscription: {err}");
+        }
+        let view_state = SubscriptionState { guard, alive };
+        (WidgetPod::new(SizedBox::empty()), view_state)
+    }
+
+    fn rebuild(
+        &self,
+        _view_state: &mut Self::ViewState,
+        _cx: &mut ViewCx,
+        _prev: &Self,
+        _element: WidgetMut<Self::Element>,
+    ) {
+    }
+
+    fn message(
+        &self,
+        _view_state: &mut Self::ViewState,
+        id_path: &[ViewId],
+        message: Box<dyn Any>,
+        app_state: &mut State,
+    ) -> MessageResult<Action> {
+        debug_assert!(
+            id_path.is_empty(),
+            "id path should be empty in Subscription::message"
+        );
+        let message = match message.downcast::<M>() {
+            Ok(message) => return MessageResult::Action((self.on_message)(app_state, *message)),
+            Err(message) => message,
+        };
+        match (message.downcast::<SetupPanicked>(), &self.on_panic) {
+            (Ok(panicked), Some(on_panic)) => {
+                MessageResult::Action(on_panic(app_state, panicked.0))
+            }
+            (Ok(_), None) => MessageResult::Nop,
+            (Err(message), _) => {
+                tracing::error!("Wrong message type in Subscription::message");
+                MessageResult::Stale(message)
+            }
+        }
+    }
+}
+
+/// The message of a panic, if it has the type `panic!` gives it.
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        (*message).to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "Box<dyn Any>".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::time::{Duration, Instant};
+
+    use super::*;
+    use crate::testing::ViewHarness;
+    use crate::view::flex;
+
+    #[derive(Default)]
+    struct AppState {
+        subscribed: bool,
+        received: Vec<u32>,
+        panic_message: String,
+    }
+
+    /// Sets its flag when dropped, like a subscription being cancelled.
+    struct DropFlag(Arc<AtomicBool>);
+
+    impl Drop for DropFlag {
+        fn drop(&mut self) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    /// Wait for the thread of a subscription, failing the test if it takes too long.
+    fn wait_until(mut condition: impl FnMut() -> bool) {
+        let start = Instant::now();
+        while !condition() {
+            assert!(
+                start.elapsed() < Duration::from_secs(5),
+                "timed out waiting for the subscription"
+            );
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn subscription_delivers_messages_in_order_and_drops_guard() {
+        let dropped = Arc::new(AtomicBool::new(false));
+        let dropped_clone = dropped.clone();
+        let state = AppState {
+            subscribed: true,
+            ..Default::default()
+        };
+        let mut harness = ViewHarness::new(state, move |state: &mut AppState| {
+            let dropped = dropped_clone.clone();
+            flex(state.subscribed.then(|| {
+                subscription(
+                    move |proxy: MessageProxy<u32>| {
+                        for item in 1..=3 {
+                            proxy.send(item).unwrap();
+                        }
+                        DropFlag(dropped.clone())
+                    },
+                    |state: &mut AppState, item| state.received.push(item),
+                )
+            }))
+        });
+
+        wait_until(|| {
+            harness.process_messages();
+            harness.state().received.len() == 3
+        });
+        assert_eq!(harness.state().received, [1, 2, 3]);
+        assert!(!dropped.load(Ordering::SeqCst));
+
+        // Rebuilding doesn't subscribe again.
+        harness.rebuild();
+        harness.process_messages();
+        assert_eq!(harness.state().received, [1, 2, 3]);
+
+        harness.state().subscribed = false;
+        harness.rebuild();
+        wait_until(|| dropped.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn subscription_messages_stop_after_teardown() {
+        let (proxy_sender, proxy_receiver) = std::sync::mpsc::channel();
+        let proxy_sender = Mutex::new(proxy_sender);
+        let state = AppState {
+            subscribed: true,
+            ..Default::default()
+        };
+        let mut harness = ViewHarness::new(state, move |state: &mut AppState| {
+            let proxy_sender = proxy_sender.lock().unwrap().clone();
+            flex(state.subscribed.then(|| {
+                subscription(
+                    move |proxy: MessageProxy<u32>| proxy_sender.send(proxy).unwrap(),
+                    |state: &mut AppState, item| state.received.push(item),
+                )
+            }))
+        });
+        let proxy = proxy_receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+
+        proxy.send(1).unwrap();
+        harness.state().subscribed = false;
+        harness.rebuild();
+        assert_eq!(proxy.send(2).unwrap_err().0, 2);
+
+        // Messages queued before the view was removed are stale.
+        harness.process_messages();
+        assert!(harness.state().received.is_empty());
+    }
+
+    #[test]
+    fn subscription_setup_panic_is_reported() {
+        let mut harness = ViewHarness::new(AppState::default(), |_: &mut AppState| {
+            subscription(
+                |_: MessageProxy<u32>| -> DropFlag { panic!("no such file") },
+                |state: &mut AppState, item| state.received.push(item),
+            )
+            .on_panic(|state: &mut AppState, message| state.panic_message = message)
+        });
+
+        wait_until(|| {
+            harness.process_messages();
+            !harness.state().panic_message.is_empty()
+        });
+        assert_eq!(harness.state().panic_message, "no such file");
+    }
+}
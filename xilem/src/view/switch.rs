@@ -0,0 +1,113 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+use masonry::{widget::WidgetMut, WidgetPod};
+
+use crate::{MasonryView, MessageResult, ViewCx, ViewId};
+
+/// A toggle switch, calling `callback` with the new value when the user toggles it.
+pub fn switch<F, State, Action>(on: bool, callback: F) -> Switch<F>
+where
+    F: Fn(&mut State, bool) -> Action + Send + 'static,
+{
+    Switch { on, callback }
+}
+
+pub struct Switch<F> {
+    on: bool,
+    callback: F,
+}
+
+impl<F, State, Action> MasonryView<State, Action> for Switch<F>
+where
+    F: Fn(&mut State, bool) -> Action + Send + Sync + 'static,
+{
+    type Element = masonry::widget::Switch;
+    type ViewState = ();
+
+    fn build(&self, cx: &mut ViewCx) -> (WidgetPod<Self::Element>, Self::ViewState) {
+        cx.with_leaf_action_widget(|_| WidgetPod::new(masonry::widget::Switch::new(self.on)))
+    }
+
+    fn rebuild(
+        &self,
+        _view_state: &mut Self::ViewState,
+        cx: &mut ViewCx,
+        prev: &Self,
+        mut element: WidgetMut<Self::Element>,
+    ) {
+        if prev.on != self.on {
+            element.set_on(self.on);
+            cx.mark_changed();
+        }
+    }
+
+    fn message(
+        &self,
+        _view_state: &mut Self::ViewState,
+        id_path: &[ViewId],
+        message: Box<dyn std::any::Any>,
+        app_state: &mut State,
+    ) -> MessageResult<Action> {
+        debug_assert!(
+            id_path.is_empty(),
+            "id path should be empty in Switch::message"
+        );
+        match message.downcast::<masonry::Action>() {
+            Ok(action) => {
+                if let masonry::Action::SwitchToggled(on) = *action {
+                    MessageResult::Action((self.callback)(app_state, on))
+                } else {
+                    tracing::error!("Wrong action type in Switch::message: {action:?}");
+                    MessageResult::Stale(action)
+                }
+            }
+            Err(message) => {
+                tracing::error!("Wrong message type in Switch::message");
+                MessageResult::Stale(message)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{assert_element_stable, ViewHarness};
+
+    #[test]
+    fn switch_toggles_state() {
+        let mut harness = ViewHarness::new(false, |on: &mut bool| {
+            switch(*on, |state: &mut bool, on| *state = on)
+        });
+        let switch_id = harness.masonry_harness().root_widget().children()[0].id();
+
+        harness.masonry_harness().mouse_click_on(switch_id);
+        harness.process_actions();
+        assert!(*harness.state());
+        let switch = harness.masonry_harness().get_widget(switch_id);
+        assert!(switch
+            .downcast::<masonry::widget::Switch>()
+            .unwrap()
+            .is_on());
+    }
+
+    #[test]
+    fn value_only_rebuild_keeps_element() {
+        let mut harness = ViewHarness::new((false, 0), |(on, _): &mut (bool, u32)| {
+            assert_element_stable(switch(*on, |(state, _): &mut (bool, u32), on| {
+                *state = on;
+            }))
+        });
+        harness.masonry_harness().paint_damage();
+
+        // An unchanged value doesn't touch the widget.
+        harness.state().1 += 1;
+        harness.rebuild();
+        assert_eq!(harness.masonry_harness().paint_damage(), vec![]);
+
+        harness.state().0 = true;
+        harness.rebuild();
+        assert!(!harness.masonry_harness().paint_damage().is_empty());
+    }
+}
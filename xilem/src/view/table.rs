@@ -0,0 +1,313 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{any::Any, marker::PhantomData};
+
+use masonry::widget::{self, SortDirection, WidgetMut};
+use masonry::{ArcStr, WidgetPod};
+
+use crate::{MasonryView, MessageResult, ViewCx, ViewId};
+
+/// The widgets of the cells of one row.
+type RowCells = Vec<WidgetPod<Box<dyn masonry::Widget>>>;
+
+type SortCallback<State, Action> = Box<dyn Fn(&mut State, SortDirection) -> Action + Send + Sync>;
+
+/// A column of a [`table`], with a title in the header.
+pub struct ColumnSpec<State, Action = ()> {
+    spec: widget::ColumnSpec,
+    on_sort: Option<SortCallback<State, Action>>,
+}
+
+impl<State, Action> ColumnSpec<State, Action> {
+    /// Create a column with the given title.
+    pub fn new(title: impl Into<ArcStr>) -> Self {
+        ColumnSpec {
+            spec: widget::ColumnSpec::new(title),
+            on_sort: None,
+        }
+    }
+
+    /// Set the initial width of the column.
+    pub fn width(mut self, width: f64) -> Self {
+        self.spec = self.spec.width(width);
+        self
+    }
+
+    /// Make the column share the width left by the fixed columns.
+    pub fn flex(mut self, factor: f64) -> Self {
+        self.spec = self.spec.flex(factor);
+        self
+    }
+
+    /// Set the width the column can't be resized below.
+    pub fn min_width(mut self, min_width: f64) -> Self {
+        self.spec = self.spec.min_width(min_width);
+        self
+    }
+
+    /// Make the header of the column clickable, calling `callback` with the direction
+    /// the rows should be sorted in.
+    ///
+    /// The table doesn't reorder its rows: `callback` is expected to sort the data they
+    /// are built from.
+    pub fn sortable(
+        mut self,
+        callback: impl Fn(&mut State, SortDirection) -> Action + Send + Sync + 'static,
+    ) -> Self {
+        self.spec = self.spec.sortable(true);
+        self.on_sort = Some(Box::new(callback));
+        self
+    }
+}
+
+/// A table with the given columns and `row_count` rows, backed by [`masonry::widget::Table`].
+///
+/// The view of each cell is built by `cell_fn`, from its row and its column. The widths
+/// the columns are resized to by the user are kept across rebuilds, as long as the
+/// columns stay the same.
+pub fn table<State, Action, V>(
+    columns: Vec<ColumnSpec<State, Action>>,
+    row_count: usize,
+    cell_fn: impl Fn(usize, usize) -> V,
+) -> Table<State, Action, V>
+where
+    V: MasonryView<State, Action>,
+{
+    let cells = (0..row_count)
+        .map(|row| {
+            (0..columns.len())
+                .map(|column| cell_fn(row, column))
+                .collect()
+        })
+        .collect();
+    Table {
+        columns,
+        cells,
+        sort: None,
+        phantom: PhantomData,
+    }
+}
+
+pub struct Table<State, Action, V> {
+    columns: Vec<ColumnSpec<State, Action>>,
+    cells: Vec<Vec<V>>,
+    sort: Option<(usize, SortDirection)>,
+    phantom: PhantomData<fn() -> (State, Action)>,
+}
+
+impl<State, Action, V> Table<State, Action, V> {
+    /// Show the table as sorted by `column`, in `direction`.
+    ///
+    /// Without it, the table shows the order last requested from its header.
+    pub fn sorted_by(mut self, column: usize, direction: SortDirection) -> Self {
+        self.sort = Some((column, direction));
+        self
+    }
+}
+
+/// The id of the view of a cell, from its row and its column.
+fn cell_id<V: 'static>(row: usize, column: usize) -> ViewId {
+    ViewId::for_type::<V>(((row as u64) << 32) | column as u64)
+}
+
+impl<State, Action, V> Table<State, Action, V>
+where
+    V: MasonryView<State, Action>,
+{
+    fn build_row(&self, cx: &mut ViewCx, row: usize) -> (RowCells, Vec<V::ViewState>) {
+        self.cells[row]
+            .iter()
+            .enumerate()
+            .map(|(column, cell)| {
+                let (element, state) = cx.with_id(cell_id::<V>(row, column), |cx| cell.build(cx));
+                (element.boxed(), state)
+            })
+            .unzip()
+    }
+}
+
+impl<State, Action, V> MasonryView<State, Action> for Table<State, Action, V>
+where
+    State: 'static,
+    Action: 'static,
+    V: MasonryView<State, Action>,
+{
+    type Element = widget::Table;
+    type ViewState = Vec<Vec<V::ViewState>>;
+
+    fn build(&self, cx: &mut ViewCx) -> (WidgetPod<Self::Element>, Self::ViewState) {
+        let mut table = widget::Table::new(self.columns.iter().map(|column| column.spec.clone()));
+        if let Some((column, direction)) = self.sort {
+            table = table.with_sort(column, direction);
+        }
+        let mut states = Vec::with_capacity(self.cells.len());
+        for row in 0..self.cells.len() {
+            let (cells, row_states) = self.build_row(cx, row);
+            table = table.with_row_pods(cells);
+            states.push(row_states);
+        }
+        let element = cx.with_action_widget(|_| WidgetPod::new(table));
+        (element, states)
+    }
+
+    fn rebuild(
+        &self,
+        view_state: &mut Self::ViewState,
+        cx: &mut ViewCx,
+        prev: &Self,
+        mut element: WidgetMut<Self::Element>,
+    ) {
+        let columns_changed = self.columns.len() != prev.columns.len()
+            || self
+                .columns
+                .iter()
+                .zip(&prev.columns)
+                .any(|(column, prev)| column.spec != prev.spec);
+        if columns_changed {
+            element.set_columns(self.columns.iter().map(|column| column.spec.clone()));
+            cx.mark_changed();
+        }
+        if self.sort != prev.sort && self.sort.is_some() {
+            element.set_sort(self.sort);
+            cx.mark_changed();
+        }
+
+        // The cells of the rows both tables have are rebuilt, unless the number of
+        // columns changed, in which case all rows are built again.
+        let kept_rows = if self.columns.len() == prev.columns.len() {
+            self.cells.len().min(prev.cells.len())
+        } else {
+            0
+        };
+        let rows = self.cells.iter().zip(&prev.cells).take(kept_rows);
+        for (row, (cells, prev_cells)) in rows.enumerate() {
+            for (column, (cell, prev_cell)) in cells.iter().zip(prev_cells).enumerate() {
+                let state = &mut view_state[row][column];
+                cx.with_id(cell_id::<V>(row, column), |cx| {
+                    element.edit_cell(row, column, |mut element| {
+                        let element = element.downcast::<V::Element>();
+                        cell.rebuild(state, cx, prev_cell, element);
+                    });
+                });
+            }
+        }
+        if prev.cells.len() > kept_rows {
+            for row in (kept_rows..prev.cells.len()).rev() {
+                element.remove_row(row);
+            }
+            view_state.truncate(kept_rows);
+            cx.mark_changed();
+        }
+        if self.cells.len() > kept_rows {
+            for row in kept_rows..self.cells.len() {
+                let (cells, row_states) = self.build_row(cx, row);
+                element.add_row_pods(cells);
+                view_state.push(row_states);
+            }
+            cx.mark_changed();
+        }
+    }
+
+    fn message(
+        &self,
+        view_state: &mut Self::ViewState,
+        id_path: &[ViewId],
+        message: Box<dyn Any>,
+        app_state: &mut State,
+    ) -> MessageResult<Action> {
+        if let Some((first, rest)) = id_path.split_first() {
+            let id = first.routing_id();
+            let (row, column) = ((id >> 32) as usize, (id & 0xffff_ffff) as usize);
+            let cell = self.cells.get(row).and_then(|cells| cells.get(column));
+            let state = view_state
+                .get_mut(row)
+                .and_then(|states| states.get_mut(column));
+            return match (cell, state) {
+                (Some(cell), Some(state)) => cell.message(state, rest, message, app_state),
+                _ => MessageResult::Stale(message),
+            };
+        }
+        match message.downcast::<masonry::Action>() {
+            Ok(action) => {
+                if let masonry::Action::TableSortRequested(column, direction) = *action {
+                    match self
+                        .columns
+                        .get(column)
+                        .and_then(|column| column.on_sort.as_ref())
+                    {
+                        Some(on_sort) => MessageResult::Action(on_sort(app_state, direction)),
+                        None => {
+                            tracing::error!("Sorted table column {column} isn't sortable");
+                            MessageResult::Nop
+                        }
+                    }
+                } else {
+                    tracing::error!("Wrong action type in Table::message: {action:?}");
+                    MessageResult::Stale(action)
+                }
+            }
+            Err(message) => {
+                tracing::error!("Wrong message type in Table::message");
+                MessageResult::Stale(message)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::ViewHarness;
+    use crate::view::button;
+
+    #[derive(Default)]
+    struct AppState {
+        items: Vec<u32>,
+        pressed: Vec<u32>,
+    }
+
+    #[test]
+    fn table_sorts_state_and_routes_cell_messages() {
+        let state = AppState {
+            items: vec![3, 1, 2],
+            ..Default::default()
+        };
+        let mut harness = ViewHarness::new(state, |state: &mut AppState| {
+            let items = state.items.clone();
+            let columns = vec![
+                ColumnSpec::new("Item").sortable(|state: &mut AppState, direction| {
+                    state.items.sort_unstable();
+                    if direction == SortDirection::Descending {
+                        state.items.reverse();
+                    }
+                }),
+                ColumnSpec::new("Tens"),
+            ];
+            table(columns, items.len(), move |row, column| {
+                let value = items[row] * 10u32.pow(column as u32);
+                button(value.to_string(), move |state: &mut AppState| {
+                    state.pressed.push(value);
+                })
+            })
+        });
+        let masonry = harness.masonry_harness();
+        let table_id = masonry.root_widget().children()[0].id();
+        let sort_header = masonry.get_widget(table_id).children()[0].children()[0].id();
+        masonry.access_action(sort_header, accesskit::Action::Default);
+        harness.process_actions();
+        assert_eq!(harness.state().items, [1, 2, 3]);
+
+        // The button in the last row and the second column is for the largest item.
+        let masonry = harness.masonry_harness();
+        let last_row = masonry.get_widget(table_id).children()[3].id();
+        let tens_button = masonry.get_widget(last_row).children()[1].children()[0].id();
+        masonry.mouse_click_on(tens_button);
+        harness.process_actions();
+        assert_eq!(harness.state().pressed, [30]);
+
+        let table = harness.masonry_harness().get_widget(table_id);
+        let table = table.downcast::<masonry::widget::Table>().unwrap();
+        assert_eq!(table.sort(), Some((0, SortDirection::Ascending)));
+    }
+}
@@ -3,7 +3,11 @@
 
 use masonry::{text2::TextBrush, widget::WidgetMut, WidgetPod};
 
-use crate::{Color, MasonryView, MessageResult, TextAlignment, ViewCx, ViewId};
+use crate::view::{disabled, Disabled};
+use crate::{
+    Color, MasonryView, MessageResult, TextAlignment, TextboxVariant, ValidationState, ViewCx,
+    ViewId,
+};
 
 // FIXME - A major problem of the current approach (always setting the textbox contents)
 // is that if the user forgets to hook up the modify the state's contents in the callback,
@@ -22,7 +26,8 @@ where
         on_enter: None,
         text_brush: Color::WHITE.into(),
         alignment: TextAlignment::default(),
-        disabled: false,
+        variant: None,
+        validation: ValidationState::Valid,
     }
 }
 
@@ -32,7 +37,8 @@ pub struct Textbox<State, Action> {
     on_enter: Option<Callback<State, Action>>,
     text_brush: TextBrush,
     alignment: TextAlignment,
-    disabled: bool,
+    variant: Option<TextboxVariant>,
+    validation: ValidationState,
     // TODO: add more attributes of `masonry::widget::Label`
 }
 
@@ -48,8 +54,20 @@ impl<State, Action> Textbox<State, Action> {
         self
     }
 
-    pub fn disabled(mut self) -> Self {
-        self.disabled = true;
+    /// Disable the textbox, the same as wrapping it in [`disabled(view, true)`](disabled()).
+    pub fn disabled(self) -> Disabled<Self> {
+        disabled(self, true)
+    }
+
+    /// Style the textbox with the colors the [`Theme`](crate::Theme) defines for `variant`.
+    pub fn variant(mut self, variant: TextboxVariant) -> Self {
+        self.variant = Some(variant);
+        self
+    }
+
+    /// Show whether the contents are valid, e.g. after checking them in `on_changed`.
+    pub fn validation(mut self, state: ValidationState) -> Self {
+        self.validation = state;
         self
     }
 
@@ -68,11 +86,14 @@ impl<State: 'static, Action: 'static> MasonryView<State, Action> for Textbox<Sta
 
     fn build(&self, cx: &mut ViewCx) -> (WidgetPod<Self::Element>, Self::ViewState) {
         cx.with_leaf_action_widget(|_| {
-            WidgetPod::new(
-                masonry::widget::Textbox::new(self.contents.clone())
-                    .with_text_brush(self.text_brush.clone())
-                    .with_text_alignment(self.alignment),
-            )
+            let mut textbox = masonry::widget::Textbox::new(self.contents.clone())
+                .with_text_brush(self.text_brush.clone())
+                .with_text_alignment(self.alignment)
+                .with_validation_state(self.validation.clone());
+            if let Some(variant) = &self.variant {
+                textbox = textbox.with_variant(variant.clone());
+            }
+            WidgetPod::new(textbox)
         })
     }
 
@@ -93,10 +114,6 @@ impl<State: 'static, Action: 'static> MasonryView<State, Action> for Textbox<Sta
             cx.mark_changed();
         }
 
-        // if prev.disabled != self.disabled {
-        //     element.set_disabled(self.disabled);
-        //     cx.mark_changed();
-        // }
         if prev.text_brush != self.text_brush {
             element.set_text_brush(self.text_brush.clone());
             cx.mark_changed();
@@ -105,6 +122,14 @@ impl<State: 'static, Action: 'static> MasonryView<State, Action> for Textbox<Sta
             element.set_alignment(self.alignment);
             cx.mark_changed();
         }
+        if prev.variant != self.variant {
+            element.set_variant(self.variant.clone());
+            cx.mark_changed();
+        }
+        if prev.validation != self.validation {
+            element.set_validation_state(self.validation.clone());
+            cx.mark_changed();
+        }
     }
 
     fn message(
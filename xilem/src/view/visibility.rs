@@ -0,0 +1,148 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{any::Any, marker::PhantomData};
+
+use masonry::{widget::WidgetMut, WidgetPod};
+
+use crate::{MasonryView, MessageResult, ViewCx, ViewId};
+
+type VisibilityCallback<State, Action> = Box<dyn Fn(&mut State, bool) -> Action + Send + Sync>;
+
+/// Call `callback` when `child` is scrolled into or out of view, e.g. to load its
+/// content lazily. Backed by [`masonry::widget::VisibilityObserver`].
+///
+/// The callback is first called once `child` has been laid out, with whether it is
+/// visible. Afterwards, it is called each time the visibility changes.
+pub fn on_visibility_change<State, Action, V, F>(
+    child: V,
+    callback: F,
+) -> OnVisibilityChange<State, Action, V>
+where
+    V: MasonryView<State, Action>,
+    F: Fn(&mut State, bool) -> Action + Send + Sync + 'static,
+{
+    OnVisibilityChange {
+        child,
+        callback: Box::new(callback),
+        threshold: 0.0,
+        phantom: PhantomData,
+    }
+}
+
+pub struct OnVisibilityChange<State, Action, V> {
+    child: V,
+    callback: VisibilityCallback<State, Action>,
+    threshold: f64,
+    phantom: PhantomData<fn() -> (State, Action)>,
+}
+
+impl<State, Action, V> OnVisibilityChange<State, Action, V> {
+    /// Set which fraction of the child's area must be in view for it to count as visible.
+    ///
+    /// With the default of 0, a partially visible child is visible. With 1, the whole
+    /// child must be in view.
+    pub fn threshold(mut self, threshold: f64) -> Self {
+        self.threshold = threshold;
+        self
+    }
+}
+
+impl<State, Action, V> MasonryView<State, Action> for OnVisibilityChange<State, Action, V>
+where
+    State: 'static,
+    Action: 'static,
+    V: MasonryView<State, Action>,
+{
+    type Element = masonry::widget::VisibilityObserver<V::Element>;
+    type ViewState = V::ViewState;
+
+    fn build(&self, cx: &mut ViewCx) -> (WidgetPod<Self::Element>, Self::ViewState) {
+        let (child, child_state) = cx.with_id(ViewId::for_type::<V>(0), |cx| self.child.build(cx));
+        let observer =
+            masonry::widget::VisibilityObserver::new_pod(child).with_threshold(self.threshold);
+        let element = cx.with_action_widget(|_| WidgetPod::new(observer));
+        (element, child_state)
+    }
+
+    fn rebuild(
+        &self,
+        view_state: &mut Self::ViewState,
+        cx: &mut ViewCx,
+        prev: &Self,
+        mut element: WidgetMut<Self::Element>,
+    ) {
+        cx.with_id(ViewId::for_type::<V>(0), |cx| {
+            self.child
+                .rebuild(view_state, cx, &prev.child, element.child_mut());
+        });
+        if prev.threshold != self.threshold {
+            element.set_threshold(self.threshold);
+        }
+    }
+
+    fn message(
+        &self,
+        view_state: &mut Self::ViewState,
+        id_path: &[ViewId],
+        message: Box<dyn Any>,
+        app_state: &mut State,
+    ) -> MessageResult<Action> {
+        if let Some((_, rest)) = id_path.split_first() {
+            return self.child.message(view_state, rest, message, app_state);
+        }
+        match message.downcast::<masonry::Action>() {
+            Ok(action) => {
+                if let masonry::Action::VisibilityChanged(visible) = *action {
+                    MessageResult::Action((self.callback)(app_state, visible))
+                } else {
+                    tracing::error!("Wrong action type in OnVisibilityChange::message: {action:?}");
+                    MessageResult::Stale(action)
+                }
+            }
+            Err(message) => {
+                tracing::error!("Wrong message type in OnVisibilityChange::message");
+                MessageResult::Stale(message)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::ViewHarness;
+    use crate::view::{flex, label, scroll};
+
+    #[derive(Default)]
+    struct AppState {
+        visible: bool,
+        scroll_y: f64,
+    }
+
+    #[test]
+    fn visibility_change_on_scroll() {
+        let mut harness = ViewHarness::new(AppState::default(), |state: &mut AppState| {
+            let filler: Vec<_> = (0..100).map(|i| label(format!("Line {i}"))).collect();
+            scroll(flex((
+                on_visibility_change(label("Watched"), |state: &mut AppState, visible| {
+                    state.visible = visible;
+                }),
+                filler,
+            )))
+            .scroll_offset(masonry::Point::new(0.0, state.scroll_y))
+        });
+        harness.process_actions();
+        assert!(harness.state().visible);
+
+        harness.state().scroll_y = 300.0;
+        harness.rebuild();
+        harness.process_actions();
+        assert!(!harness.state().visible);
+
+        harness.state().scroll_y = 0.0;
+        harness.rebuild();
+        harness.process_actions();
+        assert!(harness.state().visible);
+    }
+}
@@ -0,0 +1,57 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+use std::any::Any;
+
+use masonry::{widget::WidgetMut, WidgetPod};
+
+use crate::{MasonryView, MessageResult, ViewCx, ViewId};
+
+/// Move the window when `child` is dragged, to build a custom title bar. Backed by
+/// [`masonry::widget::WindowDragRegion`].
+///
+/// Dragging further than a small threshold starts a native window move, while shorter
+/// presses are clicks. Double clicking maximizes the window, or restores it. Buttons in
+/// `child` keep working as usual.
+pub fn window_drag_region<V>(child: V) -> WindowDragRegion<V> {
+    WindowDragRegion { child }
+}
+
+pub struct WindowDragRegion<V> {
+    child: V,
+}
+
+impl<State, Action, V> MasonryView<State, Action> for WindowDragRegion<V>
+where
+    V: MasonryView<State, Action>,
+{
+    type Element = masonry::widget::WindowDragRegion<V::Element>;
+    type ViewState = V::ViewState;
+
+    fn build(&self, cx: &mut ViewCx) -> (WidgetPod<Self::Element>, Self::ViewState) {
+        let (child, child_state) = self.child.build(cx);
+        let region = masonry::widget::WindowDragRegion::new_pod(child);
+        (WidgetPod::new(region), child_state)
+    }
+
+    fn rebuild(
+        &self,
+        view_state: &mut Self::ViewState,
+        cx: &mut ViewCx,
+        prev: &Self,
+        mut element: WidgetMut<Self::Element>,
+    ) {
+        self.child
+            .rebuild(view_state, cx, &prev.child, element.child_mut());
+    }
+
+    fn message(
+        &self,
+        view_state: &mut Self::ViewState,
+        id_path: &[ViewId],
+        message: Box<dyn Any>,
+        app_state: &mut State,
+    ) -> MessageResult<Action> {
+        self.child.message(view_state, id_path, message, app_state)
+    }
+}
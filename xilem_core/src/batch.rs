@@ -0,0 +1,188 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+use std::sync::Mutex;
+
+/// A queue of the messages sent to views from outside of the view tree, e.g. by async
+/// tasks or other threads, shared between the senders and the driver.
+///
+/// The driver takes all the queued messages at once with [`take`](Self::take) when it's
+/// woken up, routes each of them, and rebuilds the view tree once for the whole
+/// [`MessageBatch`], rather than once per message.
+///
+/// # Ordering
+///
+/// - Messages are delivered in the order they were queued, across all senders.
+/// - The messages queued by one [`push_batch`](Self::push_batch) are delivered
+///   contiguously: no message queued concurrently is delivered between them.
+/// - A message queued while the driver handles a batch is part of the next batch.
+pub struct BatchQueue<M> {
+    messages: Mutex<Vec<M>>,
+}
+
+impl<M> BatchQueue<M> {
+    pub fn new() -> Self {
+        BatchQueue {
+            messages: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Queue `message`.
+    ///
+    /// Returns whether it's the first message of a new batch, in which case the driver
+    /// should be woken up. Otherwise, a wake-up is already pending.
+    pub fn push(&self, message: M) -> bool {
+        let mut messages = self.messages.lock().unwrap();
+        messages.push(message);
+        messages.len() == 1
+    }
+
+    /// Queue all of `messages`, to be delivered contiguously and in order.
+    ///
+    /// Returns whether they start a new batch, like [`push`](Self::push). Does nothing
+    /// and returns `false` if `messages` is empty.
+    pub fn push_batch(&self, messages: impl IntoIterator<Item = M>) -> bool {
+        let mut queued = self.messages.lock().unwrap();
+        let was_empty = queued.is_empty();
+        queued.extend(messages);
+        was_empty && !queued.is_empty()
+    }
+
+    /// Take the queued messages, in the order they were queued.
+    pub fn take(&self) -> MessageBatch<M> {
+        MessageBatch {
+            messages: std::mem::take(&mut *self.messages.lock().unwrap()),
+        }
+    }
+
+    /// Whether no message is queued.
+    pub fn is_empty(&self) -> bool {
+        self.messages.lock().unwrap().is_empty()
+    }
+}
+
+impl<M> Default for BatchQueue<M> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The messages taken from a [`BatchQueue`] at once, in the order they were queued.
+pub struct MessageBatch<M> {
+    messages: Vec<M>,
+}
+
+impl<M> MessageBatch<M> {
+    /// Route each message in order with `route`, which returns whether the message
+    /// requires a rebuild.
+    ///
+    /// Returns whether any of them did, in which case the driver should rebuild the view
+    /// tree once, after the whole batch has been routed.
+    pub fn deliver(self, mut route: impl FnMut(M) -> bool) -> bool {
+        let mut rebuild = false;
+        for message in self.messages {
+            rebuild |= route(message);
+        }
+        rebuild
+    }
+
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+}
+
+impl<M> IntoIterator for MessageBatch<M> {
+    type Item = M;
+    type IntoIter = std::vec::IntoIter<M>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.messages.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+
+    #[test]
+    fn batch_preserves_arrival_order() {
+        let queue = BatchQueue::new();
+        assert!(queue.push(("first", 1)));
+        assert!(!queue.push(("second", 1)));
+        assert!(!queue.push(("first", 2)));
+        assert!(!queue.push_batch([("second", 2), ("second", 3)]));
+
+        let mut routed = vec![];
+        let rebuild = queue.take().deliver(|message| {
+            routed.push(message);
+            message.1 == 2
+        });
+        assert!(rebuild);
+        assert_eq!(
+            routed,
+            [
+                ("first", 1),
+                ("second", 1),
+                ("first", 2),
+                ("second", 2),
+                ("second", 3)
+            ]
+        );
+        // The next message starts a new batch.
+        assert!(queue.is_empty());
+        assert!(queue.push_batch([("first", 3)]));
+        assert!(!queue.take().deliver(|_| false));
+    }
+
+    #[test]
+    fn pushed_batches_are_contiguous() {
+        let queue = Arc::new(BatchQueue::new());
+        let threads: Vec<_> = (0..4)
+            .map(|sender| {
+                let queue = queue.clone();
+                std::thread::spawn(move || {
+                    for batch in 0..50 {
+                        queue.push_batch((0..10).map(|i| (sender, batch, i)));
+                        queue.push((sender, batch, 10));
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        let messages: Vec<_> = queue.take().into_iter().collect();
+        assert_eq!(messages.len(), 4 * 50 * 11);
+        for sender in 0..4 {
+            // The messages of each sender are in the order it sent them.
+            let sent: Vec<_> = messages
+                .iter()
+                .filter(|message| message.0 == sender)
+                .copied()
+                .collect();
+            let expected: Vec<_> = (0..50)
+                .flat_map(|batch| (0..=10).map(move |i| (sender, batch, i)))
+                .collect();
+            assert_eq!(sent, expected);
+
+            // No other message is between the messages of a batch.
+            for batch in 0..50 {
+                let start = messages
+                    .iter()
+                    .position(|message| *message == (sender, batch, 0))
+                    .unwrap();
+                assert_eq!(
+                    messages[start..start + 10],
+                    expected[batch * 11..batch * 11 + 10]
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,146 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// A flag to abort a rebuild in progress, e.g. because the app state changed again and
+/// the view being rebuilt is already outdated.
+///
+/// Rebuilds check the flag of their context (through its `is_rebuild_cancelled` method)
+/// between the children of `Vec` view sequences, which are where large trees spend their
+/// time. When it is set, the children which haven't been rebuilt yet are removed, so that
+/// every remaining state and element matches the new view. Other sequences and views
+/// carry on, but the `Vec` sequences they contain stop at their first child.
+///
+/// A cancelled rebuild leaves an incomplete element tree: the driver should reset the
+/// flag, and rebuild the newest view right away, using the view of the cancelled rebuild
+/// as the previous view. The removed children are then built again.
+#[derive(Clone, Debug, Default)]
+pub struct RebuildCancellation(Arc<AtomicBool>);
+
+impl RebuildCancellation {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ask the rebuild in progress, if any, to stop at the next checkpoint.
+    ///
+    /// This can be called from any thread.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether [`cancel`](Self::cancel) was called since the last [`reset`](Self::reset).
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Clear the flag, before rebuilding again.
+    pub fn reset(&self) {
+        self.0.store(false, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::any::Any;
+
+    use crate::test_support::{elements, ChangeFlags, Cx, View, ViewMarker, ViewSequence};
+    use crate::{Id, MessageResult, VecSplice};
+
+    /// A view displaying a number, which simulates a newer state update arriving
+    /// while it is being rebuilt when `interrupts` is set.
+    struct Number {
+        value: u32,
+        interrupts: bool,
+    }
+
+    fn number(value: u32) -> Number {
+        Number {
+            value,
+            interrupts: false,
+        }
+    }
+
+    impl ViewMarker for Number {}
+
+    impl View<Vec<u32>> for Number {
+        /// The value when the element was last written.
+        type State = u32;
+        type Element = u32;
+
+        fn build(&self, _cx: &mut Cx) -> (Id, u32, u32) {
+            (Id::next(), self.value, self.value)
+        }
+
+        fn rebuild(
+            &self,
+            cx: &mut Cx,
+            prev: &Self,
+            _id: &mut Id,
+            state: &mut u32,
+            element: &mut u32,
+        ) -> ChangeFlags {
+            assert_eq!(*state, prev.value, "state doesn't match the previous view");
+            *state = self.value;
+            *element = self.value;
+            if self.interrupts {
+                cx.cancellation.cancel();
+            }
+            ChangeFlags::empty()
+        }
+
+        fn message(
+            &self,
+            _id_path: &[Id],
+            state: &mut u32,
+            _message: Box<dyn Any>,
+            app_state: &mut Vec<u32>,
+        ) -> MessageResult<()> {
+            app_state.push(*state);
+            MessageResult::Nop
+        }
+    }
+
+    #[test]
+    fn cancelled_rebuild_restarts_cleanly() {
+        let mut cx = Cx::new();
+        let (mut pods, mut scratch) = (vec![], vec![]);
+        let view: Vec<Number> = (1..=6).map(number).collect();
+        let mut state = view.build(&mut cx, &mut VecSplice::new(&mut pods, &mut scratch));
+
+        // A newer update arrives while the third child is being rebuilt.
+        let mut cancelled: Vec<Number> = (11..=16).map(number).collect();
+        cancelled[2].interrupts = true;
+        let changed = cancelled.rebuild(
+            &mut cx,
+            &view,
+            &mut state,
+            &mut VecSplice::new(&mut pods, &mut scratch),
+        );
+        assert!(cx.is_rebuild_cancelled());
+        assert!(changed.0);
+        // Nothing older than the cancelled view is left.
+        assert_eq!(elements::<u32>(&pods), [11, 12, 13]);
+        assert_eq!(cancelled.count(&state), 3);
+
+        cx.cancellation.reset();
+        let newest: Vec<Number> = (21..=27).map(number).collect();
+        newest.rebuild(
+            &mut cx,
+            &cancelled,
+            &mut state,
+            &mut VecSplice::new(&mut pods, &mut scratch),
+        );
+        assert!(!cx.is_rebuild_cancelled());
+        assert_eq!(elements::<u32>(&pods), [21, 22, 23, 24, 25, 26, 27]);
+        assert_eq!(newest.count(&state), 7);
+
+        // Messages reach the children which were built again.
+        let mut app_state = vec![];
+        let id = state[5].1;
+        newest.message(&[id], &mut state, Box::new(()), &mut app_state);
+        assert_eq!(app_state, [26]);
+    }
+}
@@ -6,6 +6,9 @@ use std::{
     sync::atomic::{AtomicU64, Ordering},
 };
 
+/// The bit distinguishing the ids chosen by the user from the allocated ones.
+const STABLE_BIT: u64 = 1 << 63;
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Hash)]
 /// A stable identifier for an element.
 pub struct Id(NonZeroU64);
@@ -20,6 +23,20 @@ impl Id {
         Id(NonZeroU64::new(ID_COUNTER.fetch_add(1, Ordering::Relaxed)).unwrap())
     }
 
+    /// The `Id` chosen by the user with the given `key`.
+    ///
+    /// Unlike the ids allocated with [`Id::next`], which change when a view is rebuilt
+    /// from scratch, this is the same for a given `key` across the lifetime of the app.
+    /// The top bit of `key` is ignored.
+    pub fn stable(key: u64) -> Id {
+        Id(NonZeroU64::new(key | STABLE_BIT).unwrap())
+    }
+
+    /// Whether this id was created with [`Id::stable`].
+    pub fn is_stable(self) -> bool {
+        self.to_raw() & STABLE_BIT != 0
+    }
+
     #[allow(unused)]
     pub fn to_raw(self) -> u64 {
         self.0.into()
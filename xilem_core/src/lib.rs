@@ -16,12 +16,55 @@
 //! here, but that also requires coordination with the context.
 
 mod any_view;
+mod batch;
+mod cancel;
 mod id;
 mod message;
+mod retry;
 mod sequence;
+mod split_state;
+mod stable_id;
+mod subscription;
+#[cfg(test)]
+mod test_support;
 mod vec_splice;
 mod view;
+mod zip_sequences;
 
+pub use batch::{BatchQueue, MessageBatch};
+pub use cancel::RebuildCancellation;
 pub use id::{Id, IdPath};
-pub use message::{AsyncWake, MessageResult};
+pub use message::{AsyncWake, MessageOrigin, MessageResult};
+pub use retry::{retry, Retry};
+pub use split_state::{split_state, SplitState};
+pub use stable_id::{StableIdRegistration, StableIds};
+pub use subscription::Subscription;
 pub use vec_splice::VecSplice;
+pub use zip_sequences::{zip_sequences, ZipSequences};
+
+#[doc(hidden)]
+pub use futures_core;
+#[cfg(feature = "trace_diff")]
+#[doc(hidden)]
+pub use tracing;
+
+/// Log a change made by a rebuild.
+///
+/// With the `trace_diff` feature, this emits a `TRACE` event with the `xilem_core::diff`
+/// target, which records the views that are built, rebuilt or skipped by memoization,
+/// and the elements that are removed. Without it, this expands to nothing.
+#[cfg(feature = "trace_diff")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! trace_diff {
+    ($($arg:tt)*) => {
+        $crate::tracing::trace!(target: "xilem_core::diff", $($arg)*)
+    };
+}
+
+#[cfg(not(feature = "trace_diff"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! trace_diff {
+    ($($arg:tt)*) => {};
+}
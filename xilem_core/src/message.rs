@@ -1,7 +1,7 @@
 // Copyright 2022 the Xilem Authors
 // SPDX-License-Identifier: Apache-2.0
 
-use std::any::Any;
+use std::{any::Any, fmt, panic::Location};
 
 #[macro_export]
 macro_rules! message {
@@ -9,19 +9,75 @@ macro_rules! message {
         pub struct Message {
             pub id_path: xilem_core::IdPath,
             pub body: Box<dyn std::any::Any + $($bounds)*>,
+            /// Where the message comes from, for debugging.
+            pub origin: xilem_core::MessageOrigin,
         }
 
         impl Message {
+            /// Create a message, whose origin is the caller.
+            #[track_caller]
             pub fn new(id_path: xilem_core::IdPath, event: impl std::any::Any + $($bounds)*) -> Message {
+                Self::new_with_origin(id_path, event, xilem_core::MessageOrigin::caller())
+            }
+
+            pub fn new_with_origin(
+                id_path: xilem_core::IdPath,
+                event: impl std::any::Any + $($bounds)*,
+                origin: xilem_core::MessageOrigin,
+            ) -> Message {
                 Message {
                     id_path,
                     body: Box::new(event),
+                    origin,
                 }
             }
         }
     };
 }
 
+/// The place in the source code where a message comes from, usually where its handler was
+/// registered, e.g. the call to `on_click`.
+///
+/// This is only recorded in debug builds, to find where an action comes from. In release
+/// builds, this is a zero-sized type, and the origin is always unknown.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MessageOrigin {
+    #[cfg(debug_assertions)]
+    location: &'static Location<'static>,
+}
+
+impl MessageOrigin {
+    /// The location of the caller, like [`Location::caller`].
+    ///
+    /// To record the caller of a function calling this, that function must also be
+    /// annotated with `#[track_caller]`.
+    #[track_caller]
+    #[inline]
+    pub fn caller() -> Self {
+        MessageOrigin {
+            #[cfg(debug_assertions)]
+            location: Location::caller(),
+        }
+    }
+
+    /// The location where the message comes from, or `None` in release builds.
+    pub fn location(&self) -> Option<&'static Location<'static>> {
+        #[cfg(debug_assertions)]
+        return Some(self.location);
+        #[cfg(not(debug_assertions))]
+        return None;
+    }
+}
+
+impl fmt::Display for MessageOrigin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.location() {
+            Some(location) => write!(f, "{location}"),
+            None => f.write_str("<unknown origin>"),
+        }
+    }
+}
+
 /// A result wrapper type for event handlers.
 #[derive(Default)]
 pub enum MessageResult<A> {
@@ -72,3 +128,53 @@ impl<A> MessageResult<A> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::MessageOrigin;
+    // The `message!` macro refers to this crate by name.
+    use crate as xilem_core;
+    use crate::IdPath;
+
+    crate::message!();
+
+    #[cfg(debug_assertions)]
+    fn register_handler() -> MessageOrigin {
+        MessageOrigin::caller()
+    }
+
+    #[cfg(debug_assertions)]
+    #[track_caller]
+    fn register_tracked_handler() -> MessageOrigin {
+        MessageOrigin::caller()
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    fn message_reports_its_origin() {
+        let line = line!() + 1;
+        let message = Message::new(IdPath::new(), 42);
+        assert!(message.id_path.is_empty() && message.body.is::<i32>());
+        let location = message.origin.location().unwrap();
+        assert_eq!((location.file(), location.line()), (file!(), line));
+        assert_eq!(message.origin.to_string(), format!("{location}"));
+
+        // The origin is the first caller which isn't tracked.
+        let untracked = register_handler();
+        assert_ne!(untracked.location().unwrap().line(), line!() - 1);
+        let tracked = register_tracked_handler();
+        assert_eq!(tracked.location().unwrap().line(), line!() - 1);
+        let message = Message::new_with_origin(IdPath::new(), 42, tracked);
+        assert_eq!(message.origin, tracked);
+    }
+
+    #[test]
+    #[cfg(not(debug_assertions))]
+    fn origin_is_free_in_release_builds() {
+        assert_eq!(std::mem::size_of::<MessageOrigin>(), 0);
+        let message = Message::new(IdPath::new(), 42);
+        assert!(message.id_path.is_empty() && message.body.is::<i32>());
+        assert_eq!(message.origin.location(), None);
+        assert_eq!(message.origin.to_string(), "<unknown origin>");
+    }
+}
@@ -0,0 +1,217 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures_core::Stream;
+
+/// Run the fallible task created by `try_task` until it succeeds, at most `max_attempts` times.
+///
+/// After each failed attempt but the last one, the future returned by `backoff` is awaited
+/// before trying again. It is given the number of attempts made so far, starting at 1, so
+/// that the delay can grow with it. This crate has no timer of its own: `backoff` usually
+/// returns the sleep future of the framework's runtime.
+///
+/// The result is the output of the first successful attempt, or the error of the last one.
+/// It can be awaited, or used as a stream with a single item, e.g. as the source of a
+/// `Subscribe` view. A `max_attempts` of 0 is treated as 1.
+pub fn retry<F, Fut, T, E, B, D>(max_attempts: u32, backoff: B, try_task: F) -> Retry<F, Fut, B, D>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+    B: FnMut(u32) -> D,
+    D: Future<Output = ()>,
+{
+    Retry {
+        try_task,
+        backoff,
+        max_attempts: max_attempts.max(1),
+        attempts: 0,
+        phase: Phase::Start,
+    }
+}
+
+/// The future and stream returned by [`retry`].
+pub struct Retry<F, Fut, B, D> {
+    try_task: F,
+    backoff: B,
+    max_attempts: u32,
+    attempts: u32,
+    phase: Phase<Fut, D>,
+}
+
+enum Phase<Fut, D> {
+    Start,
+    Trying(Pin<Box<Fut>>),
+    Waiting(Pin<Box<D>>),
+    Done,
+}
+
+// The task and backoff futures are boxed, and the functions creating them are never pinned.
+impl<F, Fut, B, D> Unpin for Retry<F, Fut, B, D> {}
+
+impl<F, Fut, T, E, B, D> Retry<F, Fut, B, D>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+    B: FnMut(u32) -> D,
+    D: Future<Output = ()>,
+{
+    fn poll_result(&mut self, cx: &mut Context<'_>) -> Poll<Option<Result<T, E>>> {
+        loop {
+            self.phase = match &mut self.phase {
+                Phase::Start => {
+                    self.attempts += 1;
+                    Phase::Trying(Box::pin((self.try_task)()))
+                }
+                Phase::Trying(task) => match task.as_mut().poll(cx) {
+                    Poll::Pending => return Poll::Pending,
+                    Poll::Ready(Err(_)) if self.attempts < self.max_attempts => {
+                        Phase::Waiting(Box::pin((self.backoff)(self.attempts)))
+                    }
+                    Poll::Ready(result) => {
+                        self.phase = Phase::Done;
+                        return Poll::Ready(Some(result));
+                    }
+                },
+                Phase::Waiting(delay) => match delay.as_mut().poll(cx) {
+                    Poll::Pending => return Poll::Pending,
+                    Poll::Ready(()) => Phase::Start,
+                },
+                Phase::Done => return Poll::Ready(None),
+            };
+        }
+    }
+}
+
+impl<F, Fut, T, E, B, D> Future for Retry<F, Fut, B, D>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+    B: FnMut(u32) -> D,
+    D: Future<Output = ()>,
+{
+    type Output = Result<T, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.get_mut()
+            .poll_result(cx)
+            .map(|result| result.expect("`Retry` polled after completion"))
+    }
+}
+
+impl<F, Fut, T, E, B, D> Stream for Retry<F, Fut, B, D>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+    B: FnMut(u32) -> D,
+    D: Future<Output = ()>,
+{
+    type Item = Result<T, E>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().poll_result(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::{Cell, RefCell};
+    use std::future::{poll_fn, ready};
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::test_support::noop_waker;
+    use crate::Subscription;
+
+    /// Poll `task` until it's waiting, and return whether it completed.
+    fn poll_once(task: &mut Pin<Box<dyn Future<Output = ()>>>) -> bool {
+        let waker = noop_waker();
+        task.as_mut()
+            .poll(&mut Context::from_waker(&waker))
+            .is_ready()
+    }
+
+    /// A task failing `failures` times before it succeeds, counting its attempts.
+    fn flaky(
+        failures: u32,
+        attempts: Rc<Cell<u32>>,
+    ) -> impl FnMut() -> std::future::Ready<Result<u32, String>> {
+        move || {
+            attempts.set(attempts.get() + 1);
+            ready(if attempts.get() > failures {
+                Ok(attempts.get())
+            } else {
+                Err(format!("attempt {} failed", attempts.get()))
+            })
+        }
+    }
+
+    #[test]
+    fn success_after_failures_reaches_state() {
+        let attempts = Rc::new(Cell::new(0));
+        let delays = Rc::new(RefCell::new(vec![]));
+        let delays_clone = delays.clone();
+        let backoff = move |attempt| {
+            delays_clone.borrow_mut().push(attempt);
+            ready(())
+        };
+        let state = Rc::new(RefCell::new(vec![]));
+        let state_clone = state.clone();
+        let (_subscription, task) = Subscription::new(
+            retry(5, backoff, flaky(2, attempts.clone())),
+            move |result| state_clone.borrow_mut().push(result),
+        );
+
+        let mut task: Pin<Box<dyn Future<Output = ()>>> = Box::pin(task);
+        assert!(poll_once(&mut task));
+        assert_eq!(*state.borrow(), [Ok(3)]);
+        assert_eq!(attempts.get(), 3);
+        assert_eq!(*delays.borrow(), [1, 2]);
+    }
+
+    #[test]
+    fn last_error_after_max_attempts() {
+        let attempts = Rc::new(Cell::new(0));
+        let mut task: Pin<Box<dyn Future<Output = ()>>> = Box::pin({
+            let retry = retry(2, |_| ready(()), flaky(5, attempts.clone()));
+            async move {
+                assert_eq!(retry.await, Err("attempt 2 failed".to_string()));
+            }
+        });
+
+        assert!(poll_once(&mut task));
+        assert_eq!(attempts.get(), 2);
+    }
+
+    #[test]
+    fn waits_for_backoff() {
+        let attempts = Rc::new(Cell::new(0));
+        let elapsed = Rc::new(Cell::new(false));
+        let elapsed_clone = elapsed.clone();
+        let backoff = move |_| {
+            let elapsed = elapsed_clone.clone();
+            poll_fn(move |_| {
+                if elapsed.get() {
+                    Poll::Ready(())
+                } else {
+                    Poll::Pending
+                }
+            })
+        };
+        let mut task: Pin<Box<dyn Future<Output = ()>>> = Box::pin({
+            let retry = retry(3, backoff, flaky(1, attempts.clone()));
+            async move {
+                assert_eq!(retry.await, Ok(2));
+            }
+        });
+
+        assert!(!poll_once(&mut task));
+        assert_eq!(attempts.get(), 1);
+        elapsed.set(true);
+        assert!(poll_once(&mut task));
+        assert_eq!(attempts.get(), 2);
+    }
+}
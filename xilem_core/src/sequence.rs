@@ -53,6 +53,10 @@ macro_rules! impl_view_tuple {
         }
     }
 }
+/// Create the `ViewSequence` trait and its implementations for a particular xilem context.
+///
+/// Besides the methods used by [`generate_view_trait`], the context must have an
+/// `is_rebuild_cancelled(&self) -> bool` method, see [`RebuildCancellation`](crate::RebuildCancellation).
 #[macro_export]
 macro_rules! generate_viewsequence_trait {
     ($viewseq:ident, $view:ident, $viewmarker: ident, $elements_splice: ident, $bound:ident, $cx:ty, $changeflags:ty, $pod:ty; $( $ss:tt )* ) => {
@@ -151,6 +155,7 @@ macro_rules! generate_viewsequence_trait {
 
             fn build(&self, cx: &mut $cx, elements: &mut dyn $elements_splice) -> Self::State {
                 let (id, state, pod) = cx.with_new_pod(|cx| <V as $view<T, A>>::build(self, cx));
+                $crate::trace_diff!(view = std::any::type_name::<V>(), "build");
                 elements.push(pod, cx);
                 (state, id)
             }
@@ -162,6 +167,7 @@ macro_rules! generate_viewsequence_trait {
                 state: &mut Self::State,
                 elements: &mut dyn $elements_splice,
             ) -> $changeflags {
+                $crate::trace_diff!(view = std::any::type_name::<V>(), "rebuild");
                 let pod = elements.mutate(cx);
                 let flags = cx.with_pod(pod, |el, cx| {
                     <V as $view<T, A>>::rebuild(
@@ -226,6 +232,7 @@ macro_rules! generate_viewsequence_trait {
                     (Some(this), Some(state), Some(prev)) => this.rebuild(cx, prev, state, elements),
                     (None, Some(seq_state), Some(prev)) => {
                         let count = prev.count(&seq_state);
+                        $crate::trace_diff!(elements = count, "remove");
                         elements.delete(count, cx);
                         *state = None;
 
@@ -278,25 +285,41 @@ macro_rules! generate_viewsequence_trait {
                 state: &mut Self::State,
                 elements: &mut dyn $elements_splice,
             ) -> $changeflags {
+                // After a cancelled rebuild, only the first `state.len()` children of `prev`
+                // have been rebuilt, the other ones have been removed.
+                let synced = state.len();
                 let mut changed = <$changeflags>::default();
-                for ((child, child_prev), child_state) in self.iter().zip(prev).zip(state.iter_mut()) {
-                    let el_changed = child.rebuild(cx, child_prev, child_state, elements);
-                    changed |= el_changed;
+                for (i, child) in self.iter().enumerate() {
+                    if cx.is_rebuild_cancelled() {
+                        // Drop what hasn't been rebuilt yet, so that every remaining
+                        // state and element matches `self`.
+                        let n_delete = state
+                            .splice(i.., [])
+                            .enumerate()
+                            .map(|(j, state)| prev[i + j].count(&state))
+                            .sum();
+                        $crate::trace_diff!(elements = n_delete, "cancel");
+                        elements.delete(n_delete, cx);
+                        changed |= <$changeflags>::tree_structure();
+                        return changed;
+                    }
+                    if i < synced {
+                        changed |= child.rebuild(cx, &prev[i], &mut state[i], elements);
+                    } else {
+                        state.push(child.build(cx, elements));
+                        changed |= <$changeflags>::tree_structure();
+                    }
                 }
                 let n = self.len();
-                if n < prev.len() {
+                if n < synced {
                     let n_delete = state
                         .splice(n.., [])
                         .enumerate()
                         .map(|(i, state)| prev[n + i].count(&state))
                         .sum();
+                    $crate::trace_diff!(elements = n_delete, "remove");
                     elements.delete(n_delete, cx);
                     changed |= <$changeflags>::tree_structure();
-                } else if n > prev.len() {
-                    for i in prev.len()..n {
-                        state.push(self[i].build(cx, elements));
-                    }
-                    changed |= <$changeflags>::tree_structure();
                 }
                 changed
             }
@@ -326,6 +349,153 @@ macro_rules! generate_viewsequence_trait {
             }
         }
 
+        impl<T, A, VA: $viewseq<T, A>, VB: $viewseq<T, A>> $viewseq<T, A>
+            for $crate::ZipSequences<VA, VB>
+        {
+            type State = Vec<(Option<VA::State>, Option<VB::State>)>;
+
+            fn build(&self, cx: &mut $cx, elements: &mut dyn $elements_splice) -> Self::State {
+                (0..self.len())
+                    .map(|i| {
+                        let (a, b) = self.get(i);
+                        let a_state = a.map(|a| a.build(cx, elements));
+                        let b_state = b.map(|b| b.build(cx, elements));
+                        (a_state, b_state)
+                    })
+                    .collect()
+            }
+
+            fn rebuild(
+                &self,
+                cx: &mut $cx,
+                prev: &Self,
+                state: &mut Self::State,
+                elements: &mut dyn $elements_splice,
+            ) -> $changeflags {
+                // Same as the `Option<VT>` implementation, for one item of a pair
+                fn rebuild_item<T, A, V: $viewseq<T, A>>(
+                    cx: &mut $cx,
+                    this: Option<&V>,
+                    prev: Option<&V>,
+                    state: &mut Option<V::State>,
+                    elements: &mut dyn $elements_splice,
+                ) -> $changeflags {
+                    match (this, &mut *state, prev) {
+                        (Some(this), Some(state), Some(prev)) => this.rebuild(cx, prev, state, elements),
+                        (None, Some(seq_state), Some(prev)) => {
+                            let count = prev.count(seq_state);
+                            $crate::trace_diff!(elements = count, "remove");
+                            elements.delete(count, cx);
+                            *state = None;
+
+                            <$changeflags>::tree_structure()
+                        }
+                        (Some(this), None, None) => {
+                            *state = Some(this.build(cx, elements));
+
+                            <$changeflags>::tree_structure()
+                        }
+                        (None, None, None) => <$changeflags>::empty(),
+                        _ => panic!("non matching state and prev value"),
+                    }
+                }
+
+                let mut changed = <$changeflags>::default();
+                let n = self.len();
+                for i in 0..n.max(prev.len()) {
+                    let (a, b) = self.get(i);
+                    let (prev_a, prev_b) = prev.get(i);
+                    if i == state.len() {
+                        state.push((None, None));
+                    }
+                    let (a_state, b_state) = &mut state[i];
+                    changed |= rebuild_item(cx, a, prev_a, a_state, elements);
+                    changed |= rebuild_item(cx, b, prev_b, b_state, elements);
+                }
+                state.truncate(n);
+                changed
+            }
+
+            fn count(&self, state: &Self::State) -> usize {
+                state
+                    .iter()
+                    .enumerate()
+                    .map(|(i, (a_state, b_state))| {
+                        let (a, b) = self.get(i);
+                        a.zip(a_state.as_ref()).map_or(0, |(a, s)| a.count(s))
+                            + b.zip(b_state.as_ref()).map_or(0, |(b, s)| b.count(s))
+                    })
+                    .sum()
+            }
+
+            fn message(
+                &self,
+                id_path: &[$crate::Id],
+                state: &mut Self::State,
+                message: Box<dyn std::any::Any>,
+                app_state: &mut T,
+            ) -> $crate::MessageResult<A> {
+                let mut result = $crate::MessageResult::Stale(message);
+                for (i, (a_state, b_state)) in state.iter_mut().enumerate() {
+                    let (a, b) = self.get(i);
+                    if let (Some(a), Some(a_state)) = (a, a_state) {
+                        result = result.or(|message| a.message(id_path, a_state, message, app_state));
+                    }
+                    if let (Some(b), Some(b_state)) = (b, b_state) {
+                        result = result.or(|message| b.message(id_path, b_state, message, app_state));
+                    }
+                    if !matches!(result, $crate::MessageResult::Stale(_)) {
+                        break;
+                    }
+                }
+                result
+            }
+        }
+
+        impl<T, A, L, R, VL, VR, F> $viewseq<T, A> for $crate::SplitState<T, L, R, VL, VR, F>
+        where
+            VL: $viewseq<L, A>,
+            VR: $viewseq<R, A>,
+            F: Fn(&mut T) -> (&mut L, &mut R),
+        {
+            type State = (VL::State, VR::State);
+
+            fn build(&self, cx: &mut $cx, elements: &mut dyn $elements_splice) -> Self::State {
+                let left_state = self.left().build(cx, elements);
+                let right_state = self.right().build(cx, elements);
+                (left_state, right_state)
+            }
+
+            fn rebuild(
+                &self,
+                cx: &mut $cx,
+                prev: &Self,
+                state: &mut Self::State,
+                elements: &mut dyn $elements_splice,
+            ) -> $changeflags {
+                let mut changed = self.left().rebuild(cx, prev.left(), &mut state.0, elements);
+                changed |= self.right().rebuild(cx, prev.right(), &mut state.1, elements);
+                changed
+            }
+
+            fn message(
+                &self,
+                id_path: &[$crate::Id],
+                state: &mut Self::State,
+                message: Box<dyn std::any::Any>,
+                app_state: &mut T,
+            ) -> $crate::MessageResult<A> {
+                let (left_state, right_state) = self.split(app_state);
+                self.left()
+                    .message(id_path, &mut state.0, message, left_state)
+                    .or(|message| self.right().message(id_path, &mut state.1, message, right_state))
+            }
+
+            fn count(&self, state: &Self::State) -> usize {
+                self.left().count(&state.0) + self.right().count(&state.1)
+            }
+        }
+
         /// This trait marks a type a
         #[doc = concat!(stringify!($view), ".")]
         ///
@@ -361,3 +531,222 @@ macro_rules! generate_viewsequence_trait {
             V0, V1, V2, V3, V4, V5, V6, V7, V8, V9; 0, 1, 2, 3, 4, 5, 6, 7, 8, 9);
     };
 }
+
+#[cfg(all(test, feature = "trace_diff"))]
+// The generated `static_view` function isn't used.
+#[allow(dead_code)]
+mod tests {
+    use std::any::{type_name, Any};
+    use std::fmt::{Debug, Write};
+    use std::sync::{Arc, Mutex};
+
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id as SpanId, Record};
+    use tracing::{Event, Metadata, Subscriber};
+
+    use crate::test_support::{ChangeFlags, Cx, View, ViewMarker, ViewSequence};
+    use crate::{Id, MessageResult, VecSplice};
+
+    crate::generate_memoize_view! {Memoize, MemoizeState, View, ViewMarker, Cx, ChangeFlags, static_view, memoize;}
+
+    /// Records the diff events, as `message field=value ...`.
+    #[derive(Clone, Default)]
+    struct DiffRecorder(Arc<Mutex<Vec<String>>>);
+
+    impl Subscriber for DiffRecorder {
+        fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+            metadata.target() == "xilem_core::diff"
+        }
+
+        fn new_span(&self, _span: &Attributes<'_>) -> SpanId {
+            SpanId::from_u64(1)
+        }
+
+        fn record(&self, _span: &SpanId, _values: &Record<'_>) {}
+
+        fn record_follows_from(&self, _span: &SpanId, _follows: &SpanId) {}
+
+        fn event(&self, event: &Event<'_>) {
+            let mut fields = EventFields::default();
+            event.record(&mut fields);
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("{}{}", fields.message, fields.fields));
+        }
+
+        fn enter(&self, _span: &SpanId) {}
+
+        fn exit(&self, _span: &SpanId) {}
+    }
+
+    #[derive(Default)]
+    struct EventFields {
+        message: String,
+        fields: String,
+    }
+
+    impl Visit for EventFields {
+        fn record_str(&mut self, field: &Field, value: &str) {
+            write!(self.fields, " {}={value}", field.name()).unwrap();
+        }
+
+        fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+            if field.name() == "message" {
+                self.message = format!("{value:?}");
+            } else {
+                write!(self.fields, " {}={value:?}", field.name()).unwrap();
+            }
+        }
+    }
+
+    struct Number(u32);
+
+    impl ViewMarker for Number {}
+
+    impl View<()> for Number {
+        type State = ();
+        type Element = u32;
+
+        fn build(&self, _cx: &mut Cx) -> (Id, (), u32) {
+            (Id::next(), (), self.0)
+        }
+
+        fn rebuild(
+            &self,
+            _cx: &mut Cx,
+            prev: &Self,
+            _id: &mut Id,
+            _state: &mut (),
+            element: &mut u32,
+        ) -> ChangeFlags {
+            *element = self.0;
+            ChangeFlags(self.0 != prev.0)
+        }
+
+        fn message(
+            &self,
+            _id_path: &[Id],
+            _state: &mut (),
+            message: Box<dyn Any>,
+            _app_state: &mut (),
+        ) -> MessageResult<()> {
+            MessageResult::Stale(message)
+        }
+    }
+
+    type Memoized = Memoize<u32, fn(&u32) -> Number>;
+
+    fn numbers(values: &[u32]) -> Vec<Memoized> {
+        values
+            .iter()
+            .map(|&value| memoize(value, (|value: &u32| Number(*value)) as fn(&u32) -> Number))
+            .collect()
+    }
+
+    #[test]
+    fn rebuild_diff_is_traced() {
+        let recorder = DiffRecorder::default();
+        let events = recorder.0.clone();
+        let mut cx = Cx::new();
+        let (mut pods, mut scratch) = (vec![], vec![]);
+        let view = numbers(&[1, 2, 3]);
+        let new_view = numbers(&[1, 5]);
+        tracing::subscriber::with_default(recorder, || {
+            let mut state = view.build(&mut cx, &mut VecSplice::new(&mut pods, &mut scratch));
+            let built = events.lock().unwrap().split_off(0);
+            assert_eq!(
+                built,
+                vec![format!("build view={}", type_name::<Memoized>()); 3]
+            );
+
+            new_view.rebuild(
+                &mut cx,
+                &view,
+                &mut state,
+                &mut VecSplice::new(&mut pods, &mut scratch),
+            );
+        });
+        assert_eq!(
+            *events.lock().unwrap(),
+            [
+                format!("rebuild view={}", type_name::<Memoized>()),
+                format!("memoized view={}", type_name::<Number>()),
+                format!("rebuild view={}", type_name::<Memoized>()),
+                "remove elements=1".into(),
+            ]
+        );
+    }
+}
+
+#[cfg(test)]
+mod path_tests {
+    use crate::test_support::{Cx, PathProbe, ViewSequence};
+    use crate::{Id, VecSplice};
+
+    /// Build `views[0]` under `parent`, then rebuild it into each of the next views,
+    /// checking that the probes are rebuilt at the path they were built at.
+    fn build_and_rebuild<V: ViewSequence<()>>(views: &[V]) {
+        let mut cx = Cx::new();
+        let (mut pods, mut scratch) = (vec![], vec![]);
+        let parent = Id::next();
+        let mut state = cx.with_id(parent, |cx| {
+            views[0].build(cx, &mut VecSplice::new(&mut pods, &mut scratch))
+        });
+        for pair in views.windows(2) {
+            cx.with_id(parent, |cx| {
+                pair[1].rebuild(
+                    cx,
+                    &pair[0],
+                    &mut state,
+                    &mut VecSplice::new(&mut pods, &mut scratch),
+                )
+            });
+        }
+    }
+
+    #[test]
+    fn option_rebuilds_at_build_path() {
+        build_and_rebuild(&[
+            Some(PathProbe),
+            Some(PathProbe),
+            None,
+            Some(PathProbe),
+            Some(PathProbe),
+        ]);
+    }
+
+    #[test]
+    fn vec_rebuilds_at_build_path() {
+        let probes = |n| (0..n).map(|_| PathProbe).collect::<Vec<_>>();
+        build_and_rebuild(&[probes(3), probes(3), probes(1), probes(4), probes(4)]);
+    }
+
+    #[test]
+    fn nested_sequences_rebuild_at_build_path() {
+        let probes = |n| {
+            (0..n)
+                .map(|_| Some((PathProbe, PathProbe)))
+                .collect::<Vec<_>>()
+        };
+        build_and_rebuild(&[probes(2), probes(2), probes(3), probes(3)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "different id path")]
+    fn probe_detects_moved_path() {
+        let mut cx = Cx::new();
+        let (mut pods, mut scratch) = (vec![], vec![]);
+        let mut state = cx.with_id(Id::next(), |cx| {
+            PathProbe.build(cx, &mut VecSplice::new(&mut pods, &mut scratch))
+        });
+        cx.with_id(Id::next(), |cx| {
+            PathProbe.rebuild(
+                cx,
+                &PathProbe,
+                &mut state,
+                &mut VecSplice::new(&mut pods, &mut scratch),
+            )
+        });
+    }
+}
@@ -0,0 +1,130 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+use std::marker::PhantomData;
+
+/// Two view sequences over the two halves of the app state, rendered one after the other.
+///
+/// Created with [`split_state`]. The view sequence implementation is generated
+/// by [`generate_viewsequence_trait`](crate::generate_viewsequence_trait).
+pub struct SplitState<T, L, R, VL, VR, F> {
+    split: F,
+    left: VL,
+    right: VR,
+    phantom: PhantomData<fn(T) -> (L, R)>,
+}
+
+/// Run `left` over the `L` half of the app state, and `right` over its `R` half, as
+/// given by `split`.
+///
+/// The elements of both sequences are added to the parent view, e.g. a row, the ones of
+/// `left` first. Each half is only reachable from its own sequence: messages are routed
+/// to the sequence they are addressed to, with the half of the app state it was given.
+/// Unlike two separate `AdaptState` views, this borrows both halves at once, so `split`
+/// guarantees they are disjoint.
+///
+/// ```ignore
+/// split_state(
+///     |state: &mut (Document, Document)| (&mut state.0, &mut state.1),
+///     editor(&state.0),
+///     editor(&state.1),
+/// )
+/// ```
+pub fn split_state<T, L, R, VL, VR, F>(
+    split: F,
+    left: VL,
+    right: VR,
+) -> SplitState<T, L, R, VL, VR, F>
+where
+    F: Fn(&mut T) -> (&mut L, &mut R),
+{
+    SplitState {
+        split,
+        left,
+        right,
+        phantom: PhantomData,
+    }
+}
+
+impl<T, L, R, VL, VR, F> SplitState<T, L, R, VL, VR, F>
+where
+    F: Fn(&mut T) -> (&mut L, &mut R),
+{
+    /// The sequence running over the left half of the app state.
+    pub fn left(&self) -> &VL {
+        &self.left
+    }
+
+    /// The sequence running over the right half of the app state.
+    pub fn right(&self) -> &VR {
+        &self.right
+    }
+
+    /// Split `app_state` into its halves.
+    pub fn split<'a>(&self, app_state: &'a mut T) -> (&'a mut L, &'a mut R) {
+        (self.split)(app_state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{elements, Button, ChangeFlags, Cx, ViewSequence};
+    use crate::{Id, MessageResult, VecSplice};
+
+    type Clicks = Vec<&'static str>;
+
+    type Editors = SplitState<
+        (Clicks, Clicks),
+        Clicks,
+        Clicks,
+        Button,
+        (Button, Button),
+        fn(&mut (Clicks, Clicks)) -> (&mut Clicks, &mut Clicks),
+    >;
+
+    fn editors(left: &'static str, right: (&'static str, &'static str)) -> Editors {
+        split_state(
+            |state| (&mut state.0, &mut state.1),
+            Button(left),
+            (Button(right.0), Button(right.1)),
+        )
+    }
+
+    #[test]
+    fn halves_are_updated_independently() {
+        let mut cx = Cx::new();
+        let (mut pods, mut scratch) = (vec![], vec![]);
+        let view = editors("a", ("b", "c"));
+        let mut state = view.build(&mut cx, &mut VecSplice::new(&mut pods, &mut scratch));
+        assert_eq!(elements::<String>(&pods), ["a", "b", "c"]);
+        assert_eq!(view.count(&state), 3);
+
+        let mut app_state = (vec![], vec![]);
+        let left_id = state.0 .1;
+        let right_id = state.1 .1 .1;
+        view.message(&[left_id], &mut state, Box::new(()), &mut app_state);
+        assert_eq!(app_state, (vec!["a"], vec![]));
+        view.message(&[right_id], &mut state, Box::new(()), &mut app_state);
+        view.message(&[right_id], &mut state, Box::new(()), &mut app_state);
+        assert_eq!(app_state, (vec!["a"], vec!["c", "c"]));
+
+        // Messages addressed to neither half leave both untouched.
+        let result = view.message(&[Id::next()], &mut state, Box::new(()), &mut app_state);
+        assert!(matches!(result, MessageResult::Stale(_)));
+        assert_eq!(app_state, (vec!["a"], vec!["c", "c"]));
+
+        // A rebuild only changes the elements of the half whose views changed.
+        let new_view = editors("a", ("b", "d"));
+        let changed = new_view.rebuild(
+            &mut cx,
+            &view,
+            &mut state,
+            &mut VecSplice::new(&mut pods, &mut scratch),
+        );
+        assert_eq!(changed, ChangeFlags(true));
+        assert_eq!(elements::<String>(&pods), ["a", "b", "d"]);
+        new_view.message(&[right_id], &mut state, Box::new(()), &mut app_state);
+        assert_eq!(app_state, (vec!["a"], vec!["c", "c", "d"]));
+    }
+}
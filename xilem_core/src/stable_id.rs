@@ -0,0 +1,173 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use crate::{Id, IdPath};
+
+/// The current location of each stable id in the view tree.
+///
+/// Views created with a `provide_id` function register their stable [`Id`] here when they
+/// are built or rebuilt, and keep the returned [`StableIdRegistration`] in their state, so
+/// that the id is unregistered when they are torn down. The id paths of messages which
+/// were created before the tree was restructured can then be [resolved](Self::resolve) to
+/// the current location of their target, instead of being delivered to whichever view now
+/// has their old ids.
+///
+/// This is a handle: clones share the same registrations.
+#[derive(Clone, Debug, Default)]
+pub struct StableIds(Arc<Mutex<Registry>>);
+
+#[derive(Debug, Default)]
+struct Registry {
+    /// The location of each stable id, with the number of the registration which put it there.
+    paths: HashMap<Id, (IdPath, u64)>,
+    next_registration: u64,
+}
+
+/// Keeps a stable id registered at the location it was registered at, until it's dropped.
+///
+/// Returned by [`StableIds::register`]. When the same stable id is registered again, e.g.
+/// at its new location after the tree was restructured, dropping the previous registration
+/// doesn't unregister it.
+#[must_use = "the stable id is unregistered when the registration is dropped"]
+#[derive(Debug)]
+pub struct StableIdRegistration {
+    stable_ids: StableIds,
+    id: Id,
+    registration: u64,
+}
+
+impl StableIds {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record that the stable id at the end of `id_path` is currently found at `id_path`.
+    ///
+    /// The id stays registered there until the returned registration is dropped, or the
+    /// id is registered again.
+    ///
+    /// # Panics
+    ///
+    /// If `id_path` doesn't end with a stable id.
+    pub fn register(&self, id_path: &[Id]) -> StableIdRegistration {
+        let id = *id_path.last().expect("empty id path");
+        assert!(id.is_stable(), "{id:?} is not a stable id");
+        let mut registry = self.0.lock().unwrap();
+        let registration = registry.next_registration;
+        registry.next_registration += 1;
+        registry.paths.insert(id, (id_path.to_vec(), registration));
+        StableIdRegistration {
+            stable_ids: self.clone(),
+            id,
+            registration,
+        }
+    }
+
+    /// Whether the stable `id` is currently registered.
+    pub fn contains(&self, id: Id) -> bool {
+        self.0.lock().unwrap().paths.contains_key(&id)
+    }
+
+    /// Rewrite `id_path` so that it goes through the current location of the deepest stable
+    /// id it contains.
+    ///
+    /// Paths without a registered stable id are returned unchanged.
+    pub fn resolve(&self, id_path: &[Id]) -> IdPath {
+        let registry = self.0.lock().unwrap();
+        let stable = id_path
+            .iter()
+            .enumerate()
+            .rev()
+            .find_map(|(idx, id)| Some((idx, &registry.paths.get(id)?.0)));
+        match stable {
+            Some((idx, current)) => {
+                let mut resolved = current.clone();
+                resolved.extend_from_slice(&id_path[idx + 1..]);
+                resolved
+            }
+            None => id_path.to_vec(),
+        }
+    }
+}
+
+impl Drop for StableIdRegistration {
+    fn drop(&mut self) {
+        let mut registry = self.stable_ids.0.lock().unwrap();
+        let current = registry
+            .paths
+            .get(&self.id)
+            .map(|(_, registration)| *registration);
+        if current == Some(self.registration) {
+            registry.paths.remove(&self.id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_to_current_location() {
+        let root = Id::next();
+        let stable = Id::stable(1);
+        let target = Id::next();
+        let stable_ids = StableIds::new();
+        let old_location = stable_ids.register(&[root, Id::next(), stable]);
+        let message_path = [root, Id::next(), stable, target];
+
+        // The subtree was moved into a new container, so the ids above it changed.
+        let (container, wrapper) = (Id::next(), Id::next());
+        let _new_location = stable_ids.register(&[root, container, wrapper, stable]);
+        // Tearing down the view at the old location doesn't unregister the new one.
+        drop(old_location);
+
+        assert_eq!(
+            stable_ids.resolve(&message_path),
+            vec![root, container, wrapper, stable, target]
+        );
+    }
+
+    #[test]
+    fn deepest_stable_id_wins() {
+        let (outer, inner) = (Id::stable(1), Id::stable(2));
+        let target = Id::next();
+        let stable_ids = StableIds::new();
+        let _outer = stable_ids.register(&[outer]);
+        let _inner = stable_ids.register(&[outer, Id::next(), inner]);
+        let message_path = [outer, Id::next(), inner, target];
+
+        let inner_path = vec![Id::next(), outer, Id::next(), Id::next(), inner];
+        let _inner = stable_ids.register(&inner_path);
+
+        let mut expected = inner_path;
+        expected.push(target);
+        assert_eq!(stable_ids.resolve(&message_path), expected);
+    }
+
+    #[test]
+    fn paths_without_stable_ids_are_unchanged() {
+        let message_path = [Id::next(), Id::next()];
+        let stable_ids = StableIds::new();
+        let _registration = stable_ids.register(&[Id::next(), Id::stable(1)]);
+
+        assert_eq!(stable_ids.resolve(&message_path), message_path.to_vec());
+    }
+
+    #[test]
+    fn dropped_registration_unregisters() {
+        let stable = Id::stable(1);
+        let target = Id::next();
+        let stable_ids = StableIds::new();
+        let registration = stable_ids.register(&[Id::next(), stable]);
+        assert!(stable_ids.contains(stable));
+
+        drop(registration);
+        assert!(!stable_ids.contains(stable));
+        let message_path = [Id::next(), stable, target];
+        assert_eq!(stable_ids.resolve(&message_path), message_path.to_vec());
+    }
+}
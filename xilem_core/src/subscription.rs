@@ -0,0 +1,243 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+use std::future::{poll_fn, Future};
+use std::pin::pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Poll, Waker};
+
+use futures_core::Stream;
+
+#[derive(Default)]
+struct SubscriptionState {
+    cancelled: bool,
+    /// The waker of the task polling the stream, to stop it when the subscription is dropped.
+    waker: Option<Waker>,
+}
+
+/// Keeps a view subscribed to a stream of events from outside the view tree.
+///
+/// The items of the stream are delivered by a task running on the framework's runtime.
+/// Dropping the subscription stops that task, and drops the stream, even if it is
+/// waiting for an item. Views store it in their state, so that they unsubscribe when
+/// they are removed.
+pub struct Subscription {
+    state: Arc<Mutex<SubscriptionState>>,
+}
+
+impl Subscription {
+    /// Subscribe to `stream`, passing each of its items to `deliver`.
+    ///
+    /// Returns the subscription, and the task which polls the stream. The task must be
+    /// spawned on the framework's runtime. It completes once the stream ends or the
+    /// subscription is dropped.
+    pub fn new<S>(
+        stream: S,
+        mut deliver: impl FnMut(S::Item) + 'static,
+    ) -> (Self, impl Future<Output = ()> + 'static)
+    where
+        S: Stream + 'static,
+    {
+        let state = Arc::new(Mutex::new(SubscriptionState::default()));
+        let task_state = state.clone();
+        let task = async move {
+            let mut stream = pin!(stream);
+            loop {
+                let item = poll_fn(|cx| {
+                    {
+                        let mut state = task_state.lock().unwrap();
+                        if state.cancelled {
+                            return Poll::Ready(None);
+                        }
+                        state.waker = Some(cx.waker().clone());
+                    }
+                    stream.as_mut().poll_next(cx)
+                })
+                .await;
+                match item {
+                    Some(item) => deliver(item),
+                    None => break,
+                }
+            }
+        };
+        (Subscription { state }, task)
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        let waker = {
+            let mut state = self.state.lock().unwrap();
+            state.cancelled = true;
+            state.waker.take()
+        };
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::any::Any;
+    use std::cell::{Cell, RefCell};
+    use std::collections::VecDeque;
+    use std::pin::Pin;
+    use std::rc::Rc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::task::{Context, Wake};
+
+    use super::*;
+    use crate::test_support::{run_tasks, spawn, ChangeFlags, Cx, View, ViewMarker};
+    use crate::{Id, MessageResult};
+
+    #[derive(Default)]
+    struct CountWakes(AtomicUsize);
+
+    impl Wake for CountWakes {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// A stream of the items pushed to it by the test.
+    #[derive(Clone, Default)]
+    struct Feed {
+        items: Rc<RefCell<VecDeque<u32>>>,
+        ended: Rc<Cell<bool>>,
+        dropped: Rc<Cell<bool>>,
+    }
+
+    struct FeedStream(Feed);
+
+    impl Stream for FeedStream {
+        type Item = u32;
+
+        fn poll_next(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<u32>> {
+            match self.0.items.borrow_mut().pop_front() {
+                Some(item) => Poll::Ready(Some(item)),
+                None if self.0.ended.get() => Poll::Ready(None),
+                None => Poll::Pending,
+            }
+        }
+    }
+
+    impl Drop for FeedStream {
+        fn drop(&mut self) {
+            self.0.dropped.set(true);
+        }
+    }
+
+    crate::generate_subscribe_view! {View, Cx, ChangeFlags, Thunk, spawn;}
+
+    struct Number(u32);
+
+    impl ViewMarker for Number {}
+
+    impl View<Vec<u32>, u32> for Number {
+        type State = ();
+        type Element = u32;
+
+        fn build(&self, _cx: &mut Cx) -> (Id, (), u32) {
+            (Id::next(), (), self.0)
+        }
+
+        fn rebuild(
+            &self,
+            _cx: &mut Cx,
+            _prev: &Self,
+            _id: &mut Id,
+            _: &mut (),
+            _: &mut u32,
+        ) -> ChangeFlags {
+            ChangeFlags::empty()
+        }
+
+        fn message(
+            &self,
+            _id_path: &[Id],
+            _state: &mut (),
+            message: Box<dyn Any>,
+            _app_state: &mut Vec<u32>,
+        ) -> MessageResult<u32> {
+            MessageResult::Stale(message)
+        }
+    }
+
+    #[test]
+    fn items_reach_the_handler() {
+        let mut cx = Cx::new();
+        let feed = Feed::default();
+        let source = feed.clone();
+        let view = Subscribe::new(
+            Number(0),
+            move || FeedStream(source.clone()),
+            |received: &mut Vec<u32>, item: u32| {
+                received.push(item);
+                item * 10
+            },
+        );
+        let (_, mut state, element) = view.build(&mut cx);
+        assert_eq!(element, 0);
+        assert_eq!(run_tasks(), 1);
+
+        feed.items.borrow_mut().extend([1, 2, 3]);
+        assert_eq!(run_tasks(), 1);
+        let mut received = vec![];
+        let actions: Vec<u32> = cx
+            .take_messages()
+            .into_iter()
+            .map(
+                |message| match view.message(&[], &mut state, message, &mut received) {
+                    MessageResult::Action(action) => action,
+                    _ => panic!("item wasn't handled"),
+                },
+            )
+            .collect();
+        assert_eq!(received, [1, 2, 3]);
+        assert_eq!(actions, [10, 20, 30]);
+
+        // The task stops with the stream.
+        feed.ended.set(true);
+        assert_eq!(run_tasks(), 0);
+    }
+
+    #[test]
+    fn dropping_the_state_unsubscribes() {
+        let mut cx = Cx::new();
+        let feed = Feed::default();
+        let source = feed.clone();
+        let view = Subscribe::new(
+            Number(0),
+            move || FeedStream(source.clone()),
+            |_: &mut Vec<u32>, item: u32| item,
+        );
+        let (_, state, _) = view.build(&mut cx);
+        assert_eq!(run_tasks(), 1);
+
+        drop(state);
+        feed.items.borrow_mut().push_back(1);
+        assert_eq!(run_tasks(), 0);
+        assert!(feed.dropped.get());
+        assert!(cx.messages.borrow().is_empty());
+    }
+
+    #[test]
+    fn dropping_wakes_the_task() {
+        let wakes = Arc::new(CountWakes::default());
+        let waker = Waker::from(wakes.clone());
+        let (subscription, task) = Subscription::new(FeedStream(Feed::default()), |_| {});
+        let mut task = pin!(task);
+        assert!(task
+            .as_mut()
+            .poll(&mut Context::from_waker(&waker))
+            .is_pending());
+
+        drop(subscription);
+        assert_eq!(wakes.0.load(Ordering::Relaxed), 1);
+        assert!(task
+            .as_mut()
+            .poll(&mut Context::from_waker(&waker))
+            .is_ready());
+    }
+}
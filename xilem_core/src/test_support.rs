@@ -0,0 +1,279 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! A mock xilem context, and a mock executor and clock, shared by the tests of this crate.
+//!
+//! The views of each module are generated in its own tests, from the view traits and
+//! types defined here.
+
+// The view traits are generated in this crate, so the lints which are silenced in the
+// expansions of external macros apply to them.
+#![allow(unused)]
+
+use std::any::Any;
+use std::cell::{Cell, RefCell};
+use std::future::{poll_fn, Future};
+use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+use std::time::Duration;
+
+use crate::{Id, IdPath, MessageResult, RebuildCancellation, StableIdRegistration, StableIds};
+
+pub struct Cx {
+    pub id_path: IdPath,
+    pub stable_ids: StableIds,
+    pub cancellation: RebuildCancellation,
+    /// The messages pushed by the thunks of this context.
+    pub messages: Rc<RefCell<Vec<Box<dyn Any>>>>,
+}
+
+impl Cx {
+    pub fn new() -> Self {
+        Cx {
+            id_path: IdPath::default(),
+            stable_ids: StableIds::new(),
+            cancellation: RebuildCancellation::new(),
+            messages: Rc::default(),
+        }
+    }
+
+    pub fn with_id<T>(&mut self, id: Id, f: impl FnOnce(&mut Cx) -> T) -> T {
+        self.id_path.push(id);
+        let result = f(self);
+        self.id_path.pop();
+        result
+    }
+
+    pub fn with_new_id<T>(&mut self, f: impl FnOnce(&mut Cx) -> T) -> (Id, T) {
+        let id = Id::next();
+        (id, self.with_id(id, f))
+    }
+
+    pub fn with_new_pod<S, E: Node, F: FnOnce(&mut Cx) -> (Id, S, E)>(
+        &mut self,
+        f: F,
+    ) -> (Id, S, Pod) {
+        let (id, state, element) = f(self);
+        (id, state, Pod(Box::new(element)))
+    }
+
+    pub fn with_pod<T, E: Node, F: FnOnce(&mut E, &mut Cx) -> T>(
+        &mut self,
+        pod: &mut Pod,
+        f: F,
+    ) -> T {
+        f(pod.0.downcast_mut().unwrap(), self)
+    }
+
+    pub fn is_rebuild_cancelled(&self) -> bool {
+        self.cancellation.is_cancelled()
+    }
+
+    pub fn register_stable_id(&mut self, id: Id) -> StableIdRegistration {
+        self.id_path.push(id);
+        let registration = self.stable_ids.register(&self.id_path);
+        self.id_path.pop();
+        registration
+    }
+
+    pub fn message_thunk(&self) -> Thunk {
+        Thunk(self.messages.clone())
+    }
+
+    /// Take the messages pushed so far.
+    pub fn take_messages(&self) -> Vec<Box<dyn Any>> {
+        self.messages.take()
+    }
+}
+
+#[derive(Clone)]
+pub struct Thunk(Rc<RefCell<Vec<Box<dyn Any>>>>);
+
+impl Thunk {
+    pub fn push_message(&self, message: impl Any + 'static) {
+        self.0.borrow_mut().push(Box::new(message));
+    }
+}
+
+pub trait Node: 'static {}
+impl Node for u32 {}
+impl Node for String {}
+
+pub struct Pod(pub Box<dyn Any>);
+
+impl Pod {
+    pub fn mark(&mut self, flags: ChangeFlags) -> ChangeFlags {
+        flags
+    }
+}
+
+/// Whether anything changed.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ChangeFlags(pub bool);
+
+impl ChangeFlags {
+    pub fn tree_structure() -> Self {
+        ChangeFlags(true)
+    }
+
+    pub fn empty() -> Self {
+        ChangeFlags(false)
+    }
+}
+
+impl std::ops::BitOrAssign for ChangeFlags {
+    fn bitor_assign(&mut self, other: Self) {
+        self.0 |= other.0;
+    }
+}
+
+crate::generate_view_trait! {View, Node, Cx, ChangeFlags;}
+crate::generate_viewsequence_trait! {ViewSequence, View, ViewMarker, ElementsSplice, Node, Cx, ChangeFlags, Pod;}
+
+/// The elements of a view sequence, downcast to `E`.
+pub fn elements<E: Node + Clone>(pods: &[Pod]) -> Vec<E> {
+    pods.iter()
+        .map(|pod| pod.0.downcast_ref::<E>().unwrap().clone())
+        .collect()
+}
+
+/// A button adding its text to the state it is given when clicked.
+pub struct Button(pub &'static str);
+
+impl ViewMarker for Button {}
+
+impl View<Vec<&'static str>> for Button {
+    type State = ();
+    type Element = String;
+
+    fn build(&self, _cx: &mut Cx) -> (Id, (), String) {
+        (Id::next(), (), self.0.into())
+    }
+
+    fn rebuild(
+        &self,
+        _cx: &mut Cx,
+        prev: &Self,
+        _id: &mut Id,
+        _state: &mut (),
+        element: &mut String,
+    ) -> ChangeFlags {
+        *element = self.0.into();
+        ChangeFlags(self.0 != prev.0)
+    }
+
+    fn message(
+        &self,
+        _id_path: &[Id],
+        _state: &mut (),
+        _message: Box<dyn Any>,
+        app_state: &mut Vec<&'static str>,
+    ) -> MessageResult<()> {
+        app_state.push(self.0);
+        MessageResult::Nop
+    }
+}
+
+/// A view checking that it's rebuilt at the id path it was built at.
+///
+/// Its state is the path it was built at, including its own id.
+pub struct PathProbe;
+
+impl ViewMarker for PathProbe {}
+
+impl View<()> for PathProbe {
+    type State = IdPath;
+    type Element = u32;
+
+    fn build(&self, cx: &mut Cx) -> (Id, IdPath, u32) {
+        let (id, path) = cx.with_new_id(|cx| cx.id_path.clone());
+        (id, path, 0)
+    }
+
+    fn rebuild(
+        &self,
+        cx: &mut Cx,
+        _prev: &Self,
+        id: &mut Id,
+        state: &mut IdPath,
+        _element: &mut u32,
+    ) -> ChangeFlags {
+        cx.with_id(*id, |cx| assert_built_path(cx, state));
+        ChangeFlags::empty()
+    }
+
+    fn message(
+        &self,
+        _id_path: &[Id],
+        _state: &mut IdPath,
+        _message: Box<dyn Any>,
+        _app_state: &mut (),
+    ) -> MessageResult<()> {
+        MessageResult::Nop
+    }
+}
+
+/// Assert that `cx` is at `built_path`, the path a view was built at.
+pub fn assert_built_path(cx: &Cx, built_path: &[Id]) {
+    assert_eq!(
+        cx.id_path, built_path,
+        "view visited at a different id path than the one it was built at"
+    );
+}
+
+type Task = Pin<Box<dyn Future<Output = ()>>>;
+
+thread_local! {
+    static TASKS: RefCell<Vec<Task>> = RefCell::default();
+    /// The time of the mock clock.
+    static NOW: Cell<Duration> = const { Cell::new(Duration::ZERO) };
+}
+
+/// Spawn a task on the mock executor, it's polled by [`run_tasks`].
+pub fn spawn(task: impl Future<Output = ()> + 'static) {
+    TASKS.with_borrow_mut(|tasks| tasks.push(Box::pin(task)));
+}
+
+struct NoopWake;
+
+impl Wake for NoopWake {
+    fn wake(self: Arc<Self>) {}
+}
+
+/// A waker doing nothing, as the tests poll the tasks explicitly.
+pub fn noop_waker() -> Waker {
+    Waker::from(Arc::new(NoopWake))
+}
+
+/// Poll the spawned tasks until they're all waiting, and return how many are left.
+pub fn run_tasks() -> usize {
+    let waker = noop_waker();
+    let mut cx = Context::from_waker(&waker);
+    let mut tasks = TASKS.take();
+    tasks.retain_mut(|task| task.as_mut().poll(&mut cx).is_pending());
+    // Tasks spawned while polling the others are kept too.
+    TASKS.with_borrow_mut(|spawned| tasks.append(spawned));
+    let left = tasks.len();
+    TASKS.set(tasks);
+    left
+}
+
+/// Sleep on the mock clock.
+pub fn sleep(duration: Duration) -> impl Future<Output = ()> {
+    let deadline = NOW.get() + duration;
+    poll_fn(move |_| {
+        if NOW.get() >= deadline {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    })
+}
+
+/// Move the mock clock forward, and poll the spawned tasks.
+pub fn advance(duration: Duration) {
+    NOW.set(NOW.get() + duration);
+    run_tasks();
+}
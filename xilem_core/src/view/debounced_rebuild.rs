@@ -0,0 +1,256 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+/// Create the `DebouncedRebuild` view for a particular xilem context.
+///
+/// Arguments are the same as for
+/// [`generate_map_message_async_view`](crate::generate_map_message_async_view), plus
+///
+///  - `$sleep` - A function taking a [`Duration`](std::time::Duration), and returning a
+///    `Future<Output = ()> + 'static` which completes once that time has passed.
+#[macro_export]
+macro_rules! generate_debounced_rebuild_view {
+    ($viewtrait:ident, $cx:ty, $changeflags:ty, $thunk:ty, $spawn:path, $sleep:path; $($ss:tt)*) => {
+        /// A view that coalesces the rebuilds of its child, for a child which is expensive
+        /// to rebuild, like a chart derived from data changing on every keystroke.
+        ///
+        /// Rebuilding this view only starts a timer: the child is rebuilt once no other
+        /// rebuild happened for `duration`, with the latest view. In the meantime, the
+        /// element of the last rendered view is kept, and messages are handled by that view.
+        /// When the child itself requests a rebuild, e.g. after a click, it's rebuilt right
+        /// away, as the user expects to see the result.
+        pub struct DebouncedRebuild<V> {
+            duration: std::time::Duration,
+            child: V,
+        }
+
+        pub struct DebouncedRebuildState<V, S> {
+            child_id: $crate::Id,
+            child_state: S,
+            /// The view the element was last built or rebuilt with.
+            rendered: V,
+            thunk: $thunk,
+            /// Bumped by every rebuild, so that only the timer of the last one settles.
+            generation: u64,
+            /// Whether the next rebuild should rebuild the child.
+            settled: bool,
+        }
+
+        /// The message sent by the timer of a rebuild, with its generation.
+        struct DebounceElapsed(u64);
+
+        impl<V> DebouncedRebuild<V> {
+            pub fn new(duration: std::time::Duration, child: V) -> Self {
+                DebouncedRebuild { duration, child }
+            }
+        }
+
+        impl<V> ViewMarker for DebouncedRebuild<V> {}
+
+        impl<T, A, V> $viewtrait<T, A> for DebouncedRebuild<V>
+        where
+            V: $viewtrait<T, A> + Clone $( $ss )*,
+        {
+            type State = DebouncedRebuildState<V, V::State>;
+
+            type Element = V::Element;
+
+            fn build(&self, cx: &mut $cx) -> ($crate::Id, Self::State, Self::Element) {
+                let (id, (thunk, (child_id, child_state, element))) =
+                    cx.with_new_id(|cx| (cx.message_thunk(), self.child.build(cx)));
+                let state = DebouncedRebuildState {
+                    child_id,
+                    child_state,
+                    rendered: self.child.clone(),
+                    thunk,
+                    generation: 0,
+                    settled: false,
+                };
+                (id, state, element)
+            }
+
+            fn rebuild(
+                &self,
+                cx: &mut $cx,
+                _prev: &Self,
+                id: &mut $crate::Id,
+                state: &mut Self::State,
+                element: &mut Self::Element,
+            ) -> $changeflags {
+                if std::mem::take(&mut state.settled) {
+                    let changed = cx.with_id(*id, |cx| {
+                        self.child.rebuild(
+                            cx,
+                            &state.rendered,
+                            &mut state.child_id,
+                            &mut state.child_state,
+                            element,
+                        )
+                    });
+                    state.rendered = self.child.clone();
+                    changed
+                } else {
+                    state.generation += 1;
+                    let elapsed = DebounceElapsed(state.generation);
+                    let thunk = state.thunk.clone();
+                    let sleep = $sleep(self.duration);
+                    $spawn(async move {
+                        sleep.await;
+                        thunk.push_message(elapsed);
+                    });
+                    $crate::trace_diff!(view = std::any::type_name::<V>(), "debounced");
+                    <$changeflags>::empty()
+                }
+            }
+
+            fn message(
+                &self,
+                id_path: &[$crate::Id],
+                state: &mut Self::State,
+                message: Box<dyn std::any::Any>,
+                app_state: &mut T,
+            ) -> $crate::MessageResult<A> {
+                match id_path {
+                    // The timer of a rebuild
+                    [] => match message.downcast::<DebounceElapsed>() {
+                        Ok(elapsed) if elapsed.0 == state.generation => {
+                            state.settled = true;
+                            $crate::MessageResult::RequestRebuild
+                        }
+                        // Another rebuild happened since this timer was started.
+                        Ok(_) => $crate::MessageResult::Nop,
+                        Err(message) => $crate::MessageResult::Stale(message),
+                    },
+                    [first, rest_path @ ..] if *first == state.child_id => {
+                        let result = state.rendered.message(
+                            rest_path,
+                            &mut state.child_state,
+                            message,
+                            app_state,
+                        );
+                        if matches!(result, $crate::MessageResult::RequestRebuild) {
+                            // The pending timer, if any, is no longer needed.
+                            state.generation += 1;
+                            state.settled = true;
+                        }
+                        result
+                    }
+                    _ => $crate::MessageResult::Stale(message),
+                }
+            }
+        }
+
+        /// Rebuild `view` only once its rebuilds settle for `duration`, see [`DebouncedRebuild`].
+        pub fn debounced_rebuild<V>(duration: std::time::Duration, view: V) -> DebouncedRebuild<V> {
+            DebouncedRebuild::new(duration, view)
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use std::any::Any;
+    use std::cell::Cell;
+    use std::rc::Rc;
+    use std::time::Duration;
+
+    use crate::test_support::{advance, sleep, spawn, ChangeFlags, Cx, Thunk, View, ViewMarker};
+    use crate::{Id, MessageResult};
+
+    crate::generate_debounced_rebuild_view! {View, Cx, ChangeFlags, Thunk, spawn, sleep;}
+
+    /// A view displaying a number, which counts its rebuilds.
+    #[derive(Clone)]
+    struct Number {
+        value: u32,
+        rebuilds: Rc<Cell<u32>>,
+    }
+
+    impl View<(), ()> for Number {
+        type State = ();
+        type Element = u32;
+
+        fn build(&self, _cx: &mut Cx) -> (Id, (), u32) {
+            (Id::next(), (), self.value)
+        }
+
+        fn rebuild(
+            &self,
+            _cx: &mut Cx,
+            _prev: &Self,
+            _id: &mut Id,
+            _state: &mut (),
+            element: &mut u32,
+        ) -> ChangeFlags {
+            self.rebuilds.set(self.rebuilds.get() + 1);
+            *element = self.value;
+            ChangeFlags(true)
+        }
+
+        fn message(
+            &self,
+            _id_path: &[Id],
+            _state: &mut (),
+            message: Box<dyn Any>,
+            _app_state: &mut (),
+        ) -> MessageResult<()> {
+            MessageResult::Stale(message)
+        }
+    }
+
+    /// Route the messages of the timers to `view`, and return how many rebuilds it requested.
+    fn deliver<V: View<(), ()>>(cx: &Cx, view: &V, state: &mut V::State) -> usize {
+        cx.take_messages()
+            .into_iter()
+            .map(|message| view.message(&[], state, message, &mut ()))
+            .filter(|result| matches!(result, MessageResult::RequestRebuild))
+            .count()
+    }
+
+    #[test]
+    fn burst_of_changes_rebuilds_once() {
+        let mut cx = Cx::new();
+        let rebuilds = Rc::new(Cell::new(0));
+        let view = |value| {
+            debounced_rebuild(
+                Duration::from_millis(100),
+                Number {
+                    value,
+                    rebuilds: rebuilds.clone(),
+                },
+            )
+        };
+        let mut prev = view(0);
+        let (mut id, mut state, mut element) = prev.build(&mut cx);
+
+        // A change every 50ms keeps the old element.
+        for value in 1..=5 {
+            let next = view(value);
+            assert_eq!(
+                next.rebuild(&mut cx, &prev, &mut id, &mut state, &mut element),
+                ChangeFlags(false)
+            );
+            prev = next;
+            advance(Duration::from_millis(50));
+            assert_eq!(deliver(&cx, &prev, &mut state), 0);
+        }
+        assert_eq!(element, 0);
+        assert_eq!(rebuilds.get(), 0);
+
+        // Once the changes settle, a single rebuild is requested.
+        advance(Duration::from_millis(100));
+        assert_eq!(deliver(&cx, &prev, &mut state), 1);
+
+        let next = view(5);
+        assert_eq!(
+            next.rebuild(&mut cx, &prev, &mut id, &mut state, &mut element),
+            ChangeFlags(true)
+        );
+        assert_eq!(element, 5);
+        assert_eq!(rebuilds.get(), 1);
+
+        // No rebuild is pending anymore.
+        advance(Duration::from_millis(200));
+        assert_eq!(deliver(&cx, &next, &mut state), 0);
+    }
+}
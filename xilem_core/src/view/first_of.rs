@@ -0,0 +1,316 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+/// Create the `FirstOf` view and the `first_of` function for a particular xilem context.
+///
+/// Arguments are the same as for [`generate_adapt_view`](crate::generate_adapt_view).
+/// Additionally, `$cx` must have the `with_id` and `with_new_id` methods, and `$changeflags`
+/// must have a `tree_structure()` constructor, returned when the element is replaced.
+#[macro_export]
+macro_rules! generate_first_of_view {
+    ($viewtrait:ident, $cx:ty, $changeflags:ty; $($ss:tt)*) => {
+        /// A view showing the first of several options whose condition holds.
+        ///
+        /// See [`first_of`].
+        pub struct FirstOf<V, D> {
+            selected: FirstOfSelected<V, D>,
+        }
+
+        enum FirstOfSelected<V, D> {
+            Option(usize, V),
+            Default(D),
+        }
+
+        pub struct FirstOfState<S, DS> {
+            child_id: $crate::Id,
+            child_state: FirstOfSelected<S, DS>,
+        }
+
+        /// Show the view of the first option whose condition holds, or `default` if none do.
+        ///
+        /// This is a generalization of `OneOf2` to any number of options, e.g. to try a rich
+        /// renderer before falling back to a plain one. The options are given as
+        /// `(condition, view)` pairs, in order of preference.
+        ///
+        /// While the same option is selected, its view is rebuilt as usual. When another one
+        /// is selected, the previous view is torn down and the new one is built, so its
+        /// element replaces the previous one, and messages still addressed to the previous
+        /// view are stale.
+        pub fn first_of<V, D>(
+            options: impl IntoIterator<Item = (bool, V)>,
+            default: D,
+        ) -> FirstOf<V, D> {
+            let selected = options
+                .into_iter()
+                .enumerate()
+                .find(|(_, (condition, _))| *condition)
+                .map(|(index, (_, view))| FirstOfSelected::Option(index, view))
+                .unwrap_or(FirstOfSelected::Default(default));
+            FirstOf { selected }
+        }
+
+        impl<V, D> FirstOf<V, D> {
+            /// The index of the selected option, or `None` if `default` is shown.
+            pub fn selected_index(&self) -> Option<usize> {
+                match self.selected {
+                    FirstOfSelected::Option(index, _) => Some(index),
+                    FirstOfSelected::Default(_) => None,
+                }
+            }
+
+            fn build_selected<T, A>(
+                &self,
+                cx: &mut $cx,
+            ) -> ($crate::Id, FirstOfSelected<V::State, D::State>, V::Element)
+            where
+                V: $viewtrait<T, A>,
+                D: $viewtrait<T, A, Element = V::Element>,
+            {
+                match &self.selected {
+                    FirstOfSelected::Option(index, view) => {
+                        let (child_id, state, element) = view.build(cx);
+                        (child_id, FirstOfSelected::Option(*index, state), element)
+                    }
+                    FirstOfSelected::Default(view) => {
+                        let (child_id, state, element) = view.build(cx);
+                        (child_id, FirstOfSelected::Default(state), element)
+                    }
+                }
+            }
+        }
+
+        impl<T, A, V, D> $viewtrait<T, A> for FirstOf<V, D>
+        where
+            V: $viewtrait<T, A>,
+            D: $viewtrait<T, A, Element = V::Element>,
+        {
+            type State = FirstOfState<V::State, D::State>;
+
+            type Element = V::Element;
+
+            fn build(&self, cx: &mut $cx) -> ($crate::Id, Self::State, Self::Element) {
+                let (id, (child_id, child_state, element)) =
+                    cx.with_new_id(|cx| self.build_selected(cx));
+                let state = FirstOfState {
+                    child_id,
+                    child_state,
+                };
+                (id, state, element)
+            }
+
+            fn rebuild(
+                &self,
+                cx: &mut $cx,
+                prev: &Self,
+                id: &mut $crate::Id,
+                state: &mut Self::State,
+                element: &mut Self::Element,
+            ) -> $changeflags {
+                cx.with_id(*id, |cx| {
+                    match (&prev.selected, &self.selected, &mut state.child_state) {
+                        (
+                            FirstOfSelected::Option(prev_index, prev_view),
+                            FirstOfSelected::Option(index, view),
+                            FirstOfSelected::Option(_, child_state),
+                        ) if prev_index == index => {
+                            return view.rebuild(
+                                cx,
+                                prev_view,
+                                &mut state.child_id,
+                                child_state,
+                                element,
+                            );
+                        }
+                        (
+                            FirstOfSelected::Default(prev_view),
+                            FirstOfSelected::Default(view),
+                            FirstOfSelected::Default(child_state),
+                        ) => {
+                            return view.rebuild(
+                                cx,
+                                prev_view,
+                                &mut state.child_id,
+                                child_state,
+                                element,
+                            );
+                        }
+                        _ => {}
+                    }
+                    // Another option is selected: the previous view is dropped with its state.
+                    $crate::trace_diff!(view = std::any::type_name::<Self>(), "switch");
+                    let (child_id, child_state, new_element) = self.build_selected(cx);
+                    state.child_id = child_id;
+                    state.child_state = child_state;
+                    *element = new_element;
+                    <$changeflags>::tree_structure()
+                })
+            }
+
+            fn message(
+                &self,
+                id_path: &[$crate::Id],
+                state: &mut Self::State,
+                message: Box<dyn std::any::Any>,
+                app_state: &mut T,
+            ) -> $crate::MessageResult<A> {
+                match (id_path, &self.selected, &mut state.child_state) {
+                    (
+                        [first, rest_path @ ..],
+                        FirstOfSelected::Option(_, view),
+                        FirstOfSelected::Option(_, child_state),
+                    ) if *first == state.child_id => {
+                        view.message(rest_path, child_state, message, app_state)
+                    }
+                    (
+                        [first, rest_path @ ..],
+                        FirstOfSelected::Default(view),
+                        FirstOfSelected::Default(child_state),
+                    ) if *first == state.child_id => {
+                        view.message(rest_path, child_state, message, app_state)
+                    }
+                    _ => $crate::MessageResult::Stale(message),
+                }
+            }
+        }
+
+        impl<V, D> ViewMarker for FirstOf<V, D> {}
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use std::any::Any;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    use crate::test_support::{ChangeFlags, Cx, View, ViewMarker};
+    use crate::{Id, MessageResult};
+
+    crate::generate_first_of_view! {View, Cx, ChangeFlags;}
+
+    /// A view showing a text, counting how many of its states are alive.
+    struct Text {
+        text: &'static str,
+        alive: Rc<Cell<u32>>,
+    }
+
+    struct TextState(Rc<Cell<u32>>);
+
+    impl Drop for TextState {
+        fn drop(&mut self) {
+            self.0.set(self.0.get() - 1);
+        }
+    }
+
+    impl View<Vec<&'static str>> for Text {
+        type State = TextState;
+        type Element = String;
+
+        fn build(&self, _cx: &mut Cx) -> (Id, TextState, String) {
+            self.alive.set(self.alive.get() + 1);
+            (Id::next(), TextState(self.alive.clone()), self.text.into())
+        }
+
+        fn rebuild(
+            &self,
+            _cx: &mut Cx,
+            _prev: &Self,
+            _id: &mut Id,
+            _state: &mut TextState,
+            element: &mut String,
+        ) -> ChangeFlags {
+            *element = self.text.into();
+            ChangeFlags(false)
+        }
+
+        fn message(
+            &self,
+            _id_path: &[Id],
+            _state: &mut TextState,
+            _message: Box<dyn Any>,
+            app_state: &mut Vec<&'static str>,
+        ) -> MessageResult<()> {
+            app_state.push(self.text);
+            MessageResult::Nop
+        }
+    }
+
+    /// A `first_of` view trying a rich text, then a plain one, then an error message.
+    fn renderer(rich: bool, plain: bool, alive: &Rc<Cell<u32>>) -> FirstOf<Text, Text> {
+        let text = |text| Text {
+            text,
+            alive: alive.clone(),
+        };
+        first_of(
+            [(rich, text("rich")), (plain, text("plain"))],
+            text("unsupported"),
+        )
+    }
+
+    #[test]
+    fn first_matching_option_is_shown() {
+        let alive = Rc::new(Cell::new(0));
+        let mut cx = Cx::new();
+        let view = renderer(false, true, &alive);
+        assert_eq!(view.selected_index(), Some(1));
+        let (mut id, mut state, mut element) = view.build(&mut cx);
+        assert_eq!(element, "plain");
+
+        // Both options match: the first one wins, and replaces the plain one.
+        let rich = renderer(true, true, &alive);
+        assert_eq!(rich.selected_index(), Some(0));
+        let changed = rich.rebuild(&mut cx, &view, &mut id, &mut state, &mut element);
+        assert_eq!(changed, ChangeFlags(true));
+        assert_eq!(element, "rich");
+        assert_eq!(alive.get(), 1);
+
+        // The same option is rebuilt in place.
+        let still_rich = renderer(true, false, &alive);
+        let changed = still_rich.rebuild(&mut cx, &rich, &mut id, &mut state, &mut element);
+        assert_eq!(changed, ChangeFlags(false));
+        assert_eq!(element, "rich");
+        assert_eq!(alive.get(), 1);
+    }
+
+    #[test]
+    fn default_is_shown_when_nothing_matches() {
+        let alive = Rc::new(Cell::new(0));
+        let mut cx = Cx::new();
+        let view = renderer(true, false, &alive);
+        let (mut id, mut state, mut element) = view.build(&mut cx);
+
+        let unsupported = renderer(false, false, &alive);
+        assert_eq!(unsupported.selected_index(), None);
+        let changed = unsupported.rebuild(&mut cx, &view, &mut id, &mut state, &mut element);
+        assert_eq!(changed, ChangeFlags(true));
+        assert_eq!(element, "unsupported");
+        assert_eq!(alive.get(), 1);
+
+        let plain = renderer(false, true, &alive);
+        plain.rebuild(&mut cx, &unsupported, &mut id, &mut state, &mut element);
+        assert_eq!(element, "plain");
+        drop(state);
+        assert_eq!(alive.get(), 0);
+    }
+
+    #[test]
+    fn messages_to_a_replaced_view_are_stale() {
+        let alive = Rc::new(Cell::new(0));
+        let mut cx = Cx::new();
+        let view = renderer(false, true, &alive);
+        let (mut id, mut state, mut element) = view.build(&mut cx);
+        let plain_id = state.child_id;
+        let mut app_state = vec![];
+        let result = view.message(&[plain_id], &mut state, Box::new(()), &mut app_state);
+        assert!(matches!(result, MessageResult::Nop));
+        assert_eq!(app_state, ["plain"]);
+
+        let rich = renderer(true, true, &alive);
+        rich.rebuild(&mut cx, &view, &mut id, &mut state, &mut element);
+        let result = rich.message(&[plain_id], &mut state, Box::new(()), &mut app_state);
+        assert!(matches!(result, MessageResult::Stale(_)));
+        let rich_id = state.child_id;
+        rich.message(&[rich_id], &mut state, Box::new(()), &mut app_state);
+        assert_eq!(app_state, ["plain", "rich"]);
+    }
+}
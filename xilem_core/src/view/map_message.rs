@@ -0,0 +1,238 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+/// Create the `MapMessageAsync` view for a particular xilem context.
+///
+/// Arguments are the same as for [`generate_adapt_view`](crate::generate_adapt_view), plus
+///
+///  - `$thunk` - A cloneable handle, obtained with `$cx::message_thunk`, whose
+///    `push_message` method routes a message back to the view that created it.
+///  - `$spawn` - A function that runs a `Future<Output = ()> + 'static` to completion
+///    on the framework's runtime.
+#[macro_export]
+macro_rules! generate_map_message_async_view {
+    ($viewtrait:ident, $cx:ty, $changeflags:ty, $thunk:ty, $spawn:path; $($ss:tt)*) => {
+        /// A view that turns the actions of its child into futures, and the output of
+        /// those futures into its own actions.
+        ///
+        /// When the child returns an action, `f` is called with the app state and that
+        /// action. The future it returns is spawned, and once it completes its output is
+        /// routed back to this view and returned as a [`MessageResult::Action`] to the parent.
+        ///
+        /// [`MessageResult::Action`]: crate::MessageResult::Action
+        pub struct MapMessageAsync<T, ParentA, ChildA, V, F> {
+            f: F,
+            child: V,
+            phantom: std::marker::PhantomData<fn() -> (T, ParentA, ChildA)>,
+        }
+
+        pub struct MapMessageAsyncState<S> {
+            child_id: $crate::Id,
+            child_state: S,
+            thunk: $thunk,
+        }
+
+        impl<T, ParentA, ChildA, V, F, Fut> MapMessageAsync<T, ParentA, ChildA, V, F>
+        where
+            V: $viewtrait<T, ChildA>,
+            F: Fn(&mut T, ChildA) -> Fut $( $ss )*,
+            Fut: std::future::Future<Output = ParentA> + 'static,
+        {
+            pub fn new(f: F, child: V) -> Self {
+                MapMessageAsync {
+                    f,
+                    child,
+                    phantom: Default::default(),
+                }
+            }
+        }
+
+        impl<T, ParentA, ChildA, V, F, Fut> $viewtrait<T, ParentA>
+            for MapMessageAsync<T, ParentA, ChildA, V, F>
+        where
+            ParentA: 'static,
+            V: $viewtrait<T, ChildA>,
+            F: Fn(&mut T, ChildA) -> Fut $( $ss )*,
+            Fut: std::future::Future<Output = ParentA> + 'static,
+        {
+            type State = MapMessageAsyncState<V::State>;
+
+            type Element = V::Element;
+
+            fn build(&self, cx: &mut $cx) -> ($crate::Id, Self::State, Self::Element) {
+                let (id, (thunk, (child_id, child_state, element))) =
+                    cx.with_new_id(|cx| (cx.message_thunk(), self.child.build(cx)));
+                let state = MapMessageAsyncState {
+                    child_id,
+                    child_state,
+                    thunk,
+                };
+                (id, state, element)
+            }
+
+            fn rebuild(
+                &self,
+                cx: &mut $cx,
+                prev: &Self,
+                id: &mut $crate::Id,
+                state: &mut Self::State,
+                element: &mut Self::Element,
+            ) -> $changeflags {
+                cx.with_id(*id, |cx| {
+                    self.child.rebuild(
+                        cx,
+                        &prev.child,
+                        &mut state.child_id,
+                        &mut state.child_state,
+                        element,
+                    )
+                })
+            }
+
+            fn message(
+                &self,
+                id_path: &[$crate::Id],
+                state: &mut Self::State,
+                message: Box<dyn std::any::Any>,
+                app_state: &mut T,
+            ) -> $crate::MessageResult<ParentA> {
+                match id_path {
+                    // The output of a future spawned below
+                    [] => match message.downcast::<ParentA>() {
+                        Ok(action) => $crate::MessageResult::Action(*action),
+                        Err(message) => $crate::MessageResult::Stale(message),
+                    },
+                    [first, rest_path @ ..] if *first == state.child_id => {
+                        match self
+                            .child
+                            .message(rest_path, &mut state.child_state, message, app_state)
+                        {
+                            $crate::MessageResult::Action(action) => {
+                                let future = (self.f)(app_state, action);
+                                let thunk = state.thunk.clone();
+                                $spawn(async move { thunk.push_message(future.await) });
+                                $crate::MessageResult::Nop
+                            }
+                            $crate::MessageResult::RequestRebuild => {
+                                $crate::MessageResult::RequestRebuild
+                            }
+                            $crate::MessageResult::Nop => $crate::MessageResult::Nop,
+                            $crate::MessageResult::Stale(message) => {
+                                $crate::MessageResult::Stale(message)
+                            }
+                        }
+                    }
+                    _ => $crate::MessageResult::Stale(message),
+                }
+            }
+        }
+
+        impl<T, ParentA, ChildA, V, F, Fut> ViewMarker for MapMessageAsync<T, ParentA, ChildA, V, F>
+        where
+            V: $viewtrait<T, ChildA>,
+            F: Fn(&mut T, ChildA) -> Fut $( $ss )*,
+            Fut: std::future::Future<Output = ParentA> + 'static,
+        {
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use std::any::Any;
+    use std::time::Duration;
+
+    use crate::test_support::{advance, sleep, spawn, ChangeFlags, Cx, Thunk, View, ViewMarker};
+    use crate::{Id, MessageResult};
+
+    crate::generate_adapt_view! {View, Cx, ChangeFlags;}
+    crate::generate_map_message_async_view! {View, Cx, ChangeFlags, Thunk, spawn;}
+
+    #[derive(Default)]
+    struct Search {
+        queries: Vec<String>,
+        results: Vec<String>,
+    }
+
+    /// A search box whose action, when submitted, is its query.
+    struct SearchBox(&'static str);
+
+    impl ViewMarker for SearchBox {}
+
+    impl View<Search, String> for SearchBox {
+        type State = ();
+        type Element = String;
+
+        fn build(&self, _cx: &mut Cx) -> (Id, (), String) {
+            (Id::next(), (), self.0.into())
+        }
+
+        fn rebuild(
+            &self,
+            _cx: &mut Cx,
+            _prev: &Self,
+            _id: &mut Id,
+            _state: &mut (),
+            element: &mut String,
+        ) -> ChangeFlags {
+            *element = self.0.into();
+            ChangeFlags::empty()
+        }
+
+        fn message(
+            &self,
+            _id_path: &[Id],
+            _state: &mut (),
+            _message: Box<dyn Any>,
+            _app_state: &mut Search,
+        ) -> MessageResult<String> {
+            MessageResult::Action(self.0.into())
+        }
+    }
+
+    #[test]
+    fn deferred_action_reaches_parent_state() {
+        let mut cx = Cx::new();
+        // Look the query of the search box up asynchronously, and store the results in the
+        // app state.
+        let lookup = MapMessageAsync::new(
+            |search: &mut Search, query: String| {
+                search.queries.push(query.clone());
+                let lookup = sleep(Duration::from_millis(100));
+                async move {
+                    lookup.await;
+                    vec![format!("{query} 1"), format!("{query} 2")]
+                }
+            },
+            SearchBox("xilem"),
+        );
+        let view = Adapt::new(
+            |search: &mut Search, thunk: AdaptThunk<Search, Vec<String>, _>| {
+                thunk.call(search).map(|results| search.results = results)
+            },
+            lookup,
+        );
+        let (_, mut state, _) = view.build(&mut cx);
+        let mut app_state = Search::default();
+
+        // Submitting the query calls `f` right away, but its action is deferred.
+        let search_box = state.child_id;
+        let result = view.message(&[search_box], &mut state, Box::new(()), &mut app_state);
+        assert!(matches!(result, MessageResult::Nop));
+        assert_eq!(app_state.queries, ["xilem"]);
+        assert!(app_state.results.is_empty());
+        advance(Duration::from_millis(50));
+        assert!(cx.take_messages().is_empty());
+
+        // Once the future completes, its output is routed back to the view, and becomes
+        // the action handled by the parent.
+        advance(Duration::from_millis(50));
+        let messages = cx.take_messages();
+        assert_eq!(messages.len(), 1);
+        for message in messages {
+            let result = view.message(&[], &mut state, message, &mut app_state);
+            assert!(matches!(result, MessageResult::Action(())));
+        }
+        assert_eq!(app_state.results, ["xilem 1", "xilem 2"]);
+    }
+}
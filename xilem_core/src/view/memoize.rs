@@ -70,6 +70,7 @@ macro_rules! generate_memoize_view {
                     state.view = view;
                     changed
                 } else {
+                    $crate::trace_diff!(view = std::any::type_name::<V>(), "memoized");
                     <$changeflags>::empty()
                 }
             }
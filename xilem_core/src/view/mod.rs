@@ -2,7 +2,12 @@
 // SPDX-License-Identifier: Apache-2.0
 
 mod adapt;
+mod debounced_rebuild;
+mod first_of;
+mod map_message;
 mod memoize;
+mod provide_id;
+mod subscribe;
 
 /// Create the `View` trait for a particular xilem context (e.g. html, native, ...).
 ///
@@ -38,6 +43,10 @@ macro_rules! generate_view_trait {
         /// given to view nodes, which in turn can expose it to callbacks.
         pub trait $viewtrait<T, A = ()> $( $ss )* {
             /// Associated state for the view.
+            ///
+            /// There is no separate teardown step: when a view is removed from the tree,
+            /// its state and element are dropped. Resources tied to the view, such as event
+            /// listeners, should be released by the `Drop` implementation of its state.
             type State $( $ss )*;
 
             /// The associated element for the view.
@@ -0,0 +1,168 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+/// Create the `ProvideId` view and the `provide_id` function for a particular xilem context.
+///
+/// Arguments are the same as for [`generate_adapt_view`](crate::generate_adapt_view).
+/// Additionally, `$cx` must have a `register_stable_id(&mut self, id: Id)` method, which
+/// records in a [`StableIds`](crate::StableIds) that `id` is found at the current id path,
+/// and returns the [`StableIdRegistration`](crate::StableIdRegistration). The context
+/// should resolve the id paths of messages accordingly before routing them.
+#[macro_export]
+macro_rules! generate_provide_id_view {
+    ($viewtrait:ident, $cx:ty, $changeflags:ty; $($ss:tt)*) => {
+        /// A view that gives its child a stable id, chosen by the user.
+        ///
+        /// See [`provide_id`].
+        pub struct ProvideId<V> {
+            id: $crate::Id,
+            child: V,
+        }
+
+        pub struct ProvideIdState<S> {
+            child_id: $crate::Id,
+            child_state: S,
+            /// Unregisters the stable id when the view is torn down.
+            registration: $crate::StableIdRegistration,
+        }
+
+        /// Give `child` a stable id, derived from `user_id`, to route messages with.
+        ///
+        /// The ids of views are otherwise allocated when they are built, so when the view
+        /// tree is restructured (e.g. a sibling is inserted before `child` in a `Vec`), the
+        /// messages already in flight, such as the output of a future spawned by
+        /// `map_message_async`, may be delivered to another view, or be stale. Messages
+        /// addressed to `child` are instead delivered wherever a `provide_id` view with
+        /// the same `user_id` currently is.
+        ///
+        /// `user_id` should be unique within the app.
+        pub fn provide_id<V>(user_id: u64, child: V) -> ProvideId<V> {
+            ProvideId {
+                id: $crate::Id::stable(user_id),
+                child,
+            }
+        }
+
+        impl<T, A, V> $viewtrait<T, A> for ProvideId<V>
+        where
+            V: $viewtrait<T, A>,
+        {
+            type State = ProvideIdState<V::State>;
+
+            type Element = V::Element;
+
+            fn build(&self, cx: &mut $cx) -> ($crate::Id, Self::State, Self::Element) {
+                let registration = cx.register_stable_id(self.id);
+                let (child_id, child_state, element) =
+                    cx.with_id(self.id, |cx| self.child.build(cx));
+                let state = ProvideIdState {
+                    child_id,
+                    child_state,
+                    registration,
+                };
+                (self.id, state, element)
+            }
+
+            fn rebuild(
+                &self,
+                cx: &mut $cx,
+                prev: &Self,
+                id: &mut $crate::Id,
+                state: &mut Self::State,
+                element: &mut Self::Element,
+            ) -> $changeflags {
+                *id = self.id;
+                state.registration = cx.register_stable_id(self.id);
+                cx.with_id(self.id, |cx| {
+                    self.child.rebuild(
+                        cx,
+                        &prev.child,
+                        &mut state.child_id,
+                        &mut state.child_state,
+                        element,
+                    )
+                })
+            }
+
+            fn message(
+                &self,
+                id_path: &[$crate::Id],
+                state: &mut Self::State,
+                message: Box<dyn std::any::Any>,
+                app_state: &mut T,
+            ) -> $crate::MessageResult<A> {
+                match id_path {
+                    [first, rest_path @ ..] if *first == state.child_id => {
+                        self.child
+                            .message(rest_path, &mut state.child_state, message, app_state)
+                    }
+                    // The child got a new id since the message was sent, e.g. because the
+                    // `Vec` it's in was restructured. The stable id takes precedence.
+                    [_] => self
+                        .child
+                        .message(&[], &mut state.child_state, message, app_state),
+                    _ => $crate::MessageResult::Stale(message),
+                }
+            }
+        }
+
+        impl<V> ViewMarker for ProvideId<V> {}
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_support::{Button, ChangeFlags, Cx, View, ViewMarker, ViewSequence};
+    use crate::{Id, MessageResult, VecSplice};
+
+    crate::generate_provide_id_view! {View, Cx, ChangeFlags;}
+
+    fn buttons(buttons: &[(u64, &'static str)]) -> Vec<ProvideId<Button>> {
+        buttons
+            .iter()
+            .map(|&(user_id, text)| provide_id(user_id, Button(text)))
+            .collect()
+    }
+
+    #[test]
+    fn stable_id_follows_restructured_siblings() {
+        let mut cx = Cx::new();
+        let (mut pods, mut scratch) = (vec![], vec![]);
+        let list = Id::next();
+        let view = buttons(&[(1, "a"), (2, "b"), (3, "c")]);
+        let mut state = cx.with_id(list, |cx| {
+            view.build(cx, &mut VecSplice::new(&mut pods, &mut scratch))
+        });
+        // A click on "c", sent before the tree is restructured.
+        let click = [list, Id::stable(3), state[2].0.child_id];
+
+        // "b" is removed, and the list is moved into a new container: "c" is now rebuilt
+        // from the state "b" had, and the view which had the state of "c" is torn down.
+        let container = Id::next();
+        let restructured = buttons(&[(1, "a"), (3, "c")]);
+        cx.with_id(container, |cx| {
+            restructured.rebuild(
+                cx,
+                &view,
+                &mut state,
+                &mut VecSplice::new(&mut pods, &mut scratch),
+            )
+        });
+        assert!(cx.stable_ids.contains(Id::stable(1)));
+        assert!(!cx.stable_ids.contains(Id::stable(2)));
+        assert!(cx.stable_ids.contains(Id::stable(3)));
+
+        // The click still reaches "c", at its new location.
+        let resolved = cx.stable_ids.resolve(&click);
+        assert_eq!(resolved[..2], [container, Id::stable(3)]);
+        let mut app_state = vec![];
+        let result = restructured.message(&resolved[1..], &mut state, Box::new(()), &mut app_state);
+        assert!(matches!(result, MessageResult::Nop));
+        assert_eq!(app_state, ["c"]);
+
+        // Tearing down the views unregisters their stable ids.
+        drop(state);
+        assert!(!cx.stable_ids.contains(Id::stable(1)));
+        assert!(!cx.stable_ids.contains(Id::stable(3)));
+    }
+}
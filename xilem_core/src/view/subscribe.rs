@@ -0,0 +1,129 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+/// Create the `Subscribe` view for a particular xilem context.
+///
+/// Arguments are the same as for
+/// [`generate_map_message_async_view`](crate::generate_map_message_async_view).
+#[macro_export]
+macro_rules! generate_subscribe_view {
+    ($viewtrait:ident, $cx:ty, $changeflags:ty, $thunk:ty, $spawn:path; $($ss:tt)*) => {
+        /// A view that subscribes to a stream of events from outside the view tree, like
+        /// the messages of a websocket, while it's in the tree.
+        ///
+        /// When the view is built, `source_factory` is called to create the stream, which
+        /// is then polled on the framework's runtime. Each item is routed back to this view,
+        /// and passed to `on_event` with the app state; what `on_event` returns is the
+        /// action of this view. The child is shown as is, and its actions are passed through.
+        ///
+        /// The stream is dropped with the view's state, i.e. when the view is removed,
+        /// even if it is waiting for an item. Rebuilding the view doesn't subscribe again.
+        pub struct Subscribe<T, A, V, F, H> {
+            child: V,
+            source_factory: F,
+            on_event: H,
+            phantom: std::marker::PhantomData<fn() -> (T, A)>,
+        }
+
+        pub struct SubscribeState<S> {
+            child_id: $crate::Id,
+            child_state: S,
+            // Only kept to be dropped with the state.
+            #[allow(dead_code)]
+            subscription: $crate::Subscription,
+        }
+
+        impl<T, A, V, F, H, St> Subscribe<T, A, V, F, H>
+        where
+            V: $viewtrait<T, A>,
+            F: Fn() -> St $( $ss )*,
+            St: $crate::futures_core::Stream + 'static,
+            H: Fn(&mut T, St::Item) -> A $( $ss )*,
+        {
+            pub fn new(child: V, source_factory: F, on_event: H) -> Self {
+                Subscribe {
+                    child,
+                    source_factory,
+                    on_event,
+                    phantom: Default::default(),
+                }
+            }
+        }
+
+        impl<T, A, V, F, H, St> $viewtrait<T, A> for Subscribe<T, A, V, F, H>
+        where
+            V: $viewtrait<T, A>,
+            F: Fn() -> St $( $ss )*,
+            St: $crate::futures_core::Stream + 'static,
+            H: Fn(&mut T, St::Item) -> A $( $ss )*,
+        {
+            type State = SubscribeState<V::State>;
+
+            type Element = V::Element;
+
+            fn build(&self, cx: &mut $cx) -> ($crate::Id, Self::State, Self::Element) {
+                let (id, (thunk, (child_id, child_state, element))) =
+                    cx.with_new_id(|cx| (cx.message_thunk(), self.child.build(cx)));
+                let stream = (self.source_factory)();
+                let (subscription, task) =
+                    $crate::Subscription::new(stream, move |item| thunk.push_message(item));
+                $spawn(task);
+                let state = SubscribeState {
+                    child_id,
+                    child_state,
+                    subscription,
+                };
+                (id, state, element)
+            }
+
+            fn rebuild(
+                &self,
+                cx: &mut $cx,
+                prev: &Self,
+                id: &mut $crate::Id,
+                state: &mut Self::State,
+                element: &mut Self::Element,
+            ) -> $changeflags {
+                cx.with_id(*id, |cx| {
+                    self.child.rebuild(
+                        cx,
+                        &prev.child,
+                        &mut state.child_id,
+                        &mut state.child_state,
+                        element,
+                    )
+                })
+            }
+
+            fn message(
+                &self,
+                id_path: &[$crate::Id],
+                state: &mut Self::State,
+                message: Box<dyn std::any::Any>,
+                app_state: &mut T,
+            ) -> $crate::MessageResult<A> {
+                match id_path {
+                    // An item of the stream
+                    [] => match message.downcast::<St::Item>() {
+                        Ok(item) => $crate::MessageResult::Action((self.on_event)(app_state, *item)),
+                        Err(message) => $crate::MessageResult::Stale(message),
+                    },
+                    [first, rest_path @ ..] if *first == state.child_id => {
+                        self.child
+                            .message(rest_path, &mut state.child_state, message, app_state)
+                    }
+                    _ => $crate::MessageResult::Stale(message),
+                }
+            }
+        }
+
+        impl<T, A, V, F, H, St> ViewMarker for Subscribe<T, A, V, F, H>
+        where
+            V: $viewtrait<T, A>,
+            F: Fn() -> St $( $ss )*,
+            St: $crate::futures_core::Stream + 'static,
+            H: Fn(&mut T, St::Item) -> A $( $ss )*,
+        {
+        }
+    };
+}
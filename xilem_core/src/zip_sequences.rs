@@ -0,0 +1,129 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+/// Two lists of view sequences, rendered interleaved: `a[0], b[0], a[1], b[1], ...`.
+///
+/// Created with [`zip_sequences`]. The view sequence implementation is generated
+/// by [`generate_viewsequence_trait`](crate::generate_viewsequence_trait).
+pub struct ZipSequences<VA, VB> {
+    a: Vec<VA>,
+    b: Vec<VB>,
+    pad: bool,
+}
+
+/// Interleave the items of `a` and `b`.
+///
+/// By default, this stops at the end of the shorter list; see [`ZipSequences::pad`]
+/// to keep the remaining items of the longer one.
+pub fn zip_sequences<VA, VB>(a: Vec<VA>, b: Vec<VB>) -> ZipSequences<VA, VB> {
+    ZipSequences { a, b, pad: false }
+}
+
+impl<VA, VB> ZipSequences<VA, VB> {
+    /// Keep the remaining items of the longer list after the end of the shorter one,
+    /// instead of dropping them.
+    pub fn pad(mut self) -> Self {
+        self.pad = true;
+        self
+    }
+
+    /// The number of pairs, including the incomplete ones when padding.
+    pub fn len(&self) -> usize {
+        if self.pad {
+            self.a.len().max(self.b.len())
+        } else {
+            self.a.len().min(self.b.len())
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The items of the pair at `index`.
+    ///
+    /// Both are `None` past the end, and one of them may be `None` when padding.
+    pub fn get(&self, index: usize) -> (Option<&VA>, Option<&VB>) {
+        if index >= self.len() {
+            return (None, None);
+        }
+        (self.a.get(index), self.b.get(index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{elements, Button, ChangeFlags, Cx, ViewSequence};
+    use crate::{Id, MessageResult, VecSplice};
+
+    fn taps(texts: &[&'static str]) -> Vec<Button> {
+        texts.iter().map(|text| Button(text)).collect()
+    }
+
+    #[test]
+    fn items_are_interleaved() {
+        let mut cx = Cx::new();
+        let (mut pods, mut scratch) = (vec![], vec![]);
+        let view = zip_sequences(taps(&["a0", "a1"]), taps(&["b0", "b1"]));
+        let state = view.build(&mut cx, &mut VecSplice::new(&mut pods, &mut scratch));
+        assert_eq!(elements::<String>(&pods), ["a0", "b0", "a1", "b1"]);
+        assert_eq!(view.count(&state), 4);
+    }
+
+    #[test]
+    fn messages_reach_their_source_item() {
+        let mut cx = Cx::new();
+        let (mut pods, mut scratch) = (vec![], vec![]);
+        let view = zip_sequences(taps(&["a0", "a1"]), taps(&["b0", "b1"]));
+        let mut state = view.build(&mut cx, &mut VecSplice::new(&mut pods, &mut scratch));
+        let id = |state: &Option<((), Id)>| state.as_ref().unwrap().1;
+        let (a1, b0) = (id(&state[1].0), id(&state[0].1));
+
+        let mut app_state = vec![];
+        view.message(&[a1], &mut state, Box::new(()), &mut app_state);
+        view.message(&[b0], &mut state, Box::new(()), &mut app_state);
+        assert_eq!(app_state, ["a1", "b0"]);
+
+        let result = view.message(&[Id::next()], &mut state, Box::new(()), &mut app_state);
+        assert!(matches!(result, MessageResult::Stale(_)));
+        assert_eq!(app_state, ["a1", "b0"]);
+    }
+
+    #[test]
+    fn unequal_lengths_stop_or_pad() {
+        let mut cx = Cx::new();
+        let (mut pods, mut scratch) = (vec![], vec![]);
+        let view = zip_sequences(taps(&["a0", "a1", "a2"]), taps(&["b0"]));
+        let mut state = view.build(&mut cx, &mut VecSplice::new(&mut pods, &mut scratch));
+        assert_eq!(elements::<String>(&pods), ["a0", "b0"]);
+
+        // Padding keeps the remaining items of the longer list.
+        let padded = zip_sequences(taps(&["a0", "a1", "a2"]), taps(&["b0"])).pad();
+        let changed = padded.rebuild(
+            &mut cx,
+            &view,
+            &mut state,
+            &mut VecSplice::new(&mut pods, &mut scratch),
+        );
+        assert_eq!(changed, ChangeFlags::tree_structure());
+        assert_eq!(elements::<String>(&pods), ["a0", "b0", "a1", "a2"]);
+        assert_eq!(padded.count(&state), 4);
+
+        let mut app_state = vec![];
+        let a2 = state[2].0.as_ref().unwrap().1;
+        padded.message(&[a2], &mut state, Box::new(()), &mut app_state);
+        assert_eq!(app_state, ["a2"]);
+
+        // Without padding again, they are removed.
+        let unpadded = zip_sequences(taps(&["a0", "a1", "a2"]), taps(&["b0"]));
+        unpadded.rebuild(
+            &mut cx,
+            &padded,
+            &mut state,
+            &mut VecSplice::new(&mut pods, &mut scratch),
+        );
+        assert_eq!(elements::<String>(&pods), ["a0", "b0"]);
+        assert_eq!(unpadded.count(&state), 2);
+    }
+}
@@ -5,11 +5,16 @@ use std::{cell::RefCell, rc::Rc};
 
 use crate::{
     context::Cx,
+    error_toasts::ErrorSink,
     view::{DomNode, View},
     Message,
 };
 use xilem_core::{Id, MessageResult};
 
+/// How many times a cancelled rebuild is restarted right away, before the next restart
+/// is left to a microtask.
+const MAX_REBUILD_RESTARTS: u32 = 3;
+
 /// The type responsible for running your app.
 pub struct App<T, V: View<T>, F: FnMut(&mut T) -> V>(Rc<RefCell<AppInner<T, V, F>>>);
 
@@ -44,6 +49,18 @@ impl<T: 'static, V: View<T> + 'static, F: FnMut(&mut T) -> V + 'static> App<T, V
         app
     }
 
+    /// Whether changes to existing elements are batched, and applied in a microtask after
+    /// each rebuild. This is disabled by default, see [`Cx::set_batch_dom_writes`].
+    pub fn with_batched_dom_writes(self, batched: bool) -> Self {
+        self.0.borrow_mut().cx.set_batch_dom_writes(batched);
+        self
+    }
+
+    /// The app-wide channel for reporting errors, displayed by [`error_toasts`](crate::error_toasts).
+    pub fn error_sink(&self) -> ErrorSink {
+        self.0.borrow().cx.error_sink()
+    }
+
     /// Run the app.
     ///
     /// Because we don't want to block the render thread, we return immediately here. The app is
@@ -53,6 +70,35 @@ impl<T: 'static, V: View<T> + 'static, F: FnMut(&mut T) -> V + 'static> App<T, V
         // Latter may not be necessary, we have an rc loop.
         std::mem::forget(self);
     }
+
+    /// Flush the DOM writes of the last rebuild in a microtask, if there are any.
+    fn schedule_dom_writes(&self, inner: &AppInner<T, V, F>) {
+        if inner.cx.has_pending_dom_writes() {
+            let app = self.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                // If a message is being handled, it has flushed the writes itself.
+                if let Ok(mut inner) = app.0.try_borrow_mut() {
+                    inner.cx.flush_dom_writes();
+                }
+            });
+        }
+    }
+
+    /// Restart a cancelled rebuild in a microtask.
+    fn restart_rebuild_later(&self) {
+        let app = self.clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            // If a message is being handled, its rebuild replaces the restart.
+            let Ok(mut inner_guard) = app.0.try_borrow_mut() else {
+                return;
+            };
+            let inner = &mut *inner_guard;
+            if inner.rebuild() {
+                app.restart_rebuild_later();
+            }
+            app.schedule_dom_writes(inner);
+        });
+    }
 }
 
 impl<T, V: View<T>, F: FnMut(&mut T) -> V> AppInner<T, V, F> {
@@ -81,6 +127,30 @@ impl<T, V: View<T>, F: FnMut(&mut T) -> V> AppInner<T, V, F> {
             self.element = Some(element);
         }
     }
+
+    /// Run the app logic and rebuild the view tree, returning whether the rebuild was
+    /// cancelled, see [`Cx::rebuild_cancellation`].
+    fn rebuild(&mut self) -> bool {
+        let Some(view) = &mut self.view else {
+            return false;
+        };
+        let new_view = (self.app_logic)(&mut self.data);
+        let _changed = new_view.rebuild(
+            &mut self.cx,
+            view,
+            self.id.as_mut().unwrap(),
+            self.state.as_mut().unwrap(),
+            self.element.as_mut().unwrap(),
+        );
+        // Not sure we have to do anything on changed, the rebuild
+        // traversal should cause the DOM to update.
+        *view = new_view;
+        if !self.cx.is_rebuild_cancelled() {
+            return false;
+        }
+        self.cx.rebuild_cancellation().reset();
+        true
+    }
 }
 
 impl<T: 'static, V: View<T> + 'static, F: FnMut(&mut T) -> V + 'static> AppRunner for App<T, V, F> {
@@ -89,9 +159,14 @@ impl<T: 'static, V: View<T> + 'static, F: FnMut(&mut T) -> V + 'static> AppRunne
     fn handle_message(&self, message: Message) {
         let mut inner_guard = self.0.borrow_mut();
         let inner = &mut *inner_guard;
+        // The handlers should see the changes of the previous rebuild.
+        inner.cx.flush_dom_writes();
         if let Some(view) = &mut inner.view {
+            #[cfg(debug_assertions)]
+            log::debug!("Handling a message from {}", message.origin);
+            let id_path = inner.cx.resolve_id_path(&message.id_path);
             let message_result = view.message(
-                &message.id_path[1..],
+                &id_path[1..],
                 inner.state.as_mut().unwrap(),
                 message.body,
                 &mut inner.data,
@@ -108,17 +183,20 @@ impl<T: 'static, V: View<T> + 'static, F: FnMut(&mut T) -> V + 'static> AppRunne
                 }
             }
 
-            let new_view = (inner.app_logic)(&mut inner.data);
-            let _changed = new_view.rebuild(
-                &mut inner.cx,
-                view,
-                inner.id.as_mut().unwrap(),
-                inner.state.as_mut().unwrap(),
-                inner.element.as_mut().unwrap(),
-            );
-            // Not sure we have to do anything on changed, the rebuild
-            // traversal should cause the DOM to update.
-            *view = new_view;
+            // A cancelled rebuild leaves parts of the tree out, so it is restarted right
+            // away (against the view it was cancelled on). Past a few restarts, the next
+            // one runs in a microtask, so that a view cancelling every rebuild doesn't
+            // block the page.
+            let mut restarts = 0;
+            while inner.rebuild() {
+                restarts += 1;
+                if restarts == MAX_REBUILD_RESTARTS {
+                    self.restart_rebuild_later();
+                    break;
+                }
+            }
+
+            self.schedule_dom_writes(inner);
         }
     }
 
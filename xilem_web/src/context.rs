@@ -1,17 +1,20 @@
 // Copyright 2023 the Xilem Authors
 // SPDX-License-Identifier: Apache-2.0
 
-use std::any::Any;
+use std::{any::Any, rc::Rc};
 
 use bitflags::bitflags;
 use wasm_bindgen::{JsCast, UnwrapThrowExt};
 use web_sys::Document;
 
-use xilem_core::{Id, IdPath};
+use xilem_core::{Id, IdPath, MessageOrigin, RebuildCancellation, StableIdRegistration, StableIds};
 
 use crate::{
     app::AppRunner,
     diff::{diff_kv_iterables, Diff},
+    error_toasts::ErrorSink,
+    intl::{browser_language, IntlCache},
+    suspense::SuspenseHandle,
     vecmap::VecMap,
     view::DomNode,
     AttributeValue, Message, Pod,
@@ -65,27 +68,37 @@ impl HtmlProps {
         styles
     }
 
-    fn apply_changes(&mut self, element: &web_sys::Element, props: &mut HtmlProps) -> ChangeFlags {
-        self.apply_attribute_changes(element, &mut props.attributes)
-            | self.apply_class_changes(element, &mut props.classes)
-            | self.apply_style_changes(element, &mut props.styles)
+    fn apply_changes(
+        &mut self,
+        element: &web_sys::Element,
+        props: &mut HtmlProps,
+        writes: &mut DomWrites,
+    ) -> ChangeFlags {
+        self.apply_attribute_changes(element, &mut props.attributes, writes)
+            | self.apply_class_changes(element, &mut props.classes, writes)
+            | self.apply_style_changes(element, &mut props.styles, writes)
     }
 
     pub(crate) fn apply_attribute_changes(
         &mut self,
         element: &web_sys::Element,
         attributes: &mut VecMap<CowStr, AttributeValue>,
+        writes: &mut DomWrites,
     ) -> ChangeFlags {
         let mut changed = ChangeFlags::empty();
         // update attributes
         for itm in diff_kv_iterables(&*attributes, &self.attributes) {
             match itm {
                 Diff::Add(name, value) | Diff::Change(name, value) => {
-                    set_attribute(element, name, &value.serialize());
+                    writes.push(DomWrite::SetAttribute(
+                        element.clone(),
+                        name.clone(),
+                        value.serialize(),
+                    ));
                     changed |= ChangeFlags::OTHER_CHANGE;
                 }
                 Diff::Remove(name) => {
-                    remove_attribute(element, name);
+                    writes.push(DomWrite::RemoveAttribute(element.clone(), name.clone()));
                     changed |= ChangeFlags::OTHER_CHANGE;
                 }
             }
@@ -99,17 +112,18 @@ impl HtmlProps {
         &mut self,
         element: &web_sys::Element,
         classes: &mut VecMap<CowStr, ()>,
+        writes: &mut DomWrites,
     ) -> ChangeFlags {
         let mut changed = ChangeFlags::empty();
         // update attributes
         for itm in diff_kv_iterables(&*classes, &self.classes) {
             match itm {
                 Diff::Add(class_name, ()) | Diff::Change(class_name, ()) => {
-                    set_class(element, class_name);
+                    writes.push(DomWrite::AddClass(element.clone(), class_name.clone()));
                     changed |= ChangeFlags::OTHER_CHANGE;
                 }
                 Diff::Remove(class_name) => {
-                    remove_class(element, class_name);
+                    writes.push(DomWrite::RemoveClass(element.clone(), class_name.clone()));
                     changed |= ChangeFlags::OTHER_CHANGE;
                 }
             }
@@ -123,17 +137,22 @@ impl HtmlProps {
         &mut self,
         element: &web_sys::Element,
         styles: &mut VecMap<CowStr, CowStr>,
+        writes: &mut DomWrites,
     ) -> ChangeFlags {
         let mut changed = ChangeFlags::empty();
         // update attributes
         for itm in diff_kv_iterables(&*styles, &self.styles) {
             match itm {
                 Diff::Add(name, value) | Diff::Change(name, value) => {
-                    set_style(element, name, value);
+                    writes.push(DomWrite::SetStyle(
+                        element.clone(),
+                        name.clone(),
+                        value.clone(),
+                    ));
                     changed |= ChangeFlags::OTHER_CHANGE;
                 }
                 Diff::Remove(name) => {
-                    remove_style(element, name);
+                    writes.push(DomWrite::RemoveStyle(element.clone(), name.clone()));
                     changed |= ChangeFlags::OTHER_CHANGE;
                 }
             }
@@ -209,6 +228,59 @@ fn remove_style(element: &web_sys::Element, name: &str) {
     }
 }
 
+/// A change to an element, which is applied when the [`DomWrites`] are flushed.
+enum DomWrite {
+    SetAttribute(web_sys::Element, CowStr, CowStr),
+    RemoveAttribute(web_sys::Element, CowStr),
+    AddClass(web_sys::Element, CowStr),
+    RemoveClass(web_sys::Element, CowStr),
+    SetStyle(web_sys::Element, CowStr, CowStr),
+    RemoveStyle(web_sys::Element, CowStr),
+}
+
+impl DomWrite {
+    fn apply(self) {
+        match self {
+            DomWrite::SetAttribute(element, name, value) => set_attribute(&element, &name, &value),
+            DomWrite::RemoveAttribute(element, name) => remove_attribute(&element, &name),
+            DomWrite::AddClass(element, class_name) => set_class(&element, &class_name),
+            DomWrite::RemoveClass(element, class_name) => remove_class(&element, &class_name),
+            DomWrite::SetStyle(element, name, value) => set_style(&element, &name, &value),
+            DomWrite::RemoveStyle(element, name) => remove_style(&element, &name),
+        }
+    }
+}
+
+/// The attribute, class and style changes of elements made during a rebuild.
+///
+/// When batching is enabled, they are queued instead of being applied right away, so
+/// that the rebuild (the reconcile phase) doesn't interleave DOM reads and writes, and
+/// the writes are applied together once the rebuild is over (the commit phase).
+///
+/// Structural changes (i.e. adding, removing and moving nodes) are always applied right
+/// away, since the rebuild relies on the current children of the elements. Newly built
+/// elements aren't in the document yet, so their attributes are also set right away.
+pub(crate) struct DomWrites {
+    batched: bool,
+    pending: Vec<DomWrite>,
+}
+
+impl DomWrites {
+    fn push(&mut self, write: DomWrite) {
+        if self.batched {
+            self.pending.push(write);
+        } else {
+            write.apply();
+        }
+    }
+
+    fn flush(&mut self) {
+        for write in self.pending.drain(..) {
+            write.apply();
+        }
+    }
+}
+
 // Note: xilem has derive Clone here. Not sure.
 pub struct Cx {
     id_path: IdPath,
@@ -216,11 +288,19 @@ pub struct Cx {
     // TODO There's likely a cleaner more robust way to propagate the attributes to an element
     pub(crate) current_element_props: HtmlProps,
     app_ref: Option<Box<dyn AppRunner>>,
+    error_sink: ErrorSink,
+    stable_ids: StableIds,
+    dom_writes: DomWrites,
+    rebuild_cancellation: RebuildCancellation,
+    locale: Rc<str>,
+    suspense: Option<SuspenseHandle>,
+    pub(crate) intl: IntlCache,
 }
 
 pub struct MessageThunk {
     id_path: IdPath,
     app_ref: Box<dyn AppRunner>,
+    origin: MessageOrigin,
 }
 
 bitflags! {
@@ -238,6 +318,16 @@ impl Cx {
             document: crate::document(),
             app_ref: None,
             current_element_props: Default::default(),
+            error_sink: ErrorSink::default(),
+            stable_ids: StableIds::new(),
+            dom_writes: DomWrites {
+                batched: false,
+                pending: Vec::new(),
+            },
+            rebuild_cancellation: RebuildCancellation::new(),
+            locale: browser_language(),
+            suspense: None,
+            intl: IntlCache::default(),
         }
     }
 
@@ -264,6 +354,21 @@ impl Cx {
         result
     }
 
+    /// Record that the stable `id` (see [`provide_id`](crate::provide_id)) is found at
+    /// the current id path, until the returned registration is dropped.
+    pub fn register_stable_id(&mut self, id: Id) -> StableIdRegistration {
+        self.id_path.push(id);
+        let registration = self.stable_ids.register(&self.id_path);
+        self.id_path.pop();
+        registration
+    }
+
+    /// Rewrite the id path of a message to the current location of the stable ids it goes
+    /// through.
+    pub(crate) fn resolve_id_path(&self, id_path: &[Id]) -> IdPath {
+        self.stable_ids.resolve(id_path)
+    }
+
     /// Allocate a new id and run logic with the new id added to the id path.
     ///
     /// Also an ergonomic helper.
@@ -309,6 +414,11 @@ impl Cx {
         &self.document
     }
 
+    /// The app-wide channel for reporting errors, displayed by [`error_toasts`](crate::error_toasts).
+    pub fn error_sink(&self) -> ErrorSink {
+        self.error_sink.clone()
+    }
+
     pub(crate) fn build_element(&mut self, ns: &str, name: &str) -> (web_sys::Element, HtmlProps) {
         let el = self
             .document
@@ -323,7 +433,87 @@ impl Cx {
         element: &web_sys::Element,
         props: &mut HtmlProps,
     ) -> ChangeFlags {
-        self.current_element_props.apply_changes(element, props)
+        self.current_element_props
+            .apply_changes(element, props, &mut self.dom_writes)
+    }
+
+    /// Whether changes to the attributes, classes and styles of existing elements are
+    /// batched until [`flush_dom_writes`](Self::flush_dom_writes) is called, rather than
+    /// applied during the rebuild. This is disabled by default.
+    ///
+    /// The [`App`](crate::App) flushes them in a microtask after each rebuild, and
+    /// before handling a message, so event handlers always see an up-to-date DOM.
+    /// Code reading the DOM during a rebuild (i.e. in the reconcile phase) sees the
+    /// elements as they were before the rebuild, which is why this is opt-in. Apps whose
+    /// views measure elements while they're rebuilt (e.g. virtualized lists) benefit the
+    /// most from it, see the `dom_batching` example.
+    ///
+    /// Disabling batching flushes the pending changes.
+    pub fn set_batch_dom_writes(&mut self, batched: bool) {
+        self.dom_writes.batched = batched;
+        if !batched {
+            self.flush_dom_writes();
+        }
+    }
+
+    /// Apply the changes batched during the previous rebuilds, see
+    /// [`set_batch_dom_writes`](Self::set_batch_dom_writes).
+    pub fn flush_dom_writes(&mut self) {
+        self.dom_writes.flush();
+    }
+
+    /// Whether there are batched changes which haven't been applied yet.
+    pub fn has_pending_dom_writes(&self) -> bool {
+        !self.dom_writes.pending.is_empty()
+    }
+
+    /// A handle to abort the rebuild in progress, e.g. from a view which finds out that
+    /// the app state it is built from is already outdated.
+    ///
+    /// After a cancelled rebuild, the [`App`](crate::App) runs the app logic again and
+    /// rebuilds the new view right away, see [`RebuildCancellation`]. After three restarts
+    /// in a row, the next one is left to a microtask, so that the page stays responsive.
+    pub fn rebuild_cancellation(&self) -> RebuildCancellation {
+        self.rebuild_cancellation.clone()
+    }
+
+    /// Whether the rebuild in progress has been cancelled.
+    pub fn is_rebuild_cancelled(&self) -> bool {
+        self.rebuild_cancellation.is_cancelled()
+    }
+
+    /// The locale the views format their text for, a BCP 47 language tag like "en-US".
+    ///
+    /// This is the one of the closest [`locale`](crate::locale) view, or the language of
+    /// the browser.
+    pub fn locale(&self) -> Rc<str> {
+        self.locale.clone()
+    }
+
+    /// Run some logic with `locale` as the locale of the views, see [`Cx::locale`].
+    pub(crate) fn with_locale<T>(&mut self, locale: Rc<str>, f: impl FnOnce(&mut Cx) -> T) -> T {
+        let prev = std::mem::replace(&mut self.locale, locale);
+        let result = f(self);
+        self.locale = prev;
+        result
+    }
+
+    /// The handle to report the status of async dependencies to the closest
+    /// [`suspense`](crate::suspense) boundary, if there's one.
+    pub fn suspense(&self) -> Option<SuspenseHandle> {
+        self.suspense.clone()
+    }
+
+    /// Run some logic with `handle` as the closest suspense boundary, see [`Cx::suspense`].
+    pub(crate) fn with_suspense<T>(
+        &mut self,
+        handle: SuspenseHandle,
+        f: impl FnOnce(&mut Cx) -> T,
+    ) -> T {
+        let prev = self.suspense.replace(handle);
+        let result = f(self);
+        self.suspense = prev;
+        result
     }
 
     // TODO Not sure how multiple attribute definitions with the same name should be handled (e.g. `e.attr("class", "a").attr("class", "b")`)
@@ -367,10 +557,14 @@ impl Cx {
         }
     }
 
+    /// A handle to send messages to the current view, whose origin is the caller, see
+    /// [`MessageThunk::with_origin`].
+    #[track_caller]
     pub fn message_thunk(&self) -> MessageThunk {
         MessageThunk {
             id_path: self.id_path.clone(),
             app_ref: self.app_ref.as_ref().unwrap().clone_box(),
+            origin: MessageOrigin::caller(),
         }
     }
     pub(crate) fn set_runner(&mut self, runner: impl AppRunner + 'static) {
@@ -384,12 +578,26 @@ impl Default for Cx {
     }
 }
 
+impl Clone for MessageThunk {
+    fn clone(&self) -> Self {
+        MessageThunk {
+            id_path: self.id_path.clone(),
+            app_ref: self.app_ref.clone_box(),
+            origin: self.origin,
+        }
+    }
+}
+
 impl MessageThunk {
+    /// Set the origin of the messages pushed with this thunk, usually where the handler
+    /// of the messages was registered, e.g. the call to `on_click`.
+    pub fn with_origin(mut self, origin: MessageOrigin) -> Self {
+        self.origin = origin;
+        self
+    }
+
     pub fn push_message(&self, message_body: impl Any + 'static) {
-        let message = Message {
-            id_path: self.id_path.clone(),
-            body: Box::new(message_body),
-        };
+        let message = Message::new_with_origin(self.id_path.clone(), message_body, self.origin);
         self.app_ref.handle_message(message);
     }
 }
@@ -399,3 +607,46 @@ impl ChangeFlags {
         Self::STRUCTURE
     }
 }
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod tests {
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use super::*;
+    use crate::{elements::html as el, interfaces::Element, View};
+
+    wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+    fn view(title: &'static str) -> impl View<(), (), Element = web_sys::HtmlDivElement> {
+        el::div(()).attr("title", title)
+    }
+
+    #[wasm_bindgen_test]
+    fn dom_writes_are_applied_right_away_by_default() {
+        let mut cx = Cx::new();
+        let prev = view("a");
+        let (mut id, mut state, mut element) = prev.build(&mut cx);
+
+        view("b").rebuild(&mut cx, &prev, &mut id, &mut state, &mut element);
+        assert!(!cx.has_pending_dom_writes());
+        assert_eq!(element.get_attribute("title").unwrap(), "b");
+    }
+
+    #[wasm_bindgen_test]
+    fn batched_dom_writes_wait_for_flush() {
+        let mut cx = Cx::new();
+        cx.set_batch_dom_writes(true);
+        let prev = view("a");
+        // Newly built elements get their attributes right away.
+        let (mut id, mut state, mut element) = prev.build(&mut cx);
+        assert_eq!(element.get_attribute("title").unwrap(), "a");
+
+        view("b").rebuild(&mut cx, &prev, &mut id, &mut state, &mut element);
+        assert!(cx.has_pending_dom_writes());
+        assert_eq!(element.get_attribute("title").unwrap(), "a");
+
+        cx.flush_dom_writes();
+        assert!(!cx.has_pending_dom_writes());
+        assert_eq!(element.get_attribute("title").unwrap(), "b");
+    }
+}
@@ -0,0 +1,150 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{any::Any, marker::PhantomData};
+
+use wasm_bindgen::JsCast;
+use xilem_core::{Id, MessageResult};
+
+use crate::{
+    interfaces::{sealed::Sealed, Element},
+    page_events::push_message_later,
+    view::DomNode,
+    ChangeFlags, Cx, View, ViewMarker,
+};
+
+struct Attached(web_sys::Element);
+
+/// Gives access to the DOM node of the wrapped element, see [`Element::dom_ref`].
+pub struct DomRef<E, T, A, F> {
+    pub(crate) element: E,
+    pub(crate) on_attach: F,
+    pub(crate) phantom: PhantomData<fn() -> (T, A)>,
+}
+
+/// State for the [`DomRef`] view.
+///
+/// The cleanup callback of the current node runs when this is dropped, i.e. when the
+/// view is torn down.
+pub struct DomRefState<S> {
+    child_id: Id,
+    child_state: S,
+    node: web_sys::Element,
+    cleanup: Option<Box<dyn FnOnce()>>,
+}
+
+impl<S> DomRefState<S> {
+    fn cleanup(&mut self) {
+        if let Some(cleanup) = self.cleanup.take() {
+            cleanup();
+        }
+    }
+}
+
+impl<S> Drop for DomRefState<S> {
+    fn drop(&mut self) {
+        self.cleanup();
+    }
+}
+
+/// Call the callback once the node is in the document, i.e. after the parent is done
+/// building or rebuilding.
+fn attach_later<N: DomNode>(cx: &Cx, element: &N) -> web_sys::Element {
+    let node = element
+        .as_node_ref()
+        .unchecked_ref::<web_sys::Element>()
+        .clone();
+    push_message_later(cx, Attached(node.clone()));
+    node
+}
+
+impl<E, T, A, F> ViewMarker for DomRef<E, T, A, F> {}
+impl<E, T, A, F> Sealed for DomRef<E, T, A, F> {}
+
+impl<E, T, A, F, C> View<T, A> for DomRef<E, T, A, F>
+where
+    E: Element<T, A>,
+    F: Fn(web_sys::Element) -> C,
+    C: FnOnce() + 'static,
+{
+    type State = DomRefState<E::State>;
+
+    type Element = E::Element;
+
+    fn build(&self, cx: &mut Cx) -> (Id, Self::State, Self::Element) {
+        let (id, (element, state)) = cx.with_new_id(|cx| {
+            let (child_id, child_state, element) = self.element.build(cx);
+            let node = attach_later(cx, &element);
+            let state = DomRefState {
+                child_id,
+                child_state,
+                node,
+                cleanup: None,
+            };
+            (element, state)
+        });
+        (id, state, element)
+    }
+
+    fn rebuild(
+        &self,
+        cx: &mut Cx,
+        prev: &Self,
+        id: &mut Id,
+        state: &mut Self::State,
+        element: &mut Self::Element,
+    ) -> ChangeFlags {
+        cx.with_id(*id, |cx| {
+            let changed = self.element.rebuild(
+                cx,
+                &prev.element,
+                &mut state.child_id,
+                &mut state.child_state,
+                element,
+            );
+            if changed.contains(ChangeFlags::STRUCTURE) {
+                // The node was recreated, so the old one is gone
+                state.cleanup();
+                state.node = attach_later(cx, element);
+            }
+            changed
+        })
+    }
+
+    fn message(
+        &self,
+        id_path: &[Id],
+        state: &mut Self::State,
+        message: Box<dyn Any>,
+        app_state: &mut T,
+    ) -> MessageResult<A> {
+        match id_path {
+            [] if message.downcast_ref::<Attached>().is_some() => {
+                let Attached(node) = *message.downcast::<Attached>().unwrap();
+                // The node may have been recreated again since then
+                if node != state.node {
+                    return MessageResult::Nop;
+                }
+                state.cleanup();
+                state.cleanup = Some(Box::new((self.on_attach)(node)));
+                MessageResult::Nop
+            }
+            [element_id, rest_path @ ..] if *element_id == state.child_id => {
+                self.element
+                    .message(rest_path, &mut state.child_state, message, app_state)
+            }
+            _ => MessageResult::Stale(message),
+        }
+    }
+}
+
+crate::interfaces::impl_dom_interfaces_for_ty!(
+    Element,
+    DomRef,
+    vars: <F, C,>,
+    vars_on_ty: <F,>,
+    bounds: {
+        F: Fn(web_sys::Element) -> C,
+        C: FnOnce() + 'static,
+    }
+);
@@ -62,6 +62,9 @@ struct ChildrenSplice<'a, 'b, 'c> {
     parent: &'c web_sys::Node,
     node_list: Option<web_sys::NodeList>,
     prev_element_count: usize,
+    /// The number of old elements after the current index, which haven't been mutated
+    /// or deleted yet.
+    old_elements_left: usize,
 }
 
 impl<'a, 'b, 'c> ChildrenSplice<'a, 'b, 'c> {
@@ -77,20 +80,36 @@ impl<'a, 'b, 'c> ChildrenSplice<'a, 'b, 'c> {
             parent,
             node_list: None,
             prev_element_count,
+            old_elements_left: prev_element_count,
         }
     }
 }
 
 impl<'a, 'b, 'c> ElementsSplice for ChildrenSplice<'a, 'b, 'c> {
     fn push(&mut self, element: Pod, _cx: &mut Cx) {
-        self.parent
-            .append_child(element.0.as_node_ref())
-            .unwrap_throw();
+        if self.old_elements_left > 0 {
+            // The element is inserted in the middle, e.g. by a view sequence which has grown
+            let node_list = if let Some(node_list) = &self.node_list {
+                node_list
+            } else {
+                self.node_list = Some(self.parent.child_nodes());
+                self.node_list.as_ref().unwrap()
+            };
+            let next_child = node_list.get(self.child_idx);
+            self.parent
+                .insert_before(element.0.as_node_ref(), next_child.as_ref())
+                .unwrap_throw();
+        } else {
+            self.parent
+                .append_child(element.0.as_node_ref())
+                .unwrap_throw();
+        }
         self.child_idx += 1;
         self.children.push(element);
     }
 
     fn mutate(&mut self, _cx: &mut Cx) -> &mut Pod {
+        self.old_elements_left -= 1;
         self.children.mutate()
     }
 
@@ -111,6 +130,7 @@ impl<'a, 'b, 'c> ElementsSplice for ChildrenSplice<'a, 'b, 'c> {
                 self.parent.remove_child(&child).unwrap_throw();
             }
         }
+        self.old_elements_left -= n;
         self.children.delete(n);
     }
 
@@ -0,0 +1,204 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! An app-wide channel for errors, e.g. from background tasks, and a view displaying them.
+
+use std::{any::Any, cell::RefCell, fmt::Display, marker::PhantomData, rc::Rc};
+
+use gloo::events::EventListener;
+use wasm_bindgen::{JsCast, UnwrapThrowExt};
+use xilem_core::{Id, MessageResult};
+
+use crate::{context::MessageThunk, ChangeFlags, Cx, View, ViewMarker};
+
+struct ErrorReported(String);
+
+#[derive(Default)]
+struct ErrorChannel {
+    /// The [`ErrorToasts`] view errors are routed to.
+    target: Option<MessageThunk>,
+    /// Errors reported before any [`ErrorToasts`] view was built.
+    pending: Vec<String>,
+}
+
+/// A handle to report errors to the app-wide error channel.
+///
+/// Errors reported here are displayed by the [`error_toasts`] view. A sink can be obtained
+/// with [`Cx::error_sink`] or [`App::error_sink`](crate::App::error_sink), and can be
+/// cloned and moved into futures, so that background tasks don't need to thread their
+/// errors through the app state.
+#[derive(Clone, Default)]
+pub struct ErrorSink(Rc<RefCell<ErrorChannel>>);
+
+impl ErrorSink {
+    /// Report an error to the app.
+    ///
+    /// If no [`error_toasts`] view has been built yet, the error is kept until one is.
+    pub fn report(&self, error: impl Display) {
+        let message = error.to_string();
+        let target = self.0.borrow().target.clone();
+        match target {
+            Some(thunk) => push_message_later(thunk, ErrorReported(message)),
+            None => self.0.borrow_mut().pending.push(message),
+        }
+    }
+
+    fn set_target(&self, thunk: MessageThunk) {
+        let mut channel = self.0.borrow_mut();
+        for message in channel.pending.drain(..) {
+            push_message_later(thunk.clone(), ErrorReported(message));
+        }
+        channel.target = Some(thunk);
+    }
+}
+
+/// Errors may be reported while the app is handling a message, so they're delivered
+/// once it's done.
+fn push_message_later(thunk: MessageThunk, message: impl Any + 'static) {
+    wasm_bindgen_futures::spawn_local(async move { thunk.push_message(message) });
+}
+
+/// Displays the errors reported to the app's [`ErrorSink`].
+///
+/// The element is a `div` with the class `xilem-error-toasts`, containing a `div` with
+/// the class `xilem-error-toast` for every error. Clicking a toast dismisses it.
+///
+/// There should be only one of these views in the app, usually near the root; errors are
+/// routed to the one built most recently.
+pub fn error_toasts<T, A>() -> ErrorToasts<T, A> {
+    ErrorToasts {
+        phantom: PhantomData,
+    }
+}
+
+pub struct ErrorToasts<T, A> {
+    phantom: PhantomData<fn() -> (T, A)>,
+}
+
+pub struct ErrorToastsState {
+    container: web_sys::HtmlDivElement,
+    #[allow(unused)]
+    dismiss_listener: EventListener,
+}
+
+impl<T, A> ViewMarker for ErrorToasts<T, A> {}
+
+impl<T, A> View<T, A> for ErrorToasts<T, A> {
+    type State = ErrorToastsState;
+
+    type Element = web_sys::HtmlDivElement;
+
+    fn build(&self, cx: &mut Cx) -> (Id, Self::State, Self::Element) {
+        let (id, element) = cx.with_new_id(|cx| {
+            cx.error_sink().set_target(cx.message_thunk());
+            cx.document()
+                .create_element("div")
+                .unwrap_throw()
+                .unchecked_into::<web_sys::HtmlDivElement>()
+        });
+        element.set_class_name("xilem-error-toasts");
+        let dismiss_listener = EventListener::new(&element, "click", |event| {
+            let toast = event
+                .target()
+                .and_then(|target| target.dyn_into::<web_sys::Element>().ok())
+                .and_then(|target| target.closest(".xilem-error-toast").ok().flatten());
+            if let Some(toast) = toast {
+                toast.remove();
+            }
+        });
+        let state = ErrorToastsState {
+            container: element.clone(),
+            dismiss_listener,
+        };
+        (id, state, element)
+    }
+
+    fn rebuild(
+        &self,
+        _cx: &mut Cx,
+        _prev: &Self,
+        _id: &mut Id,
+        _state: &mut Self::State,
+        _element: &mut Self::Element,
+    ) -> ChangeFlags {
+        ChangeFlags::empty()
+    }
+
+    fn message(
+        &self,
+        id_path: &[Id],
+        state: &mut Self::State,
+        message: Box<dyn Any>,
+        _app_state: &mut T,
+    ) -> MessageResult<A> {
+        match id_path {
+            [] if message.downcast_ref::<ErrorReported>().is_some() => {
+                let ErrorReported(error) = *message.downcast::<ErrorReported>().unwrap();
+                let toast = crate::document().create_element("div").unwrap_throw();
+                toast.set_class_name("xilem-error-toast");
+                toast.set_text_content(Some(&error));
+                state.container.append_child(&toast).unwrap_throw();
+                MessageResult::Nop
+            }
+            _ => MessageResult::Stale(message),
+        }
+    }
+}
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod tests {
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use super::*;
+    use crate::test_util::Recorder;
+
+    wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+    /// Wait until the spawned tasks, and the messages they deferred, are done.
+    async fn settle() {
+        gloo_timers::future::TimeoutFuture::new(0).await;
+    }
+
+    fn toasts(element: &web_sys::HtmlDivElement) -> Vec<String> {
+        let toasts = element.query_selector_all(".xilem-error-toast").unwrap();
+        (0..toasts.length())
+            .map(|i| toasts.get(i).unwrap().text_content().unwrap())
+            .collect()
+    }
+
+    #[wasm_bindgen_test]
+    async fn errors_from_tasks_are_shown() {
+        let recorder = Recorder::default();
+        let mut cx = recorder.cx();
+        let view = error_toasts::<(), ()>();
+        let (_, mut state, element) = view.build(&mut cx);
+
+        let sink = cx.error_sink();
+        wasm_bindgen_futures::spawn_local(async move {
+            settle().await;
+            sink.report("Couldn't save the document");
+        });
+        settle().await;
+        settle().await;
+        recorder.deliver(&view, &mut state, &mut ());
+        assert_eq!(toasts(&element), ["Couldn't save the document"]);
+
+        // Clicking a toast dismisses it.
+        let toast = element.first_element_child().unwrap();
+        toast.unchecked_ref::<web_sys::HtmlElement>().click();
+        assert!(toasts(&element).is_empty());
+    }
+
+    #[wasm_bindgen_test]
+    async fn errors_reported_before_build_are_kept() {
+        let recorder = Recorder::default();
+        let mut cx = recorder.cx();
+        cx.error_sink().report("Offline");
+
+        let view = error_toasts::<(), ()>();
+        let (_, mut state, element) = view.build(&mut cx);
+        settle().await;
+        recorder.deliver(&view, &mut state, &mut ());
+        assert_eq!(toasts(&element), ["Offline"]);
+    }
+}
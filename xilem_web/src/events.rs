@@ -8,7 +8,7 @@ use crate::{
 };
 use std::{any::Any, borrow::Cow, marker::PhantomData};
 use wasm_bindgen::{JsCast, UnwrapThrowExt};
-use xilem_core::{Id, MessageResult};
+use xilem_core::{Id, MessageOrigin, MessageResult};
 
 pub use gloo::events::EventListenerOptions;
 
@@ -20,6 +20,7 @@ pub struct OnEvent<E, T, A, Ev, C> {
     pub(crate) event: Cow<'static, str>,
     pub(crate) options: EventListenerOptions,
     pub(crate) handler: C,
+    pub(crate) origin: MessageOrigin,
     #[allow(clippy::type_complexity)]
     pub(crate) phantom_event_ty: PhantomData<fn() -> (T, A, Ev)>,
 }
@@ -28,16 +29,21 @@ impl<E, T, A, Ev, C> OnEvent<E, T, A, Ev, C>
 where
     Ev: JsCast + 'static,
 {
+    /// Create an event handler view, whose messages come from the caller, see
+    /// [`MessageOrigin`].
+    #[track_caller]
     pub fn new(element: E, event: impl Into<Cow<'static, str>>, handler: C) -> Self {
         OnEvent {
             element,
             event: event.into(),
             options: Default::default(),
             handler,
+            origin: MessageOrigin::caller(),
             phantom_event_ty: PhantomData,
         }
     }
 
+    #[track_caller]
     pub fn new_with_options(
         element: E,
         event: impl Into<Cow<'static, str>>,
@@ -49,6 +55,7 @@ where
             event: event.into(),
             options,
             handler,
+            origin: MessageOrigin::caller(),
             phantom_event_ty: PhantomData,
         }
     }
@@ -68,9 +75,10 @@ fn create_event_listener<Ev: JsCast + 'static>(
     target: &web_sys::EventTarget,
     event: impl Into<Cow<'static, str>>,
     options: EventListenerOptions,
+    origin: MessageOrigin,
     cx: &Cx,
 ) -> gloo::events::EventListener {
-    let thunk = cx.message_thunk();
+    let thunk = cx.message_thunk().with_origin(origin);
     gloo::events::EventListener::new_with_options(
         target,
         event,
@@ -111,6 +119,7 @@ where
                 element.as_node_ref(),
                 self.event.clone(),
                 self.options,
+                self.origin,
                 cx,
             );
             let state = OnEventState {
@@ -149,6 +158,7 @@ where
                     element.as_node_ref(),
                     self.event.clone(),
                     self.options,
+                    self.origin,
                     cx,
                 );
                 changed |= ChangeFlags::OTHER_CHANGE;
@@ -211,15 +221,18 @@ macro_rules! event_definitions {
             target: E,
             callback: C,
             options: EventListenerOptions,
+            origin: MessageOrigin,
             phantom: PhantomData<fn() -> (T, A)>,
         }
 
         impl<E, T, A, C> $ty_name<E, T, A, C> {
+            #[track_caller]
             pub fn new(target: E, callback: C) -> Self {
                 Self {
                     target,
                     options: Default::default(),
                     callback,
+                    origin: MessageOrigin::caller(),
                     phantom: PhantomData,
                 }
             }
@@ -251,7 +264,7 @@ macro_rules! event_definitions {
             fn build(&self, cx: &mut Cx) -> (Id, Self::State, Self::Element) {
                 let (id, (element, state)) = cx.with_new_id(|cx| {
                     let (child_id, child_state, el) = self.target.build(cx);
-                    let listener = create_event_listener::<web_sys::$web_sys_ty>(el.as_node_ref(), $event_name, self.options, cx);
+                    let listener = create_event_listener::<web_sys::$web_sys_ty>(el.as_node_ref(), $event_name, self.options, self.origin, cx);
                     (el, OnEventState { child_state, child_id, listener })
                 });
                 (id, state, element)
@@ -273,7 +286,7 @@ macro_rules! event_definitions {
                     }
                     // TODO check equality of prev and current element somehow
                     if changed.contains(ChangeFlags::STRUCTURE) {
-                        state.listener = create_event_listener::<web_sys::$web_sys_ty>(element.as_node_ref(), $event_name, self.options, cx);
+                        state.listener = create_event_listener::<web_sys::$web_sys_ty>(element.as_node_ref(), $event_name, self.options, self.origin, cx);
                         changed |= ChangeFlags::OTHER_CHANGE;
                     }
                     changed
@@ -382,3 +395,37 @@ event_definitions!(
     (OnWaiting, "waiting", Event),
     (OnWheel, "wheel", WheelEvent)
 );
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod tests {
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use super::*;
+    use crate::{elements::html::button, test_util::Recorder};
+
+    wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn messages_come_from_the_handler_registration() {
+        let recorder = Recorder::default();
+        let mut cx = recorder.cx();
+        let line = line!() + 1;
+        let view = button::<u32, (), _>("+1").on_click(|count: &mut u32, _| *count += 1);
+        let (_, mut state, element) = view.build(&mut cx);
+
+        element.unchecked_ref::<web_sys::HtmlElement>().click();
+        let message = recorder.0.borrow_mut().pop().unwrap();
+        #[cfg(debug_assertions)]
+        {
+            let location = message.origin.location().unwrap();
+            assert_eq!((location.file(), location.line()), (file!(), line));
+        }
+        #[cfg(not(debug_assertions))]
+        let _ = line;
+
+        // The origin doesn't change how the message is handled.
+        let mut count = 0;
+        view.message(&message.id_path[1..], &mut state, message.body, &mut count);
+        assert_eq!(count, 1);
+    }
+}
@@ -0,0 +1,223 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Modifiers focusing elements or scrolling them into view, see
+//! [`HtmlElement::autofocus`] and [`Element::scroll_into_view_when`].
+
+use std::{any::Any, marker::PhantomData};
+
+use wasm_bindgen::JsCast;
+use xilem_core::{Id, MessageResult};
+
+use crate::{
+    interfaces::{sealed::Sealed, Element, HtmlElement},
+    view::DomNode,
+    ChangeFlags, Cx, View, ViewMarker,
+};
+
+/// Run `f` with the node of `element` once the current build/rebuild is over, i.e. once
+/// the node was inserted in the document, if it's going to be.
+fn with_node_later<N: DomNode>(element: &N, f: impl FnOnce(web_sys::Element) + 'static) {
+    let node = element
+        .as_node_ref()
+        .unchecked_ref::<web_sys::Element>()
+        .clone();
+    wasm_bindgen_futures::spawn_local(async move { f(node) });
+}
+
+/// Focuses the wrapped element when it's added to the document, see
+/// [`HtmlElement::autofocus`].
+pub struct Autofocus<E, T, A> {
+    pub(crate) element: E,
+    pub(crate) phantom: PhantomData<fn() -> (T, A)>,
+}
+
+fn focus_later<N: DomNode>(element: &N) {
+    with_node_later(element, |node| {
+        // The element may not be in the document, e.g. if it's kept aside by the app.
+        if node.is_connected() {
+            let _ = node.unchecked_ref::<web_sys::HtmlElement>().focus();
+        }
+    });
+}
+
+impl<E, T, A> ViewMarker for Autofocus<E, T, A> {}
+impl<E, T, A> Sealed for Autofocus<E, T, A> {}
+
+impl<E: HtmlElement<T, A>, T, A> View<T, A> for Autofocus<E, T, A> {
+    type State = E::State;
+
+    type Element = E::Element;
+
+    fn build(&self, cx: &mut Cx) -> (Id, Self::State, Self::Element) {
+        let (id, state, element) = self.element.build(cx);
+        focus_later(&element);
+        (id, state, element)
+    }
+
+    fn rebuild(
+        &self,
+        cx: &mut Cx,
+        prev: &Self,
+        id: &mut Id,
+        state: &mut Self::State,
+        element: &mut Self::Element,
+    ) -> ChangeFlags {
+        let changed = self.element.rebuild(cx, &prev.element, id, state, element);
+        // Only a new node is focused, so the user can move the focus elsewhere.
+        if changed.contains(ChangeFlags::STRUCTURE) {
+            focus_later(element);
+        }
+        changed
+    }
+
+    fn message(
+        &self,
+        id_path: &[Id],
+        state: &mut Self::State,
+        message: Box<dyn Any>,
+        app_state: &mut T,
+    ) -> MessageResult<A> {
+        self.element.message(id_path, state, message, app_state)
+    }
+}
+
+crate::interfaces::impl_dom_interfaces_for_ty!(HtmlElement, Autofocus);
+
+/// Scrolls the wrapped element into view when a condition becomes true, see
+/// [`Element::scroll_into_view_when`].
+pub struct ScrollIntoView<E, T, A> {
+    pub(crate) element: E,
+    pub(crate) condition: bool,
+    pub(crate) phantom: PhantomData<fn() -> (T, A)>,
+}
+
+fn scroll_into_view_later<N: DomNode>(element: &N) {
+    with_node_later(element, |node| {
+        if node.is_connected() {
+            node.scroll_into_view();
+        }
+    });
+}
+
+impl<E, T, A> ViewMarker for ScrollIntoView<E, T, A> {}
+impl<E, T, A> Sealed for ScrollIntoView<E, T, A> {}
+
+impl<E: Element<T, A>, T, A> View<T, A> for ScrollIntoView<E, T, A> {
+    type State = E::State;
+
+    type Element = E::Element;
+
+    fn build(&self, cx: &mut Cx) -> (Id, Self::State, Self::Element) {
+        let (id, state, element) = self.element.build(cx);
+        if self.condition {
+            scroll_into_view_later(&element);
+        }
+        (id, state, element)
+    }
+
+    fn rebuild(
+        &self,
+        cx: &mut Cx,
+        prev: &Self,
+        id: &mut Id,
+        state: &mut Self::State,
+        element: &mut Self::Element,
+    ) -> ChangeFlags {
+        let changed = self.element.rebuild(cx, &prev.element, id, state, element);
+        let became_true = self.condition && !prev.condition;
+        if became_true || (self.condition && changed.contains(ChangeFlags::STRUCTURE)) {
+            scroll_into_view_later(element);
+        }
+        changed
+    }
+
+    fn message(
+        &self,
+        id_path: &[Id],
+        state: &mut Self::State,
+        message: Box<dyn Any>,
+        app_state: &mut T,
+    ) -> MessageResult<A> {
+        self.element.message(id_path, state, message, app_state)
+    }
+}
+
+crate::interfaces::impl_dom_interfaces_for_ty!(Element, ScrollIntoView);
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod tests {
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use super::*;
+    use crate::{
+        elements::html::{div, input},
+        test_util::next_tick,
+    };
+
+    wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+    fn is_focused(element: &web_sys::HtmlInputElement) -> bool {
+        crate::document().active_element().as_ref() == Some(element.unchecked_ref())
+    }
+
+    #[wasm_bindgen_test]
+    async fn autofocus_focuses_on_mount_only() {
+        let mut cx = Cx::new();
+        let view = input::<(), (), _>(()).autofocus();
+        let (mut id, mut state, mut element) = view.build(&mut cx);
+        crate::document_body().append_child(&element).unwrap();
+        next_tick().await;
+        assert!(is_focused(&element));
+
+        // Rebuilding doesn't take the focus back.
+        element.blur().unwrap();
+        let next = input::<(), (), _>(()).autofocus();
+        next.rebuild(&mut cx, &view, &mut id, &mut state, &mut element);
+        next_tick().await;
+        assert!(!is_focused(&element));
+        element.remove();
+    }
+
+    #[wasm_bindgen_test]
+    async fn autofocus_ignores_detached_elements() {
+        let mut cx = Cx::new();
+        let view = input::<(), (), _>(()).autofocus();
+        let (_, _, element) = view.build(&mut cx);
+        next_tick().await;
+        assert!(!element.is_connected());
+        assert!(!is_focused(&element));
+    }
+
+    #[wasm_bindgen_test]
+    async fn scroll_into_view_fires_on_transition() {
+        let window = web_sys::window().unwrap();
+        let body = crate::document_body();
+        let spacer = crate::document().create_element("div").unwrap();
+        spacer.set_attribute("style", "height: 10000px").unwrap();
+        body.append_child(&spacer).unwrap();
+        window.scroll_to_with_x_and_y(0.0, 0.0);
+
+        let mut cx = Cx::new();
+        let view = |condition| div::<(), (), _>("target").scroll_into_view_when(condition);
+        let prev = view(false);
+        let (mut id, mut state, mut element) = prev.build(&mut cx);
+        body.append_child(&element).unwrap();
+        next_tick().await;
+        assert_eq!(window.scroll_y().unwrap(), 0.0);
+
+        let next = view(true);
+        next.rebuild(&mut cx, &prev, &mut id, &mut state, &mut element);
+        next_tick().await;
+        assert!(window.scroll_y().unwrap() > 0.0);
+
+        // Staying true doesn't scroll again.
+        window.scroll_to_with_x_and_y(0.0, 0.0);
+        view(true).rebuild(&mut cx, &next, &mut id, &mut state, &mut element);
+        next_tick().await;
+        assert_eq!(window.scroll_y().unwrap(), 0.0);
+
+        element.remove();
+        spacer.remove();
+    }
+}
@@ -0,0 +1,794 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! A `<canvas>` rendered to with the GPU through [wgpu](https://wgpu.rs), using WebGPU
+//! where the browser supports it and WebGL2 otherwise.
+//!
+//! This needs the `gpu` feature, so that apps which don't render with the GPU don't
+//! depend on wgpu.
+//!
+//! The GPU is accessed through the [`GpuBackend`] trait, which can be implemented to fake
+//! it, e.g. in tests.
+
+use std::{
+    any::Any,
+    cell::{Cell, RefCell},
+    fmt,
+    marker::PhantomData,
+    rc::{Rc, Weak},
+};
+
+use gloo::events::EventListener;
+use wasm_bindgen::{closure::Closure, JsCast, UnwrapThrowExt};
+use xilem_core::{Id, MessageResult};
+
+use crate::{context::MessageThunk, ChangeFlags, Cx, OptionalAction, View, ViewMarker};
+
+/// How [`gpu_canvas`] acquires its GPU device.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GpuCanvasConfig {
+    pub power_preference: wgpu::PowerPreference,
+    /// Whether to use WebGL2 when the browser doesn't support WebGPU.
+    pub webgl_fallback: bool,
+    /// The features the device must have.
+    pub required_features: wgpu::Features,
+}
+
+impl Default for GpuCanvasConfig {
+    fn default() -> Self {
+        GpuCanvasConfig {
+            power_preference: wgpu::PowerPreference::default(),
+            webgl_fallback: true,
+            required_features: wgpu::Features::empty(),
+        }
+    }
+}
+
+/// Why a [`gpu_canvas`] has no surface to render to.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GpuError {
+    /// The browser supports neither WebGPU nor WebGL2, or WebGL2 wasn't allowed by the
+    /// [config](GpuCanvasConfig::webgl_fallback).
+    Unsupported,
+    /// No adapter matching the config was found.
+    NoAdapter,
+    /// The adapter refused to create the device, with its explanation.
+    RequestDevice(String),
+    /// The context was lost, e.g. because the GPU driver was reset. The canvas acquires
+    /// a new one right away.
+    ContextLost,
+}
+
+impl fmt::Display for GpuError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GpuError::Unsupported => write!(f, "neither WebGPU nor WebGL2 is supported"),
+            GpuError::NoAdapter => write!(f, "no suitable GPU adapter was found"),
+            GpuError::RequestDevice(message) => {
+                write!(f, "the GPU device couldn't be created: {message}")
+            }
+            GpuError::ContextLost => write!(f, "the GPU context was lost"),
+        }
+    }
+}
+
+impl std::error::Error for GpuError {}
+
+/// The GPU access used by [`gpu_canvas`].
+pub trait GpuBackend: 'static {
+    /// What the canvas renders to, handed to the app and the render callback.
+    type Surface: Clone + 'static;
+
+    /// Acquire a surface rendering to `canvas`.
+    ///
+    /// `on_result` is called with the surface or the error once it's done. If the
+    /// context is lost later on, it is called again with [`GpuError::ContextLost`].
+    fn acquire(
+        &self,
+        canvas: &web_sys::HtmlCanvasElement,
+        config: &GpuCanvasConfig,
+        on_result: Rc<dyn Fn(Result<Self::Surface, GpuError>)>,
+    );
+
+    /// Resize the surface to `width` by `height` physical pixels.
+    fn resize(&self, surface: &Self::Surface, width: u32, height: u32);
+
+    /// Release the device of a surface which isn't used anymore.
+    fn release(&self, surface: &Self::Surface);
+}
+
+/// The backend using wgpu, with the [`SurfaceHandle`] surface.
+pub struct WgpuBackend;
+
+/// A wgpu surface rendering to a [`gpu_canvas`], with the device it's configured for.
+///
+/// Clones refer to the same surface.
+#[derive(Clone)]
+pub struct SurfaceHandle(Rc<WgpuSurface>);
+
+struct WgpuSurface {
+    surface: wgpu::Surface<'static>,
+    adapter: wgpu::Adapter,
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    config: RefCell<wgpu::SurfaceConfiguration>,
+    lost: Cell<bool>,
+    on_lost: Box<dyn Fn()>,
+    // Only kept to be dropped with the surface.
+    #[allow(unused)]
+    context_lost_listener: Option<EventListener>,
+}
+
+impl SurfaceHandle {
+    pub fn surface(&self) -> &wgpu::Surface<'static> {
+        &self.0.surface
+    }
+
+    pub fn adapter(&self) -> &wgpu::Adapter {
+        &self.0.adapter
+    }
+
+    pub fn device(&self) -> &wgpu::Device {
+        &self.0.device
+    }
+
+    pub fn queue(&self) -> &wgpu::Queue {
+        &self.0.queue
+    }
+
+    /// The configuration of the surface, which is updated when the canvas is resized.
+    pub fn config(&self) -> wgpu::SurfaceConfiguration {
+        self.0.config.borrow().clone()
+    }
+
+    /// Whether the surface renders with WebGL2 rather than WebGPU.
+    pub fn is_webgl(&self) -> bool {
+        self.0.adapter.get_info().backend == wgpu::Backend::Gl
+    }
+
+    /// The texture to render the next frame to, or `None` if there's none, e.g. because
+    /// the context was lost.
+    ///
+    /// A lost context is reported to the canvas, which acquires a new one.
+    pub fn current_texture(&self) -> Option<wgpu::SurfaceTexture> {
+        if self.0.lost.get() {
+            return None;
+        }
+        match self.0.surface.get_current_texture() {
+            Ok(texture) => Some(texture),
+            Err(wgpu::SurfaceError::Lost) => {
+                self.0.report_lost();
+                None
+            }
+            Err(error) => {
+                log::warn!("Couldn't get the texture of a GPU canvas: {error}");
+                None
+            }
+        }
+    }
+}
+
+impl WgpuSurface {
+    fn report_lost(&self) {
+        if !self.lost.replace(true) {
+            (self.on_lost)();
+        }
+    }
+}
+
+fn webgpu_supported() -> bool {
+    let navigator = web_sys::window().unwrap_throw().navigator();
+    js_sys::Reflect::get(&navigator, &"gpu".into()).is_ok_and(|gpu| !gpu.is_undefined())
+}
+
+fn create_surface(
+    instance: &wgpu::Instance,
+    canvas: web_sys::HtmlCanvasElement,
+) -> Result<wgpu::Surface<'static>, GpuError> {
+    #[cfg(target_arch = "wasm32")]
+    return instance
+        .create_surface(wgpu::SurfaceTarget::Canvas(canvas))
+        .map_err(|_| GpuError::Unsupported);
+    // wgpu only renders to canvases on the web, this keeps native builds of the workspace working.
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let _ = (instance, canvas);
+        Err(GpuError::Unsupported)
+    }
+}
+
+/// Find an adapter, with a surface configured for it.
+///
+/// WebGPU is checked first without a surface, since a canvas can't switch to WebGL2
+/// once it has a WebGPU context.
+async fn request_surface(
+    canvas: web_sys::HtmlCanvasElement,
+    config: &GpuCanvasConfig,
+) -> Result<(wgpu::Surface<'static>, wgpu::Adapter), GpuError> {
+    if webgpu_supported() {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+            backends: wgpu::Backends::BROWSER_WEBGPU,
+            ..Default::default()
+        });
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: config.power_preference,
+                force_fallback_adapter: false,
+                compatible_surface: None,
+            })
+            .await;
+        if let Some(adapter) = adapter {
+            let surface = create_surface(&instance, canvas)?;
+            return Ok((surface, adapter));
+        }
+    }
+    if !config.webgl_fallback {
+        return Err(GpuError::Unsupported);
+    }
+    let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+        backends: wgpu::Backends::GL,
+        ..Default::default()
+    });
+    // WebGL2 adapters need the surface they render to.
+    let surface = create_surface(&instance, canvas)?;
+    let adapter = instance
+        .request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: config.power_preference,
+            force_fallback_adapter: false,
+            compatible_surface: Some(&surface),
+        })
+        .await
+        .ok_or(GpuError::NoAdapter)?;
+    Ok((surface, adapter))
+}
+
+impl GpuBackend for WgpuBackend {
+    type Surface = SurfaceHandle;
+
+    fn acquire(
+        &self,
+        canvas: &web_sys::HtmlCanvasElement,
+        config: &GpuCanvasConfig,
+        on_result: Rc<dyn Fn(Result<SurfaceHandle, GpuError>)>,
+    ) {
+        let canvas = canvas.clone();
+        let config = config.clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            let result = async {
+                let (surface, adapter) = request_surface(canvas.clone(), &config).await?;
+                let required_limits = if adapter.get_info().backend == wgpu::Backend::Gl {
+                    wgpu::Limits::downlevel_webgl2_defaults().using_resolution(adapter.limits())
+                } else {
+                    wgpu::Limits::default().using_resolution(adapter.limits())
+                };
+                let (device, queue) = adapter
+                    .request_device(
+                        &wgpu::DeviceDescriptor {
+                            label: Some("gpu_canvas"),
+                            required_features: config.required_features,
+                            required_limits,
+                        },
+                        None,
+                    )
+                    .await
+                    .map_err(|error| GpuError::RequestDevice(error.to_string()))?;
+                let surface_config = surface
+                    .get_default_config(&adapter, canvas.width().max(1), canvas.height().max(1))
+                    .ok_or(GpuError::Unsupported)?;
+                surface.configure(&device, &surface_config);
+                Ok::<_, GpuError>((surface, adapter, device, queue, surface_config))
+            }
+            .await;
+            let handle = result.map(|(surface, adapter, device, queue, surface_config)| {
+                let is_webgl = adapter.get_info().backend == wgpu::Backend::Gl;
+                SurfaceHandle(Rc::new_cyclic(|weak: &Weak<WgpuSurface>| {
+                    // WebGPU reports a lost device when getting the next texture, but
+                    // WebGL2 only through this event.
+                    let context_lost_listener = is_webgl.then(|| {
+                        let weak = weak.clone();
+                        EventListener::new(&canvas, "webglcontextlost", move |_| {
+                            if let Some(surface) = weak.upgrade() {
+                                surface.report_lost();
+                            }
+                        })
+                    });
+                    let on_lost = {
+                        let on_result = on_result.clone();
+                        Box::new(move || on_result(Err(GpuError::ContextLost)))
+                    };
+                    WgpuSurface {
+                        surface,
+                        adapter,
+                        device,
+                        queue,
+                        config: RefCell::new(surface_config),
+                        lost: Cell::new(false),
+                        on_lost,
+                        context_lost_listener,
+                    }
+                }))
+            });
+            on_result(handle);
+        });
+    }
+
+    fn resize(&self, surface: &SurfaceHandle, width: u32, height: u32) {
+        let mut config = surface.0.config.borrow_mut();
+        config.width = width.max(1);
+        config.height = height.max(1);
+        surface.0.surface.configure(&surface.0.device, &config);
+    }
+
+    fn release(&self, surface: &SurfaceHandle) {
+        // Stop reporting the loss of the device being destroyed.
+        surface.0.lost.set(true);
+        surface.0.device.destroy();
+    }
+}
+
+type ReadyHandler<T, A, S> = Box<dyn Fn(&mut T, Result<S, GpuError>) -> Option<A>>;
+type RenderCallback<S, R> = Box<dyn Fn(&S, &R)>;
+
+/// A `<canvas>` rendered to with the GPU, e.g. for a 3D preview.
+///
+/// When the view is built, the canvas acquires a GPU device as configured by `config`,
+/// which takes a while. `on_ready` is then called with the surface to create the
+/// pipelines and buffers with, or with the error if there's none. If the context is lost
+/// later on, `on_ready` is called with [`GpuError::ContextLost`], and again once a new
+/// surface was acquired.
+///
+/// The [render callback](GpuCanvas::on_render) is called with the surface and
+/// `render_state` once the surface is ready, whenever `render_state` changes or the canvas
+/// is resized, and on every animation frame if the canvas [animates](GpuCanvas::animate).
+/// The canvas fills its parent element, which should be sized with CSS, and its drawing
+/// buffer follows its size in physical pixels. The device is released when the view is
+/// torn down.
+///
+/// ```ignore
+/// gpu_canvas(
+///     GpuCanvasConfig::default(),
+///     |state: &mut AppState, surface| state.renderer = surface.ok().map(Renderer::new),
+///     state.rotation,
+/// )
+/// .on_render(move |surface, rotation| renderer.render(surface, *rotation))
+/// ```
+pub fn gpu_canvas<T, A, R, F, OA>(
+    config: GpuCanvasConfig,
+    on_ready: F,
+    render_state: R,
+) -> GpuCanvas<T, A, R, WgpuBackend>
+where
+    R: PartialEq,
+    F: Fn(&mut T, Result<SurfaceHandle, GpuError>) -> OA + 'static,
+    OA: OptionalAction<A>,
+{
+    GpuCanvas::new(WgpuBackend, config, on_ready, render_state)
+}
+
+pub struct GpuCanvas<T, A, R, B: GpuBackend> {
+    config: GpuCanvasConfig,
+    on_ready: ReadyHandler<T, A, B::Surface>,
+    render_state: R,
+    render: Option<RenderCallback<B::Surface, R>>,
+    animate: bool,
+    backend: Rc<B>,
+    phantom: PhantomData<fn() -> (T, A)>,
+}
+
+impl<T, A, R, B: GpuBackend> GpuCanvas<T, A, R, B> {
+    /// A canvas like [`gpu_canvas`], using `backend` instead of wgpu.
+    pub fn new<F, OA>(backend: B, config: GpuCanvasConfig, on_ready: F, render_state: R) -> Self
+    where
+        F: Fn(&mut T, Result<B::Surface, GpuError>) -> OA + 'static,
+        OA: OptionalAction<A>,
+    {
+        GpuCanvas {
+            config,
+            on_ready: Box::new(move |app_state, result| on_ready(app_state, result).action()),
+            render_state,
+            render: None,
+            animate: false,
+            backend: Rc::new(backend),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Render a frame to `surface` from `render_state`.
+    pub fn on_render(mut self, render: impl Fn(&B::Surface, &R) + 'static) -> Self {
+        self.render = Some(Box::new(render));
+        self
+    }
+
+    /// Whether to render on every animation frame, e.g. for a rotating model, rather than
+    /// only when `render_state` changes.
+    pub fn animate(mut self, animate: bool) -> Self {
+        self.animate = animate;
+        self
+    }
+
+    fn render(&self, state: &GpuCanvasState<B>) {
+        if let (Some(render), Some(surface)) = (&self.render, &state.surface) {
+            render(surface, &self.render_state);
+        }
+    }
+
+    fn acquire(&self, state: &GpuCanvasState<B>) {
+        let thunk = state.thunk.clone();
+        self.backend.acquire(
+            &state.canvas,
+            &self.config,
+            // The result is delivered later, as the app may be in the middle of a rebuild.
+            Rc::new(move |result| {
+                let thunk = thunk.clone();
+                wasm_bindgen_futures::spawn_local(async move {
+                    thunk.push_message(Acquired(result));
+                });
+            }),
+        );
+    }
+}
+
+/// The callback of the next animation frame, which schedules the one after.
+type FrameCallback = Rc<RefCell<Option<Closure<dyn FnMut()>>>>;
+
+/// Pushes a [`Frame`] message on every animation frame while it's alive.
+struct AnimationLoop {
+    handle: Rc<Cell<i32>>,
+    callback: FrameCallback,
+}
+
+impl AnimationLoop {
+    fn start(thunk: MessageThunk) -> Self {
+        let window = web_sys::window().unwrap_throw();
+        let handle = Rc::new(Cell::new(0));
+        let callback: FrameCallback = Rc::default();
+        let next_frame = {
+            let window = window.clone();
+            let handle = handle.clone();
+            let callback = Rc::downgrade(&callback);
+            move || {
+                thunk.push_message(Frame);
+                if let Some(callback) = callback.upgrade() {
+                    if let Some(callback) = &*callback.borrow() {
+                        let next =
+                            window.request_animation_frame(callback.as_ref().unchecked_ref());
+                        handle.set(next.unwrap_throw());
+                    }
+                }
+            }
+        };
+        let closure = Closure::<dyn FnMut()>::new(next_frame);
+        handle.set(
+            window
+                .request_animation_frame(closure.as_ref().unchecked_ref())
+                .unwrap_throw(),
+        );
+        *callback.borrow_mut() = Some(closure);
+        AnimationLoop { handle, callback }
+    }
+}
+
+impl Drop for AnimationLoop {
+    fn drop(&mut self) {
+        let _ = web_sys::window()
+            .unwrap_throw()
+            .cancel_animation_frame(self.handle.get());
+        self.callback.borrow_mut().take();
+    }
+}
+
+/// Watches the size of the canvas, pushing [`Resized`] messages.
+struct SizeObserver {
+    observer: web_sys::ResizeObserver,
+    // Only kept to be dropped with the observer.
+    #[allow(unused)]
+    callback: Closure<dyn FnMut(js_sys::Array)>,
+}
+
+impl SizeObserver {
+    fn start(canvas: &web_sys::HtmlCanvasElement, thunk: MessageThunk) -> Self {
+        let callback = Closure::<dyn FnMut(js_sys::Array)>::new(move |entries: js_sys::Array| {
+            let Some(entry) = entries
+                .iter()
+                .last()
+                .and_then(|entry| entry.dyn_into::<web_sys::ResizeObserverEntry>().ok())
+            else {
+                return;
+            };
+            let rect = entry.content_rect();
+            let scale = web_sys::window().unwrap_throw().device_pixel_ratio();
+            let width = (rect.width() * scale).round() as u32;
+            let height = (rect.height() * scale).round() as u32;
+            thunk.push_message(Resized(width, height));
+        });
+        let observer =
+            web_sys::ResizeObserver::new(callback.as_ref().unchecked_ref()).unwrap_throw();
+        observer.observe(canvas);
+        SizeObserver { observer, callback }
+    }
+}
+
+impl Drop for SizeObserver {
+    fn drop(&mut self) {
+        self.observer.disconnect();
+    }
+}
+
+/// State for the [`GpuCanvas`] view.
+///
+/// The surface is released when this is dropped, i.e. when the view is torn down.
+pub struct GpuCanvasState<B: GpuBackend> {
+    canvas: web_sys::HtmlCanvasElement,
+    thunk: MessageThunk,
+    surface: Option<B::Surface>,
+    /// The size of the drawing buffer in physical pixels, once it's known.
+    size: Option<(u32, u32)>,
+    backend: Rc<B>,
+    // Only kept to be dropped with the state.
+    #[allow(unused)]
+    size_observer: SizeObserver,
+    animation: Option<AnimationLoop>,
+}
+
+impl<B: GpuBackend> Drop for GpuCanvasState<B> {
+    fn drop(&mut self) {
+        if let Some(surface) = self.surface.take() {
+            self.backend.release(&surface);
+        }
+    }
+}
+
+struct Acquired<S>(Result<S, GpuError>);
+
+struct Resized(u32, u32);
+
+struct Frame;
+
+impl<T, A, R, B: GpuBackend> ViewMarker for GpuCanvas<T, A, R, B> {}
+
+impl<T, A, R, B> View<T, A> for GpuCanvas<T, A, R, B>
+where
+    R: PartialEq,
+    B: GpuBackend,
+{
+    type State = GpuCanvasState<B>;
+
+    type Element = web_sys::HtmlCanvasElement;
+
+    fn build(&self, cx: &mut Cx) -> (Id, Self::State, Self::Element) {
+        let canvas: web_sys::HtmlCanvasElement = cx
+            .document()
+            .create_element("canvas")
+            .unwrap_throw()
+            .unchecked_into();
+        canvas
+            .set_attribute("style", "display: block; width: 100%; height: 100%")
+            .unwrap_throw();
+        let (id, state) = cx.with_new_id(|cx| {
+            let thunk = cx.message_thunk();
+            GpuCanvasState {
+                canvas: canvas.clone(),
+                size_observer: SizeObserver::start(&canvas, thunk.clone()),
+                animation: self.animate.then(|| AnimationLoop::start(thunk.clone())),
+                thunk,
+                surface: None,
+                size: None,
+                backend: self.backend.clone(),
+            }
+        });
+        self.acquire(&state);
+        (id, state, canvas)
+    }
+
+    fn rebuild(
+        &self,
+        _cx: &mut Cx,
+        prev: &Self,
+        _id: &mut Id,
+        state: &mut Self::State,
+        _element: &mut Self::Element,
+    ) -> ChangeFlags {
+        if self.animate != prev.animate {
+            state.animation = self
+                .animate
+                .then(|| AnimationLoop::start(state.thunk.clone()));
+        }
+        if self.render_state != prev.render_state && !self.animate {
+            self.render(state);
+        }
+        ChangeFlags::empty()
+    }
+
+    fn message(
+        &self,
+        id_path: &[Id],
+        state: &mut Self::State,
+        message: Box<dyn Any>,
+        app_state: &mut T,
+    ) -> MessageResult<A> {
+        if !id_path.is_empty() {
+            return MessageResult::Stale(message);
+        }
+        let message = match message.downcast::<Frame>() {
+            Ok(_) => {
+                self.render(state);
+                return MessageResult::Nop;
+            }
+            Err(message) => message,
+        };
+        let message = match message.downcast::<Resized>() {
+            Ok(resized) => {
+                let Resized(width, height) = *resized;
+                if state.size != Some((width, height)) {
+                    state.size = Some((width, height));
+                    state.canvas.set_width(width);
+                    state.canvas.set_height(height);
+                    if let Some(surface) = &state.surface {
+                        self.backend.resize(surface, width, height);
+                        self.render(state);
+                    }
+                }
+                return MessageResult::Nop;
+            }
+            Err(message) => message,
+        };
+        let result = match message.downcast::<Acquired<B::Surface>>() {
+            Ok(acquired) => acquired.0,
+            Err(message) => return MessageResult::Stale(message),
+        };
+        match &result {
+            Ok(surface) => {
+                if let Some((width, height)) = state.size {
+                    self.backend.resize(surface, width, height);
+                }
+                state.surface = Some(surface.clone());
+            }
+            Err(GpuError::ContextLost) => {
+                if let Some(surface) = state.surface.take() {
+                    self.backend.release(&surface);
+                }
+                self.acquire(state);
+            }
+            Err(error) => log::warn!("{error}"),
+        }
+        let action = (self.on_ready)(app_state, result);
+        // Rendered once the app had the chance to set up its pipelines.
+        self.render(state);
+        match action {
+            Some(a) => MessageResult::Action(a),
+            None => MessageResult::Nop,
+        }
+    }
+}
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod tests {
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use super::*;
+    use crate::test_util::{next_tick, Recorder};
+
+    wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+    type ResultCallback = Rc<dyn Fn(Result<u32, GpuError>)>;
+
+    /// A fake GPU whose surfaces are numbered, logging what is done with them.
+    #[derive(Clone, Default)]
+    struct MockGpu {
+        log: Rc<RefCell<Vec<String>>>,
+        acquired: Rc<Cell<u32>>,
+        on_result: Rc<RefCell<Option<ResultCallback>>>,
+    }
+
+    impl MockGpu {
+        fn log(&self) -> Vec<String> {
+            self.log.borrow_mut().drain(..).collect()
+        }
+
+        fn lose_context(&self) {
+            let on_result = self.on_result.borrow().clone().unwrap();
+            on_result(Err(GpuError::ContextLost));
+        }
+    }
+
+    impl GpuBackend for MockGpu {
+        type Surface = u32;
+
+        fn acquire(
+            &self,
+            _canvas: &web_sys::HtmlCanvasElement,
+            _config: &GpuCanvasConfig,
+            on_result: Rc<dyn Fn(Result<u32, GpuError>)>,
+        ) {
+            self.acquired.set(self.acquired.get() + 1);
+            self.log.borrow_mut().push("acquire".into());
+            *self.on_result.borrow_mut() = Some(on_result.clone());
+            on_result(Ok(self.acquired.get()));
+        }
+
+        fn resize(&self, surface: &u32, width: u32, height: u32) {
+            self.log
+                .borrow_mut()
+                .push(format!("resize {surface} {width}x{height}"));
+        }
+
+        fn release(&self, surface: &u32) {
+            self.log.borrow_mut().push(format!("release {surface}"));
+        }
+    }
+
+    #[derive(Default)]
+    struct AppState {
+        ready: Vec<Result<u32, GpuError>>,
+    }
+
+    fn canvas_view(mock: &MockGpu, frame: u32) -> GpuCanvas<AppState, (), u32, MockGpu> {
+        let log = mock.log.clone();
+        GpuCanvas::new(
+            mock.clone(),
+            GpuCanvasConfig::default(),
+            |state: &mut AppState, surface| state.ready.push(surface),
+            frame,
+        )
+        .on_render(move |surface, frame| {
+            log.borrow_mut().push(format!("render {surface} {frame}"));
+        })
+    }
+
+    #[wasm_bindgen_test]
+    async fn reacquires_lost_contexts() {
+        let recorder = Recorder::default();
+        let mut cx = recorder.cx();
+        let mock = MockGpu::default();
+        let view = canvas_view(&mock, 0);
+        let (_, mut state, _) = view.build(&mut cx);
+        assert_eq!(mock.log(), ["acquire"]);
+
+        // The surface isn't handed out during the build.
+        let mut app_state = AppState::default();
+        recorder.deliver(&view, &mut state, &mut app_state);
+        assert!(app_state.ready.is_empty());
+        next_tick().await;
+        recorder.deliver(&view, &mut state, &mut app_state);
+        assert_eq!(app_state.ready, [Ok(1)]);
+        assert_eq!(mock.log(), ["render 1 0"]);
+
+        view.message(&[], &mut state, Box::new(Resized(300, 150)), &mut app_state);
+        assert_eq!(mock.log(), ["resize 1 300x150", "render 1 0"]);
+        assert_eq!((state.canvas.width(), state.canvas.height()), (300, 150));
+
+        mock.lose_context();
+        next_tick().await;
+        recorder.deliver(&view, &mut state, &mut app_state);
+        assert_eq!(app_state.ready[1], Err(GpuError::ContextLost));
+        assert_eq!(mock.log(), ["release 1", "acquire"]);
+        next_tick().await;
+        recorder.deliver(&view, &mut state, &mut app_state);
+        assert_eq!(app_state.ready[2], Ok(2));
+        // The new surface gets the size of the canvas.
+        assert_eq!(mock.log(), ["resize 2 300x150", "render 2 0"]);
+
+        drop(state);
+        assert_eq!(mock.log(), ["release 2"]);
+    }
+
+    #[wasm_bindgen_test]
+    async fn renders_when_render_state_changes() {
+        let recorder = Recorder::default();
+        let mut cx = recorder.cx();
+        let mock = MockGpu::default();
+        let view = canvas_view(&mock, 0);
+        let (mut id, mut state, mut element) = view.build(&mut cx);
+        next_tick().await;
+        recorder.deliver(&view, &mut state, &mut AppState::default());
+        mock.log();
+
+        let same = canvas_view(&mock, 0);
+        same.rebuild(&mut cx, &view, &mut id, &mut state, &mut element);
+        assert!(mock.log().is_empty());
+
+        let changed = canvas_view(&mock, 1);
+        changed.rebuild(&mut cx, &same, &mut id, &mut state, &mut element);
+        assert_eq!(mock.log(), ["render 1 1"]);
+    }
+}
@@ -3,8 +3,12 @@
 
 use crate::{
     class::{Class, IntoClasses},
+    dom_ref::DomRef,
+    focus::{Autofocus, ScrollIntoView},
+    media::{MediaCommand, MediaControl, OnMedia},
+    pointer::{OnPointer, PointerEventKind},
     style::{IntoStyles, Style},
-    Pointer, PointerMsg, View, ViewMarker,
+    Pointer, PointerDetails, PointerMsg, View, ViewMarker,
 };
 use std::{borrow::Cow, marker::PhantomData};
 
@@ -25,6 +29,7 @@ pub(crate) mod sealed {
 macro_rules! event_handler_mixin {
     ($(($event_ty: ident, $fn_name:ident, $event:expr, $web_sys_event_type:ident),)*) => {
     $(
+        #[track_caller]
         fn $fn_name<EH, OA>(self, handler: EH) -> events::$event_ty<Self, T, A, EH>
         where
             OA: OptionalAction<A>,
@@ -40,6 +45,7 @@ pub trait Element<T, A = ()>: View<T, A> + ViewMarker + sealed::Sealed
 where
     Self: Sized,
 {
+    #[track_caller]
     fn on<E, EH, OA>(
         self,
         event: impl Into<Cow<'static, str>>,
@@ -54,6 +60,7 @@ where
         OnEvent::new(self, event, handler)
     }
 
+    #[track_caller]
     fn on_with_options<Ev, EH, OA>(
         self,
         event: impl Into<Cow<'static, str>>,
@@ -73,6 +80,48 @@ where
         crate::pointer::pointer(self, f)
     }
 
+    /// Call `handler` when a mouse button, finger or pen is pressed on this element.
+    ///
+    /// Use [`capture`](OnPointer::capture) to keep receiving the events of the pointer
+    /// while it's dragged outside of the element.
+    fn on_pointer_down<EH, OA>(self, handler: EH) -> OnPointer<Self, T, A, EH>
+    where
+        OA: OptionalAction<A>,
+        EH: Fn(&mut T, PointerDetails) -> OA,
+    {
+        OnPointer::new(self, PointerEventKind::Down, handler)
+    }
+
+    /// Call `handler` when a mouse, finger or pen moves over this element, or anywhere
+    /// while the element captures it.
+    fn on_pointer_move<EH, OA>(self, handler: EH) -> OnPointer<Self, T, A, EH>
+    where
+        OA: OptionalAction<A>,
+        EH: Fn(&mut T, PointerDetails) -> OA,
+    {
+        OnPointer::new(self, PointerEventKind::Move, handler)
+    }
+
+    /// Call `handler` when a mouse button, finger or pen is released over this element,
+    /// or anywhere while the element captures it.
+    fn on_pointer_up<EH, OA>(self, handler: EH) -> OnPointer<Self, T, A, EH>
+    where
+        OA: OptionalAction<A>,
+        EH: Fn(&mut T, PointerDetails) -> OA,
+    {
+        OnPointer::new(self, PointerEventKind::Up, handler)
+    }
+
+    /// Call `handler` when the browser stops sending the events of a pointer, e.g.
+    /// because a touch started scrolling the page. Drags should be aborted then.
+    fn on_pointer_cancel<EH, OA>(self, handler: EH) -> OnPointer<Self, T, A, EH>
+    where
+        OA: OptionalAction<A>,
+        EH: Fn(&mut T, PointerDetails) -> OA,
+    {
+        OnPointer::new(self, PointerEventKind::Cancel, handler)
+    }
+
     // TODO should the API be "functional" in the sense, that new attributes are wrappers around the type,
     // or should they modify the underlying instance (e.g. via the following methods)?
     // The disadvantage that "functional" brings in, is that elements are not modifiable (i.e. attributes can't be simply added etc.)
@@ -113,11 +162,47 @@ where
         }
     }
 
+    /// Call `on_attach` with the DOM node of this element once it's in the document,
+    /// e.g. to hand it to a JS library that manipulates it imperatively.
+    ///
+    /// `on_attach` returns a cleanup callback, which is called when the view is torn
+    /// down. If the node is recreated, the cleanup callback of the old node is called,
+    /// and `on_attach` is called again with the new node.
+    fn dom_ref<F, C>(self, on_attach: F) -> DomRef<Self, T, A, F>
+    where
+        F: Fn(web_sys::Element) -> C,
+        C: FnOnce() + 'static,
+    {
+        DomRef {
+            element: self,
+            on_attach,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Scroll this element into view when `condition` becomes true, with
+    /// [`scrollIntoView()`](https://developer.mozilla.org/en-US/docs/Web/API/Element/scrollIntoView),
+    /// e.g. for the selected item of a long list.
+    ///
+    /// The element is scrolled into view when it's added to the document while
+    /// `condition` is true, and when `condition` changes from false to true, but not on
+    /// every rebuild while it stays true, so the user is free to scroll away.
+    fn scroll_into_view_when(self, condition: bool) -> ScrollIntoView<Self, T, A> {
+        ScrollIntoView {
+            element: self,
+            condition,
+            phantom: PhantomData,
+        }
+    }
+
     // event list from
     // https://html.spec.whatwg.org/multipage/webappapis.html#idl-definitions
     //
     // I didn't include the events on the window, since we aren't attaching
     // any events to the window in xilem_web
+    //
+    // The media events with a typed payload are handled by `HtmlMediaElement` instead,
+    // e.g. `HtmlMediaElement::on_timeupdate`.
     event_handler_mixin!(
         (OnAbort, on_abort, "abort", Event),
         (OnAuxClick, on_auxclick, "auxclick", PointerEvent),
@@ -150,9 +235,7 @@ where
         (OnDragOver, on_dragover, "dragover", Event),
         (OnDragStart, on_dragstart, "dragstart", Event),
         (OnDrop, on_drop, "drop", Event),
-        (OnDurationChange, on_durationchange, "durationchange", Event),
         (OnEmptied, on_emptied, "emptied", Event),
-        (OnEnded, on_ended, "ended", Event),
         (OnError, on_error, "error", Event),
         (OnFocus, on_focus, "focus", FocusEvent),
         (OnFocusIn, on_focusin, "focusin", FocusEvent),
@@ -174,8 +257,6 @@ where
         (OnMouseOver, on_mouseover, "mouseover", MouseEvent),
         (OnMouseUp, on_mouseup, "mouseup", MouseEvent),
         (OnPaste, on_paste, "paste", Event),
-        (OnPause, on_pause, "pause", Event),
-        (OnPlay, on_play, "play", Event),
         (OnPlaying, on_playing, "playing", Event),
         (OnProgress, on_progress, "progress", Event),
         (OnRateChange, on_ratechange, "ratechange", Event),
@@ -196,10 +277,8 @@ where
         (OnStalled, on_stalled, "stalled", Event),
         (OnSubmit, on_submit, "submit", Event),
         (OnSuspend, on_suspend, "suspend", Event),
-        (OnTimeUpdate, on_timeupdate, "timeupdate", Event),
         (OnToggle, on_toggle, "toggle", Event),
         (OnVolumeChange, on_volumechange, "volumechange", Event),
-        (OnWaiting, on_waiting, "waiting", Event),
         (OnWheel, on_wheel, "wheel", WheelEvent),
     );
 }
@@ -343,6 +422,19 @@ dom_interface_macro_and_trait_definitions!(
                     phantom: PhantomData,
                 }
             }
+            /// Focus this element when it's added to the document, e.g. the search box of
+            /// a dialog when it opens.
+            ///
+            /// Unlike the `autofocus` attribute, this works for elements added after the
+            /// page was loaded. Rebuilding doesn't focus the element again, so the user is
+            /// free to move the focus elsewhere. An element which isn't in the document
+            /// once it's built isn't focused.
+            fn autofocus(self) -> Autofocus<Self, T, A> {
+                Autofocus {
+                    element: self,
+                    phantom: PhantomData,
+                }
+            }
         },
         child_interfaces: {
             HtmlAnchorElement { methods: {}, child_interfaces: {} },
@@ -389,7 +481,115 @@ dom_interface_macro_and_trait_definitions!(
             HtmlLinkElement { methods: {}, child_interfaces: {} },
             HtmlMapElement { methods: {}, child_interfaces: {} },
             HtmlMediaElement {
-                methods: {},
+                methods: {
+                    /// Call `handler` with the playback position, in seconds, whenever it changes,
+                    /// e.g. to show a progress bar.
+                    fn on_timeupdate<F, OA>(self, handler: F) -> OnMedia<Self, T, A, f64, F>
+                    where
+                        F: Fn(&mut T, f64) -> OA,
+                        OA: OptionalAction<A>,
+                    {
+                        OnMedia::new(self, "timeupdate", |media| media.current_time(), handler)
+                    }
+                    /// Call `handler` with the duration of the media, in seconds, when it becomes
+                    /// known or changes.
+                    ///
+                    /// The duration is `NaN` while no media is loaded, and infinite for streams.
+                    fn on_durationchange<F, OA>(self, handler: F) -> OnMedia<Self, T, A, f64, F>
+                    where
+                        F: Fn(&mut T, f64) -> OA,
+                        OA: OptionalAction<A>,
+                    {
+                        OnMedia::new(self, "durationchange", |media| media.duration(), handler)
+                    }
+                    /// Call `handler` when playback starts, or resumes.
+                    fn on_play<F, OA>(self, handler: F) -> OnMedia<Self, T, A, (), F>
+                    where
+                        F: Fn(&mut T, ()) -> OA,
+                        OA: OptionalAction<A>,
+                    {
+                        OnMedia::new(self, "play", |_| (), handler)
+                    }
+                    /// Call `handler` when playback is paused.
+                    ///
+                    /// This is also called when a [`playing(true)`](HtmlMediaElement::playing)
+                    /// command is refused by the browser, e.g. because autoplay isn't allowed yet.
+                    fn on_pause<F, OA>(self, handler: F) -> OnMedia<Self, T, A, (), F>
+                    where
+                        F: Fn(&mut T, ()) -> OA,
+                        OA: OptionalAction<A>,
+                    {
+                        OnMedia::new(self, "pause", |_| (), handler)
+                    }
+                    /// Call `handler` when playback stops until more data is buffered.
+                    fn on_waiting<F, OA>(self, handler: F) -> OnMedia<Self, T, A, (), F>
+                    where
+                        F: Fn(&mut T, ()) -> OA,
+                        OA: OptionalAction<A>,
+                    {
+                        OnMedia::new(self, "waiting", |_| (), handler)
+                    }
+                    /// Call `handler` when playback reaches the end of the media.
+                    fn on_ended<F, OA>(self, handler: F) -> OnMedia<Self, T, A, (), F>
+                    where
+                        F: Fn(&mut T, ()) -> OA,
+                        OA: OptionalAction<A>,
+                    {
+                        OnMedia::new(self, "ended", |_| (), handler)
+                    }
+                    /// Call `handler` when loading the media fails, with the
+                    /// [`MediaError` code](https://developer.mozilla.org/en-US/docs/Web/API/MediaError/code)
+                    /// if one is available.
+                    ///
+                    /// [`on_error`](Element::on_error) can still be used to get the raw event.
+                    fn on_media_error<F, OA>(self, handler: F) -> OnMedia<Self, T, A, Option<u16>, F>
+                    where
+                        F: Fn(&mut T, Option<u16>) -> OA,
+                        OA: OptionalAction<A>,
+                    {
+                        OnMedia::new(self, "error", |media| media.error().map(|error| error.code()), handler)
+                    }
+                    /// Play or pause the media.
+                    ///
+                    /// `play()`/`pause()` is only called when the value changes. If the browser
+                    /// refuses to play, [`on_pause`](HtmlMediaElement::on_pause) handlers are
+                    /// called.
+                    fn playing(self, playing: bool) -> MediaControl<Self, T, A> {
+                        MediaControl {
+                            element: self,
+                            command: MediaCommand::Playing(playing),
+                            phantom: PhantomData,
+                        }
+                    }
+                    /// Seek to `time`, in seconds.
+                    ///
+                    /// This is a seek target, not the playback position: seeking only happens when
+                    /// the value changes, so it shouldn't be fed from
+                    /// [`on_timeupdate`](HtmlMediaElement::on_timeupdate).
+                    fn current_time(self, time: f64) -> MediaControl<Self, T, A> {
+                        MediaControl {
+                            element: self,
+                            command: MediaCommand::CurrentTime(time),
+                            phantom: PhantomData,
+                        }
+                    }
+                    /// Set the volume, between `0.0` and `1.0`.
+                    fn volume(self, volume: f64) -> MediaControl<Self, T, A> {
+                        MediaControl {
+                            element: self,
+                            command: MediaCommand::Volume(volume),
+                            phantom: PhantomData,
+                        }
+                    }
+                    /// Mute or unmute the media.
+                    fn muted(self, muted: bool) -> MediaControl<Self, T, A> {
+                        MediaControl {
+                            element: self,
+                            command: MediaCommand::Muted(muted),
+                            phantom: PhantomData,
+                        }
+                    }
+                },
                 child_interfaces: {
                     HtmlAudioElement { methods: {}, child_interfaces: {} },
                     HtmlVideoElement {
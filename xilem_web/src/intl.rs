@@ -0,0 +1,677 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Locale-aware formatting of numbers, dates and plurals, using the `Intl` API of the
+//! browser.
+
+use std::{borrow::Cow, collections::BTreeMap, collections::HashMap, rc::Rc};
+
+use js_sys::{Array, Function, Intl, Object, Reflect};
+use wasm_bindgen::{JsCast, JsValue, UnwrapThrowExt};
+use xilem_core::{Id, MessageResult};
+
+use crate::{ChangeFlags, Cx, ErrorSink, View, ViewMarker};
+
+type CowStr = Cow<'static, str>;
+
+/// The value of an option of an `Intl` formatter.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+enum OptionValue {
+    Str(CowStr),
+    Int(u32),
+    Bool(bool),
+}
+
+/// The options of an `Intl` formatter, also used as the key of the formatter cache.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+struct IntlOptions(BTreeMap<&'static str, OptionValue>);
+
+impl IntlOptions {
+    fn with(mut self, name: &'static str, value: OptionValue) -> Self {
+        self.0.insert(name, value);
+        self
+    }
+
+    fn to_object(&self) -> Object {
+        let object = Object::new();
+        for (name, value) in &self.0 {
+            let value = match value {
+                OptionValue::Str(value) => JsValue::from_str(value),
+                OptionValue::Int(value) => JsValue::from(*value),
+                OptionValue::Bool(value) => JsValue::from_bool(*value),
+            };
+            Reflect::set(&object, &JsValue::from_str(name), &value).unwrap_throw();
+        }
+        object
+    }
+}
+
+/// How [`formatted_number`] formats its number, see the options of
+/// [`Intl.NumberFormat`](https://developer.mozilla.org/en-US/docs/Web/JavaScript/Reference/Global_Objects/Intl/NumberFormat/NumberFormat#options).
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct NumberOptions(IntlOptions);
+
+impl NumberOptions {
+    /// Format a plain number.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Format a fraction as a percentage, e.g. `0.25` as "25%".
+    pub fn percent() -> Self {
+        Self::new().option("style", "percent")
+    }
+
+    /// Format an amount of money in the currency with the given ISO 4217 code, e.g. "EUR".
+    pub fn currency(code: impl Into<CowStr>) -> Self {
+        Self::new()
+            .option("style", "currency")
+            .option("currency", code)
+    }
+
+    /// Format a quantity of a unit, e.g. "kilobyte" or "kilometer-per-hour".
+    pub fn unit(unit: impl Into<CowStr>) -> Self {
+        Self::new().option("style", "unit").option("unit", unit)
+    }
+
+    pub fn minimum_fraction_digits(self, digits: u32) -> Self {
+        NumberOptions(
+            self.0
+                .with("minimumFractionDigits", OptionValue::Int(digits)),
+        )
+    }
+
+    pub fn maximum_fraction_digits(self, digits: u32) -> Self {
+        NumberOptions(
+            self.0
+                .with("maximumFractionDigits", OptionValue::Int(digits)),
+        )
+    }
+
+    /// Whether to separate the groups of digits, e.g. "1,000" rather than "1000".
+    pub fn use_grouping(self, grouping: bool) -> Self {
+        NumberOptions(self.0.with("useGrouping", OptionValue::Bool(grouping)))
+    }
+
+    /// Abbreviate large numbers, e.g. "1.2K".
+    pub fn compact(self) -> Self {
+        self.option("notation", "compact")
+    }
+
+    /// Set any other option taking a string.
+    pub fn option(self, name: &'static str, value: impl Into<CowStr>) -> Self {
+        NumberOptions(self.0.with(name, OptionValue::Str(value.into())))
+    }
+}
+
+/// The length of a date or a time formatted by [`formatted_date`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DateStyle {
+    Full,
+    Long,
+    Medium,
+    Short,
+}
+
+impl DateStyle {
+    fn as_str(self) -> &'static str {
+        match self {
+            DateStyle::Full => "full",
+            DateStyle::Long => "long",
+            DateStyle::Medium => "medium",
+            DateStyle::Short => "short",
+        }
+    }
+}
+
+/// How [`formatted_date`] formats its date, see the options of
+/// [`Intl.DateTimeFormat`](https://developer.mozilla.org/en-US/docs/Web/JavaScript/Reference/Global_Objects/Intl/DateTimeFormat/DateTimeFormat#options).
+///
+/// Without options, only the date is shown, in a short numeric format.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct DateOptions(IntlOptions);
+
+impl DateOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn date_style(self, style: DateStyle) -> Self {
+        self.option("dateStyle", style.as_str())
+    }
+
+    pub fn time_style(self, style: DateStyle) -> Self {
+        self.option("timeStyle", style.as_str())
+    }
+
+    /// Show the date in the given IANA time zone, e.g. "UTC" or "Europe/Paris", rather than
+    /// in the time zone of the user.
+    pub fn time_zone(self, time_zone: impl Into<CowStr>) -> Self {
+        self.option("timeZone", time_zone)
+    }
+
+    /// Whether to use a 12-hour clock rather than the one of the locale.
+    pub fn hour12(self, hour12: bool) -> Self {
+        DateOptions(self.0.with("hour12", OptionValue::Bool(hour12)))
+    }
+
+    /// Set any other option taking a string.
+    pub fn option(self, name: &'static str, value: impl Into<CowStr>) -> Self {
+        DateOptions(self.0.with(name, OptionValue::Str(value.into())))
+    }
+}
+
+/// The texts [`plural`] chooses from, for each plural category of
+/// [`Intl.PluralRules`](https://developer.mozilla.org/en-US/docs/Web/JavaScript/Reference/Global_Objects/Intl/PluralRules).
+///
+/// Which categories are used depends on the locale, e.g. English only uses "one" and
+/// "other", and the text of "other" is used for missing categories. A `{}` in the text
+/// is replaced by the count, formatted for the locale.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct PluralForms {
+    forms: BTreeMap<&'static str, CowStr>,
+    ordinal: bool,
+}
+
+impl PluralForms {
+    /// Plural forms using `other` for all the categories.
+    pub fn new(other: impl Into<CowStr>) -> Self {
+        PluralForms {
+            forms: BTreeMap::from([("other", other.into())]),
+            ordinal: false,
+        }
+    }
+
+    pub fn zero(self, text: impl Into<CowStr>) -> Self {
+        self.form("zero", text)
+    }
+
+    pub fn one(self, text: impl Into<CowStr>) -> Self {
+        self.form("one", text)
+    }
+
+    pub fn two(self, text: impl Into<CowStr>) -> Self {
+        self.form("two", text)
+    }
+
+    pub fn few(self, text: impl Into<CowStr>) -> Self {
+        self.form("few", text)
+    }
+
+    pub fn many(self, text: impl Into<CowStr>) -> Self {
+        self.form("many", text)
+    }
+
+    /// Use the ordinal categories, e.g. for "1st", "2nd" and "3rd" in English, rather
+    /// than the cardinal ones.
+    pub fn ordinal(mut self) -> Self {
+        self.ordinal = true;
+        self
+    }
+
+    fn form(mut self, category: &'static str, text: impl Into<CowStr>) -> Self {
+        self.forms.insert(category, text.into());
+        self
+    }
+
+    fn options(&self) -> IntlOptions {
+        let kind = if self.ordinal { "ordinal" } else { "cardinal" };
+        IntlOptions::default().with("type", OptionValue::Str(kind.into()))
+    }
+
+    fn text(&self, category: &str) -> &str {
+        self.forms
+            .get(category)
+            .or_else(|| self.forms.get("other"))
+            .unwrap_throw()
+    }
+}
+
+/// The `Intl` formatters of an app, by locale and options.
+///
+/// Constructing a formatter is much slower than using it, so they're kept for the
+/// whole life of the app.
+#[derive(Default)]
+pub(crate) struct IntlCache {
+    formatters: HashMap<(&'static str, Rc<str>, IntlOptions), Object>,
+    /// How many formatters were constructed.
+    pub(crate) created: u32,
+    /// How many times a formatter was reused.
+    pub(crate) hits: u32,
+}
+
+impl IntlCache {
+    /// The `Intl` formatter of class `class`, for the given locale and options.
+    ///
+    /// If the locale or the options are invalid, the error is reported to `errors`, and
+    /// the formatter of the default locale of the browser is used instead.
+    fn formatter<F: JsCast>(
+        &mut self,
+        class: &'static str,
+        locale: &Rc<str>,
+        options: &IntlOptions,
+        errors: &ErrorSink,
+    ) -> F {
+        let key = (class, locale.clone(), options.clone());
+        if let Some(formatter) = self.formatters.get(&key) {
+            self.hits += 1;
+            return formatter.clone().unchecked_into();
+        }
+        self.created += 1;
+        let locales = Array::of1(&JsValue::from_str(locale));
+        let formatter = construct(class, &locales, &options.to_object()).unwrap_or_else(|err| {
+            errors.report(format!(
+                "Invalid Intl.{class} for the locale {locale}: {}",
+                String::from(js_sys::Error::from(err).message())
+            ));
+            construct(class, &Array::new(), &Object::new()).unwrap_throw()
+        });
+        self.formatters.insert(key, formatter.clone());
+        formatter.unchecked_into()
+    }
+
+    fn format_number(
+        &mut self,
+        locale: &Rc<str>,
+        options: &NumberOptions,
+        value: f64,
+        errors: &ErrorSink,
+    ) -> String {
+        let format: Intl::NumberFormat = self.formatter("NumberFormat", locale, &options.0, errors);
+        let text = format
+            .format()
+            .call1(&JsValue::NULL, &JsValue::from_f64(value))
+            .unwrap_throw();
+        text.as_string().unwrap_throw()
+    }
+
+    fn format_date(
+        &mut self,
+        locale: &Rc<str>,
+        options: &DateOptions,
+        timestamp: f64,
+        errors: &ErrorSink,
+    ) -> String {
+        let format: Intl::DateTimeFormat =
+            self.formatter("DateTimeFormat", locale, &options.0, errors);
+        let date = js_sys::Date::new(&JsValue::from_f64(timestamp));
+        let text = format.format().call1(&JsValue::NULL, &date).unwrap_throw();
+        text.as_string().unwrap_throw()
+    }
+
+    fn format_plural(
+        &mut self,
+        locale: &Rc<str>,
+        forms: &PluralForms,
+        count: f64,
+        errors: &ErrorSink,
+    ) -> String {
+        let rules: Intl::PluralRules =
+            self.formatter("PluralRules", locale, &forms.options(), errors);
+        let category = String::from(rules.select(count));
+        let text = forms.text(&category);
+        if text.contains("{}") {
+            let count = self.format_number(locale, &NumberOptions::new(), count, errors);
+            text.replace("{}", &count)
+        } else {
+            text.to_owned()
+        }
+    }
+}
+
+/// `new Intl[class](locales, options)`, which throws if the locales or options are invalid.
+fn construct(class: &str, locales: &Array, options: &Object) -> Result<Object, JsValue> {
+    let intl = Reflect::get(&js_sys::global(), &JsValue::from_str("Intl"))?;
+    let constructor: Function = Reflect::get(&intl, &JsValue::from_str(class))?.dyn_into()?;
+    let formatter = Reflect::construct(&constructor, &Array::of2(locales, options))?;
+    Ok(formatter.unchecked_into())
+}
+
+/// The preferred language of the user, as set in the browser.
+pub(crate) fn browser_language() -> Rc<str> {
+    web_sys::window()
+        .and_then(|window| window.navigator().language())
+        .unwrap_or_else(|| "en-US".into())
+        .into()
+}
+
+/// See [`locale`].
+pub struct Locale<V> {
+    locale: Option<CowStr>,
+    child: V,
+}
+
+/// Format the text of the views in `child` for `locale`, a BCP 47 language tag like
+/// "en-US" or "de", or for the language of the browser if it's `None`.
+///
+/// The locale is available to the views in `child` with [`Cx::locale`], and is used by
+/// [`formatted_number`], [`formatted_date`] and [`plural`]. Without a `locale` view,
+/// the language of the browser is used.
+///
+/// When the locale changes, the text of these views is formatted again. The views in
+/// [`memoize`](crate::memoize) aren't rebuilt, and keep the text of the previous locale.
+pub fn locale<V>(locale: Option<impl Into<CowStr>>, child: V) -> Locale<V> {
+    Locale {
+        locale: locale.map(Into::into),
+        child,
+    }
+}
+
+impl<V> ViewMarker for Locale<V> {}
+
+impl<T, A, V: View<T, A>> View<T, A> for Locale<V> {
+    type State = V::State;
+
+    type Element = V::Element;
+
+    fn build(&self, cx: &mut Cx) -> (Id, Self::State, Self::Element) {
+        let locale = self
+            .locale
+            .as_deref()
+            .map_or_else(browser_language, Rc::from);
+        cx.with_locale(locale, |cx| self.child.build(cx))
+    }
+
+    fn rebuild(
+        &self,
+        cx: &mut Cx,
+        prev: &Self,
+        id: &mut Id,
+        state: &mut Self::State,
+        element: &mut Self::Element,
+    ) -> ChangeFlags {
+        let locale = self
+            .locale
+            .as_deref()
+            .map_or_else(browser_language, Rc::from);
+        cx.with_locale(locale, |cx| {
+            self.child.rebuild(cx, &prev.child, id, state, element)
+        })
+    }
+
+    fn message(
+        &self,
+        id_path: &[Id],
+        state: &mut Self::State,
+        message: Box<dyn std::any::Any>,
+        app_state: &mut T,
+    ) -> MessageResult<A> {
+        self.child.message(id_path, state, message, app_state)
+    }
+}
+
+/// The state of the formatting views, like [`formatted_number`].
+pub struct FormatState {
+    /// The locale the text was formatted for.
+    locale: Rc<str>,
+}
+
+/// A text view formatting its value with `Intl`, for the locale of [`Cx::locale`].
+trait Format: PartialEq {
+    fn format(&self, cx: &mut Cx, locale: &Rc<str>) -> String;
+}
+
+macro_rules! impl_format_view {
+    ($ty:ident) => {
+        impl ViewMarker for $ty {}
+
+        impl<T, A> View<T, A> for $ty {
+            type State = FormatState;
+
+            type Element = web_sys::Text;
+
+            fn build(&self, cx: &mut Cx) -> (Id, Self::State, Self::Element) {
+                let locale = cx.locale();
+                let text = self.format(cx, &locale);
+                let element = cx.document().create_text_node(&text);
+                (Id::next(), FormatState { locale }, element)
+            }
+
+            fn rebuild(
+                &self,
+                cx: &mut Cx,
+                prev: &Self,
+                _id: &mut Id,
+                state: &mut Self::State,
+                element: &mut Self::Element,
+            ) -> ChangeFlags {
+                let locale = cx.locale();
+                if self == prev && locale == state.locale {
+                    return ChangeFlags::empty();
+                }
+                let text = self.format(cx, &locale);
+                state.locale = locale;
+                // The text node is kept, see the string views.
+                if element.data() != text {
+                    element.set_data(&text);
+                    ChangeFlags::OTHER_CHANGE
+                } else {
+                    ChangeFlags::empty()
+                }
+            }
+
+            fn message(
+                &self,
+                _id_path: &[Id],
+                _state: &mut Self::State,
+                message: Box<dyn std::any::Any>,
+                _app_state: &mut T,
+            ) -> MessageResult<A> {
+                MessageResult::Stale(message)
+            }
+        }
+    };
+}
+
+/// See [`formatted_number`].
+#[derive(PartialEq)]
+pub struct FormattedNumber {
+    value: f64,
+    options: NumberOptions,
+}
+
+/// A text showing `value` formatted with
+/// [`Intl.NumberFormat`](https://developer.mozilla.org/en-US/docs/Web/JavaScript/Reference/Global_Objects/Intl/NumberFormat),
+/// for the locale set with [`locale`].
+pub fn formatted_number(value: f64, options: NumberOptions) -> FormattedNumber {
+    FormattedNumber { value, options }
+}
+
+impl Format for FormattedNumber {
+    fn format(&self, cx: &mut Cx, locale: &Rc<str>) -> String {
+        let errors = cx.error_sink();
+        cx.intl
+            .format_number(locale, &self.options, self.value, &errors)
+    }
+}
+
+impl_format_view!(FormattedNumber);
+
+/// See [`formatted_date`].
+#[derive(PartialEq)]
+pub struct FormattedDate {
+    timestamp: f64,
+    options: DateOptions,
+}
+
+/// A text showing the date at `timestamp`, in milliseconds since the Unix epoch like
+/// `Date.now()`, formatted with
+/// [`Intl.DateTimeFormat`](https://developer.mozilla.org/en-US/docs/Web/JavaScript/Reference/Global_Objects/Intl/DateTimeFormat),
+/// for the locale set with [`locale`].
+pub fn formatted_date(timestamp: f64, options: DateOptions) -> FormattedDate {
+    FormattedDate { timestamp, options }
+}
+
+impl Format for FormattedDate {
+    fn format(&self, cx: &mut Cx, locale: &Rc<str>) -> String {
+        let errors = cx.error_sink();
+        cx.intl
+            .format_date(locale, &self.options, self.timestamp, &errors)
+    }
+}
+
+impl_format_view!(FormattedDate);
+
+/// See [`plural`].
+#[derive(PartialEq)]
+pub struct Plural {
+    count: f64,
+    forms: PluralForms,
+}
+
+/// A text showing the form of `forms` matching `count`, according to the plural rules
+/// of the locale set with [`locale`].
+///
+/// # Examples
+///
+/// ```
+/// use xilem_web::{plural, PluralForms};
+///
+/// # fn items_left(count: usize) -> xilem_web::Plural {
+/// plural(count as f64, PluralForms::new("{} items left").one("{} item left"))
+/// # }
+/// ```
+pub fn plural(count: f64, forms: PluralForms) -> Plural {
+    Plural { count, forms }
+}
+
+impl Format for Plural {
+    fn format(&self, cx: &mut Cx, locale: &Rc<str>) -> String {
+        let errors = cx.error_sink();
+        cx.intl
+            .format_plural(locale, &self.forms, self.count, &errors)
+    }
+}
+
+impl_format_view!(Plural);
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod tests {
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use super::*;
+    use crate::elements::html as el;
+
+    wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+    fn texts(element: &web_sys::Node) -> Vec<String> {
+        let nodes = element.child_nodes();
+        (0..nodes.length())
+            .map(|index| nodes.get(index).unwrap().text_content().unwrap())
+            .collect()
+    }
+
+    /// The first day of 1970, in UTC.
+    fn epoch() -> FormattedDate {
+        formatted_date(
+            0.0,
+            DateOptions::new()
+                .date_style(DateStyle::Long)
+                .time_zone("UTC"),
+        )
+    }
+
+    #[wasm_bindgen_test]
+    fn formats_for_the_locale() {
+        let mut cx = Cx::new();
+        let view = |tag: &'static str| {
+            locale(
+                Some(tag),
+                el::div::<(), (), _>((
+                    formatted_number(1234.5, NumberOptions::new()),
+                    formatted_number(0.25, NumberOptions::percent()),
+                    epoch(),
+                    plural(1.0, PluralForms::new("{} items").one("{} item")),
+                    plural(1000.0, PluralForms::new("{} items").one("{} item")),
+                )),
+            )
+        };
+
+        let (_, _, element) = view("en-US").build(&mut cx);
+        assert_eq!(
+            texts(&element),
+            ["1,234.5", "25%", "January 1, 1970", "1 item", "1,000 items"]
+        );
+
+        let (_, _, element) = view("de-DE").build(&mut cx);
+        assert_eq!(
+            texts(&element),
+            [
+                "1.234,5",
+                "25\u{a0}%",
+                "1. Januar 1970",
+                "1 item",
+                "1.000 items"
+            ]
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn formatters_are_cached() {
+        let mut cx = Cx::new();
+        let view = |count: f64| {
+            locale(
+                Some("en-US"),
+                el::div::<(), (), _>((
+                    formatted_number(count, NumberOptions::new()),
+                    formatted_number(count * 2.0, NumberOptions::new()),
+                    formatted_number(count, NumberOptions::new().maximum_fraction_digits(0)),
+                )),
+            )
+        };
+
+        let prev = view(1.5);
+        let (mut id, mut state, mut element) = prev.build(&mut cx);
+        assert_eq!(texts(&element), ["1.5", "3", "2"]);
+        assert_eq!((cx.intl.created, cx.intl.hits), (2, 1));
+
+        // Nothing changed, so nothing is formatted.
+        let next = view(1.5);
+        next.rebuild(&mut cx, &prev, &mut id, &mut state, &mut element);
+        assert_eq!((cx.intl.created, cx.intl.hits), (2, 1));
+
+        let prev = next;
+        let next = view(4.0);
+        next.rebuild(&mut cx, &prev, &mut id, &mut state, &mut element);
+        assert_eq!(texts(&element), ["4", "8", "4"]);
+        assert_eq!((cx.intl.created, cx.intl.hits), (2, 4));
+    }
+
+    #[wasm_bindgen_test]
+    fn locale_changes_reach_descendants() {
+        let mut cx = Cx::new();
+        let view = |tag: &'static str| {
+            el::div::<(), (), _>((
+                locale(
+                    Some(tag),
+                    el::p((
+                        formatted_number(1234.5, NumberOptions::new()),
+                        locale(Some("en-US"), epoch()),
+                    )),
+                ),
+                epoch(),
+            ))
+        };
+
+        let prev = view("en-US");
+        let (mut id, mut state, mut element) = prev.build(&mut cx);
+        let number = element.first_child().unwrap().first_child().unwrap();
+        assert_eq!(number.text_content().unwrap(), "1,234.5");
+
+        let next = view("fr-FR");
+        next.rebuild(&mut cx, &prev, &mut id, &mut state, &mut element);
+        // The text node is updated in place.
+        let same_number = element.first_child().unwrap().first_child().unwrap();
+        assert!(same_number.is_same_node(Some(&number)));
+        assert_eq!(number.text_content().unwrap(), "1\u{202f}234,5");
+        // The nested locale wins, and the views outside of the locale view use the one
+        // of the browser.
+        let paragraph = element.first_child().unwrap();
+        assert_eq!(texts(&paragraph)[1], "January 1, 1970");
+        let browser = View::<(), ()>::build(&locale(None::<&str>, epoch()), &mut cx).2;
+        assert_eq!(
+            element.last_child().unwrap().text_content(),
+            browser.text_content()
+        );
+    }
+}
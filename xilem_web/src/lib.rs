@@ -13,34 +13,91 @@ mod attribute_value;
 mod class;
 mod context;
 mod diff;
+mod dom_ref;
 pub mod elements;
+mod error_toasts;
 pub mod events;
+mod focus;
+#[cfg(feature = "gpu")]
+mod gpu_canvas;
 pub mod interfaces;
+mod intl;
+mod media;
+mod notifications;
 mod one_of;
 mod optional_action;
+mod page_events;
 mod pointer;
+mod raw_html;
+mod sanitize;
+mod sensors;
+mod shortcuts;
 mod style;
+mod suspense;
 pub mod svg;
+#[cfg(all(test, target_arch = "wasm32"))]
+pub(crate) mod test_util;
 mod vecmap;
 mod view;
 mod view_ext;
 
-pub use xilem_core::MessageResult;
+pub use xilem_core::{zip_sequences, MessageResult, RebuildCancellation, ZipSequences};
 
 pub use app::App;
 pub use attribute::Attr;
 pub use attribute_value::{AttributeValue, IntoAttributeValue};
 pub use context::{ChangeFlags, Cx};
+pub use dom_ref::{DomRef, DomRefState};
+pub use error_toasts::{error_toasts, ErrorSink, ErrorToasts, ErrorToastsState};
+pub use focus::{Autofocus, ScrollIntoView};
+#[cfg(feature = "gpu")]
+pub use gpu_canvas::{
+    gpu_canvas, GpuBackend, GpuCanvas, GpuCanvasConfig, GpuCanvasState, GpuError, SurfaceHandle,
+    WgpuBackend,
+};
+pub use intl::{
+    formatted_date, formatted_number, locale, plural, DateOptions, DateStyle, FormatState,
+    FormattedDate, FormattedNumber, Locale, NumberOptions, Plural, PluralForms,
+};
+pub use media::{MediaControl, OnMedia, OnMediaState};
+pub use notifications::{
+    notification_permission, notify, request_notification_permission, set_app_badge,
+    NotificationError, NotificationHandle, NotificationPermission, NotificationProvider,
+    NotificationSpec, Notify, NotifyState,
+};
 pub use one_of::{
     OneOf2, OneOf3, OneOf4, OneOf5, OneOf6, OneOf7, OneOf8, OneSeqOf2, OneSeqOf3, OneSeqOf4,
     OneSeqOf5, OneSeqOf6, OneSeqOf7, OneSeqOf8,
 };
 pub use optional_action::{Action, OptionalAction};
-pub use pointer::{Pointer, PointerDetails, PointerMsg};
+pub use page_events::{
+    confirm_unload, is_unload_confirmation_active, on_online_change, on_unload,
+    on_visibility_change, ConfirmUnload, ConfirmUnloadState, OnOnlineChange, OnUnload,
+    OnVisibilityChange, PageEventState,
+};
+pub use pointer::{OnPointer, OnPointerState, Pointer, PointerDetails, PointerMsg, PointerType};
+pub use raw_html::{sanitized_html, unsafe_raw_html, RawHtml};
+pub use sanitize::{sanitize_html, SanitizePolicy};
+pub use sensors::{
+    on_device_orientation, request_orientation_permission, watch_geolocation, GeoError, GeoOptions,
+    GeoPosition, GeolocationProvider, OnDeviceOrientation, OnDeviceOrientationState, Orientation,
+    OrientationPermission, OrientationProvider, SensorGuard, WatchGeolocation,
+    WatchGeolocationState,
+};
+pub use shortcuts::{
+    has_registered_shortcuts, shortcut, shortcuts, InvalidShortcut, KeyCombo, Shortcut, Shortcuts,
+    ShortcutsState,
+};
 pub use style::style;
+pub use suspense::{
+    suspense, DependencyError, DependencyStatus, Suspense, SuspenseDeps, SuspenseHandle,
+    SuspenseState,
+};
 pub use view::{
-    memoize, static_view, Adapt, AdaptState, AdaptThunk, AnyView, BoxedView, ElementsSplice,
-    Memoize, MemoizeState, Pod, View, ViewMarker, ViewSequence,
+    debounced_rebuild, first_of, memoize, provide_id, static_view, Adapt, AdaptState, AdaptThunk,
+    AnyView, BoxedView, DebouncedRebuild, DebouncedRebuildState, ElementsSplice, FirstOf,
+    FirstOfState, MapMessageAsync, MapMessageAsyncState, Memoize, MemoizeState, Pod, ProvideId,
+    ProvideIdState, Subscribe, SubscribeState, View, ViewMarker, ViewSequence,
 };
 pub use view_ext::ViewExt;
 
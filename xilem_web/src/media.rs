@@ -0,0 +1,314 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+use std::{any::Any, marker::PhantomData};
+
+use gloo::events::EventListener;
+use wasm_bindgen::{JsCast, UnwrapThrowExt};
+use wasm_bindgen_futures::JsFuture;
+use xilem_core::{Id, MessageResult};
+
+use crate::{
+    interfaces::{sealed::Sealed, HtmlMediaElement},
+    view::DomNode,
+    ChangeFlags, Cx, OptionalAction, View, ViewMarker,
+};
+
+/// Wraps a media element view and calls a handler when one of its media events is fired,
+/// with a payload read from the element, see e.g. [`HtmlMediaElement::on_timeupdate`].
+pub struct OnMedia<E, T, A, P, F> {
+    pub(crate) element: E,
+    pub(crate) event: &'static str,
+    pub(crate) payload: fn(&web_sys::HtmlMediaElement) -> P,
+    pub(crate) handler: F,
+    pub(crate) phantom: PhantomData<fn() -> (T, A)>,
+}
+
+impl<E, T, A, P, F> OnMedia<E, T, A, P, F> {
+    pub(crate) fn new(
+        element: E,
+        event: &'static str,
+        payload: fn(&web_sys::HtmlMediaElement) -> P,
+        handler: F,
+    ) -> Self {
+        OnMedia {
+            element,
+            event,
+            payload,
+            handler,
+            phantom: PhantomData,
+        }
+    }
+}
+
+/// State for the [`OnMedia`] view.
+pub struct OnMediaState<S> {
+    #[allow(unused)]
+    listener: EventListener,
+    child_id: Id,
+    child_state: S,
+}
+
+/// The message sent by the listener of an [`OnMedia`] view.
+struct MediaMessage<P>(P);
+
+fn create_media_listener<N: DomNode, P: 'static>(
+    element: &N,
+    event: &'static str,
+    payload: fn(&web_sys::HtmlMediaElement) -> P,
+    cx: &Cx,
+) -> EventListener {
+    let thunk = cx.message_thunk();
+    EventListener::new(element.as_node_ref(), event, move |event| {
+        let media = event
+            .target()
+            .and_then(|target| target.dyn_into::<web_sys::HtmlMediaElement>().ok());
+        if let Some(media) = media {
+            thunk.push_message(MediaMessage(payload(&media)));
+        }
+    })
+}
+
+impl<E, T, A, P, F> ViewMarker for OnMedia<E, T, A, P, F> {}
+impl<E, T, A, P, F> Sealed for OnMedia<E, T, A, P, F> {}
+
+impl<E, T, A, P, F, OA> View<T, A> for OnMedia<E, T, A, P, F>
+where
+    E: HtmlMediaElement<T, A>,
+    P: 'static,
+    F: Fn(&mut T, P) -> OA,
+    OA: OptionalAction<A>,
+{
+    type State = OnMediaState<E::State>;
+
+    type Element = E::Element;
+
+    fn build(&self, cx: &mut Cx) -> (Id, Self::State, Self::Element) {
+        let (id, (element, state)) = cx.with_new_id(|cx| {
+            let (child_id, child_state, element) = self.element.build(cx);
+            let listener = create_media_listener(&element, self.event, self.payload, cx);
+            let state = OnMediaState {
+                listener,
+                child_id,
+                child_state,
+            };
+            (element, state)
+        });
+        (id, state, element)
+    }
+
+    fn rebuild(
+        &self,
+        cx: &mut Cx,
+        prev: &Self,
+        id: &mut Id,
+        state: &mut Self::State,
+        element: &mut Self::Element,
+    ) -> ChangeFlags {
+        cx.with_id(*id, |cx| {
+            let changed = self.element.rebuild(
+                cx,
+                &prev.element,
+                &mut state.child_id,
+                &mut state.child_state,
+                element,
+            );
+            if prev.event != self.event || changed.contains(ChangeFlags::STRUCTURE) {
+                state.listener = create_media_listener(element, self.event, self.payload, cx);
+            }
+            changed
+        })
+    }
+
+    fn message(
+        &self,
+        id_path: &[Id],
+        state: &mut Self::State,
+        message: Box<dyn Any>,
+        app_state: &mut T,
+    ) -> MessageResult<A> {
+        match id_path {
+            [] if message.downcast_ref::<MediaMessage<P>>().is_some() => {
+                let MediaMessage(payload) = *message.downcast::<MediaMessage<P>>().unwrap();
+                match (self.handler)(app_state, payload).action() {
+                    Some(a) => MessageResult::Action(a),
+                    None => MessageResult::Nop,
+                }
+            }
+            [element_id, rest_path @ ..] if *element_id == state.child_id => {
+                self.element
+                    .message(rest_path, &mut state.child_state, message, app_state)
+            }
+            _ => MessageResult::Stale(message),
+        }
+    }
+}
+
+crate::interfaces::impl_dom_interfaces_for_ty!(
+    HtmlMediaElement,
+    OnMedia,
+    vars: <P, F, OA,>,
+    vars_on_ty: <P, F,>,
+    bounds: {
+        P: 'static,
+        F: Fn(&mut T, P) -> OA,
+        OA: OptionalAction<A>,
+    }
+);
+
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum MediaCommand {
+    Playing(bool),
+    CurrentTime(f64),
+    Volume(f64),
+    Muted(bool),
+}
+
+impl MediaCommand {
+    fn apply(self, media: &web_sys::HtmlMediaElement) {
+        match self {
+            MediaCommand::Playing(true) => play(media),
+            MediaCommand::Playing(false) => media.pause().unwrap_throw(),
+            MediaCommand::CurrentTime(time) => media.set_current_time(time),
+            MediaCommand::Volume(volume) => media.set_volume(volume.clamp(0.0, 1.0)),
+            MediaCommand::Muted(muted) => media.set_muted(muted),
+        }
+    }
+}
+
+/// Start playback, reporting a refusal (e.g. autoplay being blocked) as a `pause` event,
+/// so that `on_pause` handlers can bring the app state back in line.
+fn play(media: &web_sys::HtmlMediaElement) {
+    let Ok(promise) = media.play() else {
+        return;
+    };
+    let media = media.clone();
+    wasm_bindgen_futures::spawn_local(async move {
+        if let Err(error) = JsFuture::from(promise).await {
+            web_sys::console::warn_2(&"media playback was refused:".into(), &error);
+            let pause = web_sys::Event::new("pause").unwrap_throw();
+            media.dispatch_event(&pause).unwrap_throw();
+        }
+    });
+}
+
+/// Controls the playback of the wrapped media element, see e.g.
+/// [`HtmlMediaElement::playing`].
+///
+/// The command is only issued when the element is built, and when the value differs from
+/// the one of the previous view, so the user is free to e.g. seek with the native controls
+/// in between.
+pub struct MediaControl<E, T, A> {
+    pub(crate) element: E,
+    pub(crate) command: MediaCommand,
+    pub(crate) phantom: PhantomData<fn() -> (T, A)>,
+}
+
+impl<E, T, A> ViewMarker for MediaControl<E, T, A> {}
+impl<E, T, A> Sealed for MediaControl<E, T, A> {}
+
+impl<E: HtmlMediaElement<T, A>, T, A> View<T, A> for MediaControl<E, T, A> {
+    type State = E::State;
+
+    type Element = E::Element;
+
+    fn build(&self, cx: &mut Cx) -> (Id, Self::State, Self::Element) {
+        let (id, state, element) = self.element.build(cx);
+        self.command.apply(element.as_node_ref().unchecked_ref());
+        (id, state, element)
+    }
+
+    fn rebuild(
+        &self,
+        cx: &mut Cx,
+        prev: &Self,
+        id: &mut Id,
+        state: &mut Self::State,
+        element: &mut Self::Element,
+    ) -> ChangeFlags {
+        let changed = self.element.rebuild(cx, &prev.element, id, state, element);
+        if self.command != prev.command || changed.contains(ChangeFlags::STRUCTURE) {
+            self.command.apply(element.as_node_ref().unchecked_ref());
+        }
+        changed
+    }
+
+    fn message(
+        &self,
+        id_path: &[Id],
+        state: &mut Self::State,
+        message: Box<dyn Any>,
+        app_state: &mut T,
+    ) -> MessageResult<A> {
+        self.element.message(id_path, state, message, app_state)
+    }
+}
+
+crate::interfaces::impl_dom_interfaces_for_ty!(HtmlMediaElement, MediaControl);
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod tests {
+    use std::{cell::Cell, rc::Rc};
+
+    use wasm_bindgen::{closure::Closure, JsValue};
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use super::*;
+    use crate::{
+        elements::html::audio,
+        test_util::{dispatch, Recorder},
+    };
+
+    wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+    /// Replace the `play` method of `media` by one counting its calls.
+    fn count_play_calls(media: &JsValue) -> Rc<Cell<u32>> {
+        let calls = Rc::new(Cell::new(0));
+        let play = Closure::<dyn FnMut() -> js_sys::Promise>::new({
+            let calls = calls.clone();
+            move || {
+                calls.set(calls.get() + 1);
+                js_sys::Promise::resolve(&JsValue::UNDEFINED)
+            }
+        });
+        js_sys::Reflect::set(media, &"play".into(), play.as_ref()).unwrap();
+        play.forget();
+        calls
+    }
+
+    #[wasm_bindgen_test]
+    fn play_is_called_once_per_flip() {
+        let mut cx = Cx::new();
+        let view = |playing| audio::<(), (), _>(()).playing(playing);
+        let mut prev = view(false);
+        let (mut id, mut state, mut element) = prev.build(&mut cx);
+        let calls = count_play_calls(&element);
+
+        for (playing, expected_calls) in [(true, 1), (true, 1), (false, 1), (true, 2)] {
+            let next = view(playing);
+            next.rebuild(&mut cx, &prev, &mut id, &mut state, &mut element);
+            assert_eq!(calls.get(), expected_calls, "after playing({playing})");
+            prev = next;
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn handlers_receive_their_event() {
+        let recorder = Recorder::default();
+        let mut cx = recorder.cx();
+        let view = audio::<Vec<String>, (), _>(())
+            .on_ended(|events: &mut Vec<String>, ()| events.push("ended".into()))
+            .on_media_error(|events: &mut Vec<String>, code| {
+                events.push(format!("error {code:?}"));
+            });
+        let (_, mut state, element) = view.build(&mut cx);
+
+        let mut events = vec![];
+        dispatch(&element, "ended");
+        dispatch(&element, "error");
+        // Events without a handler aren't reported.
+        dispatch(&element, "play");
+        recorder.deliver(&view, &mut state, &mut events);
+        assert_eq!(events, ["ended", "error None"]);
+    }
+}
@@ -0,0 +1,623 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Showing [system notifications](https://developer.mozilla.org/en-US/docs/Web/API/Notifications_API),
+//! and setting the badge of an installed app.
+//!
+//! Like the [sensors](crate::watch_geolocation), the [`notify`] view doesn't render
+//! anything, so it can be put anywhere in a view sequence. The browser's `Notification`
+//! API is accessed through the [`NotificationProvider`] trait, which can be implemented
+//! to fake it, e.g. in tests.
+
+use std::{any::Any, fmt, marker::PhantomData, rc::Rc};
+
+use gloo::events::EventListener;
+use wasm_bindgen::{JsCast, JsValue, UnwrapThrowExt};
+use wasm_bindgen_futures::JsFuture;
+use xilem_core::{Id, MessageResult};
+
+use crate::{page_events::push_message_later, ChangeFlags, Cx, OptionalAction, View, ViewMarker};
+
+/// What a notification shown by [`notify`] contains.
+///
+/// See [`Notification()`](https://developer.mozilla.org/en-US/docs/Web/API/Notification/Notification).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NotificationSpec {
+    pub title: String,
+    pub body: Option<String>,
+    /// The URL of the icon.
+    pub icon: Option<String>,
+    /// Notifications with the same tag replace each other, e.g. so that a chat only
+    /// shows its latest message.
+    pub tag: Option<String>,
+}
+
+/// Whether the user allows the page to show notifications.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotificationPermission {
+    Granted,
+    Denied,
+    /// The user wasn't asked yet, see [`request_notification_permission`].
+    Default,
+}
+
+impl NotificationPermission {
+    fn from_answer(answer: Option<&str>) -> Self {
+        match answer {
+            Some("granted") => NotificationPermission::Granted,
+            Some("denied") => NotificationPermission::Denied,
+            _ => NotificationPermission::Default,
+        }
+    }
+}
+
+/// Why [`notify`] couldn't show a notification.
+#[derive(Debug, Clone, PartialEq)]
+pub enum NotificationError {
+    /// The user denied the permission to show notifications.
+    PermissionDenied,
+    /// The permission wasn't requested yet, see [`request_notification_permission`].
+    PermissionNotRequested,
+    /// The browser doesn't support notifications, e.g. because the page isn't served
+    /// over HTTPS.
+    Unsupported,
+    /// The browser refused to show the notification, with its explanation.
+    Failed(String),
+}
+
+impl fmt::Display for NotificationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NotificationError::PermissionDenied => {
+                write!(f, "the permission to show notifications was denied")
+            }
+            NotificationError::PermissionNotRequested => {
+                write!(f, "the permission to show notifications wasn't requested")
+            }
+            NotificationError::Unsupported => write!(f, "notifications are not supported"),
+            NotificationError::Failed(message) => {
+                write!(f, "the notification couldn't be shown: {message}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for NotificationError {}
+
+/// A notification shown by a [`NotificationProvider`].
+///
+/// Dropping the handle doesn't close the notification, but clicks on it aren't reported
+/// anymore.
+pub struct NotificationHandle(Box<dyn Fn()>);
+
+impl NotificationHandle {
+    /// Create a handle calling `close` to close the notification.
+    pub fn new(close: impl Fn() + 'static) -> Self {
+        NotificationHandle(Box::new(close))
+    }
+
+    /// Close the notification.
+    pub fn close(&self) {
+        (self.0)();
+    }
+}
+
+/// The browser's notification API, used by [`notify`].
+pub trait NotificationProvider {
+    /// Whether the page may show notifications, or `None` if they aren't supported.
+    fn permission(&self) -> Option<NotificationPermission>;
+
+    /// Ask the user for the permission to show notifications.
+    fn request_permission(&self, on_result: Box<dyn FnOnce(NotificationPermission)>);
+
+    /// Show a notification, calling `on_click` when the user clicks it.
+    ///
+    /// This is only called once the permission is granted.
+    fn show(
+        &self,
+        spec: &NotificationSpec,
+        on_click: Box<dyn Fn()>,
+    ) -> Result<NotificationHandle, NotificationError>;
+}
+
+/// The provider using the browser's `Notification` API.
+struct BrowserNotifications;
+
+fn notifications_supported() -> bool {
+    js_sys::Reflect::has(&js_sys::global(), &"Notification".into()).unwrap_or(false)
+}
+
+fn js_error_message(error: &JsValue) -> String {
+    match error.dyn_ref::<js_sys::Error>() {
+        Some(error) => error.message().into(),
+        None => format!("{error:?}"),
+    }
+}
+
+impl NotificationProvider for BrowserNotifications {
+    fn permission(&self) -> Option<NotificationPermission> {
+        if !notifications_supported() {
+            return None;
+        }
+        Some(match web_sys::Notification::permission() {
+            web_sys::NotificationPermission::Granted => NotificationPermission::Granted,
+            web_sys::NotificationPermission::Denied => NotificationPermission::Denied,
+            _ => NotificationPermission::Default,
+        })
+    }
+
+    fn request_permission(&self, on_result: Box<dyn FnOnce(NotificationPermission)>) {
+        let promise = match web_sys::Notification::request_permission() {
+            Ok(promise) if notifications_supported() => promise,
+            _ => {
+                on_result(NotificationPermission::Denied);
+                return;
+            }
+        };
+        wasm_bindgen_futures::spawn_local(async move {
+            let answer = JsFuture::from(promise)
+                .await
+                .ok()
+                .and_then(|answer| answer.as_string());
+            on_result(NotificationPermission::from_answer(answer.as_deref()));
+        });
+    }
+
+    fn show(
+        &self,
+        spec: &NotificationSpec,
+        on_click: Box<dyn Fn()>,
+    ) -> Result<NotificationHandle, NotificationError> {
+        let mut options = web_sys::NotificationOptions::new();
+        if let Some(body) = &spec.body {
+            options.body(body);
+        }
+        if let Some(icon) = &spec.icon {
+            options.icon(icon);
+        }
+        if let Some(tag) = &spec.tag {
+            options.tag(tag);
+        }
+        let notification = web_sys::Notification::new_with_options(&spec.title, &options)
+            .map_err(|error| NotificationError::Failed(js_error_message(&error)))?;
+        let listener = EventListener::new(&notification, "click", move |_| {
+            let _ = web_sys::window().unwrap_throw().focus();
+            on_click();
+        });
+        Ok(NotificationHandle::new(move || {
+            notification.close();
+            // Clicks are reported as long as the handle is alive.
+            let _ = &listener;
+        }))
+    }
+}
+
+/// Ask the user for the permission to show notifications with [`notify`].
+///
+/// Browsers only ask from the handler of a user gesture, e.g. a click. `on_result` is
+/// called with the answer once the user has given it. It is called outside of the app's
+/// message handling, so it should e.g. push a message through a
+/// [thunk](Cx::message_thunk) to update the app state.
+pub fn request_notification_permission(on_result: impl FnOnce(NotificationPermission) + 'static) {
+    BrowserNotifications.request_permission(Box::new(on_result));
+}
+
+/// Whether the page may show notifications, or `None` if the browser doesn't support them.
+pub fn notification_permission() -> Option<NotificationPermission> {
+    BrowserNotifications.permission()
+}
+
+/// Set the badge of the installed app to `count`, or clear it with `None`, using
+/// [`navigator.setAppBadge`](https://developer.mozilla.org/en-US/docs/Web/API/Navigator/setAppBadge).
+///
+/// This does nothing in browsers which don't support app badges.
+pub fn set_app_badge(count: Option<u64>) {
+    let navigator = web_sys::window().unwrap_throw().navigator();
+    let (method, args) = match count {
+        Some(count) => ("setAppBadge", js_sys::Array::of1(&(count as f64).into())),
+        None => ("clearAppBadge", js_sys::Array::new()),
+    };
+    let Some(function) = js_sys::Reflect::get(&navigator, &method.into())
+        .ok()
+        .and_then(|function| function.dyn_into::<js_sys::Function>().ok())
+    else {
+        return;
+    };
+    if let Ok(promise) = function.apply(&navigator, &args) {
+        if let Ok(promise) = promise.dyn_into::<js_sys::Promise>() {
+            // Rejections, e.g. because the app isn't installed, are ignored.
+            wasm_bindgen_futures::spawn_local(async move {
+                let _ = JsFuture::from(promise).await;
+            });
+        }
+    }
+}
+
+type ClickHandler<T, A> = Box<dyn Fn(&mut T) -> Option<A>>;
+type ErrorHandler<T, A> = Box<dyn Fn(&mut T, NotificationError) -> Option<A>>;
+
+/// Shows a notification whenever `key` changes, e.g. with the number of received
+/// messages, with the content returned by `spec`.
+///
+/// No notification is shown when the view is built. Notifications are only shown once
+/// the user has granted the [permission](request_notification_permission); otherwise
+/// the [error handler](Notify::on_error) is called instead. When the view is torn down,
+/// the notifications it showed with a [tag](NotificationSpec::tag) are closed.
+///
+/// ```ignore
+/// notify(state.messages.len(), |_| NotificationSpec {
+///     title: "New message".into(),
+///     tag: Some("chat".into()),
+///     ..Default::default()
+/// })
+/// .on_notification_click(|state: &mut AppState| state.show_chat = true)
+/// ```
+pub fn notify<T, A, K, F>(key: K, spec: F) -> Notify<T, A, K, F>
+where
+    K: PartialEq,
+    F: Fn(&K) -> NotificationSpec,
+{
+    Notify {
+        key,
+        spec,
+        on_click: None,
+        on_error: None,
+        provider: Rc::new(BrowserNotifications),
+        phantom: PhantomData,
+    }
+}
+
+pub struct Notify<T, A, K, F> {
+    key: K,
+    spec: F,
+    on_click: Option<ClickHandler<T, A>>,
+    on_error: Option<ErrorHandler<T, A>>,
+    provider: Rc<dyn NotificationProvider>,
+    phantom: PhantomData<fn() -> (T, A)>,
+}
+
+impl<T, A, K, F> Notify<T, A, K, F> {
+    /// Call `handler` when the user clicks a notification shown by this view.
+    ///
+    /// The window is focused before `handler` is called.
+    pub fn on_notification_click<OA>(mut self, handler: impl Fn(&mut T) -> OA + 'static) -> Self
+    where
+        OA: OptionalAction<A>,
+    {
+        self.on_click = Some(Box::new(move |app_state| handler(app_state).action()));
+        self
+    }
+
+    /// Call `handler` when a notification can't be shown, e.g. because the permission
+    /// was denied.
+    pub fn on_error<OA>(
+        mut self,
+        handler: impl Fn(&mut T, NotificationError) -> OA + 'static,
+    ) -> Self
+    where
+        OA: OptionalAction<A>,
+    {
+        self.on_error = Some(Box::new(move |app_state, error| {
+            handler(app_state, error).action()
+        }));
+        self
+    }
+
+    /// Use `provider` instead of the browser's `Notification` API.
+    pub fn provider(mut self, provider: impl NotificationProvider + 'static) -> Self {
+        self.provider = Rc::new(provider);
+        self
+    }
+}
+
+struct ShownNotification {
+    tag: Option<String>,
+    handle: NotificationHandle,
+}
+
+/// State for the [`Notify`] view.
+///
+/// The tagged notifications shown by the view are closed when this is dropped, i.e.
+/// when the view is torn down.
+pub struct NotifyState {
+    shown: Vec<ShownNotification>,
+}
+
+impl Drop for NotifyState {
+    fn drop(&mut self) {
+        for shown in &self.shown {
+            if shown.tag.is_some() {
+                shown.handle.close();
+            }
+        }
+    }
+}
+
+struct NotificationClicked;
+
+struct NotificationFailed(NotificationError);
+
+impl<T, A, K, F> Notify<T, A, K, F>
+where
+    F: Fn(&K) -> NotificationSpec,
+{
+    fn show(&self, cx: &Cx, state: &mut NotifyState) {
+        let spec = (self.spec)(&self.key);
+        let result = match self.provider.permission() {
+            Some(NotificationPermission::Granted) => {
+                let thunk = cx.message_thunk();
+                self.provider.show(
+                    &spec,
+                    Box::new(move || thunk.push_message(NotificationClicked)),
+                )
+            }
+            Some(NotificationPermission::Denied) => Err(NotificationError::PermissionDenied),
+            Some(NotificationPermission::Default) => Err(NotificationError::PermissionNotRequested),
+            None => Err(NotificationError::Unsupported),
+        };
+        match result {
+            Ok(handle) => {
+                if spec.tag.is_some() {
+                    // The browser replaced the previous notification with the same tag.
+                    state.shown.retain(|shown| shown.tag != spec.tag);
+                }
+                state.shown.push(ShownNotification {
+                    tag: spec.tag,
+                    handle,
+                });
+            }
+            Err(error) => push_message_later(cx, NotificationFailed(error)),
+        }
+    }
+}
+
+impl<T, A, K, F> ViewMarker for Notify<T, A, K, F> {}
+
+impl<T, A, K, F> View<T, A> for Notify<T, A, K, F>
+where
+    K: PartialEq,
+    F: Fn(&K) -> NotificationSpec,
+{
+    type State = NotifyState;
+
+    type Element = web_sys::Text;
+
+    fn build(&self, cx: &mut Cx) -> (Id, Self::State, Self::Element) {
+        let state = NotifyState { shown: Vec::new() };
+        (Id::next(), state, cx.document().create_text_node(""))
+    }
+
+    fn rebuild(
+        &self,
+        cx: &mut Cx,
+        prev: &Self,
+        id: &mut Id,
+        state: &mut Self::State,
+        _element: &mut Self::Element,
+    ) -> ChangeFlags {
+        if self.key != prev.key {
+            cx.with_id(*id, |cx| self.show(cx, state));
+        }
+        ChangeFlags::empty()
+    }
+
+    fn message(
+        &self,
+        id_path: &[Id],
+        _state: &mut Self::State,
+        message: Box<dyn Any>,
+        app_state: &mut T,
+    ) -> MessageResult<A> {
+        if !id_path.is_empty() {
+            return MessageResult::Stale(message);
+        }
+        let message = match message.downcast::<NotificationClicked>() {
+            Ok(_) => {
+                let action = self
+                    .on_click
+                    .as_ref()
+                    .and_then(|handler| handler(app_state));
+                return match action {
+                    Some(a) => MessageResult::Action(a),
+                    None => MessageResult::Nop,
+                };
+            }
+            Err(message) => message,
+        };
+        match message.downcast::<NotificationFailed>() {
+            Ok(failed) => {
+                let NotificationFailed(error) = *failed;
+                let action = match &self.on_error {
+                    Some(handler) => handler(app_state, error),
+                    None => {
+                        log::warn!("{error}");
+                        None
+                    }
+                };
+                match action {
+                    Some(a) => MessageResult::Action(a),
+                    None => MessageResult::Nop,
+                }
+            }
+            Err(message) => MessageResult::Stale(message),
+        }
+    }
+}
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod tests {
+    use std::cell::{Cell, RefCell};
+
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use super::*;
+    use crate::test_util::{next_tick, Recorder};
+
+    wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+    type ClickCallback = Box<dyn Fn()>;
+
+    /// A fake `Notification` constructor, recording the notifications it shows.
+    #[derive(Clone)]
+    struct MockNotifications {
+        permission: Rc<Cell<Option<NotificationPermission>>>,
+        shown: Rc<RefCell<Vec<NotificationSpec>>>,
+        open: Rc<RefCell<Vec<NotificationSpec>>>,
+        on_click: Rc<RefCell<Option<ClickCallback>>>,
+    }
+
+    impl MockNotifications {
+        fn new(permission: NotificationPermission) -> Self {
+            MockNotifications {
+                permission: Rc::new(Cell::new(Some(permission))),
+                shown: Rc::default(),
+                open: Rc::default(),
+                on_click: Rc::default(),
+            }
+        }
+    }
+
+    impl NotificationProvider for MockNotifications {
+        fn permission(&self) -> Option<NotificationPermission> {
+            self.permission.get()
+        }
+
+        fn request_permission(&self, on_result: Box<dyn FnOnce(NotificationPermission)>) {
+            self.permission.set(Some(NotificationPermission::Granted));
+            on_result(NotificationPermission::Granted);
+        }
+
+        fn show(
+            &self,
+            spec: &NotificationSpec,
+            on_click: Box<dyn Fn()>,
+        ) -> Result<NotificationHandle, NotificationError> {
+            self.shown.borrow_mut().push(spec.clone());
+            self.open.borrow_mut().push(spec.clone());
+            *self.on_click.borrow_mut() = Some(on_click);
+            let open = self.open.clone();
+            let spec = spec.clone();
+            Ok(NotificationHandle::new(move || {
+                open.borrow_mut().retain(|open| *open != spec);
+            }))
+        }
+    }
+
+    fn message_spec(count: &u32) -> NotificationSpec {
+        NotificationSpec {
+            title: "New message".into(),
+            body: Some(format!("{count} unread")),
+            icon: Some("/icon.png".into()),
+            tag: Some("chat".into()),
+        }
+    }
+
+    #[derive(Default)]
+    struct AppState {
+        clicks: u32,
+        errors: Vec<NotificationError>,
+    }
+
+    fn notify_view(
+        count: u32,
+        mock: &MockNotifications,
+    ) -> Notify<AppState, (), u32, fn(&u32) -> NotificationSpec> {
+        notify(count, message_spec as fn(&u32) -> NotificationSpec)
+            .on_notification_click(|state: &mut AppState| state.clicks += 1)
+            .on_error(|state: &mut AppState, error| state.errors.push(error))
+            .provider(mock.clone())
+    }
+
+    #[wasm_bindgen_test]
+    async fn shows_notification_when_key_changes() {
+        let recorder = Recorder::default();
+        let mut cx = recorder.cx();
+        let mock = MockNotifications::new(NotificationPermission::Granted);
+        let view = notify_view(0, &mock);
+        let (mut id, mut state, mut element) = view.build(&mut cx);
+        assert!(mock.shown.borrow().is_empty());
+
+        let same = notify_view(0, &mock);
+        same.rebuild(&mut cx, &view, &mut id, &mut state, &mut element);
+        assert!(mock.shown.borrow().is_empty());
+
+        let changed = notify_view(2, &mock);
+        changed.rebuild(&mut cx, &same, &mut id, &mut state, &mut element);
+        assert_eq!(*mock.shown.borrow(), [message_spec(&2)]);
+
+        // Clicks are delivered to the view.
+        (mock.on_click.borrow().as_ref().unwrap())();
+        let mut app_state = AppState::default();
+        recorder.deliver(&changed, &mut state, &mut app_state);
+        assert_eq!(app_state.clicks, 1);
+    }
+
+    #[wasm_bindgen_test]
+    async fn notifications_need_the_permission() {
+        let recorder = Recorder::default();
+        let mut cx = recorder.cx();
+        let mock = MockNotifications::new(NotificationPermission::Default);
+        let view = notify_view(0, &mock);
+        let (mut id, mut state, mut element) = view.build(&mut cx);
+
+        let changed = notify_view(1, &mock);
+        changed.rebuild(&mut cx, &view, &mut id, &mut state, &mut element);
+        assert!(mock.shown.borrow().is_empty());
+        next_tick().await;
+        let mut app_state = AppState::default();
+        recorder.deliver(&changed, &mut state, &mut app_state);
+        assert_eq!(
+            app_state.errors,
+            [NotificationError::PermissionNotRequested]
+        );
+
+        mock.permission.set(Some(NotificationPermission::Denied));
+        let denied = notify_view(2, &mock);
+        denied.rebuild(&mut cx, &changed, &mut id, &mut state, &mut element);
+        next_tick().await;
+        recorder.deliver(&denied, &mut state, &mut app_state);
+        assert_eq!(app_state.errors[1], NotificationError::PermissionDenied);
+        assert!(mock.shown.borrow().is_empty());
+
+        mock.request_permission(Box::new(|_| {}));
+        let granted = notify_view(3, &mock);
+        granted.rebuild(&mut cx, &denied, &mut id, &mut state, &mut element);
+        assert_eq!(mock.shown.borrow().len(), 1);
+    }
+
+    #[wasm_bindgen_test]
+    fn tagged_notifications_are_closed_on_teardown() {
+        let recorder = Recorder::default();
+        let mut cx = recorder.cx();
+        let mock = MockNotifications::new(NotificationPermission::Granted);
+        let view = notify::<(), (), _, _>(0, |count: &u32| NotificationSpec {
+            title: format!("{count}"),
+            tag: count.is_multiple_of(2).then(|| "even".into()),
+            ..Default::default()
+        })
+        .provider(mock.clone());
+        let (mut id, mut state, mut element) = view.build(&mut cx);
+        let mut prev = view;
+        for count in 1..=3 {
+            let next = notify(count, prev.spec).provider(mock.clone());
+            next.rebuild(&mut cx, &prev, &mut id, &mut state, &mut element);
+            prev = next;
+        }
+        assert_eq!(mock.open.borrow().len(), 3);
+
+        drop(state);
+        let open: Vec<String> = mock
+            .open
+            .borrow()
+            .iter()
+            .map(|spec| spec.title.clone())
+            .collect();
+        assert_eq!(open, ["1", "3"]);
+    }
+
+    #[wasm_bindgen_test]
+    fn app_badge_is_a_no_op_when_unsupported() {
+        set_app_badge(Some(3));
+        set_app_badge(None);
+    }
+}
@@ -0,0 +1,575 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Views reacting to the state of the page rather than to one of its elements.
+//!
+//! These views don't render anything (their element is an empty text node),
+//! so they can be put anywhere in a view sequence.
+
+use std::{any::Any, cell::RefCell, marker::PhantomData};
+
+use gloo::events::{EventListener, EventListenerOptions};
+use wasm_bindgen::{JsCast, UnwrapThrowExt};
+use xilem_core::{Id, MessageResult};
+
+use crate::{ChangeFlags, Cx, OptionalAction, View, ViewMarker};
+
+struct VisibilityChanged(bool);
+
+struct OnlineChanged(bool);
+
+struct Unloading;
+
+/// Calls `handler` with whether the page is visible, once after the view is built
+/// and then whenever the `visibilitychange` event fires on the document.
+///
+/// This can be used to pause expensive work while the tab is in the background:
+///
+/// ```ignore
+/// (
+///     on_visibility_change(|state: &mut AppState, visible| state.paused = !visible),
+///     // ... the rest of the app, which checks `state.paused` before polling
+/// )
+/// ```
+pub fn on_visibility_change<T, A, F, OA>(handler: F) -> OnVisibilityChange<T, A, F>
+where
+    F: Fn(&mut T, bool) -> OA,
+    OA: OptionalAction<A>,
+{
+    OnVisibilityChange {
+        handler,
+        phantom: PhantomData,
+    }
+}
+
+/// Calls `handler` with whether the browser is online, once after the view is built
+/// (using `navigator.onLine`) and then whenever the `online` or `offline` events fire
+/// on the window.
+pub fn on_online_change<T, A, F, OA>(handler: F) -> OnOnlineChange<T, A, F>
+where
+    F: Fn(&mut T, bool) -> OA,
+    OA: OptionalAction<A>,
+{
+    OnOnlineChange {
+        handler,
+        phantom: PhantomData,
+    }
+}
+
+/// Asks the browser to confirm before the user leaves the page, while `when` is true.
+///
+/// This is meant to warn about unsaved changes, e.g. with `confirm_unload(state.dirty)`.
+/// The browser shows its own dialog, whose text can't be customized. Any number of these
+/// views can be in the app; confirmation is requested while at least one of them has
+/// `when` set.
+pub fn confirm_unload(when: bool) -> ConfirmUnload {
+    ConfirmUnload { when }
+}
+
+/// Calls `handler` synchronously when the page is about to be unloaded.
+///
+/// This is a best-effort hook, e.g. to save state to `localStorage`: browsers may skip
+/// the `beforeunload` event (notably on mobile), and nothing asynchronous started from
+/// `handler` is guaranteed to complete.
+pub fn on_unload<T, A, F, OA>(handler: F) -> OnUnload<T, A, F>
+where
+    F: Fn(&mut T) -> OA,
+    OA: OptionalAction<A>,
+{
+    OnUnload {
+        handler,
+        phantom: PhantomData,
+    }
+}
+
+pub struct OnVisibilityChange<T, A, F> {
+    handler: F,
+    phantom: PhantomData<fn() -> (T, A)>,
+}
+
+pub struct OnOnlineChange<T, A, F> {
+    handler: F,
+    phantom: PhantomData<fn() -> (T, A)>,
+}
+
+pub struct ConfirmUnload {
+    when: bool,
+}
+
+pub struct OnUnload<T, A, F> {
+    handler: F,
+    phantom: PhantomData<fn() -> (T, A)>,
+}
+
+/// State for the [`OnVisibilityChange`], [`OnOnlineChange`] and [`OnUnload`] views.
+///
+/// The listeners are removed when this is dropped, i.e. when the view is torn down.
+pub struct PageEventState {
+    #[allow(unused)]
+    listeners: Vec<EventListener>,
+}
+
+/// Deliver a message to the view being built once the current build/rebuild is over,
+/// since the app can't handle messages while it's in the middle of one.
+pub(crate) fn push_message_later(cx: &Cx, message: impl Any + 'static) {
+    let thunk = cx.message_thunk();
+    wasm_bindgen_futures::spawn_local(async move { thunk.push_message(message) });
+}
+
+fn is_online() -> bool {
+    web_sys::window().unwrap_throw().navigator().on_line()
+}
+
+/// The `beforeunload` listener asking for confirmation, shared by all the
+/// [`ConfirmUnload`] views requesting it.
+#[derive(Default)]
+struct UnloadConfirmation {
+    requests: usize,
+    listener: Option<EventListener>,
+}
+
+thread_local! {
+    static UNLOAD_CONFIRMATION: RefCell<UnloadConfirmation> = RefCell::default();
+}
+
+fn request_unload_confirmation() {
+    UNLOAD_CONFIRMATION.with_borrow_mut(|confirmation| {
+        if confirmation.requests == 0 {
+            let window = web_sys::window().unwrap_throw();
+            confirmation.listener = Some(EventListener::new_with_options(
+                &window,
+                "beforeunload",
+                EventListenerOptions::enable_prevent_default(),
+                |event| {
+                    event.prevent_default();
+                    // Older browsers only show the dialog if `returnValue` is set
+                    event
+                        .unchecked_ref::<web_sys::BeforeUnloadEvent>()
+                        .set_return_value("");
+                },
+            ));
+        }
+        confirmation.requests += 1;
+    });
+}
+
+fn release_unload_confirmation() {
+    UNLOAD_CONFIRMATION.with_borrow_mut(|confirmation| {
+        confirmation.requests -= 1;
+        if confirmation.requests == 0 {
+            confirmation.listener = None;
+        }
+    });
+}
+
+/// Whether a [`confirm_unload`] view currently asks for confirmation before unloading,
+/// i.e. whether the `beforeunload` listener is registered.
+pub fn is_unload_confirmation_active() -> bool {
+    UNLOAD_CONFIRMATION.with_borrow(|confirmation| confirmation.listener.is_some())
+}
+
+/// State for the [`ConfirmUnload`] view.
+///
+/// Its request for confirmation is released when this is dropped, i.e. when the view
+/// is torn down.
+pub struct ConfirmUnloadState {
+    requested: bool,
+}
+
+impl ConfirmUnloadState {
+    fn set_requested(&mut self, requested: bool) {
+        match (self.requested, requested) {
+            (false, true) => request_unload_confirmation(),
+            (true, false) => release_unload_confirmation(),
+            _ => {}
+        }
+        self.requested = requested;
+    }
+}
+
+impl Drop for ConfirmUnloadState {
+    fn drop(&mut self) {
+        self.set_requested(false);
+    }
+}
+
+impl<T, A, F> ViewMarker for OnVisibilityChange<T, A, F> {}
+
+impl<T, A, F, OA> View<T, A> for OnVisibilityChange<T, A, F>
+where
+    F: Fn(&mut T, bool) -> OA,
+    OA: OptionalAction<A>,
+{
+    type State = PageEventState;
+
+    type Element = web_sys::Text;
+
+    fn build(&self, cx: &mut Cx) -> (Id, Self::State, Self::Element) {
+        let (id, listener) = cx.with_new_id(|cx| {
+            let document = cx.document().clone();
+            push_message_later(cx, VisibilityChanged(!document.hidden()));
+
+            let thunk = cx.message_thunk();
+            EventListener::new(cx.document(), "visibilitychange", move |_| {
+                thunk.push_message(VisibilityChanged(!document.hidden()));
+            })
+        });
+        let state = PageEventState {
+            listeners: vec![listener],
+        };
+        (id, state, cx.document().create_text_node(""))
+    }
+
+    fn rebuild(
+        &self,
+        _cx: &mut Cx,
+        _prev: &Self,
+        _id: &mut Id,
+        _state: &mut Self::State,
+        _element: &mut Self::Element,
+    ) -> ChangeFlags {
+        ChangeFlags::empty()
+    }
+
+    fn message(
+        &self,
+        id_path: &[Id],
+        _state: &mut Self::State,
+        message: Box<dyn Any>,
+        app_state: &mut T,
+    ) -> MessageResult<A> {
+        match id_path {
+            [] if message.downcast_ref::<VisibilityChanged>().is_some() => {
+                let VisibilityChanged(visible) = *message.downcast::<VisibilityChanged>().unwrap();
+                match (self.handler)(app_state, visible).action() {
+                    Some(a) => MessageResult::Action(a),
+                    None => MessageResult::Nop,
+                }
+            }
+            _ => MessageResult::Stale(message),
+        }
+    }
+}
+
+impl<T, A, F> ViewMarker for OnOnlineChange<T, A, F> {}
+
+impl<T, A, F, OA> View<T, A> for OnOnlineChange<T, A, F>
+where
+    F: Fn(&mut T, bool) -> OA,
+    OA: OptionalAction<A>,
+{
+    type State = PageEventState;
+
+    type Element = web_sys::Text;
+
+    fn build(&self, cx: &mut Cx) -> (Id, Self::State, Self::Element) {
+        let (id, listeners) = cx.with_new_id(|cx| {
+            push_message_later(cx, OnlineChanged(is_online()));
+
+            let window = web_sys::window().unwrap_throw();
+            ["online", "offline"]
+                .into_iter()
+                .map(|event| {
+                    // The event tells which way the status changed, even if
+                    // `navigator.onLine` changes again before the message is handled.
+                    let online = event == "online";
+                    let thunk = cx.message_thunk();
+                    EventListener::new(&window, event, move |_| {
+                        thunk.push_message(OnlineChanged(online));
+                    })
+                })
+                .collect::<Vec<_>>()
+        });
+        (
+            id,
+            PageEventState { listeners },
+            cx.document().create_text_node(""),
+        )
+    }
+
+    fn rebuild(
+        &self,
+        _cx: &mut Cx,
+        _prev: &Self,
+        _id: &mut Id,
+        _state: &mut Self::State,
+        _element: &mut Self::Element,
+    ) -> ChangeFlags {
+        ChangeFlags::empty()
+    }
+
+    fn message(
+        &self,
+        id_path: &[Id],
+        _state: &mut Self::State,
+        message: Box<dyn Any>,
+        app_state: &mut T,
+    ) -> MessageResult<A> {
+        match id_path {
+            [] if message.downcast_ref::<OnlineChanged>().is_some() => {
+                let OnlineChanged(online) = *message.downcast::<OnlineChanged>().unwrap();
+                match (self.handler)(app_state, online).action() {
+                    Some(a) => MessageResult::Action(a),
+                    None => MessageResult::Nop,
+                }
+            }
+            _ => MessageResult::Stale(message),
+        }
+    }
+}
+
+impl ViewMarker for ConfirmUnload {}
+
+impl<T, A> View<T, A> for ConfirmUnload {
+    type State = ConfirmUnloadState;
+
+    type Element = web_sys::Text;
+
+    fn build(&self, cx: &mut Cx) -> (Id, Self::State, Self::Element) {
+        let mut state = ConfirmUnloadState { requested: false };
+        state.set_requested(self.when);
+        (Id::next(), state, cx.document().create_text_node(""))
+    }
+
+    fn rebuild(
+        &self,
+        _cx: &mut Cx,
+        _prev: &Self,
+        _id: &mut Id,
+        state: &mut Self::State,
+        _element: &mut Self::Element,
+    ) -> ChangeFlags {
+        state.set_requested(self.when);
+        ChangeFlags::empty()
+    }
+
+    fn message(
+        &self,
+        _id_path: &[Id],
+        _state: &mut Self::State,
+        message: Box<dyn Any>,
+        _app_state: &mut T,
+    ) -> MessageResult<A> {
+        MessageResult::Stale(message)
+    }
+}
+
+impl<T, A, F> ViewMarker for OnUnload<T, A, F> {}
+
+impl<T, A, F, OA> View<T, A> for OnUnload<T, A, F>
+where
+    F: Fn(&mut T) -> OA,
+    OA: OptionalAction<A>,
+{
+    type State = PageEventState;
+
+    type Element = web_sys::Text;
+
+    fn build(&self, cx: &mut Cx) -> (Id, Self::State, Self::Element) {
+        let (id, listener) = cx.with_new_id(|cx| {
+            // Messages are handled synchronously, so the handler runs before the
+            // listener returns
+            let thunk = cx.message_thunk();
+            let window = web_sys::window().unwrap_throw();
+            EventListener::new(&window, "beforeunload", move |_| {
+                thunk.push_message(Unloading);
+            })
+        });
+        let state = PageEventState {
+            listeners: vec![listener],
+        };
+        (id, state, cx.document().create_text_node(""))
+    }
+
+    fn rebuild(
+        &self,
+        _cx: &mut Cx,
+        _prev: &Self,
+        _id: &mut Id,
+        _state: &mut Self::State,
+        _element: &mut Self::Element,
+    ) -> ChangeFlags {
+        ChangeFlags::empty()
+    }
+
+    fn message(
+        &self,
+        id_path: &[Id],
+        _state: &mut Self::State,
+        message: Box<dyn Any>,
+        app_state: &mut T,
+    ) -> MessageResult<A> {
+        match id_path {
+            [] if message.downcast_ref::<Unloading>().is_some() => {
+                match (self.handler)(app_state).action() {
+                    Some(a) => MessageResult::Action(a),
+                    None => MessageResult::Nop,
+                }
+            }
+            _ => MessageResult::Stale(message),
+        }
+    }
+}
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod tests {
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use super::*;
+    use crate::test_util::{dispatch, next_tick, Recorder};
+
+    wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+    /// Override `document.hidden`, which can't be changed from a test otherwise, and
+    /// fire `visibilitychange`.
+    fn set_hidden(hidden: bool) {
+        let document = crate::document();
+        let descriptor = js_sys::Object::new();
+        js_sys::Reflect::set(&descriptor, &"value".into(), &hidden.into()).unwrap();
+        js_sys::Reflect::set(&descriptor, &"configurable".into(), &true.into()).unwrap();
+        js_sys::Object::define_property(&document, &"hidden".into(), &descriptor);
+        dispatch(&document, "visibilitychange");
+    }
+
+    fn reset_hidden() {
+        js_sys::Reflect::delete_property(&crate::document(), &"hidden".into()).unwrap();
+    }
+
+    fn record_changes(seen: &mut Vec<bool>, value: bool) {
+        seen.push(value);
+    }
+
+    #[wasm_bindgen_test]
+    async fn visibility_changes_are_delivered() {
+        let recorder = Recorder::default();
+        let mut cx = recorder.cx();
+        let view = on_visibility_change(record_changes);
+        let (_, mut state, _) = view.build(&mut cx);
+        let mut seen = vec![];
+
+        // The initial value is delivered once the build is over.
+        assert!(recorder.0.borrow().is_empty());
+        next_tick().await;
+        recorder.deliver(&view, &mut state, &mut seen);
+        assert_eq!(seen, [!crate::document().hidden()]);
+
+        set_hidden(true);
+        set_hidden(false);
+        reset_hidden();
+        recorder.deliver(&view, &mut state, &mut seen);
+        assert_eq!(seen[1..], [false, true]);
+
+        // The listener is removed on teardown.
+        drop(state);
+        dispatch(&crate::document(), "visibilitychange");
+        assert!(recorder.0.borrow().is_empty());
+    }
+
+    #[wasm_bindgen_test]
+    async fn online_changes_are_delivered() {
+        let recorder = Recorder::default();
+        let mut cx = recorder.cx();
+        let view = on_online_change(record_changes);
+        let (_, mut state, _) = view.build(&mut cx);
+        let mut seen = vec![];
+
+        next_tick().await;
+        recorder.deliver(&view, &mut state, &mut seen);
+        assert_eq!(seen, [is_online()]);
+
+        let window = web_sys::window().unwrap();
+        dispatch(&window, "offline");
+        dispatch(&window, "online");
+        recorder.deliver(&view, &mut state, &mut seen);
+        assert_eq!(seen[1..], [false, true]);
+
+        drop(state);
+        dispatch(&window, "offline");
+        assert!(recorder.0.borrow().is_empty());
+    }
+
+    #[wasm_bindgen_test]
+    async fn each_instance_is_notified_once() {
+        let recorder = Recorder::default();
+        let mut cx = recorder.cx();
+        let view = on_online_change::<Vec<bool>, (), _, _>(record_changes);
+        let (first_id, _first, _) = view.build(&mut cx);
+        let (second_id, _second, _) = view.build(&mut cx);
+        next_tick().await;
+        recorder.0.borrow_mut().clear();
+
+        dispatch(&web_sys::window().unwrap(), "offline");
+        let targets: Vec<_> = recorder
+            .0
+            .take()
+            .iter()
+            .map(|message| message.id_path.clone())
+            .collect();
+        assert_eq!(targets, [vec![first_id], vec![second_id]]);
+    }
+
+    /// Dispatch a cancelable `beforeunload` event, returning whether it was cancelled,
+    /// i.e. whether the browser would ask for confirmation.
+    fn before_unload() -> bool {
+        let mut init = web_sys::EventInit::new();
+        init.cancelable(true);
+        let event = web_sys::Event::new_with_event_init_dict("beforeunload", &init).unwrap();
+        web_sys::window().unwrap().dispatch_event(&event).unwrap();
+        event.default_prevented()
+    }
+
+    #[wasm_bindgen_test]
+    fn confirmation_follows_the_flag() {
+        let mut cx = Cx::new();
+        let prev = confirm_unload(false);
+        let (mut id, mut state, mut element) = View::<(), ()>::build(&prev, &mut cx);
+        assert!(!is_unload_confirmation_active());
+        assert!(!before_unload());
+
+        let next = confirm_unload(true);
+        View::<(), ()>::rebuild(&next, &mut cx, &prev, &mut id, &mut state, &mut element);
+        assert!(is_unload_confirmation_active());
+        assert!(before_unload());
+
+        View::<(), ()>::rebuild(&prev, &mut cx, &next, &mut id, &mut state, &mut element);
+        assert!(!is_unload_confirmation_active());
+        assert!(!before_unload());
+
+        // Teardown releases the request.
+        View::<(), ()>::rebuild(&next, &mut cx, &prev, &mut id, &mut state, &mut element);
+        drop(state);
+        assert!(!is_unload_confirmation_active());
+    }
+
+    #[wasm_bindgen_test]
+    fn confirmation_is_refcounted() {
+        let mut cx = Cx::new();
+        let (_, first, _) = View::<(), ()>::build(&confirm_unload(true), &mut cx);
+        let (_, second, _) = View::<(), ()>::build(&confirm_unload(true), &mut cx);
+        assert!(is_unload_confirmation_active());
+
+        // Any instance still requesting it keeps it active.
+        drop(first);
+        assert!(is_unload_confirmation_active());
+        drop(second);
+        assert!(!is_unload_confirmation_active());
+    }
+
+    #[wasm_bindgen_test]
+    fn unload_handler_runs_synchronously() {
+        let recorder = Recorder::default();
+        let mut cx = recorder.cx();
+        let view = on_unload(|unloads: &mut u32| *unloads += 1);
+        let (_, mut state, _) = view.build(&mut cx);
+
+        // The message is pushed from the listener itself, not deferred.
+        before_unload();
+        let mut unloads = 0;
+        recorder.deliver(&view, &mut state, &mut unloads);
+        assert_eq!(unloads, 1);
+
+        drop(state);
+        before_unload();
+        assert!(recorder.0.borrow().is_empty());
+    }
+}
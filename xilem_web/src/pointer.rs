@@ -2,18 +2,25 @@
 // SPDX-License-Identifier: Apache-2.0
 
 //! Interactivity with pointer events.
+//!
+//! [Pointer events](https://developer.mozilla.org/en-US/docs/Web/API/Pointer_events) are
+//! sent for mice, touches and pens alike, like masonry's `PointerEvent`. The views
+//! created by [`Element::on_pointer_down`] and its siblings pass them to their handler
+//! as [`PointerDetails`].
 
 use std::{any::Any, marker::PhantomData};
 
-use wasm_bindgen::{prelude::Closure, JsCast};
+use gloo::events::{EventListener, EventListenerOptions};
+use wasm_bindgen::{prelude::Closure, JsCast, UnwrapThrowExt};
 use web_sys::PointerEvent;
 
 use xilem_core::{Id, MessageResult};
 
 use crate::{
     context::{ChangeFlags, Cx},
-    interfaces::Element,
+    interfaces::{sealed::Sealed, Element},
     view::{DomNode, View, ViewMarker},
+    OptionalAction,
 };
 
 pub struct Pointer<V, T, A, F> {
@@ -41,26 +48,69 @@ pub enum PointerMsg {
     Up(PointerDetails),
 }
 
-#[derive(Debug)]
+/// The kind of device which sent a pointer event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PointerType {
+    Mouse,
+    Touch,
+    Pen,
+    /// A device the browser doesn't name.
+    Unknown,
+}
+
+#[derive(Debug, Clone)]
 /// Details of a pointer event.
 pub struct PointerDetails {
+    /// Identifies the pointer, e.g. a finger, for as long as it touches the screen.
     pub id: i32,
+    pub pointer_type: PointerType,
+    /// Whether this is the primary pointer of its type, e.g. the first finger to touch
+    /// the screen.
+    pub is_primary: bool,
+    /// The button which changed, see [`MouseEvent.button`](https://developer.mozilla.org/en-US/docs/Web/API/MouseEvent/button).
     pub button: i16,
+    /// The buttons which are pressed, as a bit mask.
+    pub buttons: u16,
+    /// The pressure between 0 and 1. Devices which don't report it use 0.5 while a
+    /// button is pressed, and 0 otherwise.
+    pub pressure: f32,
+    /// The position in the viewport.
     pub x: f64,
     pub y: f64,
+    /// The position relative to the padding edge of the element.
+    pub offset_x: f64,
+    pub offset_y: f64,
 }
 
 impl PointerDetails {
     fn from_pointer_event(e: &PointerEvent) -> Self {
+        let pointer_type = match e.pointer_type().as_str() {
+            "mouse" => PointerType::Mouse,
+            "touch" => PointerType::Touch,
+            "pen" => PointerType::Pen,
+            _ => PointerType::Unknown,
+        };
         PointerDetails {
             id: e.pointer_id(),
+            pointer_type,
+            is_primary: e.is_primary(),
             button: e.button(),
+            buttons: e.buttons(),
+            pressure: e.pressure(),
             x: e.client_x() as f64,
             y: e.client_y() as f64,
+            offset_x: e.offset_x() as f64,
+            offset_y: e.offset_y() as f64,
         }
     }
 }
 
+impl From<&PointerEvent> for PointerDetails {
+    fn from(event: &PointerEvent) -> Self {
+        PointerDetails::from_pointer_event(event)
+    }
+}
+
 pub fn pointer<T, A, F: Fn(&mut T, PointerMsg), V: Element<T, A>>(
     child: V,
     callback: F,
@@ -155,3 +205,212 @@ impl<T, A, F: Fn(&mut T, PointerMsg) -> A, V: View<T, A>> View<T, A> for Pointer
         }
     }
 }
+
+/// The pointer event an [`OnPointer`] view listens to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PointerEventKind {
+    Down,
+    Move,
+    Up,
+    Cancel,
+}
+
+impl PointerEventKind {
+    fn event_name(self) -> &'static str {
+        match self {
+            PointerEventKind::Down => "pointerdown",
+            PointerEventKind::Move => "pointermove",
+            PointerEventKind::Up => "pointerup",
+            PointerEventKind::Cancel => "pointercancel",
+        }
+    }
+}
+
+/// Wraps an element and calls a handler with the [`PointerDetails`] of a pointer event,
+/// for mice, touches and pens alike.
+///
+/// See [`Element::on_pointer_down`], [`Element::on_pointer_move`],
+/// [`Element::on_pointer_up`] and [`Element::on_pointer_cancel`].
+pub struct OnPointer<E, T, A, C> {
+    target: E,
+    kind: PointerEventKind,
+    handler: C,
+    capture: bool,
+    options: EventListenerOptions,
+    phantom: PhantomData<fn() -> (T, A)>,
+}
+
+impl<E, T, A, C> OnPointer<E, T, A, C> {
+    pub(crate) fn new(target: E, kind: PointerEventKind, handler: C) -> Self {
+        OnPointer {
+            target,
+            kind,
+            handler,
+            capture: false,
+            options: EventListenerOptions::default(),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Capture the pointer when it's pressed on the element, with
+    /// [`setPointerCapture`](https://developer.mozilla.org/en-US/docs/Web/API/Element/setPointerCapture).
+    /// (default = `false`)
+    ///
+    /// The element then keeps receiving the move and up events of the pointer until it's
+    /// released, even when it leaves the element, which is what drags need. This only
+    /// has an effect on [`on_pointer_down`](Element::on_pointer_down).
+    ///
+    /// For touch drags, the element should also have the `touch-action: none` style, so
+    /// that the browser doesn't scroll the page and cancel the pointer instead.
+    pub fn capture(mut self, value: bool) -> Self {
+        self.capture = value;
+        self
+    }
+
+    /// Whether the event handler should be passive. (default = `true`)
+    ///
+    /// Passive event handlers can't prevent the browser's default action from
+    /// running (otherwise possible with `event.prevent_default()`), which
+    /// restricts what they can be used for, but reduces overhead.
+    pub fn passive(mut self, value: bool) -> Self {
+        self.options.passive = value;
+        self
+    }
+}
+
+fn create_pointer_listener(
+    target: &web_sys::EventTarget,
+    kind: PointerEventKind,
+    capture: bool,
+    options: EventListenerOptions,
+    cx: &Cx,
+) -> EventListener {
+    let thunk = cx.message_thunk();
+    let capture = capture && kind == PointerEventKind::Down;
+    EventListener::new_with_options(target, kind.event_name(), options, move |event| {
+        let event = event.dyn_ref::<PointerEvent>().unwrap_throw();
+        if capture {
+            if let Some(element) = event
+                .current_target()
+                .and_then(|target| target.dyn_into::<web_sys::Element>().ok())
+            {
+                // This fails if the pointer was already released, which can be ignored.
+                let _ = element.set_pointer_capture(event.pointer_id());
+            }
+        }
+        thunk.push_message(PointerDetails::from_pointer_event(event));
+    })
+}
+
+/// State for the [`OnPointer`] view.
+pub struct OnPointerState<S> {
+    #[allow(unused)]
+    listener: EventListener,
+    child_id: Id,
+    child_state: S,
+}
+
+impl<E, T, A, C> ViewMarker for OnPointer<E, T, A, C> {}
+impl<E, T, A, C> Sealed for OnPointer<E, T, A, C> {}
+
+impl<E, T, A, C, OA> View<T, A> for OnPointer<E, T, A, C>
+where
+    OA: OptionalAction<A>,
+    C: Fn(&mut T, PointerDetails) -> OA,
+    E: Element<T, A>,
+{
+    type State = OnPointerState<E::State>;
+
+    type Element = E::Element;
+
+    fn build(&self, cx: &mut Cx) -> (Id, Self::State, Self::Element) {
+        let (id, (element, state)) = cx.with_new_id(|cx| {
+            let (child_id, child_state, element) = self.target.build(cx);
+            let listener = create_pointer_listener(
+                element.as_node_ref(),
+                self.kind,
+                self.capture,
+                self.options,
+                cx,
+            );
+            let state = OnPointerState {
+                listener,
+                child_id,
+                child_state,
+            };
+            (element, state)
+        });
+        (id, state, element)
+    }
+
+    fn rebuild(
+        &self,
+        cx: &mut Cx,
+        prev: &Self,
+        id: &mut Id,
+        state: &mut Self::State,
+        element: &mut Self::Element,
+    ) -> ChangeFlags {
+        cx.with_id(*id, |cx| {
+            let prev_child_id = state.child_id;
+            let mut changed = self.target.rebuild(
+                cx,
+                &prev.target,
+                &mut state.child_id,
+                &mut state.child_state,
+                element,
+            );
+            if state.child_id != prev_child_id {
+                changed |= ChangeFlags::OTHER_CHANGE;
+            }
+            if changed.contains(ChangeFlags::STRUCTURE)
+                || prev.capture != self.capture
+                || prev.options != self.options
+            {
+                state.listener = create_pointer_listener(
+                    element.as_node_ref(),
+                    self.kind,
+                    self.capture,
+                    self.options,
+                    cx,
+                );
+                changed |= ChangeFlags::OTHER_CHANGE;
+            }
+            changed
+        })
+    }
+
+    fn message(
+        &self,
+        id_path: &[Id],
+        state: &mut Self::State,
+        message: Box<dyn Any>,
+        app_state: &mut T,
+    ) -> MessageResult<A> {
+        match id_path {
+            [] if message.downcast_ref::<PointerDetails>().is_some() => {
+                let details = message.downcast::<PointerDetails>().unwrap();
+                match (self.handler)(app_state, *details).action() {
+                    Some(a) => MessageResult::Action(a),
+                    None => MessageResult::Nop,
+                }
+            }
+            [element_id, rest_path @ ..] if *element_id == state.child_id => {
+                self.target
+                    .message(rest_path, &mut state.child_state, message, app_state)
+            }
+            _ => MessageResult::Stale(message),
+        }
+    }
+}
+
+crate::interfaces::impl_dom_interfaces_for_ty!(
+    Element,
+    OnPointer,
+    vars: <C, OA,>,
+    vars_on_ty: <C,>,
+    bounds: {
+        OA: OptionalAction<A>,
+        C: Fn(&mut T, PointerDetails) -> OA,
+    }
+);
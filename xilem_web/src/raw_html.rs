@@ -0,0 +1,220 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! View sequences inserting pre-rendered HTML into the tree.
+
+use std::{borrow::Cow, sync::Arc};
+
+use wasm_bindgen::{JsCast, UnwrapThrowExt};
+use xilem_core::{Id, MessageResult};
+
+use crate::{
+    sanitize::{sanitize_html, SanitizePolicy},
+    ChangeFlags, Cx, ElementsSplice, Pod, ViewSequence,
+};
+
+type CowStr = Cow<'static, str>;
+
+/// Inserts the nodes of `html` as is, without modelling them as views.
+///
+/// **This is an injection risk**: the markup can contain event handler attributes
+/// (e.g. `onclick`) or `javascript:` links, which run with the privileges of the page.
+/// Only use it for trusted content, e.g. HTML rendered from markdown at build time.
+/// Use [`sanitized_html`] for anything else.
+///
+/// `html` may have several top-level nodes, they are all inserted as elements of this
+/// view sequence, so it can be used as (a part of) the children of any element.
+/// The markup is parsed with a `<template>` element, so `<script>` elements are inert.
+///
+/// The nodes are only compared at the string level: when `html` changes, all of them
+/// are replaced with the nodes of the new markup.
+pub fn unsafe_raw_html(html: impl Into<CowStr>) -> RawHtml {
+    RawHtml {
+        html: html.into(),
+        policy: None,
+    }
+}
+
+/// Inserts the nodes of `html`, after removing the tags, attributes and URL schemes not
+/// allowed by `policy`.
+///
+/// This is meant for untrusted content, see [`SanitizePolicy`] for what is kept.
+/// Apart from this, it works like [`unsafe_raw_html`]. The markup is only sanitized
+/// again when it, or the policy, changes.
+pub fn sanitized_html(html: impl Into<CowStr>, policy: Arc<SanitizePolicy>) -> RawHtml {
+    RawHtml {
+        html: html.into(),
+        policy: Some(policy),
+    }
+}
+
+/// The view sequence created by [`unsafe_raw_html`] and [`sanitized_html`].
+pub struct RawHtml {
+    html: CowStr,
+    policy: Option<Arc<SanitizePolicy>>,
+}
+
+impl RawHtml {
+    fn same_policy(&self, other: &RawHtml) -> bool {
+        match (&self.policy, &other.policy) {
+            (Some(policy), Some(other)) => Arc::ptr_eq(policy, other) || policy == other,
+            (None, None) => true,
+            _ => false,
+        }
+    }
+
+    /// Parse the (sanitized) markup, and return its top-level nodes.
+    fn create_nodes(&self, cx: &Cx) -> Vec<web_sys::Node> {
+        let sanitized;
+        let html = match &self.policy {
+            Some(policy) => {
+                sanitized = sanitize_html(&self.html, policy);
+                &sanitized
+            }
+            None => &*self.html,
+        };
+        let template: web_sys::HtmlTemplateElement = cx
+            .document()
+            .create_element("template")
+            .unwrap_throw()
+            .unchecked_into();
+        template.set_inner_html(html);
+        let content = template.content();
+        let mut nodes = Vec::new();
+        // Moving a node out of the fragment removes it from `first_child`.
+        while let Some(node) = content.first_child() {
+            content.remove_child(&node).unwrap_throw();
+            nodes.push(node);
+        }
+        nodes
+    }
+}
+
+impl<T, A> ViewSequence<T, A> for RawHtml {
+    /// The number of top-level nodes.
+    type State = usize;
+
+    fn build(&self, cx: &mut Cx, elements: &mut dyn ElementsSplice) -> Self::State {
+        let nodes = self.create_nodes(cx);
+        let count = nodes.len();
+        for node in nodes {
+            elements.push(Pod::new(node), cx);
+        }
+        count
+    }
+
+    fn rebuild(
+        &self,
+        cx: &mut Cx,
+        prev: &Self,
+        state: &mut Self::State,
+        elements: &mut dyn ElementsSplice,
+    ) -> ChangeFlags {
+        if self.html == prev.html && self.same_policy(prev) {
+            for _ in 0..*state {
+                elements.mutate(cx);
+                elements.mark(ChangeFlags::empty(), cx);
+            }
+            return ChangeFlags::empty();
+        }
+
+        let nodes = self.create_nodes(cx);
+        let count = nodes.len();
+        let mut nodes = nodes.into_iter();
+        // Replace the existing nodes in place, then add or delete the remaining ones.
+        for node in nodes.by_ref().take(*state) {
+            *elements.mutate(cx) = Pod::new(node);
+            elements.mark(ChangeFlags::tree_structure(), cx);
+        }
+        if count < *state {
+            elements.delete(*state - count, cx);
+        }
+        for node in nodes {
+            elements.push(Pod::new(node), cx);
+        }
+        *state = count;
+        ChangeFlags::tree_structure()
+    }
+
+    fn message(
+        &self,
+        _id_path: &[Id],
+        _state: &mut Self::State,
+        message: Box<dyn std::any::Any>,
+        _app_state: &mut T,
+    ) -> MessageResult<A> {
+        MessageResult::Stale(message)
+    }
+
+    fn count(&self, state: &Self::State) -> usize {
+        *state
+    }
+}
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod tests {
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use super::*;
+    use crate::{elements::html::div, View};
+
+    wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+    fn children(element: &web_sys::Node) -> Vec<String> {
+        let nodes = element.child_nodes();
+        (0..nodes.length())
+            .map(|index| {
+                let node = nodes.get(index).unwrap();
+                match node.dyn_ref::<web_sys::Element>() {
+                    Some(element) => element.outer_html(),
+                    None => node.text_content().unwrap_or_default(),
+                }
+            })
+            .collect()
+    }
+
+    #[wasm_bindgen_test]
+    fn insert_replace_and_remove() {
+        let mut cx = Cx::new();
+        let view = |html: Option<&'static str>| {
+            div::<(), (), _>(("before", html.map(unsafe_raw_html), "after"))
+        };
+
+        let prev = view(Some("<b>1</b><i>2</i>"));
+        let (mut id, mut state, mut element) = View::build(&prev, &mut cx);
+        assert_eq!(
+            children(&element),
+            ["before", "<b>1</b>", "<i>2</i>", "after"]
+        );
+
+        // More nodes than before
+        let next = view(Some("<p>3</p> <p>4</p>"));
+        View::rebuild(&next, &mut cx, &prev, &mut id, &mut state, &mut element);
+        assert_eq!(
+            children(&element),
+            ["before", "<p>3</p>", " ", "<p>4</p>", "after"]
+        );
+
+        // Fewer nodes than before
+        let prev = next;
+        let next = view(Some("text"));
+        View::rebuild(&next, &mut cx, &prev, &mut id, &mut state, &mut element);
+        assert_eq!(children(&element), ["before", "text", "after"]);
+
+        // Torn down
+        let prev = next;
+        let next = view(None);
+        View::rebuild(&next, &mut cx, &prev, &mut id, &mut state, &mut element);
+        assert_eq!(children(&element), ["before", "after"]);
+    }
+
+    #[wasm_bindgen_test]
+    fn sanitized() {
+        let mut cx = Cx::new();
+        let policy = Arc::new(SanitizePolicy::default());
+        let html = r#"<a href="/" onclick="steal()">home</a><script>steal()</script>"#;
+        let view = div::<(), (), _>(sanitized_html(html, policy));
+        let (_, _, element) = View::build(&view, &mut cx);
+        assert_eq!(element.inner_html(), r#"<a href="/">home</a>"#);
+    }
+}
@@ -0,0 +1,506 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! An allowlist-based HTML sanitizer, used by [`sanitized_html`](crate::sanitized_html).
+//!
+//! This doesn't build a DOM: it tokenizes the markup, and writes out the allowed tags
+//! with their allowed attributes. Everything else is dropped, except the text inside
+//! dropped tags (unless they are raw text elements like `<script>`, whose content is
+//! dropped as well). The browser then parses the result as usual, closing unclosed tags.
+
+use std::{borrow::Cow, collections::HashSet};
+
+type CowStr = Cow<'static, str>;
+
+/// Elements whose content is not markup, and is dropped with the element.
+const RAW_TEXT_ELEMENTS: &[&str] = &[
+    "script", "style", "iframe", "noembed", "noframes", "noscript", "xmp", "textarea", "title",
+];
+
+/// Attributes whose value is a URL, and is checked against the allowed URL schemes.
+const URL_ATTRIBUTES: &[&str] = &[
+    "action",
+    "background",
+    "cite",
+    "formaction",
+    "href",
+    "longdesc",
+    "poster",
+    "src",
+    "xlink:href",
+];
+
+/// Which tags, attributes and URL schemes are kept by [`sanitize_html`].
+///
+/// Tag and attribute names are matched case-insensitively, and should be given in
+/// lowercase. URLs without a scheme (i.e. relative URLs) are always allowed.
+///
+/// The default policy allows common formatting tags (as produced by e.g. a markdown
+/// renderer), links and images, with `http`, `https` and `mailto` URLs:
+///
+/// ```
+/// use xilem_web::SanitizePolicy;
+///
+/// let policy = SanitizePolicy::default()
+///     .allow_tags(["details", "summary"])
+///     .allow_attributes(["open"]);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SanitizePolicy {
+    tags: HashSet<CowStr>,
+    attributes: HashSet<CowStr>,
+    url_schemes: HashSet<CowStr>,
+}
+
+impl SanitizePolicy {
+    /// A policy which doesn't allow anything, so only the text is kept.
+    pub fn empty() -> Self {
+        SanitizePolicy {
+            tags: HashSet::new(),
+            attributes: HashSet::new(),
+            url_schemes: HashSet::new(),
+        }
+    }
+
+    /// Allow these tags, in addition to the ones already allowed.
+    pub fn allow_tags(mut self, tags: impl IntoIterator<Item = impl Into<CowStr>>) -> Self {
+        self.tags.extend(tags.into_iter().map(Into::into));
+        self
+    }
+
+    /// Allow these attributes on all allowed tags, in addition to the ones already allowed.
+    pub fn allow_attributes(
+        mut self,
+        attributes: impl IntoIterator<Item = impl Into<CowStr>>,
+    ) -> Self {
+        self.attributes
+            .extend(attributes.into_iter().map(Into::into));
+        self
+    }
+
+    /// Allow URLs with these schemes (e.g. `"https"`), in addition to the ones already allowed.
+    pub fn allow_url_schemes(
+        mut self,
+        url_schemes: impl IntoIterator<Item = impl Into<CowStr>>,
+    ) -> Self {
+        self.url_schemes
+            .extend(url_schemes.into_iter().map(Into::into));
+        self
+    }
+
+    fn is_tag_allowed(&self, tag: &str) -> bool {
+        self.tags.contains(tag)
+    }
+
+    fn is_attribute_allowed(&self, name: &str, value: &str) -> bool {
+        if !self.attributes.contains(name) {
+            return false;
+        }
+        if !URL_ATTRIBUTES.contains(&name) {
+            return true;
+        }
+        match url_scheme(&decode_entities(value)) {
+            Some(scheme) => self.url_schemes.contains(scheme.as_str()),
+            None => true,
+        }
+    }
+}
+
+impl Default for SanitizePolicy {
+    fn default() -> Self {
+        SanitizePolicy::empty()
+            .allow_tags([
+                "a",
+                "abbr",
+                "b",
+                "blockquote",
+                "br",
+                "code",
+                "dd",
+                "del",
+                "div",
+                "dl",
+                "dt",
+                "em",
+                "h1",
+                "h2",
+                "h3",
+                "h4",
+                "h5",
+                "h6",
+                "hr",
+                "i",
+                "img",
+                "ins",
+                "kbd",
+                "li",
+                "ol",
+                "p",
+                "pre",
+                "s",
+                "span",
+                "strong",
+                "sub",
+                "sup",
+                "table",
+                "tbody",
+                "td",
+                "th",
+                "thead",
+                "tr",
+                "u",
+                "ul",
+            ])
+            .allow_attributes(["alt", "class", "height", "href", "src", "title", "width"])
+            .allow_url_schemes(["http", "https", "mailto"])
+    }
+}
+
+/// Remove the tags, attributes and URLs not allowed by `policy` from `html`.
+pub fn sanitize_html(html: &str, policy: &SanitizePolicy) -> String {
+    let mut output = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        output.push_str(&rest[..start]);
+        rest = &rest[start..];
+        rest = match parse_markup(rest) {
+            Some((Markup::StartTag(tag), after)) => {
+                if !policy.is_tag_allowed(&tag.name) {
+                    if RAW_TEXT_ELEMENTS.contains(&tag.name.as_str()) && !tag.self_closing {
+                        skip_raw_text(after, &tag.name)
+                    } else {
+                        after
+                    }
+                } else {
+                    write_start_tag(&mut output, &tag, policy);
+                    after
+                }
+            }
+            Some((Markup::EndTag(name), after)) => {
+                if policy.is_tag_allowed(&name) {
+                    output.push_str("</");
+                    output.push_str(&name);
+                    output.push('>');
+                }
+                after
+            }
+            Some((Markup::Ignored, after)) => after,
+            None => {
+                // Not markup, e.g. `a < b`.
+                output.push_str("&lt;");
+                &rest[1..]
+            }
+        };
+    }
+    output.push_str(rest);
+    output
+}
+
+struct StartTag<'a> {
+    name: String,
+    attributes: Vec<(String, &'a str)>,
+    self_closing: bool,
+}
+
+enum Markup<'a> {
+    StartTag(StartTag<'a>),
+    EndTag(String),
+    /// Comments, doctypes, processing instructions and CDATA sections.
+    Ignored,
+}
+
+/// Parse the markup at the start of `input` (which starts with `<`), returning it and
+/// what comes after it, or `None` if `input` doesn't start with markup.
+fn parse_markup(input: &str) -> Option<(Markup<'_>, &str)> {
+    let after_lt = &input[1..];
+    if let Some(comment) = after_lt.strip_prefix("!--") {
+        let after = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+        return Some((Markup::Ignored, after));
+    }
+    if after_lt.starts_with(['!', '?']) {
+        let after = after_lt.find('>').map_or("", |end| &after_lt[end + 1..]);
+        return Some((Markup::Ignored, after));
+    }
+    if let Some(end_tag) = after_lt.strip_prefix('/') {
+        if !end_tag.starts_with(|c: char| c.is_ascii_alphabetic()) {
+            return None;
+        }
+        let (name, rest) = split_name(end_tag);
+        // Attributes on end tags are meaningless, drop them.
+        let after = rest.find('>').map_or("", |end| &rest[end + 1..]);
+        return Some((Markup::EndTag(name), after));
+    }
+    if !after_lt.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return None;
+    }
+
+    let (name, mut rest) = split_name(after_lt);
+    let mut tag = StartTag {
+        name,
+        attributes: Vec::new(),
+        self_closing: false,
+    };
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_ascii_whitespace());
+        if let Some(after) = rest.strip_prefix('>') {
+            return Some((Markup::StartTag(tag), after));
+        }
+        if let Some(after) = rest.strip_prefix("/>") {
+            tag.self_closing = true;
+            return Some((Markup::StartTag(tag), after));
+        }
+        if let Some(after) = rest.strip_prefix('/') {
+            rest = after;
+            continue;
+        }
+        if rest.is_empty() {
+            // An unterminated tag is dropped, like browsers do.
+            return Some((Markup::Ignored, ""));
+        }
+
+        let name_end = rest
+            .find(|c: char| c.is_ascii_whitespace() || c == '/' || c == '>' || c == '=')
+            .unwrap_or(rest.len())
+            // An attribute name can start with `=`.
+            .max(1);
+        let name = rest[..name_end].to_ascii_lowercase();
+        rest = rest[name_end..].trim_start_matches(|c: char| c.is_ascii_whitespace());
+
+        let mut value = "";
+        if let Some(after_eq) = rest.strip_prefix('=') {
+            let after_eq = after_eq.trim_start_matches(|c: char| c.is_ascii_whitespace());
+            (value, rest) = split_attribute_value(after_eq);
+        }
+        tag.attributes.push((name, value));
+    }
+}
+
+/// Split a tag name (in lowercase) from what follows it.
+fn split_name(input: &str) -> (String, &str) {
+    let end = input
+        .find(|c: char| c.is_ascii_whitespace() || c == '/' || c == '>')
+        .unwrap_or(input.len());
+    (input[..end].to_ascii_lowercase(), &input[end..])
+}
+
+/// Split a (possibly quoted) attribute value, without its quotes, from what follows it.
+fn split_attribute_value(input: &str) -> (&str, &str) {
+    for quote in ['"', '\''] {
+        if let Some(quoted) = input.strip_prefix(quote) {
+            return match quoted.find(quote) {
+                Some(end) => (&quoted[..end], &quoted[end + 1..]),
+                None => (quoted, ""),
+            };
+        }
+    }
+    let end = input
+        .find(|c: char| c.is_ascii_whitespace() || c == '>')
+        .unwrap_or(input.len());
+    (&input[..end], &input[end..])
+}
+
+/// Skip the content of the raw text element `name`, and its end tag.
+fn skip_raw_text<'a>(input: &'a str, name: &str) -> &'a str {
+    let mut rest = input;
+    while let Some(start) = rest.find("</") {
+        let after = &rest[start + 2..];
+        let is_end_tag = after
+            .get(..name.len())
+            .is_some_and(|candidate| candidate.eq_ignore_ascii_case(name))
+            && after[name.len()..]
+                .starts_with(|c: char| c.is_ascii_whitespace() || c == '/' || c == '>');
+        if is_end_tag {
+            return after.find('>').map_or("", |end| &after[end + 1..]);
+        }
+        rest = after;
+    }
+    ""
+}
+
+fn write_start_tag(output: &mut String, tag: &StartTag, policy: &SanitizePolicy) {
+    output.push('<');
+    output.push_str(&tag.name);
+    let mut written = HashSet::new();
+    for (name, value) in &tag.attributes {
+        // Browsers only keep the first of duplicate attributes.
+        if !written.insert(name.as_str()) || !policy.is_attribute_allowed(name, value) {
+            continue;
+        }
+        output.push(' ');
+        output.push_str(name);
+        output.push_str("=\"");
+        // The value is written as is (so entities stay escaped) in double quotes,
+        // which must then be escaped in turn.
+        for c in value.chars() {
+            match c {
+                '"' => output.push_str("&quot;"),
+                '<' => output.push_str("&lt;"),
+                '>' => output.push_str("&gt;"),
+                c => output.push(c),
+            }
+        }
+        output.push('"');
+    }
+    if tag.self_closing {
+        output.push_str(" /");
+    }
+    output.push('>');
+}
+
+/// Decode the character references in an attribute value which can be used to
+/// obfuscate a URL scheme, e.g. `javascript&#58;`.
+fn decode_entities(value: &str) -> Cow<'_, str> {
+    if !value.contains('&') {
+        return Cow::Borrowed(value);
+    }
+    let mut decoded = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start + 1..];
+        match decode_entity(rest) {
+            Some((c, len)) => {
+                decoded.push(c);
+                rest = &rest[len..];
+            }
+            None => decoded.push('&'),
+        }
+    }
+    decoded.push_str(rest);
+    Cow::Owned(decoded)
+}
+
+/// Decode the character reference at the start of `input` (after the `&`), returning
+/// the character and the length of the reference.
+fn decode_entity(input: &str) -> Option<(char, usize)> {
+    if let Some(numeric) = input.strip_prefix('#') {
+        let (digits, radix, prefix_len) = match numeric.strip_prefix(['x', 'X']) {
+            Some(hex) => (hex, 16, 2),
+            None => (numeric, 10, 1),
+        };
+        let digits_len = digits
+            .find(|c: char| !c.is_digit(radix))
+            .unwrap_or(digits.len());
+        let code = u32::from_str_radix(&digits[..digits_len], radix).ok()?;
+        let semicolon_len = usize::from(digits[digits_len..].starts_with(';'));
+        // Invalid code points are decoded as U+FFFD by browsers.
+        let c = char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER);
+        return Some((c, prefix_len + digits_len + semicolon_len));
+    }
+    const NAMED: &[(&str, char)] = &[
+        ("amp;", '&'),
+        ("apos;", '\''),
+        ("colon;", ':'),
+        ("gt;", '>'),
+        ("lt;", '<'),
+        ("NewLine;", '\n'),
+        ("quot;", '"'),
+        ("Tab;", '\t'),
+    ];
+    NAMED
+        .iter()
+        .find(|(name, _)| input.starts_with(name))
+        .map(|(name, c)| (*c, name.len()))
+}
+
+/// The scheme of `url` in lowercase, or `None` if it's a relative URL.
+fn url_scheme(url: &str) -> Option<String> {
+    // Browsers ignore whitespace and control characters in schemes, e.g. `java\tscript:`.
+    let url: String = url
+        .chars()
+        .filter(|c| !c.is_ascii_whitespace() && !c.is_ascii_control())
+        .collect();
+    let end = url.find([':', '/', '?', '#'])?;
+    url[end..]
+        .starts_with(':')
+        .then(|| url[..end].to_ascii_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sanitize(html: &str) -> String {
+        sanitize_html(html, &SanitizePolicy::default())
+    }
+
+    #[test]
+    fn keeps_allowed_markup() {
+        let html = r#"<p class="intro">Hello <strong>world</strong><br/></p>"#;
+        assert_eq!(
+            sanitize(html),
+            r#"<p class="intro">Hello <strong>world</strong><br /></p>"#
+        );
+    }
+
+    #[test]
+    fn strips_script_with_its_content() {
+        assert_eq!(
+            sanitize("<p>a</p><script>alert('</p>')</script><p>b</p>"),
+            "<p>a</p><p>b</p>"
+        );
+        assert_eq!(sanitize("<SCRIPT src=x.js></SCRIPT >ok"), "ok");
+    }
+
+    #[test]
+    fn keeps_text_of_disallowed_tags() {
+        assert_eq!(sanitize("<blink>Look</blink> at <me>"), "Look at ");
+        assert_eq!(sanitize("1 < 2 <!-- comment -->"), "1 &lt; 2 ");
+    }
+
+    #[test]
+    fn strips_event_handlers() {
+        assert_eq!(
+            sanitize(r#"<a href="/docs" onclick="steal()" ONMOUSEOVER=steal()>Docs</a>"#),
+            r#"<a href="/docs">Docs</a>"#
+        );
+        let policy = SanitizePolicy::default().allow_attributes(["onclick"]);
+        assert_eq!(
+            sanitize_html(r#"<b onclick="go()">Go</b>"#, &policy),
+            r#"<b onclick="go()">Go</b>"#
+        );
+    }
+
+    #[test]
+    fn checks_url_schemes() {
+        assert_eq!(
+            sanitize(r#"<a href="https://linebender.org">ok</a>"#),
+            r#"<a href="https://linebender.org">ok</a>"#
+        );
+        assert_eq!(
+            sanitize(r#"<a href="javascript:alert(1)">x</a>"#),
+            "<a>x</a>"
+        );
+        assert_eq!(
+            sanitize(r#"<a href=" JaVa&#x09;Script&colon;alert(1)">x</a>"#),
+            "<a>x</a>"
+        );
+        assert_eq!(
+            sanitize(r#"<img src="data:image/png;base64,AAAA">"#),
+            "<img>"
+        );
+
+        let policy = SanitizePolicy::default().allow_url_schemes(["data"]);
+        assert_eq!(
+            sanitize_html(r#"<img src="data:image/png;base64,AAAA">"#, &policy),
+            r#"<img src="data:image/png;base64,AAAA">"#
+        );
+    }
+
+    #[test]
+    fn escapes_attribute_values() {
+        assert_eq!(
+            sanitize(r#"<span title='say "hi"'>hi</span>"#),
+            r#"<span title="say &quot;hi&quot;">hi</span>"#
+        );
+    }
+
+    #[test]
+    fn empty_policy_keeps_only_text() {
+        let policy = SanitizePolicy::empty();
+        assert_eq!(
+            sanitize_html("<h1>Title</h1><p>Some <em>text</em></p>", &policy),
+            "TitleSome text"
+        );
+    }
+}
@@ -0,0 +1,786 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Views reporting the readings of the device's sensors: its position and orientation.
+//!
+//! Like the [page events](crate::on_visibility_change), these views don't render anything,
+//! so they can be put anywhere in a view sequence. Sensors can report values much faster
+//! than the page is drawn, so at most one update is delivered per animation frame, which
+//! is the latest one.
+//!
+//! The browser APIs are accessed through the [`GeolocationProvider`] and
+//! [`OrientationProvider`] traits, which can be implemented to fake them, e.g. in tests.
+
+use std::{any::Any, cell::RefCell, fmt, marker::PhantomData, rc::Rc};
+
+use gloo::events::EventListener;
+use wasm_bindgen::{prelude::Closure, JsCast, UnwrapThrowExt};
+use wasm_bindgen_futures::JsFuture;
+use xilem_core::{Id, MessageResult};
+
+use crate::{context::MessageThunk, ChangeFlags, Cx, OptionalAction, View, ViewMarker};
+
+/// A position reported by [`watch_geolocation`].
+///
+/// Distances are in meters, and angles in degrees.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GeoPosition {
+    pub latitude: f64,
+    pub longitude: f64,
+    /// The accuracy of the latitude and longitude.
+    pub accuracy: f64,
+    /// The altitude above the WGS84 ellipsoid, if the device can measure it.
+    pub altitude: Option<f64>,
+    pub altitude_accuracy: Option<f64>,
+    /// The direction of travel, clockwise from true north, if the device is moving.
+    pub heading: Option<f64>,
+    /// The speed, in meters per second.
+    pub speed: Option<f64>,
+    /// When the position was acquired, in milliseconds since the UNIX epoch.
+    pub timestamp: f64,
+}
+
+impl GeoPosition {
+    fn from_position(position: &web_sys::Position) -> Self {
+        let coords = position.coords();
+        GeoPosition {
+            latitude: coords.latitude(),
+            longitude: coords.longitude(),
+            accuracy: coords.accuracy(),
+            altitude: coords.altitude(),
+            altitude_accuracy: coords.altitude_accuracy(),
+            heading: coords.heading().filter(|heading| !heading.is_nan()),
+            speed: coords.speed(),
+            timestamp: position.timestamp(),
+        }
+    }
+}
+
+/// Why [`watch_geolocation`] couldn't report a position.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GeoError {
+    /// The user (or the browser's policy) denied access to the position.
+    ///
+    /// No more updates are delivered after this.
+    PermissionDenied,
+    /// The position couldn't be determined, with the browser's explanation.
+    PositionUnavailable(String),
+    /// No position was acquired within the [timeout](GeoOptions::timeout).
+    Timeout,
+    /// The browser doesn't support the geolocation API, e.g. because the page isn't
+    /// served over HTTPS.
+    Unsupported,
+}
+
+impl GeoError {
+    /// Map a [`GeolocationPositionError`](https://developer.mozilla.org/en-US/docs/Web/API/GeolocationPositionError/code)
+    /// code to an error.
+    pub fn from_code(code: u16, message: String) -> Self {
+        match code {
+            1 => GeoError::PermissionDenied,
+            3 => GeoError::Timeout,
+            _ => GeoError::PositionUnavailable(message),
+        }
+    }
+}
+
+impl fmt::Display for GeoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GeoError::PermissionDenied => write!(f, "access to the position was denied"),
+            GeoError::PositionUnavailable(message) if message.is_empty() => {
+                write!(f, "the position is unavailable")
+            }
+            GeoError::PositionUnavailable(message) => {
+                write!(f, "the position is unavailable: {message}")
+            }
+            GeoError::Timeout => write!(f, "timed out while acquiring the position"),
+            GeoError::Unsupported => write!(f, "geolocation is not supported"),
+        }
+    }
+}
+
+impl std::error::Error for GeoError {}
+
+/// Options for [`watch_geolocation`], see
+/// [`PositionOptions`](https://developer.mozilla.org/en-US/docs/Web/API/Geolocation/watchPosition#options).
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct GeoOptions {
+    /// Whether to use the most accurate source (e.g. GPS), at the cost of battery life.
+    pub high_accuracy: bool,
+    /// How old (in milliseconds) a cached position may be, instead of acquiring a new one.
+    pub maximum_age: Option<u32>,
+    /// How long (in milliseconds) acquiring a position may take before reporting
+    /// [`GeoError::Timeout`].
+    pub timeout: Option<u32>,
+}
+
+impl GeoOptions {
+    fn to_position_options(self) -> web_sys::PositionOptions {
+        let mut options = web_sys::PositionOptions::new();
+        options.enable_high_accuracy(self.high_accuracy);
+        if let Some(maximum_age) = self.maximum_age {
+            options.maximum_age(maximum_age);
+        }
+        if let Some(timeout) = self.timeout {
+            options.timeout(timeout);
+        }
+        options
+    }
+}
+
+/// The orientation of the device reported by [`on_device_orientation`], in degrees.
+///
+/// The angles are `None` when the device has no orientation sensor.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Orientation {
+    /// The rotation around the z axis, i.e. the compass heading, between 0 and 360.
+    pub alpha: Option<f64>,
+    /// The front-to-back tilt, between -180 and 180.
+    pub beta: Option<f64>,
+    /// The left-to-right tilt, between -90 and 90.
+    pub gamma: Option<f64>,
+}
+
+/// The answer to [`request_orientation_permission`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrientationPermission {
+    /// Orientation events are delivered, either because the user allowed it or because
+    /// the browser doesn't ask.
+    Granted,
+    Denied,
+}
+
+/// Stops a sensor subscription when dropped.
+pub struct SensorGuard(Option<Box<dyn FnOnce()>>);
+
+impl SensorGuard {
+    /// Create a guard calling `stop` when it's dropped.
+    pub fn new(stop: impl FnOnce() + 'static) -> Self {
+        SensorGuard(Some(Box::new(stop)))
+    }
+}
+
+impl Drop for SensorGuard {
+    fn drop(&mut self) {
+        if let Some(stop) = self.0.take() {
+            stop();
+        }
+    }
+}
+
+/// The source of the positions of [`watch_geolocation`].
+pub trait GeolocationProvider {
+    /// Start calling `on_update` with each new position (or error), until the returned
+    /// guard is dropped.
+    fn watch_position(
+        &self,
+        options: &GeoOptions,
+        on_update: Box<dyn Fn(Result<GeoPosition, GeoError>)>,
+    ) -> SensorGuard;
+}
+
+/// The source of the readings of [`on_device_orientation`].
+pub trait OrientationProvider {
+    /// Start calling `on_update` with each new orientation, until the returned guard is
+    /// dropped.
+    fn listen(&self, on_update: Box<dyn Fn(Orientation)>) -> SensorGuard;
+
+    /// Ask the user for the permission to receive orientation events, if needed.
+    fn request_permission(&self, on_result: Box<dyn FnOnce(OrientationPermission)>);
+}
+
+/// The providers using the browser's APIs.
+struct BrowserSensors;
+
+impl GeolocationProvider for BrowserSensors {
+    fn watch_position(
+        &self,
+        options: &GeoOptions,
+        on_update: Box<dyn Fn(Result<GeoPosition, GeoError>)>,
+    ) -> SensorGuard {
+        let navigator = web_sys::window().unwrap_throw().navigator();
+        let Ok(geolocation) = navigator.geolocation() else {
+            on_update(Err(GeoError::Unsupported));
+            return SensorGuard::new(|| {});
+        };
+        let on_update: Rc<dyn Fn(_)> = Rc::from(on_update);
+        let on_position = {
+            let on_update = on_update.clone();
+            Closure::<dyn FnMut(web_sys::Position)>::new(move |position| {
+                on_update(Ok(GeoPosition::from_position(&position)));
+            })
+        };
+        let on_error = {
+            let on_update = on_update.clone();
+            Closure::<dyn FnMut(web_sys::PositionError)>::new(
+                move |error: web_sys::PositionError| {
+                    on_update(Err(GeoError::from_code(error.code(), error.message())));
+                },
+            )
+        };
+        let watch = geolocation.watch_position_with_error_callback_and_options(
+            on_position.as_ref().unchecked_ref(),
+            Some(on_error.as_ref().unchecked_ref()),
+            &options.to_position_options(),
+        );
+        match watch {
+            Ok(watch) => SensorGuard::new(move || {
+                geolocation.clear_watch(watch);
+                // The callbacks must outlive the watch.
+                drop((on_position, on_error));
+            }),
+            Err(_) => {
+                on_update(Err(GeoError::Unsupported));
+                SensorGuard::new(|| {})
+            }
+        }
+    }
+}
+
+impl OrientationProvider for BrowserSensors {
+    fn listen(&self, on_update: Box<dyn Fn(Orientation)>) -> SensorGuard {
+        let window = web_sys::window().unwrap_throw();
+        let listener = EventListener::new(&window, "deviceorientation", move |event| {
+            let event = event.unchecked_ref::<web_sys::DeviceOrientationEvent>();
+            on_update(Orientation {
+                alpha: event.alpha(),
+                beta: event.beta(),
+                gamma: event.gamma(),
+            });
+        });
+        SensorGuard::new(move || drop(listener))
+    }
+
+    fn request_permission(&self, on_result: Box<dyn FnOnce(OrientationPermission)>) {
+        // Only iOS asks for the permission, through a static method of the event class.
+        let class = js_sys::Reflect::get(&js_sys::global(), &"DeviceOrientationEvent".into())
+            .ok()
+            .filter(|class| !class.is_undefined());
+        let request = class.as_ref().and_then(|class| {
+            js_sys::Reflect::get(class, &"requestPermission".into())
+                .ok()?
+                .dyn_into::<js_sys::Function>()
+                .ok()
+        });
+        let (Some(class), Some(request)) = (class, request) else {
+            on_result(OrientationPermission::Granted);
+            return;
+        };
+        let promise = request.call0(&class);
+        wasm_bindgen_futures::spawn_local(async move {
+            let answer = match promise {
+                Ok(promise) => JsFuture::from(promise.unchecked_into::<js_sys::Promise>())
+                    .await
+                    .ok()
+                    .and_then(|answer| answer.as_string()),
+                Err(_) => None,
+            };
+            on_result(match answer.as_deref() {
+                Some("granted") => OrientationPermission::Granted,
+                _ => OrientationPermission::Denied,
+            });
+        });
+    }
+}
+
+/// Ask the user for the permission to receive the orientation of the device, which
+/// iOS requires before [`on_device_orientation`] gets any reading.
+///
+/// This must be called from the handler of a user gesture, e.g. a click. `on_result`
+/// is called with the answer once the user has given it, or right away in browsers which
+/// don't ask. It is called outside of the app's message handling, so it should e.g. push
+/// a message through a [thunk](Cx::message_thunk) to update the app state.
+pub fn request_orientation_permission(on_result: impl FnOnce(OrientationPermission) + 'static) {
+    BrowserSensors.request_permission(Box::new(on_result));
+}
+
+/// Delivers the latest value pushed to it as a message to a view, at most once per
+/// animation frame.
+struct FrameThrottle<M> {
+    thunk: MessageThunk,
+    latest: Option<M>,
+    frame_requested: bool,
+    stopped: bool,
+}
+
+impl<M: 'static> FrameThrottle<M> {
+    fn new(thunk: MessageThunk) -> Rc<RefCell<Self>> {
+        Rc::new(RefCell::new(FrameThrottle {
+            thunk,
+            latest: None,
+            frame_requested: false,
+            stopped: false,
+        }))
+    }
+
+    fn push(this: &Rc<RefCell<Self>>, value: M) {
+        let mut throttle = this.borrow_mut();
+        if throttle.stopped {
+            return;
+        }
+        throttle.latest = Some(value);
+        if throttle.frame_requested {
+            return;
+        }
+        throttle.frame_requested = true;
+
+        let this = this.clone();
+        let deliver = Closure::once_into_js(move || {
+            let (thunk, value) = {
+                let mut throttle = this.borrow_mut();
+                throttle.frame_requested = false;
+                (throttle.thunk.clone(), throttle.latest.take())
+            };
+            // The app may push other values while handling the message.
+            if let Some(value) = value {
+                thunk.push_message(value);
+            }
+        });
+        web_sys::window()
+            .unwrap_throw()
+            .request_animation_frame(deliver.unchecked_ref())
+            .unwrap_throw();
+    }
+}
+
+impl<M> FrameThrottle<M> {
+    /// Drop the pending value, and ignore the ones pushed from now on.
+    fn stop(&mut self) {
+        self.stopped = true;
+        self.latest = None;
+    }
+}
+
+/// A sensor subscription, delivering its updates to a view.
+struct Subscription<M> {
+    #[allow(unused)]
+    guard: SensorGuard,
+    throttle: Rc<RefCell<FrameThrottle<M>>>,
+}
+
+impl<M: 'static> Subscription<M> {
+    fn start(cx: &Cx, subscribe: impl FnOnce(Box<dyn Fn(M)>) -> SensorGuard) -> Self {
+        let throttle = FrameThrottle::new(cx.message_thunk());
+        let guard = {
+            let throttle = throttle.clone();
+            subscribe(Box::new(move |value| FrameThrottle::push(&throttle, value)))
+        };
+        Subscription { guard, throttle }
+    }
+}
+
+impl<M> Drop for Subscription<M> {
+    fn drop(&mut self) {
+        // An update may already be waiting for the next frame.
+        self.throttle.borrow_mut().stop();
+    }
+}
+
+struct GeolocationUpdate(Result<GeoPosition, GeoError>);
+
+struct OrientationUpdate(Orientation);
+
+/// Calls `on_update` with the position of the device whenever it changes, using
+/// [`navigator.geolocation.watchPosition`](https://developer.mozilla.org/en-US/docs/Web/API/Geolocation/watchPosition).
+///
+/// The browser asks the user for the permission when the view is built; if it's denied,
+/// `on_update` gets [`GeoError::PermissionDenied`]. The watch is cleared when the view
+/// is torn down, and restarted when `options` change.
+///
+/// ```ignore
+/// watch_geolocation(GeoOptions::default(), |state: &mut AppState, update| {
+///     match update {
+///         Ok(position) => state.position = Some(position),
+///         Err(error) => state.error = Some(error),
+///     }
+/// })
+/// ```
+pub fn watch_geolocation<T, A, F, OA>(
+    options: GeoOptions,
+    on_update: F,
+) -> WatchGeolocation<T, A, F>
+where
+    F: Fn(&mut T, Result<GeoPosition, GeoError>) -> OA,
+    OA: OptionalAction<A>,
+{
+    WatchGeolocation {
+        options,
+        on_update,
+        provider: Rc::new(BrowserSensors),
+        phantom: PhantomData,
+    }
+}
+
+/// Calls `handler` with the orientation of the device whenever it changes, from the
+/// [`deviceorientation`](https://developer.mozilla.org/en-US/docs/Web/API/Window/deviceorientation_event)
+/// event.
+///
+/// On iOS, no event is fired until [`request_orientation_permission`] is granted.
+pub fn on_device_orientation<T, A, F, OA>(handler: F) -> OnDeviceOrientation<T, A, F>
+where
+    F: Fn(&mut T, Orientation) -> OA,
+    OA: OptionalAction<A>,
+{
+    OnDeviceOrientation {
+        handler,
+        provider: Rc::new(BrowserSensors),
+        phantom: PhantomData,
+    }
+}
+
+pub struct WatchGeolocation<T, A, F> {
+    options: GeoOptions,
+    on_update: F,
+    provider: Rc<dyn GeolocationProvider>,
+    phantom: PhantomData<fn() -> (T, A)>,
+}
+
+impl<T, A, F> WatchGeolocation<T, A, F> {
+    /// Use `provider` instead of the browser's geolocation API.
+    ///
+    /// The provider is only used when the view is built, or when the options change.
+    pub fn provider(mut self, provider: impl GeolocationProvider + 'static) -> Self {
+        self.provider = Rc::new(provider);
+        self
+    }
+}
+
+pub struct OnDeviceOrientation<T, A, F> {
+    handler: F,
+    provider: Rc<dyn OrientationProvider>,
+    phantom: PhantomData<fn() -> (T, A)>,
+}
+
+impl<T, A, F> OnDeviceOrientation<T, A, F> {
+    /// Use `provider` instead of the browser's `deviceorientation` event.
+    ///
+    /// The provider is only used when the view is built.
+    pub fn provider(mut self, provider: impl OrientationProvider + 'static) -> Self {
+        self.provider = Rc::new(provider);
+        self
+    }
+}
+
+/// State for the [`WatchGeolocation`] view.
+///
+/// The watch is cleared when this is dropped, i.e. when the view is torn down.
+pub struct WatchGeolocationState {
+    #[allow(unused)]
+    subscription: Subscription<GeolocationUpdate>,
+}
+
+/// State for the [`OnDeviceOrientation`] view.
+///
+/// The listener is removed when this is dropped, i.e. when the view is torn down.
+pub struct OnDeviceOrientationState {
+    #[allow(unused)]
+    subscription: Subscription<OrientationUpdate>,
+}
+
+fn watch(
+    cx: &Cx,
+    provider: &dyn GeolocationProvider,
+    options: &GeoOptions,
+) -> Subscription<GeolocationUpdate> {
+    Subscription::start(cx, |push| {
+        provider.watch_position(
+            options,
+            Box::new(move |update| push(GeolocationUpdate(update))),
+        )
+    })
+}
+
+impl<T, A, F> ViewMarker for WatchGeolocation<T, A, F> {}
+
+impl<T, A, F, OA> View<T, A> for WatchGeolocation<T, A, F>
+where
+    F: Fn(&mut T, Result<GeoPosition, GeoError>) -> OA,
+    OA: OptionalAction<A>,
+{
+    type State = WatchGeolocationState;
+
+    type Element = web_sys::Text;
+
+    fn build(&self, cx: &mut Cx) -> (Id, Self::State, Self::Element) {
+        let (id, subscription) = cx.with_new_id(|cx| watch(cx, &*self.provider, &self.options));
+        let state = WatchGeolocationState { subscription };
+        (id, state, cx.document().create_text_node(""))
+    }
+
+    fn rebuild(
+        &self,
+        cx: &mut Cx,
+        prev: &Self,
+        id: &mut Id,
+        state: &mut Self::State,
+        _element: &mut Self::Element,
+    ) -> ChangeFlags {
+        if self.options != prev.options {
+            state.subscription = cx.with_id(*id, |cx| watch(cx, &*self.provider, &self.options));
+        }
+        ChangeFlags::empty()
+    }
+
+    fn message(
+        &self,
+        id_path: &[Id],
+        _state: &mut Self::State,
+        message: Box<dyn Any>,
+        app_state: &mut T,
+    ) -> MessageResult<A> {
+        match id_path {
+            [] if message.downcast_ref::<GeolocationUpdate>().is_some() => {
+                let GeolocationUpdate(update) = *message.downcast::<GeolocationUpdate>().unwrap();
+                match (self.on_update)(app_state, update).action() {
+                    Some(a) => MessageResult::Action(a),
+                    None => MessageResult::Nop,
+                }
+            }
+            _ => MessageResult::Stale(message),
+        }
+    }
+}
+
+impl<T, A, F> ViewMarker for OnDeviceOrientation<T, A, F> {}
+
+impl<T, A, F, OA> View<T, A> for OnDeviceOrientation<T, A, F>
+where
+    F: Fn(&mut T, Orientation) -> OA,
+    OA: OptionalAction<A>,
+{
+    type State = OnDeviceOrientationState;
+
+    type Element = web_sys::Text;
+
+    fn build(&self, cx: &mut Cx) -> (Id, Self::State, Self::Element) {
+        let (id, subscription) = cx.with_new_id(|cx| {
+            Subscription::start(cx, |push| {
+                self.provider.listen(Box::new(move |orientation| {
+                    push(OrientationUpdate(orientation));
+                }))
+            })
+        });
+        let state = OnDeviceOrientationState { subscription };
+        (id, state, cx.document().create_text_node(""))
+    }
+
+    fn rebuild(
+        &self,
+        _cx: &mut Cx,
+        _prev: &Self,
+        _id: &mut Id,
+        _state: &mut Self::State,
+        _element: &mut Self::Element,
+    ) -> ChangeFlags {
+        ChangeFlags::empty()
+    }
+
+    fn message(
+        &self,
+        id_path: &[Id],
+        _state: &mut Self::State,
+        message: Box<dyn Any>,
+        app_state: &mut T,
+    ) -> MessageResult<A> {
+        match id_path {
+            [] if message.downcast_ref::<OrientationUpdate>().is_some() => {
+                let OrientationUpdate(orientation) =
+                    *message.downcast::<OrientationUpdate>().unwrap();
+                match (self.handler)(app_state, orientation).action() {
+                    Some(a) => MessageResult::Action(a),
+                    None => MessageResult::Nop,
+                }
+            }
+            _ => MessageResult::Stale(message),
+        }
+    }
+}
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod tests {
+    use std::cell::Cell;
+
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use super::*;
+    use crate::test_util::Recorder;
+
+    wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+    async fn next_frame() {
+        let promise = js_sys::Promise::new(&mut |resolve, _| {
+            web_sys::window()
+                .unwrap()
+                .request_animation_frame(&resolve)
+                .unwrap();
+        });
+        JsFuture::from(promise).await.unwrap();
+    }
+
+    type UpdateCallback<M> = Box<dyn Fn(M)>;
+
+    /// A sensor whose readings are pushed by the test.
+    struct MockSensor<M> {
+        on_update: Rc<RefCell<Option<UpdateCallback<M>>>>,
+        watching: Rc<Cell<bool>>,
+    }
+
+    // Not derived, as that would require `M: Default + Clone`.
+    impl<M> Default for MockSensor<M> {
+        fn default() -> Self {
+            MockSensor {
+                on_update: Rc::default(),
+                watching: Rc::default(),
+            }
+        }
+    }
+
+    impl<M> Clone for MockSensor<M> {
+        fn clone(&self) -> Self {
+            MockSensor {
+                on_update: self.on_update.clone(),
+                watching: self.watching.clone(),
+            }
+        }
+    }
+
+    impl<M: 'static> MockSensor<M> {
+        fn start(&self, on_update: Box<dyn Fn(M)>) -> SensorGuard {
+            *self.on_update.borrow_mut() = Some(on_update);
+            self.watching.set(true);
+            let watching = self.watching.clone();
+            SensorGuard::new(move || watching.set(false))
+        }
+
+        fn push(&self, value: M) {
+            if let Some(on_update) = &*self.on_update.borrow() {
+                on_update(value);
+            }
+        }
+    }
+
+    impl GeolocationProvider for MockSensor<Result<GeoPosition, GeoError>> {
+        fn watch_position(
+            &self,
+            _options: &GeoOptions,
+            on_update: Box<dyn Fn(Result<GeoPosition, GeoError>)>,
+        ) -> SensorGuard {
+            self.start(on_update)
+        }
+    }
+
+    impl OrientationProvider for MockSensor<Orientation> {
+        fn listen(&self, on_update: Box<dyn Fn(Orientation)>) -> SensorGuard {
+            self.start(on_update)
+        }
+
+        fn request_permission(&self, on_result: Box<dyn FnOnce(OrientationPermission)>) {
+            on_result(OrientationPermission::Granted);
+        }
+    }
+
+    fn position(latitude: f64) -> GeoPosition {
+        GeoPosition {
+            latitude,
+            longitude: 2.35,
+            accuracy: 10.0,
+            altitude: None,
+            altitude_accuracy: None,
+            heading: None,
+            speed: None,
+            timestamp: 0.0,
+        }
+    }
+
+    #[wasm_bindgen_test]
+    async fn geolocation_updates_are_delivered_once_per_frame() {
+        let recorder = Recorder::default();
+        let mut cx = recorder.cx();
+        let sensor = MockSensor::default();
+        let view = watch_geolocation::<_, (), _, _>(
+            GeoOptions::default(),
+            |updates: &mut Vec<_>, update| {
+                updates.push(update);
+            },
+        )
+        .provider(sensor.clone());
+        let (_, mut state, _) = view.build(&mut cx);
+        assert!(sensor.watching.get());
+
+        sensor.push(Ok(position(48.0)));
+        sensor.push(Ok(position(48.5)));
+        next_frame().await;
+        let mut updates = vec![];
+        recorder.deliver(&view, &mut state, &mut updates);
+        assert_eq!(updates, [Ok(position(48.5))]);
+
+        sensor.push(Err(GeoError::from_code(
+            1,
+            "User denied Geolocation".into(),
+        )));
+        next_frame().await;
+        recorder.deliver(&view, &mut state, &mut updates);
+        assert_eq!(updates[1], Err(GeoError::PermissionDenied));
+    }
+
+    #[wasm_bindgen_test]
+    fn geolocation_error_codes() {
+        assert_eq!(
+            GeoError::from_code(1, String::new()),
+            GeoError::PermissionDenied
+        );
+        assert_eq!(
+            GeoError::from_code(2, "no fix".into()),
+            GeoError::PositionUnavailable("no fix".into())
+        );
+        assert_eq!(GeoError::from_code(3, String::new()), GeoError::Timeout);
+    }
+
+    #[wasm_bindgen_test]
+    async fn geolocation_watch_is_cleared_on_teardown() {
+        let recorder = Recorder::default();
+        let mut cx = recorder.cx();
+        let sensor = MockSensor::default();
+        let view = watch_geolocation::<_, (), _, _>(GeoOptions::default(), |_: &mut (), _| {})
+            .provider(sensor.clone());
+        let (_, state, _) = view.build(&mut cx);
+
+        // An update waiting for the next frame is dropped with the view.
+        sensor.push(Ok(position(48.0)));
+        drop(state);
+        assert!(!sensor.watching.get());
+        next_frame().await;
+        assert!(recorder.0.borrow().is_empty());
+    }
+
+    #[wasm_bindgen_test]
+    async fn orientation_updates_are_delivered() {
+        let recorder = Recorder::default();
+        let mut cx = recorder.cx();
+        let sensor = MockSensor::default();
+        let view = on_device_orientation::<_, (), _, _>(
+            |headings: &mut Vec<_>, Orientation { alpha, .. }| {
+                headings.push(alpha);
+            },
+        )
+        .provider(sensor.clone());
+        let (_, mut state, _) = view.build(&mut cx);
+
+        let orientation = |alpha| Orientation {
+            alpha: Some(alpha),
+            beta: Some(0.0),
+            gamma: Some(0.0),
+        };
+        sensor.push(orientation(90.0));
+        next_frame().await;
+        sensor.push(orientation(180.0));
+        next_frame().await;
+        let mut headings = vec![];
+        recorder.deliver(&view, &mut state, &mut headings);
+        assert_eq!(headings, [Some(90.0), Some(180.0)]);
+
+        drop(state);
+        assert!(!sensor.watching.get());
+    }
+}
@@ -0,0 +1,524 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Keyboard shortcuts handled at the document level, whichever element has focus.
+//!
+//! Like the [page events](crate::on_visibility_change), the [`shortcuts`] view doesn't
+//! render anything, so it can be put anywhere in a view sequence. All the views share a
+//! single `keydown` listener on the document.
+
+use std::{any::Any, cell::RefCell, fmt};
+
+use gloo::events::{EventListener, EventListenerOptions};
+use wasm_bindgen::{JsCast, UnwrapThrowExt};
+use xilem_core::{Id, MessageResult};
+
+use crate::{context::MessageThunk, ChangeFlags, Cx, OptionalAction, View, ViewMarker};
+
+/// The shortcut at this index in the [`Shortcuts`] view was pressed.
+struct ShortcutPressed(usize);
+
+/// A key combination, e.g. `Ctrl+Enter`, parsed from a string like `"mod+enter"`.
+///
+/// The string is made of modifiers and a key, separated by `+`. The modifiers are `ctrl`
+/// (or `control`), `alt` (or `option`), `shift`, `meta` (or `cmd`, `command`), and `mod`,
+/// which is `meta` on Apple platforms and `ctrl` elsewhere. The key is the
+/// [`key`](https://developer.mozilla.org/en-US/docs/Web/API/KeyboardEvent/key) of the
+/// event, case-insensitively, e.g. `enter`, `arrowup` or `/`. A few common aliases are
+/// understood: `esc`, `space`, `plus`, `up`, `down`, `left`, `right` and `del`.
+///
+/// For keys producing a single character, `shift` is only compared if it's part of the
+/// shortcut, since the character already depends on it: `"?"` matches the `?` key
+/// whether or not the keyboard layout needs `Shift` to type it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyCombo {
+    key: String,
+    ctrl: bool,
+    alt: bool,
+    shift: bool,
+    meta: bool,
+}
+
+/// The error returned when a shortcut string can't be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidShortcut(pub String);
+
+impl fmt::Display for InvalidShortcut {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid shortcut \"{}\"", self.0)
+    }
+}
+
+impl std::error::Error for InvalidShortcut {}
+
+impl KeyCombo {
+    /// Parse `shortcut`, with `mod` meaning `meta` if `apple` is true and `ctrl` otherwise.
+    pub fn parse(shortcut: &str, apple: bool) -> Result<Self, InvalidShortcut> {
+        let invalid = || InvalidShortcut(shortcut.to_string());
+        let lowercase = shortcut.trim().to_lowercase();
+        // A trailing `+` is the `+` key itself, e.g. in `ctrl++`.
+        let (modifiers, key) = match lowercase.strip_suffix("++") {
+            Some(modifiers) => (Some(modifiers), "+"),
+            None if lowercase == "+" => (None, "+"),
+            None => match lowercase.rsplit_once('+') {
+                Some((modifiers, key)) => (Some(modifiers), key),
+                None => (None, lowercase.as_str()),
+            },
+        };
+        if key.is_empty() {
+            return Err(invalid());
+        }
+
+        let mut combo = KeyCombo {
+            key: normalize_key(key).to_string(),
+            ctrl: false,
+            alt: false,
+            shift: false,
+            meta: false,
+        };
+        for modifier in modifiers.into_iter().flat_map(|m| m.split('+')) {
+            let flag = match modifier {
+                "ctrl" | "control" => &mut combo.ctrl,
+                "alt" | "option" => &mut combo.alt,
+                "shift" => &mut combo.shift,
+                "meta" | "cmd" | "command" => &mut combo.meta,
+                "mod" if apple => &mut combo.meta,
+                "mod" => &mut combo.ctrl,
+                _ => return Err(invalid()),
+            };
+            if *flag {
+                return Err(invalid());
+            }
+            *flag = true;
+        }
+        Ok(combo)
+    }
+
+    /// Whether `event` is this key combination.
+    pub fn matches(&self, event: &web_sys::KeyboardEvent) -> bool {
+        let key = event.key().to_lowercase();
+        let ignore_shift = !self.shift && key.chars().count() == 1;
+        normalize_key(&key) == self.key
+            && event.ctrl_key() == self.ctrl
+            && event.alt_key() == self.alt
+            && event.meta_key() == self.meta
+            && (ignore_shift || event.shift_key() == self.shift)
+    }
+}
+
+impl fmt::Display for KeyCombo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (set, name) in [
+            (self.ctrl, "ctrl+"),
+            (self.alt, "alt+"),
+            (self.shift, "shift+"),
+            (self.meta, "meta+"),
+        ] {
+            if set {
+                f.write_str(name)?;
+            }
+        }
+        f.write_str(&self.key)
+    }
+}
+
+fn normalize_key(key: &str) -> &str {
+    match key {
+        "esc" => "escape",
+        "space" | "spacebar" => " ",
+        "plus" => "+",
+        "up" => "arrowup",
+        "down" => "arrowdown",
+        "left" => "arrowleft",
+        "right" => "arrowright",
+        "del" => "delete",
+        key => key,
+    }
+}
+
+/// Whether the browser runs on macOS or iOS, where `mod` is `Cmd` rather than `Ctrl`.
+fn is_apple_platform() -> bool {
+    let navigator = web_sys::window().unwrap_throw().navigator();
+    // `navigator.platform` is deprecated, but its replacement isn't widely available yet.
+    let platform = navigator.platform().unwrap_or_default();
+    ["Mac", "iPhone", "iPad", "iPod"]
+        .iter()
+        .any(|name| platform.starts_with(name))
+}
+
+type ShortcutHandler<T, A> = Box<dyn Fn(&mut T) -> Option<A>>;
+
+/// A keyboard shortcut of a [`shortcuts`] view, see [`shortcut`].
+pub struct Shortcut<T, A> {
+    combo: KeyCombo,
+    even_in_inputs: bool,
+    handler: ShortcutHandler<T, A>,
+}
+
+/// Calls `handler` when the keys of `combo` are pressed, e.g. `shortcut("mod+enter", submit)`.
+///
+/// See [`KeyCombo`] for the syntax of `combo`. The shortcut is ignored while an editable
+/// element (e.g. an `<input>`) has focus, unless [`even_in_inputs`](Shortcut::even_in_inputs)
+/// is set.
+///
+/// # Panics
+///
+/// If `combo` can't be parsed.
+#[track_caller]
+pub fn shortcut<T, A, F, OA>(combo: &str, handler: F) -> Shortcut<T, A>
+where
+    F: Fn(&mut T) -> OA + 'static,
+    OA: OptionalAction<A>,
+{
+    let combo = match KeyCombo::parse(combo, is_apple_platform()) {
+        Ok(combo) => combo,
+        Err(error) => panic!("{error}"),
+    };
+    Shortcut {
+        combo,
+        even_in_inputs: false,
+        handler: Box::new(move |state| handler(state).action()),
+    }
+}
+
+impl<T, A> Shortcut<T, A> {
+    /// Also handle the shortcut while an editable element has focus, e.g. for
+    /// `mod+enter` submitting a form from its text area.
+    pub fn even_in_inputs(mut self) -> Self {
+        self.even_in_inputs = true;
+        self
+    }
+
+    pub fn combo(&self) -> &KeyCombo {
+        &self.combo
+    }
+}
+
+/// Handles the keyboard `shortcuts` at the document level, whichever element has focus.
+///
+/// ```ignore
+/// (
+///     shortcuts([
+///         shortcut("/", |state: &mut AppState| state.focus_search = true),
+///         shortcut("mod+enter", |state: &mut AppState| state.submit()).even_in_inputs(),
+///     ]),
+///     // ... the rest of the app
+/// )
+/// ```
+///
+/// When a shortcut is handled, the default action of the key is prevented, and the event
+/// still reaches the other listeners. The shortcuts are unregistered when the view is
+/// torn down. If several views register the same key combination, a warning is logged,
+/// and the most recently built one handles it.
+pub fn shortcuts<T, A>(shortcuts: impl IntoIterator<Item = Shortcut<T, A>>) -> Shortcuts<T, A> {
+    Shortcuts {
+        shortcuts: shortcuts.into_iter().collect(),
+    }
+}
+
+pub struct Shortcuts<T, A> {
+    shortcuts: Vec<Shortcut<T, A>>,
+}
+
+impl<T, A> Shortcuts<T, A> {
+    fn entries(&self) -> Vec<Entry> {
+        self.shortcuts
+            .iter()
+            .map(|shortcut| Entry {
+                combo: shortcut.combo.clone(),
+                even_in_inputs: shortcut.even_in_inputs,
+            })
+            .collect()
+    }
+}
+
+#[derive(Clone, PartialEq)]
+struct Entry {
+    combo: KeyCombo,
+    even_in_inputs: bool,
+}
+
+/// The shortcuts registered by one [`Shortcuts`] view.
+struct Registration {
+    id: Id,
+    thunk: MessageThunk,
+    entries: Vec<Entry>,
+}
+
+/// The `keydown` listener shared by all the [`Shortcuts`] views.
+#[derive(Default)]
+struct ShortcutRegistry {
+    /// In the order the views were built.
+    registrations: Vec<Registration>,
+    listener: Option<EventListener>,
+}
+
+thread_local! {
+    static SHORTCUTS: RefCell<ShortcutRegistry> = RefCell::default();
+}
+
+impl ShortcutRegistry {
+    fn register(&mut self, id: Id, thunk: MessageThunk, entries: Vec<Entry>) {
+        self.warn_about_duplicates(id, &entries);
+        self.registrations.push(Registration { id, thunk, entries });
+        if self.listener.is_none() {
+            self.listener = Some(EventListener::new_with_options(
+                &crate::document(),
+                "keydown",
+                EventListenerOptions::enable_prevent_default(),
+                |event| on_keydown(event.unchecked_ref()),
+            ));
+        }
+    }
+
+    /// Replace the shortcuts of the view `id`, keeping its place in the build order.
+    fn update(&mut self, id: Id, entries: Vec<Entry>) {
+        self.warn_about_duplicates(id, &entries);
+        if let Some(registration) = self.registrations.iter_mut().find(|r| r.id == id) {
+            registration.entries = entries;
+        }
+    }
+
+    fn unregister(&mut self, id: Id) {
+        self.registrations
+            .retain(|registration| registration.id != id);
+        if self.registrations.is_empty() {
+            self.listener = None;
+        }
+    }
+
+    fn warn_about_duplicates(&self, id: Id, entries: &[Entry]) {
+        for entry in entries {
+            let is_duplicate = self
+                .registrations
+                .iter()
+                .filter(|registration| registration.id != id)
+                .flat_map(|registration| &registration.entries)
+                .any(|other| other.combo == entry.combo);
+            if is_duplicate {
+                log::warn!(
+                    "The shortcut {} is registered several times, only the last one is used",
+                    entry.combo
+                );
+            }
+        }
+    }
+
+    /// The view handling `event`, and the index of its matching shortcut.
+    fn find(&self, event: &web_sys::KeyboardEvent) -> Option<(MessageThunk, usize)> {
+        let in_input = event.target().is_some_and(|target| is_editable(&target));
+        self.registrations.iter().rev().find_map(|registration| {
+            let index = registration.entries.iter().position(|entry| {
+                (entry.even_in_inputs || !in_input) && entry.combo.matches(event)
+            })?;
+            Some((registration.thunk.clone(), index))
+        })
+    }
+}
+
+fn on_keydown(event: &web_sys::KeyboardEvent) {
+    // The registry isn't borrowed while the message is handled, since the app may
+    // register or unregister shortcuts when it rebuilds.
+    if let Some((thunk, index)) = SHORTCUTS.with_borrow(|registry| registry.find(event)) {
+        event.prevent_default();
+        thunk.push_message(ShortcutPressed(index));
+    }
+}
+
+fn is_editable(target: &web_sys::EventTarget) -> bool {
+    let Some(element) = target.dyn_ref::<web_sys::HtmlElement>() else {
+        return false;
+    };
+    matches!(element.tag_name().as_str(), "INPUT" | "TEXTAREA" | "SELECT")
+        || element.is_content_editable()
+}
+
+/// Whether a [`shortcuts`] view is registered, i.e. whether the `keydown` listener is
+/// on the document.
+pub fn has_registered_shortcuts() -> bool {
+    SHORTCUTS.with_borrow(|registry| registry.listener.is_some())
+}
+
+/// State for the [`Shortcuts`] view.
+///
+/// The shortcuts are unregistered when this is dropped, i.e. when the view is torn down.
+pub struct ShortcutsState {
+    id: Id,
+}
+
+impl Drop for ShortcutsState {
+    fn drop(&mut self) {
+        SHORTCUTS.with_borrow_mut(|registry| registry.unregister(self.id));
+    }
+}
+
+impl<T, A> ViewMarker for Shortcuts<T, A> {}
+
+impl<T, A> View<T, A> for Shortcuts<T, A> {
+    type State = ShortcutsState;
+
+    type Element = web_sys::Text;
+
+    fn build(&self, cx: &mut Cx) -> (Id, Self::State, Self::Element) {
+        let (id, thunk) = cx.with_new_id(|cx| cx.message_thunk());
+        SHORTCUTS.with_borrow_mut(|registry| registry.register(id, thunk, self.entries()));
+        (
+            id,
+            ShortcutsState { id },
+            cx.document().create_text_node(""),
+        )
+    }
+
+    fn rebuild(
+        &self,
+        _cx: &mut Cx,
+        prev: &Self,
+        _id: &mut Id,
+        state: &mut Self::State,
+        _element: &mut Self::Element,
+    ) -> ChangeFlags {
+        let entries = self.entries();
+        if entries != prev.entries() {
+            SHORTCUTS.with_borrow_mut(|registry| registry.update(state.id, entries));
+        }
+        ChangeFlags::empty()
+    }
+
+    fn message(
+        &self,
+        id_path: &[Id],
+        _state: &mut Self::State,
+        message: Box<dyn Any>,
+        app_state: &mut T,
+    ) -> MessageResult<A> {
+        match id_path {
+            [] => {
+                let shortcut = message
+                    .downcast_ref::<ShortcutPressed>()
+                    .and_then(|ShortcutPressed(index)| self.shortcuts.get(*index));
+                match shortcut.map(|shortcut| (shortcut.handler)(app_state)) {
+                    Some(Some(a)) => MessageResult::Action(a),
+                    Some(None) => MessageResult::Nop,
+                    // The shortcuts changed since the key was pressed.
+                    None => MessageResult::Stale(message),
+                }
+            }
+            _ => MessageResult::Stale(message),
+        }
+    }
+}
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod tests {
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use super::*;
+    use crate::test_util::Recorder;
+
+    wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+    /// Dispatch a `keydown` event for `key` on `target`, returning whether its default
+    /// action was prevented.
+    fn press(target: &web_sys::EventTarget, key: &str, ctrl: bool, meta: bool) -> bool {
+        let mut init = web_sys::KeyboardEventInit::new();
+        init.key(key)
+            .ctrl_key(ctrl)
+            .meta_key(meta)
+            .bubbles(true)
+            .cancelable(true);
+        let event =
+            web_sys::KeyboardEvent::new_with_keyboard_event_init_dict("keydown", &init).unwrap();
+        target.dispatch_event(&event).unwrap();
+        event.default_prevented()
+    }
+
+    fn pressed(list: &[&'static str]) -> Vec<Shortcut<Vec<&'static str>, ()>> {
+        list.iter()
+            .map(|&name| shortcut(name, move |pressed: &mut Vec<_>| pressed.push(name)))
+            .collect()
+    }
+
+    #[wasm_bindgen_test]
+    fn shortcuts_are_dispatched() {
+        let recorder = Recorder::default();
+        let mut cx = recorder.cx();
+        let view = shortcuts(pressed(&["ctrl+enter", "/"]));
+        let (_, mut state, _) = view.build(&mut cx);
+        let document = crate::document();
+
+        assert!(press(&document, "/", false, false));
+        assert!(press(&document, "Enter", true, false));
+        // Not a registered shortcut, so its default action isn't prevented.
+        assert!(!press(&document, "Enter", false, false));
+        let mut handled = vec![];
+        recorder.deliver(&view, &mut state, &mut handled);
+        assert_eq!(handled, ["/", "ctrl+enter"]);
+    }
+
+    #[wasm_bindgen_test]
+    fn shortcuts_are_ignored_in_editable_elements() {
+        let recorder = Recorder::default();
+        let mut cx = recorder.cx();
+        let view = shortcuts(
+            pressed(&["/"])
+                .into_iter()
+                .chain([shortcut("ctrl+enter", |pressed: &mut Vec<_>| {
+                    pressed.push("submit");
+                })
+                .even_in_inputs()]),
+        );
+        let (_, mut state, _) = view.build(&mut cx);
+        let input = crate::document().create_element("input").unwrap();
+        crate::document_body().append_child(&input).unwrap();
+
+        // Typing a `/` in the input is left alone.
+        assert!(!press(&input, "/", false, false));
+        assert!(press(&input, "Enter", true, false));
+        let mut handled = vec![];
+        recorder.deliver(&view, &mut state, &mut handled);
+        assert_eq!(handled, ["submit"]);
+        input.remove();
+    }
+
+    #[wasm_bindgen_test]
+    fn mod_depends_on_the_platform() {
+        let apple = KeyCombo::parse("mod+shift+K", true).unwrap();
+        assert_eq!(apple, KeyCombo::parse("cmd+shift+k", false).unwrap());
+        assert_eq!(apple.to_string(), "shift+meta+k");
+        let other = KeyCombo::parse("mod+shift+K", false).unwrap();
+        assert_eq!(other.to_string(), "ctrl+shift+k");
+
+        assert_eq!(
+            KeyCombo::parse("ctrl++", false).unwrap().to_string(),
+            "ctrl++"
+        );
+        assert_eq!(KeyCombo::parse("esc", false).unwrap().to_string(), "escape");
+        for invalid in ["", "ctrl+", "hyper+k", "ctrl+ctrl+k"] {
+            assert!(KeyCombo::parse(invalid, false).is_err(), "{invalid}");
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn last_built_duplicate_wins_and_teardown_unregisters() {
+        let first = Recorder::default();
+        let second = Recorder::default();
+        let first_view = shortcuts(pressed(&["/"]));
+        let second_view = shortcuts(pressed(&["/"]));
+        let (_, first_state, _) = first_view.build(&mut first.cx());
+        let (_, second_state, _) = second_view.build(&mut second.cx());
+        let document = crate::document();
+
+        press(&document, "/", false, false);
+        assert!(first.0.borrow().is_empty());
+        assert_eq!(second.0.borrow_mut().drain(..).count(), 1);
+
+        drop(second_state);
+        press(&document, "/", false, false);
+        assert_eq!(first.0.borrow_mut().drain(..).count(), 1);
+
+        drop(first_state);
+        assert!(!has_registered_shortcuts());
+        assert!(!press(&document, "/", false, false));
+        assert!(first.0.borrow().is_empty() && second.0.borrow().is_empty());
+    }
+}
@@ -0,0 +1,558 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! A loading boundary, showing a placeholder until the async dependencies of a part of
+//! the view tree have resolved.
+
+use std::{any::Any, cell::RefCell, collections::BTreeMap, marker::PhantomData, rc::Rc};
+
+use wasm_bindgen::{JsCast, UnwrapThrowExt};
+use xilem_core::{Id, MessageResult};
+
+use crate::{view::DomNode, ChangeFlags, Cx, View, ViewMarker};
+
+/// The status of an async dependency of a [`suspense`] boundary, e.g. a fetch.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DependencyStatus {
+    /// The dependency is loading, for the first time or again.
+    Pending,
+    Ready,
+    /// The dependency failed to load, with a description of the error.
+    Failed(String),
+}
+
+/// The first failed dependency of a [`suspense`] boundary, passed to its error view.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DependencyError {
+    /// The key of the dependency, as declared in the [`SuspenseDeps`].
+    pub dependency: String,
+    pub error: String,
+}
+
+/// The keys of the async dependencies a [`suspense`] boundary waits for.
+///
+/// Implemented for string keys, and arrays and vectors of them.
+pub trait SuspenseDeps {
+    /// Call `f` with the key of every dependency.
+    fn for_each_dependency(&self, f: &mut dyn FnMut(&str));
+}
+
+impl SuspenseDeps for &'static str {
+    fn for_each_dependency(&self, f: &mut dyn FnMut(&str)) {
+        f(self);
+    }
+}
+
+impl SuspenseDeps for String {
+    fn for_each_dependency(&self, f: &mut dyn FnMut(&str)) {
+        f(self);
+    }
+}
+
+impl<D: SuspenseDeps, const N: usize> SuspenseDeps for [D; N] {
+    fn for_each_dependency(&self, f: &mut dyn FnMut(&str)) {
+        for deps in self {
+            deps.for_each_dependency(f);
+        }
+    }
+}
+
+impl<D: SuspenseDeps> SuspenseDeps for Vec<D> {
+    fn for_each_dependency(&self, f: &mut dyn FnMut(&str)) {
+        for deps in self {
+            deps.for_each_dependency(f);
+        }
+    }
+}
+
+struct Dependency {
+    status: DependencyStatus,
+    /// Whether the dependency has been ready at least once.
+    resolved: bool,
+}
+
+/// A handle to report the status of async dependencies to the closest enclosing
+/// [`suspense`] boundary, obtained with [`Cx::suspense`].
+///
+/// Async views, like a fetch, usually get the handle when they're built, report that
+/// they're [`Pending`](DependencyStatus::Pending), and report their result once they're
+/// done, before requesting a rebuild to display it. The handle can be cloned and moved
+/// into futures.
+#[derive(Clone, Default)]
+pub struct SuspenseHandle(Rc<RefCell<BTreeMap<String, Dependency>>>);
+
+impl SuspenseHandle {
+    /// Report the status of the dependency with the key `dependency`.
+    ///
+    /// The boundary switches to the matching branch when it's rebuilt next. Reports for a
+    /// key the boundary doesn't declare are ignored.
+    pub fn report(&self, dependency: &str, status: DependencyStatus) {
+        if let Some(entry) = self.0.borrow_mut().get_mut(dependency) {
+            entry.resolved |= status == DependencyStatus::Ready;
+            entry.status = status;
+        }
+    }
+
+    /// Track the dependencies of `deps`, and forget about the others.
+    fn declare(&self, deps: &impl SuspenseDeps) {
+        let mut dependencies = self.0.borrow_mut();
+        let mut declared = BTreeMap::new();
+        deps.for_each_dependency(&mut |key| {
+            let entry = dependencies.remove(key).unwrap_or(Dependency {
+                status: DependencyStatus::Pending,
+                resolved: false,
+            });
+            declared.insert(key.to_string(), entry);
+        });
+        *dependencies = declared;
+    }
+
+    fn phase(&self, loading_on_refresh: bool) -> Phase {
+        let dependencies = self.0.borrow();
+        let failed = dependencies
+            .iter()
+            .find_map(|(key, entry)| match &entry.status {
+                DependencyStatus::Failed(error) => Some(DependencyError {
+                    dependency: key.clone(),
+                    error: error.clone(),
+                }),
+                _ => None,
+            });
+        if let Some(error) = failed {
+            Phase::Failed(error)
+        } else if dependencies.values().any(|entry| {
+            entry.status == DependencyStatus::Pending && (loading_on_refresh || !entry.resolved)
+        }) {
+            Phase::Loading
+        } else {
+            Phase::Ready
+        }
+    }
+}
+
+/// Which branch a [`Suspense`] shows.
+enum Phase {
+    Loading,
+    Failed(DependencyError),
+    Ready,
+}
+
+/// Show `loading_view` until the dependencies declared in `deps` have all resolved, then
+/// `content_view`, or the view returned by `error_view` for the first dependency which
+/// failed.
+///
+/// The content is always built, even while it isn't shown, so that the async views in
+/// it start loading: they report their status with the handle of [`Cx::suspense`], under
+/// one of the keys of `deps`. Async views report to the closest boundary only, so a
+/// nested `suspense` shows its own loading view, without holding the content of the
+/// outer one back.
+///
+/// The boundary switches branches when it's rebuilt, i.e. the async views request a
+/// rebuild once they've reported their result. Once a dependency has been ready, loading
+/// it again (e.g. a refetch) doesn't bring the loading view back, unless
+/// [`loading_on_refresh`](Suspense::loading_on_refresh) is set.
+///
+/// The element is a `div` with the class `xilem-suspense` and `display: contents`,
+/// containing the element of the branch which is shown.
+pub fn suspense<D, L, F, E, C>(
+    deps: D,
+    loading_view: L,
+    error_view: F,
+    content_view: C,
+) -> Suspense<D, L, F, C>
+where
+    D: SuspenseDeps,
+    F: Fn(&DependencyError) -> E,
+{
+    Suspense {
+        deps,
+        loading_view,
+        error_view,
+        content_view,
+        loading_on_refresh: false,
+    }
+}
+
+pub struct Suspense<D, L, F, C> {
+    deps: D,
+    loading_view: L,
+    error_view: F,
+    content_view: C,
+    loading_on_refresh: bool,
+}
+
+impl<D, L, F, C> Suspense<D, L, F, C> {
+    /// Whether to show the loading view again while a dependency which has already been
+    /// ready is loading again. Off by default, the previous content stays visible.
+    pub fn loading_on_refresh(mut self, loading_on_refresh: bool) -> Self {
+        self.loading_on_refresh = loading_on_refresh;
+        self
+    }
+}
+
+pub struct SuspenseState<T, A, L, E, C>
+where
+    L: View<T, A>,
+    E: View<T, A>,
+    C: View<T, A>,
+{
+    container: web_sys::HtmlDivElement,
+    handle: SuspenseHandle,
+    content: (Id, C::State, C::Element),
+    /// The loading view, while it's shown.
+    loading: Option<(Id, L::State, L::Element)>,
+    /// The error view, with the view it was last built or rebuilt with, while it's shown.
+    error: Option<(E, Id, E::State, E::Element)>,
+    phantom: PhantomData<fn() -> (T, A)>,
+}
+
+impl<T, A, L, E, C> SuspenseState<T, A, L, E, C>
+where
+    L: View<T, A>,
+    E: View<T, A>,
+    C: View<T, A>,
+{
+    fn shown_node(&self) -> &web_sys::Node {
+        if let Some((_, _, element)) = &self.loading {
+            element.as_node_ref()
+        } else if let Some((_, _, _, element)) = &self.error {
+            element.as_node_ref()
+        } else {
+            self.content.2.as_node_ref()
+        }
+    }
+
+    /// Make the element of the branch which is shown the child of the container.
+    ///
+    /// Returns whether it wasn't already.
+    fn mount(&self) -> bool {
+        let node = self.shown_node();
+        match self.container.first_child() {
+            Some(child) if &child == node => false,
+            Some(child) => {
+                self.container.replace_child(node, &child).unwrap_throw();
+                true
+            }
+            None => {
+                self.container.append_child(node).unwrap_throw();
+                true
+            }
+        }
+    }
+}
+
+impl<D, L, F, C> ViewMarker for Suspense<D, L, F, C> {}
+
+impl<T, A, D, L, F, E, C> View<T, A> for Suspense<D, L, F, C>
+where
+    D: SuspenseDeps,
+    L: View<T, A>,
+    F: Fn(&DependencyError) -> E,
+    E: View<T, A>,
+    C: View<T, A>,
+{
+    type State = SuspenseState<T, A, L, E, C>;
+
+    type Element = web_sys::HtmlDivElement;
+
+    fn build(&self, cx: &mut Cx) -> (Id, Self::State, Self::Element) {
+        let handle = SuspenseHandle::default();
+        handle.declare(&self.deps);
+        let (id, (content, phase)) = cx.with_new_id(|cx| {
+            let content = cx.with_suspense(handle.clone(), |cx| self.content_view.build(cx));
+            (content, handle.phase(self.loading_on_refresh))
+        });
+        let container = cx
+            .document()
+            .create_element("div")
+            .unwrap_throw()
+            .unchecked_into::<web_sys::HtmlDivElement>();
+        container.set_class_name("xilem-suspense");
+        container
+            .style()
+            .set_property("display", "contents")
+            .unwrap_throw();
+        let mut state = SuspenseState {
+            container: container.clone(),
+            handle,
+            content,
+            loading: None,
+            error: None,
+            phantom: PhantomData,
+        };
+        // The loading and error views are outside of the boundary, their async views
+        // report to the enclosing one.
+        cx.with_id(id, |cx| match phase {
+            Phase::Loading => state.loading = Some(self.loading_view.build(cx)),
+            Phase::Failed(error) => {
+                let view = (self.error_view)(&error);
+                let (id, error_state, element) = view.build(cx);
+                state.error = Some((view, id, error_state, element));
+            }
+            Phase::Ready => {}
+        });
+        state.mount();
+        (id, state, container)
+    }
+
+    fn rebuild(
+        &self,
+        cx: &mut Cx,
+        prev: &Self,
+        id: &mut Id,
+        state: &mut Self::State,
+        _element: &mut Self::Element,
+    ) -> ChangeFlags {
+        let handle = state.handle.clone();
+        handle.declare(&self.deps);
+        let mut changed = cx.with_id(*id, |cx| {
+            let (content_id, content_state, content_element) = &mut state.content;
+            let mut changed = cx.with_suspense(handle.clone(), |cx| {
+                self.content_view.rebuild(
+                    cx,
+                    &prev.content_view,
+                    content_id,
+                    content_state,
+                    content_element,
+                )
+            });
+            match handle.phase(self.loading_on_refresh) {
+                Phase::Loading => {
+                    state.error = None;
+                    match &mut state.loading {
+                        Some((id, loading_state, element)) => {
+                            changed |= self.loading_view.rebuild(
+                                cx,
+                                &prev.loading_view,
+                                id,
+                                loading_state,
+                                element,
+                            );
+                        }
+                        None => state.loading = Some(self.loading_view.build(cx)),
+                    }
+                }
+                Phase::Failed(error) => {
+                    state.loading = None;
+                    let view = (self.error_view)(&error);
+                    match &mut state.error {
+                        Some((prev_view, id, error_state, element)) => {
+                            changed |= view.rebuild(cx, prev_view, id, error_state, element);
+                            *prev_view = view;
+                        }
+                        None => {
+                            let (id, error_state, element) = view.build(cx);
+                            state.error = Some((view, id, error_state, element));
+                        }
+                    }
+                }
+                Phase::Ready => {
+                    state.loading = None;
+                    state.error = None;
+                }
+            }
+            changed
+        });
+        if state.mount() {
+            changed |= ChangeFlags::OTHER_CHANGE;
+        }
+        // The container stays the same, even when the element of a branch is replaced.
+        if changed.is_empty() {
+            ChangeFlags::empty()
+        } else {
+            ChangeFlags::OTHER_CHANGE
+        }
+    }
+
+    fn message(
+        &self,
+        id_path: &[Id],
+        state: &mut Self::State,
+        message: Box<dyn Any>,
+        app_state: &mut T,
+    ) -> MessageResult<A> {
+        let Some((first, rest_path)) = id_path.split_first() else {
+            return MessageResult::Stale(message);
+        };
+        if *first == state.content.0 {
+            return self
+                .content_view
+                .message(rest_path, &mut state.content.1, message, app_state);
+        }
+        match (&mut state.loading, &mut state.error) {
+            (Some((id, loading_state, _)), _) if *first == *id => {
+                self.loading_view
+                    .message(rest_path, loading_state, message, app_state)
+            }
+            (_, Some((view, id, error_state, _))) if *first == *id => {
+                view.message(rest_path, error_state, message, app_state)
+            }
+            _ => MessageResult::Stale(message),
+        }
+    }
+}
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod tests {
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use super::*;
+    use crate::elements::html::div;
+
+    wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+    /// A mocked fetch, reporting `status` to the closest suspense boundary, and displaying
+    /// its key once it's ready.
+    struct MockFetch {
+        key: &'static str,
+        status: DependencyStatus,
+    }
+
+    fn fetch(key: &'static str, status: &DependencyStatus) -> MockFetch {
+        MockFetch {
+            key,
+            status: status.clone(),
+        }
+    }
+
+    impl ViewMarker for MockFetch {}
+
+    impl View<(), ()> for MockFetch {
+        type State = Option<SuspenseHandle>;
+
+        type Element = web_sys::Text;
+
+        fn build(&self, cx: &mut Cx) -> (Id, Self::State, Self::Element) {
+            let handle = cx.suspense();
+            if let Some(handle) = &handle {
+                handle.report(self.key, self.status.clone());
+            }
+            let element = cx.document().create_text_node("");
+            if self.status == DependencyStatus::Ready {
+                element.set_data(self.key);
+            }
+            (Id::next(), handle, element)
+        }
+
+        fn rebuild(
+            &self,
+            _cx: &mut Cx,
+            prev: &Self,
+            _id: &mut Id,
+            state: &mut Self::State,
+            element: &mut Self::Element,
+        ) -> ChangeFlags {
+            if let Some(handle) = state {
+                handle.report(self.key, self.status.clone());
+            }
+            if self.status == DependencyStatus::Ready && prev.status != DependencyStatus::Ready {
+                element.set_data(self.key);
+                ChangeFlags::OTHER_CHANGE
+            } else {
+                ChangeFlags::empty()
+            }
+        }
+
+        fn message(
+            &self,
+            _id_path: &[Id],
+            _state: &mut Self::State,
+            message: Box<dyn Any>,
+            _app_state: &mut (),
+        ) -> MessageResult<()> {
+            MessageResult::Stale(message)
+        }
+    }
+
+    use DependencyStatus::{Failed, Pending, Ready};
+
+    fn boundary(
+        a: &DependencyStatus,
+        b: &DependencyStatus,
+    ) -> impl View<(), (), Element = web_sys::HtmlDivElement> {
+        suspense(
+            ["a", "b"],
+            "loading",
+            |error: &DependencyError| format!("{} failed: {}", error.dependency, error.error),
+            div((fetch("a", a), fetch("b", b))),
+        )
+    }
+
+    #[wasm_bindgen_test]
+    fn content_is_shown_once_all_dependencies_are_ready() {
+        let mut cx = Cx::new();
+        let view = boundary(&Pending, &Pending);
+        let (mut id, mut state, mut element) = view.build(&mut cx);
+        assert_eq!(element.text_content().unwrap(), "loading");
+
+        // The first fetch resolves.
+        let next = boundary(&Ready, &Pending);
+        next.rebuild(&mut cx, &view, &mut id, &mut state, &mut element);
+        assert_eq!(element.text_content().unwrap(), "loading");
+
+        // The second one does too.
+        let view = boundary(&Ready, &Ready);
+        view.rebuild(&mut cx, &next, &mut id, &mut state, &mut element);
+        assert_eq!(element.text_content().unwrap(), "ab");
+    }
+
+    #[wasm_bindgen_test]
+    fn failed_dependency_shows_the_error_view() {
+        let mut cx = Cx::new();
+        let view = boundary(&Pending, &Pending);
+        let (mut id, mut state, mut element) = view.build(&mut cx);
+
+        let next = boundary(&Ready, &Failed("offline".into()));
+        next.rebuild(&mut cx, &view, &mut id, &mut state, &mut element);
+        assert_eq!(element.text_content().unwrap(), "b failed: offline");
+    }
+
+    #[wasm_bindgen_test]
+    fn refetch_keeps_the_content() {
+        let mut cx = Cx::new();
+        let view = boundary(&Ready, &Ready);
+        let (mut id, mut state, mut element) = view.build(&mut cx);
+        assert_eq!(element.text_content().unwrap(), "ab");
+
+        let next = boundary(&Pending, &Ready);
+        next.rebuild(&mut cx, &view, &mut id, &mut state, &mut element);
+        assert_eq!(element.text_content().unwrap(), "ab");
+
+        // Unless the boundary opts in.
+        let refreshing = |a: &DependencyStatus| {
+            suspense(
+                ["a", "b"],
+                "loading",
+                |error: &DependencyError| error.error.clone(),
+                div((fetch("a", a), fetch("b", &Ready))),
+            )
+            .loading_on_refresh(true)
+        };
+        let view = refreshing(&Ready);
+        let (mut id, mut state, mut element) = view.build(&mut cx);
+        let next = refreshing(&Pending);
+        next.rebuild(&mut cx, &view, &mut id, &mut state, &mut element);
+        assert_eq!(element.text_content().unwrap(), "loading");
+    }
+
+    #[wasm_bindgen_test]
+    fn nested_boundaries_wait_for_their_own_dependencies() {
+        let mut cx = Cx::new();
+        let view = suspense(
+            "a",
+            "outer loading",
+            |error: &DependencyError| error.error.clone(),
+            div((
+                fetch("a", &Ready),
+                suspense(
+                    "b",
+                    "inner loading",
+                    |error: &DependencyError| error.error.clone(),
+                    fetch("b", &Pending),
+                ),
+            )),
+        );
+        let (_, _, element) = view.build(&mut cx);
+        assert_eq!(element.text_content().unwrap(), "ainner loading");
+    }
+}
@@ -0,0 +1,62 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Helpers shared by the tests of the views.
+
+use std::{cell::RefCell, rc::Rc};
+
+use wasm_bindgen::JsValue;
+use wasm_bindgen_futures::JsFuture;
+
+use crate::{app::AppRunner, Cx, Message, View};
+
+/// Records the messages pushed to the app.
+#[derive(Clone, Default)]
+pub(crate) struct Recorder(pub(crate) Rc<RefCell<Vec<Message>>>);
+
+impl AppRunner for Recorder {
+    fn handle_message(&self, message: Message) {
+        self.0.borrow_mut().push(message);
+    }
+
+    fn clone_box(&self) -> Box<dyn AppRunner> {
+        Box::new(self.clone())
+    }
+}
+
+impl Recorder {
+    /// A context pushing its messages to this recorder.
+    pub(crate) fn cx(&self) -> Cx {
+        let mut cx = Cx::new();
+        cx.set_runner(self.clone());
+        cx
+    }
+
+    /// Deliver the recorded messages to `view`.
+    ///
+    /// The messages pushed while delivering are recorded for the next call.
+    pub(crate) fn deliver<T, V: View<T, ()>>(
+        &self,
+        view: &V,
+        state: &mut V::State,
+        app_state: &mut T,
+    ) {
+        let messages: Vec<Message> = self.0.borrow_mut().drain(..).collect();
+        for message in messages {
+            view.message(&message.id_path[1..], state, message.body, app_state);
+        }
+    }
+}
+
+/// Let the work deferred to a microtask run, like the messages pushed once a rebuild is over.
+pub(crate) async fn next_tick() {
+    JsFuture::from(js_sys::Promise::resolve(&JsValue::NULL))
+        .await
+        .unwrap();
+}
+
+/// Dispatch an event named `event` on `target`.
+pub(crate) fn dispatch(target: &web_sys::EventTarget, event: &str) {
+    let event = web_sys::Event::new(event).unwrap();
+    target.dispatch_event(&event).unwrap();
+}
@@ -8,7 +8,10 @@ use std::{any::Any, borrow::Cow, ops::Deref};
 
 use xilem_core::{Id, MessageResult};
 
-use crate::{context::Cx, ChangeFlags};
+use crate::{
+    context::{Cx, MessageThunk},
+    ChangeFlags,
+};
 
 pub(crate) mod sealed {
     pub trait Sealed {}
@@ -90,8 +93,16 @@ xilem_core::generate_anyview_trait! {AnyView, View, ViewMarker, Cx, ChangeFlags,
 xilem_core::generate_memoize_view! {Memoize, MemoizeState, View, ViewMarker, Cx, ChangeFlags, static_view, memoize;}
 xilem_core::generate_adapt_view! {View, Cx, ChangeFlags;}
 xilem_core::generate_adapt_state_view! {View, Cx, ChangeFlags;}
+xilem_core::generate_map_message_async_view! {View, Cx, ChangeFlags, MessageThunk, wasm_bindgen_futures::spawn_local;}
+xilem_core::generate_subscribe_view! {View, Cx, ChangeFlags, MessageThunk, wasm_bindgen_futures::spawn_local;}
+xilem_core::generate_debounced_rebuild_view! {View, Cx, ChangeFlags, MessageThunk, wasm_bindgen_futures::spawn_local, gloo_timers::future::sleep;}
+xilem_core::generate_provide_id_view! {View, Cx, ChangeFlags;}
+xilem_core::generate_first_of_view! {View, Cx, ChangeFlags;}
 
 // strings -> text nodes
+//
+// A changed string only updates the data of its text node, which stays in the DOM.
+// Switching to a view with another element, e.g. with `OneOf2`, replaces the node.
 
 macro_rules! impl_string_view {
     ($ty:ty) => {
@@ -197,3 +208,62 @@ impl_to_string_view!(usize);
 fn new_text(text: &str) -> web_sys::Text {
     web_sys::Text::new_with_data(text).unwrap()
 }
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod tests {
+    use wasm_bindgen::JsCast;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use super::*;
+    use crate::{elements::html as el, OneOf2};
+
+    wasm_bindgen_test::wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    fn text_node_is_updated_in_place() {
+        let mut cx = Cx::new();
+        let view = |count: u32| el::div::<(), (), _>(("Count: ", count));
+
+        let prev = view(1);
+        let (mut id, mut state, mut element) = View::build(&prev, &mut cx);
+        let text = element.last_child().unwrap();
+
+        let next = view(2);
+        View::rebuild(&next, &mut cx, &prev, &mut id, &mut state, &mut element);
+        assert!(element.last_child().unwrap().is_same_node(Some(&text)));
+        assert_eq!(text.node_value().unwrap(), "2");
+        assert_eq!(element.text_content().unwrap(), "Count: 2");
+    }
+
+    #[wasm_bindgen_test]
+    fn switching_between_text_and_element_replaces_the_node() {
+        let mut cx = Cx::new();
+        let view = |bold: bool| {
+            el::div::<(), (), _>(if bold {
+                OneOf2::A(el::b("Title"))
+            } else {
+                OneOf2::B("Title")
+            })
+        };
+
+        let prev = view(false);
+        let (mut id, mut state, mut element) = View::build(&prev, &mut cx);
+        let text = element.first_child().unwrap();
+        assert!(text.is_instance_of::<web_sys::Text>());
+
+        let next = view(true);
+        View::rebuild(&next, &mut cx, &prev, &mut id, &mut state, &mut element);
+        let bold = element.first_element_child().unwrap();
+        assert_eq!(bold.tag_name(), "B");
+        assert_eq!(bold.text_content().unwrap(), "Title");
+
+        let prev = next;
+        let next = view(false);
+        View::rebuild(&next, &mut cx, &prev, &mut id, &mut state, &mut element);
+        let new_text = element.first_child().unwrap();
+        assert!(new_text.is_instance_of::<web_sys::Text>());
+        assert!(!new_text.is_same_node(Some(&text)));
+        assert_eq!(element.child_nodes().length(), 1);
+        assert_eq!(element.text_content().unwrap(), "Title");
+    }
+}
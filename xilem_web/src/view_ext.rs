@@ -1,7 +1,11 @@
 // Copyright 2023 the Xilem Authors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{view::View, Adapt, AdaptState, AdaptThunk};
+use std::future::Future;
+
+use xilem_core::futures_core::Stream;
+
+use crate::{view::View, Adapt, AdaptState, AdaptThunk, MapMessageAsync, Subscribe};
 
 /// A trait that makes it possible to use core views such as [`Adapt`] in the continuation/builder style.
 pub trait ViewExt<T, A>: View<T, A> + Sized {
@@ -18,6 +22,30 @@ pub trait ViewExt<T, A>: View<T, A> + Sized {
     {
         AdaptState::new(f, self)
     }
+
+    /// Map the actions of this view to futures, whose outputs become the parent's actions.
+    ///
+    /// See [`MapMessageAsync`].
+    fn map_message_async<ParentA, F, Fut>(self, f: F) -> MapMessageAsync<T, ParentA, A, Self, F>
+    where
+        F: Fn(&mut T, A) -> Fut,
+        Fut: Future<Output = ParentA> + 'static,
+    {
+        MapMessageAsync::new(f, self)
+    }
+
+    /// Subscribe to the stream created by `source_factory` while this view is in the tree,
+    /// turning each of its items into an action with `on_event`.
+    ///
+    /// See [`Subscribe`].
+    fn subscribe<F, H, St>(self, source_factory: F, on_event: H) -> Subscribe<T, A, Self, F, H>
+    where
+        F: Fn() -> St,
+        St: Stream + 'static,
+        H: Fn(&mut T, St::Item) -> A,
+    {
+        Subscribe::new(self, source_factory, on_event)
+    }
 }
 
 impl<T, A, V: View<T, A>> ViewExt<T, A> for V {}
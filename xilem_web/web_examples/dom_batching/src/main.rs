@@ -0,0 +1,83 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Compares rebuilding a large tree with and without batching the DOM writes.
+//!
+//! Each row reads its height after being rebuilt, like e.g. a virtualized list
+//! measuring its items does. Without batching, every read forces the browser to
+//! compute the layout again, since the previous row was just restyled. With
+//! batching, the styles are only written once all the rows are rebuilt, so the
+//! layout is computed once per step.
+
+use wasm_bindgen::UnwrapThrowExt;
+use xilem_web::{
+    document_body,
+    elements::html as el,
+    interfaces::{Element, HtmlElement},
+    style as s, Cx, View,
+};
+
+const ROWS: usize = 2000;
+const STEPS: usize = 20;
+
+fn row(index: usize, step: usize) -> impl View<(), (), Element = web_sys::HtmlDivElement> {
+    let width = 100 + (index * 7 + step * 13) % 300;
+    el::div(format!("row {index}"))
+        .class((index + step).is_multiple_of(2).then_some("even"))
+        .style([
+            s("width", format!("{width}px")),
+            s("padding", format!("{}px", step % 3)),
+        ])
+}
+
+/// Rebuild all the rows `STEPS` times, and return the elapsed time in milliseconds.
+fn run(cx: &mut Cx, batched: bool) -> f64 {
+    let performance = web_sys::window()
+        .unwrap_throw()
+        .performance()
+        .unwrap_throw();
+    let body = document_body();
+    cx.set_batch_dom_writes(batched);
+
+    let mut rows: Vec<_> = (0..ROWS)
+        .map(|index| {
+            let view = row(index, 0);
+            let (id, state, element) = view.build(cx);
+            body.append_child(&element).unwrap_throw();
+            (view, id, state, element)
+        })
+        .collect();
+
+    let start = performance.now();
+    let mut total_height = 0;
+    for step in 1..=STEPS {
+        for (index, (prev, id, state, element)) in rows.iter_mut().enumerate() {
+            let view = row(index, step);
+            view.rebuild(cx, prev, id, state, element);
+            *prev = view;
+            total_height += element.offset_height();
+        }
+        cx.flush_dom_writes();
+    }
+    let elapsed = performance.now() - start;
+
+    for (_, _, _, element) in rows {
+        element.remove();
+    }
+    // Keep the reads from being optimized out.
+    web_sys::console::debug_1(&total_height.into());
+    elapsed
+}
+
+pub fn main() {
+    console_error_panic_hook::set_once();
+    let mut cx = Cx::new();
+    for batched in [false, true] {
+        let elapsed = run(&mut cx, batched);
+        let message =
+            format!("{ROWS} rows, {STEPS} rebuilds, batched: {batched}: {elapsed:.1} ms",);
+        web_sys::console::log_1(&message.clone().into());
+        let (_, _, result) = View::<(), ()>::build(&el::p(message), &mut cx);
+        document_body().append_child(&result).unwrap_throw();
+    }
+}
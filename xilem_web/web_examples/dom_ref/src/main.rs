@@ -0,0 +1,70 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Hands a canvas to an imperative drawing "library" with `dom_ref`.
+
+use gloo::events::EventListener;
+use wasm_bindgen::{JsCast, UnwrapThrowExt};
+use xilem_web::{document_body, elements::html as el, interfaces::Element, App, View};
+
+/// Stands in for a JS library, which manages the content of the canvas by itself.
+struct Sketchpad {
+    #[allow(unused)]
+    listener: EventListener,
+}
+
+impl Sketchpad {
+    fn attach(canvas: web_sys::HtmlCanvasElement) -> Self {
+        let context = canvas
+            .get_context("2d")
+            .unwrap_throw()
+            .unwrap_throw()
+            .unchecked_into::<web_sys::CanvasRenderingContext2d>();
+        context.set_fill_style(&"steelblue".into());
+        let listener = EventListener::new(&canvas, "pointermove", move |event| {
+            let event = event.unchecked_ref::<web_sys::PointerEvent>();
+            if event.buttons() == 1 {
+                let (x, y) = (f64::from(event.offset_x()), f64::from(event.offset_y()));
+                context.fill_rect(x - 2.0, y - 2.0, 4.0, 4.0);
+            }
+        });
+        Sketchpad { listener }
+    }
+
+    fn destroy(self) {
+        web_sys::console::log_1(&"sketchpad destroyed".into());
+    }
+}
+
+#[derive(Default)]
+struct AppState {
+    show_canvas: bool,
+}
+
+fn app_logic(state: &mut AppState) -> impl View<AppState> {
+    let toggle_label = if state.show_canvas {
+        "Remove canvas"
+    } else {
+        "Add canvas"
+    };
+    el::div((
+        el::button(toggle_label).on_click(|state: &mut AppState, _| {
+            state.show_canvas = !state.show_canvas;
+        }),
+        el::br(()),
+        state.show_canvas.then(|| {
+            el::canvas(())
+                .attr("width", 400)
+                .attr("height", 300)
+                .dom_ref(|node| {
+                    let sketchpad = Sketchpad::attach(node.unchecked_into());
+                    move || sketchpad.destroy()
+                })
+        }),
+    ))
+}
+
+pub fn main() {
+    console_error_panic_hook::set_once();
+    App::new(AppState::default(), app_logic).run(&document_body());
+}
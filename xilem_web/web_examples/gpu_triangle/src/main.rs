@@ -0,0 +1,199 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! A rotating triangle rendered with wgpu, using WebGPU or falling back to WebGL2.
+
+use std::rc::Rc;
+
+use xilem_web::{
+    document_body,
+    elements::html as el,
+    gpu_canvas,
+    interfaces::{Element, HtmlElement},
+    style as s, App, GpuCanvasConfig, SurfaceHandle, View,
+};
+
+const SHADER: &str = r"
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) color: vec3<f32>,
+};
+
+@vertex
+fn vs_main(@location(0) position: vec2<f32>, @location(1) color: vec3<f32>) -> VertexOutput {
+    var out: VertexOutput;
+    out.position = vec4<f32>(position, 0.0, 1.0);
+    out.color = color;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(in.color, 1.0);
+}
+";
+
+/// The colors of the corners of the triangle.
+const COLORS: [[f32; 3]; 3] = [[1.0, 0.2, 0.2], [0.2, 1.0, 0.2], [0.2, 0.2, 1.0]];
+
+/// The size of a vertex: a 2D position and a color.
+const VERTEX_SIZE: u64 = 5 * 4;
+
+/// The pipeline and vertex buffer of the triangle, created once the surface is ready.
+struct Triangle {
+    pipeline: wgpu::RenderPipeline,
+    vertices: wgpu::Buffer,
+}
+
+impl Triangle {
+    fn new(surface: &SurfaceHandle) -> Self {
+        let device = surface.device();
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("triangle"),
+            source: wgpu::ShaderSource::Wgsl(SHADER.into()),
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("triangle"),
+            layout: None,
+            vertex: wgpu::VertexState {
+                module: &module,
+                entry_point: "vs_main",
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: VERTEX_SIZE,
+                    step_mode: wgpu::VertexStepMode::Vertex,
+                    attributes: &wgpu::vertex_attr_array![0 => Float32x2, 1 => Float32x3],
+                }],
+            },
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            fragment: Some(wgpu::FragmentState {
+                module: &module,
+                entry_point: "fs_main",
+                targets: &[Some(surface.config().format.into())],
+            }),
+            multiview: None,
+        });
+        let vertices = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("triangle vertices"),
+            size: 3 * VERTEX_SIZE,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        Triangle { pipeline, vertices }
+    }
+
+    /// Render the triangle rotated by one radian per second.
+    fn render(&self, surface: &SurfaceHandle) {
+        let Some(frame) = surface.current_texture() else {
+            return;
+        };
+        let config = surface.config();
+        let aspect = config.height as f32 / config.width as f32;
+        let angle = (js_sys::Date::now() / 1000.0 % std::f64::consts::TAU) as f32;
+        let bytes: Vec<u8> = (0..3)
+            .flat_map(|corner| {
+                let corner_angle = angle + corner as f32 * std::f32::consts::TAU / 3.0;
+                let position = [0.7 * corner_angle.cos() * aspect, 0.7 * corner_angle.sin()];
+                position.into_iter().chain(COLORS[corner])
+            })
+            .flat_map(f32::to_le_bytes)
+            .collect();
+        surface.queue().write_buffer(&self.vertices, 0, &bytes);
+
+        let view = frame
+            .texture
+            .create_view(&wgpu::TextureViewDescriptor::default());
+        let mut encoder = surface
+            .device()
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        {
+            let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("triangle"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            pass.set_pipeline(&self.pipeline);
+            pass.set_vertex_buffer(0, self.vertices.slice(..));
+            pass.draw(0..3, 0..1);
+        }
+        surface.queue().submit([encoder.finish()]);
+        frame.present();
+    }
+}
+
+/// What the canvas renders, compared by identity since it's recreated with the surface.
+#[derive(Clone)]
+struct Scene(Option<Rc<Triangle>>);
+
+impl PartialEq for Scene {
+    fn eq(&self, other: &Self) -> bool {
+        match (&self.0, &other.0) {
+            (Some(a), Some(b)) => Rc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+}
+
+struct AppState {
+    scene: Scene,
+    status: String,
+    spinning: bool,
+}
+
+fn app_logic(state: &mut AppState) -> impl View<AppState> {
+    let toggle_label = if state.spinning { "Pause" } else { "Spin" };
+    el::div((
+        el::p(state.status.clone()),
+        el::button(toggle_label)
+            .on_click(|state: &mut AppState, _| state.spinning = !state.spinning),
+        el::div(
+            gpu_canvas(
+                GpuCanvasConfig::default(),
+                |state: &mut AppState, surface| match surface {
+                    Ok(surface) => {
+                        let backend = if surface.is_webgl() {
+                            "WebGL2"
+                        } else {
+                            "WebGPU"
+                        };
+                        state.status = format!("Rendering with {backend}");
+                        state.scene = Scene(Some(Rc::new(Triangle::new(&surface))));
+                    }
+                    Err(error) => {
+                        state.status = format!("No GPU: {error}");
+                        state.scene = Scene(None);
+                    }
+                },
+                state.scene.clone(),
+            )
+            .on_render(|surface, scene: &Scene| {
+                if let Some(triangle) = &scene.0 {
+                    triangle.render(surface);
+                }
+            })
+            .animate(state.spinning),
+        )
+        .style([s("width", "100%"), s("height", "70vh")]),
+    ))
+}
+
+pub fn main() {
+    console_error_panic_hook::set_once();
+    let state = AppState {
+        scene: Scene(None),
+        status: "Acquiring the GPU…".into(),
+        spinning: true,
+    };
+    App::new(state, app_logic).run(&document_body());
+}
@@ -0,0 +1,80 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! A minimal audio player, driving an `<audio>` element from the app state.
+
+use xilem_web::{
+    document_body,
+    elements::html as el,
+    interfaces::{Element, HtmlMediaElement},
+    App, View,
+};
+
+const SOURCE: &str = "https://upload.wikimedia.org/wikipedia/commons/c/c8/Example.ogg";
+
+#[derive(Default)]
+struct AppState {
+    playing: bool,
+    muted: bool,
+    position: f64,
+    duration: f64,
+    /// Where to seek to, kept apart from `position` so that playback doesn't seek.
+    seek_target: f64,
+    error: Option<String>,
+}
+
+impl AppState {
+    fn seek_by(&mut self, seconds: f64) {
+        self.seek_target = (self.position + seconds).clamp(0.0, self.duration);
+    }
+}
+
+fn format_time(seconds: f64) -> String {
+    let seconds = seconds as u32;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+fn app_logic(state: &mut AppState) -> impl View<AppState> {
+    let play_label = if state.playing { "Pause" } else { "Play" };
+    let mute_label = if state.muted { "Unmute" } else { "Mute" };
+    el::div((
+        el::audio(())
+            .attr("src", SOURCE)
+            .on_timeupdate(|state: &mut AppState, position| state.position = position)
+            .on_durationchange(|state: &mut AppState, duration| {
+                state.duration = if duration.is_finite() { duration } else { 0.0 };
+            })
+            .on_play(|state: &mut AppState, ()| state.playing = true)
+            .on_pause(|state: &mut AppState, ()| state.playing = false)
+            .on_ended(|state: &mut AppState, ()| state.playing = false)
+            .on_media_error(|state: &mut AppState, code| {
+                state.playing = false;
+                state.error = Some(format!("Could not load the audio (error code {code:?})"));
+            })
+            .playing(state.playing)
+            .current_time(state.seek_target)
+            .muted(state.muted),
+        el::progress(())
+            .attr("max", state.duration.max(f64::EPSILON))
+            .attr("value", state.position),
+        el::div(format!(
+            "{} / {}",
+            format_time(state.position),
+            format_time(state.duration)
+        )),
+        el::button("-10s").on_click(|state: &mut AppState, _| state.seek_by(-10.0)),
+        el::button(play_label).on_click(|state: &mut AppState, _| {
+            state.playing = !state.playing;
+        }),
+        el::button("+10s").on_click(|state: &mut AppState, _| state.seek_by(10.0)),
+        el::button(mute_label).on_click(|state: &mut AppState, _| {
+            state.muted = !state.muted;
+        }),
+        state.error.clone().map(el::p),
+    ))
+}
+
+pub fn main() {
+    console_error_panic_hook::set_once();
+    App::new(AppState::default(), app_logic).run(&document_body());
+}
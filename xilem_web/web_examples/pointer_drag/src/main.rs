@@ -0,0 +1,119 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! A box which can be dragged with a mouse, a finger or a pen.
+
+use xilem_web::{
+    document_body,
+    elements::html as el,
+    interfaces::{Element, HtmlElement},
+    style as s, App, PointerDetails, PointerType, View,
+};
+
+const BOX_SIZE: f64 = 80.0;
+
+struct Drag {
+    pointer: i32,
+    start_x: f64,
+    start_y: f64,
+    start_pointer_x: f64,
+    start_pointer_y: f64,
+}
+
+struct AppState {
+    x: f64,
+    y: f64,
+    drag: Option<Drag>,
+    last_pointer: Option<(PointerType, f32)>,
+}
+
+impl AppState {
+    fn start_drag(&mut self, details: PointerDetails) {
+        if self.drag.is_some() {
+            // Another finger is already dragging the box.
+            return;
+        }
+        self.drag = Some(Drag {
+            pointer: details.id,
+            start_x: self.x,
+            start_y: self.y,
+            start_pointer_x: details.x,
+            start_pointer_y: details.y,
+        });
+        self.last_pointer = Some((details.pointer_type, details.pressure));
+    }
+
+    fn move_drag(&mut self, details: PointerDetails) {
+        let Some(drag) = self.drag.as_ref().filter(|drag| drag.pointer == details.id) else {
+            return;
+        };
+        self.x = drag.start_x + details.x - drag.start_pointer_x;
+        self.y = drag.start_y + details.y - drag.start_pointer_y;
+        self.last_pointer = Some((details.pointer_type, details.pressure));
+    }
+
+    fn end_drag(&mut self, details: PointerDetails) {
+        if self
+            .drag
+            .as_ref()
+            .is_some_and(|drag| drag.pointer == details.id)
+        {
+            self.drag = None;
+        }
+    }
+
+    fn cancel_drag(&mut self, details: PointerDetails) {
+        // Put the box back where the drag started.
+        if let Some(drag) = self.drag.as_ref().filter(|drag| drag.pointer == details.id) {
+            self.x = drag.start_x;
+            self.y = drag.start_y;
+            self.drag = None;
+        }
+    }
+}
+
+fn app_logic(state: &mut AppState) -> impl View<AppState> {
+    let status = match state.last_pointer {
+        Some((pointer_type, pressure)) => {
+            format!("last dragged with {pointer_type:?}, pressure {pressure:.2}")
+        }
+        None => "drag the box with a mouse, a finger or a pen".to_string(),
+    };
+    let color = if state.drag.is_some() {
+        "crimson"
+    } else {
+        "steelblue"
+    };
+    el::div((
+        el::p(status),
+        el::div(())
+            .style([
+                s("left", format!("{}px", state.x)),
+                s("top", format!("{}px", state.y)),
+                s("width", format!("{BOX_SIZE}px")),
+                s("height", format!("{BOX_SIZE}px")),
+            ])
+            .style([
+                s("position", "absolute"),
+                s("background", color),
+                // Keep touches from scrolling the page instead of dragging.
+                s("touch-action", "none"),
+            ])
+            .on_pointer_down(|state: &mut AppState, details| state.start_drag(details))
+            .capture(true)
+            .on_pointer_move(|state: &mut AppState, details| state.move_drag(details))
+            .on_pointer_up(|state: &mut AppState, details| state.end_drag(details))
+            .on_pointer_cancel(|state: &mut AppState, details| state.cancel_drag(details)),
+    ))
+}
+
+pub fn main() {
+    console_error_panic_hook::set_once();
+    let state = AppState {
+        x: 40.0,
+        y: 80.0,
+        drag: None,
+        last_pointer: None,
+    };
+    App::new(state, app_logic).run(&document_body());
+}
@@ -0,0 +1,74 @@
+// Copyright 2024 the Xilem Authors
+// SPDX-License-Identifier: Apache-2.0
+
+//! A search box which takes the focus when it's opened, and a list scrolling its
+//! selected item into view, with `autofocus` and `scroll_into_view_when`.
+
+use wasm_bindgen::JsCast;
+use xilem_web::{
+    document_body,
+    elements::html as el,
+    interfaces::{Element, HtmlElement},
+    App, View,
+};
+
+const ITEMS: usize = 100;
+
+#[derive(Default)]
+struct AppState {
+    search_open: bool,
+    query: String,
+    selected: usize,
+}
+
+fn app_logic(state: &mut AppState) -> impl View<AppState> {
+    let toggle_label = if state.search_open {
+        "Close search"
+    } else {
+        "Search"
+    };
+    let items = (0..ITEMS)
+        .map(|i| {
+            let selected = i == state.selected;
+            el::li(format!("Item {i}"))
+                .class(selected.then_some("selected"))
+                .scroll_into_view_when(selected)
+        })
+        .collect::<Vec<_>>();
+    el::div((
+        el::button(toggle_label).on_click(|state: &mut AppState, _| {
+            state.search_open = !state.search_open;
+        }),
+        state.search_open.then(|| {
+            el::input(())
+                .attr("placeholder", "Search")
+                .attr("value", state.query.clone())
+                .autofocus()
+                .on_input(|state: &mut AppState, event| {
+                    if let Some(input) = event
+                        .target()
+                        .and_then(|t| t.dyn_into::<web_sys::HtmlInputElement>().ok())
+                    {
+                        state.query = input.value();
+                    }
+                })
+        }),
+        el::div((
+            el::button("Previous").on_click(|state: &mut AppState, _| {
+                state.selected = state.selected.saturating_sub(1);
+            }),
+            el::button("Next").on_click(|state: &mut AppState, _| {
+                state.selected = (state.selected + 1).min(ITEMS - 1);
+            }),
+            el::button("Last").on_click(|state: &mut AppState, _| {
+                state.selected = ITEMS - 1;
+            }),
+        )),
+        el::ul(items),
+    ))
+}
+
+pub fn main() {
+    console_error_panic_hook::set_once();
+    App::new(AppState::default(), app_logic).run(&document_body());
+}
@@ -7,8 +7,8 @@ use state::{AppState, Filter, Todo};
 
 use wasm_bindgen::JsCast;
 use xilem_web::{
-    elements::html as el, get_element_by_id, interfaces::*, style as s, Action, Adapt, App,
-    MessageResult, View,
+    elements::html as el, get_element_by_id, interfaces::*, plural, style as s, Action, Adapt, App,
+    MessageResult, PluralForms, View,
 };
 
 // All of these actions arise from within a `Todo`, but we need access to the full state to reduce
@@ -71,12 +71,6 @@ fn todo_item(todo: &mut Todo, editing: bool) -> impl Element<Todo, TodoAction> {
 }
 
 fn footer_view(state: &mut AppState, should_display: bool) -> impl Element<AppState> {
-    let item_str = if state.todos.len() == 1 {
-        "item"
-    } else {
-        "items"
-    };
-
     let clear_button = (state.todos.iter().filter(|todo| todo.completed).count() > 0).then(|| {
         Element::on_click(
             el::button("Clear completed").class("clear-completed"),
@@ -91,7 +85,10 @@ fn footer_view(state: &mut AppState, should_display: bool) -> impl Element<AppSt
     el::footer((
         el::span((
             el::strong(state.todos.len().to_string()),
-            format!(" {} left", item_str),
+            plural(
+                state.todos.len() as f64,
+                PluralForms::new(" items left").one(" item left"),
+            ),
         ))
         .class("todo-count"),
         el::ul((